@@ -0,0 +1,333 @@
+use crate::{
+    flat::{unflatten, Token},
+    node::OCTREE_CHILDREN,
+    Error, Octree, Vector3,
+};
+
+use alloc::{vec, vec::Vec};
+use core::{convert::TryFrom, fmt::Debug, hash::Hash};
+use rkyv::{
+    bytecheck::CheckBytes,
+    munge::munge,
+    rancor::{Fallible, Source},
+    ser::{Allocator, Writer},
+    vec::{ArchivedVec, VecResolver},
+    Archive, Archived, Deserialize, Place, Portable, Serialize,
+};
+
+/// The index held in place of a missing child or value.
+const NONE: u32 = u32::MAX;
+
+/// One `Node` of an archived `Octree`, referring to its children and data by index.
+#[derive(Archive, Serialize)]
+struct Slot {
+    /// The index of the slot of the child in each octant, or [`NONE`] for octants never written.
+    children: [u32; OCTREE_CHILDREN],
+    /// The index of the data of a leaf, or [`NONE`] for internal `Node`s.
+    value: u32,
+}
+
+/// An `Octree` archived with `rkyv`, which can be read in place without deserializing it.
+///
+/// `Node`s are held in slots, listed breadth-first with the root first, which refer to their children and the
+/// data of their leaves by index, so the archive holds no pointers other than those to its two arrays. Created by
+/// serializing an `Octree` with `rkyv`. Reads are answered straight from the archive by [`ArchivedOctree::get`];
+/// editing needs the `Octree` to be deserialized first.
+#[derive(Portable, CheckBytes)]
+#[bytecheck(crate = rkyv::bytecheck)]
+#[repr(C)]
+pub struct ArchivedOctree<T> {
+    dimension: Archived<u32>,
+    lod_level: Archived<u32>,
+    background: T,
+    slots: ArchivedVec<ArchivedSlot>,
+    values: ArchivedVec<T>,
+}
+
+impl<T> ArchivedOctree<T> {
+    /// Returns the dimension of the root node.
+    pub fn dimension(&self) -> u32 {
+        self.dimension.to_native()
+    }
+
+    /// Returns the LOD level the `Octree` was archived at.
+    pub fn lod_level(&self) -> u32 {
+        self.lod_level.to_native()
+    }
+
+    /// Returns the archived background of the `Octree`.
+    pub fn background(&self) -> &T {
+        &self.background
+    }
+
+    /// Retrieves the archived data at the given position, returning the same as [`Octree::get`] on the archived
+    /// `Octree`, without deserializing any of it.
+    ///
+    /// Slots are only followed down as far as the dimension of a single voxel, and indices out of range are
+    /// read as missing, so a malformed archive which passed validation cannot loop or panic.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{ArchivedOctree, Octree};
+    /// # use core::num::NonZeroU32;
+    /// # use rkyv::rancor::Error;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([9, 8, 31], 1).unwrap();
+    ///
+    /// let bytes = rkyv::to_bytes::<Error>(&octree)?;
+    /// let archived = rkyv::access::<ArchivedOctree<u8>, Error>(&bytes)?;
+    /// assert_eq!(archived.get([9, 8, 31]), Some(&1));
+    /// assert_eq!(archived.get([20, 1, 12]), Some(&0));
+    /// assert_eq!(archived.get([32, 1, 12]), None);
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn get(&self, position: impl Into<Vector3<u32>>) -> Option<&T> {
+        let position = <[u32; 3]>::from(position.into());
+        let mut dimension = self.dimension();
+        if position.iter().any(|c| *c >= dimension) {
+            return None;
+        }
+
+        let mut slot = self.slots.first()?;
+        loop {
+            let value = slot.value.to_native();
+            if value != NONE {
+                return self.values.get(value as usize);
+            }
+
+            dimension /= 2;
+            if dimension == 0 {
+                return None;
+            }
+
+            let [x, y, z] = position.map(|c| (c & dimension != 0) as usize);
+            let child = slot.children[y << 2 | z << 1 | x].to_native();
+            if child == NONE {
+                return Some(&self.background);
+            }
+
+            slot = self.slots.get(child as usize)?;
+        }
+    }
+}
+
+/// The resolver of an archived `Octree`.
+pub struct OctreeResolver<T>
+where
+    T: Archive,
+{
+    background: T::Resolver,
+    slots: (usize, VecResolver),
+    values: (usize, VecResolver),
+}
+
+impl<T> Archive for Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + Archive,
+{
+    type Archived = ArchivedOctree<T::Archived>;
+    type Resolver = OctreeResolver<T>;
+
+    fn resolve(&self, resolver: Self::Resolver, out: Place<Self::Archived>) {
+        munge!(let ArchivedOctree { dimension, lod_level, background, slots, values } = out);
+
+        self.dimension().resolve((), dimension);
+        self.lod_level().resolve((), lod_level);
+        self.background().resolve(resolver.background, background);
+        ArchivedVec::resolve_from_len(resolver.slots.0, resolver.slots.1, slots);
+        ArchivedVec::resolve_from_len(resolver.values.0, resolver.values.1, values);
+    }
+}
+
+impl<T, S> Serialize<S> for Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + Serialize<S>,
+    S: Fallible + Allocator + Writer + ?Sized,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        let mut queue = vec![self.root()];
+        let (mut slots, mut values) = (Vec::new(), Vec::new());
+
+        // Slots are numbered in the order `Node`s are queued, so walking the queue in order is breadth-first.
+        let mut next = 0;
+        while let Some(node) = queue.get(next).copied() {
+            let mut slot = Slot {
+                children: [NONE; OCTREE_CHILDREN],
+                value: NONE,
+            };
+
+            match node.leaf_data() {
+                Some(data) => {
+                    slot.value = u32::try_from(values.len()).expect("value index overflow");
+                    values.push(*data);
+                }
+                None => {
+                    for octant in 0..OCTREE_CHILDREN {
+                        if let Some(child) = node.child(octant) {
+                            slot.children[octant] = u32::try_from(queue.len()).expect("slot index overflow");
+                            queue.push(child);
+                        }
+                    }
+                }
+            }
+
+            slots.push(slot);
+            next += 1;
+        }
+
+        Ok(OctreeResolver {
+            background: self.background().serialize(serializer)?,
+            slots: (slots.len(), ArchivedVec::serialize_from_slice(&slots, serializer)?),
+            values: (values.len(), ArchivedVec::serialize_from_slice(&values, serializer)?),
+        })
+    }
+}
+
+impl<T, D> Deserialize<Octree<T>, D> for ArchivedOctree<T::Archived>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + Archive,
+    T::Archived: Deserialize<T, D>,
+    D: Fallible + ?Sized,
+    D::Error: Source,
+{
+    /// Rebuilds the `Octree`, failing with [`Error::InvalidEncoding`] if any index is out of range or slots are
+    /// nested below a single voxel, as they are when a slot refers back to one above it.
+    fn deserialize(&self, deserializer: &mut D) -> Result<Octree<T>, D::Error> {
+        let dimension = self.dimension();
+        if !dimension.is_power_of_two() {
+            return Err(D::Error::new(Error::InvalidDimension(dimension)));
+        }
+
+        let values = self
+            .values
+            .iter()
+            .map(|value| value.deserialize(deserializer))
+            .collect::<Result<Vec<T>, _>>()?;
+
+        // Walked depth-first, pushing children in reverse, so that tokens come in the pre-order expected.
+        let mut stack = vec![0];
+        let tokens = core::iter::from_fn(|| {
+            let slot = self.slots.get(stack.pop()? as usize).ok_or(Error::InvalidEncoding);
+            let slot = match slot {
+                Ok(slot) => slot,
+                Err(error) => return Some(Err(error)),
+            };
+
+            let value = slot.value.to_native();
+            if value != NONE {
+                return Some(
+                    values
+                        .get(value as usize)
+                        .map(|data| Token::Leaf(*data))
+                        .ok_or(Error::InvalidEncoding),
+                );
+            }
+
+            let mut mask = 0;
+            for octant in (0..OCTREE_CHILDREN).rev() {
+                let child = slot.children[octant].to_native();
+                if child != NONE {
+                    mask |= 1 << octant;
+                    stack.push(child);
+                }
+            }
+
+            Some(Ok(Token::Branch(mask)))
+        });
+
+        let root = unflatten(dimension, tokens, |_| Error::InvalidEncoding).map_err(D::Error::new)?;
+        let background = self.background.deserialize(deserializer)?;
+
+        Octree::from_root(dimension, root, background, self.lod_level()).map_err(D::Error::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArchivedOctree;
+    use crate::{test_utils::XorShift, Octree};
+
+    use core::num::NonZeroU32;
+    use rkyv::{rancor::Error, util::AlignedVec};
+
+    #[test]
+    fn archives_read_as_the_octree() {
+        let mut rng = XorShift::new(0x7c10);
+
+        for dimension in [1, 2, 8, 16] {
+            for _ in 0..5 {
+                let inserts = rng.below(dimension * dimension * 4);
+                let mut octree = rng.octree(dimension, inserts, 4);
+                for _ in 0..rng.below(dimension * 4) {
+                    octree.clear_at(rng.position(dimension)).unwrap();
+                }
+                if dimension > 1 && rng.below(2) == 0 {
+                    octree.lod_down();
+                }
+
+                let bytes = rkyv::to_bytes::<Error>(&octree).unwrap();
+                let archived = rkyv::access::<ArchivedOctree<u8>, Error>(&bytes).unwrap();
+                assert_eq!(archived.lod_level(), octree.lod_level());
+
+                for x in 0..dimension {
+                    for y in 0..dimension {
+                        for z in 0..dimension {
+                            let position = [x, y, z];
+                            assert_eq!(archived.get(position), octree.get(position), "at {:?}", position);
+                        }
+                    }
+                }
+                assert_eq!(archived.get([dimension, 0, 0]), None);
+
+                let copy = rkyv::deserialize::<Octree<u8>, Error>(archived).unwrap();
+                assert_eq!(copy.to_bytes(), octree.to_bytes());
+                assert_eq!(copy.lod_level(), octree.lod_level());
+            }
+        }
+    }
+
+    #[test]
+    fn backgrounds_are_archived() {
+        let mut octree = Octree::new_with_background(NonZeroU32::new(8).unwrap(), 7_u16).unwrap();
+        octree.insert([1, 2, 3], 2).unwrap();
+
+        let bytes = rkyv::to_bytes::<Error>(&octree).unwrap();
+        let archived = rkyv::access::<ArchivedOctree<rkyv::Archived<u16>>, Error>(&bytes).unwrap();
+        assert_eq!(archived.background().to_native(), 7);
+        assert_eq!(archived.get([1, 2, 3]).map(|data| data.to_native()), Some(2));
+
+        let copy = rkyv::deserialize::<Octree<u16>, Error>(archived).unwrap();
+        assert_eq!(copy.background(), 7);
+        assert_eq!(copy.get([7, 7, 7]), octree.get([7, 7, 7]));
+    }
+
+    #[test]
+    fn malformed_archives_are_rejected() {
+        let octree = XorShift::new(0x7c11).octree(8, 40, 3);
+        let bytes = rkyv::to_bytes::<Error>(&octree).unwrap();
+
+        // Cutting the archive short leaves the root pointing outside it.
+        let mut truncated = AlignedVec::<16>::new();
+        truncated.extend_from_slice(&bytes[..bytes.len() - 4]);
+        assert!(rkyv::access::<ArchivedOctree<u8>, Error>(&truncated).is_err());
+
+        // Pointing the first child of the root back at the root passes validation, as indices are not pointers,
+        // but reads stop at a single voxel and deserializing fails.
+        let archived = rkyv::access::<ArchivedOctree<u8>, Error>(&bytes).unwrap();
+        let (octant, _) = (0..8)
+            .map(|octant| (octant, archived.slots[0].children[octant].to_native()))
+            .find(|(_, child)| *child != u32::MAX)
+            .unwrap();
+        let offset = &archived.slots[0].children[octant] as *const _ as usize - bytes.as_ptr() as usize;
+
+        let mut cycle = AlignedVec::<16>::new();
+        cycle.extend_from_slice(&bytes);
+        cycle[offset..offset + 4].copy_from_slice(&0_u32.to_le_bytes());
+
+        let archived = rkyv::access::<ArchivedOctree<u8>, Error>(&cycle).unwrap();
+        let [x, y, z] = [octant & 1, (octant >> 2) & 1, (octant >> 1) & 1].map(|c| c as u32 * 4);
+        assert_eq!(archived.get([x, y, z]), None);
+        assert!(rkyv::deserialize::<Octree<u8>, Error>(archived).is_err());
+    }
+}
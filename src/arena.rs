@@ -0,0 +1,697 @@
+use crate::{
+    codec::write_tokens,
+    flat::Token,
+    node::{occupied, octant_index, rank, MAX_DEPTH, OCTREE_CHILDREN},
+    Error, LeafInfo, Node, NodeRef, Octree, ValueCodec, Vector3,
+};
+
+use alloc::{vec, vec::Vec};
+use core::{convert::TryFrom, fmt::Debug, hash::Hash, iter, num::NonZeroU32};
+
+/// Marks the end of a free list, and the first child of a branch with none.
+const NONE: u32 = u32::MAX;
+/// The index of the root, which is never freed.
+const ROOT: u32 = 0;
+
+/// A `Node` of an [`ArenaOctree`], referring to its children by their index in the arena.
+///
+/// The children of a branch are held in a block of consecutive slots, one for each octant holding a child, in
+/// octant order, so that a branch only holds the index of the first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Slot<T> {
+    /// A leaf holding the given data.
+    Leaf(T),
+    /// An internal `Node`, with the bit mask of the octants holding a child, and the index of the first child.
+    Branch(u8, u32),
+    /// A recycled slot. The first slot of a recycled block holds the index of the next free block of the same
+    /// length, and the others `NONE`.
+    Free(u32),
+}
+
+/// A sparse voxel octree holding its `Node`s in a single arena rather than a heap allocation each.
+///
+/// Behaves as an [`Octree`] at LOD level 1: it is simplified after every edit exactly as an `Octree` is, so both hold
+/// the same `Node`s after the same edits, and [`ArenaOctree::to_bytes`] writes the same bytes as [`Octree::to_bytes`],
+/// as does serializing with the `serde` feature. A branch refers to its children by the `u32` index of the first, as
+/// they are held next to each other, so each `Node` takes a few bytes. The blocks of `Node`s dropped by simplifying and
+/// clearing are recycled by later edits, so heavy editing does not fragment the heap.
+///
+/// # Example
+/// ```
+/// # use svo_rs::{ArenaOctree, Error, Octree};
+/// # use core::num::NonZeroU32;
+/// #
+/// let mut arena = ArenaOctree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+/// arena.insert([9, 8, 31], 1).unwrap();
+/// assert!(matches!(arena.get([9, 8, 31]), Some(1)));
+///
+/// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+/// octree.insert([9, 8, 31], 1).unwrap();
+/// assert_eq!(arena.to_bytes(), octree.to_bytes());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ArenaOctree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    dimension: NonZeroU32,
+    background: T,
+    nodes: Vec<Slot<T>>,
+    /// The first free block of each length, from 1 to 8 slots.
+    free: [u32; OCTREE_CHILDREN],
+}
+
+impl<T> ArenaOctree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Creates a new `ArenaOctree<T>` of given dimension, which must be a power of 2, as by [`Octree::new`].
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{ArenaOctree, Error};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// assert!(ArenaOctree::<u8>::new(NonZeroU32::new(32).unwrap()).is_ok());
+    ///
+    /// let arena = ArenaOctree::<u8>::new(NonZeroU32::new(15).unwrap());
+    /// assert!(matches!(arena, Err(Error::InvalidDimension(15))));
+    /// ```
+    pub fn new(dimension: NonZeroU32) -> Result<Self, Error> {
+        Self::new_with_background(dimension, T::default())
+    }
+
+    /// Creates a new `ArenaOctree<T>` of given dimension, where unwritten and cleared space holds
+    /// `background` rather than `T::default()`, as by [`Octree::new_with_background`].
+    pub fn new_with_background(dimension: NonZeroU32, background: T) -> Result<Self, Error> {
+        if !dimension.is_power_of_two() {
+            return Err(Error::InvalidDimension(dimension.get()));
+        }
+
+        Ok(Self {
+            dimension,
+            background,
+            nodes: vec![Slot::Leaf(background)],
+            free: [NONE; OCTREE_CHILDREN],
+        })
+    }
+
+    /// Inserts data of type `T` into the given position, as by [`Octree::insert`].
+    pub fn insert(&mut self, position: impl Into<Vector3<u32>>, data: T) -> Result<(), Error> {
+        let position = <[u32; 3]>::from(position.into());
+        self.check(position)?;
+
+        let mut path = [ROOT; MAX_DEPTH];
+        let mut depth = 0;
+        let (mut index, mut dimension) = (ROOT, self.dimension());
+
+        loop {
+            if dimension == 1 {
+                self.set_leaf(index, data);
+                break;
+            }
+            if self.nodes[index as usize] == Slot::Leaf(data) {
+                break;
+            }
+
+            let octant = octant_index(position, dimension / 2);
+            let child = match self.split(index) {
+                (mask, first) if mask & 1 << octant != 0 => first + rank(mask, octant) as u32,
+                _ => self.add_child(index, octant),
+            };
+
+            path[depth] = index;
+            depth += 1;
+            index = child;
+            dimension /= 2;
+        }
+
+        for &index in path[..depth].iter().rev() {
+            self.merge(index);
+        }
+
+        Ok(())
+    }
+
+    /// Retrieves data of type `T` from the given position, as by [`Octree::get`].
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{ArenaOctree, Error};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut arena = ArenaOctree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// arena.insert([9, 8, 31], 1).unwrap();
+    ///
+    /// assert!(matches!(arena.get([9, 8, 31]), Some(1)));
+    /// assert!(matches!(arena.get([20, 1, 12]), Some(0)));
+    /// assert!(arena.get([32, 1, 12]).is_none());
+    /// ```
+    pub fn get(&self, position: impl Into<Vector3<u32>>) -> Option<&T> {
+        let position = <[u32; 3]>::from(position.into());
+        if !self.contains(position) {
+            return None;
+        }
+
+        let (mut index, mut dimension) = (ROOT, self.dimension());
+
+        loop {
+            match &self.nodes[index as usize] {
+                Slot::Leaf(data) => return Some(data),
+                Slot::Branch(mask, first) => {
+                    let octant = octant_index(position, dimension / 2);
+                    if mask & 1 << octant == 0 {
+                        return Some(&self.background);
+                    }
+
+                    index = first + rank(*mask, octant) as u32;
+                    dimension /= 2;
+                }
+                Slot::Free(_) => unreachable!("free slot reached from the root"),
+            }
+        }
+    }
+
+    /// Clears the voxel at the given position to the background, as by [`Octree::clear_at`].
+    pub fn clear_at(&mut self, position: impl Into<Vector3<u32>>) -> Result<(), Error> {
+        let position = <[u32; 3]>::from(position.into());
+        self.check(position)?;
+
+        let mut path = [ROOT; MAX_DEPTH];
+        let mut depth = 0;
+        let (mut index, mut dimension) = (ROOT, self.dimension());
+
+        loop {
+            if dimension == 1 {
+                self.set_leaf(index, self.background);
+                break;
+            }
+            if self.nodes[index as usize] == Slot::Leaf(self.background) {
+                break;
+            }
+
+            let octant = octant_index(position, dimension / 2);
+            match self.split(index) {
+                (mask, first) if mask & 1 << octant != 0 => {
+                    path[depth] = index;
+                    depth += 1;
+                    index = first + rank(mask, octant) as u32;
+                    dimension /= 2;
+                }
+                _ => break,
+            }
+        }
+
+        for &index in path[..depth].iter().rev() {
+            self.merge(index);
+        }
+
+        Ok(())
+    }
+
+    /// Removes all `Node`s, keeping the memory of the arena for later edits.
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.nodes.push(Slot::Leaf(self.background));
+        self.free = [NONE; OCTREE_CHILDREN];
+    }
+
+    /// Simplifies every `Node` whose children are leaves all holding the same data into a leaf holding that
+    /// data, as by [`Octree::simplify`].
+    ///
+    /// Edits already simplify the `Node`s they pass through, but an `ArenaOctree` copied from an `Octree` is
+    /// kept as it was.
+    pub fn simplify(&mut self) {
+        self.merge_below(ROOT);
+    }
+
+    /// Returns an iterator over the non-empty leaves, in octant order, as by [`Octree::iter_leaves`].
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{ArenaOctree, Error, LeafInfo};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut arena = ArenaOctree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// arena.insert([1, 0, 0], 2).unwrap();
+    ///
+    /// let leaves = arena.iter_leaves().collect::<Vec<_>>();
+    /// assert_eq!(leaves, vec![LeafInfo { min: [1, 0, 0], dimension: 1, data: 2 }]);
+    /// ```
+    pub fn iter_leaves(&self) -> ArenaLeaves<'_, T> {
+        ArenaLeaves {
+            octree: self,
+            stack: vec![(ROOT, [0; 3], self.dimension())],
+        }
+    }
+
+    /// Returns the value held by unwritten and cleared space.
+    pub fn background(&self) -> T {
+        self.background
+    }
+
+    /// Returns the dimension of the root node.
+    pub fn dimension(&self) -> u32 {
+        self.dimension.get()
+    }
+
+    /// Returns whether the given position exists within the confines of the `ArenaOctree`.
+    pub fn contains(&self, position: impl Into<Vector3<u32>>) -> bool {
+        let position = <[u32; 3]>::from(position.into());
+        position.iter().all(|c| *c < self.dimension())
+    }
+
+    fn check(&self, position: [u32; 3]) -> Result<(), Error> {
+        if self.contains(position) {
+            Ok(())
+        } else {
+            Err(Error::InvalidPosition(position.into()))
+        }
+    }
+
+    /// Takes a recycled block of `len` slots if there is one, or appends one to the arena, returning the index
+    /// of its first slot. The slots are left free until written.
+    fn alloc(&mut self, len: usize) -> u32 {
+        match self.free[len - 1] {
+            NONE => {
+                // Indices run out only after billions of `Node`s, long after memory does.
+                let end = u32::try_from(self.nodes.len() + len).expect("arena index overflow");
+                self.nodes.resize(end as usize, Slot::Free(NONE));
+                end - len as u32
+            }
+            index => {
+                match self.nodes[index as usize] {
+                    Slot::Free(next) => self.free[len - 1] = next,
+                    _ => unreachable!("used slot in the free list"),
+                }
+                index
+            }
+        }
+    }
+
+    /// Recycles the block of `len` slots starting at `first`, without freeing the `Node`s below them.
+    fn recycle(&mut self, first: u32, len: usize) {
+        let block = &mut self.nodes[first as usize..first as usize + len];
+        block.fill(Slot::Free(NONE));
+        block[0] = Slot::Free(self.free[len - 1]);
+        self.free[len - 1] = first;
+    }
+
+    /// Frees the children of the `Node` at `index` and every `Node` below them.
+    fn release(&mut self, index: u32) {
+        if let Slot::Branch(mask, first) = self.nodes[index as usize] {
+            let len = mask.count_ones();
+            if len > 0 {
+                for child in first..first + len {
+                    self.release(child);
+                }
+                self.recycle(first, len as usize);
+            }
+        }
+    }
+
+    /// Makes the `Node` at `index` a leaf holding `data`, freeing its children.
+    fn set_leaf(&mut self, index: u32, data: T) {
+        self.release(index);
+        self.nodes[index as usize] = Slot::Leaf(data);
+    }
+
+    /// Gives the branch at `index` a child in `octant` holding the background, moving its other children into
+    /// a block one slot longer, and returns the index of the new child.
+    fn add_child(&mut self, index: u32, octant: usize) -> u32 {
+        let (mask, first) = match self.nodes[index as usize] {
+            Slot::Branch(mask, first) => (mask, first),
+            _ => unreachable!("child added to a leaf"),
+        };
+        let (len, rank) = (mask.count_ones() as usize, rank(mask, octant));
+
+        let block = self.alloc(len + 1);
+        for i in 0..len {
+            let moved = block as usize + i + (i >= rank) as usize;
+            self.nodes[moved] = self.nodes[first as usize + i];
+        }
+        self.nodes[block as usize + rank] = Slot::Leaf(self.background);
+
+        if len > 0 {
+            self.recycle(first, len);
+        }
+        self.nodes[index as usize] = Slot::Branch(mask | 1 << octant, block);
+
+        block + rank as u32
+    }
+
+    /// Turns the `Node` at `index` into a branch with identical contents, as by `Node::split`, returning its
+    /// mask and first child.
+    fn split(&mut self, index: u32) -> (u8, u32) {
+        match self.nodes[index as usize] {
+            Slot::Branch(mask, first) => (mask, first),
+            Slot::Leaf(data) => {
+                let (mask, first) = if data == self.background {
+                    (0, NONE)
+                } else {
+                    let first = self.alloc(OCTREE_CHILDREN);
+                    self.nodes[first as usize..first as usize + OCTREE_CHILDREN].fill(Slot::Leaf(data));
+                    (u8::MAX, first)
+                };
+
+                self.nodes[index as usize] = Slot::Branch(mask, first);
+                (mask, first)
+            }
+            Slot::Free(_) => unreachable!("free slot reached from the root"),
+        }
+    }
+
+    /// Returns the data of the `Node` at `index`, if it is a leaf.
+    fn leaf_data(&self, index: u32) -> Option<T> {
+        match self.nodes.get(index as usize) {
+            Some(Slot::Leaf(data)) => Some(*data),
+            _ => None,
+        }
+    }
+
+    /// Makes the `Node` at `index` a leaf if all of its children are leaves holding the same data, as by
+    /// `Node::simplify`.
+    fn merge(&mut self, index: u32) {
+        if let Slot::Branch(u8::MAX, first) = self.nodes[index as usize] {
+            if let Some(data) = self.leaf_data(first) {
+                if (first..first + OCTREE_CHILDREN as u32).all(|child| self.leaf_data(child) == Some(data)) {
+                    self.set_leaf(index, data);
+                }
+            }
+        }
+    }
+
+    /// Merges every `Node` below the `Node` at `index`, and then that `Node`.
+    fn merge_below(&mut self, index: u32) {
+        if let Slot::Branch(mask, first) = self.nodes[index as usize] {
+            for child in first..first + mask.count_ones() {
+                self.merge_below(child);
+            }
+            self.merge(index);
+        }
+    }
+
+    /// Writes a copy of `node` and every `Node` below it to the slot at `index`.
+    fn copy(&mut self, node: NodeRef<'_, T>, index: u32) {
+        match node.leaf_data() {
+            Some(data) => self.nodes[index as usize] = Slot::Leaf(*data),
+            None => {
+                let mask = (0..OCTREE_CHILDREN)
+                    .filter(|octant| node.child(*octant).is_some())
+                    .fold(0_u8, |mask, octant| mask | 1 << octant);
+                let first = match mask {
+                    0 => NONE,
+                    _ => self.alloc(mask.count_ones() as usize),
+                };
+
+                self.nodes[index as usize] = Slot::Branch(mask, first);
+                for (child, octant) in (first..).zip(occupied(mask)) {
+                    self.copy(node.child(octant).unwrap(), child);
+                }
+            }
+        }
+    }
+
+    /// Rebuilds the `Node` at `index` and every `Node` below it.
+    fn node(&self, index: u32) -> Node<T> {
+        match self.nodes[index as usize] {
+            Slot::Leaf(data) => Node::leaf(data),
+            Slot::Branch(mask, first) => {
+                let mut octants: [Option<Node<T>>; OCTREE_CHILDREN] = Default::default();
+                for (child, octant) in (first..).zip(occupied(mask)) {
+                    octants[octant] = Some(self.node(child));
+                }
+
+                Node::branch(octants)
+            }
+            Slot::Free(_) => unreachable!("free slot reached from the root"),
+        }
+    }
+
+    /// Copies the `Node`s of an `Octree` into an arena, failing with [`Error::InvalidLodLevel`] if it is above
+    /// LOD level 1.
+    pub(crate) fn from_level_one(octree: &Octree<T>) -> Result<Self, Error> {
+        match octree.lod_level() {
+            1 => Ok(Self::from(octree)),
+            level => Err(Error::InvalidLodLevel(level)),
+        }
+    }
+
+    /// Returns the tokens of every `Node` in pre-order, as those of an `Octree` are listed by `Flatten`.
+    pub(crate) fn tokens(&self) -> impl Iterator<Item = Token<T>> + '_ {
+        let mut stack = vec![ROOT];
+        iter::from_fn(move || {
+            let token = match self.nodes[stack.pop()? as usize] {
+                Slot::Leaf(data) => Token::Leaf(data),
+                Slot::Branch(mask, first) => {
+                    // Push in reverse, so that children are listed in octant order.
+                    stack.extend((first..first + mask.count_ones()).rev());
+                    Token::Branch(mask)
+                }
+                Slot::Free(_) => unreachable!("free slot reached from the root"),
+            };
+
+            Some(token)
+        })
+    }
+}
+
+impl<T> ArenaOctree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + ValueCodec,
+{
+    /// Encodes the `ArenaOctree` as bytes, in the same layout as [`Octree::to_bytes`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        write_tokens(self.dimension(), 1, self.background, self.tokens())
+    }
+
+    /// Decodes an `ArenaOctree` encoded by [`ArenaOctree::to_bytes`] or [`Octree::to_bytes`].
+    ///
+    /// Fails as [`Octree::from_bytes`] does, and with [`Error::InvalidLodLevel`] if the bytes hold an `Octree`
+    /// above LOD level 1.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Self::from_level_one(&Octree::from_bytes(bytes)?)
+    }
+}
+
+impl<T> From<&Octree<T>> for ArenaOctree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Copies the `Node`s of an `Octree` into an arena. Journaled LOD detail and subtrees held in storage are
+    /// not copied.
+    fn from(octree: &Octree<T>) -> Self {
+        let mut arena =
+            Self::new_with_background(NonZeroU32::new(octree.dimension()).unwrap(), octree.background()).unwrap();
+        arena.copy(octree.root(), ROOT);
+
+        arena
+    }
+}
+
+impl<T> From<&ArenaOctree<T>> for Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Copies the `Node`s of an `ArenaOctree` into an `Octree` at LOD level 1.
+    fn from(arena: &ArenaOctree<T>) -> Self {
+        Octree::from_root(arena.dimension(), arena.node(ROOT), arena.background, 1).unwrap()
+    }
+}
+
+/// An iterator over the non-empty leaves of an [`ArenaOctree`], in octant order.
+///
+/// Created by [`ArenaOctree::iter_leaves`].
+pub struct ArenaLeaves<'a, T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    octree: &'a ArenaOctree<T>,
+    stack: Vec<(u32, [u32; 3], u32)>,
+}
+
+impl<'a, T> Iterator for ArenaLeaves<'a, T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    type Item = LeafInfo<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((index, min, dimension)) = self.stack.pop() {
+            match self.octree.nodes[index as usize] {
+                Slot::Leaf(data) if data != self.octree.background => {
+                    return Some(LeafInfo { min, dimension, data });
+                }
+                Slot::Leaf(_) => {}
+                Slot::Branch(mask, first) => {
+                    let half = dimension / 2;
+
+                    // Push in reverse, so that octants are yielded in order.
+                    for i in (0..OCTREE_CHILDREN).rev().filter(|i| mask & 1 << i != 0) {
+                        let offset = [i & 1, i >> 2 & 1, i >> 1 & 1].map(|bit| bit as u32 * half);
+                        let min = [0, 1, 2].map(|axis| min[axis] + offset[axis]);
+                        self.stack.push((first + rank(mask, i) as u32, min, half));
+                    }
+                }
+                Slot::Free(_) => unreachable!("free slot reached from the root"),
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArenaOctree, Slot};
+    use crate::{
+        node::{Node, NodeSlot, OCTREE_CHILDREN},
+        test_utils::{assert_converts_octree, assert_matches_octree, assert_rejects_out_of_bounds, XorShift},
+        Octree,
+    };
+
+    use core::{mem, num::NonZeroU32};
+    use std::time::Instant;
+
+    #[test]
+    fn matches_octree() {
+        assert_matches_octree::<ArenaOctree<u8>>(0xa7e4);
+    }
+
+    #[test]
+    fn converts_to_and_from_octree() {
+        assert_converts_octree::<ArenaOctree<u8>>(0xa7e5);
+    }
+
+    #[test]
+    fn out_of_bounds_positions_are_rejected() {
+        assert_rejects_out_of_bounds::<ArenaOctree<u8>>();
+    }
+
+    #[test]
+    fn copies_are_simplified_as_octrees_are() {
+        // Decoded trees are kept as they were encoded, and so are their copies.
+        let uniform = |data| Node::branch([(); OCTREE_CHILDREN].map(|_| Some(Node::leaf(data))));
+        let mut octants = [(); OCTREE_CHILDREN].map(|_| Some(uniform(1)));
+        octants[2] = Some(Node::branch([(); OCTREE_CHILDREN].map(|_| Some(uniform(2)))));
+        octants[5] = None;
+        let octree = Octree::from_root(8, Node::branch(octants), 0, 1).unwrap();
+
+        let mut arena = ArenaOctree::from(&octree);
+        assert_eq!(arena.to_bytes(), octree.to_bytes());
+
+        let mut simplified = octree.clone();
+        simplified.simplify();
+        arena.simplify();
+        assert!(arena.to_bytes().len() < octree.to_bytes().len());
+        assert_eq!(arena.to_bytes(), simplified.to_bytes());
+
+        // Every slot dropped by simplifying is free for reuse.
+        let used = Octree::from(&arena).node_count();
+        let free = arena.nodes.iter().filter(|slot| matches!(slot, Slot::Free(_))).count();
+        assert_eq!(used + free, arena.nodes.len());
+    }
+
+    #[test]
+    fn freed_slots_are_reused() {
+        let mut arena = ArenaOctree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+        let mut rng = XorShift::new(0xa7e6);
+
+        for _ in 0..2000 {
+            arena.insert(rng.position(32), 1 + rng.below(3) as u8).unwrap();
+            arena.clear_at(rng.position(32)).unwrap();
+        }
+
+        // Filling the tree drops every `Node` but the root, so that all others are free.
+        for x in 0..32 {
+            for y in 0..32 {
+                for z in 0..32 {
+                    arena.insert([x, y, z], 7).unwrap();
+                }
+            }
+        }
+        assert_eq!(arena.nodes[0], Slot::Leaf(7));
+        let free = arena.nodes.iter().filter(|slot| matches!(slot, Slot::Free(_))).count();
+        assert_eq!(free, arena.nodes.len() - 1);
+
+        // Writing and clearing voxels takes their `Node`s from the free list rather than growing the arena.
+        let len = arena.nodes.len();
+        for _ in 0..2000 {
+            let position = rng.position(32);
+            arena.insert(position, 1).unwrap();
+            arena.insert(position, 7).unwrap();
+        }
+        assert_eq!(arena.nodes.len(), len);
+        assert_eq!(arena.nodes[0], Slot::Leaf(7));
+    }
+
+    /// Compares the memory taken by each `Node` of both backends.
+    ///
+    /// The children of a `Node` of an `Octree` share a heap allocation, which allocators prefix with a header
+    /// of at least 16 bytes on 64-bit targets, and each holds its own children and their occupancy. Each `Node`
+    /// of an `ArenaOctree` is an element of one `Vec`, holding the mask of its children and the index of the
+    /// first, as they are held next to each other.
+    #[test]
+    fn nodes_take_less_memory() {
+        const ALLOCATION_HEADER: usize = 16;
+
+        let boxed = mem::size_of::<NodeSlot<u8>>() + ALLOCATION_HEADER / OCTREE_CHILDREN;
+        let arena = mem::size_of::<Slot<u8>>();
+        assert!(
+            arena * 4 < boxed,
+            "bytes per node: {} boxed, {} in an arena",
+            boxed,
+            arena
+        );
+
+        let octree = XorShift::new(0xa7e7).octree(64, 5000, 8);
+        let arena = ArenaOctree::from(&octree);
+        let boxed_bytes = octree.root().heap_bytes() + mem::size_of::<Node<u8>>();
+        let arena_bytes = arena.nodes.capacity() * mem::size_of::<Slot<u8>>();
+        assert!(
+            arena_bytes * 2 < boxed_bytes,
+            "bytes for {} nodes: {} boxed, {} in an arena",
+            arena.nodes.len(),
+            boxed_bytes,
+            arena_bytes
+        );
+    }
+
+    /// Times reading every voxel of a large tree through both backends.
+    ///
+    /// Timings are only meaningful in release builds, so this is ignored by default; run it with
+    /// `cargo test --release -- --ignored reading_is_faster`.
+    #[test]
+    #[ignore]
+    fn reading_is_faster() {
+        let octree = XorShift::new(0xa7e8).octree(128, 200_000, 8);
+        let arena = ArenaOctree::from(&octree);
+
+        let time = |get: &dyn Fn([u32; 3]) -> Option<u8>| {
+            let start = Instant::now();
+            let mut sum = 0_u64;
+            for _ in 0..4 {
+                for x in 0..128 {
+                    for y in 0..128 {
+                        for z in 0..128 {
+                            sum += get([x, y, z]).unwrap_or(0) as u64;
+                        }
+                    }
+                }
+            }
+
+            (start.elapsed(), sum)
+        };
+
+        let (boxed, boxed_sum) = time(&|position| octree.get(position).copied());
+        let (arena, arena_sum) = time(&|position| arena.get(position).copied());
+
+        assert_eq!(boxed_sum, arena_sum);
+        assert!(
+            arena < boxed,
+            "reading every voxel 4 times: {:?} boxed, {:?} in an arena",
+            boxed,
+            arena
+        );
+    }
+}
@@ -0,0 +1,730 @@
+use crate::{Error, Node, NodeRef, Octree};
+
+use core::{fmt::Debug, hash::Hash, ptr};
+
+/// One operand of a boolean operation within a cube: either uniform data, or a `Node` to descend into
+/// along with the background of its `Octree`.
+#[derive(Clone, Copy)]
+enum Operand<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    Uniform(T),
+    Node(NodeRef<'a, T>, T),
+}
+
+impl<'a, T> Operand<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    /// Describes the contents of a child, treating unwritten space as `background`.
+    fn from_child(child: Option<NodeRef<'a, T>>, background: T) -> Self {
+        match child {
+            Some(node) => match node.leaf_data() {
+                Some(data) => Self::Uniform(*data),
+                None => Self::Node(node, background),
+            },
+            None => Self::Uniform(background),
+        }
+    }
+
+    fn is_empty(&self, empty: T) -> bool {
+        matches!(self, Self::Uniform(data) if *data == empty)
+    }
+
+    /// Returns whether both operands are the very same subtree.
+    fn is_same(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Node(a, _), Self::Node(b, _)) => a.node().zip(b.node()).is_some_and(|(a, b)| ptr::eq(a, b)),
+            _ => false,
+        }
+    }
+
+    /// Counts the voxels of the operand not holding `empty` within a cube of the given dimension.
+    fn count(&self, dimension: u32, empty: T) -> u64 {
+        match self {
+            Self::Uniform(data) if *data == empty => 0,
+            Self::Uniform(_) => (dimension as u64).pow(3),
+            Self::Node(..) => self
+                .octants()
+                .iter()
+                .map(|octant| octant.count(dimension / 2, empty))
+                .sum(),
+        }
+    }
+
+    /// Splits the operand into its eight octants, in octant order.
+    fn octants(&self) -> [Self; 8] {
+        match self {
+            Self::Uniform(data) => [Self::Uniform(*data); 8],
+            Self::Node(node, background) => {
+                let mut octants = [Self::Uniform(*background); 8];
+                for (i, octant) in octants.iter_mut().enumerate() {
+                    *octant = Self::from_child(node.child(i), *background);
+                }
+
+                octants
+            }
+        }
+    }
+
+    /// Builds a `Node` holding the contents of the operand, for an `Octree` with the given background.
+    ///
+    /// The subtree of the operand is reused whole if its background is the same, and rebuilt otherwise so
+    /// that its unwritten space keeps its data.
+    fn to_node(self, background: T) -> Node<T> {
+        match self {
+            Self::Uniform(data) => Node::leaf(data),
+            Self::Node(node, own) if own == background => node.to_node(),
+            Self::Node(..) => Node::from_octants(self.octants().map(|octant| octant.to_node(background)), background),
+        }
+    }
+}
+
+/// A voxel-wise operation on two `Octree`s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// Non-empty data from either operand.
+    Union,
+    /// Non-empty data from the left operand, wherever the right operand is empty.
+    Difference,
+    /// Non-empty data from whichever operand is non-empty, wherever the other is empty.
+    SymmetricDifference,
+}
+
+impl Kind {
+    /// Returns the result of the operation within a cube without descending further, if the operands
+    /// already decide it. Voxels holding `empty` are empty.
+    fn shortcut<'a, T>(self, left: Operand<'a, T>, right: Operand<'a, T>, empty: T) -> Option<Operand<'a, T>>
+    where
+        T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+    {
+        let cleared = Operand::Uniform(empty);
+
+        match self {
+            Self::Difference | Self::SymmetricDifference if left.is_same(&right) => Some(cleared),
+            Self::Union | Self::SymmetricDifference if left.is_empty(empty) => Some(right),
+            Self::Union | Self::SymmetricDifference if right.is_empty(empty) => Some(left),
+            Self::Difference if left.is_empty(empty) || right.is_empty(empty) => Some(left),
+            Self::Difference if matches!(right, Operand::Uniform(_)) => Some(cleared),
+            _ => None,
+        }
+    }
+}
+
+/// A voxel-wise operation on two `Octree`s, treating voxels holding the background of the left `Octree` as
+/// empty.
+struct Operation<F> {
+    kind: Kind,
+    /// Combines two non-empty values, where the operation does not decide the result itself.
+    combine: F,
+}
+
+impl<F> Operation<F> {
+    fn run<T>(&self, left: &Octree<T>, right: &Octree<T>) -> Result<Octree<T>, Error>
+    where
+        T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+        F: Fn(&T, &T) -> T,
+    {
+        if left.dimension() != right.dimension() {
+            return Err(Error::DimensionMismatch {
+                expected: left.dimension(),
+                found: right.dimension(),
+            });
+        }
+
+        let root = self.apply(
+            Operand::from_child(Some(left.root()), left.background()),
+            Operand::from_child(Some(right.root()), right.background()),
+            left.background(),
+        );
+
+        Ok(left.with_root(root))
+    }
+
+    /// Applies the operation within a cube, descending only where the operands do not decide the result.
+    fn apply<T>(&self, left: Operand<'_, T>, right: Operand<'_, T>, empty: T) -> Node<T>
+    where
+        T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+        F: Fn(&T, &T) -> T,
+    {
+        if let Some(result) = self.kind.shortcut(left, right, empty) {
+            return result.to_node(empty);
+        }
+
+        match (left, right) {
+            (Operand::Uniform(left), Operand::Uniform(right)) => Node::leaf((self.combine)(&left, &right)),
+            _ => {
+                let (left, right) = (left.octants(), right.octants());
+
+                Node::from_octants(
+                    [0, 1, 2, 3, 4, 5, 6, 7].map(|i| self.apply(left[i], right[i], empty)),
+                    empty,
+                )
+            }
+        }
+    }
+}
+
+/// Counts the voxels within a cube of the given dimension where exactly one of the operands is non-empty.
+fn count_symmetric_difference<T>(left: Operand<'_, T>, right: Operand<'_, T>, dimension: u32, empty: T) -> u64
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    if left.is_same(&right) {
+        0
+    } else if left.is_empty(empty) {
+        right.count(dimension, empty)
+    } else if right.is_empty(empty) {
+        left.count(dimension, empty)
+    } else if let (Operand::Uniform(_), Operand::Uniform(_)) = (left, right) {
+        0
+    } else {
+        let (left, right) = (left.octants(), right.octants());
+
+        left.iter()
+            .zip(right.iter())
+            .map(|(left, right)| count_symmetric_difference(*left, *right, dimension / 2, empty))
+            .sum()
+    }
+}
+
+/// Returns whether the operands hold the same data in every voxel, descending into whichever holds detail.
+fn equivalent<T>(left: Operand<'_, T>, right: Operand<'_, T>) -> bool
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    match (left, right) {
+        _ if left.is_same(&right) => true,
+        (Operand::Uniform(left), Operand::Uniform(right)) => left == right,
+        _ => {
+            let (left, right) = (left.octants(), right.octants());
+            left.iter()
+                .zip(right.iter())
+                .all(|(left, right)| equivalent(*left, *right))
+        }
+    }
+}
+
+/// Clears every voxel of `node` to `background` where `other` does not hold `background`, in place.
+///
+/// Nothing is allocated where `other` is empty, and leaves of `node` are only split where `other` holds
+/// detail.
+fn subtract<T>(node: &mut Node<T>, other: Operand<'_, T>, background: T)
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    if other.is_empty(background) || node.leaf_data() == Some(&background) {
+        return;
+    }
+
+    if let Operand::Uniform(_) = other {
+        *node = Node::leaf(background);
+        return;
+    }
+
+    node.split(background);
+
+    for (octant, other) in other.octants().iter().enumerate() {
+        if let Some(child) = node.child_mut(octant) {
+            subtract(child, *other, background);
+
+            if child.leaf_data() == Some(&background) {
+                node.remove_child(octant);
+            }
+        }
+    }
+
+    if node.children().next().is_none() {
+        *node = Node::leaf(background);
+    } else {
+        node.simplify();
+    }
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Returns a new `Octree` where each voxel holds the non-empty data of either this `Octree` or `other`.
+    ///
+    /// Where both hold non-empty data, `resolve` is called with the data of this `Octree` and of `other`
+    /// to decide the result. Voxels holding the background of this `Octree` are empty, and the unwritten
+    /// space of `other` holds its own background. Subtrees of either `Octree` are reused whole wherever the
+    /// other is empty, so only the structure the two share is traversed. Returns an error if the dimensions
+    /// of the two `Octree`s differ.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut a = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// a.insert([0, 0, 0], 1).unwrap();
+    /// a.insert([1, 0, 0], 1).unwrap();
+    ///
+    /// let mut b = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// b.insert([1, 0, 0], 2).unwrap();
+    /// b.insert([2, 0, 0], 2).unwrap();
+    ///
+    /// let union = a.union(&b, |a, b| *a.max(b)).unwrap();
+    /// assert!(matches!(union.get([0, 0, 0]), Some(1)));
+    /// assert!(matches!(union.get([1, 0, 0]), Some(2)));
+    /// assert!(matches!(union.get([2, 0, 0]), Some(2)));
+    ///
+    /// let small = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+    /// assert!(matches!(a.union(&small, |a, _| *a), Err(Error::DimensionMismatch { .. })));
+    /// ```
+    pub fn union(&self, other: &Octree<T>, resolve: impl Fn(&T, &T) -> T) -> Result<Octree<T>, Error> {
+        Operation {
+            kind: Kind::Union,
+            combine: resolve,
+        }
+        .run(self, other)
+    }
+
+    /// Returns a new `Octree` holding the data of this `Octree`, cleared wherever `other` is non-empty.
+    ///
+    /// Voxels holding the background of this `Octree` are empty, and cleared voxels take it. Subtrees of
+    /// this `Octree` are reused whole wherever `other` is empty, and dropped whole wherever `other` holds a
+    /// single non-empty leaf, so leaves are only split along the boundary of `other`. Returns an error if the
+    /// dimensions of the two `Octree`s differ.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut a = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// a.insert([0, 0, 0], 1).unwrap();
+    /// a.insert([1, 0, 0], 1).unwrap();
+    ///
+    /// let mut b = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// b.insert([1, 0, 0], 2).unwrap();
+    ///
+    /// let difference = a.difference(&b).unwrap();
+    /// assert!(matches!(difference.get([0, 0, 0]), Some(1)));
+    /// assert!(!matches!(difference.get([1, 0, 0]), Some(1)));
+    /// ```
+    pub fn difference(&self, other: &Octree<T>) -> Result<Octree<T>, Error> {
+        let background = self.background();
+        Operation {
+            kind: Kind::Difference,
+            combine: |_: &T, _: &T| background,
+        }
+        .run(self, other)
+    }
+
+    /// Returns a new `Octree` holding the non-empty data of whichever of this `Octree` and `other` is
+    /// non-empty, wherever the other is empty.
+    ///
+    /// Voxels which are non-empty in both are cleared, whatever their data, so regions the two share vanish
+    /// from the result. Voxels holding the background of this `Octree` are empty. Subtrees of either
+    /// `Octree` are reused whole wherever the other is empty. Returns an error if the dimensions of the two
+    /// `Octree`s differ.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut a = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// a.insert([0, 0, 0], 1).unwrap();
+    /// a.insert([1, 0, 0], 1).unwrap();
+    ///
+    /// let mut b = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// b.insert([1, 0, 0], 2).unwrap();
+    /// b.insert([2, 0, 0], 2).unwrap();
+    ///
+    /// let difference = a.symmetric_difference(&b).unwrap();
+    /// assert!(matches!(difference.get([0, 0, 0]), Some(1)));
+    /// assert!(!matches!(difference.get([1, 0, 0]), Some(1) | Some(2)));
+    /// assert!(matches!(difference.get([2, 0, 0]), Some(2)));
+    /// ```
+    pub fn symmetric_difference(&self, other: &Octree<T>) -> Result<Octree<T>, Error> {
+        let background = self.background();
+        Operation {
+            kind: Kind::SymmetricDifference,
+            combine: |_: &T, _: &T| background,
+        }
+        .run(self, other)
+    }
+
+    /// Returns the number of voxels which are non-empty in exactly one of this `Octree` and `other`.
+    ///
+    /// Counts the voxels [`Octree::symmetric_difference`] would keep, without building the result.
+    /// Returns an error if the dimensions of the two `Octree`s differ.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut a = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// a.insert([0, 0, 0], 1).unwrap();
+    /// a.insert([1, 0, 0], 1).unwrap();
+    ///
+    /// let mut b = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// b.insert([1, 0, 0], 2).unwrap();
+    /// b.insert([2, 0, 0], 2).unwrap();
+    ///
+    /// assert_eq!(a.symmetric_difference_count(&b).unwrap(), 2);
+    /// ```
+    pub fn symmetric_difference_count(&self, other: &Octree<T>) -> Result<u64, Error> {
+        if self.dimension() != other.dimension() {
+            return Err(Error::DimensionMismatch {
+                expected: self.dimension(),
+                found: other.dimension(),
+            });
+        }
+
+        Ok(count_symmetric_difference(
+            Operand::from_child(Some(self.root()), self.background()),
+            Operand::from_child(Some(other.root()), other.background()),
+            self.dimension(),
+            self.background(),
+        ))
+    }
+
+    /// Returns whether this `Octree` and `other` hold the same data in every voxel, whatever their internal
+    /// structure.
+    ///
+    /// Unwritten space counts as the background of its `Octree`, so a simplified leaf is equivalent to the
+    /// children it was simplified from, and an unwritten region to one cleared explicitly. Returns `false` if
+    /// the dimensions of the two `Octree`s differ.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut a = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// a.insert([0, 0, 0], 1).unwrap();
+    ///
+    /// let mut b = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// b.insert([0, 0, 0], 1).unwrap();
+    /// b.insert([5, 5, 5], 2).unwrap();
+    /// b.clear_at([5, 5, 5]).unwrap();
+    ///
+    /// assert!(a.equivalent(&b));
+    ///
+    /// b.insert([0, 0, 0], 2).unwrap();
+    /// assert!(!a.equivalent(&b));
+    /// ```
+    pub fn equivalent(&self, other: &Octree<T>) -> bool {
+        self.dimension() == other.dimension()
+            && equivalent(
+                Operand::from_child(Some(self.root()), self.background()),
+                Operand::from_child(Some(other.root()), other.background()),
+            )
+    }
+
+    /// Clears every voxel of this `Octree` wherever `other` is non-empty, in place.
+    ///
+    /// Behaves as [`Octree::difference`], but only touches the parts of this `Octree` overlapping non-empty
+    /// data in `other`, so subtracting a small `Octree` from a large one is cheap. Returns an error, without
+    /// modifying this `Octree`, if the dimensions of the two `Octree`s differ.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut world = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// world.insert([4, 4, 4], 1).unwrap();
+    /// world.insert([5, 4, 4], 1).unwrap();
+    ///
+    /// let mut brush = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// brush.insert([5, 4, 4], 1).unwrap();
+    ///
+    /// world.subtract_assign(&brush).unwrap();
+    /// assert!(matches!(world.get([4, 4, 4]), Some(1)));
+    /// assert!(!matches!(world.get([5, 4, 4]), Some(1)));
+    /// ```
+    pub fn subtract_assign(&mut self, other: &Octree<T>) -> Result<(), Error> {
+        if self.dimension() != other.dimension() {
+            return Err(Error::DimensionMismatch {
+                expected: self.dimension(),
+                found: other.dimension(),
+            });
+        }
+
+        let background = self.background();
+        subtract(
+            self.root_mut(),
+            Operand::from_child(Some(other.root()), other.background()),
+            background,
+        );
+        Ok(())
+    }
+}
+
+/// `Octree`s are equal when they hold the same data in every voxel, as decided by [`Octree::equivalent`],
+/// whatever their internal structure.
+impl<T> PartialEq for Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.equivalent(other)
+    }
+}
+
+impl<T> Eq for Octree<T> where T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_utils::XorShift, Error, Node, Octree};
+
+    use alloc::vec::Vec;
+    use core::num::NonZeroU32;
+
+    /// Checks every voxel of `result` against `expected`, given the data of both operands.
+    fn check(result: &Octree<u8>, a: &Octree<u8>, b: &Octree<u8>, expected: impl Fn(u8, u8) -> u8) {
+        let dimension = result.dimension();
+
+        for x in 0..dimension {
+            for y in 0..dimension {
+                for z in 0..dimension {
+                    let data = |octree: &Octree<u8>| *octree.get([x, y, z]).unwrap_or(&0);
+                    assert_eq!(data(result), expected(data(a), data(b)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn union_matches_voxel_reference() {
+        let mut rng = XorShift::new(0x0410);
+
+        for _ in 0..20 {
+            let a = rng.octree(16, 300, 3);
+            let b = rng.octree(16, 300, 3);
+
+            let union = a.union(&b, |a, b| a * 10 + b).unwrap();
+            check(&union, &a, &b, |a, b| match (a, b) {
+                (0, b) => b,
+                (a, 0) => a,
+                (a, b) => a * 10 + b,
+            });
+        }
+    }
+
+    #[test]
+    fn union_with_empty_reuses_structure() {
+        let mut rng = XorShift::new(0x0411);
+        let a = rng.octree(16, 300, 3);
+        let empty = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+
+        let spans = |octree: &Octree<u8>| {
+            octree
+                .query_region([0; 3], [16; 3])
+                .map(|(min, dimensions, data)| (min, dimensions, data.copied()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(spans(&a.union(&empty, |_, _| 0).unwrap()), spans(&a));
+        assert_eq!(spans(&empty.union(&a, |_, _| 0).unwrap()), spans(&a));
+    }
+
+    #[test]
+    fn union_simplifies_result() {
+        let mut a = Octree::<u8>::new(NonZeroU32::new(4).unwrap()).unwrap();
+        let mut b = Octree::<u8>::new(NonZeroU32::new(4).unwrap()).unwrap();
+
+        for i in 0..8 {
+            let position = [i & 1, (i >> 1) & 1, (i >> 2) & 1];
+            if i % 2 == 0 {
+                a.insert(position, 1).unwrap();
+            } else {
+                b.insert(position, 1).unwrap();
+            }
+        }
+
+        let union = a.union(&b, |a, _| *a).unwrap();
+        assert_eq!(union.query_region([0; 3], [2; 3]).count(), 1);
+    }
+
+    #[test]
+    fn union_dimension_mismatch() {
+        let a = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        let b = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+
+        assert_eq!(
+            a.union(&b, |a, _| *a).err(),
+            Some(Error::DimensionMismatch { expected: 16, found: 8 })
+        );
+    }
+
+    /// Collects the address of every `Node` below `node`.
+    fn addresses(node: &Node<u8>, out: &mut Vec<*const Node<u8>>) {
+        out.push(node as *const _);
+        for child in node.children() {
+            addresses(child, out);
+        }
+    }
+
+    #[test]
+    fn difference_matches_voxel_reference() {
+        let mut rng = XorShift::new(0xd1ff);
+
+        for _ in 0..20 {
+            let seed = rng.next_u32() as u64;
+            let a = XorShift::new(seed).octree(16, 300, 3);
+            let b = rng.octree(16, 150, 3);
+
+            let difference = a.difference(&b).unwrap();
+            check(&difference, &a, &b, |a, b| if b == 0 { a } else { 0 });
+
+            let mut in_place = XorShift::new(seed).octree(16, 300, 3);
+            in_place.subtract_assign(&b).unwrap();
+            check(&in_place, &a, &b, |a, b| if b == 0 { a } else { 0 });
+
+            let spans = |octree: &Octree<u8>| {
+                octree
+                    .query_region([0; 3], [16; 3])
+                    .map(|(min, dimensions, data)| (min, dimensions, data.copied().unwrap_or(0)))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(spans(&in_place), spans(&difference));
+        }
+    }
+
+    #[test]
+    fn large_leaf_drops_fine_detail() {
+        let mut rng = XorShift::new(0xd1f0);
+        let mut a = rng.octree(16, 400, 3);
+
+        let mut b = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        b.insert_sphere([4.0, 4.0, 4.0], 100.0, 1, true).unwrap();
+        b.insert([15, 15, 15], 0).unwrap();
+
+        a.subtract_assign(&b).unwrap();
+
+        // Everything but the one voxel left empty in `b` is cleared.
+        let written = a
+            .query_region([0; 3], [16; 3])
+            .filter(|(_, _, data)| matches!(data, Some(data) if **data != 0))
+            .count();
+        assert!(written <= 1);
+
+        // Only the path down to that voxel remains split, leaving seven spans at each level besides it.
+        assert!(a.query_region([0; 3], [16; 3]).count() <= 4 * 7 + 1);
+    }
+
+    #[test]
+    fn subtracting_empty_is_no_op() {
+        let mut rng = XorShift::new(0xd1f1);
+        let mut a = rng.octree(16, 300, 3);
+        let empty = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+
+        let mut before = Vec::new();
+        addresses(a.root().node().unwrap(), &mut before);
+
+        a.subtract_assign(&empty).unwrap();
+
+        let mut after = Vec::new();
+        addresses(a.root().node().unwrap(), &mut after);
+        assert_eq!(before, after);
+
+        assert_eq!(
+            a.subtract_assign(&Octree::new(NonZeroU32::new(8).unwrap()).unwrap()),
+            Err(Error::DimensionMismatch { expected: 16, found: 8 })
+        );
+    }
+
+    #[test]
+    fn symmetric_difference_matches_voxel_reference() {
+        let mut rng = XorShift::new(0x0e0e);
+
+        for _ in 0..20 {
+            let a = rng.octree(16, 300, 3);
+            let b = rng.octree(16, 300, 3);
+
+            let difference = a.symmetric_difference(&b).unwrap();
+            check(&difference, &a, &b, |a, b| match (a, b) {
+                (0, b) => b,
+                (a, 0) => a,
+                _ => 0,
+            });
+
+            let mut expected = 0;
+            for x in 0..16 {
+                for y in 0..16 {
+                    for z in 0..16 {
+                        let a = *a.get([x, y, z]).unwrap_or(&0) != 0;
+                        let b = *b.get([x, y, z]).unwrap_or(&0) != 0;
+                        expected += (a != b) as u64;
+                    }
+                }
+            }
+
+            assert_eq!(a.symmetric_difference_count(&b).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn identical_regions_vanish() {
+        let mut rng = XorShift::new(0x0e0f);
+        let seed = rng.next_u32() as u64;
+
+        let a = XorShift::new(seed).octree(16, 300, 3);
+        let mut b = XorShift::new(seed).octree(16, 300, 3);
+        b.insert([3, 3, 3], 7).unwrap();
+        b.insert([12, 3, 3], 0).unwrap();
+
+        let changed = (*a.get([3, 3, 3]).unwrap_or(&0) == 0) as u64 + (*a.get([12, 3, 3]).unwrap_or(&0) != 0) as u64;
+        assert_eq!(a.symmetric_difference_count(&b).unwrap(), changed);
+        assert_eq!(a.symmetric_difference_count(&a).unwrap(), 0);
+
+        let same = a.symmetric_difference(&a).unwrap();
+        assert_eq!(
+            same.query_region([0; 3], [16; 3]).collect::<Vec<_>>(),
+            vec![([0; 3], [16; 3], Some(&0))]
+        );
+    }
+
+    #[test]
+    fn equivalent_ignores_structure() {
+        let mut rng = XorShift::new(0xe0e0);
+
+        for _ in 0..20 {
+            let seed = rng.next_u32() as u64;
+            let a = XorShift::new(seed).octree(16, 300, 3);
+            let mut b = XorShift::new(seed).octree(16, 300, 3);
+
+            // Splitting every leaf of `b` leaves its contents unchanged.
+            let mut stack = vec![(b.root_mut(), 16)];
+            while let Some((node, dimension)) = stack.pop() {
+                if dimension > 1 {
+                    node.split(0);
+                    stack.extend(node.children_mut().map(|child| (child, dimension / 2)));
+                }
+            }
+
+            assert!(a.equivalent(&b));
+            assert_eq!(a, b);
+
+            let position = rng.position(16);
+            let data = *b.get(position).unwrap_or(&0);
+            b.insert(position, data + 1).unwrap();
+            assert_ne!(a, b);
+
+            b.insert(position, data).unwrap();
+            assert_eq!(a, b);
+        }
+    }
+
+    #[test]
+    fn gaps_equal_default_leaves() {
+        let mut a = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        let b = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+
+        a.insert([1, 2, 3], 4).unwrap();
+        assert_ne!(a, b);
+
+        a.clear_at([1, 2, 3]).unwrap();
+        assert_eq!(a, b);
+        assert_ne!(a, Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap());
+    }
+}
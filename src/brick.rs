@@ -0,0 +1,390 @@
+use crate::{node::OCTREE_CHILDREN, Node, NodeRef, Vector3};
+
+use alloc::{boxed::Box, vec};
+use core::{fmt::Debug, hash::Hash, mem, ops::Range};
+
+/// The dimensions a [`Brick`] may have.
+pub(crate) const BRICK_DIMENSIONS: [u32; 2] = [4, 8];
+
+/// The most voxels a [`Brick`] holds, those of a cube of the largest dimension allowed.
+const MAX_VOXELS: usize = 512;
+
+/// A subtree of an `Octree` held as the data of every voxel within it rather than as `Node`s, as enabled by
+/// [`Octree::enable_bricks`](crate::Octree::enable_bricks).
+///
+/// Voxels are listed in octant order, as by `node::octant_order`, so that the voxels of every cube within the brick
+/// aligned to its own dimension are listed together. A brick stands for the `Node`s an `Octree` would hold in its
+/// place: every such cube whose voxels have all been written with the same data is a leaf, every cube holding no
+/// written voxel is an empty octant, and every other cube is an internal `Node`. Bricks are aligned to their
+/// dimension, so the voxel at a position is found from its coordinates alone.
+#[derive(Debug, Clone)]
+pub(crate) struct Brick<T> {
+    dimension: u32,
+    /// The bit of each voxel which has been written, in octant order.
+    written: [u64; MAX_VOXELS / 64],
+    values: Box<[T]>,
+}
+
+impl<T> Brick<T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    /// Creates a new `Brick<T>` holding the voxels of `node`.
+    pub(crate) fn from_node(node: NodeRef<'_, T>) -> Self {
+        let mut brick = Self {
+            dimension: node.dimension(),
+            written: [0; MAX_VOXELS / 64],
+            values: vec![T::default(); node.dimension().pow(3) as usize].into_boxed_slice(),
+        };
+
+        let mut stack = vec![node];
+        while let Some(node) = stack.pop() {
+            match node.leaf_data() {
+                Some(data) => brick.write(brick.range(node.min_position(), node.dimension()), *data),
+                None => stack.extend(node.children()),
+            }
+        }
+
+        brick
+    }
+
+    /// Returns the index of the voxel at the given position.
+    fn index(&self, position: Vector3<u32>) -> usize {
+        let position: [u32; 3] = position.into();
+
+        (0..self.dimension.trailing_zeros()).rev().fold(0, |index, bit| {
+            let [x, y, z] = position.map(|c| (c >> bit & 1) as usize);
+            index << 3 | y << 2 | z << 1 | x
+        })
+    }
+
+    /// Returns the range of the voxels of the cube of the given dimension at the given position.
+    pub(crate) fn range(&self, min: Vector3<u32>, dimension: u32) -> Range<usize> {
+        let start = self.index(min);
+        start..start + dimension.pow(3) as usize
+    }
+
+    /// Returns the range of every voxel of the `Brick`.
+    pub(crate) fn all(&self) -> Range<usize> {
+        0..self.values.len()
+    }
+
+    fn is_written(&self, index: usize) -> bool {
+        self.written[index / 64] & 1 << (index % 64) != 0
+    }
+
+    fn write(&mut self, range: Range<usize>, data: T) {
+        for index in range {
+            self.written[index / 64] |= 1 << (index % 64);
+            self.values[index] = data;
+        }
+    }
+
+    fn erase(&mut self, range: Range<usize>) {
+        for index in range {
+            self.written[index / 64] &= !(1 << (index % 64));
+        }
+    }
+
+    /// Returns the data of the given voxels if they have all been written with the same data, so that they stand
+    /// for a leaf.
+    pub(crate) fn uniform(&self, range: Range<usize>) -> Option<&T> {
+        let data = &self.values[range.start];
+        range
+            .clone()
+            .all(|index| self.is_written(index) && self.values[index] == *data)
+            .then_some(data)
+    }
+
+    /// Returns whether none of the given voxels have been written, so that they stand for an empty octant.
+    pub(crate) fn is_empty(&self, range: Range<usize>) -> bool {
+        range.clone().all(|index| !self.is_written(index))
+    }
+
+    /// Returns the range and dimension of the leaf holding the voxel with the given index, no larger than
+    /// `max_dimension`, or `None` if the voxel has never been written.
+    ///
+    /// The cube of each dimension holding a voxel holds that of the dimension below it, so the leaf is found by
+    /// growing a cube from the voxel for as long as it stays a leaf.
+    fn leaf(&self, index: usize, max_dimension: u32) -> Option<(Range<usize>, u32)> {
+        if !self.is_written(index) {
+            return None;
+        }
+
+        let (mut range, mut dimension) = (index..index + 1, 1);
+        while dimension < max_dimension {
+            let len = range.len() * OCTREE_CHILDREN;
+            let parent = index & !(len - 1)..(index & !(len - 1)) + len;
+            if self.uniform(parent.clone()).is_none() {
+                break;
+            }
+
+            range = parent;
+            dimension *= 2;
+        }
+
+        Some((range, dimension))
+    }
+
+    /// Returns the dimension of the leaf holding the given position, no larger than `max_dimension`, or `None` if
+    /// the voxel there has never been written.
+    pub(crate) fn leaf_dimension(&self, position: Vector3<u32>, max_dimension: u32) -> Option<u32> {
+        self.leaf(self.index(position), max_dimension)
+            .map(|(_, dimension)| dimension)
+    }
+
+    /// Writes `data` to the voxel at the given position, leaving the `Brick` standing for the `Node`s an `Octree`
+    /// would hold after [`Node::insert`].
+    ///
+    /// A leaf holding `background` is split into empty octants on the way down to the voxel, as by `Node::split`,
+    /// and a voxel never written is written through the empty octant of the smallest internal `Node` holding it,
+    /// which is filled with a leaf holding `background`, split down to the voxel unless that is the data written.
+    pub(crate) fn insert(&mut self, position: Vector3<u32>, data: T, background: T) {
+        let index = self.index(position);
+
+        match self.leaf(index, self.dimension) {
+            Some((leaf, _)) => {
+                let leaf_data = self.values[leaf.start];
+                if leaf_data == data {
+                    return;
+                }
+                if leaf_data == background {
+                    self.erase(leaf);
+                }
+
+                self.write(index..index + 1, data);
+            }
+            None => {
+                let mut len = OCTREE_CHILDREN;
+                while len < self.values.len() && self.is_empty(index & !(len - 1)..(index & !(len - 1)) + len) {
+                    len *= OCTREE_CHILDREN;
+                }
+
+                match data == background {
+                    true => {
+                        let len = len / OCTREE_CHILDREN;
+                        self.write(index & !(len - 1)..(index & !(len - 1)) + len, data);
+                    }
+                    false => self.write(index..index + 1, data),
+                }
+            }
+        }
+    }
+
+    /// Clears the voxel at the given position to `background`, leaving the `Brick` standing for the `Node`s an
+    /// `Octree` would hold after [`Node::clear`]. Voxels never written are left untouched.
+    pub(crate) fn clear(&mut self, position: Vector3<u32>, background: T) {
+        let index = self.index(position);
+
+        if let Some((leaf, _)) = self.leaf(index, self.dimension) {
+            if self.values[leaf.start] != background {
+                self.write(index..index + 1, background);
+            }
+        }
+    }
+
+    /// Builds the `Node` the given voxels stand for, and every `Node` below it.
+    pub(crate) fn to_node(&self, range: Range<usize>) -> Node<T> {
+        if let Some(data) = self.uniform(range.clone()) {
+            return Node::leaf(*data);
+        }
+
+        let mut octants = octants(range).map(|range| match self.is_empty(range.clone()) {
+            true => None,
+            false => Some(self.to_node(range)),
+        });
+
+        Node::branch([(); OCTREE_CHILDREN].map(|_| octants.next().unwrap()))
+    }
+
+    /// Returns the number of `Node`s the given voxels stand for, as [`Node::node_count`] counts them.
+    pub(crate) fn node_count(&self, range: Range<usize>) -> usize {
+        match self.uniform(range.clone()) {
+            Some(_) => 1,
+            None => {
+                1 + octants(range)
+                    .filter(|range| !self.is_empty(range.clone()))
+                    .map(|range| self.node_count(range))
+                    .sum::<usize>()
+            }
+        }
+    }
+
+    /// Returns the number of bytes allocated on the heap for the `Brick`, including itself.
+    pub(crate) fn heap_bytes(&self) -> usize {
+        mem::size_of::<Self>() + self.values.len() * mem::size_of::<T>()
+    }
+}
+
+/// Returns the ranges of the voxels of each octant of the given cube, in octant order.
+pub(crate) fn octants(range: Range<usize>) -> impl Iterator<Item = Range<usize>> {
+    let len = range.len() / OCTREE_CHILDREN;
+    (0..OCTREE_CHILDREN).map(move |octant| range.start + octant * len..range.start + (octant + 1) * len)
+}
+
+/// Returns the number of voxels below `node` which have been written, counting those held in storage as unwritten.
+pub(crate) fn written_voxels<T>(node: NodeRef<'_, T>) -> u32
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    match node.leaf_data() {
+        Some(_) => node.dimension().pow(3),
+        None => node.children().map(written_voxels).sum(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Brick;
+    use crate::{test_utils::XorShift, Error, NodeRef, Octree};
+
+    use alloc::vec::Vec;
+    use core::num::NonZeroU32;
+
+    /// Lists every `Node` below `node`, with its bounds, in pre-order.
+    fn nodes(root: NodeRef<'_, u8>) -> Vec<([u32; 3], u32, Option<u8>, u8)> {
+        let mut nodes = Vec::new();
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            nodes.push((
+                node.min_position().into(),
+                node.dimension(),
+                node.leaf_data().copied(),
+                node.occupancy(),
+            ));
+            stack.extend(node.children().collect::<Vec<_>>().into_iter().rev());
+        }
+
+        nodes
+    }
+
+    /// Returns the number of bricks of the given dimension below `root`, which are the only internal `Node`s not
+    /// held as `Node`s of their own.
+    fn bricks(root: NodeRef<'_, u8>, dimension: u32) -> usize {
+        nodes_below(root)
+            .into_iter()
+            .filter(|node| node.dimension() == dimension && node.node().is_none() && !node.is_leaf())
+            .count()
+    }
+
+    fn nodes_below(root: NodeRef<'_, u8>) -> Vec<NodeRef<'_, u8>> {
+        let mut nodes = vec![root];
+        let mut next = 0;
+        while let Some(node) = nodes.get(next).copied() {
+            nodes.extend(node.children());
+            next += 1;
+        }
+
+        nodes
+    }
+
+    /// Applies the same random edits to an `Octree` and one holding bricks, checking after each batch that they
+    /// read the same everywhere, hold the same `Node`s, and encode to the same bytes.
+    #[test]
+    fn matches_octree_without_bricks() {
+        let mut rng = XorShift::new(0xb71c);
+
+        for (dimension, brick, threshold) in [(16, 4, 1), (16, 4, 40), (16, 8, 100), (32, 8, 150)] {
+            let background = rng.below(3) as u8;
+            let dimension_nz = NonZeroU32::new(dimension).unwrap();
+            let mut octree = Octree::new_with_background(dimension_nz, background).unwrap();
+            let mut bricked = Octree::new_with_background(dimension_nz, background).unwrap();
+            bricked.enable_bricks(brick, threshold).unwrap();
+
+            for _ in 0..20 {
+                for _ in 0..200 {
+                    // Edits are kept to one corner, so that it fills up and bricks are made.
+                    let position = rng.position(dimension / 2);
+                    match rng.below(10) {
+                        0..=6 => {
+                            let data = rng.below(3) as u8;
+                            octree.insert(position, data).unwrap();
+                            bricked.insert(position, data).unwrap();
+                        }
+                        _ => {
+                            octree.clear_at(position).unwrap();
+                            bricked.clear_at(position).unwrap();
+                        }
+                    }
+                }
+
+                for x in 0..dimension {
+                    for y in 0..dimension {
+                        for z in 0..dimension {
+                            assert_eq!(bricked.get([x, y, z]), octree.get([x, y, z]), "at {:?}", [x, y, z]);
+                        }
+                    }
+                }
+                assert_eq!(nodes(bricked.root()), nodes(octree.root()));
+                assert_eq!(
+                    bricked.iter_leaves().collect::<Vec<_>>(),
+                    octree.iter_leaves().collect::<Vec<_>>()
+                );
+                assert_eq!(bricked.to_bytes(), octree.to_bytes());
+                assert_eq!(bricked.root().node_count(), octree.root().node_count());
+                assert!(bricked.equivalent(&octree));
+            }
+
+            assert!(bricks(bricked.root(), brick) > 0);
+
+            // Coarsening holds bricks as `Node`s again.
+            octree.lod_down();
+            bricked.lod_down();
+            assert_eq!(nodes(bricked.root()), nodes(octree.root()));
+            assert_eq!(bricks(bricked.root(), brick), 0);
+        }
+    }
+
+    #[test]
+    fn dense_regions_become_bricks() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+        octree.enable_bricks(8, 256).unwrap();
+
+        let mut rng = XorShift::new(0xb71d);
+        for x in 0..8 {
+            for y in 0..8 {
+                for z in 0..8 {
+                    octree.insert([x, y, z], 1 + rng.below(3) as u8).unwrap();
+                }
+            }
+        }
+
+        let mut plain = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+        plain.insert([0, 0, 0], 0).unwrap();
+        for leaf in octree.iter_leaves() {
+            plain.insert(leaf.min, leaf.data).unwrap();
+        }
+        assert!(plain.equivalent(&octree));
+        assert_eq!(bricks(octree.root(), 8), 1);
+
+        // A brick takes a bit per voxel besides its data, where `Node`s take the whole of a `Node` each.
+        let bricked = octree.root().heap_bytes();
+        let nodes = plain.root().heap_bytes();
+        assert!(bricked * 2 < nodes, "{} bytes bricked, {} as nodes", bricked, nodes);
+
+        // Filling the brick with a single value turns it back into a leaf.
+        for x in 0..8 {
+            for y in 0..8 {
+                for z in 0..8 {
+                    octree.insert([x, y, z], 7).unwrap();
+                }
+            }
+        }
+        let leaf = octree.root().leaf_at([0, 0, 0].into()).unwrap();
+        assert_eq!((leaf.dimension(), leaf.leaf_data()), (8, Some(&7)));
+        assert_eq!(bricks(octree.root(), 8), 0);
+    }
+
+    #[test]
+    fn bricks_are_rebuilt_as_nodes() {
+        let mut octree = XorShift::new(0xb71e).octree(8, 400, 3);
+        let brick = Brick::from_node(octree.root());
+
+        let node = brick.to_node(brick.all());
+        assert_eq!(nodes(NodeRef::new(&node, octree.bounds())), nodes(octree.root()));
+
+        assert!(matches!(octree.enable_bricks(2, 1), Err(Error::InvalidDimension(2))));
+        assert!(matches!(octree.enable_bricks(8, 1), Err(Error::InvalidDimension(8))));
+        assert!(octree.enable_bricks(4, 1).is_ok());
+    }
+}
@@ -0,0 +1,304 @@
+use crate::{
+    node::{contains, Bounds},
+    Node, NodeRef, Vector3,
+};
+
+use alloc::boxed::Box;
+use core::{
+    fmt::{self, Debug},
+    hash::Hash,
+    ops::Deref,
+    ptr,
+    sync::atomic::{fence, AtomicPtr, AtomicU32, AtomicUsize, Ordering},
+};
+
+/// The root `Node` of an `Octree`, along with the data of the leaf last found by [`CachedRoot::get`] and its bounds,
+/// so that reads landing in the same leaf are answered without walking down from the root. Reads finding an empty
+/// octant cache its bounds along with a null pointer instead, so that reads of the empty space around a surface
+/// are answered as quickly. Octants yet to be loaded are never cached.
+///
+/// The cache holds a pointer into the tree rather than a borrow of it, which is only sound while that tree is left
+/// unmodified. The tree is only reachable for modification through [`CachedRoot::get_mut`], which clears the cache
+/// first, so the pointer is always either null or the data of a leaf of the tree as it stands whenever it can be read.
+/// A cleared cache has a dimension of zero, so that it contains no position. Leaves held inline by their parents are
+/// not `Node`s of their own, so the data is cached rather than the leaf.
+///
+/// The root is boxed so that it stays in place as the `CachedRoot` is moved, but moving the box still asserts
+/// unique access to the root, invalidating any pointer into it. Data held by the root itself is therefore never
+/// cached: neither that of a root leaf, which answers every read immediately anyway, nor that of the leaves a packed
+/// root holds inline.
+///
+/// `Node`s do not keep their bounds, so those of the cached leaf are cached alongside its data. Reads from several
+/// threads may replace the cached leaf at once, so the pointer and bounds are guarded by `sequence`, as in a
+/// seqlock: it is odd while they are being replaced, and changes whenever they are, so a read seeing the same even
+/// value before and after reading them has read a leaf and its own bounds.
+pub(crate) struct CachedRoot<T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    root: Box<Node<T>>,
+    sequence: AtomicUsize,
+    leaf: AtomicPtr<T>,
+    min: [AtomicU32; 3],
+    dimension: AtomicU32,
+}
+
+impl<T> CachedRoot<T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    pub(crate) fn new(root: Node<T>) -> Self {
+        Self {
+            root: Box::new(root),
+            sequence: AtomicUsize::new(0),
+            leaf: AtomicPtr::new(ptr::null_mut()),
+            min: Default::default(),
+            dimension: AtomicU32::new(0),
+        }
+    }
+
+    /// Gets data at the given position as [`NodeRef::get`] does, given the bounds of the root, first checking the
+    /// leaf found by the last call. Empty octants read as `background`.
+    ///
+    /// Reads may come from several threads at once, each replacing the leaf cached by the others, so the cache
+    /// is only ever a hint: a leaf found by another thread is as valid as one found by this one, and a read
+    /// finding the cache being replaced walks down from the root instead.
+    pub(crate) fn get<'a>(&'a self, bounds: Bounds, position: Vector3<u32>, background: &'a T) -> Option<&'a T> {
+        let sequence = self.sequence.load(Ordering::Acquire);
+        let cached = self.leaf.load(Ordering::Relaxed);
+        let min = Vector3::from(self.min.each_ref().map(|c| c.load(Ordering::Relaxed)));
+        let dimension = self.dimension.load(Ordering::Relaxed);
+        fence(Ordering::Acquire);
+
+        if sequence & 1 == 0
+            && self.sequence.load(Ordering::Relaxed) == sequence
+            && contains([min, min + Vector3::from([dimension; 3])], position)
+        {
+            // SAFETY: the pointer and bounds were read together, as nothing replaced them meanwhile. The cache is
+            // cleared before any modification of the tree, and only ever holds data found since outside the root's
+            // own allocation, so any leaf held is still in place, and stays so while `self` is borrowed.
+            return Some(unsafe { cached.as_ref() }.unwrap_or(background));
+        }
+
+        match NodeRef::new(&self.root, bounds).region_at(position)? {
+            Ok(leaf) => {
+                let data = leaf.leaf_data()?;
+                if !self.root.is_leaf() && !self.root.is_packed() {
+                    self.store(data, leaf.min_position(), leaf.dimension());
+                }
+
+                Some(data)
+            }
+            Err((_, _, true)) => None,
+            Err((min, dimension, false)) => {
+                self.store(ptr::null(), min, dimension);
+                Some(background)
+            }
+        }
+    }
+
+    /// Caches the given leaf data, or null for an octant holding no leaf, along with its bounds, unless another
+    /// read is caching one at the same time.
+    fn store(&self, data: *const T, min: Vector3<u32>, dimension: u32) {
+        let sequence = self.sequence.load(Ordering::Relaxed);
+        if sequence % 2 == 1
+            || self
+                .sequence
+                .compare_exchange(sequence, sequence.wrapping_add(1), Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+        {
+            return;
+        }
+
+        fence(Ordering::Release);
+        self.leaf.store(data as *mut T, Ordering::Relaxed);
+        for (cached, c) in self.min.iter().zip(<[u32; 3]>::from(min)) {
+            cached.store(c, Ordering::Relaxed);
+        }
+        self.dimension.store(dimension, Ordering::Relaxed);
+
+        self.sequence.store(sequence.wrapping_add(2), Ordering::Release);
+    }
+
+    /// Returns the root for modification, clearing the cache as any leaf held may be freed, and any empty octant
+    /// held written.
+    pub(crate) fn get_mut(&mut self) -> &mut Node<T> {
+        *self.leaf.get_mut() = ptr::null_mut();
+        *self.dimension.get_mut() = 0;
+        &mut self.root
+    }
+}
+
+impl<T> Deref for CachedRoot<T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    type Target = Node<T>;
+
+    fn deref(&self) -> &Node<T> {
+        &self.root
+    }
+}
+
+impl<T> Debug for CachedRoot<T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    // The cache says nothing about the contents of the tree, so only the root is printed.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.root.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_utils::XorShift, Octree, Vector3};
+
+    use alloc::{boxed::Box, vec::Vec};
+    use core::num::NonZeroU32;
+    use std::time::Instant;
+
+    #[test]
+    fn interleaved_edits_and_reads_match_uncached_reads() {
+        let mut rng = XorShift::new(0xcac4);
+        let (trees, steps) = if cfg!(miri) { (2, 200) } else { (20, 2000) };
+
+        for _ in 0..trees {
+            let mut octree = rng.octree(16, 100, 3);
+            let mut expected = (0..16 * 16 * 16)
+                .map(|i| *octree.get([i % 16, i / 16 % 16, i / 256]).unwrap())
+                .collect::<Vec<_>>();
+
+            for _ in 0..steps {
+                let position = rng.position(16);
+                let index = (position[0] + 16 * (position[1] + 16 * position[2])) as usize;
+
+                match rng.below(40) {
+                    0 => {
+                        octree.simplify();
+                        octree.prune();
+                    }
+                    1 => {
+                        octree.clear();
+                        expected.iter_mut().for_each(|data| *data = 0);
+                    }
+                    2..=15 => {
+                        let data = rng.below(3) as u8;
+                        octree.insert(position, data).unwrap();
+                        expected[index] = data;
+                    }
+                    16..=19 => {
+                        octree.clear_at(position).unwrap();
+                        expected[index] = 0;
+                    }
+                    _ => {}
+                }
+
+                // Reads around the position, so that the cached leaf is both hit and missed.
+                for _ in 0..4 {
+                    let position = position.map(|c| (c + rng.below(3)).min(15));
+                    let index = (position[0] + 16 * (position[1] + 16 * position[2])) as usize;
+                    let data = octree.get(position);
+
+                    assert_eq!(data, octree.root().get(Vector3::from(position)).or(Some(&0)));
+                    assert_eq!(data, Some(&expected[index]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reads_survive_moves_and_coarsening() {
+        let mut octree = XorShift::new(0xcac5).octree(16, 200, 3);
+        let positions = || (0..16 * 16 * 16).map(|i| [i % 16, i / 16 % 16, i / 256]);
+        let reads = |octree: &Octree<u8>| positions().map(|p| octree.get(p).copied()).collect::<Vec<_>>();
+        let uncached_reads = |octree: &Octree<u8>| {
+            positions()
+                .map(|p| octree.root().get(Vector3::from(p)).or(Some(&0)).copied())
+                .collect::<Vec<_>>()
+        };
+
+        // Moving the `Octree` leaves its `Node`s in place, so the leaves cached before stay valid.
+        let expected = reads(&octree);
+        let moved = Box::new(octree);
+        assert_eq!(reads(&moved), expected);
+
+        octree = *moved;
+        octree.lod_down();
+        let coarse = reads(&octree);
+        assert_eq!(coarse, uncached_reads(&octree));
+
+        // Reads from several threads at once replace each other's cached leaves.
+        std::thread::scope(|scope| {
+            for _ in 0..2 {
+                scope.spawn(|| assert_eq!(reads(&octree), coarse));
+            }
+        });
+    }
+
+    #[test]
+    fn reads_survive_moving_a_packed_root() {
+        // The children of a root 2 voxels across are all voxels, so the root holds them inline.
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(2).unwrap()).unwrap();
+        octree.insert([0, 0, 0], 1).unwrap();
+        octree.insert([1, 1, 1], 2).unwrap();
+        assert_eq!(octree.get([0, 0, 0]), Some(&1));
+
+        let moved = Box::new(octree);
+        assert_eq!(moved.get([0, 0, 0]), Some(&1));
+        assert_eq!(moved.get([1, 1, 1]), Some(&2));
+
+        let octree = *moved;
+        assert_eq!(octree.get([1, 1, 1]), Some(&2));
+    }
+
+    /// Times sweeping every voxel along each axis in turn with and without the cache.
+    ///
+    /// Timings are only meaningful in release builds, so this is ignored by default; run it with
+    /// `cargo test --release -- --ignored sweeps_are_faster`.
+    #[test]
+    #[ignore]
+    fn sweeps_are_faster_with_the_cache() {
+        let dimension = 128;
+
+        // Blocky terrain, so that sweeps stay within a leaf for several voxels at a time.
+        let octree = Octree::from_fn(NonZeroU32::new(dimension).unwrap(), |[x, y, z]| {
+            let height = 48 + (x / 8 * 7 + z / 8 * 13) % 32;
+            match y {
+                y if y < height / 2 => 2,
+                y if y < height => 1,
+                _ => 0,
+            }
+        })
+        .unwrap();
+        let root = octree.root();
+
+        let time = |get: &dyn Fn([u32; 3]) -> Option<u8>| {
+            let start = Instant::now();
+            let mut sum = 0_u64;
+            for axes in [[0, 1, 2], [1, 2, 0], [2, 0, 1]] {
+                for i in 0..dimension.pow(3) {
+                    // The first axis of `axes` varies fastest.
+                    let mut position = [0; 3];
+                    position[axes[0]] = i % dimension;
+                    position[axes[1]] = i / dimension % dimension;
+                    position[axes[2]] = i / dimension / dimension;
+                    sum += get(position).unwrap_or(0) as u64;
+                }
+            }
+
+            (start.elapsed(), sum)
+        };
+
+        let (uncached, uncached_sum) = time(&|position| root.get(Vector3::from(position)).copied());
+        let (cached, cached_sum) = time(&|position| octree.get(position).copied());
+
+        assert_eq!(uncached_sum, cached_sum);
+        assert!(
+            cached < uncached,
+            "sweeping every voxel along each axis: {:?} uncached, {:?} cached",
+            uncached,
+            cached
+        );
+    }
+}
@@ -0,0 +1,548 @@
+use crate::{
+    fill::fill,
+    flat::{unflatten, Flatten, Token},
+    hash::Crc32,
+    paged::Reader,
+    query::classify_box,
+    Error, Node, NodeRef, Octree, Vector3,
+};
+
+use alloc::{vec, vec::Vec};
+use core::{convert::TryInto, fmt::Debug, hash::Hash, mem};
+
+/// Starts encodings of [`Octree::to_bytes`], followed by the version.
+const MAGIC: &[u8; 4] = b"svoB";
+/// The version of the encoding of [`Octree::to_bytes`], bumped whenever its layout changes.
+const VERSION: u16 = 1;
+/// The size of the checksum ending each encoding.
+const CRC: usize = 4;
+
+const LEAF: u8 = 0;
+const BRANCH: u8 = 1;
+
+/// Data which can be encoded as bytes, as used by the binary formats of `Octree`.
+///
+/// Encodings may vary in length between values, so long as each is read back from the start of the bytes
+/// following it without being told its length.
+pub trait ValueCodec: Sized {
+    /// Appends the encoding of the value to `bytes`.
+    fn write(&self, bytes: &mut Vec<u8>);
+
+    /// Decodes a value from the start of `bytes`, returning it along with the number of bytes it took, or
+    /// `None` if `bytes` does not start with a valid encoding.
+    fn read(bytes: &[u8]) -> Option<(Self, usize)>;
+}
+
+macro_rules! impl_value_codec {
+    ($($ty:ty),*) => {
+        $(
+            impl ValueCodec for $ty {
+                fn write(&self, bytes: &mut Vec<u8>) {
+                    bytes.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn read(bytes: &[u8]) -> Option<(Self, usize)> {
+                    let bytes = bytes.get(..mem::size_of::<$ty>())?;
+                    Some((<$ty>::from_le_bytes(bytes.try_into().unwrap()), bytes.len()))
+                }
+            }
+        )*
+    };
+}
+
+impl_value_codec!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl ValueCodec for bool {
+    fn write(&self, bytes: &mut Vec<u8>) {
+        bytes.push(*self as u8);
+    }
+
+    fn read(bytes: &[u8]) -> Option<(Self, usize)> {
+        match bytes.first()? {
+            0 => Some((false, 1)),
+            1 => Some((true, 1)),
+            _ => None,
+        }
+    }
+}
+
+/// The header of an encoding of [`Octree::to_bytes`], followed by the reader of its `Node`s.
+struct Header<'a, T> {
+    dimension: u32,
+    lod_level: u32,
+    background: T,
+    count: u32,
+    reader: Reader<'a>,
+}
+
+/// Returns whether no space below `node` is unwritten.
+fn is_complete<T>(node: NodeRef<'_, T>) -> bool
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    let mut stack = vec![node];
+
+    while let Some(node) = stack.pop() {
+        if node.leaf_data().is_none() {
+            if (0..8).any(|i| node.child(i).is_none()) {
+                return false;
+            }
+
+            stack.extend(node.children());
+        }
+    }
+
+    true
+}
+
+/// Returns the number of bytes `value` is encoded in.
+pub(crate) fn encoded_len<T: ValueCodec>(value: &T) -> usize {
+    let mut bytes = Vec::new();
+    value.write(&mut bytes);
+    bytes.len()
+}
+
+/// Reads a value with its codec.
+pub(crate) fn read_value<T: ValueCodec>(reader: &mut Reader<'_>) -> Result<T, Error> {
+    let (value, len) = T::read(reader.remaining()).ok_or(Error::InvalidEncoding)?;
+    reader.take(len)?;
+    Ok(value)
+}
+
+/// Encodes the tokens of an `Octree` in the layout of [`Octree::to_bytes`], under a header of the given
+/// dimension, LOD level and background.
+pub(crate) fn write_tokens<T>(
+    dimension: u32,
+    lod_level: u32,
+    background: T,
+    tokens: impl Iterator<Item = Token<T>>,
+) -> Vec<u8>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash + ValueCodec,
+{
+    let mut nodes = Vec::new();
+    let mut count = 0_u32;
+
+    for token in tokens {
+        match token {
+            Token::Leaf(data) => {
+                nodes.push(LEAF);
+                data.write(&mut nodes);
+            }
+            Token::Branch(mask) => {
+                nodes.push(BRANCH);
+                nodes.push(mask);
+            }
+        }
+
+        count += 1;
+    }
+
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&dimension.to_le_bytes());
+    bytes.extend_from_slice(&lod_level.to_le_bytes());
+    bytes.extend_from_slice(&count.to_le_bytes());
+    background.write(&mut bytes);
+    bytes.extend_from_slice(&nodes);
+
+    let mut crc = Crc32::new();
+    crc.update(&bytes);
+    bytes.extend_from_slice(&crc.finish().to_le_bytes());
+    bytes
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + ValueCodec,
+{
+    /// Encodes the `Octree` as bytes, without relying on any serialization library.
+    ///
+    /// The bytes hold a header of the format version, the dimension, the LOD level, the number of `Node`s and
+    /// the background, followed by each `Node` in pre-order: a leaf as its tag and data, and a branch as its
+    /// tag and the bit mask of its children, which follow it in octant order. Data is encoded by its
+    /// [`ValueCodec`] and everything else in little-endian order. The bytes end with a CRC-32 checksum of
+    /// everything before it. Journaled LOD detail is not encoded.
+    ///
+    /// Subtrees held in storage are not loaded, and are encoded as if never written, so load them through
+    /// [`Octree::with_source`] first.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u16>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([1, 2, 3], 4).unwrap();
+    ///
+    /// let bytes = octree.to_bytes();
+    /// assert!(Octree::<u16>::from_bytes(&bytes).unwrap().equivalent(&octree));
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        enter_span!(DEBUG, "to_bytes", [bytes]);
+        let bytes = write_tokens(
+            self.dimension(),
+            self.lod_level(),
+            self.background(),
+            Flatten::new(self.root()),
+        );
+
+        record!(bytes = bytes.len());
+        bytes
+    }
+
+    /// Decodes an `Octree` encoded by [`Octree::to_bytes`].
+    ///
+    /// Returns [`Error::ChecksumMismatch`] if the bytes do not match their checksum,
+    /// [`Error::UnsupportedVersion`] if they were written by another version of the format, and
+    /// [`Error::InvalidEncoding`] if they do not describe an `Octree`, or hold more or fewer `Node`s than
+    /// their header records.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([1, 2, 3], 4).unwrap();
+    ///
+    /// let mut bytes = octree.to_bytes();
+    /// bytes.pop();
+    /// assert!(matches!(Octree::<u8>::from_bytes(&bytes), Err(Error::ChecksumMismatch { .. })));
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        enter_span!(DEBUG, "from_bytes", [nodes], bytes = bytes.len());
+        let header = Self::read_header(bytes)?;
+        let (background, lod_level) = (header.background, header.lod_level);
+        let dimension = header.dimension;
+        record!(nodes = header.count);
+        let root = Self::read_nodes(header)?;
+
+        Octree::from_root(dimension, root, background, lod_level)
+    }
+
+    /// Checks the checksum, magic and version of an encoding of [`Octree::to_bytes`], and reads its header.
+    fn read_header(bytes: &[u8]) -> Result<Header<'_, T>, Error> {
+        let len = bytes.len().checked_sub(CRC).ok_or(Error::InvalidEncoding)?;
+        let (body, stored) = bytes.split_at(len);
+
+        let mut crc = Crc32::new();
+        crc.update(body);
+        let actual = crc.finish();
+        let expected = u32::from_le_bytes(stored.try_into().unwrap());
+
+        if expected != actual {
+            return Err(Error::ChecksumMismatch { expected, actual });
+        }
+
+        let mut reader = Reader::new(body);
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(Error::InvalidEncoding);
+        }
+
+        let version = reader.u16()?;
+        if version != VERSION {
+            return Err(Error::UnsupportedVersion(version as u32));
+        }
+
+        let dimension = reader.u32()?;
+        let lod_level = reader.u32()?;
+        let count = reader.u32()?;
+        let background = read_value::<T>(&mut reader)?;
+
+        if !dimension.is_power_of_two() {
+            return Err(Error::InvalidDimension(dimension));
+        }
+
+        Ok(Header {
+            dimension,
+            lod_level,
+            background,
+            count,
+            reader,
+        })
+    }
+
+    /// Reads the `Node`s following a header.
+    fn read_nodes(header: Header<'_, T>) -> Result<Node<T>, Error> {
+        let Header {
+            dimension,
+            count,
+            mut reader,
+            ..
+        } = header;
+
+        let mut remaining = count;
+        let tokens = core::iter::from_fn(|| {
+            remaining = remaining.checked_sub(1)?;
+
+            Some(match reader.u8() {
+                Ok(LEAF) => read_value(&mut reader).map(Token::Leaf),
+                Ok(BRANCH) => reader.u8().map(Token::Branch),
+                Ok(_) => Err(Error::InvalidEncoding),
+                Err(error) => Err(error),
+            })
+        });
+
+        let root = unflatten(dimension, tokens, |_| Error::InvalidEncoding)?;
+
+        if remaining != 0 || !reader.is_empty() {
+            return Err(Error::InvalidEncoding);
+        }
+
+        Ok(root)
+    }
+
+    /// Writes the voxels of an `Octree` encoded by [`Octree::to_bytes`] into this one, with its minimum corner
+    /// at `offset`.
+    ///
+    /// Space never written in the encoded `Octree` is left as it is, and its LOD level and background are
+    /// ignored. Subtrees with no unwritten space which line up with the `Node`s of this `Octree` are moved in
+    /// whole, so merging `Octree`s placed at multiples of their dimension takes time in proportion to their
+    /// `Node`s rather than their voxels. Everything else is written leaf by leaf, clipped as for
+    /// [`Octree::clear_region`]. Subtrees are only moved in whole while this `Octree` is at LOD level 1.
+    ///
+    /// Returns [`Error::OutOfBounds`] if the encoded `Octree` placed at `offset` extends outside this one, and
+    /// reports corruption as [`Octree::from_bytes`] does, leaving this `Octree` unchanged.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut chunk = Octree::<u16>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// chunk.insert([1, 2, 3], 4).unwrap();
+    /// let bytes = chunk.to_bytes();
+    ///
+    /// let mut world = Octree::<u16>::new(NonZeroU32::new(512).unwrap()).unwrap();
+    /// world.merge_encoded(&bytes, [64, 0, 32]).unwrap();
+    /// assert!(matches!(world.get([65, 2, 35]), Some(4)));
+    ///
+    /// assert_eq!(world.merge_encoded(&bytes, [500, 0, 0]), Err(Error::OutOfBounds));
+    /// ```
+    pub fn merge_encoded(&mut self, bytes: &[u8], offset: impl Into<Vector3<u32>>) -> Result<(), Error> {
+        let offset = <[u32; 3]>::from(offset.into());
+        let header = Self::read_header(bytes)?;
+
+        let fits = offset
+            .iter()
+            .all(|c| matches!(c.checked_add(header.dimension), Some(max) if max <= self.dimension()));
+        if !fits {
+            return Err(Error::OutOfBounds);
+        }
+
+        let bounds = [
+            Vector3::from(offset),
+            Vector3::from(offset.map(|c| c + header.dimension)),
+        ];
+        let root = Self::read_nodes(header)?;
+
+        let (octree_bounds, background) = (self.bounds(), self.background());
+        let graft = self.lod_level() == 1;
+        let mut stack = vec![(bounds, root)];
+
+        while let Some((bounds, node)) = stack.pop() {
+            let min = <[u32; 3]>::from(bounds[0]);
+            let dimension = bounds[1].x - bounds[0].x;
+
+            if graft && min.iter().all(|c| c % dimension == 0) && is_complete(NodeRef::new(&node, bounds)) {
+                self.root_mut().graft(octree_bounds, node, bounds, background)?;
+            } else if let Some(data) = node.leaf_data().copied() {
+                let max = min.map(|c| c + dimension);
+                fill(self, Some(data), |cube, dimension| {
+                    classify_box(min, max, cube, dimension)
+                });
+            } else {
+                stack.extend(node.into_octants(bounds));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ValueCodec, VERSION};
+    use crate::{
+        test_utils::{Voxel, XorShift},
+        Error, Octree,
+    };
+
+    use alloc::vec::Vec;
+    use core::{fmt::Debug, hash::Hash, num::NonZeroU32};
+
+    /// Builds an `Octree` from random writes of the values `value` maps small numbers to.
+    fn random<T>(rng: &mut XorShift, background: T, value: impl Fn(u32) -> T) -> Octree<T>
+    where
+        T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+    {
+        let mut octree = Octree::new_with_background(NonZeroU32::new(16).unwrap(), background).unwrap();
+        for _ in 0..300 {
+            octree.insert(rng.position(16), value(rng.below(4))).unwrap();
+        }
+
+        octree
+    }
+
+    fn round_trip<T>(octree: &Octree<T>)
+    where
+        T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + ValueCodec,
+    {
+        let copy = Octree::<T>::from_bytes(&octree.to_bytes()).unwrap();
+        assert_eq!(alloc::format!("{:#?}", copy), alloc::format!("{:#?}", octree));
+    }
+
+    #[test]
+    fn primitives_round_trip() {
+        let mut rng = XorShift::new(0xb17e);
+
+        round_trip(&random(&mut rng, 0_u8, |n| n as u8));
+        round_trip(&random(&mut rng, 0_u16, |n| n as u16 * 1000));
+        round_trip(&random(&mut rng, 0_u32, |n| n * 100_000));
+        round_trip(&random(&mut rng, 0_u64, |n| n as u64 * (1 << 40)));
+        round_trip(&random(&mut rng, 0_u128, |n| n as u128 * (1 << 100)));
+        round_trip(&random(&mut rng, -1_i8, |n| -(n as i8)));
+        round_trip(&random(&mut rng, 0_i16, |n| n as i16 * -1000));
+        round_trip(&random(&mut rng, 0_i32, |n| n as i32 * -100_000));
+        round_trip(&random(&mut rng, 0_i64, |n| n as i64 * -(1 << 40)));
+        round_trip(&random(&mut rng, 0_i128, |n| n as i128 * -(1 << 100)));
+        round_trip(&random(&mut rng, true, |n| n % 2 == 0));
+    }
+
+    #[test]
+    fn custom_codecs_round_trip() {
+        let mut rng = XorShift::new(0xb17f);
+        let octree = random(&mut rng, Voxel::Air, |n| match n {
+            0 => Voxel::Air,
+            n => Voxel::Block(n as u16 * 300),
+        });
+
+        round_trip(&octree);
+
+        round_trip(&octree.at_lod(2));
+    }
+
+    #[test]
+    fn corrupt_bytes_are_rejected() {
+        let mut rng = XorShift::new(0xb180);
+        let octree = random(&mut rng, 0_u8, |n| n as u8);
+        let bytes = octree.to_bytes();
+
+        for len in 0..bytes.len() {
+            assert!(Octree::<u8>::from_bytes(&bytes[..len]).is_err());
+        }
+
+        let mut corrupt = bytes.clone();
+        corrupt[20] ^= 0x40;
+        assert!(matches!(
+            Octree::<u8>::from_bytes(&corrupt),
+            Err(Error::ChecksumMismatch { .. })
+        ));
+
+        // Corruption which keeps the checksum valid still fails cleanly.
+        let seal = |bytes: &mut Vec<u8>| {
+            let len = bytes.len() - 4;
+            let mut crc = super::Crc32::new();
+            crc.update(&bytes[..len]);
+            bytes[len..].copy_from_slice(&crc.finish().to_le_bytes());
+        };
+
+        // Flipped values still decode, but flipping any byte of the magic, version, dimension or node count, or
+        // the tag of any node, leaves the encoding invalid. Nodes follow the header at byte 19, two bytes each.
+        let tags = (19..bytes.len() - 4).step_by(2);
+        for i in (0..10).chain(14..18).chain(tags) {
+            for flip in [0x02, 0x80, 0xff] {
+                let mut corrupt = bytes.clone();
+                corrupt[i] ^= flip;
+                seal(&mut corrupt);
+                assert!(Octree::<u8>::from_bytes(&corrupt).is_err(), "at {}", i);
+            }
+        }
+
+        // The node count must match the nodes held.
+        let mut miscounted = bytes.clone();
+        miscounted[14] = miscounted[14].wrapping_add(1);
+        seal(&mut miscounted);
+        assert_eq!(
+            Octree::<u8>::from_bytes(&miscounted).err(),
+            Some(Error::InvalidEncoding)
+        );
+
+        let mut newer = bytes;
+        newer[4..6].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        seal(&mut newer);
+        assert_eq!(
+            Octree::<u8>::from_bytes(&newer).err(),
+            Some(Error::UnsupportedVersion(VERSION as u32 + 1))
+        );
+    }
+
+    /// Writes every written voxel of `chunk` into `world` one at a time, placed at `offset`.
+    fn merge_voxels(world: &mut Octree<u8>, chunk: &Octree<u8>, offset: [u32; 3]) {
+        let dimension = chunk.dimension();
+
+        for i in 0..dimension.pow(3) {
+            let position = [i % dimension, i / dimension % dimension, i / dimension / dimension];
+            if let Some(data) = chunk.root().get(position.into()) {
+                let position = [0, 1, 2].map(|axis| offset[axis] + position[axis]);
+                world.insert(position, *data).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn merging_matches_copying_voxels() {
+        let mut rng = XorShift::new(0xc0de);
+
+        // Aligned to the dimension of the chunk, aligned to smaller `Node`s only, and misaligned.
+        for offset in [[32, 64, 96], [0, 0, 0], [8, 16, 72], [3, 17, 40], [95, 1, 64]] {
+            let chunk = rng.octree(32, 800, 5);
+            let mut world = rng.octree(128, 3000, 5);
+
+            let mut expected = world.at_lod(0);
+            merge_voxels(&mut expected, &chunk, offset);
+
+            world.merge_encoded(&chunk.to_bytes(), offset).unwrap();
+            assert!(world.equivalent(&expected), "at {:?}", offset);
+        }
+
+        // A chunk with no unwritten space replaces everything below it.
+        let mut full = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        full.insert_sphere([8.0, 8.0, 8.0], 30.0, 1, true).unwrap();
+        full.insert([3, 4, 5], 2).unwrap();
+
+        let mut world = rng.octree(64, 2000, 5);
+        let mut expected = world.at_lod(0);
+        merge_voxels(&mut expected, &full, [16, 48, 0]);
+
+        world.merge_encoded(&full.to_bytes(), [16, 48, 0]).unwrap();
+        assert!(world.equivalent(&expected));
+        assert!(matches!(world.get([19, 52, 5]), Some(2)));
+    }
+
+    #[test]
+    fn merging_checks_bounds_first() {
+        let mut rng = XorShift::new(0xc0df);
+        let bytes = rng.octree(32, 300, 5).to_bytes();
+        let mut world = rng.octree(128, 1000, 5);
+        let before = world.to_bytes();
+
+        for offset in [[97, 0, 0], [0, 120, 0], [0, 0, u32::MAX]] {
+            assert_eq!(world.merge_encoded(&bytes, offset), Err(Error::OutOfBounds));
+        }
+
+        let mut corrupt = bytes.clone();
+        corrupt[20] ^= 1;
+        assert!(matches!(
+            world.merge_encoded(&corrupt, [0; 3]),
+            Err(Error::ChecksumMismatch { .. })
+        ));
+        assert_eq!(world.to_bytes(), before);
+
+        // The chunk must fit at its offset, not just within the `Octree`.
+        let mut small = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        assert_eq!(small.merge_encoded(&bytes, [0; 3]), Err(Error::OutOfBounds));
+    }
+}
@@ -0,0 +1,962 @@
+use crate::{Face, LeafInfo, NodeRef, Octree, Vector3};
+
+use alloc::{vec, vec::Vec};
+use core::{cmp::Ordering, fmt::Debug, hash::Hash};
+
+/// Returns whether the open box from `min` to `max` overlaps the given cube.
+fn box_overlaps(min: [f32; 3], max: [f32; 3], cube_min: [u32; 3], dimension: u32) -> bool {
+    (0..3).all(|i| {
+        let lower = cube_min[i] as f32;
+        max[i] > lower && min[i] < lower + dimension as f32
+    })
+}
+
+/// Visits every solid leaf of `node` overlapping the box, stopping as soon as `found` returns `true`.
+///
+/// Unwritten space is tested as `background`, and visited as a leaf covering the missing octant.
+fn visit_aabb<T, S, F>(
+    node: NodeRef<'_, T>,
+    min: [f32; 3],
+    max: [f32; 3],
+    background: T,
+    solid: &S,
+    found: &mut F,
+) -> bool
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+    S: Fn(&T) -> bool,
+    F: FnMut(LeafInfo<T>) -> bool,
+{
+    if let Some(leaf) = LeafInfo::from_node(node) {
+        return solid(&leaf.data) && found(leaf);
+    }
+
+    let dimension = node.dimension() / 2;
+    let gap_solid = solid(&background);
+
+    for (child_min, child) in node.octants() {
+        let child_min = child_min.into();
+        if !box_overlaps(min, max, child_min, dimension) {
+            continue;
+        }
+
+        let stop = match child {
+            Some(child) => visit_aabb(child, min, max, background, solid, found),
+            None => {
+                gap_solid
+                    && found(LeafInfo {
+                        min: child_min,
+                        dimension,
+                        data: background,
+                    })
+            }
+        };
+
+        if stop {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// A cube of an `Octree` visited while testing two trees against each other, along with the background of
+/// its `Octree`.
+#[derive(Clone, Copy)]
+enum Part<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    Node(NodeRef<'a, T>, T),
+    Gap(Vector3<u32>, u32, T),
+}
+
+impl<'a, T> Part<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    fn bounds(&self, offset: [i64; 3]) -> ([i64; 3], [i64; 3]) {
+        let (min, dimension): ([u32; 3], _) = match self {
+            Self::Node(node, _) => (node.min_position().into(), node.dimension()),
+            Self::Gap(min, dimension, _) => ((*min).into(), *dimension),
+        };
+
+        let min = [0, 1, 2].map(|i| min[i] as i64 + offset[i]);
+        (min, min.map(|c| c + dimension as i64))
+    }
+
+    fn leaf_data(&self) -> Option<T> {
+        match self {
+            Self::Node(node, _) => node.leaf_data().copied(),
+            Self::Gap(_, _, background) => Some(*background),
+        }
+    }
+
+    /// Returns whether the part is a leaf holding the background of its `Octree`.
+    fn is_empty(&self) -> bool {
+        match self {
+            Self::Node(node, background) => node.leaf_data() == Some(background),
+            Self::Gap(..) => true,
+        }
+    }
+
+    fn leaf_info(&self) -> Option<LeafInfo<T>> {
+        match self {
+            Self::Node(node, _) => LeafInfo::from_node(*node),
+            Self::Gap(min, dimension, background) => Some(LeafInfo {
+                min: (*min).into(),
+                dimension: *dimension,
+                data: *background,
+            }),
+        }
+    }
+
+    fn dimension(&self) -> u32 {
+        match self {
+            Self::Node(node, _) => node.dimension(),
+            Self::Gap(_, dimension, _) => *dimension,
+        }
+    }
+
+    fn children(&self) -> impl Iterator<Item = Part<'a, T>> + 'a {
+        let node = match self {
+            Self::Node(node, background) => Some((*node, *background)),
+            Self::Gap(..) => None,
+        };
+
+        node.into_iter().flat_map(|(node, background)| {
+            let dimension = node.dimension() / 2;
+            node.octants().map(move |(min, child)| match child {
+                Some(child) => Part::Node(child, background),
+                None => Part::Gap(min, dimension, background),
+            })
+        })
+    }
+}
+
+/// Returns the position of a voxel where solid parts of `a` and `b` overlap, with `b` moved by `offset`.
+///
+/// Pairs of cubes which do not overlap are pruned, and pairs of solid leaves are resolved without
+/// descending any further, however large they are.
+fn overlap<T, S>(a: Part<T>, b: Part<T>, offset: [i64; 3], solid: &S) -> Option<[i64; 3]>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+    S: Fn(&T) -> bool,
+{
+    let (a_min, a_max) = a.bounds([0; 3]);
+    let (b_min, b_max) = b.bounds(offset);
+
+    let lower = [0, 1, 2].map(|i| a_min[i].max(b_min[i]));
+    if (0..3).any(|i| lower[i] >= a_max[i].min(b_max[i])) {
+        return None;
+    }
+
+    match (a.leaf_data(), b.leaf_data()) {
+        (Some(a), Some(b)) => Some(lower).filter(|_| solid(&a) && solid(&b)),
+        (Some(a), None) | (None, Some(a)) if !solid(&a) => None,
+        (Some(_), None) => b.children().find_map(|b| overlap(a, b, offset, solid)),
+        (None, Some(_)) => a.children().find_map(|a| overlap(a, b, offset, solid)),
+        (None, None) if a.dimension() >= b.dimension() => a.children().find_map(|a| overlap(a, b, offset, solid)),
+        (None, None) => b.children().find_map(|b| overlap(a, b, offset, solid)),
+    }
+}
+
+/// An iterator over the overlapping pairs of non-empty leaves of two `Octree`s.
+///
+/// Yields `(leaf, other_leaf, min, dimensions)`, where `min` and `dimensions` describe the overlap of the
+/// two leaves in the coordinates of the first `Octree`. Created by [`Octree::overlapping_leaves`].
+pub struct OverlappingLeaves<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    offset: [i64; 3],
+    stack: Vec<(Part<'a, T>, Part<'a, T>)>,
+}
+
+impl<'a, T> Iterator for OverlappingLeaves<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    type Item = (LeafInfo<T>, LeafInfo<T>, [u32; 3], [u32; 3]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((a, b)) = self.stack.pop() {
+            let (a_min, a_max) = a.bounds([0; 3]);
+            let (b_min, b_max) = b.bounds(self.offset);
+
+            let lower = [0, 1, 2].map(|i| a_min[i].max(b_min[i]));
+            let upper = [0, 1, 2].map(|i| a_max[i].min(b_max[i]));
+            if (0..3).any(|i| lower[i] >= upper[i]) {
+                continue;
+            }
+
+            if a.is_empty() || b.is_empty() {
+                continue;
+            }
+
+            match (a.leaf_info(), b.leaf_info()) {
+                (Some(a), Some(b)) => {
+                    return Some((
+                        a,
+                        b,
+                        lower.map(|c| c as u32),
+                        [0, 1, 2].map(|i| (upper[i] - lower[i]) as u32),
+                    ))
+                }
+                // Descend the larger of the two, so that each step shrinks the pair as much as possible.
+                (None, b_leaf) if b_leaf.is_some() || a.dimension() >= b.dimension() => {
+                    let children = a.children().collect::<Vec<_>>();
+                    self.stack.extend(children.into_iter().rev().map(|a| (a, b)));
+                }
+                _ => {
+                    let children = b.children().collect::<Vec<_>>();
+                    self.stack.extend(children.into_iter().rev().map(|b| (a, b)));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// The first contact of a box swept through an `Octree`, returned by [`Octree::sweep_aabb`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SweepHit<T> {
+    /// The fraction of the velocity travelled before the box touches the leaf, between 0 and 1.
+    pub t: f32,
+    /// The face of the leaf the box runs into, whose normal points back towards the box.
+    pub normal: Face,
+    /// The leaf the box runs into.
+    pub leaf: LeafInfo<T>,
+}
+
+/// A box moving along a velocity, tested against cubes expanded by its extents.
+struct Sweep {
+    min: [f32; 3],
+    max: [f32; 3],
+    velocity: [f32; 3],
+}
+
+impl Sweep {
+    /// Returns the fractions of the velocity at which the box starts and stops overlapping the given cube,
+    /// along with the face of the cube it first touches, if they overlap at any point of the sweep.
+    fn interval(&self, cube_min: [u32; 3], dimension: u32) -> Option<(f32, f32, Face)> {
+        let mut enter = f32::NEG_INFINITY;
+        let mut exit = f32::INFINITY;
+        let mut face = None;
+
+        for (i, (((min, max), velocity), lower)) in self
+            .min
+            .iter()
+            .zip(self.max.iter())
+            .zip(self.velocity.iter())
+            .zip(cube_min.iter())
+            .enumerate()
+        {
+            let lower = *lower as f32;
+            let upper = lower + dimension as f32;
+            let (min, max, velocity) = (*min, *max, *velocity);
+
+            if velocity == 0.0 {
+                if max <= lower || min >= upper {
+                    return None;
+                }
+
+                continue;
+            }
+
+            let (t0, t1) = if velocity > 0.0 {
+                ((lower - max) / velocity, (upper - min) / velocity)
+            } else {
+                ((upper - min) / velocity, (lower - max) / velocity)
+            };
+
+            // Only a strictly later entry replaces the axis, so ties go to the earlier axis.
+            if t0 > enter {
+                enter = t0;
+                face = Some(Face::ALL[2 * i + (velocity < 0.0) as usize]);
+            }
+
+            exit = exit.min(t1);
+        }
+
+        if enter >= exit || enter >= 1.0 || exit <= 0.0 {
+            return None;
+        }
+
+        match face {
+            Some(face) if enter >= 0.0 => Some((enter, exit, face)),
+            _ => Some((0.0, exit, self.escape(cube_min, dimension))),
+        }
+    }
+
+    /// Returns the face of a cube the box already overlaps through which it is closest to escaping.
+    fn escape(&self, cube_min: [u32; 3], dimension: u32) -> Face {
+        let mut best = (f32::INFINITY, Face::Left);
+
+        for (i, ((min, max), lower)) in self.min.iter().zip(self.max.iter()).zip(cube_min.iter()).enumerate() {
+            let lower = *lower as f32;
+            let upper = lower + dimension as f32;
+
+            for (depth, face) in [(max - lower, Face::ALL[2 * i]), (upper - min, Face::ALL[2 * i + 1])].iter() {
+                if *depth < best.0 {
+                    best = (*depth, *face);
+                }
+            }
+        }
+
+        best.1
+    }
+}
+
+/// Finds the earliest solid leaf of `node` the sweep runs into, if it is earlier than `best`.
+fn sweep_node<T, S>(node: NodeRef<'_, T>, sweep: &Sweep, background: T, solid: &S, best: &mut Option<SweepHit<T>>)
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+    S: Fn(&T) -> bool,
+{
+    if let Some(leaf) = LeafInfo::from_node(node) {
+        if solid(&leaf.data) {
+            sweep_leaf(leaf, sweep, best);
+        }
+
+        return;
+    }
+
+    let dimension = node.dimension() / 2;
+    let gap_solid = solid(&background);
+
+    let mut hits = [None; 8];
+    let mut count = 0;
+
+    for (min, child) in node.octants() {
+        if child.is_none() && !gap_solid {
+            continue;
+        }
+
+        if let Some((enter, _, _)) = sweep.interval(min.into(), dimension) {
+            hits[count] = Some((enter, min, child));
+            count += 1;
+        }
+    }
+
+    // Visit octants in the order the box reaches them, so that later ones are pruned by earlier hits.
+    let hits = &mut hits[..count];
+    hits.sort_unstable_by(|a, b| match (a, b) {
+        (Some(a), Some(b)) => a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal),
+        _ => Ordering::Equal,
+    });
+
+    for (enter, min, child) in hits.iter().flatten() {
+        if matches!(best, Some(hit) if hit.t <= *enter) {
+            break;
+        }
+
+        match child {
+            Some(child) => sweep_node(*child, sweep, background, solid, best),
+            None => sweep_leaf(
+                LeafInfo {
+                    min: (*min).into(),
+                    dimension,
+                    data: background,
+                },
+                sweep,
+                best,
+            ),
+        }
+    }
+}
+
+fn sweep_leaf<T: Copy>(leaf: LeafInfo<T>, sweep: &Sweep, best: &mut Option<SweepHit<T>>) {
+    if let Some((t, _, normal)) = sweep.interval(leaf.min, leaf.dimension) {
+        if !matches!(best, Some(hit) if hit.t <= t) {
+            *best = Some(SweepHit { t, normal, leaf });
+        }
+    }
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Returns whether the axis-aligned box from `min` to `max` overlaps any voxel for which `solid` returns
+    /// `true`.
+    ///
+    /// The box is treated as open, and each voxel as the cube from its position to its position plus one,
+    /// so a box only collides with a voxel it penetrates by a non-zero amount on every axis. A box resting
+    /// exactly on a face does not collide with it, while a box of zero size collides with the voxel it lies
+    /// strictly inside. Space outside the `Octree` is never solid, and unwritten space is tested as
+    /// the background.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([4, 4, 0], 1).unwrap();
+    ///
+    /// assert!(octree.collides_aabb([4.5, 4.5, 0.5], [5.5, 5.5, 1.5], |data| *data != 0));
+    /// assert!(!octree.collides_aabb([4.0, 4.0, 1.0], [5.0, 5.0, 2.0], |data| *data != 0));
+    /// ```
+    pub fn collides_aabb(
+        &self,
+        min: impl Into<Vector3<f32>>,
+        max: impl Into<Vector3<f32>>,
+        solid: impl Fn(&T) -> bool,
+    ) -> bool {
+        visit_aabb(
+            self.root(),
+            <[f32; 3]>::from(min.into()),
+            <[f32; 3]>::from(max.into()),
+            self.background(),
+            &solid,
+            &mut |_| true,
+        )
+    }
+
+    /// Returns every solid leaf overlapping the axis-aligned box from `min` to `max`.
+    ///
+    /// Uses the same conventions as [`Octree::collides_aabb`]. Leaves are returned whole rather than
+    /// clipped to the box, and unwritten space is returned as leaves holding the background when it is
+    /// solid.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([4, 4, 0], 1).unwrap();
+    /// octree.insert([5, 4, 0], 2).unwrap();
+    ///
+    /// let leaves = octree.collision_leaves_aabb([3.5, 4.0, 0.0], [5.5, 5.0, 1.0], |data| *data != 0);
+    /// assert_eq!(leaves.iter().map(|leaf| leaf.data).collect::<Vec<_>>(), vec![1, 2]);
+    /// ```
+    pub fn collision_leaves_aabb(
+        &self,
+        min: impl Into<Vector3<f32>>,
+        max: impl Into<Vector3<f32>>,
+        solid: impl Fn(&T) -> bool,
+    ) -> Vec<LeafInfo<T>> {
+        let min = <[f32; 3]>::from(min.into());
+        let max = <[f32; 3]>::from(max.into());
+        let mut leaves = Vec::new();
+
+        visit_aabb(self.root(), min, max, self.background(), &solid, &mut |leaf| {
+            leaves.push(leaf);
+            false
+        });
+
+        leaves
+    }
+
+    /// Returns whether any solid voxel of this `Octree` overlaps a solid voxel of `other`, when `other` is
+    /// moved by `offset`.
+    ///
+    /// Both trees are walked together, skipping any pair of subtrees whose bounds do not overlap, so two
+    /// large solid leaves are tested against each other as a whole. Voxels which only touch at a face do
+    /// not overlap. Unwritten space is tested as the background of its `Octree`, while space outside either
+    /// `Octree` is never solid.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut world = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// world.insert([10, 10, 10], 1).unwrap();
+    ///
+    /// let mut ship = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+    /// ship.insert([2, 2, 2], 1).unwrap();
+    ///
+    /// assert!(world.collides_with(&ship, [8, 8, 8], |data| *data != 0));
+    /// assert!(!world.collides_with(&ship, [8, 8, 9], |data| *data != 0));
+    /// ```
+    pub fn collides_with(&self, other: &Octree<T>, offset: [i32; 3], solid: impl Fn(&T) -> bool) -> bool {
+        self.overlap_with(other, offset, solid).is_some()
+    }
+
+    /// Returns a pair of overlapping solid voxels of this `Octree` and `other`, when `other` is moved by
+    /// `offset`.
+    ///
+    /// Behaves like [`Octree::collides_with`], returning the position of a voxel inside the first
+    /// overlapping pair of leaves found, once in the coordinates of this `Octree` and once in the
+    /// coordinates of `other`.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut world = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// world.insert([10, 10, 10], 1).unwrap();
+    ///
+    /// let mut ship = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+    /// ship.insert([2, 2, 2], 1).unwrap();
+    ///
+    /// let overlap = world.overlap_with(&ship, [8, 8, 8], |data| *data != 0);
+    /// assert_eq!(overlap, Some(([10, 10, 10], [2, 2, 2])));
+    /// ```
+    pub fn overlap_with(
+        &self,
+        other: &Octree<T>,
+        offset: [i32; 3],
+        solid: impl Fn(&T) -> bool,
+    ) -> Option<([u32; 3], [u32; 3])> {
+        let offset = offset.map(|c| c as i64);
+        let position = overlap(
+            Part::Node(self.root(), self.background()),
+            Part::Node(other.root(), other.background()),
+            offset,
+            &solid,
+        )?;
+
+        Some((
+            position.map(|c| c as u32),
+            [0, 1, 2].map(|i| (position[i] - offset[i]) as u32),
+        ))
+    }
+
+    /// Sweeps the axis-aligned box from `min` to `max` along `velocity`, returning the first solid leaf it
+    /// runs into.
+    ///
+    /// The box collides with a leaf under the same conventions as [`Octree::collides_aabb`], so sliding
+    /// along a face is not a hit. The returned `t` is the fraction of `velocity` travelled before the box
+    /// touches the leaf, and the normal is the face of the leaf it touches. When the box touches two faces
+    /// at the same moment, such as when hitting an edge exactly, the face on the earlier axis is chosen in
+    /// the order X, Y, Z. A box which already overlaps a solid leaf hits it at `t = 0`, with the face
+    /// through which it could escape with the least movement.
+    ///
+    /// The `Octree` is traversed against its node bounds expanded by the extents of the box, skipping any
+    /// node the swept box cannot reach, or cannot reach before a hit already found.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Face, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([4, 4, 0], 1).unwrap();
+    ///
+    /// let hit = octree
+    ///     .sweep_aabb([4.0, 4.0, 3.0], [5.0, 5.0, 5.0], [0.0, 0.0, -4.0], |data| *data != 0)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(hit.t, 0.5);
+    /// assert_eq!(hit.normal, Face::Top);
+    /// assert_eq!(hit.leaf.min, [4, 4, 0]);
+    /// ```
+    pub fn sweep_aabb(
+        &self,
+        min: impl Into<Vector3<f32>>,
+        max: impl Into<Vector3<f32>>,
+        velocity: impl Into<Vector3<f32>>,
+        solid: impl Fn(&T) -> bool,
+    ) -> Option<SweepHit<T>> {
+        let min = <[f32; 3]>::from(min.into());
+        let max = <[f32; 3]>::from(max.into());
+        let velocity = <[f32; 3]>::from(velocity.into());
+        let sweep = Sweep { min, max, velocity };
+        if !min
+            .iter()
+            .chain(max.iter())
+            .chain(velocity.iter())
+            .all(|c| c.is_finite())
+        {
+            return None;
+        }
+
+        let mut best = None;
+        if sweep
+            .interval(self.root().min_position().into(), self.dimension())
+            .is_some()
+        {
+            sweep_node(self.root(), &sweep, self.background(), &solid, &mut best);
+        }
+
+        best
+    }
+
+    /// Returns an iterator over every pair of overlapping non-empty leaves of this `Octree` and `other`, when
+    /// `other` is moved by `offset`.
+    ///
+    /// Each item is `(leaf, other_leaf, min, dimensions)`. Both leaves are described in the coordinates of
+    /// their own `Octree`, while `min` and `dimensions` describe the box where they overlap in the
+    /// coordinates of this `Octree`. Both trees are walked together, always splitting the larger of each
+    /// pair of candidate nodes, and skipping pairs which do not overlap or where either side is empty.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut a = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+    /// a.insert([5, 4, 4], 1).unwrap();
+    ///
+    /// let mut b = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+    /// b.insert([1, 0, 0], 2).unwrap();
+    ///
+    /// let pairs = a.overlapping_leaves(&b, [4, 4, 4]).collect::<Vec<_>>();
+    /// assert_eq!(pairs.len(), 1);
+    ///
+    /// let (leaf, other_leaf, min, dimensions) = pairs[0];
+    /// assert_eq!((leaf.data, other_leaf.data), (1, 2));
+    /// assert_eq!((min, dimensions), ([5, 4, 4], [1, 1, 1]));
+    /// ```
+    pub fn overlapping_leaves<'a>(&'a self, other: &'a Octree<T>, offset: [i32; 3]) -> OverlappingLeaves<'a, T> {
+        OverlappingLeaves {
+            offset: offset.map(|c| c as i64),
+            stack: vec![(
+                Part::Node(self.root(), self.background()),
+                Part::Node(other.root(), other.background()),
+            )],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sweep;
+    use crate::{test_utils::XorShift, Face, Octree};
+
+    use alloc::vec::Vec;
+    use core::num::NonZeroU32;
+
+    fn solid(data: &u8) -> bool {
+        *data != 0
+    }
+
+    #[test]
+    fn touching_faces_do_not_collide() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        octree.insert([2, 2, 2], 1).unwrap();
+
+        assert!(!octree.collides_aabb([3.0, 2.0, 2.0], [4.0, 3.0, 3.0], solid));
+        assert!(!octree.collides_aabb([1.0, 1.0, 1.0], [2.0, 2.0, 2.0], solid));
+        assert!(octree.collides_aabb([2.999, 2.0, 2.0], [4.0, 3.0, 3.0], solid));
+
+        // Boxes smaller than a voxel, including points, collide with the voxel containing them.
+        assert!(octree.collides_aabb([2.25, 2.25, 2.25], [2.5, 2.5, 2.5], solid));
+        assert!(octree.collides_aabb([2.5, 2.5, 2.5], [2.5, 2.5, 2.5], solid));
+        assert!(!octree.collides_aabb([3.0, 2.5, 2.5], [3.0, 2.5, 2.5], solid));
+    }
+
+    #[test]
+    fn boxes_outside_octree() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        octree.insert([0, 0, 0], 1).unwrap();
+        octree.insert([7, 7, 7], 1).unwrap();
+
+        assert!(!octree.collides_aabb([-2.0, -2.0, -2.0], [0.0, 1.0, 1.0], solid));
+        assert!(!octree.collides_aabb([8.0, 7.0, 7.0], [9.0, 8.0, 8.0], solid));
+        assert!(octree.collides_aabb([-2.0, -2.0, -2.0], [0.5, 0.5, 0.5], |_| true));
+        assert!(!octree.collides_aabb([-2.0, -2.0, -2.0], [-1.0, -1.0, -1.0], |_| true));
+    }
+
+    #[test]
+    fn collisions_match_brute_force() {
+        let mut rng = XorShift::new(0xaabb);
+
+        for _ in 0..20 {
+            let octree = rng.octree(16, 200, 2);
+
+            for _ in 0..100 {
+                let min = [0, 1, 2].map(|_| rng.f32(-4.0, 20.0));
+                let max = [0, 1, 2].map(|i| min[i] + rng.f32(0.0, 6.0));
+                let solid = |data: &u8| *data == 1;
+
+                let mut expected = Vec::new();
+                for x in 0..16 {
+                    for y in 0..16 {
+                        for z in 0..16 {
+                            let p = [x, y, z];
+                            let overlaps = (0..3).all(|i| max[i] > p[i] as f32 && min[i] < p[i] as f32 + 1.0);
+
+                            if overlaps && matches!(octree.get(p), Some(data) if solid(data)) {
+                                expected.push(p);
+                            }
+                        }
+                    }
+                }
+
+                assert_eq!(octree.collides_aabb(min, max, solid), !expected.is_empty());
+
+                let leaves = octree.collision_leaves_aabb(min, max, solid);
+                for leaf in leaves.iter() {
+                    assert!(solid(&leaf.data));
+                }
+
+                for p in expected.iter() {
+                    assert_eq!(leaves.iter().filter(|leaf| leaf.contains(*p)).count(), 1);
+                }
+
+                let covered = leaves.iter().all(|leaf| expected.iter().any(|p| leaf.contains(*p)));
+                assert!(covered);
+            }
+        }
+    }
+
+    fn fill(octree: &mut Octree<u8>, min: [u32; 3], max: [u32; 3], data: u8) {
+        for x in min[0]..max[0] {
+            for y in min[1]..max[1] {
+                for z in min[2]..max[2] {
+                    octree.insert([x, y, z], data).unwrap();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ship_touching_terrain() {
+        let mut terrain = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        fill(&mut terrain, [0, 0, 0], [16, 16, 4], 1);
+
+        // A solid hull, with a single voxel keel protruding below it.
+        let mut ship = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        fill(&mut ship, [0, 0, 1], [4, 4, 5], 1);
+        ship.insert([1, 1, 0], 1).unwrap();
+
+        assert!(terrain.collides_with(&ship, [4, 4, 3], solid));
+        assert_eq!(
+            terrain.overlap_with(&ship, [4, 4, 3], solid),
+            Some(([5, 5, 3], [1, 1, 0]))
+        );
+
+        // Resting on the terrain, or separated from it by a one voxel gap.
+        assert!(!terrain.collides_with(&ship, [4, 4, 4], solid));
+        assert!(!terrain.collides_with(&ship, [4, 4, 5], solid));
+
+        // Hanging over the edge of the terrain, with only the hull overlapping it.
+        assert!(terrain.collides_with(&ship, [14, -2, 2], solid));
+        assert!(!terrain.collides_with(&ship, [16, -2, 2], solid));
+        assert!(!terrain.collides_with(&ship, [14, -4, 2], solid));
+    }
+
+    #[test]
+    fn large_solid_leaves_overlap() {
+        let dimension = 1 << 20;
+        let mut a = Octree::<u8>::new(NonZeroU32::new(dimension).unwrap()).unwrap();
+        let mut b = Octree::<u8>::new(NonZeroU32::new(dimension).unwrap()).unwrap();
+        let bounds = a.bounds();
+        a.root_mut()
+            .insert(bounds, [0, 0, 0].into(), dimension, 1, 0, &mut Default::default())
+            .unwrap();
+        b.root_mut()
+            .insert(bounds, [0, 0, 0].into(), dimension, 1, 0, &mut Default::default())
+            .unwrap();
+
+        let offset = (dimension - 1) as i32;
+        assert_eq!(
+            a.overlap_with(&b, [offset; 3], solid),
+            Some(([dimension - 1; 3], [0, 0, 0]))
+        );
+        assert!(!a.collides_with(&b, [offset + 1, 0, 0], solid));
+    }
+
+    #[test]
+    fn octree_overlaps_match_brute_force() {
+        let mut rng = XorShift::new(0x5417);
+
+        for _ in 0..50 {
+            let a = rng.octree(8, 60, 2);
+            let b = rng.octree(8, 60, 2);
+            let offset = [0, 1, 2].map(|_| rng.below(17) as i32 - 8);
+            let solid = |data: &u8| *data == 1;
+
+            let mut expected = false;
+            for x in 0..8 {
+                for y in 0..8 {
+                    for z in 0..8 {
+                        let q = [x, y, z];
+                        let p = [0, 1, 2].map(|i| q[i] as i32 + offset[i]);
+
+                        if p.iter().any(|c| !(0..8).contains(c)) {
+                            continue;
+                        }
+
+                        let p = p.map(|c| c as u32);
+                        let solid_at = |octree: &Octree<u8>, p| matches!(octree.get(p), Some(data) if solid(data));
+                        expected |= solid_at(&a, p) && solid_at(&b, q);
+                    }
+                }
+            }
+
+            assert_eq!(a.collides_with(&b, offset, solid), expected);
+
+            if let Some((p, q)) = a.overlap_with(&b, offset, solid) {
+                assert_eq!([0, 1, 2].map(|i| p[i] as i32 - q[i] as i32), offset);
+                assert!(solid(a.get(p).unwrap()) && solid(b.get(q).unwrap()));
+            }
+        }
+    }
+
+    #[test]
+    fn sweep_along_and_onto_floor() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        fill(&mut octree, [0, 0, 0], [16, 16, 4], 1);
+
+        assert!(octree
+            .sweep_aabb([2.0, 2.0, 4.0], [3.0, 3.0, 5.5], [8.0, 3.0, 0.0], solid)
+            .is_none());
+
+        let hit = octree
+            .sweep_aabb([2.0, 2.0, 6.0], [3.0, 3.0, 7.5], [2.0, 0.0, -4.0], solid)
+            .unwrap();
+        assert_eq!((hit.t, hit.normal), (0.5, Face::Top));
+        assert!(hit.leaf.contains([3, 2, 3]));
+
+        // Moving away from the floor while resting on it.
+        assert!(octree
+            .sweep_aabb([2.0, 2.0, 4.0], [3.0, 3.0, 5.0], [0.0, 0.0, 1.0], solid)
+            .is_none());
+    }
+
+    #[test]
+    fn sweep_into_corner() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        octree.insert([4, 4, 0], 1).unwrap();
+
+        // Reaching the edge of the voxel exactly picks the earlier axis.
+        let hit = octree
+            .sweep_aabb([2.0, 2.0, 0.0], [3.0, 3.0, 1.0], [2.0, 2.0, 0.0], solid)
+            .unwrap();
+        assert_eq!((hit.t, hit.normal), (0.5, Face::Left));
+
+        // Otherwise, the face of the axis reached last is the one touched.
+        let hit = octree
+            .sweep_aabb([3.0, 2.0, 0.0], [4.0, 3.0, 1.0], [2.0, 2.0, 0.0], solid)
+            .unwrap();
+        assert_eq!((hit.t, hit.normal), (0.5, Face::Rear));
+
+        let hit = octree
+            .sweep_aabb([6.0, 2.0, 0.0], [7.0, 3.0, 1.0], [-2.0, 4.0, 0.0], solid)
+            .unwrap();
+        assert_eq!((hit.t, hit.normal), (0.5, Face::Right));
+    }
+
+    #[test]
+    fn sweep_starting_in_penetration() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        fill(&mut octree, [0, 0, 0], [16, 16, 4], 1);
+
+        let hit = octree
+            .sweep_aabb([2.0, 2.0, 3.75], [3.0, 3.0, 4.75], [1.0, 0.0, 0.0], solid)
+            .unwrap();
+        assert_eq!((hit.t, hit.normal), (0.0, Face::Top));
+
+        let hit = octree
+            .sweep_aabb([2.0, 2.0, 3.75], [3.0, 3.0, 4.75], [0.0, 0.0, 0.0], solid)
+            .unwrap();
+        assert_eq!((hit.t, hit.normal), (0.0, Face::Top));
+    }
+
+    #[test]
+    fn sweep_matches_brute_force() {
+        let mut rng = XorShift::new(0x5ee9);
+
+        for _ in 0..20 {
+            let octree = rng.octree(16, 100, 2);
+
+            for _ in 0..50 {
+                let min = [0, 1, 2].map(|_| rng.f32(-4.0, 20.0));
+                let max = [0, 1, 2].map(|i| min[i] + rng.f32(0.0, 3.0));
+                let velocity = [0, 1, 2].map(|_| rng.f32(-10.0, 10.0));
+                let solid = |data: &u8| *data == 1;
+
+                let sweep = Sweep { min, max, velocity };
+                let mut expected = None::<f32>;
+
+                for x in 0..16 {
+                    for y in 0..16 {
+                        for z in 0..16 {
+                            if matches!(octree.get([x, y, z]), Some(data) if solid(data)) {
+                                if let Some((t, _, _)) = sweep.interval([x, y, z], 1) {
+                                    expected = Some(expected.map_or(t, |e| e.min(t)));
+                                }
+                            }
+                        }
+                    }
+                }
+
+                let hit = octree.sweep_aabb(min, max, velocity, solid);
+                assert_eq!(hit.map(|hit| hit.t), expected);
+
+                if let Some(hit) = hit {
+                    assert!(solid(&hit.leaf.data));
+                    assert_eq!(sweep.interval(hit.leaf.min, hit.leaf.dimension).unwrap().0, hit.t);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn overlapping_leaf_pairs_in_hand_built_scene() {
+        let mut a = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        fill(&mut a, [0, 0, 0], [8, 8, 8], 1);
+        a.insert([8, 0, 0], 2).unwrap();
+
+        let mut b = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        fill(&mut b, [0, 0, 0], [4, 4, 4], 3);
+        b.insert([4, 0, 0], 4).unwrap();
+
+        // The large leaf of `a` overlaps the large leaf of `b` as a single pair.
+        let pairs = a.overlapping_leaves(&b, [-2, 0, 0]).collect::<Vec<_>>();
+        assert_eq!(pairs.len(), 2);
+        assert_eq!((pairs[0].0.dimension, pairs[0].1.dimension), (8, 4));
+        assert_eq!((pairs[0].2, pairs[0].3), ([0, 0, 0], [2, 4, 4]));
+        assert_eq!((pairs[1].0.data, pairs[1].1.data), (1, 4));
+        assert_eq!((pairs[1].2, pairs[1].3), ([2, 0, 0], [1, 1, 1]));
+
+        // Moved so that the single voxels of both trees overlap as well.
+        let pairs = a.overlapping_leaves(&b, [4, 0, 0]).map(|(a, b, _, _)| (a.data, b.data));
+        assert_eq!(pairs.collect::<Vec<_>>(), vec![(1, 3), (2, 4)]);
+
+        assert_eq!(a.overlapping_leaves(&b, [16, 0, 0]).count(), 0);
+    }
+
+    #[test]
+    fn overlapping_leaves_match_dense_intersection() {
+        let mut rng = XorShift::new(0x0a1a);
+
+        for _ in 0..50 {
+            let a = rng.octree(8, 60, 2);
+            let b = rng.octree(8, 60, 2);
+            let offset = [0, 1, 2].map(|_| rng.below(17) as i32 - 8);
+
+            let mut expected = 0;
+            for x in 0..8 {
+                for y in 0..8 {
+                    for z in 0..8 {
+                        let q = [x, y, z];
+                        let p = [0, 1, 2].map(|i| q[i] as i32 + offset[i]);
+
+                        if p.iter().all(|c| (0..8).contains(c)) {
+                            let non_empty = |octree: &Octree<u8>, p| matches!(octree.get(p), Some(data) if *data != 0);
+                            if non_empty(&a, p.map(|c| c as u32)) && non_empty(&b, q) {
+                                expected += 1;
+                            }
+                        }
+                    }
+                }
+            }
+
+            let mut volume = 0;
+            for (leaf, other_leaf, min, dimensions) in a.overlapping_leaves(&b, offset) {
+                assert!(leaf.data != 0 && other_leaf.data != 0);
+                assert!(leaf.contains(min));
+
+                let other_min = [0, 1, 2].map(|i| (min[i] as i32 - offset[i]) as u32);
+                assert!(other_leaf.contains(other_min));
+                assert!((0..3).all(|i| {
+                    min[i] + dimensions[i] <= leaf.min[i] + leaf.dimension
+                        && other_min[i] + dimensions[i] <= other_leaf.min[i] + other_leaf.dimension
+                }));
+
+                volume += dimensions[0] * dimensions[1] * dimensions[2];
+            }
+
+            assert_eq!(volume, expected);
+        }
+    }
+}
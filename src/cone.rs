@@ -0,0 +1,319 @@
+use crate::{float, LeafInfo, NodeRef, Octree, Vector3};
+
+use alloc::collections::BinaryHeap;
+use core::{cmp::Ordering, fmt::Debug, hash::Hash};
+
+/// A node in the trace queue, keyed by the distance at which the cone first reaches it.
+struct Queued<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    t: f32,
+    node: NodeRef<'a, T>,
+}
+
+impl<'a, T> Queued<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    /// Returns a key breaking ties between nodes reached at equal distances, so that the trace is
+    /// deterministic.
+    fn tie_break(&self) -> (u32, [u32; 3]) {
+        (self.node.dimension(), self.node.min_position().into())
+    }
+}
+
+impl<'a, T> PartialEq for Queued<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<'a, T> Eq for Queued<'a, T> where T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash {}
+
+impl<'a, T> PartialOrd for Queued<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a, T> Ord for Queued<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    // Reversed, so that the `BinaryHeap` pops the nearest node first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .t
+            .partial_cmp(&self.t)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| other.tie_break().cmp(&self.tie_break()))
+    }
+}
+
+/// Returns the data of the first leaf below `node` not holding `background`, in octant order.
+fn first_value<T>(node: NodeRef<'_, T>, background: T) -> Option<&T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    match node.leaf_data() {
+        Some(data) if *data != background => Some(data),
+        Some(_) => None,
+        None => node.children().find_map(|child| first_value(child, background)),
+    }
+}
+
+/// A lazy, front-to-back iterator over the spans of an `Octree` reached by a cone, each no finer than the
+/// width of the cone where it is reached.
+///
+/// Yields `(t, leaf)`, where `t` is the distance along the cone's axis at which the span is first reached.
+/// Created by [`Octree::cone_trace`].
+pub struct ConeIter<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    origin: [f32; 3],
+    direction: [f32; 3],
+    slope: f32,
+    max_distance: f32,
+    background: T,
+    queue: BinaryHeap<Queued<'a, T>>,
+}
+
+impl<'a, T> ConeIter<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    pub(crate) fn new(
+        root: NodeRef<'a, T>,
+        origin: [f32; 3],
+        direction: [f32; 3],
+        half_angle: f32,
+        max_distance: f32,
+        background: T,
+    ) -> Self {
+        let length = float::sqrt(direction.iter().map(|c| c * c).sum::<f32>());
+
+        let mut iter = Self {
+            origin,
+            direction: direction.map(|c| c / length),
+            slope: float::tan(half_angle),
+            max_distance,
+            background,
+            queue: BinaryHeap::new(),
+        };
+
+        let valid = origin.iter().chain(direction.iter()).all(|c| c.is_finite())
+            && length > 0.0
+            && (0.0..core::f32::consts::FRAC_PI_2).contains(&half_angle)
+            && max_distance >= 0.0;
+
+        if valid {
+            iter.push(root);
+        }
+
+        iter
+    }
+
+    /// Returns the distance at which the cone first reaches the given cube, if it does so within the maximum
+    /// distance.
+    ///
+    /// The cone is widened to the square pyramid around it, whose cross-section at distance `t` extends
+    /// `t * slope` from the axis along each axis of the `Octree`. Each face of the cube then bounds the
+    /// distance linearly, just as for a ray, which the pyramid reduces to when the slope is zero.
+    fn enter(&self, min: [u32; 3], dimension: u32) -> Option<f32> {
+        let mut t_enter = 0.0_f32;
+        let mut t_exit = self.max_distance;
+
+        for ((min, origin), direction) in min.iter().zip(self.origin.iter()).zip(self.direction.iter()) {
+            let lower = *min as f32;
+            let upper = lower + dimension as f32;
+
+            // The cross-section reaches past the lower face once `(direction + slope) * t >= lower - origin`.
+            let (a, b) = (direction + self.slope, lower - origin);
+            if a > 0.0 {
+                t_enter = t_enter.max(b / a);
+            } else if a < 0.0 {
+                t_exit = t_exit.min(b / a);
+            } else if b > 0.0 {
+                return None;
+            }
+
+            // The cross-section reaches before the upper face while `(direction - slope) * t < upper - origin`.
+            let (a, b) = (direction - self.slope, upper - origin);
+            if a > 0.0 {
+                t_exit = t_exit.min(b / a);
+            } else if a < 0.0 {
+                t_enter = t_enter.max(b / a);
+            } else if b <= 0.0 {
+                return None;
+            }
+        }
+
+        if t_enter < t_exit {
+            Some(t_enter)
+        } else {
+            None
+        }
+    }
+
+    fn push(&mut self, node: NodeRef<'a, T>) {
+        if let Some(t) = self.enter(node.min_position().into(), node.dimension()) {
+            self.queue.push(Queued { t, node });
+        }
+    }
+}
+
+impl<'a, T> Iterator for ConeIter<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    type Item = (f32, LeafInfo<T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(Queued { t, node }) = self.queue.pop() {
+            if node.is_leaf() || node.dimension() as f32 <= 2.0 * t * self.slope {
+                if let Some(data) = first_value(node, self.background) {
+                    let leaf = LeafInfo {
+                        min: node.min_position().into(),
+                        dimension: node.dimension(),
+                        data: *data,
+                    };
+
+                    return Some((t, leaf));
+                }
+
+                continue;
+            }
+
+            for child in node.children() {
+                self.push(child);
+            }
+        }
+
+        None
+    }
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Traces a cone through the `Octree`, returning a lazy iterator over the non-empty spans it reaches,
+    /// front-to-back, at a detail matching the width of the cone.
+    ///
+    /// The cone starts at `origin`, opens around `direction` with the given half-angle in radians, and
+    /// ends `max_distance` along its axis. Each item is `(t, leaf)`, where `t` is the distance along the
+    /// axis at which the span is first reached. Nodes are not subdivided once their dimension is no larger
+    /// than the diameter of the cone where it reaches them, and are yielded whole instead, holding the
+    /// value of the first non-empty leaf below them. Empty subtrees are never yielded. For efficiency the
+    /// cone is widened to the square pyramid containing it, and with a half-angle of zero the trace
+    /// reduces to [`Octree::raycast_iter`]. An invalid cone yields nothing.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([20, 0, 0], 1).unwrap();
+    /// octree.insert([21, 0, 0], 2).unwrap();
+    ///
+    /// let narrow = octree.cone_trace([0.5, 0.5, 0.5], [1.0, 0.0, 0.0], 0.0, 32.0).collect::<Vec<_>>();
+    /// assert_eq!(narrow.len(), 2);
+    ///
+    /// let wide = octree.cone_trace([0.5, 0.5, 0.5], [1.0, 0.0, 0.0], 0.3, 32.0).collect::<Vec<_>>();
+    /// assert_eq!(wide.len(), 1);
+    /// assert!(wide[0].1.dimension > 1);
+    /// ```
+    pub fn cone_trace(
+        &self,
+        origin: impl Into<Vector3<f32>>,
+        direction: impl Into<Vector3<f32>>,
+        half_angle: f32,
+        max_distance: f32,
+    ) -> ConeIter<'_, T> {
+        ConeIter::new(
+            self.root(),
+            <[f32; 3]>::from(origin.into()),
+            <[f32; 3]>::from(direction.into()),
+            half_angle,
+            max_distance,
+            self.background(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_utils::XorShift, Octree};
+
+    use alloc::vec::Vec;
+    use core::num::NonZeroU32;
+
+    #[test]
+    fn narrow_cone_matches_raycast() {
+        let mut rng = XorShift::new(0xc0e);
+
+        for _ in 0..20 {
+            let octree = rng.octree(16, 200, 3);
+
+            for _ in 0..20 {
+                let origin = [0, 1, 2].map(|_| rng.f32(-4.0, 20.0));
+                let direction = [0, 1, 2].map(|_| rng.f32(-1.0, 1.0));
+                let length = direction.iter().map(|c| c * c).sum::<f32>().sqrt();
+                let direction = direction.map(|c| c / length);
+
+                let rays = octree.raycast_iter(origin, direction).collect::<Vec<_>>();
+                let cones = octree.cone_trace(origin, direction, 0.0, 100.0).collect::<Vec<_>>();
+
+                assert_eq!(rays.len(), cones.len());
+                for ((t_enter, _, ray_leaf), (t, cone_leaf)) in rays.iter().zip(cones.iter()) {
+                    assert_eq!(ray_leaf, cone_leaf);
+                    assert!((t_enter - t).abs() < 1e-3);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn wide_cone_yields_fewer_larger_spans() {
+        let mut rng = XorShift::new(0xc0e5);
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(64).unwrap()).unwrap();
+
+        for _ in 0..4000 {
+            let p = rng.position(32);
+            octree.insert([p[0] + 32, p[1] + 16, p[2] + 16], 1).unwrap();
+        }
+
+        let origin = [0.5, 32.0, 32.0];
+        let direction = [1.0, 0.0, 0.0];
+
+        let narrow = octree.cone_trace(origin, direction, 0.05, 64.0).collect::<Vec<_>>();
+        let wide = octree.cone_trace(origin, direction, 0.5, 64.0).collect::<Vec<_>>();
+
+        assert!(wide.len() < narrow.len());
+
+        let largest = |spans: &[(f32, crate::LeafInfo<u8>)]| spans.iter().map(|(_, leaf)| leaf.dimension).max();
+        assert!(largest(&wide) > largest(&narrow));
+
+        for spans in [&narrow, &wide].iter() {
+            for pair in spans.windows(2) {
+                assert!(pair[0].0 <= pair[1].0);
+            }
+        }
+
+        // Every span is either a leaf, or no larger than the cone is wide where it is reached.
+        for (t, leaf) in wide.iter() {
+            let max = leaf.min.map(|c| c + leaf.dimension);
+            let is_leaf = octree.query_region(leaf.min, max).count() == 1;
+            assert!(is_leaf || leaf.dimension as f32 <= 2.0 * t * 0.5_f32.tan());
+        }
+    }
+}
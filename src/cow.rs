@@ -0,0 +1,468 @@
+use crate::{
+    codec::write_tokens,
+    flat::Token,
+    node::{octant_index, OCTREE_CHILDREN},
+    Error, Node, NodeRef, Octree, ValueCodec, Vector3,
+};
+
+use alloc::{sync::Arc, vec, vec::Vec};
+use core::{fmt::Debug, hash::Hash, iter, num::NonZeroU32};
+
+/// A `Node` of a [`CowOctree`], shared by every snapshot holding it.
+#[derive(Debug, Clone)]
+enum CowNode<T> {
+    /// A leaf holding the given data.
+    Leaf(T),
+    /// An internal `Node`, with its child in each octant, in octant order.
+    Branch([Option<Arc<CowNode<T>>>; OCTREE_CHILDREN]),
+}
+
+impl<T> CowNode<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Writes `data` to `position` below `node`, which has the given dimension, copying the `Node`s on the way
+    /// down which are shared. Stops at a leaf already holding `data`, as `Node::insert` does.
+    fn insert(node: &mut Arc<Self>, position: [u32; 3], dimension: u32, data: T, background: T) {
+        if matches!(**node, Self::Leaf(leaf) if leaf == data) {
+            return;
+        }
+        if dimension == 1 {
+            *node = Arc::new(Self::Leaf(data));
+            return;
+        }
+
+        let node = Arc::make_mut(node);
+        let child = node.split(background)[octant_index(position, dimension / 2)]
+            .get_or_insert_with(|| Arc::new(Self::Leaf(background)));
+
+        Self::insert(child, position, dimension / 2, data, background);
+        node.simplify();
+    }
+
+    /// Clears `position` below `node`, which has the given dimension and holds data other than `background`
+    /// there, to `background`, copying the `Node`s on the way down which are shared.
+    fn clear(node: &mut Arc<Self>, position: [u32; 3], dimension: u32, background: T) {
+        if dimension == 1 {
+            *node = Arc::new(Self::Leaf(background));
+            return;
+        }
+
+        let node = Arc::make_mut(node);
+        if let Some(child) = &mut node.split(background)[octant_index(position, dimension / 2)] {
+            Self::clear(child, position, dimension / 2, background);
+        }
+
+        node.simplify();
+    }
+
+    /// Turns a leaf into a branch with identical contents, as by `Node::split`, returning its children.
+    ///
+    /// The children of a leaf all share a single leaf, which is copied as soon as one of them is written.
+    fn split(&mut self, background: T) -> &mut [Option<Arc<Self>>; OCTREE_CHILDREN] {
+        if let Self::Leaf(data) = *self {
+            let child = (data != background).then(|| Arc::new(Self::Leaf(data)));
+            *self = Self::Branch([(); OCTREE_CHILDREN].map(|_| child.clone()));
+        }
+
+        match self {
+            Self::Branch(children) => children,
+            Self::Leaf(_) => unreachable!("leaf left after splitting"),
+        }
+    }
+
+    /// Makes the `Node` a leaf if all of its children are leaves holding the same data, as by `Node::simplify`.
+    fn simplify(&mut self) {
+        if let Self::Branch(children) = self {
+            let data = match children[0].as_deref() {
+                Some(Self::Leaf(data)) => *data,
+                _ => return,
+            };
+
+            if children
+                .iter()
+                .all(|child| matches!(child.as_deref(), Some(Self::Leaf(leaf)) if *leaf == data))
+            {
+                *self = Self::Leaf(data);
+            }
+        }
+    }
+
+    /// Copies `node` and every `Node` below it.
+    fn copy(node: NodeRef<'_, T>) -> Self {
+        match node.leaf_data() {
+            Some(data) => Self::Leaf(*data),
+            None => Self::Branch(
+                [0, 1, 2, 3, 4, 5, 6, 7].map(|octant| node.child(octant).map(|child| Arc::new(Self::copy(child)))),
+            ),
+        }
+    }
+
+    /// Rebuilds the `Node` and every `Node` below it as a `Node` of an `Octree`.
+    fn node(&self) -> Node<T> {
+        match self {
+            Self::Leaf(data) => Node::leaf(*data),
+            Self::Branch(children) => Node::branch(children.each_ref().map(|child| child.as_deref().map(Self::node))),
+        }
+    }
+}
+
+/// A sparse voxel octree whose `Node`s are shared between snapshots of it, and copied only when written.
+///
+/// Behaves as an [`Octree`] at LOD level 1, as [`ArenaOctree`](crate::ArenaOctree) does: it is simplified after
+/// every edit exactly as an `Octree` is, and [`CowOctree::to_bytes`] writes the same bytes as
+/// [`Octree::to_bytes`]. [`CowOctree::snapshot`] only shares the root, so taking one costs the same however large
+/// the tree is. Edits to either the snapshot or the tree it was taken from copy the `Node`s on the way down to the
+/// voxel written which are still shared, and leave every other `Node` shared, so each reads as it did when the
+/// snapshot was taken. Snapshots can be sent to other threads, to be saved there while the tree keeps being
+/// edited.
+///
+/// # Example
+/// ```
+/// # use svo_rs::{CowOctree, Error};
+/// # use core::num::NonZeroU32;
+/// #
+/// let mut world = CowOctree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+/// world.insert([9, 8, 31], 1).unwrap();
+///
+/// let snapshot = world.snapshot();
+/// world.insert([9, 8, 31], 2).unwrap();
+///
+/// assert!(matches!(world.get([9, 8, 31]), Some(2)));
+/// assert!(matches!(snapshot.get([9, 8, 31]), Some(1)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct CowOctree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    dimension: NonZeroU32,
+    background: T,
+    root: Arc<CowNode<T>>,
+}
+
+impl<T> CowOctree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Creates a new `CowOctree<T>` of given dimension, which must be a power of 2, as by [`Octree::new`].
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{CowOctree, Error};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// assert!(CowOctree::<u8>::new(NonZeroU32::new(32).unwrap()).is_ok());
+    ///
+    /// let octree = CowOctree::<u8>::new(NonZeroU32::new(15).unwrap());
+    /// assert!(matches!(octree, Err(Error::InvalidDimension(15))));
+    /// ```
+    pub fn new(dimension: NonZeroU32) -> Result<Self, Error> {
+        Self::new_with_background(dimension, T::default())
+    }
+
+    /// Creates a new `CowOctree<T>` of given dimension, where unwritten and cleared space holds `background`
+    /// rather than `T::default()`, as by [`Octree::new_with_background`].
+    pub fn new_with_background(dimension: NonZeroU32, background: T) -> Result<Self, Error> {
+        if !dimension.is_power_of_two() {
+            return Err(Error::InvalidDimension(dimension.get()));
+        }
+
+        Ok(Self {
+            dimension,
+            background,
+            root: Arc::new(CowNode::Leaf(background)),
+        })
+    }
+
+    /// Returns a snapshot of the `CowOctree` as it stands, sharing all of its `Node`s.
+    ///
+    /// This is the same as cloning it. Later edits to either leave the other as it was.
+    pub fn snapshot(&self) -> Self {
+        self.clone()
+    }
+
+    /// Inserts data of type `T` into the given position, as by [`Octree::insert`].
+    ///
+    /// Writing data already held there copies nothing.
+    pub fn insert(&mut self, position: impl Into<Vector3<u32>>, data: T) -> Result<(), Error> {
+        let position = <[u32; 3]>::from(position.into());
+        self.check(position)?;
+        if self.leaf(position) != Some(&data) {
+            let (dimension, background) = (self.dimension(), self.background);
+            CowNode::insert(&mut self.root, position, dimension, data, background);
+        }
+
+        Ok(())
+    }
+
+    /// Retrieves data of type `T` from the given position, as by [`Octree::get`].
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{CowOctree, Error};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = CowOctree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([9, 8, 31], 1).unwrap();
+    ///
+    /// assert!(matches!(octree.get([9, 8, 31]), Some(1)));
+    /// assert!(matches!(octree.get([20, 1, 12]), Some(0)));
+    /// assert!(octree.get([32, 1, 12]).is_none());
+    /// ```
+    pub fn get(&self, position: impl Into<Vector3<u32>>) -> Option<&T> {
+        let position = <[u32; 3]>::from(position.into());
+        if !self.contains(position) {
+            return None;
+        }
+
+        Some(self.leaf(position).unwrap_or(&self.background))
+    }
+
+    /// Returns the data of the leaf holding the given position, or `None` if it lies in an empty octant.
+    fn leaf(&self, position: [u32; 3]) -> Option<&T> {
+        let (mut node, mut dimension) = (&*self.root, self.dimension());
+
+        loop {
+            match node {
+                CowNode::Leaf(data) => return Some(data),
+                CowNode::Branch(children) => {
+                    node = children[octant_index(position, dimension / 2)].as_deref()?;
+                    dimension /= 2;
+                }
+            }
+        }
+    }
+
+    /// Clears the voxel at the given position to the background, as by [`Octree::clear_at`].
+    ///
+    /// Clearing unwritten space, or a voxel already holding the background, copies nothing.
+    pub fn clear_at(&mut self, position: impl Into<Vector3<u32>>) -> Result<(), Error> {
+        let position = <[u32; 3]>::from(position.into());
+        self.check(position)?;
+        if matches!(self.get(position), Some(data) if *data != self.background) {
+            let (dimension, background) = (self.dimension(), self.background);
+            CowNode::clear(&mut self.root, position, dimension, background);
+        }
+
+        Ok(())
+    }
+
+    /// Removes all `Node`s, leaving snapshots sharing them untouched.
+    pub fn clear(&mut self) {
+        self.root = Arc::new(CowNode::Leaf(self.background));
+    }
+
+    /// Returns the value held by unwritten and cleared space.
+    pub fn background(&self) -> T {
+        self.background
+    }
+
+    /// Returns the dimension of the root node.
+    pub fn dimension(&self) -> u32 {
+        self.dimension.get()
+    }
+
+    /// Returns whether the given position exists within the confines of the `CowOctree`.
+    pub fn contains(&self, position: impl Into<Vector3<u32>>) -> bool {
+        let position = <[u32; 3]>::from(position.into());
+        position.iter().all(|c| *c < self.dimension())
+    }
+
+    fn check(&self, position: [u32; 3]) -> Result<(), Error> {
+        if self.contains(position) {
+            Ok(())
+        } else {
+            Err(Error::InvalidPosition(position.into()))
+        }
+    }
+}
+
+impl<T> CowOctree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + ValueCodec,
+{
+    /// Encodes the `CowOctree` as bytes, in the same layout as [`Octree::to_bytes`].
+    ///
+    /// Encoding a snapshot leaves the tree it was taken from free to be edited meanwhile, even from another
+    /// thread.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{CowOctree, Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut world = CowOctree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// world.insert([9, 8, 31], 1).unwrap();
+    ///
+    /// let snapshot = world.snapshot();
+    /// let save = std::thread::spawn(move || snapshot.to_bytes());
+    /// world.insert([1, 2, 3], 4).unwrap();
+    ///
+    /// let saved = Octree::<u8>::from_bytes(&save.join().unwrap()).unwrap();
+    /// assert!(matches!(saved.get([9, 8, 31]), Some(1)));
+    /// assert!(matches!(saved.get([1, 2, 3]), Some(0)));
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut stack = vec![&*self.root];
+        let tokens = iter::from_fn(|| {
+            let token = match stack.pop()? {
+                CowNode::Leaf(data) => Token::Leaf(*data),
+                CowNode::Branch(children) => {
+                    let mut mask = 0;
+                    // Push in reverse, so that children are listed in octant order.
+                    for (i, child) in children.iter().enumerate().rev() {
+                        if let Some(child) = child {
+                            mask |= 1 << i;
+                            stack.push(child);
+                        }
+                    }
+
+                    Token::Branch(mask)
+                }
+            };
+
+            Some(token)
+        });
+
+        write_tokens(self.dimension(), 1, self.background, tokens)
+    }
+
+    /// Decodes a `CowOctree` encoded by [`CowOctree::to_bytes`] or [`Octree::to_bytes`].
+    ///
+    /// Fails as [`Octree::from_bytes`] does, and with [`Error::InvalidLodLevel`] if the bytes hold an `Octree`
+    /// above LOD level 1.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let octree = Octree::from_bytes(bytes)?;
+
+        match octree.lod_level() {
+            1 => Ok(Self::from(&octree)),
+            level => Err(Error::InvalidLodLevel(level)),
+        }
+    }
+}
+
+impl<T> From<&Octree<T>> for CowOctree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Copies the `Node`s of an `Octree`. Journaled LOD detail and subtrees held in storage are not copied.
+    fn from(octree: &Octree<T>) -> Self {
+        Self {
+            dimension: NonZeroU32::new(octree.dimension()).unwrap(),
+            background: octree.background(),
+            root: Arc::new(CowNode::copy(octree.root())),
+        }
+    }
+}
+
+impl<T> From<&CowOctree<T>> for Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Copies the `Node`s of a `CowOctree` into an `Octree` at LOD level 1.
+    fn from(octree: &CowOctree<T>) -> Self {
+        Octree::from_root(octree.dimension(), octree.root.node(), octree.background, 1).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CowNode, CowOctree};
+    use crate::test_utils::{assert_converts_octree, assert_matches_octree, assert_rejects_out_of_bounds, XorShift};
+
+    use alloc::{sync::Arc, vec::Vec};
+    use core::num::NonZeroU32;
+
+    #[test]
+    fn matches_octree() {
+        assert_matches_octree::<CowOctree<u8>>(0xc0e4);
+    }
+
+    #[test]
+    fn converts_to_and_from_octree() {
+        assert_converts_octree::<CowOctree<u8>>(0xc0e5);
+    }
+
+    #[test]
+    fn out_of_bounds_positions_are_rejected() {
+        assert_rejects_out_of_bounds::<CowOctree<u8>>();
+    }
+
+    #[test]
+    fn snapshots_keep_their_contents_and_share_untouched_nodes() {
+        let mut rng = XorShift::new(0xc0e6);
+        let mut live = CowOctree::from(&rng.octree(64, 5000, 4));
+        let snapshot = live.snapshot();
+        let bytes = snapshot.to_bytes();
+        let voxels = |octree: &CowOctree<u8>| {
+            (0..64 * 64 * 64)
+                .map(|i| octree.get([i % 64, i / 64 % 64, i / 4096]).copied())
+                .collect::<Vec<_>>()
+        };
+        let original = voxels(&snapshot);
+
+        // Heavy editing of the live tree, all of it in the lower half along x.
+        for _ in 0..20_000 {
+            let [x, y, z] = rng.position(64);
+            match rng.below(3) {
+                0 => live.clear_at([x / 2, y, z]).unwrap(),
+                _ => live.insert([x / 2, y, z], 1 + rng.below(4) as u8).unwrap(),
+            }
+        }
+
+        assert_eq!(voxels(&snapshot), original);
+        assert_eq!(snapshot.to_bytes(), bytes);
+        assert_ne!(voxels(&live), original);
+
+        // The upper half was never written, so its `Node`s are still shared by both trees.
+        let children = |octree: &CowOctree<u8>| match &*octree.root {
+            CowNode::Branch(children) => children.clone(),
+            CowNode::Leaf(_) => panic!("root simplified"),
+        };
+        let (shared, copied) = (children(&snapshot), children(&live));
+        for octant in [1, 3, 5, 7] {
+            let (a, b) = (shared[octant].as_ref().unwrap(), copied[octant].as_ref().unwrap());
+            assert!(Arc::ptr_eq(a, b));
+            // Held by both roots, and by both copies of them made above.
+            assert_eq!(Arc::strong_count(a), 4);
+        }
+        for octant in [0, 2, 4, 6] {
+            let a = shared[octant].as_ref().unwrap();
+            assert!(!Arc::ptr_eq(a, copied[octant].as_ref().unwrap()));
+            assert_eq!(Arc::strong_count(a), 2);
+        }
+    }
+
+    #[test]
+    fn snapshots_are_saved_while_editing() {
+        let mut rng = XorShift::new(0xc0e7);
+        let mut live = CowOctree::from(&rng.octree(32, 2000, 4));
+        let expected = live.to_bytes();
+
+        let snapshot = live.snapshot();
+        let save = std::thread::spawn(move || snapshot.to_bytes());
+        for _ in 0..2000 {
+            live.insert(rng.position(32), 1 + rng.below(4) as u8).unwrap();
+        }
+
+        assert_eq!(save.join().unwrap(), expected);
+        assert_ne!(live.to_bytes(), expected);
+    }
+
+    #[test]
+    fn no_op_edits_leave_nodes_shared() {
+        let mut live = CowOctree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        live.insert([3, 4, 5], 1).unwrap();
+        let snapshot = live.snapshot();
+
+        // Clearing unwritten space, and writing data already held, copy nothing.
+        live.clear_at([12, 12, 12]).unwrap();
+        live.insert([3, 4, 5], 1).unwrap();
+        assert!(Arc::ptr_eq(&live.root, &snapshot.root));
+
+        live.clear_at([3, 4, 5]).unwrap();
+        assert!(!Arc::ptr_eq(&live.root, &snapshot.root));
+        assert!(matches!(snapshot.get([3, 4, 5]), Some(1)));
+    }
+}
@@ -0,0 +1,281 @@
+use crate::{NodeRef, Octree, Vector3};
+
+use alloc::string::String;
+use core::{
+    fmt::{Debug, Write},
+    hash::Hash,
+};
+
+/// Limits on how much of an `Octree` [`Octree::to_debug_json`] writes out.
+///
+/// Branches whose children would exceed either limit are written without them, marked as truncated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DebugLimits {
+    /// The depth below the root beyond which children are not written.
+    pub max_depth: u32,
+    /// The number of `Node`s after which children are not written. The root is always written.
+    pub max_nodes: usize,
+}
+
+impl Default for DebugLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: u32::MAX,
+            max_nodes: 10_000,
+        }
+    }
+}
+
+/// Appends `text` to `json` as a JSON string.
+fn write_string(json: &mut String, text: &str) {
+    json.push('"');
+
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+
+    json.push('"');
+}
+
+/// Writes the `Node`s of an `Octree` as nested JSON objects, within the given limits.
+struct Writer<'a, F> {
+    json: String,
+    value: &'a F,
+    limits: DebugLimits,
+    nodes: usize,
+    truncated: bool,
+}
+
+impl<'a, F> Writer<'a, F> {
+    /// Opens the object of a `Node` with the given minimum position and size.
+    fn bounds(&mut self, min: Vector3<u32>, size: u32) {
+        write!(
+            self.json,
+            "{{\"min\":[{},{},{}],\"size\":{},",
+            min.x, min.y, min.z, size
+        )
+        .unwrap();
+    }
+
+    /// Appends `node`, at the given depth below the root, and as many of its descendants as the limits allow.
+    fn node<T>(&mut self, node: NodeRef<'_, T>, depth: u32)
+    where
+        T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+        F: Fn(&T) -> String,
+    {
+        self.bounds(node.min_position(), node.dimension());
+
+        if let Some(data) = node.leaf_data() {
+            self.json.push_str("\"type\":\"leaf\",\"value\":");
+            write_string(&mut self.json, &(self.value)(data));
+            self.json.push('}');
+            return;
+        }
+
+        self.json.push_str("\"type\":\"branch\",");
+
+        let count = node.occupancy().count_ones() as usize;
+        if depth >= self.limits.max_depth || self.nodes + count > self.limits.max_nodes {
+            self.truncated = true;
+            self.json.push_str("\"truncated\":true}");
+            return;
+        }
+
+        self.nodes += count;
+        self.json.push_str("\"children\":[");
+
+        for (i, (min, child)) in node.octants().enumerate() {
+            if i > 0 {
+                self.json.push(',');
+            }
+
+            // An octant whose child is held, but not in memory, is held in storage.
+            match (child, node.occupancy() & 1 << i != 0) {
+                (Some(child), _) => self.node(child, depth + 1),
+                (None, true) => {
+                    self.bounds(min, node.dimension() / 2);
+                    self.json.push_str("\"type\":\"unloaded\"}");
+                }
+                (None, false) => self.json.push_str("null"),
+            }
+        }
+
+        self.json.push_str("]}");
+    }
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Writes the `Octree` as human-readable JSON, for inspecting its structure while debugging.
+    ///
+    /// The JSON object holds the dimension, LOD level and background of the `Octree`, the number of `Node`s
+    /// written, whether any were left out, and the root `Node`. Each `Node` holds its minimum position, its
+    /// size and its type: a `"leaf"` holds its `"value"`, a `"branch"` its eight `"children"` in octant order,
+    /// `null` where unwritten, and an `"unloaded"` `Node` is held in storage. Branches whose children exceed
+    /// `limits` are marked `"truncated"` instead. Values are written as the strings `value` returns for them.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{DebugLimits, Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(2).unwrap()).unwrap();
+    /// octree.insert([1, 0, 0], 4).unwrap();
+    ///
+    /// let json = octree.to_debug_json(|value| format!("{:?}", value), DebugLimits::default());
+    /// assert!(json.contains(r#"{"min":[1,0,0],"size":1,"type":"leaf","value":"4"}"#));
+    /// ```
+    pub fn to_debug_json<F>(&self, value: F, limits: DebugLimits) -> String
+    where
+        F: Fn(&T) -> String,
+    {
+        let mut writer = Writer {
+            json: String::new(),
+            value: &value,
+            limits,
+            nodes: 1,
+            truncated: false,
+        };
+        writer.node(self.root(), 0);
+
+        let mut json = String::new();
+        write!(
+            json,
+            "{{\"dimension\":{},\"lod_level\":{},\"background\":",
+            self.dimension(),
+            self.lod_level()
+        )
+        .unwrap();
+        write_string(&mut json, &value(&self.background()));
+        write!(
+            json,
+            ",\"nodes\":{},\"truncated\":{},\"root\":{}}}",
+            writer.nodes, writer.truncated, writer.json
+        )
+        .unwrap();
+
+        json
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DebugLimits;
+    use crate::{test_utils::XorShift, Octree};
+
+    use core::num::NonZeroU32;
+    use serde_json::Value;
+
+    fn parse(octree: &Octree<u8>, limits: DebugLimits) -> Value {
+        serde_json::from_str(&octree.to_debug_json(|value| format!("{:?}", value), limits)).unwrap()
+    }
+
+    /// Counts the `Node` objects below and including `node`.
+    fn count(node: &Value) -> usize {
+        match node["children"].as_array() {
+            Some(children) => {
+                1 + children
+                    .iter()
+                    .filter(|child| !child.is_null())
+                    .map(count)
+                    .sum::<usize>()
+            }
+            None => 1,
+        }
+    }
+
+    #[test]
+    fn small_trees_are_written_whole() {
+        let mut octree = Octree::<u8>::new_with_background(NonZeroU32::new(4).unwrap(), 7).unwrap();
+        octree.insert([3, 0, 0], 1).unwrap();
+        octree.insert([0, 2, 0], 2).unwrap();
+
+        let json = parse(&octree, DebugLimits::default());
+        assert_eq!(json["dimension"], 4);
+        assert_eq!(json["lod_level"], octree.lod_level());
+        assert_eq!(json["background"], "7");
+        assert_eq!(json["truncated"], false);
+
+        let root = &json["root"];
+        assert_eq!(root["type"], "branch");
+        assert_eq!(root["size"], 4);
+        assert_eq!(json["nodes"], count(root));
+
+        // Octant 1 holds x of 2 and above, and octant 4 y of 2 and above.
+        let children = root["children"].as_array().unwrap();
+        assert_eq!(children.len(), 8);
+        assert_eq!(children.iter().filter(|child| child.is_null()).count(), 6);
+        assert_eq!(children[1]["min"], serde_json::json!([2, 0, 0]));
+        assert_eq!(children[1]["children"][1]["value"], "1");
+        assert_eq!(children[4]["children"][0]["type"], "leaf");
+        assert_eq!(children[4]["children"][0]["value"], "2");
+    }
+
+    #[test]
+    fn limits_truncate_output() {
+        let mut rng = XorShift::new(0xd650);
+        let octree = rng.octree(32, 2000, 5);
+
+        let whole = parse(&octree, DebugLimits::default());
+        assert_eq!(whole["truncated"], false);
+        assert_eq!(whole["nodes"], count(&whole["root"]));
+
+        let few = parse(
+            &octree,
+            DebugLimits {
+                max_depth: u32::MAX,
+                max_nodes: 50,
+            },
+        );
+        assert_eq!(few["truncated"], true);
+        assert_eq!(few["nodes"], count(&few["root"]));
+        assert!(count(&few["root"]) <= 50);
+
+        let shallow = parse(
+            &octree,
+            DebugLimits {
+                max_depth: 1,
+                max_nodes: usize::MAX,
+            },
+        );
+        assert_eq!(shallow["truncated"], true);
+        for child in shallow["root"]["children"].as_array().unwrap() {
+            assert!(child.is_null() || child["children"].is_null());
+        }
+    }
+
+    #[test]
+    fn values_are_escaped() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(2).unwrap()).unwrap();
+        octree.insert([0, 0, 0], 1).unwrap();
+
+        let json = octree.to_debug_json(|value| format!("\"{}\"\n\\", value), DebugLimits::default());
+        let json: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json["root"]["children"][0]["value"], "\"1\"\n\\");
+    }
+
+    #[test]
+    fn unloaded_subtrees_are_marked() {
+        let mut rng = XorShift::new(0xd651);
+        let octree = rng.octree(16, 500, 5);
+
+        let blobs = octree.encode_subtrees(NonZeroU32::new(1).unwrap());
+        let mut source = |path: &[u8]| blobs.get(path).cloned();
+        let copy = Octree::<u8>::open_subtrees(&mut source).unwrap();
+
+        let json = parse(&copy, DebugLimits::default());
+        let children = json["root"]["children"].as_array().unwrap();
+        assert!(children.iter().any(|child| child["type"] == "unloaded"));
+        assert!(children.iter().all(|child| child.is_null() || child["size"] == 8));
+    }
+}
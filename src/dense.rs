@@ -0,0 +1,257 @@
+use crate::{Error, Node, Octree};
+
+#[cfg(feature = "rayon")]
+use crate::node::{octant_bounds, Bounds};
+
+use core::{fmt::Debug, hash::Hash, num::NonZeroU32};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// The largest dimension of the subtrees [`Octree::par_from_fn`] builds on a single thread.
+#[cfg(feature = "rayon")]
+const PARALLEL_DIMENSION: u32 = 32;
+
+/// Builds the `Node` with the given bounds as [`Node::from_fn`] does, building its octants on the rayon pool
+/// down to subtrees no larger than [`PARALLEL_DIMENSION`].
+///
+/// The octants are combined with [`Node::from_octants`] exactly as on a single thread, so the result does not
+/// depend on the order in which they finish.
+#[cfg(feature = "rayon")]
+fn par_from_fn<T, F>(bounds: Bounds, f: &F, background: T) -> Node<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + Send + Sync,
+    F: Fn([u32; 3]) -> T + Sync,
+{
+    if bounds[1].x - bounds[0].x <= PARALLEL_DIMENSION {
+        return Node::from_fn(bounds, &mut |position| f(position), background);
+    }
+
+    let children = octant_bounds(bounds);
+    let mut octants = children.map(|_| Node::leaf(background));
+    octants
+        .par_iter_mut()
+        .zip(children.par_iter())
+        .for_each(|(octant, bounds)| *octant = par_from_fn(*bounds, f, background));
+
+    Node::from_octants(octants, background)
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Creates a new `Octree<T>` of given dimension holding the data `f` returns for each voxel.
+    ///
+    /// The `Octree` is built bottom up rather than by inserting each voxel: every 2*2*2 block becomes a leaf
+    /// if its voxels hold the same data, and so on up, so uniform regions never allocate `Node`s inside them.
+    /// Voxels holding `T::default()` are left unwritten. `f` is called once for each voxel, in no particular
+    /// order.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let octree = Octree::<u8>::from_fn(NonZeroU32::new(16).unwrap(), |[_, y, _]| (y < 4) as u8).unwrap();
+    ///
+    /// assert!(matches!(octree.get([9, 3, 12]), Some(1)));
+    /// assert!(matches!(octree.get([9, 4, 12]), Some(0)));
+    /// ```
+    pub fn from_fn(dimension: NonZeroU32, mut f: impl FnMut([u32; 3]) -> T) -> Result<Self, Error> {
+        let mut octree = Self::new(dimension)?;
+        let root = Node::from_fn(octree.root().bounds(), &mut f, octree.background());
+
+        *octree.root_mut() = root;
+        Ok(octree)
+    }
+
+    /// Creates a new `Octree<T>` of given dimension from the data of every voxel, with x varying fastest,
+    /// then y, then z.
+    ///
+    /// The `Octree` is built as by [`Octree::from_fn`]. Returns [`Error::LengthMismatch`] if `data` does not
+    /// hold exactly one value for each voxel.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut data = vec![3_u8; 8 * 8 * 8];
+    /// data[1 + 2 * 8 + 7 * 64] = 5;
+    ///
+    /// let octree = Octree::from_dense(NonZeroU32::new(8).unwrap(), &data).unwrap();
+    /// assert!(matches!(octree.get([1, 2, 7]), Some(5)));
+    /// assert!(matches!(octree.get([0, 0, 0]), Some(3)));
+    ///
+    /// assert_eq!(
+    ///     Octree::from_dense(NonZeroU32::new(4).unwrap(), &data).unwrap_err(),
+    ///     Error::LengthMismatch { expected: 64, found: 512 }
+    /// );
+    /// ```
+    pub fn from_dense(dimension: NonZeroU32, data: &[T]) -> Result<Self, Error> {
+        let side = dimension.get() as usize;
+        let expected = side.saturating_mul(side).saturating_mul(side);
+
+        if data.len() != expected {
+            return Err(Error::LengthMismatch {
+                expected,
+                found: data.len(),
+            });
+        }
+
+        Self::from_fn(dimension, |[x, y, z]| {
+            data[x as usize + side * (y as usize + side * z as usize)]
+        })
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + Send + Sync,
+{
+    /// Creates a new `Octree<T>` of given dimension holding the data `f` returns for each voxel, building its
+    /// octants on the rayon pool.
+    ///
+    /// Octants are split further until they are at most 32 voxels across, and each is built as by
+    /// [`Octree::from_fn`] on one thread. The result is identical to that of [`Octree::from_fn`], however the
+    /// work is scheduled.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let dimension = NonZeroU32::new(128).unwrap();
+    /// let f = |[x, y, z]: [u32; 3]| (x * x + y * y + z * z < 100 * 100) as u8;
+    ///
+    /// let octree = Octree::par_from_fn(dimension, f).unwrap();
+    /// assert!(octree.equivalent(&Octree::from_fn(dimension, f).unwrap()));
+    /// ```
+    pub fn par_from_fn(dimension: NonZeroU32, f: impl Fn([u32; 3]) -> T + Sync) -> Result<Self, Error> {
+        let mut octree = Self::new(dimension)?;
+        let root = par_from_fn(octree.root().bounds(), &f, octree.background());
+
+        *octree.root_mut() = root;
+        Ok(octree)
+    }
+
+    /// Creates a new `Octree<T>` of given dimension from the data of every voxel, with x varying fastest,
+    /// then y, then z, building its octants on the rayon pool as by [`Octree::par_from_fn`].
+    ///
+    /// Returns [`Error::LengthMismatch`] if `data` does not hold exactly one value for each voxel.
+    pub fn par_from_dense(dimension: NonZeroU32, data: &[T]) -> Result<Self, Error> {
+        let side = dimension.get() as usize;
+        let expected = side.saturating_mul(side).saturating_mul(side);
+
+        if data.len() != expected {
+            return Err(Error::LengthMismatch {
+                expected,
+                found: data.len(),
+            });
+        }
+
+        Self::par_from_fn(dimension, |[x, y, z]| {
+            data[x as usize + side * (y as usize + side * z as usize)]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_utils::XorShift, Error, Octree};
+
+    use alloc::vec::Vec;
+    use core::num::NonZeroU32;
+
+    #[test]
+    fn matches_insert_loop() {
+        let mut rng = XorShift::new(0xd5e1);
+
+        for dimension in [1, 2, 8, 32] {
+            // Random trees hold both large uniform regions and single voxels.
+            let source = rng.octree(dimension, dimension.pow(2), 3);
+            let data = (0..dimension.pow(3))
+                .map(|i| {
+                    let position = [i % dimension, i / dimension % dimension, i / dimension / dimension];
+                    *source.get(position).unwrap()
+                })
+                .collect::<Vec<_>>();
+
+            let mut expected = Octree::<u8>::new(NonZeroU32::new(dimension).unwrap()).unwrap();
+            for (i, data) in data.iter().enumerate() {
+                let i = i as u32;
+                let position = [i % dimension, i / dimension % dimension, i / dimension / dimension];
+                expected.insert(position, *data).unwrap();
+            }
+
+            let octree = Octree::from_dense(NonZeroU32::new(dimension).unwrap(), &data).unwrap();
+            assert!(octree.equivalent(&expected));
+
+            for i in 0..dimension.pow(3) {
+                let position = [i % dimension, i / dimension % dimension, i / dimension / dimension];
+                assert_eq!(octree.get(position), Some(&data[i as usize]));
+            }
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_builds_match_sequential_ones() {
+        let mut rng = XorShift::new(0xd5e2);
+
+        for dimension in [1, 32, 64, 128] {
+            let dimension = NonZeroU32::new(dimension).unwrap();
+            let seed = rng.next_u32();
+
+            // Noise over a few blocky terrain layers, so that the tree holds both uniform and detailed regions.
+            let f = |[x, y, z]: [u32; 3]| {
+                let hash = (x.wrapping_mul(0x9e37_79b9) ^ y.wrapping_mul(0x85eb_ca6b) ^ z ^ seed).count_ones();
+                match (y / 4 + x / 16 + z / 16) % 5 {
+                    0 => 0,
+                    1 => 1 + (hash % 2) as u8,
+                    layer => layer as u8,
+                }
+            };
+
+            let expected = format!("{:#?}", Octree::from_fn(dimension, f).unwrap().root());
+            for _ in 0..3 {
+                assert_eq!(
+                    format!("{:#?}", Octree::par_from_fn(dimension, f).unwrap().root()),
+                    expected
+                );
+            }
+
+            let side = dimension.get();
+            let data = (0..side.pow(3))
+                .map(|i| f([i % side, i / side % side, i / side / side]))
+                .collect::<Vec<_>>();
+            let octree = Octree::par_from_dense(dimension, &data).unwrap();
+            assert_eq!(format!("{:#?}", octree.root()), expected);
+        }
+
+        assert_eq!(
+            Octree::<u8>::par_from_dense(NonZeroU32::new(2).unwrap(), &[0; 9]).unwrap_err(),
+            Error::LengthMismatch { expected: 8, found: 9 }
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_dimensions_and_lengths() {
+        assert_eq!(
+            Octree::<u8>::from_dense(NonZeroU32::new(6).unwrap(), &[0; 216]).unwrap_err(),
+            Error::InvalidDimension(6)
+        );
+        assert_eq!(
+            Octree::<u8>::from_dense(NonZeroU32::new(2).unwrap(), &[0; 7]).unwrap_err(),
+            Error::LengthMismatch { expected: 8, found: 7 }
+        );
+        assert_eq!(
+            Octree::<u8>::from_dense(NonZeroU32::new(1 << 31).unwrap(), &[]).unwrap_err(),
+            Error::LengthMismatch {
+                expected: usize::MAX,
+                found: 0
+            }
+        );
+    }
+}
@@ -1,20 +1,51 @@
+use crate::Vector3;
+
 use core::fmt;
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum Error {
     InvalidDimension(u32),
-    InvalidPosition { x: u32, y: u32, z: u32 },
+    InvalidPosition(Vector3<u32>),
     InvalidOctant(usize),
+    OutOfBounds,
+    InvalidMesh,
+    DimensionMismatch { expected: u32, found: u32 },
+    InvalidLodLevel(u32),
+    InvalidEncoding,
+    UnsupportedVersion(u32),
+    ChecksumMismatch { expected: u32, actual: u32 },
+    SubtreeNotLoaded,
+    LengthMismatch { expected: usize, found: usize },
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::InvalidDimension(dimension) => write!(f, "Invalid dimension: {}. Must be a power of 2.", dimension),
-            Self::InvalidPosition { x, y, z } => {
-                write!(f, "Position {{{}, {}, {}}} does not exist in octree.", x, y, z)
-            }
+            Self::InvalidPosition(position) => write!(f, "Position {} does not exist in octree.", position),
             Self::InvalidOctant(octant) => write!(f, "Invalid octant: {}", octant),
+            Self::OutOfBounds => write!(f, "Shape extends outside octree."),
+            Self::InvalidMesh => write!(f, "Mesh has invalid indices or vertices."),
+            Self::DimensionMismatch { expected, found } => {
+                write!(f, "Dimension mismatch: expected {}, found {}.", expected, found)
+            }
+            Self::InvalidLodLevel(level) => write!(f, "Invalid LOD level: {}", level),
+            Self::InvalidEncoding => write!(f, "Encoded octree is truncated or corrupt."),
+            Self::UnsupportedVersion(version) => write!(f, "Unsupported format version: {}", version),
+            Self::ChecksumMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "Checksum mismatch: expected {:#010x}, found {:#010x}.",
+                    expected, actual
+                )
+            }
+            Self::SubtreeNotLoaded => write!(f, "Subtree is held in storage and has not been loaded."),
+            Self::LengthMismatch { expected, found } => {
+                write!(f, "Length mismatch: expected {} values, found {}.", expected, found)
+            }
         }
     }
 }
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
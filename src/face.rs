@@ -0,0 +1,291 @@
+use crate::{LeafInfo, NodeRef, Octree, Vector3};
+
+use core::{fmt::Debug, hash::Hash};
+
+/// One of the six faces of a voxel or leaf, named to match the octants of a `Node`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Face {
+    /// The face pointing towards -X.
+    Left,
+    /// The face pointing towards +X.
+    Right,
+    /// The face pointing towards -Y.
+    Rear,
+    /// The face pointing towards +Y.
+    Front,
+    /// The face pointing towards -Z.
+    Base,
+    /// The face pointing towards +Z.
+    Top,
+}
+
+impl Face {
+    /// All six faces, in axis order.
+    pub const ALL: [Face; 6] = [Face::Left, Face::Right, Face::Rear, Face::Front, Face::Base, Face::Top];
+
+    /// Returns the index of the axis the face is perpendicular to (0 for X, 1 for Y, 2 for Z).
+    pub fn axis(&self) -> usize {
+        match self {
+            Self::Left | Self::Right => 0,
+            Self::Rear | Self::Front => 1,
+            Self::Base | Self::Top => 2,
+        }
+    }
+
+    /// Returns whether the face points along the positive direction of its axis.
+    pub fn is_positive(&self) -> bool {
+        matches!(self, Self::Right | Self::Front | Self::Top)
+    }
+
+    /// Returns the face pointing in the opposite direction.
+    pub fn opposite(&self) -> Self {
+        match self {
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+            Self::Rear => Self::Front,
+            Self::Front => Self::Rear,
+            Self::Base => Self::Top,
+            Self::Top => Self::Base,
+        }
+    }
+
+    /// Returns the unit normal of the face.
+    pub fn normal(&self) -> [i32; 3] {
+        let mut normal = [0; 3];
+        normal[self.axis()] = if self.is_positive() { 1 } else { -1 };
+        normal
+    }
+}
+
+/// One of the three axes of an `Octree`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Axis {
+    /// The X axis.
+    X,
+    /// The Y axis.
+    Y,
+    /// The Z axis.
+    Z,
+}
+
+impl Axis {
+    /// All three axes, in order.
+    pub const ALL: [Axis; 3] = [Axis::X, Axis::Y, Axis::Z];
+
+    /// Returns the index of the axis (0 for X, 1 for Y, 2 for Z).
+    pub fn index(&self) -> usize {
+        match self {
+            Self::X => 0,
+            Self::Y => 1,
+            Self::Z => 2,
+        }
+    }
+}
+
+/// Descends from `node` to the leaf covering `position`, describing unwritten space as a leaf holding
+/// `background`.
+fn leaf_at<T>(node: NodeRef<'_, T>, position: Vector3<u32>, background: T) -> LeafInfo<T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    let mut node = node;
+
+    loop {
+        if let Some(leaf) = LeafInfo::from_node(node) {
+            return leaf;
+        }
+
+        match node.octant_at(position).unwrap() {
+            (_, Some(child)) => node = child,
+            (min, None) => {
+                return LeafInfo {
+                    min: min.into(),
+                    dimension: node.dimension() / 2,
+                    data: background,
+                }
+            }
+        }
+    }
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Returns the leaf on the other side of the given face of the leaf covering `position`.
+    ///
+    /// The neighbor is the leaf covering the voxel directly across the face from `position`, which may be
+    /// larger or smaller than the leaf covering `position` itself. Space which has never been written is
+    /// described as a leaf holding the background. Returns `None` if `position` is outside the `Octree`, or
+    /// if the face lies on the boundary of the `Octree`.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Face, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([15, 4, 4], 1).unwrap();
+    /// octree.insert([16, 4, 4], 2).unwrap();
+    ///
+    /// let neighbor = octree.face_neighbor([15, 4, 4], Face::Right).unwrap();
+    /// assert_eq!((neighbor.min, neighbor.dimension, neighbor.data), ([16, 4, 4], 1, 2));
+    ///
+    /// assert!(octree.face_neighbor([0, 4, 4], Face::Left).is_none());
+    /// ```
+    pub fn face_neighbor(&self, position: impl Into<Vector3<u32>>, face: Face) -> Option<LeafInfo<T>> {
+        let position = position.into();
+        if !self.root().contains(position) {
+            return None;
+        }
+
+        // Record the path down to the leaf covering `position`, so that the search for the neighbor only
+        // needs to climb back to the nearest common ancestor.
+        let mut path = [None; 33];
+        let mut depth = 0;
+        let mut node = self.root();
+
+        let leaf = loop {
+            path[depth] = Some(node);
+            depth += 1;
+
+            if let Some(leaf) = LeafInfo::from_node(node) {
+                break leaf;
+            }
+
+            match node.octant_at(position).unwrap() {
+                (_, Some(child)) => node = child,
+                (min, None) => {
+                    break LeafInfo {
+                        min: min.into(),
+                        dimension: node.dimension() / 2,
+                        data: self.background(),
+                    }
+                }
+            }
+        };
+
+        let mut across: [u32; 3] = position.into();
+        let axis = face.axis();
+
+        across[axis] = if face.is_positive() {
+            leaf.min[axis] + leaf.dimension
+        } else {
+            leaf.min[axis].checked_sub(1)?
+        };
+
+        let across = Vector3::from(across);
+
+        path[..depth]
+            .iter()
+            .rev()
+            .flatten()
+            .find(|ancestor| ancestor.contains(across))
+            .map(|ancestor| leaf_at(*ancestor, across, self.background()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{leaf_at, Face};
+    use crate::{test_utils::XorShift, LeafInfo, Octree};
+
+    use core::num::NonZeroU32;
+
+    fn fill_cube(octree: &mut Octree<u8>, min: [u32; 3], dimension: u32, data: u8) {
+        for x in 0..dimension {
+            for y in 0..dimension {
+                for z in 0..dimension {
+                    octree.insert([min[0] + x, min[1] + y, min[2] + z], data).unwrap();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn neighbors_across_root_planes() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        octree.insert([7, 7, 7], 1).unwrap();
+        octree.insert([8, 7, 7], 2).unwrap();
+        octree.insert([7, 8, 7], 3).unwrap();
+        octree.insert([7, 7, 8], 4).unwrap();
+
+        let data = |face| octree.face_neighbor([7, 7, 7], face).map(|leaf| leaf.data);
+        assert_eq!(data(Face::Right), Some(2));
+        assert_eq!(data(Face::Front), Some(3));
+        assert_eq!(data(Face::Top), Some(4));
+        assert_eq!(data(Face::Left), Some(0));
+
+        let neighbor = octree.face_neighbor([8, 7, 7], Face::Left).unwrap();
+        assert_eq!((neighbor.min, neighbor.dimension, neighbor.data), ([7, 7, 7], 1, 1));
+    }
+
+    #[test]
+    fn neighbors_of_large_leaf_bordering_detail() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        fill_cube(&mut octree, [8, 8, 8], 4, 5);
+        octree.insert([7, 9, 10], 6).unwrap();
+
+        // From inside the large leaf, the neighbor is the fine voxel across from the queried position.
+        let neighbor = octree.face_neighbor([9, 9, 10], Face::Left).unwrap();
+        assert_eq!(
+            neighbor,
+            LeafInfo {
+                min: [7, 9, 10],
+                dimension: 1,
+                data: 6
+            }
+        );
+
+        // From the fine voxel, the neighbor is the whole large leaf.
+        let neighbor = octree.face_neighbor([7, 9, 10], Face::Right).unwrap();
+        assert_eq!(
+            neighbor,
+            LeafInfo {
+                min: [8, 8, 8],
+                dimension: 4,
+                data: 5
+            }
+        );
+
+        assert!(octree.face_neighbor([11, 11, 11], Face::Top).is_some());
+        assert!(octree.face_neighbor([15, 11, 11], Face::Right).is_none());
+        assert!(octree.face_neighbor([16, 11, 11], Face::Left).is_none());
+    }
+
+    #[test]
+    fn face_neighbor_matches_descent_from_root() {
+        let mut rng = XorShift::new(0xface);
+
+        for _ in 0..20 {
+            let octree = rng.octree(16, 300, 3);
+
+            for _ in 0..100 {
+                let position = rng.position(16);
+                let face = Face::ALL[rng.below(6) as usize];
+
+                let own = leaf_at(octree.root(), position.into(), 0);
+                assert!(own.contains(position));
+
+                let axis = face.axis();
+                let mut across = position;
+                across[axis] = if face.is_positive() {
+                    own.min[axis] + own.dimension
+                } else {
+                    own.min[axis].wrapping_sub(1)
+                };
+
+                let neighbor = octree.face_neighbor(position, face);
+
+                if octree.contains(across) {
+                    let neighbor = neighbor.unwrap();
+                    assert!(neighbor.contains(across));
+                    assert_eq!(*octree.get(across).unwrap_or(&0), neighbor.data);
+                    assert_eq!(neighbor, leaf_at(octree.root(), across.into(), 0));
+                } else {
+                    assert!(neighbor.is_none());
+                }
+            }
+        }
+    }
+}
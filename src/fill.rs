@@ -0,0 +1,52 @@
+use crate::{query::Containment, trace::Counter, Octree};
+
+use alloc::vec::Vec;
+use core::{fmt::Debug, hash::Hash};
+
+/// Writes `data` to every voxel of the `Octree` inside a shape, or clears them if `data` is `None`.
+///
+/// `classify` is called with the minimum position and dimension of cubes of the `Octree`, starting from the
+/// whole `Octree` and subdividing only the cubes it reports as straddling the shape, so cubes entirely
+/// inside it are written as single leaves. Single voxels reported as straddling are written. The number of cubes
+/// classified and of those written are recorded as `cubes` and `written` on the span of the caller.
+pub(crate) fn fill<T, F>(octree: &mut Octree<T>, data: Option<T>, classify: F)
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+    F: Fn([u32; 3], u32) -> Containment,
+{
+    let min_dimension = octree.min_dimension();
+    let (bounds, background) = (octree.bounds(), octree.background());
+    let mut stack = Vec::new();
+    stack.push(([0; 3], octree.dimension()));
+    let (mut cubes, mut written) = (Counter::default(), Counter::default());
+
+    while let Some((min, dimension)) = stack.pop() {
+        cubes.increment();
+
+        match classify(min, dimension) {
+            Containment::Outside => {}
+            Containment::Straddling if dimension > 1 => {
+                let half = dimension / 2;
+
+                for i in 0..8 {
+                    let offset = [i & 1, (i >> 1) & 1, (i >> 2) & 1];
+                    stack.push(([0, 1, 2].map(|axis| min[axis] + offset[axis] * half), half));
+                }
+            }
+            _ => {
+                let (root, pool) = octree.root_and_pool_mut();
+                let dimension = dimension.max(min_dimension);
+                written.increment();
+
+                match data {
+                    Some(data) => root
+                        .insert(bounds, min.into(), dimension, data, background, pool)
+                        .unwrap(),
+                    None => root.clear(bounds, min.into(), dimension, background, pool).unwrap(),
+                }
+            }
+        }
+    }
+
+    record!(cubes = cubes.get(), written = written.get());
+}
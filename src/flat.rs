@@ -0,0 +1,202 @@
+use crate::{
+    node::OCTREE_CHILDREN,
+    query::{classify_box, Containment},
+    Node, NodeRef,
+};
+
+use alloc::{vec, vec::Vec};
+use core::{fmt::Debug, hash::Hash};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// One `Node` of the flattened form of an `Octree`, listing nodes in pre-order.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) enum Token<T> {
+    /// A leaf holding the given data.
+    Leaf(T),
+    /// An internal `Node`, followed by the tokens of each of its children present in the bit mask, in octant
+    /// order.
+    Branch(u8),
+}
+
+/// An iterator over the tokens of a `Node` and every `Node` below it, in pre-order.
+///
+/// Subtrees held in storage are left out of the bit masks, as if never written, so encoders check
+/// [`NodeRef::is_loaded`] first where that would lose data.
+pub(crate) struct Flatten<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    stack: Vec<NodeRef<'a, T>>,
+    region: Option<([u32; 3], [u32; 3])>,
+}
+
+impl<'a, T> Flatten<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    pub(crate) fn new(root: NodeRef<'a, T>) -> Self {
+        Self {
+            stack: vec![root],
+            region: None,
+        }
+    }
+
+    /// Lists only the `Node`s below `root` intersecting the box from `min` (inclusive) to `max` (exclusive),
+    /// leaving the others out of the masks of their parents. `root` is always listed.
+    pub(crate) fn within(root: NodeRef<'a, T>, min: [u32; 3], max: [u32; 3]) -> Self {
+        Self {
+            stack: vec![root],
+            region: Some((min, max)),
+        }
+    }
+}
+
+impl<'a, T> Iterator for Flatten<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    type Item = Token<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+
+        match node.leaf_data() {
+            Some(data) => Some(Token::Leaf(*data)),
+            None => {
+                let region = self.region;
+                let children = node
+                    .octants()
+                    .enumerate()
+                    .filter_map(|(i, (min, child))| Some((i, child?, <[u32; 3]>::from(min))))
+                    .filter(|(_, child, min)| match region {
+                        Some(region) => {
+                            classify_box(region.0, region.1, *min, child.dimension()) != Containment::Outside
+                        }
+                        None => true,
+                    })
+                    .collect::<Vec<_>>();
+
+                let mut mask = 0;
+                for (i, _, _) in children.iter() {
+                    mask |= 1 << i;
+                }
+
+                // Push in reverse, so that children are listed in octant order.
+                self.stack.extend(children.into_iter().rev().map(|(_, child, _)| child));
+
+                Some(Token::Branch(mask))
+            }
+        }
+    }
+}
+
+/// An internal `Node` being rebuilt, with the children decoded so far.
+struct Frame<T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    dimension: u32,
+    mask: u8,
+    children: [Option<Node<T>>; OCTREE_CHILDREN],
+    next: usize,
+}
+
+/// Rebuilds the `Node` with the given dimension from its tokens, as listed by [`Flatten`].
+///
+/// Tokens are consumed one at a time, stopping at the first error. Malformed tokens are reported through
+/// `malformed`.
+pub(crate) fn unflatten<T, E>(
+    dimension: u32,
+    mut tokens: impl Iterator<Item = Result<Token<T>, E>>,
+    malformed: impl Fn(&'static str) -> E,
+) -> Result<Node<T>, E>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    let mut stack: Vec<Frame<T>> = Vec::new();
+    let mut dimension = Some(dimension);
+
+    loop {
+        let mut finished = None;
+
+        if let Some(dimension) = dimension.take() {
+            match tokens.next().ok_or_else(|| malformed("missing nodes"))?? {
+                Token::Leaf(data) => finished = Some(Node::leaf(data)),
+                Token::Branch(_) if dimension < 2 => return Err(malformed("branch of a single voxel")),
+                Token::Branch(mask) => stack.push(Frame {
+                    dimension,
+                    mask,
+                    children: Default::default(),
+                    next: 0,
+                }),
+            }
+        }
+
+        loop {
+            let frame = match (finished.take(), stack.last_mut()) {
+                (Some(node), None) => return Ok(node),
+                (Some(node), Some(frame)) => {
+                    frame.children[frame.next] = Some(node);
+                    frame.next += 1;
+                    frame
+                }
+                (None, frame) => frame.unwrap(),
+            };
+
+            while frame.next < OCTREE_CHILDREN && frame.mask & (1 << frame.next) == 0 {
+                frame.next += 1;
+            }
+
+            if frame.next < OCTREE_CHILDREN {
+                dimension = Some(frame.dimension / 2);
+                break;
+            }
+
+            let frame = stack.pop().unwrap();
+            finished = Some(Node::branch(frame.children));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{unflatten, Flatten};
+    use crate::{test_utils::XorShift, NodeRef};
+
+    use alloc::vec::Vec;
+    use core::convert::Infallible;
+
+    #[test]
+    fn bounds_are_rebuilt_from_paths() {
+        let mut rng = XorShift::new(0xf1a7);
+
+        for _ in 0..10 {
+            let octree = rng.octree(32, 1000, 4);
+            let root = octree.root();
+
+            let tokens = Flatten::new(root).map(Ok::<_, Infallible>);
+            let copy = unflatten(root.dimension(), tokens, |reason| panic!("{}", reason)).unwrap();
+            let copy = NodeRef::new(&copy, root.bounds());
+
+            let nodes = |root: NodeRef<'_, u8>| {
+                let mut nodes = Vec::new();
+                let mut stack = vec![root];
+                while let Some(node) = stack.pop() {
+                    let [min, max] = node.bounds();
+                    nodes.push((<[u32; 3]>::from(min), <[u32; 3]>::from(max), node.leaf_data().copied()));
+                    stack.extend(node.children());
+                }
+                nodes
+            };
+            assert_eq!(nodes(copy), nodes(root));
+
+            for _ in 0..100 {
+                let position = rng.position(32).into();
+                assert_eq!(copy.contains(position), root.contains(position));
+                assert_eq!(copy.get(position), root.get(position));
+            }
+        }
+    }
+}
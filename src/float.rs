@@ -0,0 +1,44 @@
+//! The functions on `f32` that `core` leaves to `std`, taken from `micromath` without it.
+//!
+//! They are called by path rather than as methods, as another crate in the graph may link `std` into a build
+//! without the `std` feature, such as when the dev-dependencies are built along with the integration tests, and
+//! the methods of `std` would then be used instead, leaving an import of `micromath::F32Ext` unused.
+
+#[cfg(not(any(test, feature = "std")))]
+use micromath::F32Ext;
+
+/// Returns the square root of `x`.
+#[inline]
+pub(crate) fn sqrt(x: f32) -> f32 {
+    #[cfg(any(test, feature = "std"))]
+    return f32::sqrt(x);
+    #[cfg(not(any(test, feature = "std")))]
+    return F32Ext::sqrt(x);
+}
+
+/// Returns the largest integer less than or equal to `x`.
+#[inline]
+pub(crate) fn floor(x: f32) -> f32 {
+    #[cfg(any(test, feature = "std"))]
+    return f32::floor(x);
+    #[cfg(not(any(test, feature = "std")))]
+    return F32Ext::floor(x);
+}
+
+/// Returns the smallest integer greater than or equal to `x`.
+#[inline]
+pub(crate) fn ceil(x: f32) -> f32 {
+    #[cfg(any(test, feature = "std"))]
+    return f32::ceil(x);
+    #[cfg(not(any(test, feature = "std")))]
+    return F32Ext::ceil(x);
+}
+
+/// Returns the tangent of `x`, in radians.
+#[inline]
+pub(crate) fn tan(x: f32) -> f32 {
+    #[cfg(any(test, feature = "std"))]
+    return f32::tan(x);
+    #[cfg(not(any(test, feature = "std")))]
+    return F32Ext::tan(x);
+}
@@ -0,0 +1,170 @@
+use crate::Octree;
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+use core::{fmt::Debug, hash::Hash, num::NonZeroU32};
+
+/// The largest depth of an arbitrary `Octree`, so that its dimension is at most 32.
+const MAX_DEPTH: u32 = 5;
+
+/// The largest number of operations applied to an arbitrary `Octree`.
+const MAX_OPERATIONS: u32 = 64;
+
+/// Returns an arbitrary position within an `Octree` of the given dimension.
+fn position(u: &mut Unstructured<'_>, dimension: u32) -> Result<[u32; 3]> {
+    Ok([
+        u.int_in_range(0..=dimension - 1)?,
+        u.int_in_range(0..=dimension - 1)?,
+        u.int_in_range(0..=dimension - 1)?,
+    ])
+}
+
+/// Returns an arbitrary sphere centred within an `Octree` of the given dimension, which may extend outside it.
+fn sphere(u: &mut Unstructured<'_>, dimension: u32) -> Result<([f32; 3], f32)> {
+    let center = position(u, dimension)?.map(|c| c as f32 + 0.5);
+    let radius = u.int_in_range(0..=dimension)? as f32 / 2.0;
+
+    Ok((center, radius))
+}
+
+impl<'a, T> Arbitrary<'a> for Octree<T>
+where
+    T: Arbitrary<'a> + Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Builds an `Octree` of dimension up to 32 with an arbitrary background, then applies up to 64 arbitrary
+    /// operations to it: inserting and clearing voxels, lines and spheres, changing the LOD level and
+    /// recording LOD detail. The same input always builds the same `Octree`.
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let dimension = 1 << u.int_in_range(0..=MAX_DEPTH)?;
+        let mut octree = Octree::new_with_background(NonZeroU32::new(dimension).unwrap(), T::arbitrary(u)?).unwrap();
+
+        for _ in 0..u.int_in_range(0..=MAX_OPERATIONS)? {
+            // Every operation is given positions within the `Octree`, so none of them fail.
+            match u.int_in_range(0..=6)? {
+                0 => octree.insert(position(u, dimension)?, T::arbitrary(u)?).unwrap(),
+                1 => octree.clear_at(position(u, dimension)?).unwrap(),
+                2 => {
+                    let (a, b) = (position(u, dimension)?, position(u, dimension)?);
+                    let thickness = u.int_in_range(0..=2)?;
+                    octree.insert_line(a, b, thickness, T::arbitrary(u)?).unwrap();
+                }
+                3 => {
+                    let (center, radius) = sphere(u, dimension)?;
+                    octree.insert_sphere(center, radius, T::arbitrary(u)?, true).unwrap();
+                }
+                4 => {
+                    let (center, radius) = sphere(u, dimension)?;
+                    octree.clear_sphere(center, radius);
+                }
+                5 => {
+                    let level = u.int_in_range(1..=octree.max_lod_level().max(1))?;
+                    octree.set_lod_level(level).unwrap();
+                }
+                _ => octree.enable_lod_journal(),
+            }
+        }
+
+        Ok(octree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{node::Node, test_utils::XorShift, Octree};
+
+    use alloc::vec::Vec;
+    use arbitrary::{Arbitrary, Unstructured};
+    use std::io::Cursor;
+
+    /// Builds arbitrary `Octree`s from random inputs of up to 1024 bytes, passing each to `check`.
+    fn for_arbitrary_trees(seed: u64, mut check: impl FnMut(&[u8], &Octree<u8>)) {
+        let mut rng = XorShift::new(seed);
+
+        for _ in 0..200 {
+            let bytes = (0..rng.below(1024)).map(|_| rng.next_u32() as u8).collect::<Vec<_>>();
+            let octree = Octree::<u8>::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+            check(&bytes, &octree);
+        }
+    }
+
+    /// Asserts that two `Octree`s hold the same data at every position.
+    fn assert_same_voxels(a: &Octree<u8>, b: &Octree<u8>) {
+        assert_eq!(a.dimension(), b.dimension());
+        assert_eq!(a.background(), b.background());
+
+        let dimension = a.dimension();
+        for i in 0..dimension.pow(3) {
+            let position = [i % dimension, i / dimension % dimension, i / dimension / dimension];
+            assert_eq!(a.get(position), b.get(position), "at {:?}", position);
+        }
+    }
+
+    /// Simplifies every `Node` below and including `node`, children first.
+    fn simplify_all(node: &mut Node<u8>) {
+        for child in node.children_mut() {
+            simplify_all(child);
+        }
+
+        node.simplify();
+    }
+
+    #[test]
+    fn same_input_builds_same_tree() {
+        for_arbitrary_trees(0xa4b1, |bytes, octree| {
+            let again = Octree::<u8>::arbitrary(&mut Unstructured::new(bytes)).unwrap();
+            assert_eq!(again.lod_level(), octree.lod_level());
+            assert_eq!(again.to_bytes(), octree.to_bytes());
+        });
+    }
+
+    #[test]
+    fn encodings_round_trip() {
+        for_arbitrary_trees(0xa4b2, |_, octree| {
+            let copy = Octree::<u8>::from_bytes(&octree.to_bytes()).unwrap();
+            assert_eq!(copy.lod_level(), octree.lod_level());
+            assert_same_voxels(&copy, octree);
+
+            let mut bytes = Vec::new();
+            octree.encode_to(&mut bytes).unwrap();
+            assert_same_voxels(&Octree::decode_from(&mut Cursor::new(bytes)).unwrap(), octree);
+
+            let paged = octree.encode_paged_dag(256);
+            let paged = Octree::<u8>::open_paged(paged.as_bytes()).unwrap();
+            assert_same_voxels(&paged.to_octree().unwrap(), octree);
+        });
+    }
+
+    #[test]
+    fn simplifying_keeps_voxels() {
+        for_arbitrary_trees(0xa4b3, |bytes, octree| {
+            let mut simplified = Octree::<u8>::arbitrary(&mut Unstructured::new(bytes)).unwrap();
+            simplify_all(simplified.root_mut());
+            assert_same_voxels(&simplified, octree);
+        });
+    }
+
+    #[test]
+    fn simplify_matches_simplifying_every_node() {
+        for_arbitrary_trees(0xa4b5, |bytes, _| {
+            let mut simplified = Octree::<u8>::arbitrary(&mut Unstructured::new(bytes)).unwrap();
+            simplify_all(simplified.root_mut());
+
+            let mut octree = Octree::<u8>::arbitrary(&mut Unstructured::new(bytes)).unwrap();
+            octree.simplify();
+            assert_eq!(format!("{:#?}", octree.root()), format!("{:#?}", simplified.root()));
+        });
+    }
+
+    #[test]
+    fn inputs_cover_every_dimension_and_lod_level() {
+        let mut seen = Vec::new();
+        for_arbitrary_trees(0xa4b4, |_, octree| {
+            assert!(octree.dimension().is_power_of_two() && octree.dimension() <= 32);
+            seen.push((octree.dimension(), octree.lod_level()));
+        });
+
+        for depth in 0..=5 {
+            assert!(seen.iter().any(|(dimension, _)| *dimension == 1 << depth));
+        }
+        assert!(seen.iter().any(|(_, lod_level)| *lod_level > 1));
+    }
+}
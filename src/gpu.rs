@@ -0,0 +1,294 @@
+use crate::{
+    node::{octant_index, OCTREE_CHILDREN},
+    Octree, Vector3,
+};
+
+use alloc::{vec, vec::Vec};
+use core::{convert::TryFrom, fmt::Debug, hash::Hash};
+
+/// Data which can be uploaded to a GPU as a tightly packed array, as held in the values of a [`GpuOctree`].
+pub trait GpuValue: Copy {
+    /// The number of bytes taken by each value.
+    const SIZE: usize;
+
+    /// Appends the little-endian bytes of the value to `bytes`.
+    fn write_bytes(&self, bytes: &mut Vec<u8>);
+}
+
+macro_rules! impl_gpu_value {
+    ($($ty:ty),*) => {
+        $(
+            impl GpuValue for $ty {
+                const SIZE: usize = core::mem::size_of::<$ty>();
+
+                fn write_bytes(&self, bytes: &mut Vec<u8>) {
+                    bytes.extend_from_slice(&self.to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_gpu_value!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+/// The words of each descriptor in [`GpuOctree::descriptors`].
+const WORDS: usize = 2;
+
+/// An `Octree` flattened into contiguous buffers for traversal on a GPU, in the layout of Efficient Sparse
+/// Voxel Octrees.
+///
+/// Every `Node` takes a slot, numbered breadth-first from the root at slot 0, so that the children of each
+/// internal `Node` take consecutive slots in octant order, with no slot for an octant which has never been
+/// written. Slot `i` is described by two words of [`GpuOctree::descriptors`]:
+///
+/// - `descriptors[2 * i]`: the slot of the first child.
+/// - `descriptors[2 * i + 1]`: the valid mask in bits 8 to 15, with a bit set for each octant holding a child,
+///   and the leaf mask in bits 0 to 7, with a bit set for each child which is a leaf.
+///
+/// The child in an octant takes the slot of the first child plus the number of valid octants before it. The
+/// data of a leaf is in [`GpuOctree::values`] at its slot, and the values of internal `Node`s hold the
+/// background, which empty octants read as; the descriptors of leaves are unused. A root which is a leaf is
+/// stored as an internal `Node` whose eight children are leaves holding its data, so that slot 0 always holds
+/// an internal `Node`.
+///
+/// Created by [`Octree::to_gpu_buffer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GpuOctree<T> {
+    dimension: u32,
+    descriptors: Vec<u32>,
+    values: Vec<T>,
+}
+
+impl<T> GpuOctree<T>
+where
+    T: GpuValue,
+{
+    /// Returns the dimension of the root node.
+    pub fn dimension(&self) -> u32 {
+        self.dimension
+    }
+
+    /// Returns the descriptors, two words per slot.
+    pub fn descriptors(&self) -> &[u32] {
+        &self.descriptors
+    }
+
+    /// Returns the values, one per slot.
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    /// Returns the little-endian bytes of the descriptors, for uploading.
+    pub fn descriptor_bytes(&self) -> Vec<u8> {
+        self.descriptors.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    /// Returns the little-endian bytes of the values, `T::SIZE` bytes each, for uploading.
+    pub fn value_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.values.len() * T::SIZE);
+        for value in self.values.iter() {
+            value.write_bytes(&mut bytes);
+        }
+
+        bytes
+    }
+
+    /// Retrieves data of type `T` from the given position by walking the descriptors down from the root, as a
+    /// shader would, returning the same as [`Octree::get`] on the `Octree` the buffers were made from.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([9, 8, 31], 1).unwrap();
+    ///
+    /// let gpu = octree.to_gpu_buffer();
+    /// assert_eq!(gpu.get([9, 8, 31]), Some(&1));
+    /// assert_eq!(gpu.get([20, 1, 12]), Some(&0));
+    /// assert_eq!(gpu.get([32, 1, 12]), None);
+    /// ```
+    pub fn get(&self, position: impl Into<Vector3<u32>>) -> Option<&T> {
+        let position = <[u32; 3]>::from(position.into());
+        if position.iter().any(|c| *c >= self.dimension) {
+            return None;
+        }
+
+        let (mut slot, mut dimension) = (0, self.dimension);
+
+        loop {
+            let first = self.descriptors[WORDS * slot];
+            let masks = self.descriptors[WORDS * slot + 1];
+            let (valid, leaf) = (masks >> 8 & 0xff, masks & 0xff);
+
+            let half = dimension / 2;
+            let bit = 1 << octant_index(position, half);
+
+            if valid & bit == 0 {
+                return Some(&self.values[0]);
+            }
+
+            let child = (first + (valid & (bit - 1)).count_ones()) as usize;
+            if leaf & bit != 0 {
+                return Some(&self.values[child]);
+            }
+
+            slot = child;
+            dimension = half;
+        }
+    }
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + GpuValue,
+{
+    /// Flattens the `Octree` into buffers of descriptors and values for traversal on a GPU, as described by
+    /// [`GpuOctree`]. Subtrees held in storage and not loaded are left out, as octants never written.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(4).unwrap()).unwrap();
+    /// octree.insert([3, 0, 0], 1).unwrap();
+    ///
+    /// // The root has a child in octant 1, which has a leaf in octant 1.
+    /// let gpu = octree.to_gpu_buffer();
+    /// assert_eq!(gpu.descriptors(), &[1, 0b10 << 8, 2, 0b10 << 8 | 0b10, 0, 0]);
+    /// assert_eq!(gpu.values(), &[0, 0, 1]);
+    /// ```
+    pub fn to_gpu_buffer(&self) -> GpuOctree<T> {
+        let root = self.root();
+        let mut slots = vec![root];
+        let mut gpu = GpuOctree {
+            dimension: self.dimension(),
+            descriptors: Vec::new(),
+            values: Vec::new(),
+        };
+
+        // Slots are numbered in the order `Node`s are pushed, so walking them in order is breadth-first.
+        let mut slot = 0;
+        while let Some(node) = slots.get(slot).copied() {
+            match node.leaf_data() {
+                Some(data) if slot != 0 => {
+                    gpu.descriptors.extend([0; WORDS]);
+                    gpu.values.push(*data);
+                }
+                _ => {
+                    let first = u32::try_from(slots.len()).expect("slot index overflow");
+                    let mut masks = 0;
+
+                    for octant in 0..OCTREE_CHILDREN {
+                        let child = match node.is_leaf() {
+                            true => Some(node),
+                            false => node.child(octant),
+                        };
+
+                        if let Some(child) = child {
+                            masks |= 1 << (octant + 8) | (child.is_leaf() as u32) << octant;
+                            slots.push(child);
+                        }
+                    }
+
+                    gpu.descriptors.extend([first, masks]);
+                    gpu.values.push(self.background());
+                }
+            }
+
+            slot += 1;
+        }
+
+        gpu
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WORDS;
+    use crate::{test_utils::XorShift, NodeRef, Octree};
+
+    use alloc::vec::Vec;
+    use core::num::NonZeroU32;
+
+    /// Returns the `Node`s of an `Octree` as they would be read back from a `GpuOctree`, breadth-first.
+    fn breadth_first(root: NodeRef<'_, u8>) -> Vec<NodeRef<'_, u8>> {
+        let mut nodes = vec![root];
+        let mut next = 0;
+        while let Some(node) = nodes.get(next).copied() {
+            nodes.extend(node.children());
+            next += 1;
+        }
+
+        nodes
+    }
+
+    #[test]
+    fn buffers_read_as_the_octree() {
+        let mut rng = XorShift::new(0x69a0);
+
+        for dimension in [1, 2, 8, 32] {
+            for _ in 0..5 {
+                let inserts = rng.below(dimension * dimension * 4);
+                let mut octree = rng.octree(dimension, inserts, 4);
+                for _ in 0..rng.below(dimension * 4) {
+                    octree.clear_at(rng.position(dimension)).unwrap();
+                }
+
+                let gpu = octree.to_gpu_buffer();
+                for x in 0..dimension {
+                    for y in 0..dimension {
+                        for z in 0..dimension {
+                            assert_eq!(gpu.get([x, y, z]), octree.get([x, y, z]), "at {:?}", [x, y, z]);
+                        }
+                    }
+                }
+                assert_eq!(gpu.get([dimension, 0, 0]), None);
+            }
+        }
+    }
+
+    #[test]
+    fn slots_are_breadth_first() {
+        let octree = XorShift::new(0x69a1).octree(32, 500, 4);
+        let gpu = octree.to_gpu_buffer();
+
+        let nodes = breadth_first(octree.root());
+        assert_eq!(gpu.values().len(), nodes.len());
+        assert_eq!(gpu.descriptors().len(), nodes.len() * WORDS);
+
+        let mut next = 1;
+        for (slot, node) in nodes.iter().enumerate() {
+            let [first, masks] = [0, 1].map(|word| gpu.descriptors()[slot * WORDS + word]);
+
+            match node.leaf_data() {
+                Some(data) => assert_eq!(gpu.values()[slot], *data),
+                None => {
+                    // The children of each internal `Node` follow those of the `Node` before it.
+                    assert_eq!(first, next);
+                    assert_eq!(masks >> 8, node.occupancy() as u32);
+                    next += (masks >> 8).count_ones();
+                }
+            }
+        }
+        assert_eq!(next as usize, nodes.len());
+
+        assert_eq!(gpu.descriptor_bytes().len(), gpu.descriptors().len() * 4);
+        assert_eq!(gpu.value_bytes(), gpu.values());
+    }
+
+    #[test]
+    fn leaf_roots_are_split() {
+        for dimension in [1, 4] {
+            let octree = Octree::new_with_background(NonZeroU32::new(dimension).unwrap(), 3_u16).unwrap();
+            let gpu = octree.to_gpu_buffer();
+
+            assert_eq!(gpu.descriptors()[..WORDS], [1, 0xffff]);
+            assert_eq!(gpu.values()[1..], [3; 8]);
+            assert_eq!(gpu.get([0, 0, 0]), Some(&3));
+            assert_eq!(gpu.get([dimension - 1; 3]), Some(&3));
+        }
+    }
+}
@@ -0,0 +1,301 @@
+use crate::{node::OCTREE_CHILDREN, NodeRef, Octree};
+
+use alloc::vec::Vec;
+use core::{
+    fmt::Debug,
+    hash::{Hash, Hasher},
+};
+
+/// One step of the canonical form of an `Octree`, listing nodes in pre-order.
+#[derive(Hash)]
+enum Token<T> {
+    /// A cube holding the same data throughout.
+    Uniform(T),
+    /// A cube holding differing data, followed by the tokens of its eight octants.
+    Mixed,
+}
+
+/// Appends the canonical tokens of `node` to `tokens`, or returns its data if it is uniform.
+///
+/// Unwritten space is uniform `background`, and a node whose octants are all uniform with the same data is
+/// uniform itself, however it is stored, so that voxel-equivalent `Octree`s have identical tokens.
+fn canonical<T>(node: Option<NodeRef<'_, T>>, background: T, tokens: &mut Vec<Token<T>>) -> Option<T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    let node = match node {
+        Some(node) => node,
+        None => return Some(background),
+    };
+
+    if let Some(data) = node.leaf_data() {
+        return Some(*data);
+    }
+
+    let start = tokens.len();
+    tokens.push(Token::Mixed);
+
+    let mut uniform = None;
+    let mut mixed = false;
+
+    for child in (0..OCTREE_CHILDREN).map(|octant| node.child(octant)) {
+        match canonical(child, background, tokens) {
+            Some(data) => {
+                mixed |= matches!(uniform, Some(uniform) if uniform != data);
+                uniform = Some(data);
+                tokens.push(Token::Uniform(data));
+            }
+            None => mixed = true,
+        }
+    }
+
+    if mixed {
+        None
+    } else {
+        tokens.truncate(start);
+        uniform
+    }
+}
+
+/// The 64-bit FNV-1a hash, which is deterministic across runs and platforms, unlike the hashers of
+/// `hashbrown`. Integers are hashed as their little-endian bytes, and `usize` and `isize` as 64 bits, so that
+/// derived `Hash` implementations, which hash the lengths of slices and the discriminants of enums as `usize`
+/// and `isize`, hash the same whatever the byte order and pointer width.
+pub(crate) struct Fnv1a(u64);
+
+/// Implements the `Hasher` methods writing integers of the given types, as little-endian bytes.
+macro_rules! write_le {
+    ($($name:ident: $ty:ty),*) => {
+        $(
+            fn $name(&mut self, i: $ty) {
+                self.write(&i.to_le_bytes());
+            }
+        )*
+    };
+}
+
+impl Fnv1a {
+    pub(crate) fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    write_le!(
+        write_u16: u16,
+        write_u32: u32,
+        write_u64: u64,
+        write_u128: u128,
+        write_i16: i16,
+        write_i32: i32,
+        write_i64: i64,
+        write_i128: i128
+    );
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_i64(i as i64);
+    }
+}
+
+/// The CRC-32 of each byte value, for the reflected polynomial `0xedb8_8320`.
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+};
+
+/// The CRC-32 checksum used by zlib and PNG, computed a slice at a time.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Crc32(u32);
+
+impl Crc32 {
+    pub(crate) fn new() -> Self {
+        Self(0xffff_ffff)
+    }
+
+    pub(crate) fn update(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = CRC32_TABLE[((self.0 ^ *byte as u32) & 0xff) as usize] ^ (self.0 >> 8);
+        }
+    }
+
+    pub(crate) fn finish(&self) -> u32 {
+        !self.0
+    }
+}
+
+/// Hashes the voxel contents of the `Octree`, whatever its internal structure, consistently with its
+/// `PartialEq` implementation.
+impl<T> Hash for Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut tokens = Vec::new();
+        if let Some(data) = canonical(Some(self.root()), self.background(), &mut tokens) {
+            tokens.push(Token::Uniform(data));
+        }
+
+        self.dimension().hash(state);
+        tokens.hash(state);
+    }
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Returns a hash of the voxel contents of the `Octree`, whatever its internal structure.
+    ///
+    /// Voxel-equivalent `Octree`s, as decided by [`Octree::equivalent`], hash equally, as does an `Octree`
+    /// rebuilt from the same contents in another run, provided `T` hashes deterministically. The hash is
+    /// computed over a simplified form of the `Octree` without modifying it. It is not a cryptographic hash,
+    /// and must not be relied upon where collisions could be crafted deliberately.
+    ///
+    /// Only the contents held in memory are hashed, so an `Octree` with subtrees held in storage hashes as if
+    /// they had never been written, unlike the same `Octree` once they are loaded.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut a = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// a.insert([0, 0, 0], 1).unwrap();
+    ///
+    /// let mut b = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// b.insert([0, 0, 0], 1).unwrap();
+    /// b.insert([5, 5, 5], 2).unwrap();
+    /// b.clear_at([5, 5, 5]).unwrap();
+    ///
+    /// assert_eq!(a.content_hash(), b.content_hash());
+    ///
+    /// b.insert([0, 0, 0], 2).unwrap();
+    /// assert_ne!(a.content_hash(), b.content_hash());
+    /// ```
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = Fnv1a::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Crc32, Fnv1a};
+    use crate::{test_utils::XorShift, Octree};
+
+    use alloc::vec::Vec;
+    use core::{hash::Hasher, num::NonZeroU32};
+
+    #[test]
+    fn equivalent_trees_hash_equally() {
+        let mut rng = XorShift::new(0x4a54);
+
+        for _ in 0..20 {
+            let seed = rng.next_u32() as u64;
+            let a = XorShift::new(seed).octree(16, 300, 3);
+            let mut b = XorShift::new(seed).octree(16, 300, 3);
+
+            let mut stack = vec![(b.root_mut(), 16)];
+            while let Some((node, dimension)) = stack.pop() {
+                if dimension > 1 {
+                    node.split(0);
+                    stack.extend(node.children_mut().map(|child| (child, dimension / 2)));
+                }
+            }
+
+            assert_eq!(a.content_hash(), b.content_hash());
+
+            let position = rng.position(16);
+            let data = *b.get(position).unwrap_or(&0);
+            b.insert(position, data + 1).unwrap();
+            assert_ne!(a.content_hash(), b.content_hash());
+        }
+    }
+
+    #[test]
+    fn hashing_leaves_tree_unchanged() {
+        let mut rng = XorShift::new(0x4a55);
+        let octree = rng.octree(16, 300, 3);
+
+        let snapshot = |octree: &Octree<u8>| {
+            octree
+                .query_region([0; 3], [16; 3])
+                .map(|(min, dimensions, data)| (min, dimensions, data.copied()))
+                .collect::<Vec<_>>()
+        };
+
+        let before = snapshot(&octree);
+        let hash = octree.content_hash();
+
+        assert_eq!(snapshot(&octree), before);
+        assert_eq!(octree.content_hash(), hash);
+    }
+
+    #[test]
+    fn crc32_matches_reference() {
+        let mut crc = Crc32::new();
+        crc.update(b"1234");
+        crc.update(b"56789");
+
+        assert_eq!(crc.finish(), 0xcbf4_3926);
+        assert_eq!(Crc32::new().finish(), 0);
+    }
+
+    #[test]
+    fn hashes_do_not_depend_on_the_platform() {
+        let mut native = Fnv1a::new();
+        native.write_usize(3);
+        native.write_i32(-2);
+        let mut bytes = Fnv1a::new();
+        bytes.write(&[3, 0, 0, 0, 0, 0, 0, 0, 0xfe, 0xff, 0xff, 0xff]);
+        assert_eq!(native.finish(), bytes.finish());
+
+        // Pinned, so that a change to the bytes hashed on any platform is caught.
+        let mut octree = Octree::<u16>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        octree.insert([1, 2, 3], 300).unwrap();
+        assert_eq!(octree.content_hash(), 0xef86_df1a_0a03_5806);
+    }
+
+    #[test]
+    fn dimension_affects_hash() {
+        let small = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        let large = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+
+        assert_ne!(small.content_hash(), large.content_hash());
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{Face, NodeRef, Octree};
+
+use alloc::{vec, vec::Vec};
+use core::{fmt::Debug, hash::Hash};
+
+/// Collects, for each column of a heightfield, the first voxel not holding the background seen from a face.
+struct Heightfield<T> {
+    face: Face,
+    dimension: u32,
+    background: T,
+    cells: Vec<Option<u32>>,
+}
+
+impl<T> Heightfield<T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    /// Visits `node` and its descendants front-to-back, as seen from the face, so that the first leaf
+    /// reaching a column is the one nearest the face.
+    fn visit(&mut self, node: NodeRef<'_, T>) {
+        if let Some(data) = node.leaf_data() {
+            if *data != self.background {
+                self.fill(node.min_position().into(), node.dimension());
+            }
+
+            return;
+        }
+
+        let axis = self.face.axis();
+        let min: [u32; 3] = node.min_position().into();
+        let middle = min[axis] + node.dimension() / 2;
+
+        // Visit the half of the node nearer the face first.
+        for near in [true, false].iter() {
+            for child in node.children() {
+                let child_min: [u32; 3] = child.min_position().into();
+                if (child_min[axis] >= middle) == (*near == self.face.is_positive()) {
+                    self.visit(child);
+                }
+            }
+        }
+    }
+
+    /// Sets the empty columns covered by a non-empty leaf to its layer nearest the face.
+    fn fill(&mut self, min: [u32; 3], dimension: u32) {
+        let axis = self.face.axis();
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+
+        let layer = if self.face.is_positive() {
+            min[axis] + dimension - 1
+        } else {
+            min[axis]
+        };
+
+        for row in min[v]..min[v] + dimension {
+            let start = (row * self.dimension + min[u]) as usize;
+
+            for cell in self.cells[start..start + dimension as usize].iter_mut() {
+                cell.get_or_insert(layer);
+            }
+        }
+    }
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Returns, for each column of the `Octree` perpendicular to the given face, the position along the
+    /// column of the first non-empty voxel seen from that face.
+    ///
+    /// With [`Face::Top`], this is the highest `z` holding a non-empty voxel in each `(x, y)` column. The
+    /// grid has `dimension * dimension` cells in row-major order, indexed by the two other axes in cyclic
+    /// order after the face's axis: the cell of the column at `(u, v)` is `v * dimension + u`, so for
+    /// [`Face::Top`] the column at `(x, y)` is at `y * dimension + x`. Columns holding only empty voxels
+    /// are `None`. Leaves are written to the grid as whole rectangles, front-to-back, rather than probing
+    /// each column.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Face, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(4).unwrap()).unwrap();
+    /// octree.insert([1, 2, 0], 1).unwrap();
+    /// octree.insert([1, 2, 3], 1).unwrap();
+    ///
+    /// let heights = octree.heightfield(Face::Top);
+    /// assert_eq!(heights[2 * 4 + 1], Some(3));
+    /// assert_eq!(heights[0], None);
+    ///
+    /// let depths = octree.heightfield(Face::Base);
+    /// assert_eq!(depths[2 * 4 + 1], Some(0));
+    /// ```
+    pub fn heightfield(&self, face: Face) -> Vec<Option<u32>> {
+        let dimension = self.dimension();
+
+        let mut heightfield = Heightfield {
+            face,
+            dimension,
+            background: self.background(),
+            cells: vec![None; (dimension as usize).pow(2)],
+        };
+
+        heightfield.visit(self.root());
+        heightfield.cells
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_utils::XorShift, Face, Octree};
+
+    use alloc::vec::Vec;
+    use core::num::NonZeroU32;
+
+    /// Scans each column of the `Octree` from the given face, one voxel at a time.
+    fn reference(octree: &Octree<u8>, face: Face) -> Vec<Option<u32>> {
+        let dimension = octree.dimension();
+        let axis = face.axis();
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+
+        let mut cells = Vec::new();
+        for row in 0..dimension {
+            for column in 0..dimension {
+                let mut layers = (0..dimension).collect::<Vec<_>>();
+                if face.is_positive() {
+                    layers.reverse();
+                }
+
+                cells.push(layers.into_iter().find(|layer| {
+                    let mut position = [0; 3];
+                    position[axis] = *layer;
+                    position[u] = column;
+                    position[v] = row;
+                    matches!(octree.get(position), Some(data) if *data != 0)
+                }));
+            }
+        }
+
+        cells
+    }
+
+    #[test]
+    fn heightfield_matches_column_scan() {
+        let mut rng = XorShift::new(0x4e16);
+
+        for _ in 0..20 {
+            let octree = rng.octree(16, 300, 3);
+
+            for face in Face::ALL.iter() {
+                assert_eq!(octree.heightfield(*face), reference(&octree, *face));
+            }
+        }
+    }
+
+    #[test]
+    fn empty_columns_and_ground_level() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        assert!(octree.heightfield(Face::Top).iter().all(Option::is_none));
+
+        // Columns whose only content lies at the base of the `Octree` have a height of zero.
+        for x in 0..4 {
+            for y in 0..4 {
+                octree.insert([x, y, 0], 1).unwrap();
+            }
+        }
+        octree.insert([2, 5, 0], 1).unwrap();
+        octree.insert([2, 2, 0], 0).unwrap();
+
+        let heights = octree.heightfield(Face::Top);
+        assert_eq!(heights, reference(&octree, Face::Top));
+        assert_eq!(heights[8 + 1], Some(0));
+        assert_eq!(heights[5 * 8 + 2], Some(0));
+        assert_eq!(heights[2 * 8 + 2], None);
+        assert_eq!(heights[7 * 8 + 7], None);
+    }
+}
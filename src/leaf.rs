@@ -0,0 +1,408 @@
+use crate::{node::majority, NodeRef, Octree, Vector3};
+
+use alloc::{vec, vec::Vec};
+// The derive of `Reflect` collects with `FromIterator`, which is not in the prelude before edition 2021.
+#[cfg(feature = "bevy_reflect")]
+use core::iter::FromIterator;
+use core::{fmt::Debug, hash::Hash};
+#[cfg(feature = "rayon")]
+use rayon::iter::{self, ParallelIterator};
+
+/// The reducer of [`Octree::iter_leaves_at_lod`].
+type Majority<T> = fn(&[(T, u64)]) -> T;
+
+/// Describes a single leaf of an `Octree`: the cube of voxels it covers and the data stored there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "bevy_reflect",
+    derive(bevy_reflect::Reflect),
+    reflect(Clone, PartialEq, where T: Clone + PartialEq)
+)]
+pub struct LeafInfo<T> {
+    /// The position of the corner of the leaf closest to the origin.
+    pub min: [u32; 3],
+    /// The edge length of the leaf, in voxels.
+    pub dimension: u32,
+    /// The data shared by every voxel covered by the leaf.
+    pub data: T,
+}
+
+impl<T> LeafInfo<T> {
+    /// Returns whether the leaf covers the given position.
+    pub fn contains(&self, position: impl Into<Vector3<u32>>) -> bool {
+        let position = <[u32; 3]>::from(position.into());
+        (0..3).all(|i| position[i] >= self.min[i] && position[i] - self.min[i] < self.dimension)
+    }
+}
+
+impl<T> LeafInfo<T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    /// Creates a `LeafInfo<T>` describing the given leaf `Node`, if it is a leaf.
+    pub(crate) fn from_node(node: NodeRef<'_, T>) -> Option<Self> {
+        node.leaf_data().map(|data| Self {
+            min: node.min_position().into(),
+            dimension: node.dimension(),
+            data: *data,
+        })
+    }
+}
+
+/// An iterator over the non-empty leaves of an `Octree` as they would be at a coarser LOD level, in octant
+/// order.
+///
+/// Nodes no larger than the leaf dimension of that level are yielded whole, holding the data they would be
+/// coarsened to. Created by [`Octree::iter_leaves_at_lod`] and [`Octree::iter_leaves_at_lod_with`].
+pub struct LodLeaves<'a, T, F>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+    F: Fn(&[(T, u64)]) -> T,
+{
+    dimension: u32,
+    background: T,
+    reduce: F,
+    stack: Vec<NodeRef<'a, T>>,
+}
+
+impl<'a, T, F> Iterator for LodLeaves<'a, T, F>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+    F: Fn(&[(T, u64)]) -> T,
+{
+    type Item = LeafInfo<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.stack.pop() {
+            if node.is_leaf() || node.dimension() <= self.dimension {
+                let data = node.coarse_data(self.background, &self.reduce);
+
+                if data != self.background {
+                    return Some(LeafInfo {
+                        min: node.min_position().into(),
+                        dimension: node.dimension(),
+                        data,
+                    });
+                }
+            } else {
+                // Push in reverse, so that octants are yielded in order.
+                self.stack.extend(
+                    node.octants()
+                        .filter_map(|(_, child)| child)
+                        .collect::<Vec<_>>()
+                        .into_iter()
+                        .rev(),
+                );
+            }
+        }
+
+        None
+    }
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Returns an iterator over the non-empty leaves of the `Octree`, in octant order, as by
+    /// [`Octree::iter_leaves_at_lod`] at level 0.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, LeafInfo, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([1, 0, 0], 2).unwrap();
+    ///
+    /// let leaves = octree.iter_leaves().collect::<Vec<_>>();
+    /// assert_eq!(leaves, vec![LeafInfo { min: [1, 0, 0], dimension: 1, data: 2 }]);
+    /// ```
+    pub fn iter_leaves(&self) -> LodLeaves<'_, T, Majority<T>> {
+        self.iter_leaves_at_lod(0)
+    }
+
+    /// Returns an iterator over the non-empty leaves of the `Octree` as they would be after calling
+    /// [`Octree::lod_down`] `level` times, without modifying the `Octree`.
+    ///
+    /// The `Octree` is only descended until nodes are `2^level` voxels across, and each node reached there
+    /// is yielded as a single leaf holding the data [`Octree::get_at_lod`] returns within it. Larger leaves
+    /// are yielded as they are, so neighbouring nodes holding the same data are not merged as
+    /// [`Octree::lod_down`] would. Levels beyond the coarsest one [`Octree::lod_down`] reaches are treated
+    /// as the coarsest. At level 0, every non-empty leaf of the `Octree` is yielded.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, LeafInfo, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([0, 0, 0], 1).unwrap();
+    /// octree.insert([0, 0, 1], 1).unwrap();
+    /// octree.insert([0, 1, 0], 1).unwrap();
+    /// octree.insert([0, 1, 1], 1).unwrap();
+    /// octree.insert([1, 0, 0], 2).unwrap();
+    ///
+    /// assert_eq!(octree.iter_leaves_at_lod(0).count(), 5);
+    ///
+    /// let leaves = octree.iter_leaves_at_lod(1).collect::<Vec<_>>();
+    /// assert_eq!(leaves, vec![LeafInfo { min: [0, 0, 0], dimension: 2, data: 1 }]);
+    /// ```
+    pub fn iter_leaves_at_lod(&self, level: u32) -> LodLeaves<'_, T, Majority<T>> {
+        self.iter_leaves_at_lod_with(level, majority)
+    }
+
+    /// Returns an iterator over the non-empty leaves of the `Octree` as they would be after calling
+    /// [`Octree::lod_down_with`] `level` times with `reduce`, without modifying the `Octree`.
+    ///
+    /// Behaves as [`Octree::iter_leaves_at_lod`], but each node reached at the leaf dimension of `level`
+    /// holds the data it would be coarsened to by `reduce`, one level at a time.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, LeafInfo, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([0, 0, 0], 0b01).unwrap();
+    /// octree.insert([1, 1, 1], 0b10).unwrap();
+    ///
+    /// let or = |leaves: &[(u8, u64)]| leaves.iter().fold(0, |flags, (data, _)| flags | data);
+    /// let leaves = octree.iter_leaves_at_lod_with(1, or).collect::<Vec<_>>();
+    /// assert_eq!(leaves, vec![LeafInfo { min: [0, 0, 0], dimension: 2, data: 0b11 }]);
+    /// ```
+    pub fn iter_leaves_at_lod_with<F>(&self, level: u32, reduce: F) -> LodLeaves<'_, T, F>
+    where
+        F: Fn(&[(T, u64)]) -> T,
+    {
+        LodLeaves {
+            dimension: 2_u32.pow(level.min(self.max_lod_level().saturating_sub(1))),
+            background: self.background(),
+            reduce,
+            stack: vec![self.root()],
+        }
+    }
+}
+
+/// Splits a contiguous run of `Node`s, in octant order, into two runs for [`Octree::par_iter_leaves`], if it
+/// holds more than one internal `Node`.
+///
+/// A lone internal `Node` is first replaced by its children, so that a tree with all its detail in one octant
+/// is descended to where the detail branches out rather than left to a single thread.
+#[cfg(feature = "rayon")]
+fn split_run<T>(mut nodes: Vec<NodeRef<'_, T>>) -> (Vec<NodeRef<'_, T>>, Option<Vec<NodeRef<'_, T>>>)
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    loop {
+        let mut internal = nodes.iter().enumerate().filter(|(_, node)| !node.is_leaf());
+
+        match (internal.next(), internal.count()) {
+            (None, _) => return (nodes, None),
+            (Some((index, node)), 0) => {
+                let node = *node;
+                nodes.splice(index..=index, node.children());
+            }
+            (Some(_), others) => {
+                // Half of the internal `Node`s, and the leaves among them, are left in each run.
+                let internal = 1 + others;
+                let (index, _) = nodes
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, node)| !node.is_leaf())
+                    .nth(internal / 2)
+                    .unwrap();
+
+                let rest = nodes.split_off(index);
+                return (nodes, Some(rest));
+            }
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + Send + Sync,
+{
+    /// Returns a parallel iterator over the non-empty leaves of the `Octree`, those
+    /// [`Octree::iter_leaves_at_lod`] yields at level 0, in no particular order.
+    ///
+    /// Work is split between threads by subtree, so each thread walks a contiguous region of the `Octree`. A
+    /// `Node` with a single internal child is descended before splitting, so a tree with most of its leaves in
+    /// one octant is still shared out.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// use rayon::iter::ParallelIterator;
+    ///
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([0, 0, 0], 1).unwrap();
+    /// octree.insert([31, 2, 7], 2).unwrap();
+    ///
+    /// let voxels = octree.par_iter_leaves().map(|leaf| leaf.dimension.pow(3)).sum::<u32>();
+    /// assert_eq!(voxels, 2);
+    /// ```
+    pub fn par_iter_leaves(&self) -> impl ParallelIterator<Item = LeafInfo<T>> + '_ {
+        let background = self.background();
+
+        iter::split(vec![self.root()], split_run).flat_map_iter(move |mut nodes| {
+            // Nodes are popped from the end, so that octants are walked in order.
+            nodes.reverse();
+
+            LodLeaves {
+                dimension: 1,
+                background,
+                reduce: majority as Majority<T>,
+                stack: nodes,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_utils::XorShift, LeafInfo, Octree};
+
+    use alloc::vec::Vec;
+    use core::num::NonZeroU32;
+
+    /// Writes each of the given leaves into an empty `Octree`, one voxel at a time.
+    fn rebuild(dimension: u32, leaves: impl Iterator<Item = LeafInfo<u8>>) -> Octree<u8> {
+        let mut octree = Octree::new(NonZeroU32::new(dimension).unwrap()).unwrap();
+
+        for leaf in leaves {
+            for x in 0..leaf.dimension {
+                for y in 0..leaf.dimension {
+                    for z in 0..leaf.dimension {
+                        let position = [x, y, z];
+                        octree
+                            .insert([0, 1, 2].map(|i| leaf.min[i] + position[i]), leaf.data)
+                            .unwrap();
+                    }
+                }
+            }
+        }
+
+        octree
+    }
+
+    #[test]
+    fn level_zero_yields_every_leaf() {
+        let mut rng = XorShift::new(0x1eaf);
+
+        for _ in 0..10 {
+            let octree = rng.octree(16, 500, 3);
+            let leaves = octree
+                .iter_leaves()
+                .map(|leaf| (leaf.min, [leaf.dimension; 3], leaf.data))
+                .collect::<Vec<_>>();
+            let spans = octree
+                .query_region_values([0; 3], [16; 3])
+                .map(|(min, dimensions, data)| (min, dimensions, *data))
+                .collect::<Vec<_>>();
+
+            assert_eq!(leaves, spans);
+        }
+    }
+
+    /// Builds an `Octree` holding all its voxels in the lowest octant of the lowest octant of its root.
+    #[cfg(feature = "rayon")]
+    fn lopsided(rng: &mut XorShift) -> Octree<u8> {
+        let mut octree = Octree::new(NonZeroU32::new(64).unwrap()).unwrap();
+        for _ in 0..2000 {
+            octree.insert(rng.position(16), 1 + rng.below(3) as u8).unwrap();
+        }
+
+        octree
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_leaves_match_sequential_ones() {
+        use rayon::iter::ParallelIterator;
+
+        let mut rng = XorShift::new(0x1eb2);
+        let mut octrees = (0..10).map(|_| rng.octree(32, 800, 3)).collect::<Vec<_>>();
+        octrees.push(lopsided(&mut rng));
+        octrees.push(Octree::new(NonZeroU32::new(1).unwrap()).unwrap());
+        octrees.push(rng.octree(1, 1, 3));
+
+        for octree in octrees {
+            let mut leaves = octree.par_iter_leaves().collect::<Vec<_>>();
+            let mut expected = octree.iter_leaves().collect::<Vec<_>>();
+
+            leaves.sort_by_key(|leaf| (leaf.min, leaf.dimension, leaf.data));
+            expected.sort_by_key(|leaf| (leaf.min, leaf.dimension, leaf.data));
+            assert_eq!(leaves, expected);
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn lopsided_trees_are_split_evenly() {
+        let octree = lopsided(&mut XorShift::new(0x1eb3));
+        let total = octree.iter_leaves().count();
+
+        // Split the whole tree three times over, as rayon does when eight threads want work.
+        let mut runs = vec![vec![octree.root()]];
+        for _ in 0..3 {
+            runs = runs
+                .into_iter()
+                .flat_map(|run| {
+                    let (run, rest) = super::split_run(run);
+                    core::iter::once(run).chain(rest)
+                })
+                .collect();
+        }
+
+        assert_eq!(runs.len(), 8);
+        for run in runs {
+            let leaves = super::LodLeaves {
+                dimension: 1,
+                background: 0,
+                reduce: crate::node::majority as super::Majority<u8>,
+                stack: run.into_iter().rev().collect(),
+            }
+            .count();
+
+            assert!(leaves < total / 4, "{} of {} leaves in one run", leaves, total);
+        }
+    }
+
+    #[test]
+    fn leaves_match_at_lod() {
+        let mut rng = XorShift::new(0x1eb0);
+
+        for _ in 0..10 {
+            let octree = rng.octree(16, 1500, 3);
+
+            for level in 0..6 {
+                let leaves = octree.iter_leaves_at_lod(level).collect::<Vec<_>>();
+                let block = 1 << level.min(3);
+                assert!(leaves.iter().all(|leaf| leaf.dimension >= block));
+
+                assert!(rebuild(16, leaves.into_iter()).equivalent(&octree.at_lod(level)));
+            }
+        }
+    }
+
+    #[test]
+    fn leaves_match_lod_down_with() {
+        let mut rng = XorShift::new(0x1eb1);
+        let or = |leaves: &[(u8, u64)]| leaves.iter().fold(0, |flags, (data, _)| flags | data);
+
+        for _ in 0..10 {
+            let seed = rng.next_u32() as u64;
+            let octree = XorShift::new(seed).octree(16, 300, 7);
+            let mut coarse = XorShift::new(seed).octree(16, 300, 7);
+
+            for level in 0..4 {
+                assert!(rebuild(16, octree.iter_leaves_at_lod_with(level, or)).equivalent(&coarse));
+                coarse.lod_down_with(or);
+            }
+        }
+    }
+}
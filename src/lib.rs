@@ -7,16 +7,115 @@ extern crate alloc;
 #[macro_use]
 extern crate std;
 
+#[macro_use]
+mod trace;
+
+#[cfg(feature = "rkyv")]
+mod archive;
+mod arena;
+mod boolean;
+mod brick;
+mod cache;
+mod codec;
+mod collision;
+#[cfg(any(test, feature = "std", feature = "no-std"))]
+mod cone;
+mod cow;
+#[cfg(feature = "std")]
+mod debug_json;
+mod dense;
 mod error;
+mod face;
+mod fill;
+mod flat;
+#[cfg(any(test, feature = "std", feature = "no-std"))]
+mod float;
+#[cfg(feature = "arbitrary")]
+mod fuzz;
+mod gpu;
+mod hash;
+mod heightfield;
+mod leaf;
+mod line;
+mod linear;
+#[cfg(any(test, feature = "std", feature = "no-std"))]
+mod marching;
+mod mesh;
+mod mip;
+#[cfg(any(test, feature = "std", feature = "no-std"))]
+mod nearest;
 mod node;
+mod occupancy;
 mod octree;
+mod overlay;
+mod paged;
+#[cfg(feature = "parry3d")]
+mod parry;
+#[cfg(feature = "bytemuck")]
+mod pod;
+mod query;
+mod raycast;
+#[cfg(feature = "bevy_reflect")]
+mod reflect;
+#[cfg(any(test, feature = "std", feature = "no-std"))]
+mod sample;
+#[cfg(feature = "serde")]
+mod serialize;
+mod slice;
+#[cfg(feature = "std")]
+mod stream;
+mod subtree;
 mod vector;
+#[cfg(feature = "std")]
+mod vox;
+#[cfg(any(test, feature = "std", feature = "no-std"))]
+mod voxelize;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+#[cfg(test)]
+mod test_utils;
 
+#[cfg(feature = "rkyv")]
+pub use archive::{ArchivedOctree, OctreeResolver};
+pub use arena::{ArenaLeaves, ArenaOctree};
+pub use codec::ValueCodec;
+pub use collision::{OverlappingLeaves, SweepHit};
+#[cfg(any(test, feature = "std", feature = "no-std"))]
+pub use cone::ConeIter;
+pub use cow::CowOctree;
+#[cfg(feature = "std")]
+pub use debug_json::DebugLimits;
 pub use error::Error;
+pub use face::{Axis, Face};
+pub use gpu::{GpuOctree, GpuValue};
+pub use leaf::{LeafInfo, LodLeaves};
+pub use linear::{LinearOctree, LinearRegion};
+pub use mesh::{ExposedFaces, MeshConfig, MeshData};
+pub use mip::LodError;
+pub use node::LodPolicy;
 pub use octree::Octree;
+pub use paged::{PagedBytes, PagedOctree};
+#[cfg(feature = "bytemuck")]
+pub use pod::FlatNode;
+pub use query::{RegionIter, SphereIter};
+pub use raycast::RaycastIter;
+#[cfg(feature = "bevy_reflect")]
+pub use reflect::OctreeSummary;
+#[cfg(any(test, feature = "std", feature = "no-std"))]
+pub use sample::Boundary;
+#[cfg(feature = "std")]
+pub use stream::{CompressionMode, DecodeError, EncodeError};
+pub use subtree::{NodePath, SourcedOctree, SubtreeSource};
+pub use vector::Vector3;
+#[cfg(feature = "std")]
+pub use vox::{VoxConfig, VoxError};
+#[cfg(any(test, feature = "std", feature = "no-std"))]
+pub use voxelize::FillMode;
+#[cfg(feature = "wasm")]
+pub use wasm::JsOctree;
 
-pub(crate) use node::Node;
-pub(crate) use vector::Vector3;
+pub(crate) use node::{Node, NodeRef};
 
 #[cfg(test)]
 mod tests {
@@ -59,46 +158,677 @@ mod tests {
         assert!(matches!(octree.get([0, 0, 0]), Some(1)));
     }
 
-    // #[test]
-    // fn test() {
-    //     let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
-        
-    //     octree.insert([0, 0, 0], 1).unwrap();
-    //     octree.insert([0, 0, 1], 1).unwrap();
-    //     octree.insert([0, 1, 0], 1).unwrap();
-    //     octree.insert([0, 1, 1], 1).unwrap();
-    //     octree.insert([1, 0, 0], 1).unwrap();
-    //     octree.insert([1, 0, 1], 1).unwrap();
-    //     octree.insert([1, 1, 0], 1).unwrap();
-    //     octree.insert([1, 1, 1], 1).unwrap();
-
-        // octree.insert([0, 0, 2], 2).unwrap();
-        // octree.insert([1, 0, 2], 2).unwrap();
-        // octree.insert([0, 0, 3], 2).unwrap();
-        // octree.insert([1, 0, 3], 2).unwrap();
-        // octree.insert([0, 1, 2], 2).unwrap();
-        // octree.insert([1, 1, 2], 2).unwrap();
-        // octree.insert([0, 1, 3], 2).unwrap();
-        // octree.insert([1, 1, 3], 2).unwrap();
-
-        // octree.insert([0, 2, 0], 3).unwrap();
-        // octree.insert([1, 2, 0], 3).unwrap();
-        // octree.insert([0, 2, 1], 3).unwrap();
-        // octree.insert([1, 2, 1], 3).unwrap();
-        // octree.insert([0, 3, 0], 3).unwrap();
-        // octree.insert([1, 3, 0], 3).unwrap();
-        // octree.insert([0, 3, 1], 3).unwrap();
-        // octree.insert([1, 3, 1], 3).unwrap();
-
-        // octree.insert([2, 0, 0], 4).unwrap();
-        // octree.insert([3, 0, 0], 4).unwrap();
-        // octree.insert([2, 0, 1], 4).unwrap();
-        // octree.insert([3, 0, 1], 4).unwrap();
-        // octree.insert([2, 1, 0], 4).unwrap();
-        // octree.insert([3, 1, 0], 4).unwrap();
-        // octree.insert([2, 1, 1], 4).unwrap();
-        // octree.insert([3, 1, 1], 4).unwrap();
-
-        // println!("{:?}", octree);
-    // }
+    #[test]
+    fn insert_into_large_simplified_leaf() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+        for x in 0..4 {
+            for y in 0..4 {
+                for z in 0..4 {
+                    octree.insert([x, y, z], 1).unwrap();
+                }
+            }
+        }
+
+        octree.insert([1, 2, 3], 2).unwrap();
+
+        assert!(matches!(octree.get([1, 2, 3]), Some(2)));
+        assert!(matches!(octree.get([0, 0, 0]), Some(1)));
+        assert!(matches!(octree.get([3, 3, 3]), Some(1)));
+        assert!(matches!(octree.get([1, 2, 2]), Some(1)));
+    }
+
+    #[test]
+    fn clear_at_large_simplified_leaf() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+        for x in 0..4 {
+            for y in 0..4 {
+                for z in 0..4 {
+                    octree.insert([x, y, z], 1).unwrap();
+                }
+            }
+        }
+
+        octree.clear_at([2, 2, 2]).unwrap();
+
+        assert!(matches!(octree.get([2, 2, 2]), Some(0)));
+        assert!(matches!(octree.get([2, 2, 3]), Some(1)));
+        assert!(matches!(octree.get([0, 0, 0]), Some(1)));
+    }
+
+    #[test]
+    fn insert_and_clear_in_deepest_tree() {
+        // Positions are held in a `u32`, so 2^31 is the largest dimension, and voxels lie 31 levels down.
+        let far = u32::MAX >> 1;
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(1 << 31).unwrap()).unwrap();
+
+        octree.insert([0, 0, 0], 1).unwrap();
+        octree.insert([far, far, far], 2).unwrap();
+        octree.insert([far, 0, far / 2], 3).unwrap();
+
+        assert!(matches!(octree.get([0, 0, 0]), Some(1)));
+        assert!(matches!(octree.get([far, far, far]), Some(2)));
+        assert!(matches!(octree.get([far, 0, far / 2]), Some(3)));
+        assert_eq!(octree.get([far - 1, far, far]), Some(&0));
+
+        // Filling the block of 2*2*2 voxels around a voxel simplifies it into a single leaf.
+        for i in 0..8 {
+            octree
+                .insert([far - (i & 1), far - (i >> 1 & 1), far - (i >> 2 & 1)], 2)
+                .unwrap();
+        }
+        let leaf = octree.iter_leaves().find(|leaf| leaf.data == 2).unwrap();
+        assert_eq!(leaf.dimension, 2);
+
+        octree.clear_at([far, far, far]).unwrap();
+        octree.clear_at([0, 0, 0]).unwrap();
+
+        assert!(matches!(octree.get([far, far, far]), Some(0)));
+        assert!(matches!(octree.get([far - 1, far, far]), Some(2)));
+        assert!(matches!(octree.get([0, 0, 0]), Some(0)));
+        assert!(matches!(octree.get([far, 0, far / 2]), Some(3)));
+    }
+
+    #[test]
+    fn background_fills_cleared_space() {
+        let mut octree = Octree::<u8>::new_with_background(NonZeroU32::new(32).unwrap(), 7).unwrap();
+        assert_eq!(octree.get([5, 5, 5]), Some(&7));
+
+        octree.insert([5, 5, 5], 1).unwrap();
+        octree.insert([6, 5, 5], 0).unwrap();
+        assert_eq!(octree.get([5, 5, 5]), Some(&1));
+        assert_eq!(octree.get([6, 5, 5]), Some(&0));
+        assert_eq!(octree.get([5, 6, 5]), Some(&7));
+
+        // The default value is ordinary data, and survives writes to its neighbours.
+        octree.insert([7, 5, 5], 2).unwrap();
+        assert_eq!(octree.get([6, 5, 5]), Some(&0));
+
+        octree.clear_at([5, 5, 5]).unwrap();
+        assert_eq!(octree.get([5, 5, 5]), Some(&7));
+
+        octree.clear();
+        assert_eq!(octree.get([6, 5, 5]), Some(&7));
+        assert_eq!(octree.background(), 7);
+    }
+
+    #[test]
+    fn queries_skip_background() {
+        let mut octree = Octree::<u8>::new_with_background(NonZeroU32::new(16).unwrap(), 7).unwrap();
+        octree.insert([4, 4, 4], 0).unwrap();
+        octree.insert([5, 4, 4], 1).unwrap();
+
+        let voxels = octree
+            .query_sphere([4.5, 4.5, 4.5], 1.0)
+            .collect::<alloc::vec::Vec<_>>();
+        assert_eq!(voxels.len(), 2);
+        assert!(voxels.iter().all(|voxel| voxel.data != 7));
+
+        let density = octree.density_in_sphere([4.5, 4.5, 4.5], 1.0, |data| *data == 7);
+        assert_eq!(density, 5.0 / 7.0);
+
+        let mut other = Octree::<u8>::new_with_background(NonZeroU32::new(16).unwrap(), 7).unwrap();
+        other.insert([5, 4, 4], 1).unwrap();
+        assert_eq!(octree.symmetric_difference_count(&other).unwrap(), 1);
+
+        // Unwritten space of `other` keeps its own background when reused in the result.
+        let zeroed = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        let union = other.union(&zeroed, |a, _| *a).unwrap();
+        assert!(matches!(union.get([0, 0, 0]), Some(0)));
+        assert!(matches!(union.get([5, 4, 4]), Some(1)));
+        assert!(!union.equivalent(&zeroed.union(&other, |a, _| *a).unwrap()));
+    }
+
+    #[test]
+    fn get_at_lod_matches_lod_down() {
+        let mut rng = test_utils::XorShift::new(0x10d5);
+
+        for _ in 0..10 {
+            let seed = rng.next_u32() as u64;
+            let octree = test_utils::XorShift::new(seed).octree(16, 1500, 3);
+            let mut coarse = test_utils::XorShift::new(seed).octree(16, 1500, 3);
+
+            for level in 0..6 {
+                for _ in 0..500 {
+                    let position = rng.position(16);
+                    assert_eq!(octree.get_at_lod(position, level), coarse.get(position).copied());
+                }
+
+                coarse.lod_down();
+            }
+        }
+    }
+
+    #[test]
+    fn at_lod_matches_lod_down() {
+        let mut rng = test_utils::XorShift::new(0x10d6);
+
+        for _ in 0..10 {
+            let seed = rng.next_u32() as u64;
+            let octree = test_utils::XorShift::new(seed).octree(16, 1500, 3);
+            let mut coarse = test_utils::XorShift::new(seed).octree(16, 1500, 3);
+            let before = alloc::format!("{:#?}", octree);
+
+            for level in 0..6 {
+                let copy = octree.at_lod(level);
+                assert_eq!(alloc::format!("{:#?}", copy), alloc::format!("{:#?}", coarse));
+                assert_eq!(alloc::format!("{:#?}", octree), before);
+
+                coarse.lod_down();
+            }
+        }
+    }
+
+    #[test]
+    fn lod_counts_unwritten_space_as_background() {
+        let mut octree = Octree::<u8>::new_with_background(NonZeroU32::new(8).unwrap(), 7).unwrap();
+        octree.insert([0, 0, 0], 1).unwrap();
+        octree.insert([1, 0, 0], 1).unwrap();
+        octree.insert([0, 1, 0], 2).unwrap();
+        octree.insert([1, 1, 0], 2).unwrap();
+
+        // Four unwritten voxels outvote two pairs, while a tie goes to the first octant.
+        assert_eq!(octree.get_at_lod([0, 0, 0], 1), Some(7));
+        octree.insert([0, 0, 1], 1).unwrap();
+        octree.insert([1, 0, 1], 2).unwrap();
+        assert_eq!(octree.get_at_lod([1, 1, 1], 1), Some(1));
+
+        octree.lod_down();
+        assert!(matches!(octree.get([1, 1, 1]), Some(1)));
+        assert!(matches!(octree.get([4, 4, 4]), Some(7)));
+    }
+
+    #[test]
+    fn lod_down_handles_large_volumes() {
+        for dimension in [8192, 1 << 21, 1 << 31] {
+            let mut octree = Octree::<u8>::new(NonZeroU32::new(dimension).unwrap()).unwrap();
+            octree.insert([0, 0, 0], 1).unwrap();
+            octree.insert([dimension - 1, 0, 0], 2).unwrap();
+
+            while octree.lod_level() < octree.max_lod_level() {
+                octree.lod_down();
+            }
+            assert_eq!(octree.min_dimension(), dimension / 2);
+        }
+    }
+
+    #[test]
+    fn lod_down_with_passes_leaf_volumes() {
+        let mut octree = Octree::<u16>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        for x in 0..4 {
+            for y in 0..4 {
+                for z in 0..2 {
+                    octree.insert([x, y, z], 10 * (x + y + z) as u16).unwrap();
+                }
+            }
+        }
+
+        let average = |leaves: &[(u16, u64)]| {
+            let total = leaves.iter().map(|(data, volume)| *data as u64 * volume).sum::<u64>();
+            (total / leaves.iter().map(|(_, volume)| volume).sum::<u64>()) as u16
+        };
+
+        octree.lod_down_with(|leaves| {
+            assert_eq!(leaves.iter().map(|(_, volume)| volume).sum::<u64>(), 8);
+            average(leaves)
+        });
+        assert!(matches!(octree.get([2, 0, 1]), Some(35)));
+
+        // Unwritten octants of the block are counted as the background, covering their full volume.
+        octree.lod_down_with(|leaves| {
+            assert_eq!(leaves.iter().map(|(_, volume)| volume).sum::<u64>(), 64);
+            assert_eq!(leaves.iter().filter(|(data, _)| *data == 0).count(), 4);
+            average(leaves)
+        });
+        assert!(matches!(octree.get([0, 0, 0]), Some(17)));
+    }
+
+    /// Returns whether the block of `2^level` voxels containing `position` lies entirely outside the box.
+    fn block_outside(position: [u32; 3], level: u32, min: [u32; 3], max: [u32; 3]) -> bool {
+        let lower = position.map(|c| c >> level << level);
+        (0..3).any(|i| lower[i] + (1 << level) <= min[i] || lower[i] >= max[i])
+    }
+
+    #[test]
+    fn lod_outside_keeps_focus_detailed() {
+        let mut rng = test_utils::XorShift::new(0x10d7);
+
+        for _ in 0..10 {
+            let seed = rng.next_u32() as u64;
+            let original = test_utils::XorShift::new(seed).octree(16, 1500, 3);
+            let mut octree = test_utils::XorShift::new(seed).octree(16, 1500, 3);
+
+            let min = [0, 1, 2].map(|_| rng.below(16));
+            let max = min.map(|c| c + 1 + rng.below(8));
+            let level = 1 + rng.below(3);
+
+            octree.lod_outside(min, max, level);
+            let hash = octree.content_hash();
+
+            for _ in 0..500 {
+                let position = rng.position(16);
+                let expected = if block_outside(position, level, min, max) {
+                    original.get_at_lod(position, level)
+                } else {
+                    original.get(position).copied()
+                };
+
+                assert_eq!(octree.get(position).copied(), expected);
+            }
+
+            // Repeating the call changes nothing.
+            octree.lod_outside(min, max, level);
+            assert_eq!(octree.content_hash(), hash);
+        }
+    }
+
+    #[test]
+    fn lod_outside_composes_with_moved_focus() {
+        let mut rng = test_utils::XorShift::new(0x10d8);
+
+        for _ in 0..10 {
+            let seed = rng.next_u32() as u64;
+            let original = test_utils::XorShift::new(seed).octree(16, 1500, 3);
+            let mut octree = test_utils::XorShift::new(seed).octree(16, 1500, 3);
+
+            let first = [0, 1, 2].map(|_| rng.below(8));
+            let second = [0, 1, 2].map(|_| rng.below(8));
+
+            octree.lod_outside(first, first.map(|c| c + 8), 2);
+            octree.lod_outside(second, second.map(|c| c + 8), 2);
+
+            for _ in 0..500 {
+                let position = rng.position(16);
+                let expected = if block_outside(position, 2, first, first.map(|c| c + 8))
+                    || block_outside(position, 2, second, second.map(|c| c + 8))
+                {
+                    original.get_at_lod(position, 2)
+                } else {
+                    original.get(position).copied()
+                };
+
+                assert_eq!(octree.get(position).copied(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn lod_outside_focus_at_edge() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        for position in [
+            [0, 0, 0],
+            [0, 0, 1],
+            [0, 1, 0],
+            [0, 1, 1],
+            [14, 14, 14],
+            [15, 15, 15],
+            [12, 15, 13],
+        ] {
+            octree.insert(position, 1 + position[0] as u8 % 2).unwrap();
+        }
+
+        // The focus box reaches past the far corner of the `Octree`.
+        octree.lod_outside([12, 12, 12], [20, 20, 20], 1);
+
+        assert!(matches!(octree.get([14, 14, 14]), Some(1)));
+        assert!(matches!(octree.get([15, 15, 15]), Some(2)));
+        assert!(matches!(octree.get([12, 15, 13]), Some(1)));
+        assert!(matches!(octree.get([1, 1, 1]), Some(1)));
+        assert!(matches!(octree.get([0, 0, 0]), Some(1)));
+
+        // An empty focus box coarsens everything, leaving isolated voxels outvoted by the background.
+        octree.lod_outside([16, 16, 16], [16, 16, 16], 1);
+        assert!(matches!(octree.get([15, 15, 15]), Some(0)));
+        assert!(matches!(octree.get([12, 15, 13]), Some(0)));
+        assert!(matches!(octree.get([1, 1, 1]), Some(1)));
+    }
+
+    #[test]
+    fn set_lod_level_matches_lod_down() {
+        let mut rng = test_utils::XorShift::new(0x10d9);
+
+        for _ in 0..10 {
+            let seed = rng.next_u32() as u64;
+            let mut octree = test_utils::XorShift::new(seed).octree(16, 1500, 3);
+            let mut stepped = test_utils::XorShift::new(seed).octree(16, 1500, 3);
+
+            octree.set_lod_level(3).unwrap();
+            stepped.lod_down();
+            stepped.lod_down();
+
+            assert_eq!(octree.lod_level(), stepped.lod_level());
+            assert_eq!(octree.min_dimension(), 4);
+            assert_eq!(octree.content_hash(), stepped.content_hash());
+
+            // Moving back only changes the granularity of inserts.
+            let hash = octree.content_hash();
+            octree.set_lod_level(2).unwrap();
+            assert_eq!((octree.lod_level(), octree.min_dimension()), (2, 2));
+            assert_eq!(octree.content_hash(), hash);
+
+            octree.reset_lod();
+            assert_eq!((octree.lod_level(), octree.min_dimension()), (1, 1));
+            assert_eq!(octree.content_hash(), hash);
+        }
+    }
+
+    #[test]
+    fn lod_state_survives_copies() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        assert_eq!(octree.max_lod_level(), 4);
+        assert_eq!(octree.set_lod_level(0), Err(Error::InvalidLodLevel(0)));
+        assert_eq!(octree.set_lod_level(5), Err(Error::InvalidLodLevel(5)));
+        assert_eq!(octree.lod_level(), 1);
+
+        // Repeated calls clamp at the coarsest level.
+        for _ in 0..6 {
+            octree.lod_down();
+        }
+        assert_eq!((octree.lod_level(), octree.min_dimension()), (4, 8));
+
+        octree.set_lod_level(2).unwrap();
+        let copy = octree.at_lod(0);
+        assert_eq!(
+            (copy.lod_level(), copy.max_lod_level(), copy.min_dimension()),
+            (2, 4, 2)
+        );
+
+        let coarse = octree.at_lod(1);
+        assert_eq!((coarse.lod_level(), coarse.min_dimension()), (3, 4));
+    }
+
+    #[test]
+    fn single_voxel_octrees_have_no_coarser_level() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(1).unwrap()).unwrap();
+        octree.insert([0, 0, 0], 3).unwrap();
+        assert_eq!(octree.max_lod_level(), 0);
+
+        let copy = octree.at_lod(1);
+        assert_eq!(copy.to_bytes(), octree.to_bytes());
+        assert_eq!((copy.lod_level(), copy.min_dimension()), (1, 1));
+
+        octree.lod_down();
+        octree.lod_down_with(|leaves| leaves[0].0);
+        assert_eq!((octree.lod_level(), octree.min_dimension()), (1, 1));
+        assert_eq!(octree.to_bytes(), copy.to_bytes());
+    }
+
+    #[test]
+    fn lod_weights_leaves_by_volume() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+
+        // The first three octants of the lowest 4*4*4 node simplify into 2*2*2 leaves holding 1. Each of the
+        // others holds two voxels of its own data first, followed by six voxels of values found nowhere else.
+        for octant in 0..8_u32 {
+            let offset = [octant & 1, (octant >> 1) & 1, (octant >> 2) & 1].map(|c| c * 2);
+            for i in 0..8_u32 {
+                let position = [0, 1, 2].map(|axis| offset[axis] + ((i >> axis) & 1));
+                let data = match (octant, i) {
+                    (0..=2, _) => 1,
+                    (_, 0..=1) => (20 + octant) as u8,
+                    _ => (10 + octant * 8 + i) as u8,
+                };
+                octree.insert(position, data).unwrap();
+            }
+        }
+
+        // The two voxels each of the last five octants holds alike outweigh any one of the others.
+        octree.lod_down_with(node::majority);
+        assert_eq!((octree.lod_level(), octree.min_dimension()), (2, 2));
+        for octant in 3..8_u32 {
+            let position = [octant & 1, (octant >> 1) & 1, (octant >> 2) & 1].map(|c| c * 2 + 1);
+            assert_eq!(octree.get(position), Some(&(20 + octant as u8)));
+        }
+
+        // Only three of the eight leaves hold 1, but the volumes of leaves holding the same data add up, so its 24
+        // voxels outweigh the 8 of each of the others.
+        octree.lod_down();
+        assert_eq!((octree.lod_level(), octree.min_dimension()), (3, 4));
+        for i in 0..64 {
+            assert_eq!(octree.get([i % 4, i / 4 % 4, i / 16]), Some(&1));
+        }
+        assert_eq!(octree.get([4, 4, 4]), Some(&0));
+    }
+
+    #[test]
+    fn lod_policy_keeps_sparse_features() {
+        let build = || {
+            let mut octree = Octree::<u8>::new_with_background(NonZeroU32::new(16).unwrap(), 9).unwrap();
+            for y in 0..16 {
+                octree.insert([3, y, 10], 1).unwrap();
+            }
+            octree.insert([8, 8, 8], 2).unwrap();
+            octree.insert([9, 9, 9], 9).unwrap();
+            octree
+        };
+
+        let mut majority = build();
+        let mut ignoring = build();
+        for _ in 0..3 {
+            majority.lod_down_with_policy(LodPolicy::Majority);
+            ignoring.lod_down_with_policy(LodPolicy::IgnoreBackground);
+        }
+
+        for y in 0..16 {
+            assert!(!matches!(majority.get([3, y, 10]), Some(1)));
+            assert!(matches!(ignoring.get([0, y, 15]), Some(1)));
+        }
+
+        // Written background voxels are ignored like unwritten space.
+        assert!(matches!(ignoring.get([15, 15, 15]), Some(2)));
+        assert!(matches!(ignoring.get([0, 0, 0]), Some(9)));
+    }
+
+    #[test]
+    fn lod_journal_restores_detail() {
+        let mut rng = test_utils::XorShift::new(0x10da);
+
+        for _ in 0..10 {
+            let seed = rng.next_u32() as u64;
+            let expected = test_utils::XorShift::new(seed).octree(16, 1500, 3);
+            let mut octree = test_utils::XorShift::new(seed).octree(16, 1500, 3);
+            octree.enable_lod_journal();
+
+            octree.lod_down();
+            octree.lod_down();
+            let coarse = octree.content_hash();
+            assert!(octree.lod_journal_bytes() > 0);
+
+            octree.lod_up();
+            octree.lod_down();
+            assert_eq!(octree.content_hash(), coarse);
+
+            octree.reset_lod();
+            assert!(octree.equivalent(&expected));
+            assert_eq!(octree.lod_journal_bytes(), 0);
+
+            // Without the journal, detail is lost as before.
+            let mut unjournaled = test_utils::XorShift::new(seed).octree(16, 1500, 3);
+            unjournaled.lod_down();
+            unjournaled.lod_up();
+            assert!(!unjournaled.equivalent(&expected));
+        }
+    }
+
+    #[test]
+    fn lod_journal_skips_edited_blocks() {
+        let mut rng = test_utils::XorShift::new(0x10db);
+
+        for _ in 0..10 {
+            let seed = rng.next_u32() as u64;
+            let expected = test_utils::XorShift::new(seed).octree(16, 1500, 3);
+            let mut octree = test_utils::XorShift::new(seed).octree(16, 1500, 3);
+            octree.enable_lod_journal();
+
+            octree.lod_down();
+            octree.lod_down();
+
+            // Edits at the coarser level cover a 4*4*4 block, which must keep the new data.
+            let inserted = rng.position(16);
+            let cleared = rng.position(16);
+            octree.insert(inserted, 7).unwrap();
+            octree.clear_at(cleared).unwrap();
+
+            let block = |position: [u32; 3], c: [u32; 3]| (0..3).all(|i| position[i] / 4 == c[i] / 4);
+            octree.reset_lod();
+
+            for x in 0..16 {
+                for y in 0..16 {
+                    for z in 0..16 {
+                        let position = [x, y, z];
+                        let data = *octree.get(position).unwrap();
+
+                        if block(position, cleared) {
+                            assert_eq!(data, 0);
+                        } else if block(position, inserted) {
+                            assert_eq!(data, 7);
+                        } else {
+                            assert_eq!(Some(&data), expected.get(position));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn lod_journal_discarded_by_lod_outside() {
+        let mut rng = test_utils::XorShift::new(0x10dc);
+
+        for _ in 0..10 {
+            let mut octree = rng.octree(16, 1500, 3);
+            octree.enable_lod_journal();
+            octree.lod_down();
+
+            // The blocks collapsed away from the focus must not be restored to the detail journaled before.
+            octree.lod_outside([0, 0, 0], [4, 4, 4], 2);
+            let coarse = octree.content_hash();
+            assert_eq!(octree.lod_journal_bytes(), 0);
+
+            octree.lod_up();
+            assert_eq!(octree.content_hash(), coarse);
+        }
+    }
+
+    #[test]
+    fn prune_frees_cleared_regions() {
+        let mut rng = test_utils::XorShift::new(0x9a7e);
+        let region = |octree: &mut Octree<u8>, data: Option<u8>| {
+            for x in 3..13 {
+                for y in 5..16 {
+                    for z in 0..9 {
+                        match data {
+                            Some(data) => octree.insert([x, y, z], data).unwrap(),
+                            None => octree.clear_at([x, y, z]).unwrap(),
+                        }
+                    }
+                }
+            }
+        };
+
+        // A region filled and cleared in an empty `Octree` prunes back to the root alone.
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        region(&mut octree, Some(1));
+        region(&mut octree, None);
+        octree.simplify();
+
+        let count = octree.node_count();
+        assert!(count > 1);
+        assert_eq!(octree.prune(), count - 1);
+        assert_eq!(octree.node_count(), 1);
+        assert!(octree.equivalent(&Octree::new(NonZeroU32::new(16).unwrap()).unwrap()));
+
+        for _ in 0..10 {
+            let mut octree = rng.octree(16, 300, 3);
+            region(&mut octree, Some(1 + rng.below(3) as u8));
+            region(&mut octree, None);
+
+            let reads = |octree: &Octree<u8>| {
+                (0..16 * 16 * 16)
+                    .map(|i| octree.get([i % 16, i / 16 % 16, i / 256]).copied())
+                    .collect::<alloc::vec::Vec<_>>()
+            };
+            let expected = reads(&octree);
+            let count = octree.node_count();
+
+            let pruned = octree.prune();
+            assert!(pruned > 0);
+            assert_eq!(octree.node_count(), count - pruned);
+            assert_eq!(reads(&octree), expected);
+            assert_eq!(octree.prune(), 0);
+        }
+    }
+
+    #[test]
+    fn debug_prints_the_tree() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(4).unwrap()).unwrap();
+        octree.insert([3, 0, 0], 1).unwrap();
+        octree.insert([0, 2, 2], 2).unwrap();
+        for position in [
+            [0, 0, 0],
+            [1, 0, 0],
+            [0, 1, 0],
+            [1, 1, 0],
+            [0, 0, 1],
+            [1, 0, 1],
+            [0, 1, 1],
+            [1, 1, 1],
+        ] {
+            octree.insert(position, 3).unwrap();
+        }
+
+        let expected = "Octree { dimension: 4, curr_lod_level: 1, max_lod_level: 2, min_dimension: 1, background: 0, \
+            node_count: 6, leaf_count: 3, bricks: None, lod_journal: None, root: {(0, 0, 0)..(2, 2, 2): 3, \
+            (2, 0, 0)..(4, 2, 2): {(3, 0, 0)..(4, 1, 1): 1}, (0, 2, 2)..(2, 4, 4): {(0, 2, 2)..(1, 3, 3): 2}} }";
+        assert_eq!(alloc::format!("{:?}", octree), expected);
+
+        let expected = "\
+Octree {
+    dimension: 4,
+    curr_lod_level: 1,
+    max_lod_level: 2,
+    min_dimension: 1,
+    background: 0,
+    node_count: 6,
+    leaf_count: 3,
+    bricks: None,
+    lod_journal: None,
+    root: {
+        (0, 0, 0)..(2, 2, 2): 3,
+        (2, 0, 0)..(4, 2, 2): {
+            (3, 0, 0)..(4, 1, 1): 1,
+        },
+        (0, 2, 2)..(2, 4, 4): {
+            (0, 2, 2)..(1, 3, 3): 2,
+        },
+    },
+}";
+        assert_eq!(alloc::format!("{:#?}", octree), expected);
+
+        // A `Node` does not know its bounds, so its children are keyed by octant.
+        let root = octree.root().node().unwrap();
+        assert_eq!(alloc::format!("{:?}", root), "{0: 3, 1: {1: 1}, 6: {0: 2}}");
+    }
+
+    #[test]
+    fn debug_summarises_large_trees() {
+        let mut octree = test_utils::XorShift::new(0xdeb6).octree(64, 5000, 4);
+        octree.enable_lod_journal();
+        octree.lod_down();
+
+        let summary = alloc::format!("{:?}", octree);
+        assert!(summary.len() < 4096, "{} bytes", summary.len());
+        assert!(summary.contains("lod_journal: Some(1)"));
+        assert!(summary.contains(", ..}"));
+
+        // The alternate flag prints every `Node`, so the leaves all appear.
+        let full = alloc::format!("{:#?}", octree);
+        let leaves = octree.iter_leaves().count();
+        assert!(
+            full.lines()
+                .filter(|line| line.contains(": ") && !line.ends_with('{'))
+                .count()
+                >= leaves
+        );
+        assert!(full.lines().all(|line| line.trim() != ".."));
+    }
 }
@@ -0,0 +1,491 @@
+use crate::{fill::fill, query::Containment, Error, NodeRef, Octree, Vector3};
+
+use core::{cmp::Ordering, fmt::Debug, hash::Hash};
+
+/// Half the length of the diagonal of a unit cube.
+const HALF_SQRT_3: f64 = 0.866_025_403_784_438_6;
+
+/// A ray parameter along a `Segment`, stored as an exact fraction with a positive denominator.
+#[derive(Debug, Clone, Copy)]
+struct Param {
+    num: i128,
+    den: i128,
+}
+
+impl Param {
+    fn new(num: i128, den: i128) -> Self {
+        if den < 0 {
+            Self { num: -num, den: -den }
+        } else {
+            Self { num, den }
+        }
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.num * other.den).cmp(&(other.num * self.den))
+    }
+}
+
+/// The segment between the centers of two voxels, evaluated with exact integer arithmetic.
+///
+/// All coordinates are doubled, so that voxel centers and voxel boundaries both lie on integers.
+pub(crate) struct Segment {
+    origin: [i128; 3],
+    delta: [i128; 3],
+}
+
+impl Segment {
+    pub(crate) fn between(a: Vector3<u32>, b: Vector3<u32>) -> Self {
+        let a: [u32; 3] = a.into();
+        let b: [u32; 3] = b.into();
+
+        Self {
+            origin: a.map(|c| 2 * c as i128 + 1),
+            delta: [0, 1, 2].map(|i| 2 * (b[i] as i128 - a[i] as i128)),
+        }
+    }
+
+    /// Returns the parameter at which the segment first touches the given cube, if it touches it at all.
+    ///
+    /// The cube is treated as closed, so a segment passing exactly through an edge or corner touches every
+    /// cube sharing it.
+    fn entry(&self, min: Vector3<u32>, dimension: u32) -> Option<Param> {
+        let min: [u32; 3] = min.into();
+
+        let mut enter = Param::new(0, 1);
+        let mut exit = Param::new(1, 1);
+
+        for ((min, origin), delta) in min.iter().zip(self.origin.iter()).zip(self.delta.iter()) {
+            let lower = 2 * *min as i128;
+            let upper = lower + 2 * dimension as i128;
+            let (origin, delta) = (*origin, *delta);
+
+            if delta == 0 {
+                if origin < lower || origin > upper {
+                    return None;
+                }
+            } else {
+                let mut t0 = Param::new(lower - origin, delta);
+                let mut t1 = Param::new(upper - origin, delta);
+
+                if t0.cmp(&t1) == Ordering::Greater {
+                    core::mem::swap(&mut t0, &mut t1);
+                }
+
+                if t0.cmp(&enter) == Ordering::Greater {
+                    enter = t0;
+                }
+
+                if t1.cmp(&exit) == Ordering::Less {
+                    exit = t1;
+                }
+            }
+        }
+
+        if enter.cmp(&exit) == Ordering::Greater {
+            None
+        } else {
+            Some(enter)
+        }
+    }
+
+    /// Returns whether the segment touches the given cube.
+    pub(crate) fn touches(&self, min: Vector3<u32>, dimension: u32) -> bool {
+        self.entry(min, dimension).is_some()
+    }
+
+    /// Returns the squared distance from the segment to the given point.
+    pub(crate) fn distance_squared(&self, point: [f64; 3]) -> f64 {
+        let origin = self.origin.map(|c| c as f64);
+        let delta = self.delta.map(|c| c as f64);
+        let offset = [0, 1, 2].map(|i| 2.0 * point[i] - origin[i]);
+
+        let length_squared = (0..3).map(|i| delta[i] * delta[i]).sum::<f64>();
+        let t = if length_squared > 0.0 {
+            ((0..3).map(|i| offset[i] * delta[i]).sum::<f64>() / length_squared).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        // Undo the doubling of coordinates.
+        (0..3).map(|i| offset[i] - t * delta[i]).map(|d| d * d / 4.0).sum()
+    }
+}
+
+/// Returns whether the segment between `a` and `b` touches a voxel of the given uniform, blocking cube,
+/// other than `a` and `b` themselves.
+fn uniform_blocks(segment: &Segment, min: Vector3<u32>, dimension: u32, a: Vector3<u32>, b: Vector3<u32>) -> bool {
+    if !segment.touches(min, dimension) {
+        return false;
+    }
+
+    let contains = |p: Vector3<u32>| {
+        let (p, min): ([u32; 3], [u32; 3]) = (p.into(), min.into());
+        (0..3).all(|i| p[i] >= min[i] && p[i] - min[i] < dimension)
+    };
+
+    if !contains(a) && !contains(b) {
+        return true;
+    }
+
+    if dimension == 1 {
+        return false;
+    }
+
+    // The cube holds an endpoint, so only split it down along the endpoints' paths.
+    let half = dimension / 2;
+    (0..8).any(|i| {
+        let offset = Vector3::from([i & 1, (i >> 1) & 1, (i >> 2) & 1]);
+        let child_min = min + offset.component_mul(&Vector3::from([half, half, half]));
+        uniform_blocks(segment, child_min, half, a, b)
+    })
+}
+
+fn node_blocks<T, F>(
+    node: NodeRef<'_, T>,
+    segment: &Segment,
+    a: Vector3<u32>,
+    b: Vector3<u32>,
+    background: T,
+    blocks: &F,
+) -> bool
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+    F: Fn(&T) -> bool,
+{
+    if let Some(data) = node.leaf_data() {
+        return blocks(data) && uniform_blocks(segment, node.min_position(), node.dimension(), a, b);
+    }
+
+    let dimension = node.dimension() / 2;
+    let gap_blocks = blocks(&background);
+
+    let mut touched = [None; 8];
+    let mut count = 0;
+
+    for (min, child) in node.octants() {
+        if child.is_none() && !gap_blocks {
+            continue;
+        }
+
+        if let Some(entry) = segment.entry(min, dimension) {
+            touched[count] = Some((entry, min, child));
+            count += 1;
+        }
+    }
+
+    // Visit octants in the order the segment reaches them, so that a nearby blocker ends the search early.
+    let touched = &mut touched[..count];
+    touched.sort_unstable_by(|a, b| match (a, b) {
+        (Some(a), Some(b)) => a.0.cmp(&b.0),
+        _ => Ordering::Equal,
+    });
+
+    touched.iter().flatten().any(|(_, min, child)| match child {
+        Some(child) => node_blocks(*child, segment, a, b, background, blocks),
+        None => uniform_blocks(segment, *min, dimension, a, b),
+    })
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Returns whether there is an unobstructed line of sight between the centers of voxels `a` and `b`.
+    ///
+    /// Every voxel touched by the segment between the two centers (its supercover) is tested with `blocks`,
+    /// using exact integer arithmetic. Voxels touched only at an edge or corner count as touched, so sight
+    /// never passes through diagonal cracks. The endpoints `a` and `b` themselves never block. Empty space
+    /// is tested as the background, and large leaves are tested as a whole rather than voxel by voxel.
+    ///
+    /// Returns an error if either position does not exist within the confines of the `Octree`.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([4, 0, 0], 1).unwrap();
+    ///
+    /// assert!(!octree.line_of_sight([0, 0, 0], [8, 0, 0], |data| *data != 0).unwrap());
+    /// assert!(octree.line_of_sight([0, 1, 0], [8, 1, 0], |data| *data != 0).unwrap());
+    /// ```
+    pub fn line_of_sight(
+        &self,
+        a: impl Into<Vector3<u32>>,
+        b: impl Into<Vector3<u32>>,
+        blocks: impl Fn(&T) -> bool,
+    ) -> Result<bool, Error> {
+        let (a, b) = (a.into(), b.into());
+        for position in [a, b].iter() {
+            if !self.contains(*position) {
+                return Err(Error::InvalidPosition(*position));
+            }
+        }
+
+        let segment = Segment::between(a, b);
+
+        Ok(!node_blocks(self.root(), &segment, a, b, self.background(), &blocks))
+    }
+
+    /// Writes `data` to every voxel touched by the segment between the centers of voxels `a` and `b`,
+    /// thickened by the given radius.
+    ///
+    /// Every voxel the segment passes through is written, including voxels it only touches at an edge or
+    /// corner, along with every voxel whose center lies within `thickness` of the segment. When `a` and `b`
+    /// are equal this writes a ball of radius `thickness` around them. Nodes entirely covered by the line
+    /// are written as single leaves, and voxels around the line which fall outside the `Octree` are
+    /// skipped.
+    ///
+    /// Returns an error, without modifying the `Octree`, if either endpoint does not exist within its
+    /// confines.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert_line([0, 0, 0], [2, 1, 0], 0, 1).unwrap();
+    ///
+    /// assert!(matches!(octree.get([1, 0, 0]), Some(1)));
+    /// assert!(matches!(octree.get([1, 1, 0]), Some(1)));
+    /// assert!(!matches!(octree.get([0, 1, 0]), Some(1)));
+    ///
+    /// assert!(octree.insert_line([0, 0, 0], [32, 0, 0], 0, 1).is_err());
+    /// ```
+    pub fn insert_line(
+        &mut self,
+        a: impl Into<Vector3<u32>>,
+        b: impl Into<Vector3<u32>>,
+        thickness: u32,
+        data: T,
+    ) -> Result<(), Error> {
+        let (a, b) = (a.into(), b.into());
+        for position in [a, b].iter() {
+            if !self.contains(*position) {
+                return Err(Error::InvalidPosition(*position));
+            }
+        }
+
+        let segment = Segment::between(a, b);
+        let radius_squared = thickness as f64 * thickness as f64;
+
+        fill(self, Some(data), |min, dimension| {
+            let lower = min.map(|c| c as f64 + 0.5);
+            let upper = [0, 1, 2].map(|i| lower[i] + (dimension - 1) as f64);
+
+            // The thickened segment is convex, so it covers the whole cube if it covers the corner voxels.
+            let covered = (0..8).all(|i: usize| {
+                let corner = [0, 1, 2].map(|axis| if (i >> axis) & 1 == 1 { upper[axis] } else { lower[axis] });
+                segment.distance_squared(corner) <= radius_squared
+            });
+
+            if covered {
+                return Containment::Inside;
+            }
+
+            if segment.touches(min.into(), dimension) {
+                return Containment::Straddling;
+            }
+
+            // Every voxel center of the cube lies within this distance of its middle.
+            let reach = (dimension - 1) as f64 * HALF_SQRT_3 + thickness as f64;
+            let middle = [0, 1, 2].map(|i| (lower[i] + upper[i]) / 2.0);
+
+            if segment.distance_squared(middle) > reach * reach {
+                Containment::Outside
+            } else {
+                Containment::Straddling
+            }
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Segment;
+    use crate::{test_utils::XorShift, Error, Octree, Vector3};
+
+    use alloc::vec::Vec;
+    use core::num::NonZeroU32;
+
+    fn solid(data: &u8) -> bool {
+        *data != 0
+    }
+
+    #[test]
+    fn sight_through_one_voxel_gap() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        for y in 0..8 {
+            for z in 0..8 {
+                if (y, z) != (3, 3) {
+                    octree.insert([4, y, z], 1).unwrap();
+                }
+            }
+        }
+
+        assert!(octree.line_of_sight([0, 3, 3], [7, 3, 3], solid).unwrap());
+        assert!(octree.line_of_sight([1, 2, 3], [7, 4, 3], solid).unwrap());
+        assert!(!octree.line_of_sight([0, 2, 3], [7, 2, 3], solid).unwrap());
+        assert!(!octree.line_of_sight([0, 0, 3], [7, 5, 3], solid).unwrap());
+    }
+
+    #[test]
+    fn diagonal_grazing_corner() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        octree.insert([1, 0, 0], 1).unwrap();
+
+        // The diagonal passes exactly through a corner of the blocking voxel.
+        assert!(!octree.line_of_sight([0, 0, 0], [2, 2, 0], solid).unwrap());
+        assert!(!octree.line_of_sight([2, 2, 0], [0, 0, 0], solid).unwrap());
+
+        // Moving the diagonal up by one voxel clears the corner.
+        assert!(octree.line_of_sight([0, 1, 0], [2, 3, 0], solid).unwrap());
+    }
+
+    #[test]
+    fn endpoints_do_not_block() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        for i in 0..8 {
+            octree.insert([i & 1, (i >> 1) & 1, (i >> 2) & 1], 1).unwrap();
+        }
+
+        assert!(octree.line_of_sight([0, 0, 0], [0, 0, 0], solid).unwrap());
+        assert!(octree.line_of_sight([0, 0, 0], [1, 0, 0], solid).unwrap());
+        assert!(!octree.line_of_sight([0, 0, 0], [1, 1, 1], solid).unwrap());
+        assert!(!octree.line_of_sight([0, 0, 0], [2, 0, 0], solid).unwrap());
+    }
+
+    #[test]
+    fn out_of_bounds_endpoint() {
+        let octree = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+
+        assert_eq!(
+            octree.line_of_sight([0, 0, 0], [8, 0, 0], solid),
+            Err(Error::InvalidPosition(Vector3::new(8, 0, 0)))
+        );
+    }
+
+    #[test]
+    fn line_of_sight_matches_brute_force() {
+        let mut rng = XorShift::new(0x1057);
+
+        for _ in 0..20 {
+            let octree = rng.octree(16, 150, 2);
+
+            for _ in 0..50 {
+                let (a, b) = (rng.position(16), rng.position(16));
+                let segment = Segment::between(a.into(), b.into());
+
+                let mut expected = true;
+                for x in 0..16 {
+                    for y in 0..16 {
+                        for z in 0..16 {
+                            let p = [x, y, z];
+                            if p != a && p != b && segment.touches(p.into(), 1) {
+                                expected &= !matches!(octree.get(p), Some(data) if *data == 1);
+                            }
+                        }
+                    }
+                }
+
+                assert_eq!(octree.line_of_sight(a, b, |data| *data == 1).unwrap(), expected);
+            }
+        }
+    }
+
+    /// Returns every voxel of the `Octree` holding `1`.
+    fn written(octree: &Octree<u8>) -> Vec<[u32; 3]> {
+        let dimension = octree.dimension();
+        let mut voxels = Vec::new();
+
+        for x in 0..dimension {
+            for y in 0..dimension {
+                for z in 0..dimension {
+                    if octree.get([x, y, z]) == Some(&1) {
+                        voxels.push([x, y, z]);
+                    }
+                }
+            }
+        }
+
+        voxels
+    }
+
+    #[test]
+    fn insert_line_matches_brute_force() {
+        let mut rng = XorShift::new(0x11e);
+
+        for _ in 0..40 {
+            let (a, b) = (rng.position(16), rng.position(16));
+            let thickness = rng.below(3);
+
+            let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+            octree.insert_line(a, b, thickness, 1).unwrap();
+
+            let segment = Segment::between(a.into(), b.into());
+            let radius_squared = (thickness * thickness) as f64;
+
+            let mut expected = Vec::new();
+            for x in 0..16 {
+                for y in 0..16 {
+                    for z in 0..16 {
+                        let center = [x as f64 + 0.5, y as f64 + 0.5, z as f64 + 0.5];
+                        if segment.touches([x, y, z].into(), 1) || segment.distance_squared(center) <= radius_squared {
+                            expected.push([x, y, z]);
+                        }
+                    }
+                }
+            }
+
+            assert_eq!(written(&octree), expected);
+
+            let mut reversed = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+            reversed.insert_line(b, a, thickness, 1).unwrap();
+            assert_eq!(written(&reversed), expected);
+        }
+    }
+
+    #[test]
+    fn thin_line_covers_marched_samples() {
+        let mut rng = XorShift::new(0x11e5);
+
+        for _ in 0..40 {
+            let (a, b) = (rng.position(16), rng.position(16));
+
+            let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+            octree.insert_line(a, b, 0, 1).unwrap();
+
+            for step in 0..=1000 {
+                let t = step as f32 / 1000.0;
+                let sample = [0, 1, 2].map(|i| (a[i] as f32 + 0.5 + t * (b[i] as f32 - a[i] as f32)) as u32);
+                assert_eq!(octree.get(sample), Some(&1));
+            }
+        }
+    }
+
+    #[test]
+    fn insert_point_line() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        octree.insert_line([3, 3, 3], [3, 3, 3], 0, 1).unwrap();
+        assert_eq!(written(&octree), [[3, 3, 3]]);
+
+        octree.insert_line([3, 3, 3], [3, 3, 3], 1, 1).unwrap();
+        assert_eq!(written(&octree).len(), 7);
+    }
+
+    #[test]
+    fn insert_line_out_of_bounds() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+
+        assert_eq!(
+            octree.insert_line([0, 0, 0], [0, 9, 0], 2, 1),
+            Err(Error::InvalidPosition(Vector3::new(0, 9, 0)))
+        );
+        assert!(written(&octree).is_empty());
+    }
+}
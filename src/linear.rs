@@ -0,0 +1,694 @@
+use crate::{
+    codec::write_tokens, flat::Token, node::OCTREE_CHILDREN, Error, LeafInfo, Node, NodeRef, Octree, ValueCodec,
+    Vector3,
+};
+
+use alloc::{vec, vec::Vec};
+use core::{convert::TryFrom, fmt::Debug, hash::Hash, iter, num::NonZeroU32, ops::Range};
+
+/// The deepest a [`LinearOctree`] may be, so that the locational code of every voxel fits in a `u64`.
+const MAX_LEVEL: u32 = 21;
+
+/// Spreads the low 21 bits of `c` out to every third bit.
+fn spread(c: u32) -> u64 {
+    let mut c = c as u64 & 0x1f_ffff;
+    c = (c | c << 32) & 0x001f_0000_0000_ffff;
+    c = (c | c << 16) & 0x001f_0000_ff00_00ff;
+    c = (c | c << 8) & 0x100f_00f0_0f00_f00f;
+    c = (c | c << 4) & 0x10c3_0c30_c30c_30c3;
+    (c | c << 2) & 0x1249_2492_4924_9249
+}
+
+/// Gathers every third bit of `code` back together, undoing [`spread`].
+fn gather(code: u64) -> u32 {
+    let mut c = code & 0x1249_2492_4924_9249;
+    c = (c | c >> 2) & 0x10c3_0c30_c30c_30c3;
+    c = (c | c >> 4) & 0x100f_00f0_0f00_f00f;
+    c = (c | c >> 8) & 0x001f_0000_ff00_00ff;
+    c = (c | c >> 16) & 0x001f_0000_0000_ffff;
+    ((c | c >> 32) & 0x1f_ffff) as u32
+}
+
+/// Returns the locational code of a voxel: its position along the Z-order curve visiting octants in octant
+/// order, as by `node::octant_order`.
+///
+/// The code of a cube is the code of its corner closest to the origin, and the voxels of a cube of level `l`
+/// (of dimension `2^l`) are exactly those whose codes share all but the lowest `3l` bits of it.
+fn code(position: [u32; 3]) -> u64 {
+    let [x, y, z] = position.map(spread);
+    y << 2 | z << 1 | x
+}
+
+fn position(code: u64) -> [u32; 3] {
+    [gather(code), gather(code >> 2), gather(code >> 1)]
+}
+
+/// Returns the number of voxels, and so of codes, in a cube of the given level.
+fn span(level: u32) -> u64 {
+    1 << (3 * level)
+}
+
+/// Returns the code of the cube of the given level holding the voxel with the given code.
+fn ancestor(code: u64, level: u32) -> u64 {
+    code & !(span(level) - 1)
+}
+
+/// A leaf of a [`LinearOctree`]: the cube of the given level at the given code, holding `data`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Entry<T> {
+    code: u64,
+    level: u8,
+    data: T,
+}
+
+impl<T> Entry<T> {
+    fn new(code: u64, level: u32, data: T) -> Self {
+        Self {
+            code,
+            level: level as u8,
+            data,
+        }
+    }
+
+    fn level(&self) -> u32 {
+        self.level as u32
+    }
+
+    fn info(&self) -> LeafInfo<T>
+    where
+        T: Copy,
+    {
+        LeafInfo {
+            min: position(self.code),
+            dimension: 1 << self.level,
+            data: self.data,
+        }
+    }
+}
+
+/// A sparse voxel octree holding only its leaves, sorted by locational code in a single `Vec`.
+///
+/// Behaves as an [`Octree`] at LOD level 1, as [`ArenaOctree`](crate::ArenaOctree) does: it holds exactly the
+/// leaves an `Octree` would after the same edits, and [`LinearOctree::to_bytes`] writes the same bytes as
+/// [`Octree::to_bytes`]. Internal `Node`s are not stored; a cube is internal when it holds more than one
+/// leaf, and space below one holding no leaf has never been written. Reading a voxel is a binary search over
+/// the leaves rather than a walk down from the root, and every leaf is stored in a few bytes with no pointer,
+/// but writing a voxel shifts the leaves after it, so this suits trees which are read far more than written.
+///
+/// Locational codes are held in a `u64`, so the dimension may be at most `2^21`.
+///
+/// # Example
+/// ```
+/// # use svo_rs::{Error, LinearOctree, Octree};
+/// # use core::num::NonZeroU32;
+/// #
+/// let mut linear = LinearOctree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+/// linear.insert([9, 8, 31], 1).unwrap();
+/// assert!(matches!(linear.get([9, 8, 31]), Some(1)));
+///
+/// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+/// octree.insert([9, 8, 31], 1).unwrap();
+/// assert_eq!(linear.to_bytes(), octree.to_bytes());
+/// ```
+#[derive(Debug, Clone)]
+pub struct LinearOctree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    dimension: NonZeroU32,
+    background: T,
+    entries: Vec<Entry<T>>,
+}
+
+impl<T> LinearOctree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Creates a new `LinearOctree<T>` of given dimension, which must be a power of 2 of at most `2^21`.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, LinearOctree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// assert!(LinearOctree::<u8>::new(NonZeroU32::new(32).unwrap()).is_ok());
+    ///
+    /// let linear = LinearOctree::<u8>::new(NonZeroU32::new(15).unwrap());
+    /// assert!(matches!(linear, Err(Error::InvalidDimension(15))));
+    ///
+    /// let linear = LinearOctree::<u8>::new(NonZeroU32::new(1 << 22).unwrap());
+    /// assert!(matches!(linear, Err(Error::InvalidDimension(_))));
+    /// ```
+    pub fn new(dimension: NonZeroU32) -> Result<Self, Error> {
+        Self::new_with_background(dimension, T::default())
+    }
+
+    /// Creates a new `LinearOctree<T>` of given dimension, where unwritten and cleared space holds
+    /// `background` rather than `T::default()`, as by [`Octree::new_with_background`].
+    pub fn new_with_background(dimension: NonZeroU32, background: T) -> Result<Self, Error> {
+        let mut linear = Self::empty(dimension, background)?;
+        linear.clear();
+
+        Ok(linear)
+    }
+
+    fn empty(dimension: NonZeroU32, background: T) -> Result<Self, Error> {
+        if !dimension.is_power_of_two() || dimension.trailing_zeros() > MAX_LEVEL {
+            return Err(Error::InvalidDimension(dimension.get()));
+        }
+
+        Ok(Self {
+            dimension,
+            background,
+            entries: Vec::new(),
+        })
+    }
+
+    /// Inserts data of type `T` into the given position, as by [`Octree::insert`].
+    pub fn insert(&mut self, position: impl Into<Vector3<u32>>, data: T) -> Result<(), Error> {
+        let position = <[u32; 3]>::from(position.into());
+        self.check(position)?;
+        let code = code(position);
+
+        match self.find(code) {
+            Ok(index) if self.entries[index].data == data => {
+                let entry = self.entries[index];
+                self.merge(entry.code, entry.level());
+            }
+            Ok(index) => {
+                self.split(index, code, data);
+                self.merge(code, 0);
+            }
+            Err(index) => {
+                // An `Octree` gives the empty octant a leaf holding the background, which is split down to the
+                // voxel unless that is the data written.
+                let level = self.branch_level(code, index) - 1;
+                let entry = match data == self.background {
+                    true => Entry::new(ancestor(code, level), level, data),
+                    false => Entry::new(code, 0, data),
+                };
+
+                self.entries.insert(index, entry);
+                self.merge(entry.code, entry.level());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Retrieves data of type `T` from the given position, as by [`Octree::get`].
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, LinearOctree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut linear = LinearOctree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// linear.insert([9, 8, 31], 1).unwrap();
+    ///
+    /// assert!(matches!(linear.get([9, 8, 31]), Some(1)));
+    /// assert!(matches!(linear.get([20, 1, 12]), Some(0)));
+    /// assert!(linear.get([32, 1, 12]).is_none());
+    /// ```
+    pub fn get(&self, position: impl Into<Vector3<u32>>) -> Option<&T> {
+        let position = <[u32; 3]>::from(position.into());
+        if !self.contains(position) {
+            return None;
+        }
+
+        match self.find(code(position)) {
+            Ok(index) => Some(&self.entries[index].data),
+            Err(_) => Some(&self.background),
+        }
+    }
+
+    /// Clears the voxel at the given position to the background, as by [`Octree::clear_at`].
+    pub fn clear_at(&mut self, position: impl Into<Vector3<u32>>) -> Result<(), Error> {
+        let position = <[u32; 3]>::from(position.into());
+        self.check(position)?;
+        let code = code(position);
+
+        if let Ok(index) = self.find(code) {
+            let entry = self.entries[index];
+            if entry.data == self.background {
+                self.merge(entry.code, entry.level());
+            } else {
+                self.split(index, code, self.background);
+                self.merge(code, 0);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes all leaves, keeping the memory of the `Vec` for later edits.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.entries.push(Entry::new(0, self.level(), self.background));
+    }
+
+    /// Returns an iterator over the non-empty leaves, in octant order, as by [`Octree::iter_leaves`].
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, LeafInfo, LinearOctree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut linear = LinearOctree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// linear.insert([1, 0, 0], 2).unwrap();
+    ///
+    /// let leaves = linear.iter_leaves().collect::<Vec<_>>();
+    /// assert_eq!(leaves, vec![LeafInfo { min: [1, 0, 0], dimension: 1, data: 2 }]);
+    /// ```
+    pub fn iter_leaves(&self) -> impl Iterator<Item = LeafInfo<T>> + '_ {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.data != self.background)
+            .map(Entry::info)
+    }
+
+    /// Returns an iterator over the spans of the `LinearOctree` intersecting the box from `min` (inclusive) to
+    /// `max` (exclusive), as by [`Octree::query_region`].
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, LinearOctree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let linear = LinearOctree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// let spans = linear.query_region([4, 4, 4], [8, 40, 6]).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(spans, vec![([4, 4, 4], [4, 28, 2], Some(&0))]);
+    /// ```
+    pub fn query_region(&self, min: impl Into<Vector3<u32>>, max: impl Into<Vector3<u32>>) -> LinearRegion<'_, T> {
+        let min = <[u32; 3]>::from(min.into());
+        let max = <[u32; 3]>::from(max.into());
+        let mut iter = LinearRegion {
+            octree: self,
+            min,
+            max,
+            stack: Vec::new(),
+        };
+
+        if iter.clip(0, self.level()).is_some() {
+            iter.stack.push((0, self.level(), true));
+        }
+
+        iter
+    }
+
+    /// Returns the value held by unwritten and cleared space.
+    pub fn background(&self) -> T {
+        self.background
+    }
+
+    /// Returns the dimension of the root node.
+    pub fn dimension(&self) -> u32 {
+        self.dimension.get()
+    }
+
+    /// Returns whether the given position exists within the confines of the `LinearOctree`.
+    pub fn contains(&self, position: impl Into<Vector3<u32>>) -> bool {
+        let position = <[u32; 3]>::from(position.into());
+        position.iter().all(|c| *c < self.dimension())
+    }
+
+    fn check(&self, position: [u32; 3]) -> Result<(), Error> {
+        if self.contains(position) {
+            Ok(())
+        } else {
+            Err(Error::InvalidPosition(position.into()))
+        }
+    }
+
+    /// Returns the level of the root.
+    fn level(&self) -> u32 {
+        self.dimension.trailing_zeros()
+    }
+
+    /// Returns the index of the leaf holding the voxel with the given code, or the index the leaves after it
+    /// start at if it has never been written.
+    ///
+    /// Leaves do not overlap, so the only leaf which may hold the voxel is the last one whose code is not past
+    /// the code of the voxel; its code is that of an ancestor of the voxel if it holds it.
+    fn find(&self, code: u64) -> Result<usize, usize> {
+        let index = self.entries.partition_point(|entry| entry.code <= code);
+
+        match index.checked_sub(1).map(|last| (last, &self.entries[last])) {
+            Some((last, entry)) if code - entry.code < span(entry.level()) => Ok(last),
+            _ => Err(index),
+        }
+    }
+
+    /// Returns the range of the leaves within the cube of the given level at the given code.
+    fn range(&self, code: u64, level: u32) -> Range<usize> {
+        let start = self.entries.partition_point(|entry| entry.code < code);
+        let len = self.entries[start..].partition_point(|entry| entry.code < code + span(level));
+
+        start..start + len
+    }
+
+    /// Returns the level of the smallest internal `Node` holding the voxel with the given code, which no leaf
+    /// holds, given the index the leaves after it start at.
+    ///
+    /// Every cube holding a leaf but not held by one is internal, and the leaves nearest to the voxel in
+    /// octant order share the smallest such cube with it.
+    fn branch_level(&self, code: u64, index: usize) -> u32 {
+        let neighbours = index.checked_sub(1).into_iter().chain(Some(index));
+
+        neighbours
+            .filter_map(|index| self.entries.get(index))
+            .map(|entry| (64 - (code ^ entry.code).leading_zeros()).div_ceil(3))
+            .min()
+            .unwrap_or(self.level())
+    }
+
+    /// Replaces the leaf at `index` with the `Node`s an `Octree` splits it into to write `data` to the voxel
+    /// with the given code, as by `Node::split`.
+    ///
+    /// Each `Node` on the way down to the voxel is split into children holding the data of the leaf, or into
+    /// no children if that is the background.
+    fn split(&mut self, index: usize, code: u64, data: T) {
+        let leaf = self.entries[index];
+        let mut entries = vec![Entry::new(code, 0, data)];
+
+        if leaf.data != self.background {
+            for level in 0..leaf.level() {
+                let parent = ancestor(code, level + 1);
+                entries.extend(
+                    (0..OCTREE_CHILDREN as u64)
+                        .map(|octant| parent + octant * span(level))
+                        .filter(|sibling| *sibling != ancestor(code, level))
+                        .map(|sibling| Entry::new(sibling, level, leaf.data)),
+                );
+            }
+        }
+
+        entries.sort_unstable_by_key(|entry| entry.code);
+        self.entries.splice(index..index + 1, entries);
+    }
+
+    /// Merges the leaf of the given level at the given code with its siblings, and so on up the tree, for as
+    /// long as all eight siblings are leaves holding the same data, as by `Node::simplify`.
+    fn merge(&mut self, mut code: u64, mut level: u32) {
+        while level < self.level() {
+            let parent = ancestor(code, level + 1);
+            let range = self.range(parent, level + 1);
+            let children = &self.entries[range.clone()];
+
+            let data = children[0].data;
+            if children.len() != OCTREE_CHILDREN
+                || children
+                    .iter()
+                    .any(|child| child.level() != level || child.data != data)
+            {
+                break;
+            }
+
+            self.entries
+                .splice(range, iter::once(Entry::new(parent, level + 1, data)));
+            code = parent;
+            level += 1;
+        }
+    }
+
+    /// Copies the leaves below `node` into the `LinearOctree`, in octant order.
+    fn copy(&mut self, node: NodeRef<'_, T>) {
+        match node.leaf_data() {
+            Some(data) => {
+                let min = node.min_position().into();
+                let level = node.dimension().trailing_zeros();
+                self.entries.push(Entry::new(code(min), level, *data));
+            }
+            None => {
+                for octant in 0..OCTREE_CHILDREN {
+                    if let Some(child) = node.child(octant) {
+                        self.copy(child);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the data of the cube of the given level at the given code if it is a leaf, or the range of the
+    /// leaves within it otherwise.
+    fn cube(&self, code: u64, level: u32) -> Result<&T, Range<usize>> {
+        let range = self.range(code, level);
+
+        match self.entries.get(range.start) {
+            Some(entry) if entry.code == code && entry.level() == level => Ok(&entry.data),
+            _ => Err(range),
+        }
+    }
+
+    /// Rebuilds the `Node` of the cube of the given level at the given code, and every `Node` below it.
+    fn node(&self, code: u64, level: u32) -> Node<T> {
+        match self.cube(code, level) {
+            Ok(data) => Node::leaf(*data),
+            Err(_) => Node::branch([0, 1, 2, 3, 4, 5, 6, 7].map(|octant| {
+                let child = code + octant * span(level - 1);
+                match self.range(child, level - 1).is_empty() {
+                    true => None,
+                    false => Some(self.node(child, level - 1)),
+                }
+            })),
+        }
+    }
+}
+
+impl<T> LinearOctree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + ValueCodec,
+{
+    /// Encodes the `LinearOctree` as bytes, in the same layout as [`Octree::to_bytes`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut stack = vec![(0, self.level())];
+        let tokens = iter::from_fn(|| {
+            let (code, level) = stack.pop()?;
+
+            let token = match self.cube(code, level) {
+                Ok(data) => Token::Leaf(*data),
+                Err(_) => {
+                    let mut mask = 0;
+                    // Push in reverse, so that children are listed in octant order.
+                    for octant in (0..OCTREE_CHILDREN).rev() {
+                        let child = code + octant as u64 * span(level - 1);
+                        if !self.range(child, level - 1).is_empty() {
+                            mask |= 1 << octant;
+                            stack.push((child, level - 1));
+                        }
+                    }
+
+                    Token::Branch(mask)
+                }
+            };
+
+            Some(token)
+        });
+
+        write_tokens(self.dimension(), 1, self.background, tokens)
+    }
+
+    /// Decodes a `LinearOctree` encoded by [`LinearOctree::to_bytes`] or [`Octree::to_bytes`].
+    ///
+    /// Fails as [`Octree::from_bytes`] does, with [`Error::InvalidLodLevel`] if the bytes hold an `Octree`
+    /// above LOD level 1, and with [`Error::InvalidDimension`] if they hold an `Octree` too large.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let octree = Octree::from_bytes(bytes)?;
+
+        match octree.lod_level() {
+            1 => Self::try_from(&octree),
+            level => Err(Error::InvalidLodLevel(level)),
+        }
+    }
+}
+
+impl<T> TryFrom<&Octree<T>> for LinearOctree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    type Error = Error;
+
+    /// Copies the leaves of an `Octree`, failing with [`Error::InvalidDimension`] if it is too large. Journaled
+    /// LOD detail and subtrees held in storage are not copied.
+    fn try_from(octree: &Octree<T>) -> Result<Self, Error> {
+        let mut linear = Self::empty(NonZeroU32::new(octree.dimension()).unwrap(), octree.background())?;
+        linear.copy(octree.root());
+
+        Ok(linear)
+    }
+}
+
+impl<T> From<&LinearOctree<T>> for Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Rebuilds the `Node`s of a `LinearOctree` as an `Octree` at LOD level 1.
+    fn from(linear: &LinearOctree<T>) -> Self {
+        let root = linear.node(0, linear.level());
+        Octree::from_root(linear.dimension(), root, linear.background, 1).unwrap()
+    }
+}
+
+/// An iterator over the spans of a [`LinearOctree`] intersecting an axis-aligned box, clipped to that box, as
+/// [`RegionIter`](crate::RegionIter) is over an `Octree`.
+///
+/// Created by [`LinearOctree::query_region`].
+pub struct LinearRegion<'a, T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    octree: &'a LinearOctree<T>,
+    min: [u32; 3],
+    max: [u32; 3],
+    /// Cubes still to visit, by code and level, and whether each holds any leaf or is a gap.
+    stack: Vec<(u64, u32, bool)>,
+}
+
+impl<'a, T> LinearRegion<'a, T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Clips the cube of the given level at the given code to the query box, returning the minimum position
+    /// and dimensions of the result.
+    fn clip(&self, code: u64, level: u32) -> Option<([u32; 3], [u32; 3])> {
+        let min = position(code);
+        let dimension = 1 << level;
+
+        let lower = [0, 1, 2].map(|i| min[i].max(self.min[i]));
+        let upper = [0, 1, 2].map(|i| (min[i] + dimension).min(self.max[i]));
+
+        if (0..3).all(|i| lower[i] < upper[i]) {
+            Some((lower, [0, 1, 2].map(|i| upper[i] - lower[i])))
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, T> Iterator for LinearRegion<'a, T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    type Item = ([u32; 3], [u32; 3], Option<&'a T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((code, level, written)) = self.stack.pop() {
+            let (min, dimensions) = self.clip(code, level).unwrap();
+            if !written {
+                return Some((min, dimensions, None));
+            }
+
+            if let Ok(data) = self.octree.cube(code, level) {
+                return Some((min, dimensions, Some(data)));
+            }
+
+            // Push in reverse, so that octants are yielded in order.
+            for octant in (0..OCTREE_CHILDREN as u64).rev() {
+                let child = code + octant * span(level - 1);
+                if self.clip(child, level - 1).is_none() {
+                    continue;
+                }
+
+                let written = !self.octree.range(child, level - 1).is_empty();
+                self.stack.push((child, level - 1, written));
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{code, position, Entry, LinearOctree};
+    use crate::{
+        node::octant_order,
+        test_utils::{assert_converts_octree, assert_matches_octree, assert_rejects_out_of_bounds, XorShift},
+        Octree,
+    };
+
+    use core::{convert::TryFrom, mem, num::NonZeroU32};
+    use std::time::Instant;
+
+    #[test]
+    fn codes_follow_octant_order() {
+        let mut rng = XorShift::new(0x11e0);
+
+        for _ in 0..1000 {
+            let position = rng.position(1 << 21);
+            assert_eq!(code(position) as u128, octant_order(position));
+            assert_eq!(super::position(code(position)), position);
+        }
+        assert_eq!(position(code([(1 << 21) - 1; 3])), [(1 << 21) - 1; 3]);
+    }
+
+    #[test]
+    fn matches_octree() {
+        assert_matches_octree::<LinearOctree<u8>>(0x11e1);
+    }
+
+    #[test]
+    fn converts_to_and_from_octree() {
+        assert_converts_octree::<LinearOctree<u8>>(0x11e2);
+    }
+
+    #[test]
+    fn out_of_bounds_positions_are_rejected() {
+        assert_rejects_out_of_bounds::<LinearOctree<u8>>();
+    }
+
+    #[test]
+    fn dimensions_beyond_morton_codes_are_rejected() {
+        let large = Octree::<u8>::new(NonZeroU32::new(1 << 22).unwrap()).unwrap();
+        assert!(matches!(
+            LinearOctree::try_from(&large),
+            Err(crate::Error::InvalidDimension(_))
+        ));
+    }
+
+    #[test]
+    fn leaves_are_packed_tightly() {
+        assert_eq!(mem::size_of::<Entry<u8>>(), 16);
+    }
+
+    /// Times reading every voxel of a large tree through both backends.
+    ///
+    /// Timings are only meaningful in release builds, so this is ignored by default; run it with
+    /// `cargo test --release -- --ignored lookups_are_faster`. On a tree of 200,000 random voxels, reading took
+    /// about a sixth less time through a binary search over the leaves than through a walk down an `Octree`: the
+    /// search takes more steps than the tree is deep, but each reads only a code from a `Vec`.
+    #[test]
+    #[ignore]
+    fn lookups_are_faster_than_through_nodes() {
+        let octree = XorShift::new(0x11e3).octree(128, 200_000, 8);
+        let linear = LinearOctree::try_from(&octree).unwrap();
+
+        let time = |get: &dyn Fn([u32; 3]) -> Option<u8>| {
+            let start = Instant::now();
+            let mut sum = 0_u64;
+            for _ in 0..4 {
+                for x in 0..128 {
+                    for y in 0..128 {
+                        for z in 0..128 {
+                            sum += get([x, y, z]).unwrap_or(0) as u64;
+                        }
+                    }
+                }
+            }
+
+            (start.elapsed(), sum)
+        };
+
+        let (boxed, boxed_sum) = time(&|position| octree.get(position).copied());
+        let (linear, linear_sum) = time(&|position| linear.get(position).copied());
+
+        assert_eq!(boxed_sum, linear_sum);
+        assert!(
+            linear < boxed,
+            "reading every voxel 4 times: {:?} boxed, {:?} linear",
+            boxed,
+            linear
+        );
+    }
+}
@@ -0,0 +1,612 @@
+use crate::{float, MeshData, Octree};
+
+use core::{fmt::Debug, hash::Hash};
+use hashbrown::HashMap;
+
+/// The corners joined by each edge of a cell, with corners numbered like the octants of a `Node`.
+const EDGES: [[usize; 2]; 12] = [
+    [0, 1],
+    [2, 3],
+    [4, 5],
+    [6, 7],
+    [0, 2],
+    [1, 3],
+    [4, 6],
+    [5, 7],
+    [0, 4],
+    [1, 5],
+    [2, 6],
+    [3, 7],
+];
+
+/// The triangles to emit for each combination of corners above the iso value, as triples of indices into
+/// `EDGES` terminated by `-1`.
+///
+/// Faces with two diagonally opposite corners above the iso value are always cut so that those corners are
+/// kept apart. The choice depends only on the face, so neighboring cells agree on it and the surface is
+/// closed. Triangles wind counter-clockwise when viewed from below the iso value.
+#[rustfmt::skip]
+const TRIANGLES: [[i8; 16]; 256] = [
+    [-1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 8, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 9, 5, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [4, 8, 9, 9, 5, 4, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 10, 4, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 1, 10, 10, 8, 0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 9, 5, 1, 10, 4, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 10, 8, 8, 9, 5, 5, 1, 8, -1, -1, -1, -1, -1, -1, -1],
+    [1, 5, 11, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 8, 1, 5, 11, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 9, 11, 11, 1, 0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 4, 8, 8, 9, 11, 11, 1, 8, -1, -1, -1, -1, -1, -1, -1],
+    [4, 5, 11, 11, 10, 4, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 5, 11, 11, 10, 8, 8, 0, 11, -1, -1, -1, -1, -1, -1, -1],
+    [0, 9, 11, 11, 10, 4, 4, 0, 11, -1, -1, -1, -1, -1, -1, -1],
+    [8, 9, 11, 11, 10, 8, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [2, 8, 6, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 6, 6, 2, 0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 9, 5, 2, 8, 6, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [2, 9, 5, 5, 4, 6, 6, 2, 5, -1, -1, -1, -1, -1, -1, -1],
+    [1, 10, 4, 2, 8, 6, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 1, 10, 10, 6, 2, 2, 0, 10, -1, -1, -1, -1, -1, -1, -1],
+    [0, 9, 5, 1, 10, 4, 2, 8, 6, -1, -1, -1, -1, -1, -1, -1],
+    [1, 10, 6, 6, 2, 9, 9, 5, 1, 1, 6, 9, -1, -1, -1, -1],
+    [1, 5, 11, 2, 8, 6, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 6, 6, 2, 0, 1, 5, 11, -1, -1, -1, -1, -1, -1, -1],
+    [0, 9, 11, 11, 1, 0, 2, 8, 6, -1, -1, -1, -1, -1, -1, -1],
+    [1, 4, 6, 6, 2, 9, 9, 11, 1, 1, 6, 9, -1, -1, -1, -1],
+    [2, 8, 6, 4, 5, 11, 11, 10, 4, -1, -1, -1, -1, -1, -1, -1],
+    [0, 5, 11, 11, 10, 6, 6, 2, 0, 0, 11, 6, -1, -1, -1, -1],
+    [0, 9, 11, 11, 10, 4, 4, 0, 11, 2, 8, 6, -1, -1, -1, -1],
+    [2, 9, 11, 11, 10, 6, 6, 2, 11, -1, -1, -1, -1, -1, -1, -1],
+    [2, 7, 9, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 8, 2, 7, 9, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 2, 7, 7, 5, 0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [2, 7, 5, 5, 4, 8, 8, 2, 5, -1, -1, -1, -1, -1, -1, -1],
+    [1, 10, 4, 2, 7, 9, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 1, 10, 10, 8, 0, 2, 7, 9, -1, -1, -1, -1, -1, -1, -1],
+    [0, 2, 7, 7, 5, 0, 1, 10, 4, -1, -1, -1, -1, -1, -1, -1],
+    [1, 10, 8, 8, 2, 7, 7, 5, 1, 1, 8, 7, -1, -1, -1, -1],
+    [1, 5, 11, 2, 7, 9, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 8, 1, 5, 11, 2, 7, 9, -1, -1, -1, -1, -1, -1, -1],
+    [0, 2, 7, 7, 11, 1, 1, 0, 7, -1, -1, -1, -1, -1, -1, -1],
+    [1, 4, 8, 8, 2, 7, 7, 11, 1, 1, 8, 7, -1, -1, -1, -1],
+    [2, 7, 9, 4, 5, 11, 11, 10, 4, -1, -1, -1, -1, -1, -1, -1],
+    [0, 5, 11, 11, 10, 8, 8, 0, 11, 2, 7, 9, -1, -1, -1, -1],
+    [0, 2, 7, 7, 11, 10, 10, 4, 0, 0, 7, 10, -1, -1, -1, -1],
+    [2, 7, 11, 11, 10, 8, 8, 2, 11, -1, -1, -1, -1, -1, -1, -1],
+    [6, 7, 9, 9, 8, 6, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 6, 6, 7, 9, 9, 0, 6, -1, -1, -1, -1, -1, -1, -1],
+    [0, 8, 6, 6, 7, 5, 5, 0, 6, -1, -1, -1, -1, -1, -1, -1],
+    [4, 6, 7, 7, 5, 4, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 10, 4, 6, 7, 9, 9, 8, 6, -1, -1, -1, -1, -1, -1, -1],
+    [0, 1, 10, 10, 6, 7, 7, 9, 0, 0, 10, 7, -1, -1, -1, -1],
+    [0, 8, 6, 6, 7, 5, 5, 0, 6, 1, 10, 4, -1, -1, -1, -1],
+    [1, 10, 6, 6, 7, 5, 5, 1, 6, -1, -1, -1, -1, -1, -1, -1],
+    [1, 5, 11, 6, 7, 9, 9, 8, 6, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 6, 6, 7, 9, 9, 0, 6, 1, 5, 11, -1, -1, -1, -1],
+    [0, 8, 6, 6, 7, 11, 11, 1, 0, 0, 6, 11, -1, -1, -1, -1],
+    [1, 4, 6, 6, 7, 11, 11, 1, 6, -1, -1, -1, -1, -1, -1, -1],
+    [4, 5, 11, 11, 10, 4, 6, 7, 9, 9, 8, 6, -1, -1, -1, -1],
+    [0, 5, 11, 11, 10, 6, 6, 7, 9, 0, 11, 6, 6, 9, 0, -1],
+    [0, 8, 6, 6, 7, 11, 11, 10, 4, 0, 6, 11, 11, 4, 0, -1],
+    [6, 7, 11, 11, 10, 6, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [3, 6, 10, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 8, 3, 6, 10, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 9, 5, 3, 6, 10, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [3, 6, 10, 4, 8, 9, 9, 5, 4, -1, -1, -1, -1, -1, -1, -1],
+    [1, 3, 6, 6, 4, 1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 1, 3, 3, 6, 8, 8, 0, 3, -1, -1, -1, -1, -1, -1, -1],
+    [0, 9, 5, 1, 3, 6, 6, 4, 1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 3, 6, 6, 8, 9, 9, 5, 1, 1, 6, 9, -1, -1, -1, -1],
+    [1, 5, 11, 3, 6, 10, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 8, 1, 5, 11, 3, 6, 10, -1, -1, -1, -1, -1, -1, -1],
+    [0, 9, 11, 11, 1, 0, 3, 6, 10, -1, -1, -1, -1, -1, -1, -1],
+    [1, 4, 8, 8, 9, 11, 11, 1, 8, 3, 6, 10, -1, -1, -1, -1],
+    [3, 6, 4, 4, 5, 11, 11, 3, 4, -1, -1, -1, -1, -1, -1, -1],
+    [0, 5, 11, 11, 3, 6, 6, 8, 0, 0, 11, 6, -1, -1, -1, -1],
+    [0, 9, 11, 11, 3, 6, 6, 4, 0, 0, 11, 6, -1, -1, -1, -1],
+    [3, 6, 8, 8, 9, 11, 11, 3, 8, -1, -1, -1, -1, -1, -1, -1],
+    [2, 8, 10, 10, 3, 2, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 10, 10, 3, 2, 2, 0, 10, -1, -1, -1, -1, -1, -1, -1],
+    [0, 9, 5, 2, 8, 10, 10, 3, 2, -1, -1, -1, -1, -1, -1, -1],
+    [2, 9, 5, 5, 4, 10, 10, 3, 2, 2, 5, 10, -1, -1, -1, -1],
+    [1, 3, 2, 2, 8, 4, 4, 1, 2, -1, -1, -1, -1, -1, -1, -1],
+    [0, 1, 3, 3, 2, 0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 9, 5, 1, 3, 2, 2, 8, 4, 4, 1, 2, -1, -1, -1, -1],
+    [1, 3, 2, 2, 9, 5, 5, 1, 2, -1, -1, -1, -1, -1, -1, -1],
+    [1, 5, 11, 2, 8, 10, 10, 3, 2, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 10, 10, 3, 2, 2, 0, 10, 1, 5, 11, -1, -1, -1, -1],
+    [0, 9, 11, 11, 1, 0, 2, 8, 10, 10, 3, 2, -1, -1, -1, -1],
+    [4, 10, 3, 2, 1, 4, 4, 3, 2, 2, 9, 11, 11, 1, 2, -1],
+    [2, 8, 4, 4, 5, 11, 11, 3, 2, 2, 4, 11, -1, -1, -1, -1],
+    [0, 5, 11, 11, 3, 2, 2, 0, 11, -1, -1, -1, -1, -1, -1, -1],
+    [0, 9, 11, 11, 3, 2, 2, 8, 4, 4, 0, 11, 11, 2, 4, -1],
+    [2, 9, 11, 11, 3, 2, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [2, 7, 9, 3, 6, 10, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 8, 2, 7, 9, 3, 6, 10, -1, -1, -1, -1, -1, -1, -1],
+    [0, 2, 7, 7, 5, 0, 3, 6, 10, -1, -1, -1, -1, -1, -1, -1],
+    [2, 7, 5, 5, 4, 8, 8, 2, 5, 3, 6, 10, -1, -1, -1, -1],
+    [1, 3, 6, 6, 4, 1, 2, 7, 9, -1, -1, -1, -1, -1, -1, -1],
+    [0, 1, 3, 3, 6, 8, 8, 0, 3, 2, 7, 9, -1, -1, -1, -1],
+    [0, 2, 7, 7, 5, 0, 1, 3, 6, 6, 4, 1, -1, -1, -1, -1],
+    [1, 3, 6, 8, 2, 7, 5, 6, 8, 8, 7, 5, 5, 1, 6, -1],
+    [1, 5, 11, 2, 7, 9, 3, 6, 10, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 8, 1, 5, 11, 2, 7, 9, 3, 6, 10, -1, -1, -1, -1],
+    [0, 2, 7, 7, 11, 1, 1, 0, 7, 3, 6, 10, -1, -1, -1, -1],
+    [1, 4, 8, 8, 2, 7, 7, 11, 1, 1, 8, 7, 3, 6, 10, -1],
+    [2, 7, 9, 3, 6, 4, 4, 5, 11, 11, 3, 4, -1, -1, -1, -1],
+    [0, 5, 11, 11, 3, 6, 6, 8, 0, 0, 11, 6, 2, 7, 9, -1],
+    [0, 2, 7, 11, 3, 6, 4, 7, 11, 11, 6, 4, 4, 0, 7, -1],
+    [2, 7, 11, 11, 3, 6, 8, 2, 11, 11, 6, 8, -1, -1, -1, -1],
+    [3, 7, 9, 9, 8, 10, 10, 3, 9, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 10, 10, 3, 7, 7, 9, 0, 0, 10, 7, -1, -1, -1, -1],
+    [0, 8, 10, 10, 3, 7, 7, 5, 0, 0, 10, 7, -1, -1, -1, -1],
+    [3, 7, 5, 5, 4, 10, 10, 3, 5, -1, -1, -1, -1, -1, -1, -1],
+    [1, 3, 7, 7, 9, 8, 8, 4, 1, 1, 7, 8, -1, -1, -1, -1],
+    [0, 1, 3, 3, 7, 9, 9, 0, 3, -1, -1, -1, -1, -1, -1, -1],
+    [8, 4, 1, 3, 0, 8, 8, 1, 3, 3, 7, 5, 5, 0, 3, -1],
+    [1, 3, 7, 7, 5, 1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 5, 11, 3, 7, 9, 9, 8, 10, 10, 3, 9, -1, -1, -1, -1],
+    [0, 4, 10, 10, 3, 7, 7, 9, 0, 0, 10, 7, 1, 5, 11, -1],
+    [0, 8, 10, 10, 3, 7, 7, 11, 1, 0, 10, 7, 7, 1, 0, -1],
+    [4, 10, 3, 7, 1, 4, 4, 3, 7, 7, 11, 1, -1, -1, -1, -1],
+    [3, 7, 9, 9, 8, 4, 4, 5, 11, 3, 9, 4, 4, 11, 3, -1],
+    [0, 5, 11, 3, 7, 9, 0, 11, 3, 3, 9, 0, -1, -1, -1, -1],
+    [0, 8, 4, 3, 7, 11, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [3, 7, 11, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [3, 11, 7, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 8, 3, 11, 7, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 9, 5, 3, 11, 7, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [3, 11, 7, 4, 8, 9, 9, 5, 4, -1, -1, -1, -1, -1, -1, -1],
+    [1, 10, 4, 3, 11, 7, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 1, 10, 10, 8, 0, 3, 11, 7, -1, -1, -1, -1, -1, -1, -1],
+    [0, 9, 5, 1, 10, 4, 3, 11, 7, -1, -1, -1, -1, -1, -1, -1],
+    [1, 10, 8, 8, 9, 5, 5, 1, 8, 3, 11, 7, -1, -1, -1, -1],
+    [1, 5, 7, 7, 3, 1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 8, 1, 5, 7, 7, 3, 1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 9, 7, 7, 3, 1, 1, 0, 7, -1, -1, -1, -1, -1, -1, -1],
+    [1, 4, 8, 8, 9, 7, 7, 3, 1, 1, 8, 7, -1, -1, -1, -1],
+    [3, 10, 4, 4, 5, 7, 7, 3, 4, -1, -1, -1, -1, -1, -1, -1],
+    [0, 5, 7, 7, 3, 10, 10, 8, 0, 0, 7, 10, -1, -1, -1, -1],
+    [0, 9, 7, 7, 3, 10, 10, 4, 0, 0, 7, 10, -1, -1, -1, -1],
+    [3, 10, 8, 8, 9, 7, 7, 3, 8, -1, -1, -1, -1, -1, -1, -1],
+    [2, 8, 6, 3, 11, 7, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 6, 6, 2, 0, 3, 11, 7, -1, -1, -1, -1, -1, -1, -1],
+    [0, 9, 5, 2, 8, 6, 3, 11, 7, -1, -1, -1, -1, -1, -1, -1],
+    [2, 9, 5, 5, 4, 6, 6, 2, 5, 3, 11, 7, -1, -1, -1, -1],
+    [1, 10, 4, 2, 8, 6, 3, 11, 7, -1, -1, -1, -1, -1, -1, -1],
+    [0, 1, 10, 10, 6, 2, 2, 0, 10, 3, 11, 7, -1, -1, -1, -1],
+    [0, 9, 5, 1, 10, 4, 2, 8, 6, 3, 11, 7, -1, -1, -1, -1],
+    [1, 10, 6, 6, 2, 9, 9, 5, 1, 1, 6, 9, 3, 11, 7, -1],
+    [1, 5, 7, 7, 3, 1, 2, 8, 6, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 6, 6, 2, 0, 1, 5, 7, 7, 3, 1, -1, -1, -1, -1],
+    [0, 9, 7, 7, 3, 1, 1, 0, 7, 2, 8, 6, -1, -1, -1, -1],
+    [1, 4, 6, 6, 2, 9, 9, 7, 3, 1, 6, 9, 9, 3, 1, -1],
+    [2, 8, 6, 3, 10, 4, 4, 5, 7, 7, 3, 4, -1, -1, -1, -1],
+    [0, 5, 7, 7, 3, 10, 10, 6, 2, 0, 7, 10, 10, 2, 0, -1],
+    [0, 9, 7, 7, 3, 10, 10, 4, 0, 0, 7, 10, 2, 8, 6, -1],
+    [9, 7, 3, 10, 2, 9, 9, 3, 10, 10, 6, 2, -1, -1, -1, -1],
+    [2, 3, 11, 11, 9, 2, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 8, 2, 3, 11, 11, 9, 2, -1, -1, -1, -1, -1, -1, -1],
+    [0, 2, 3, 3, 11, 5, 5, 0, 3, -1, -1, -1, -1, -1, -1, -1],
+    [2, 3, 11, 11, 5, 4, 4, 8, 2, 2, 11, 4, -1, -1, -1, -1],
+    [1, 10, 4, 2, 3, 11, 11, 9, 2, -1, -1, -1, -1, -1, -1, -1],
+    [0, 1, 10, 10, 8, 0, 2, 3, 11, 11, 9, 2, -1, -1, -1, -1],
+    [0, 2, 3, 3, 11, 5, 5, 0, 3, 1, 10, 4, -1, -1, -1, -1],
+    [1, 10, 8, 8, 2, 3, 3, 11, 5, 5, 1, 8, 8, 3, 5, -1],
+    [1, 5, 9, 9, 2, 3, 3, 1, 9, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 8, 1, 5, 9, 9, 2, 3, 3, 1, 9, -1, -1, -1, -1],
+    [0, 2, 3, 3, 1, 0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 4, 8, 8, 2, 3, 3, 1, 8, -1, -1, -1, -1, -1, -1, -1],
+    [2, 3, 10, 10, 4, 5, 5, 9, 2, 2, 10, 5, -1, -1, -1, -1],
+    [5, 9, 2, 3, 0, 5, 5, 2, 3, 3, 10, 8, 8, 0, 3, -1],
+    [0, 2, 3, 3, 10, 4, 4, 0, 3, -1, -1, -1, -1, -1, -1, -1],
+    [2, 3, 10, 10, 8, 2, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [3, 11, 9, 9, 8, 6, 6, 3, 9, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 6, 6, 3, 11, 11, 9, 0, 0, 6, 11, -1, -1, -1, -1],
+    [0, 8, 6, 6, 3, 11, 11, 5, 0, 0, 6, 11, -1, -1, -1, -1],
+    [3, 11, 5, 5, 4, 6, 6, 3, 5, -1, -1, -1, -1, -1, -1, -1],
+    [1, 10, 4, 3, 11, 9, 9, 8, 6, 6, 3, 9, -1, -1, -1, -1],
+    [0, 1, 10, 6, 3, 11, 9, 10, 6, 6, 11, 9, 9, 0, 10, -1],
+    [0, 8, 6, 6, 3, 11, 11, 5, 0, 0, 6, 11, 1, 10, 4, -1],
+    [1, 10, 6, 6, 3, 11, 5, 1, 6, 6, 11, 5, -1, -1, -1, -1],
+    [1, 5, 9, 9, 8, 6, 6, 3, 1, 1, 9, 6, -1, -1, -1, -1],
+    [0, 4, 6, 6, 3, 1, 1, 5, 9, 9, 0, 6, 6, 1, 9, -1],
+    [0, 8, 6, 6, 3, 1, 1, 0, 6, -1, -1, -1, -1, -1, -1, -1],
+    [1, 4, 6, 6, 3, 1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [3, 10, 4, 4, 5, 9, 9, 8, 6, 3, 4, 9, 9, 6, 3, -1],
+    [0, 5, 9, 3, 10, 6, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 8, 6, 3, 10, 4, 0, 6, 3, 3, 4, 0, -1, -1, -1, -1],
+    [3, 10, 6, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [6, 10, 11, 11, 7, 6, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 8, 6, 10, 11, 11, 7, 6, -1, -1, -1, -1, -1, -1, -1],
+    [0, 9, 5, 6, 10, 11, 11, 7, 6, -1, -1, -1, -1, -1, -1, -1],
+    [4, 8, 9, 9, 5, 4, 6, 10, 11, 11, 7, 6, -1, -1, -1, -1],
+    [1, 11, 7, 7, 6, 4, 4, 1, 7, -1, -1, -1, -1, -1, -1, -1],
+    [0, 1, 11, 11, 7, 6, 6, 8, 0, 0, 11, 6, -1, -1, -1, -1],
+    [0, 9, 5, 1, 11, 7, 7, 6, 4, 4, 1, 7, -1, -1, -1, -1],
+    [1, 11, 7, 7, 6, 8, 8, 9, 5, 1, 7, 8, 8, 5, 1, -1],
+    [1, 5, 7, 7, 6, 10, 10, 1, 7, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 8, 1, 5, 7, 7, 6, 10, 10, 1, 7, -1, -1, -1, -1],
+    [0, 9, 7, 7, 6, 10, 10, 1, 0, 0, 7, 10, -1, -1, -1, -1],
+    [1, 4, 8, 8, 9, 7, 7, 6, 10, 1, 8, 7, 7, 10, 1, -1],
+    [4, 5, 7, 7, 6, 4, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 5, 7, 7, 6, 8, 8, 0, 7, -1, -1, -1, -1, -1, -1, -1],
+    [0, 9, 7, 7, 6, 4, 4, 0, 7, -1, -1, -1, -1, -1, -1, -1],
+    [6, 8, 9, 9, 7, 6, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [2, 8, 10, 10, 11, 7, 7, 2, 10, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 10, 10, 11, 7, 7, 2, 0, 0, 10, 7, -1, -1, -1, -1],
+    [0, 9, 5, 2, 8, 10, 10, 11, 7, 7, 2, 10, -1, -1, -1, -1],
+    [2, 9, 5, 5, 4, 10, 10, 11, 7, 2, 5, 10, 10, 7, 2, -1],
+    [1, 11, 7, 7, 2, 8, 8, 4, 1, 1, 7, 8, -1, -1, -1, -1],
+    [0, 1, 11, 11, 7, 2, 2, 0, 11, -1, -1, -1, -1, -1, -1, -1],
+    [0, 9, 5, 1, 11, 7, 7, 2, 8, 8, 4, 1, 1, 7, 8, -1],
+    [1, 11, 7, 2, 9, 5, 1, 7, 2, 2, 5, 1, -1, -1, -1, -1],
+    [1, 5, 7, 7, 2, 8, 8, 10, 1, 1, 7, 8, -1, -1, -1, -1],
+    [0, 4, 10, 10, 1, 5, 5, 7, 2, 2, 0, 10, 10, 5, 2, -1],
+    [0, 9, 7, 7, 2, 8, 8, 10, 1, 1, 0, 7, 7, 8, 1, -1],
+    [1, 4, 10, 2, 9, 7, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [2, 8, 4, 4, 5, 7, 7, 2, 4, -1, -1, -1, -1, -1, -1, -1],
+    [0, 5, 7, 7, 2, 0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 9, 7, 7, 2, 8, 4, 0, 7, 7, 8, 4, -1, -1, -1, -1],
+    [2, 9, 7, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [2, 6, 10, 10, 11, 9, 9, 2, 10, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 8, 2, 6, 10, 10, 11, 9, 9, 2, 10, -1, -1, -1, -1],
+    [0, 2, 6, 6, 10, 11, 11, 5, 0, 0, 6, 11, -1, -1, -1, -1],
+    [2, 6, 10, 10, 11, 5, 5, 4, 8, 2, 10, 5, 5, 8, 2, -1],
+    [1, 11, 9, 9, 2, 6, 6, 4, 1, 1, 9, 6, -1, -1, -1, -1],
+    [0, 1, 11, 11, 9, 2, 6, 8, 0, 11, 2, 6, 6, 0, 11, -1],
+    [0, 2, 6, 6, 4, 1, 11, 5, 0, 6, 1, 11, 11, 0, 6, -1],
+    [1, 11, 5, 2, 6, 8, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 5, 9, 9, 2, 6, 6, 10, 1, 1, 9, 6, -1, -1, -1, -1],
+    [0, 4, 8, 1, 5, 9, 9, 2, 6, 6, 10, 1, 1, 9, 6, -1],
+    [0, 2, 6, 6, 10, 1, 1, 0, 6, -1, -1, -1, -1, -1, -1, -1],
+    [1, 4, 8, 2, 6, 10, 1, 8, 2, 2, 10, 1, -1, -1, -1, -1],
+    [2, 6, 4, 4, 5, 9, 9, 2, 4, -1, -1, -1, -1, -1, -1, -1],
+    [5, 9, 2, 6, 0, 5, 5, 2, 6, 6, 8, 0, -1, -1, -1, -1],
+    [0, 2, 6, 6, 4, 0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [2, 6, 8, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [8, 10, 11, 11, 9, 8, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 10, 10, 11, 9, 9, 0, 10, -1, -1, -1, -1, -1, -1, -1],
+    [0, 8, 10, 10, 11, 5, 5, 0, 10, -1, -1, -1, -1, -1, -1, -1],
+    [4, 10, 11, 11, 5, 4, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 11, 9, 9, 8, 4, 4, 1, 9, -1, -1, -1, -1, -1, -1, -1],
+    [0, 1, 11, 11, 9, 0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [8, 4, 1, 11, 0, 8, 8, 1, 11, 11, 5, 0, -1, -1, -1, -1],
+    [1, 11, 5, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 5, 9, 9, 8, 10, 10, 1, 9, -1, -1, -1, -1, -1, -1, -1],
+    [0, 4, 10, 10, 1, 5, 9, 0, 10, 10, 5, 9, -1, -1, -1, -1],
+    [0, 8, 10, 10, 1, 0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 4, 10, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [4, 5, 9, 9, 8, 4, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 5, 9, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [0, 8, 4, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [-1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+];
+
+/// Builds a marching cubes mesh over the lattice of voxel centers, treating space outside the `Octree` as
+/// density zero.
+struct Mesher<'a, T, F>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    octree: &'a Octree<T>,
+    iso: f32,
+    to_density: F,
+    vertices: HashMap<([i64; 3], usize), u32>,
+    mesh: MeshData,
+}
+
+impl<'a, T, F> Mesher<'a, T, F>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+    F: Fn(&T) -> f32,
+{
+    fn new(octree: &'a Octree<T>, iso: f32, to_density: F) -> Self {
+        Self {
+            octree,
+            iso,
+            to_density,
+            vertices: HashMap::new(),
+            mesh: MeshData::default(),
+        }
+    }
+
+    fn value(&self, data: Option<&T>) -> f32 {
+        match data {
+            Some(data) if *data != self.octree.background() => (self.to_density)(data),
+            _ => 0.0,
+        }
+    }
+
+    fn density(&self, position: [i64; 3]) -> f32 {
+        let dimension = self.octree.dimension() as i64;
+
+        if position.iter().all(|c| (0..dimension).contains(c)) {
+            self.value(self.octree.get(position.map(|c| c as u32)))
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns whether every sample from `min` to `min + size` (inclusive) lies on the same side of the iso
+    /// value, in which case none of the cells between them produce triangles.
+    fn is_uniform(&self, min: [i64; 3], size: i64) -> bool {
+        let dimension = self.octree.dimension() as i64;
+        let lower = min.map(|c| c.max(0));
+        let upper = min.map(|c| (c + size + 1).min(dimension));
+
+        let mut side = None;
+        if (0..3).any(|i| min[i] < 0 || min[i] + size + 1 > dimension) {
+            side = Some(0.0 > self.iso);
+        }
+
+        if (0..3).all(|i| lower[i] < upper[i]) {
+            let (lower, upper) = (lower.map(|c| c as u32), upper.map(|c| c as u32));
+
+            for (_, _, data) in self.octree.query_region(lower, upper) {
+                let above = self.value(data) > self.iso;
+
+                match side {
+                    Some(side) if side != above => return false,
+                    _ => side = Some(above),
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Polygonizes the block of cells with their minimum corners from `min` to `min + size` (exclusive),
+    /// skipping any part of it which is uniform.
+    fn block(&mut self, min: [i64; 3], size: i64) {
+        if self.is_uniform(min, size) {
+            return;
+        }
+
+        if size == 1 {
+            self.cell(min);
+            return;
+        }
+
+        let half = size / 2;
+        for i in 0..8 {
+            let offset = [i & 1, (i >> 1) & 1, (i >> 2) & 1];
+            self.block([0, 1, 2].map(|axis| min[axis] + offset[axis] * half), half);
+        }
+    }
+
+    fn cell(&mut self, min: [i64; 3]) {
+        let corner = |i: usize| [0, 1, 2].map(|axis| min[axis] + ((i >> axis) & 1) as i64);
+
+        let mut densities = [0.0; 8];
+        let mut case = 0;
+
+        for (i, density) in densities.iter_mut().enumerate() {
+            *density = self.density(corner(i));
+            if *density > self.iso {
+                case |= 1 << i;
+            }
+        }
+
+        for triangle in TRIANGLES[case].chunks(3).take_while(|triangle| triangle[0] >= 0) {
+            let mut indices = [0; 3];
+
+            for (index, edge) in indices.iter_mut().zip(triangle.iter()) {
+                let [a, b] = EDGES[*edge as usize];
+                *index = self.vertex(corner(a), a ^ b, densities[a], densities[b]);
+            }
+
+            self.triangle(indices);
+        }
+    }
+
+    /// Returns the index of the vertex on the edge from the sample at `corner` along `axis_bit`, creating
+    /// it if this is the first cell to use it.
+    fn vertex(&mut self, corner: [i64; 3], axis_bit: usize, from: f32, to: f32) -> u32 {
+        let axis = axis_bit.trailing_zeros() as usize;
+        let iso = self.iso;
+        let mesh = &mut self.mesh;
+
+        *self.vertices.entry((corner, axis)).or_insert_with(|| {
+            let mut position = corner.map(|c| c as f32 + 0.5);
+            position[axis] += (iso - from) / (to - from);
+
+            mesh.positions.extend_from_slice(&position);
+            mesh.normals.extend_from_slice(&[0.0; 3]);
+            (mesh.positions.len() / 3 - 1) as u32
+        })
+    }
+
+    /// Appends a triangle, adding its area-weighted normal to each of its vertices.
+    fn triangle(&mut self, indices: [u32; 3]) {
+        let position = |i: u32| {
+            let i = i as usize * 3;
+            [
+                self.mesh.positions[i],
+                self.mesh.positions[i + 1],
+                self.mesh.positions[i + 2],
+            ]
+        };
+
+        let (a, b, c) = (position(indices[0]), position(indices[1]), position(indices[2]));
+        let (ab, ac) = ([0, 1, 2].map(|i| b[i] - a[i]), [0, 1, 2].map(|i| c[i] - a[i]));
+        let normal = [
+            ab[1] * ac[2] - ab[2] * ac[1],
+            ab[2] * ac[0] - ab[0] * ac[2],
+            ab[0] * ac[1] - ab[1] * ac[0],
+        ];
+
+        for index in indices.iter() {
+            let i = *index as usize * 3;
+            for (accumulated, n) in self.mesh.normals[i..i + 3].iter_mut().zip(normal.iter()) {
+                *accumulated += n;
+            }
+        }
+
+        self.mesh.indices.extend_from_slice(&indices);
+    }
+
+    fn finish(mut self) -> MeshData {
+        for normal in self.mesh.normals.chunks_mut(3) {
+            let length = float::sqrt(normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2]);
+            if length > 0.0 {
+                normal.iter_mut().for_each(|c| *c /= length);
+            }
+        }
+
+        self.mesh
+    }
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Extracts the surface where the density of the `Octree` crosses `iso`, using marching cubes.
+    ///
+    /// Each voxel is sampled at its center through `to_density`, while unwritten space, leaves holding
+    /// the background and space outside the `Octree` have a density of zero. Vertices are interpolated
+    /// along the edges between samples, and shared by the cells on either side of them. Parts of the
+    /// `Octree` whose samples all lie on the same side of `iso`, such as the interiors of large leaves, are
+    /// skipped without visiting their cells. Normals point from densities above `iso` towards densities
+    /// below it, and triangles wind counter-clockwise around them.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([4, 4, 4], 255).unwrap();
+    ///
+    /// // A single dense voxel becomes an octahedron around its center.
+    /// let mesh = octree.marching_cubes(0.5, |data| *data as f32 / 255.0);
+    /// assert_eq!(mesh.positions.len() / 3, 6);
+    /// assert_eq!(mesh.indices.len() / 3, 8);
+    /// ```
+    pub fn marching_cubes(&self, iso: f32, to_density: impl Fn(&T) -> f32) -> MeshData {
+        let mut mesher = Mesher::new(self, iso, to_density);
+        mesher.block([-1; 3], 2 * self.dimension() as i64);
+        mesher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Mesher;
+    use crate::{test_utils::XorShift, MeshData, Octree};
+
+    use alloc::vec::Vec;
+    use core::num::NonZeroU32;
+    use hashbrown::HashMap;
+
+    fn density(data: &u8) -> f32 {
+        *data as f32 / 255.0
+    }
+
+    /// Returns the triangles of the mesh as sorted triples of vertex positions, starting from the smallest.
+    fn triangles(mesh: &MeshData) -> Vec<[[u32; 3]; 3]> {
+        let position = |i: u32| {
+            let i = i as usize * 3;
+            [0, 1, 2].map(|c| mesh.positions[i + c].to_bits())
+        };
+
+        let mut triangles = mesh
+            .indices
+            .chunks(3)
+            .map(|t| {
+                let mut triangle = [position(t[0]), position(t[1]), position(t[2])];
+                let first = (0..3).min_by_key(|i| triangle[*i]).unwrap();
+                triangle.rotate_left(first);
+                triangle
+            })
+            .collect::<Vec<_>>();
+
+        triangles.sort_unstable();
+        triangles
+    }
+
+    #[test]
+    fn uniform_trees_produce_empty_meshes() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        assert_eq!(octree.marching_cubes(0.5, density), MeshData::default());
+
+        for x in 0..16 {
+            for y in 0..16 {
+                for z in 0..16 {
+                    octree.insert([x, y, z], 100).unwrap();
+                }
+            }
+        }
+
+        assert_eq!(octree.marching_cubes(0.5, density), MeshData::default());
+    }
+
+    #[test]
+    fn sphere_is_closed_and_round() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        let (center, radius) = (8.0, 5.0);
+
+        for x in 0..16 {
+            for y in 0..16 {
+                for z in 0..16 {
+                    let d = [x, y, z].map(|c| c as f32 + 0.5 - center);
+                    let distance = (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt();
+                    let value = (128.0 + (radius - distance) * 64.0).clamp(0.0, 255.0);
+                    octree.insert([x, y, z], value as u8).unwrap();
+                }
+            }
+        }
+
+        let mesh = octree.marching_cubes(128.0 / 255.0, density);
+        assert!(!mesh.indices.is_empty());
+        assert!(mesh.positions.iter().chain(mesh.normals.iter()).all(|c| c.is_finite()));
+
+        // Every edge is shared by exactly two triangles, which traverse it in opposite directions.
+        let mut edges = HashMap::new();
+        for t in mesh.indices.chunks(3) {
+            for i in 0..3 {
+                *edges.entry((t[i], t[(i + 1) % 3])).or_insert(0) += 1;
+            }
+        }
+
+        for ((a, b), count) in edges.iter() {
+            assert_eq!(*count, 1);
+            assert_eq!(edges.get(&(*b, *a)), Some(&1));
+        }
+
+        for (position, normal) in mesh.positions.chunks(3).zip(mesh.normals.chunks(3)) {
+            let d = [0, 1, 2].map(|i| position[i] - center);
+            let distance = (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt();
+
+            assert!((distance - radius).abs() < 0.5);
+            assert!((0..3).map(|i| d[i] * normal[i]).sum::<f32>() > 0.0);
+        }
+    }
+
+    #[test]
+    fn skipping_uniform_blocks_matches_every_cell() {
+        let mut rng = XorShift::new(0xc0be);
+
+        for _ in 0..20 {
+            let octree = rng.octree(16, 200, 255);
+            let iso = rng.f32(0.0, 1.0);
+
+            let mut dense = Mesher::new(&octree, iso, density);
+            for x in -1..16 {
+                for y in -1..16 {
+                    for z in -1..16 {
+                        dense.cell([x, y, z]);
+                    }
+                }
+            }
+
+            assert_eq!(
+                triangles(&octree.marching_cubes(iso, density)),
+                triangles(&dense.finish())
+            );
+        }
+    }
+}
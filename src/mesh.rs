@@ -0,0 +1,748 @@
+use crate::{query::Containment, Face, Octree, RegionIter, Vector3};
+
+use alloc::{collections::BTreeMap, vec, vec::Vec};
+use core::{fmt::Debug, hash::Hash};
+
+/// How the space across a face relates to it.
+enum Exposure {
+    /// Everything across the face is empty.
+    Exposed,
+    /// Nothing across the face is empty.
+    Covered,
+    /// Only part of the space across the face is empty.
+    Mixed,
+}
+
+/// A lazy iterator over the faces of non-empty leaves which border empty space.
+///
+/// Yields `(min, dimension, face, data)`, describing the given face of the cube at `min` with the given
+/// dimension. Created by [`Octree::exposed_faces`] and [`Octree::exposed_faces_in_region`].
+pub struct ExposedFaces<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    octree: &'a Octree<T>,
+    leaves: RegionIter<'a, T>,
+    min: [u32; 3],
+    max: [u32; 3],
+    boundary_exposed: bool,
+    pending: Vec<([u32; 3], u32, Face, &'a T)>,
+}
+
+impl<'a, T> ExposedFaces<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    fn new(octree: &'a Octree<T>, min: [u32; 3], max: [u32; 3], boundary_exposed: bool) -> Self {
+        Self {
+            octree,
+            leaves: octree.query_region(min, max),
+            min,
+            max,
+            boundary_exposed,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Classifies the layer of voxels lying directly on the given face of a cube against the query box.
+    fn classify_layer(&self, min: [u32; 3], dimension: u32, face: Face) -> Containment {
+        let axis = face.axis();
+
+        let mut lower = min;
+        let mut upper = min.map(|c| c + dimension);
+
+        if face.is_positive() {
+            lower[axis] = upper[axis] - 1;
+        } else {
+            upper[axis] = lower[axis] + 1;
+        }
+
+        if (0..3).any(|i| lower[i] >= self.max[i] || upper[i] <= self.min[i]) {
+            Containment::Outside
+        } else if (0..3).all(|i| lower[i] >= self.min[i] && upper[i] <= self.max[i]) {
+            Containment::Inside
+        } else {
+            Containment::Straddling
+        }
+    }
+
+    /// Classifies the layer of voxels directly across the given face of a cube.
+    fn exposure(&self, min: [u32; 3], dimension: u32, face: Face) -> Exposure {
+        let axis = face.axis();
+
+        let across = if face.is_positive() {
+            Some(min[axis] + dimension).filter(|c| *c < self.octree.dimension())
+        } else {
+            min[axis].checked_sub(1)
+        };
+
+        let across = match across {
+            Some(across) => across,
+            None if self.boundary_exposed => return Exposure::Exposed,
+            None => return Exposure::Covered,
+        };
+
+        let mut lower = min;
+        lower[axis] = across;
+
+        let mut upper = min.map(|c| c + dimension);
+        upper[axis] = across + 1;
+
+        let mut empty = false;
+        let mut solid = false;
+
+        for (_, _, data) in self.octree.query_region(lower, upper) {
+            match data {
+                Some(data) if *data != self.octree.background() => solid = true,
+                _ => empty = true,
+            }
+
+            if empty && solid {
+                return Exposure::Mixed;
+            }
+        }
+
+        if solid {
+            Exposure::Covered
+        } else {
+            Exposure::Exposed
+        }
+    }
+
+    /// Queues the four sub-cubes of half the dimension which lie along the given face of a cube.
+    fn split(&mut self, min: [u32; 3], dimension: u32, face: Face, data: &'a T) {
+        let axis = face.axis();
+        let half = dimension / 2;
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+
+        // Push in reverse, so that sub-faces are yielded in order.
+        for i in (0..4).rev() {
+            let mut sub_min = min;
+            sub_min[u] += (i & 1) * half;
+            sub_min[v] += (i >> 1) * half;
+
+            if face.is_positive() {
+                sub_min[axis] += half;
+            }
+
+            self.pending.push((sub_min, half, face, data));
+        }
+    }
+}
+
+impl<'a, T> Iterator for ExposedFaces<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    type Item = ([u32; 3], u32, Face, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((min, dimension, face, data)) = self.pending.pop() {
+                match self.classify_layer(min, dimension, face) {
+                    Containment::Outside => {}
+                    Containment::Straddling => self.split(min, dimension, face, data),
+                    Containment::Inside => match self.exposure(min, dimension, face) {
+                        Exposure::Exposed => return Some((min, dimension, face, data)),
+                        Exposure::Covered => {}
+                        Exposure::Mixed => self.split(min, dimension, face, data),
+                    },
+                }
+
+                continue;
+            }
+
+            let (min, dimension, data): (Vector3<u32>, _, _) = self.leaves.next_cube()?;
+
+            if let Some(data) = data.filter(|data| **data != self.octree.background()) {
+                for face in Face::ALL.iter().rev() {
+                    self.pending.push((min.into(), dimension, *face, data));
+                }
+            }
+        }
+    }
+}
+
+/// Options for [`Octree::greedy_mesh`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MeshConfig {
+    /// Whether faces of leaves holding different values may be merged into the same quad.
+    pub merge_values: bool,
+    /// Whether faces on the outer boundary of the `Octree` are meshed.
+    pub boundary_exposed: bool,
+}
+
+impl Default for MeshConfig {
+    fn default() -> Self {
+        Self {
+            merge_values: false,
+            boundary_exposed: true,
+        }
+    }
+}
+
+/// Plain vertex and index buffers describing a triangle mesh.
+///
+/// `positions` and `normals` hold three floats per vertex, and `indices` holds three vertex indices per
+/// triangle. Every quad is emitted as four vertices and two triangles, wound counter-clockwise when viewed
+/// from the side its normal points towards.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MeshData {
+    /// The position of each vertex.
+    pub positions: Vec<f32>,
+    /// The unit normal of each vertex.
+    pub normals: Vec<f32>,
+    /// The vertices of each triangle.
+    pub indices: Vec<u32>,
+}
+
+impl MeshData {
+    /// Appends a quad on the given face, spanning `u` and `v` on the two axes following the face's axis,
+    /// at `layer` on the face's axis.
+    pub(crate) fn push_quad(&mut self, face: Face, layer: u32, u: [u32; 2], v: [u32; 2]) {
+        let axis = face.axis();
+        let (u_axis, v_axis) = ((axis + 1) % 3, (axis + 2) % 3);
+
+        // The two axes following `axis` form a right-handed basis with it, so this order winds
+        // counter-clockwise around the positive direction.
+        let mut corners = [(u[0], v[0]), (u[1], v[0]), (u[1], v[1]), (u[0], v[1])];
+        if !face.is_positive() {
+            corners.reverse();
+        }
+
+        let base = (self.positions.len() / 3) as u32;
+        let normal = face.normal();
+
+        for (u, v) in corners.iter() {
+            let mut position = [0.0; 3];
+            position[axis] = layer as f32;
+            position[u_axis] = *u as f32;
+            position[v_axis] = *v as f32;
+
+            self.positions.extend_from_slice(&position);
+            self.normals.extend(normal.iter().map(|c| *c as f32));
+        }
+
+        self.indices
+            .extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+}
+
+/// Merges the exposed faces lying in one plane into maximal rectangles, appending them to `mesh`.
+///
+/// Faces are given as `(u, v, dimension, data)`. Rather than a mask of unit cells, the plane is divided
+/// along every face boundary, so that large faces stay cheap to merge.
+fn merge_plane<T: PartialEq>(
+    mesh: &mut MeshData,
+    face: Face,
+    layer: u32,
+    faces: &[(u32, u32, u32, &T)],
+    merge_values: bool,
+) {
+    let mut us = faces.iter().flat_map(|(u, _, d, _)| [*u, u + d]).collect::<Vec<_>>();
+    let mut vs = faces.iter().flat_map(|(_, v, d, _)| [*v, v + d]).collect::<Vec<_>>();
+    us.sort_unstable();
+    us.dedup();
+    vs.sort_unstable();
+    vs.dedup();
+
+    let (width, height) = (us.len() - 1, vs.len() - 1);
+    let mut mask = vec![None; width * height];
+
+    for (u, v, dimension, data) in faces.iter() {
+        let (u0, u1) = (
+            us.binary_search(u).unwrap(),
+            us.binary_search(&(u + dimension)).unwrap(),
+        );
+        let (v0, v1) = (
+            vs.binary_search(v).unwrap(),
+            vs.binary_search(&(v + dimension)).unwrap(),
+        );
+
+        for j in v0..v1 {
+            for cell in mask[j * width + u0..j * width + u1].iter_mut() {
+                *cell = Some(*data);
+            }
+        }
+    }
+
+    let mergeable = |a: Option<&T>, b: Option<&T>| match (a, b) {
+        (Some(a), Some(b)) => merge_values || a == b,
+        _ => false,
+    };
+
+    for j in 0..height {
+        let mut i = 0;
+
+        while i < width {
+            let start = mask[j * width + i];
+            if start.is_none() {
+                i += 1;
+                continue;
+            }
+
+            let mut i1 = i + 1;
+            while i1 < width && mergeable(start, mask[j * width + i1]) {
+                i1 += 1;
+            }
+
+            let mut j1 = j + 1;
+            while j1 < height && (i..i1).all(|k| mergeable(start, mask[j1 * width + k])) {
+                j1 += 1;
+            }
+
+            for row in j..j1 {
+                for cell in mask[row * width + i..row * width + i1].iter_mut() {
+                    *cell = None;
+                }
+            }
+
+            mesh.push_quad(face, layer, [us[i], us[i1]], [vs[j], vs[j1]]);
+            i = i1;
+        }
+    }
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Returns a lazy iterator over every face of a non-empty leaf which borders empty space.
+    ///
+    /// Each item is `(min, dimension, face, data)`, describing the given face of the cube at `min`. Faces of
+    /// large leaves are reported whole where possible, and subdivided into the parts which are actually
+    /// exposed where the space across them is only partly empty. Unwritten space and leaves holding
+    /// the background count as empty. `boundary_exposed` decides whether faces on the outer boundary of the
+    /// `Octree` are reported.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Face, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([4, 4, 4], 1).unwrap();
+    /// octree.insert([5, 4, 4], 1).unwrap();
+    ///
+    /// let faces = octree.exposed_faces(true).collect::<Vec<_>>();
+    /// assert_eq!(faces.len(), 10);
+    /// assert!(!faces.contains(&([4, 4, 4], 1, Face::Right, &1)));
+    /// ```
+    pub fn exposed_faces(&self, boundary_exposed: bool) -> ExposedFaces<'_, T> {
+        let dimension = self.dimension();
+        self.exposed_faces_in_region([0, 0, 0], [dimension; 3], boundary_exposed)
+    }
+
+    /// Returns a lazy iterator over the exposed faces of the voxels inside the box from `min` (inclusive) to
+    /// `max` (exclusive).
+    ///
+    /// Behaves like [`Octree::exposed_faces`], but only reports faces of voxels inside the box. Space
+    /// outside the box is still examined to decide whether a face is exposed, and leaves straddling the
+    /// box are reported as the parts of their faces which lie inside it.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Face, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([4, 4, 4], 1).unwrap();
+    /// octree.insert([5, 4, 4], 1).unwrap();
+    ///
+    /// let faces = octree.exposed_faces_in_region([0, 0, 0], [5, 5, 5], true).collect::<Vec<_>>();
+    /// assert_eq!(faces.len(), 5);
+    /// assert!(faces.contains(&([4, 4, 4], 1, Face::Left, &1)));
+    /// ```
+    pub fn exposed_faces_in_region(
+        &self,
+        min: impl Into<Vector3<u32>>,
+        max: impl Into<Vector3<u32>>,
+        boundary_exposed: bool,
+    ) -> ExposedFaces<'_, T> {
+        ExposedFaces::new(
+            self,
+            <[u32; 3]>::from(min.into()),
+            <[u32; 3]>::from(max.into()),
+            boundary_exposed,
+        )
+    }
+
+    /// Meshes the exposed faces of the `Octree`, merging coplanar faces into maximal rectangles.
+    ///
+    /// Faces are found as by [`Octree::exposed_faces`], and neighboring faces in the same plane and facing
+    /// the same way are merged greedily, one row at a time. Unless `config.merge_values` is set, only faces
+    /// of leaves holding equal values are merged. Vertex positions are in voxel units, with the `Octree`
+    /// spanning from the origin to its dimension on each axis.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, MeshConfig, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([4, 4, 4], 1).unwrap();
+    /// octree.insert([5, 4, 4], 1).unwrap();
+    ///
+    /// let mesh = octree.greedy_mesh(MeshConfig::default());
+    /// assert_eq!(mesh.indices.len() / 3, 12);
+    /// ```
+    pub fn greedy_mesh(&self, config: MeshConfig) -> MeshData {
+        let mut planes = BTreeMap::<_, Vec<_>>::new();
+
+        for (min, dimension, face, data) in self.exposed_faces(config.boundary_exposed) {
+            let axis = face.axis();
+            let layer = if face.is_positive() {
+                min[axis] + dimension
+            } else {
+                min[axis]
+            };
+
+            planes
+                .entry((face, layer))
+                .or_default()
+                .push((min[(axis + 1) % 3], min[(axis + 2) % 3], dimension, data));
+        }
+
+        let mut mesh = MeshData::default();
+        for ((face, layer), faces) in planes.iter() {
+            merge_plane(&mut mesh, *face, *layer, faces, config.merge_values);
+        }
+
+        mesh
+    }
+
+    /// Meshes every exposed leaf face as its own quad, skipping leaves whose value fails `filter`.
+    ///
+    /// Faces are found as by [`Octree::exposed_faces`], with faces on the outer boundary of the `Octree`
+    /// included, so a large leaf contributes one quad per face unless a finer neighbor only partly covers
+    /// it. Faces are exposed when they border empty space, regardless of `filter`. See [`MeshData`] for the
+    /// winding order, and [`Octree::greedy_mesh`] for a mesh with fewer quads.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([4, 4, 4], 1).unwrap();
+    /// octree.insert([5, 4, 4], 2).unwrap();
+    ///
+    /// assert_eq!(octree.cube_mesh(|_| true).indices.len() / 3, 20);
+    /// assert_eq!(octree.cube_mesh(|data| *data == 1).indices.len() / 3, 10);
+    /// ```
+    pub fn cube_mesh(&self, filter: impl Fn(&T) -> bool) -> MeshData {
+        let mut mesh = MeshData::default();
+
+        for (min, dimension, face, data) in self.exposed_faces(true) {
+            if !filter(data) {
+                continue;
+            }
+
+            let axis = face.axis();
+            let (u, v) = (min[(axis + 1) % 3], min[(axis + 2) % 3]);
+            let layer = if face.is_positive() {
+                min[axis] + dimension
+            } else {
+                min[axis]
+            };
+
+            mesh.push_quad(face, layer, [u, u + dimension], [v, v + dimension]);
+        }
+
+        mesh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MeshConfig, MeshData};
+    use crate::{test_utils::XorShift, Face, Octree};
+
+    use alloc::vec::Vec;
+    use core::num::NonZeroU32;
+
+    /// Expands reported faces into the unit faces they cover, checking that none is reported twice.
+    fn unit_faces(faces: impl Iterator<Item = ([u32; 3], u32, Face, u8)>) -> Vec<([u32; 3], Face, u8)> {
+        let mut units = Vec::new();
+
+        for (min, dimension, face, data) in faces {
+            let axis = face.axis();
+
+            for x in 0..dimension {
+                for y in 0..dimension {
+                    for z in 0..dimension {
+                        let offset = [x, y, z];
+                        let layer = if face.is_positive() { dimension - 1 } else { 0 };
+
+                        if offset[axis] == layer {
+                            units.push(([min[0] + x, min[1] + y, min[2] + z], face, data));
+                        }
+                    }
+                }
+            }
+        }
+
+        let count = units.len();
+        units.sort_unstable();
+        units.dedup();
+        assert_eq!(units.len(), count);
+
+        units
+    }
+
+    fn dense_faces(
+        octree: &Octree<u8>,
+        min: [u32; 3],
+        max: [u32; 3],
+        boundary_exposed: bool,
+    ) -> Vec<([u32; 3], Face, u8)> {
+        let empty = |p: [u32; 3]| !matches!(octree.get(p), Some(data) if *data != 0);
+        let mut units = Vec::new();
+
+        for x in min[0]..max[0] {
+            for y in min[1]..max[1] {
+                for z in min[2]..max[2] {
+                    let position = [x, y, z];
+                    if empty(position) {
+                        continue;
+                    }
+
+                    for face in Face::ALL.iter() {
+                        let normal = face.normal();
+                        let across = [0, 1, 2].map(|i| (position[i] as i32 + normal[i]) as u32);
+
+                        let exposed = if octree.contains(across) {
+                            empty(across)
+                        } else {
+                            boundary_exposed
+                        };
+
+                        if exposed {
+                            units.push((position, *face, *octree.get(position).unwrap()));
+                        }
+                    }
+                }
+            }
+        }
+
+        units.sort_unstable();
+        units
+    }
+
+    #[test]
+    fn solid_cube_reports_only_outer_faces() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        for i in 0..64 {
+            octree
+                .insert([4 + (i & 3), 4 + ((i >> 2) & 3), 4 + (i >> 4)], 1)
+                .unwrap();
+        }
+
+        let mut faces = octree.exposed_faces(false).collect::<Vec<_>>();
+        faces.sort_unstable();
+
+        let mut expected = Face::ALL
+            .iter()
+            .map(|face| ([4, 4, 4], 4, *face, &1))
+            .collect::<Vec<_>>();
+        expected.sort_unstable();
+
+        assert_eq!(faces, expected);
+    }
+
+    #[test]
+    fn large_leaf_face_split_by_finer_neighbor() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        for i in 0..64 {
+            octree
+                .insert([4 + (i & 3), 4 + ((i >> 2) & 3), 4 + (i >> 4)], 1)
+                .unwrap();
+        }
+        octree.insert([8, 4, 4], 2).unwrap();
+
+        let right = octree
+            .exposed_faces(false)
+            .filter(|(min, _, face, data)| *face == Face::Right && min[0] < 8 && **data == 1)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            right,
+            vec![
+                ([7, 5, 4], 1, Face::Right, &1),
+                ([7, 4, 5], 1, Face::Right, &1),
+                ([7, 5, 5], 1, Face::Right, &1),
+                ([6, 6, 4], 2, Face::Right, &1),
+                ([6, 4, 6], 2, Face::Right, &1),
+                ([6, 6, 6], 2, Face::Right, &1),
+            ]
+        );
+    }
+
+    #[test]
+    fn boundary_convention() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(4).unwrap()).unwrap();
+        octree.insert([0, 0, 0], 1).unwrap();
+
+        assert_eq!(octree.exposed_faces(true).count(), 6);
+        assert_eq!(octree.exposed_faces(false).count(), 3);
+    }
+
+    #[test]
+    fn exposed_faces_match_dense_reference() {
+        let mut rng = XorShift::new(0xface5);
+
+        for _ in 0..20 {
+            let octree = rng.octree(16, 300, 2);
+
+            for boundary_exposed in [false, true].iter() {
+                let faces = octree
+                    .exposed_faces(*boundary_exposed)
+                    .map(|(min, dimension, face, data)| (min, dimension, face, *data));
+
+                assert_eq!(
+                    unit_faces(faces),
+                    dense_faces(&octree, [0, 0, 0], [16, 16, 16], *boundary_exposed)
+                );
+            }
+
+            for _ in 0..10 {
+                let (a, b) = (rng.position(16), rng.position(16));
+                let min = [0, 1, 2].map(|i| a[i].min(b[i]));
+                let max = [0, 1, 2].map(|i| a[i].max(b[i]) + 1);
+                let boundary_exposed = rng.below(2) == 0;
+
+                let faces = octree
+                    .exposed_faces_in_region(min, max, boundary_exposed)
+                    .map(|(min, dimension, face, data)| (min, dimension, face, *data));
+
+                assert_eq!(unit_faces(faces), dense_faces(&octree, min, max, boundary_exposed));
+            }
+        }
+    }
+
+    fn fill_cube(octree: &mut Octree<u8>, min: [u32; 3], dimension: u32, data: u8) {
+        for x in 0..dimension {
+            for y in 0..dimension {
+                for z in 0..dimension {
+                    octree.insert([min[0] + x, min[1] + y, min[2] + z], data).unwrap();
+                }
+            }
+        }
+    }
+
+    fn quad_count(mesh: &MeshData) -> usize {
+        assert_eq!(mesh.indices.len() % 6, 0);
+        assert_eq!(mesh.positions.len(), mesh.normals.len());
+        assert_eq!(mesh.positions.len() / 3, mesh.indices.len() / 6 * 4);
+        mesh.indices.len() / 6
+    }
+
+    #[test]
+    fn greedy_solid_cube_is_six_quads() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        fill_cube(&mut octree, [4, 4, 4], 4, 1);
+
+        let mesh = octree.greedy_mesh(MeshConfig::default());
+        assert_eq!(quad_count(&mesh), 6);
+        assert_eq!(mesh.indices.len() / 3, 12);
+    }
+
+    #[test]
+    fn greedy_wall_with_hole() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        for y in 0..8 {
+            for z in 0..8 {
+                octree.insert([4, y, z], 1).unwrap();
+            }
+        }
+
+        let config = MeshConfig {
+            boundary_exposed: false,
+            ..MeshConfig::default()
+        };
+
+        assert_eq!(quad_count(&octree.greedy_mesh(config)), 2);
+
+        // Each side of the wall splits into four rectangles around the hole, and the hole adds four faces.
+        octree.clear_at([4, 3, 3]).unwrap();
+        assert_eq!(quad_count(&octree.greedy_mesh(config)), 12);
+    }
+
+    #[test]
+    fn greedy_merge_values() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        octree.insert([2, 2, 2], 1).unwrap();
+        octree.insert([3, 2, 2], 2).unwrap();
+
+        let separate = octree.greedy_mesh(MeshConfig::default());
+        assert_eq!(quad_count(&separate), 10);
+
+        let merged = octree.greedy_mesh(MeshConfig {
+            merge_values: true,
+            ..MeshConfig::default()
+        });
+        assert_eq!(quad_count(&merged), 6);
+    }
+
+    #[test]
+    fn greedy_mesh_covers_exposed_faces() {
+        let mut rng = XorShift::new(0x9eed);
+
+        for _ in 0..20 {
+            let octree = rng.octree(16, 300, 3);
+            let config = MeshConfig {
+                merge_values: rng.below(2) == 0,
+                boundary_exposed: rng.below(2) == 0,
+            };
+
+            let mesh = octree.greedy_mesh(config);
+            let expected = dense_faces(&octree, [0, 0, 0], [16, 16, 16], config.boundary_exposed);
+
+            let mut area = 0.0;
+            for quad in 0..quad_count(&mesh) {
+                let corner = |i: usize| {
+                    let vertex = mesh.indices[quad * 6 + i] as usize;
+                    [0, 1, 2].map(|c| mesh.positions[vertex * 3 + c])
+                };
+                let normal = [0, 1, 2].map(|c| mesh.normals[mesh.indices[quad * 6] as usize * 3 + c]);
+
+                let (a, b, c) = (corner(0), corner(1), corner(2));
+                let (ab, ac) = ([0, 1, 2].map(|i| b[i] - a[i]), [0, 1, 2].map(|i| c[i] - a[i]));
+                let cross = [
+                    ab[1] * ac[2] - ab[2] * ac[1],
+                    ab[2] * ac[0] - ab[0] * ac[2],
+                    ab[0] * ac[1] - ab[1] * ac[0],
+                ];
+
+                // The first triangle winds counter-clockwise around the normal, and spans half of the quad.
+                let dot = (0..3).map(|i| cross[i] * normal[i]).sum::<f32>();
+                assert!(dot > 0.0);
+                area += dot;
+            }
+
+            assert_eq!(area as usize, expected.len());
+        }
+    }
+
+    #[test]
+    fn cube_mesh_triangle_counts() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        octree.insert([2, 2, 2], 1).unwrap();
+        assert_eq!(octree.cube_mesh(|_| true).indices.len() / 3, 12);
+
+        octree.insert([2, 3, 2], 1).unwrap();
+        assert_eq!(octree.cube_mesh(|_| true).indices.len() / 3, 20);
+    }
+
+    #[test]
+    fn cube_mesh_keeps_large_leaves_whole() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        fill_cube(&mut octree, [8, 8, 8], 8, 1);
+        assert_eq!(quad_count(&octree.cube_mesh(|_| true)), 6);
+
+        // A finer neighbor splits only the face it touches, into the three exposed quads at each level.
+        octree.insert([7, 8, 8], 2).unwrap();
+        assert_eq!(quad_count(&octree.cube_mesh(|_| true)), 5 + 9 + 5);
+        assert_eq!(quad_count(&octree.cube_mesh(|data| *data == 1)), 5 + 9);
+    }
+}
@@ -0,0 +1,498 @@
+use crate::{
+    node::{majority, majority_ignoring, volume, OCTREE_CHILDREN},
+    LodPolicy, Node, NodeRef, Octree,
+};
+
+use alloc::vec::Vec;
+use core::{fmt::Debug, hash::Hash, num::NonZeroU32};
+
+/// Builds the `Node` holding `node` at half its resolution, each 2*2*2 block of voxels becoming a single voxel
+/// holding the data `reduce` returns for it.
+fn halve<T, F>(node: NodeRef<'_, T>, background: T, reduce: &F) -> Node<T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+    F: Fn(&[(T, u64)]) -> T,
+{
+    if let Some(data) = node.leaf_data() {
+        return Node::leaf(*data);
+    }
+
+    if node.dimension() == 2 {
+        return Node::leaf(node.reduce(background, reduce));
+    }
+
+    let mut octants = [(); OCTREE_CHILDREN].map(|_| Node::leaf(background));
+
+    for (octant, (_, child)) in octants.iter_mut().zip(node.octants()) {
+        if let Some(child) = child {
+            *octant = halve(child, background, reduce);
+        }
+    }
+
+    Node::from_octants(octants, background)
+}
+
+/// How much of the block a voxel of a coarsened `Octree` was formed from disagrees with the data it holds.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LodError {
+    /// The number of voxels of the block holding data other than the chosen data.
+    pub disagreeing: u64,
+    /// The number of voxels in the block.
+    pub volume: u64,
+}
+
+impl LodError {
+    /// Returns the fraction of the block disagreeing with the chosen data, or zero for an empty block.
+    pub fn fraction(&self) -> f32 {
+        if self.volume == 0 {
+            0.0
+        } else {
+            self.disagreeing as f32 / self.volume as f32
+        }
+    }
+}
+
+/// Builds the `Node` at `1 / block` the resolution of `node`, holding the error of each of its voxels against
+/// the data `mip` holds there.
+///
+/// Leaves no smaller than a block are carried over whole into every coarser level, so they have no error.
+/// Blocks which agree entirely with `mip` are left as the default `LodError`, so that uniform regions stay
+/// single leaves.
+fn errors<T>(node: NodeRef<'_, T>, block: u32, background: T, mip: &Octree<T>) -> Node<LodError>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    if node.is_leaf() {
+        return Node::leaf(LodError::default());
+    }
+
+    if node.dimension() == block {
+        let min = node.min_position();
+        let position = [min.x / block, min.y / block, min.z / block];
+        let chosen = mip.get(position).copied().unwrap_or(background);
+        let leaves = node.leaves(background);
+        let error = LodError {
+            disagreeing: leaves
+                .iter()
+                .filter(|(data, _)| *data != chosen)
+                .fold(0, |sum, (_, volume)| sum.saturating_add(*volume)),
+            volume: volume(block),
+        };
+
+        return match error.disagreeing {
+            0 => Node::leaf(LodError::default()),
+            _ => Node::leaf(error),
+        };
+    }
+
+    let mut octants = [(); OCTREE_CHILDREN].map(|_| Node::leaf(LodError::default()));
+
+    for (octant, (_, child)) in octants.iter_mut().zip(node.octants()) {
+        if let Some(child) = child {
+            *octant = errors(child, block, background, mip);
+        }
+    }
+
+    Node::from_octants(octants, LodError::default())
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Returns a chain of `levels` progressively coarser copies of the `Octree`, starting with the `Octree`
+    /// itself.
+    ///
+    /// Element `i` has a dimension of `dimension >> i`, and each of its voxels holds the most common data of
+    /// the 2*2*2 block it covers in element `i - 1`, by the same rule as [`Octree::lod_down`]: unwritten
+    /// voxels count as the background, and ties go to the voxel at the minimum corner of the block. Each
+    /// element is built from the one before it, and leaves are carried over whole, so uniform regions stay
+    /// single leaves throughout. The chain holds at most as many elements as there are LOD levels, and
+    /// never fewer than one unless `levels` is zero. The `Octree` itself is left untouched.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([0, 0, 0], 2).unwrap();
+    /// octree.insert([0, 0, 1], 2).unwrap();
+    /// octree.insert([0, 1, 0], 1).unwrap();
+    /// octree.insert([0, 1, 1], 2).unwrap();
+    /// octree.insert([1, 0, 0], 1).unwrap();
+    /// octree.insert([1, 0, 1], 2).unwrap();
+    /// octree.insert([1, 1, 0], 2).unwrap();
+    /// octree.insert([1, 1, 1], 1).unwrap();
+    ///
+    /// let chain = octree.build_mip_chain(3);
+    /// assert_eq!(chain.len(), 3);
+    /// assert_eq!(chain[1].dimension(), 16);
+    /// assert!(matches!(chain[1].get([0, 0, 0]), Some(2)));
+    /// assert_eq!(chain[2].dimension(), 8);
+    /// ```
+    pub fn build_mip_chain(&self, levels: u32) -> Vec<Octree<T>> {
+        self.build_mip_chain_with(levels, majority)
+    }
+
+    /// Returns a chain of `levels` progressively coarser copies of the `Octree`, forming each voxel with
+    /// `reduce`.
+    ///
+    /// Behaves as [`Octree::build_mip_chain`], but each voxel holds the data `reduce` returns for the 2*2*2
+    /// block it covers, as for [`Octree::lod_down_with`]. Leaves covering whole blocks are carried over
+    /// without calling `reduce`.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u16>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([0, 0, 0], 80).unwrap();
+    /// octree.insert([1, 1, 1], 160).unwrap();
+    ///
+    /// let average = |leaves: &[(u16, u64)]| {
+    ///     let total = leaves.iter().map(|(data, volume)| *data as u64 * volume).sum::<u64>();
+    ///     (total / leaves.iter().map(|(_, volume)| volume).sum::<u64>()) as u16
+    /// };
+    ///
+    /// let chain = octree.build_mip_chain_with(2, average);
+    /// assert!(matches!(chain[1].get([0, 0, 0]), Some(30)));
+    /// ```
+    pub fn build_mip_chain_with(&self, levels: u32, reduce: impl Fn(&[(T, u64)]) -> T) -> Vec<Octree<T>> {
+        let levels = levels.min(self.max_lod_level().max(1));
+        let mut chain = Vec::new();
+
+        if levels > 0 {
+            chain.push(self.with_root(self.root().to_node()));
+        }
+
+        for _ in 1..levels {
+            let previous: &Octree<T> = chain.last().unwrap();
+            let dimension = previous.dimension() / 2;
+            let root = halve(previous.root(), self.background(), &reduce);
+
+            let mut octree =
+                Octree::new_with_background(NonZeroU32::new(dimension).unwrap(), self.background()).unwrap();
+            *octree.root_mut() = root;
+            chain.push(octree);
+        }
+
+        chain
+    }
+
+    /// Returns a chain of `levels` progressively coarser copies of the `Octree`, forming each voxel by the
+    /// given policy.
+    ///
+    /// Behaves as [`Octree::build_mip_chain`], with each block coarsened as by
+    /// [`Octree::lod_down_with_policy`].
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, LodPolicy, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([0, 0, 0], 1).unwrap();
+    ///
+    /// let chain = octree.build_mip_chain_with_policy(3, LodPolicy::IgnoreBackground);
+    /// assert!(matches!(chain[2].get([0, 0, 0]), Some(1)));
+    /// assert!(matches!(chain[2].get([1, 1, 1]), Some(0)));
+    /// ```
+    pub fn build_mip_chain_with_policy(&self, levels: u32, policy: LodPolicy) -> Vec<Octree<T>> {
+        let background = self.background();
+
+        match policy {
+            LodPolicy::Majority => self.build_mip_chain(levels),
+            LodPolicy::IgnoreBackground => {
+                self.build_mip_chain_with(levels, |values| majority_ignoring(values, background))
+            }
+        }
+    }
+
+    /// Returns a chain of `levels` progressively coarser copies of the `Octree`, as by
+    /// [`Octree::build_mip_chain_with`], each paired with an `Octree` of the same dimension recording the
+    /// error of each of its voxels.
+    ///
+    /// The error of a voxel of element `i` counts the voxels of the `Octree` it covers which hold data other
+    /// than the data it was given, weighted by the volume of the leaves holding them, so that it measures
+    /// how much detail was lost against the full resolution rather than against element `i - 1`. Unwritten
+    /// voxels count as the background. Voxels with no error, including every voxel of element 0, are left
+    /// as the default `LodError`.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, LodError, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([0, 0, 0], 2).unwrap();
+    /// octree.insert([0, 0, 1], 2).unwrap();
+    /// octree.insert([0, 1, 0], 2).unwrap();
+    ///
+    /// let chain = octree.build_mip_chain_with_errors(2, |leaves| leaves[0].0);
+    /// let (mip, errors) = &chain[1];
+    ///
+    /// assert!(matches!(mip.get([0, 0, 0]), Some(2)));
+    /// assert_eq!(errors.get([0, 0, 0]), Some(&LodError { disagreeing: 5, volume: 8 }));
+    /// assert_eq!(errors.get([0, 0, 0]).unwrap().fraction(), 0.625);
+    /// ```
+    pub fn build_mip_chain_with_errors(
+        &self,
+        levels: u32,
+        reduce: impl Fn(&[(T, u64)]) -> T,
+    ) -> Vec<(Octree<T>, Octree<LodError>)> {
+        let chain = self.build_mip_chain_with(levels, &reduce);
+        let mut errors = Vec::new();
+
+        for (level, mip) in chain.iter().enumerate() {
+            let mut octree = Octree::new(NonZeroU32::new(mip.dimension()).unwrap()).unwrap();
+
+            if level > 0 {
+                *octree.root_mut() = self::errors(self.root(), 1 << level, self.background(), mip);
+            }
+
+            errors.push(octree);
+        }
+
+        chain.into_iter().zip(errors).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{node::majority, test_utils::XorShift, LodError, LodPolicy, Octree};
+
+    use alloc::vec::Vec;
+    use core::num::NonZeroU32;
+
+    #[test]
+    fn uniform_tree_gives_uniform_mips() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        for x in 0..16 {
+            for y in 0..16 {
+                for z in 0..16 {
+                    octree.insert([x, y, z], 3).unwrap();
+                }
+            }
+        }
+
+        for mip in octree.build_mip_chain(4).iter() {
+            let dimension = mip.dimension();
+            let spans = mip.query_region([0; 3], [dimension; 3]).collect::<Vec<_>>();
+            assert_eq!(spans, vec![([0; 3], [dimension; 3], Some(&3))]);
+        }
+    }
+
+    #[test]
+    fn checkerboard_ties_go_to_minimum_corner() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        for x in 0..8 {
+            for y in 0..8 {
+                for z in 0..8 {
+                    octree.insert([x, y, z], 1 + (x + y + z) as u8 % 2).unwrap();
+                }
+            }
+        }
+
+        // Each block holds four of each value, so the value at its minimum corner wins, and every corner
+        // lies on an even square.
+        let chain = octree.build_mip_chain(10);
+        assert_eq!(chain.len(), 3);
+        assert_eq!(chain.iter().map(Octree::dimension).collect::<Vec<_>>(), vec![8, 4, 2]);
+
+        for x in 0..4 {
+            for y in 0..4 {
+                for z in 0..4 {
+                    assert!(matches!(chain[1].get([x, y, z]), Some(1)));
+                }
+            }
+        }
+        assert!(matches!(chain[2].get([1, 1, 1]), Some(1)));
+    }
+
+    #[test]
+    fn mips_match_get_at_lod() {
+        let mut rng = XorShift::new(0x3195);
+
+        for _ in 0..10 {
+            let octree = rng.octree(32, 2000, 3);
+            let hash = octree.content_hash();
+            let chain = octree.build_mip_chain(5);
+
+            // Blocks which coarsen to the background may be left unwritten.
+            for (level, mip) in chain.iter().enumerate() {
+                for _ in 0..200 {
+                    let position = rng.position(mip.dimension());
+                    let original = position.map(|c| c << level);
+
+                    assert_eq!(
+                        *mip.get(position).unwrap_or(&0),
+                        octree.get_at_lod(original, level as u32).unwrap_or(0)
+                    );
+                }
+            }
+
+            assert_eq!(octree.content_hash(), hash);
+        }
+    }
+
+    /// Averages the leaves of a block, weighted by the volume each covers.
+    fn average(leaves: &[(u16, u64)]) -> u16 {
+        let total = leaves.iter().map(|(data, volume)| *data as u64 * volume).sum::<u64>();
+        (total / leaves.iter().map(|(_, volume)| volume).sum::<u64>()) as u16
+    }
+
+    #[test]
+    fn averaging_gradient() {
+        let mut octree = Octree::<u16>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        for x in 0..8 {
+            for y in 0..8 {
+                for z in 0..8 {
+                    octree.insert([x, y, z], 4 * x as u16).unwrap();
+                }
+            }
+        }
+
+        // Each level holds the mean of the gradient over the block it covers.
+        let chain = octree.build_mip_chain_with(3, average);
+        for x in 0..4 {
+            assert!(matches!(chain[1].get([x, 1, 2]), Some(data) if *data == 8 * x as u16 + 2));
+        }
+        for x in 0..2 {
+            assert!(matches!(chain[2].get([x, 1, 0]), Some(data) if *data == 16 * x as u16 + 6));
+        }
+    }
+
+    #[test]
+    fn or_of_bitflags() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        octree.insert([0, 0, 0], 0b001).unwrap();
+        octree.insert([1, 1, 0], 0b010).unwrap();
+        octree.insert([3, 3, 3], 0b100).unwrap();
+        octree.insert([5, 5, 5], 0b001).unwrap();
+
+        let or = |leaves: &[(u8, u64)]| leaves.iter().fold(0, |flags, (data, _)| flags | data);
+        let chain = octree.build_mip_chain_with(3, or);
+
+        assert!(matches!(chain[1].get([0, 0, 0]), Some(0b011)));
+        assert!(matches!(chain[1].get([1, 1, 1]), Some(0b100)));
+        assert!(matches!(chain[2].get([0, 0, 0]), Some(0b111)));
+        assert!(matches!(chain[2].get([1, 1, 1]), Some(0b001)));
+
+        // The majority chain drops the isolated flags.
+        assert!(!matches!(octree.build_mip_chain(2)[1].get([0, 0, 0]), Some(0b011)));
+    }
+
+    #[test]
+    fn uniform_region_has_no_error() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        for x in 0..4 {
+            for y in 0..4 {
+                for z in 0..4 {
+                    octree.insert([x, y, z], 5).unwrap();
+                }
+            }
+        }
+
+        let chain = octree.build_mip_chain_with_errors(3, majority);
+        assert_eq!(chain.len(), 3);
+
+        for (_, errors) in chain.iter() {
+            let dimension = errors.dimension();
+            for x in 0..dimension {
+                for y in 0..dimension {
+                    for z in 0..dimension {
+                        assert_eq!(errors.get([x, y, z]).map_or(0.0, |error| error.fraction()), 0.0);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn split_region_has_half_error() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        for x in 0..16 {
+            for y in 0..16 {
+                for z in 0..16 {
+                    octree.insert([x, y, z], 1 + (x % 8 >= 4) as u8).unwrap();
+                }
+            }
+        }
+
+        let chain = octree.build_mip_chain_with_errors(4, majority);
+        assert_eq!(chain.len(), 4);
+
+        let error = |level: usize, position| chain[level].1.get(position).map_or(0.0, |error| error.fraction());
+        assert_eq!(error(1, [2, 0, 0]), 0.0);
+        assert_eq!(error(2, [1, 1, 1]), 0.0);
+        assert_eq!(error(3, [0, 0, 0]), 0.5);
+        assert_eq!(error(3, [1, 1, 0]), 0.5);
+    }
+
+    #[test]
+    fn error_weights_leaves_by_volume() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        for x in 0..2 {
+            for y in 0..2 {
+                for z in 0..2 {
+                    octree.insert([x, y, z], 3).unwrap();
+                    if [x, y, z] != [1, 1, 1] {
+                        octree.insert([x + 2, y, z], 4).unwrap();
+                    }
+                }
+            }
+        }
+
+        let chain = octree.build_mip_chain_with_errors(3, majority);
+        let (mip, errors) = &chain[1];
+        assert!(matches!(mip.get([1, 0, 0]), Some(4)));
+        assert_eq!(
+            errors.get([1, 0, 0]),
+            Some(&LodError {
+                disagreeing: 1,
+                volume: 8
+            })
+        );
+
+        // The block holds a leaf of eight voxels and seven single voxels, all disagreeing with the
+        // background chosen for it.
+        let (mip, errors) = &chain[2];
+        assert_eq!(*mip.get([0, 0, 0]).unwrap_or(&0), 0);
+        assert_eq!(
+            errors.get([0, 0, 0]),
+            Some(&LodError {
+                disagreeing: 15,
+                volume: 64
+            })
+        );
+    }
+
+    #[test]
+    fn ignoring_background_keeps_thin_features() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        for x in 0..16 {
+            octree.insert([x, 5, 5], 3).unwrap();
+        }
+        octree.insert([12, 12, 12], 4).unwrap();
+
+        let majority = octree.build_mip_chain_with_policy(4, LodPolicy::Majority);
+        let ignoring = octree.build_mip_chain_with_policy(4, LodPolicy::IgnoreBackground);
+
+        // The fence fills a quarter of each block, so it is outvoted by the empty space around it.
+        assert_eq!(majority[1].query_region_values([0; 3], [8; 3]).count(), 0);
+        assert_eq!(majority[3].query_region_values([0; 3], [2; 3]).count(), 0);
+
+        for (level, mip) in ignoring.iter().enumerate() {
+            for x in 0..mip.dimension() {
+                assert!(matches!(mip.get([x, 5 >> level, 5 >> level]), Some(3)));
+            }
+            assert!(matches!(mip.get([12 >> level; 3]), Some(4)));
+        }
+
+        // Blocks holding nothing but the background stay empty.
+        assert!(ignoring[1].root().get([0, 0, 0].into()).is_none());
+        assert_eq!(ignoring[1].query_region_values([0; 3], [8; 3]).count(), 9);
+    }
+}
@@ -0,0 +1,372 @@
+use crate::{float, NodeRef, Octree, Vector3};
+
+use alloc::{collections::BinaryHeap, vec::Vec};
+use core::{cmp::Ordering, fmt::Debug, hash::Hash};
+
+enum Entry<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    Node(NodeRef<'a, T>),
+    Cube([u32; 3], u32, &'a T),
+}
+
+/// An entry in the search queue, keyed by the squared distance to the nearest voxel center it could hold.
+struct Queued<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    distance: f32,
+    entry: Entry<'a, T>,
+}
+
+impl<'a, T> Queued<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    /// Returns a key breaking ties between entries at equal distances, so that the search is deterministic.
+    fn tie_break(&self) -> (u8, u32, [u32; 3]) {
+        match self.entry {
+            Entry::Cube(min, dimension, _) => (0, dimension, min),
+            Entry::Node(node) => (1, node.dimension(), node.min_position().into()),
+        }
+    }
+}
+
+impl<'a, T> PartialEq for Queued<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<'a, T> Eq for Queued<'a, T> where T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash {}
+
+impl<'a, T> PartialOrd for Queued<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a, T> Ord for Queued<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    // Reversed, so that the `BinaryHeap` pops the nearest entry first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .distance
+            .partial_cmp(&self.distance)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| other.tie_break().cmp(&self.tie_break()))
+    }
+}
+
+/// A best-first search yielding the non-empty voxels of an `Octree` in order of increasing distance from a
+/// point, as `(position, data, squared distance)`.
+///
+/// Internal nodes are keyed by the distance to the nearest voxel center they cover, and leaves by the exact
+/// distance to their nearest voxel. Leaves are only split once they reach the front of the queue, so a large
+/// leaf is never scanned voxel by voxel.
+pub(crate) struct Nearest<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    point: [f32; 3],
+    background: T,
+    queue: BinaryHeap<Queued<'a, T>>,
+}
+
+impl<'a, T> Nearest<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    pub(crate) fn new(root: NodeRef<'a, T>, point: [f32; 3], background: T) -> Self {
+        let mut search = Self {
+            point,
+            background,
+            queue: BinaryHeap::new(),
+        };
+
+        if point.iter().all(|c| c.is_finite()) {
+            search.push_node(root);
+        }
+
+        search
+    }
+
+    /// Returns the squared distance from the point to the nearest voxel center within the given cube.
+    ///
+    /// The nearest center along each axis is that of the voxel containing the point, clamped to the cube.
+    fn distance(&self, min: [u32; 3], dimension: u32) -> f32 {
+        self.point
+            .iter()
+            .zip(min.iter())
+            .map(|(point, min)| {
+                let lower = *min as f32;
+                let upper = lower + (dimension - 1) as f32;
+                let d = float::floor(*point).max(lower).min(upper) + 0.5 - point;
+                d * d
+            })
+            .sum()
+    }
+
+    fn push_node(&mut self, node: NodeRef<'a, T>) {
+        let min = node.min_position().into();
+        let dimension = node.dimension();
+
+        let entry = match node.leaf_data() {
+            Some(data) if *data == self.background => return,
+            Some(data) => Entry::Cube(min, dimension, data),
+            None => Entry::Node(node),
+        };
+
+        self.queue.push(Queued {
+            distance: self.distance(min, dimension),
+            entry,
+        });
+    }
+}
+
+impl<'a, T> Iterator for Nearest<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    type Item = ([u32; 3], &'a T, f32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(Queued { distance, entry }) = self.queue.pop() {
+            match entry {
+                Entry::Node(node) => {
+                    for child in node.children() {
+                        self.push_node(child);
+                    }
+                }
+                Entry::Cube(min, 1, data) => return Some((min, data, distance)),
+                Entry::Cube(min, dimension, data) => {
+                    let half = dimension / 2;
+
+                    for i in 0..8 {
+                        let offset = Vector3::from([i & 1, (i >> 1) & 1, (i >> 2) & 1]);
+                        let min: [u32; 3] =
+                            (Vector3::from(min) + offset.component_mul(&Vector3::from([half, half, half]))).into();
+
+                        self.queue.push(Queued {
+                            distance: self.distance(min, half),
+                            entry: Entry::Cube(min, half, data),
+                        });
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Returns the non-empty voxel whose center is nearest to the given point, along with its data and the
+    /// distance to its center.
+    ///
+    /// Returns `None` if the `Octree` holds no non-default data.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// assert!(octree.nearest([4.0, 4.0, 4.0]).is_none());
+    ///
+    /// octree.insert([4, 4, 10], 1).unwrap();
+    /// octree.insert([20, 20, 20], 2).unwrap();
+    ///
+    /// assert_eq!(octree.nearest([4.5, 4.5, 4.5]), Some(([4, 4, 10], &1, 6.0)));
+    /// ```
+    pub fn nearest(&self, point: impl Into<Vector3<f32>>) -> Option<([u32; 3], &T, f32)> {
+        Nearest::new(self.root(), <[f32; 3]>::from(point.into()), self.background())
+            .next()
+            .map(|(position, data, distance)| (position, data, float::sqrt(distance)))
+    }
+
+    /// Returns up to `k` non-empty voxels nearest to the given point, sorted by the distance to their centers.
+    ///
+    /// Each result holds the position of the voxel, its data, and its distance. A large leaf may contribute
+    /// several results, and is only split as far as needed to find them. Voxels at equal distances are
+    /// ordered deterministically, and fewer than `k` results are returned if the `Octree` holds fewer
+    /// non-empty voxels.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([4, 4, 10], 1).unwrap();
+    /// octree.insert([4, 4, 0], 2).unwrap();
+    /// octree.insert([20, 20, 20], 3).unwrap();
+    ///
+    /// let nearest = octree.k_nearest([4.5, 4.5, 4.5], 2);
+    /// assert_eq!(nearest, vec![([4, 4, 0], &2, 4.0), ([4, 4, 10], &1, 6.0)]);
+    /// ```
+    pub fn k_nearest(&self, point: impl Into<Vector3<f32>>, k: usize) -> Vec<([u32; 3], &T, f32)> {
+        Nearest::new(self.root(), <[f32; 3]>::from(point.into()), self.background())
+            .take(k)
+            .map(|(position, data, distance)| (position, data, float::sqrt(distance)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_utils::XorShift, Octree};
+
+    use alloc::vec::Vec;
+    use core::num::NonZeroU32;
+
+    fn brute_force(octree: &Octree<u8>, point: [f32; 3]) -> Option<f32> {
+        let dimension = octree.dimension();
+        let mut best: Option<f32> = None;
+
+        for x in 0..dimension {
+            for y in 0..dimension {
+                for z in 0..dimension {
+                    if matches!(octree.get([x, y, z]), Some(data) if *data != 0) {
+                        let d = [x, y, z].map(|c| c as f32 + 0.5);
+                        let d = [0, 1, 2].map(|i| d[i] - point[i]);
+                        let distance = (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt();
+
+                        best = Some(best.map_or(distance, |best| best.min(distance)));
+                    }
+                }
+            }
+        }
+
+        best
+    }
+
+    #[test]
+    fn nearest_in_large_leaf() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        for i in 0..8 {
+            octree
+                .insert([8 + (i & 1), 8 + ((i >> 1) & 1), 8 + ((i >> 2) & 1)], 3)
+                .unwrap();
+        }
+
+        assert_eq!(octree.nearest([9.5, 8.5, 0.5]), Some(([9, 8, 8], &3, 8.0)));
+    }
+
+    fn brute_force_all(octree: &Octree<u8>, point: [f32; 3]) -> Vec<f32> {
+        let dimension = octree.dimension();
+        let mut distances = Vec::new();
+
+        for x in 0..dimension {
+            for y in 0..dimension {
+                for z in 0..dimension {
+                    if matches!(octree.get([x, y, z]), Some(data) if *data != 0) {
+                        let d = [x, y, z].map(|c| c as f32 + 0.5);
+                        let d = [0, 1, 2].map(|i| d[i] - point[i]);
+                        distances.push((d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt());
+                    }
+                }
+            }
+        }
+
+        distances.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        distances
+    }
+
+    #[test]
+    fn k_nearest_with_ties_and_large_k() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        assert!(octree.k_nearest([0.0, 0.0, 0.0], 3).is_empty());
+
+        octree.insert([5, 5, 4], 1).unwrap();
+        octree.insert([5, 5, 6], 2).unwrap();
+        octree.insert([5, 4, 5], 3).unwrap();
+
+        let nearest = octree.k_nearest([5.5, 5.5, 5.5], 10);
+        assert_eq!(nearest.len(), 3);
+        assert!(nearest.iter().all(|(_, _, distance)| *distance == 1.0));
+
+        let mut positions = nearest.iter().map(|(position, _, _)| *position).collect::<Vec<_>>();
+        positions.sort_unstable();
+        positions.dedup();
+        assert_eq!(positions.len(), 3);
+
+        assert!(octree.k_nearest([5.5, 5.5, 5.5], 0).is_empty());
+        assert_eq!(octree.k_nearest([5.5, 5.5, 5.5], 2), nearest[..2].to_vec());
+    }
+
+    #[test]
+    fn k_nearest_from_large_leaf() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        for i in 0..8 {
+            octree
+                .insert([8 + (i & 1), 8 + ((i >> 1) & 1), 8 + ((i >> 2) & 1)], 3)
+                .unwrap();
+        }
+
+        let nearest = octree.k_nearest([0.0, 0.0, 0.0], 8);
+        assert_eq!(nearest[0].0, [8, 8, 8]);
+        assert_eq!(nearest[7].0, [9, 9, 9]);
+        assert!(nearest.windows(2).all(|pair| pair[0].2 <= pair[1].2));
+    }
+
+    #[test]
+    fn k_nearest_matches_brute_force() {
+        let mut rng = XorShift::new(0x4ea5);
+
+        for _ in 0..20 {
+            let inserts = 1 + rng.below(60);
+            let octree = rng.octree(16, inserts, 3);
+
+            for _ in 0..10 {
+                let point = [rng.f32(-4.0, 20.0), rng.f32(-4.0, 20.0), rng.f32(-4.0, 20.0)];
+                let k = rng.below(20) as usize;
+
+                let nearest = octree.k_nearest(point, k);
+                let expected = brute_force_all(&octree, point);
+
+                assert_eq!(nearest.len(), k.min(expected.len()));
+                for ((position, data, distance), expected) in nearest.iter().zip(expected.iter()) {
+                    assert_eq!(octree.get(*position), Some(*data));
+                    assert!((distance - expected).abs() < 1e-4);
+                }
+
+                let mut positions = nearest.iter().map(|(position, _, _)| *position).collect::<Vec<_>>();
+                positions.sort_unstable();
+                positions.dedup();
+                assert_eq!(positions.len(), nearest.len());
+            }
+        }
+    }
+
+    #[test]
+    fn nearest_matches_brute_force() {
+        let mut rng = XorShift::new(0x0ea5);
+
+        for _ in 0..20 {
+            let inserts = 1 + rng.below(40);
+            let octree = rng.octree(16, inserts, 3);
+
+            for _ in 0..20 {
+                let point = [rng.f32(-4.0, 20.0), rng.f32(-4.0, 20.0), rng.f32(-4.0, 20.0)];
+                let (position, data, distance) = octree.nearest(point).unwrap();
+
+                assert_eq!(octree.get(position), Some(data));
+                assert_ne!(*data, 0);
+                assert!((distance - brute_force(&octree, point).unwrap()).abs() < 1e-4);
+            }
+        }
+    }
+}
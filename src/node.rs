@@ -1,21 +1,33 @@
-use crate::{Error, Vector3};
-
-use hashbrown::HashMap;
+use crate::{
+    brick::{self, Brick},
+    subtree::SubtreeRef,
+    trace::Counter,
+    Error, Vector3,
+};
 
-use alloc::{boxed::Box, vec::Vec};
+use alloc::{boxed::Box, vec, vec::Vec};
 use core::{
+    cell::Cell,
     convert::TryFrom,
-    fmt::Debug,
+    fmt::{self, Debug},
     hash::Hash,
-    ops::{Deref, DerefMut},
+    iter, mem,
+    ops::Range,
 };
 
 const BOUNDS_LEN: usize = 2;
 
 pub(crate) const OCTREE_CHILDREN: usize = 8;
 
+/// The most `Node`s below the root on the way to a voxel, as dimensions are powers of 2 held in a `u32`.
+pub(crate) const MAX_DEPTH: usize = 32;
+
 pub(crate) type Bounds = [Vector3<u32>; BOUNDS_LEN];
 
+/// The minimum position and dimension of an octant holding no `Node` in memory, and whether it is yet to be loaded
+/// rather than empty.
+pub(crate) type Vacancy = (Vector3<u32>, u32, bool);
+
 #[repr(usize)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 enum Octant {
@@ -47,20 +59,48 @@ impl TryFrom<usize> for Octant {
     }
 }
 
+/// Returns the index of the octant of a `Node` with octants `half` voxels across holding the given offset from its
+/// minimum position, as `y << 2 | z << 1 | x`.
+///
+/// Dimensions are powers of 2 and `Node`s are aligned to their dimension, so the octant is selected by the bit of
+/// the offset along each axis matching `half`, which is also that of the position itself.
+pub(crate) fn octant_index(offset: [u32; 3], half: u32) -> usize {
+    let [x, y, z] = offset.map(|c| (c & half != 0) as usize);
+    y << 2 | z << 1 | x
+}
+
 impl Octant {
+    /// Returns the offset of the octant from the minimum position of its `Node`, in octants along each axis.
+    ///
+    /// The variants are numbered by these offsets, as `y << 2 | z << 1 | x`.
     fn offset(&self) -> Vector3<u32> {
-        match self {
-            Self::LeftRearBase => Vector3::from([0, 0, 0]),
-            Self::RightRearBase => Vector3::from([1, 0, 0]),
-            Self::LeftRearTop => Vector3::from([0, 0, 1]),
-            Self::RightRearTop => Vector3::from([1, 0, 1]),
-            Self::LeftFrontBase => Vector3::from([0, 1, 0]),
-            Self::RightFrontBase => Vector3::from([1, 1, 0]),
-            Self::LeftFrontTop => Vector3::from([0, 1, 1]),
-            Self::RightFrontTop => Vector3::from([1, 1, 1]),
-        }
+        let i = *self as u32;
+        Vector3::from([i & 1, i >> 2 & 1, i >> 1 & 1])
+    }
+
+    /// Every octant, indexed by the bit of its offset along each axis as `y << 2 | z << 1 | x`, which is the
+    /// order of the variants.
+    const BY_BITS: [Self; OCTREE_CHILDREN] = [
+        Self::LeftRearBase,
+        Self::RightRearBase,
+        Self::LeftRearTop,
+        Self::RightRearTop,
+        Self::LeftFrontBase,
+        Self::RightFrontBase,
+        Self::LeftFrontTop,
+        Self::RightFrontTop,
+    ];
+
+    /// Returns the octant of a `Node` with octants `half` voxels across holding the given offset from its
+    /// minimum position, as selected by [`octant_index`] rather than by comparing the position with the midpoint
+    /// of the `Node`.
+    fn from_offset(offset: [u32; 3], half: u32) -> Self {
+        Self::BY_BITS[octant_index(offset, half)]
     }
 
+    /// Returns the octant around `rhs` holding `lhs` by comparing them along each axis, as octants were
+    /// selected before [`Octant::from_offset`].
+    #[cfg(test)]
     fn vector_diff(rhs: Vector3<u32>, lhs: Vector3<u32>) -> Self {
         if lhs.z < rhs.z {
             if lhs.y < rhs.y {
@@ -94,294 +134,2665 @@ impl Octant {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Returns the number of voxels in a cube `dimension` voxels across.
+///
+/// Cubes more than 2^21 voxels across hold more voxels than a `u64` counts, so their volume saturates.
+pub(crate) fn volume(dimension: u32) -> u64 {
+    u64::from(dimension).saturating_pow(3)
+}
+
+/// Returns the data covering the most volume among `values`, each paired with the volume it covers, with
+/// ties going to the data coming first.
+pub(crate) fn majority<T: PartialEq + Copy>(values: &[(T, u64)]) -> T {
+    majority_by(values, |_| true).unwrap_or(values[0].0)
+}
+
+/// Returns the data covering the most volume among `values` other than `ignored`, as by [`majority`], or
+/// `ignored` if nothing else is present.
+pub(crate) fn majority_ignoring<T: PartialEq + Copy>(values: &[(T, u64)], ignored: T) -> T {
+    majority_by(values, |data| *data != ignored).unwrap_or(ignored)
+}
+
+/// Returns the data covering the most volume among those of `values` for which `counted` holds, as by
+/// [`majority`], or `None` if there are none. Volumes are counted in place, so that nothing is allocated.
+fn majority_by<T: PartialEq + Copy>(values: &[(T, u64)], counted: impl Fn(&T) -> bool) -> Option<T> {
+    let volume = |data: &T| -> u64 {
+        values
+            .iter()
+            .filter(|(other, _)| other == data)
+            .fold(0, |sum, (_, volume)| sum.saturating_add(*volume))
+    };
+
+    let mut best: Option<(T, u64)> = None;
+    for (data, _) in values.iter().filter(|(data, _)| counted(data)) {
+        let volume = volume(data);
+        if best.is_none_or(|(_, best)| volume > best) {
+            best = Some((*data, volume));
+        }
+    }
+
+    best.map(|(data, _)| data)
+}
+
+/// How each block is coarsened by [`Octree::lod_down_with_policy`] and
+/// [`Octree::build_mip_chain_with_policy`].
+///
+/// [`Octree::lod_down_with_policy`]: crate::Octree::lod_down_with_policy
+/// [`Octree::build_mip_chain_with_policy`]: crate::Octree::build_mip_chain_with_policy
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LodPolicy {
+    /// The data covering the most volume wins, with unwritten space counted as the background, as for
+    /// [`Octree::lod_down`](crate::Octree::lod_down).
+    #[default]
+    Majority,
+    /// The data covering the most volume other than the background wins, so that sparse features survive
+    /// coarsening. Blocks hold the background only if nothing else was written in them.
+    IgnoreBackground,
+}
+
+/// Returns the bounds of each octant of the given bounds, in octant order.
+pub(crate) fn octant_bounds(bounds: Bounds) -> [Bounds; OCTREE_CHILDREN] {
+    Octant::BY_BITS.map(|octant| child_bounds(bounds, octant))
+}
+
+/// Returns the bounds of the given octant of the given bounds.
+fn child_bounds(bounds: Bounds, octant: Octant) -> Bounds {
+    let dimension = (bounds[1].x - bounds[0].x) / 2;
+    let dimension_3d = Vector3::from([dimension, dimension, dimension]);
+    let lower = bounds[0] + dimension_3d.component_mul(&octant.offset());
+
+    [lower, lower + dimension_3d]
+}
+
+/// Returns the octant of the given bounds containing the given position, if they contain it.
+fn octant_of(bounds: Bounds, position: Vector3<u32>) -> Option<Octant> {
+    if !contains(bounds, position) {
+        return None;
+    }
+
+    let min = bounds[0];
+    let half = (bounds[1].x - min.x) / 2;
+    Some(Octant::from_offset(
+        [position.x - min.x, position.y - min.y, position.z - min.z],
+        half,
+    ))
+}
+
+/// Returns whether the given bounds contain the given position.
+pub(crate) fn contains(bounds: Bounds, position: Vector3<u32>) -> bool {
+    position.x >= bounds[0].x
+        && position.x < bounds[1].x
+        && position.y >= bounds[0].y
+        && position.y < bounds[1].y
+        && position.z >= bounds[0].z
+        && position.z < bounds[1].z
+}
+
+/// Returns the position of a voxel along the Z-order curve visiting octants in octant order at every level,
+/// so that sorting voxels by it groups the voxels of each `Node` together.
+pub(crate) fn octant_order(position: [u32; 3]) -> u128 {
+    (0..32).rev().fold(0, |key, bit| {
+        let [x, y, z] = position.map(|c| (c >> bit) & 1);
+        key << 3 | (y << 2 | z << 1 | x) as u128
+    })
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 enum NodeType<T> {
     Leaf(T),
+    #[default]
     Internal,
     Simplified,
+    /// An internal `Node` whose children are all leaves, holding their data inline rather than as `Node`s of their
+    /// own. The occupancy tells which octants hold a child.
+    Packed([T; OCTREE_CHILDREN]),
 }
 
-impl<T> Default for NodeType<T> {
-    fn default() -> Self {
-        Self::Internal
+/// The child of a `Node` in one of its octants.
+///
+/// Subtrees which have not been loaded read as unwritten, except through
+/// [`Octree::with_source`](crate::Octree::with_source), and writes reaching them fail with
+/// [`Error::SubtreeNotLoaded`].
+#[derive(Default, Clone)]
+pub(crate) enum NodeSlot<T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    /// The octant has never been written, and holds the background.
+    #[default]
+    Empty,
+    /// The child is held in memory.
+    Loaded(Node<T>),
+    /// The child is held in storage, and is yet to be loaded.
+    Unloaded(SubtreeRef),
+    /// The child is held as the data of every voxel within it, standing for the `Node`s it would otherwise hold.
+    Brick(Box<Brick<T>>),
+}
+
+impl<T> Debug for NodeSlot<T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    // A brick prints as the `Node` it stands for, so that a tree prints the same whether it holds bricks or not.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("Empty"),
+            Self::Loaded(node) => f.debug_tuple("Loaded").field(node).finish(),
+            Self::Unloaded(reference) => f.debug_tuple("Unloaded").field(reference).finish(),
+            Self::Brick(brick) => f.debug_tuple("Loaded").field(&brick.to_node(brick.all())).finish(),
+        }
     }
 }
 
-struct ChildInfo {
-    dimension: u32,
-    dimension_3d: Vector3<u32>,
-    octant: Octant,
+impl<T> NodeSlot<T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    /// Returns the child, if it is held in memory.
+    pub(crate) fn get(&self) -> Option<&Node<T>> {
+        match self {
+            Self::Loaded(node) => Some(node),
+            _ => None,
+        }
+    }
+
+    /// Returns the child for modification, if it is held in memory.
+    pub(crate) fn get_mut(&mut self) -> Option<&mut Node<T>> {
+        match self {
+            Self::Loaded(node) => Some(node),
+            _ => None,
+        }
+    }
+
+    /// Returns the child, first filling an empty octant with the `Node` returned by `f`.
+    fn get_or_insert_with(&mut self, f: impl FnOnce() -> Node<T>) -> Result<&mut Node<T>, Error> {
+        if let Self::Empty = self {
+            *self = Self::Loaded(f());
+        }
+
+        self.get_mut().ok_or(Error::SubtreeNotLoaded)
+    }
+
+    /// Holds the child as the `Node`s it stands for, if it is held as a brick.
+    fn unbrick(&mut self) {
+        if let Self::Brick(brick) = self {
+            *self = Self::Loaded(brick.to_node(brick.all()));
+        }
+    }
 }
 
-#[derive(Debug, Default, Clone)]
-pub(crate) struct Node<T>
+impl<T> From<Option<Node<T>>> for NodeSlot<T>
 where
     T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
 {
-    ty: NodeType<T>,
-    bounds: Bounds,
-    children: [Box<Option<Node<T>>>; OCTREE_CHILDREN],
+    fn from(node: Option<Node<T>>) -> Self {
+        match node {
+            Some(node) => Self::Loaded(node),
+            None => Self::Empty,
+        }
+    }
 }
 
-impl<T> Node<T>
+/// Returns the data of each of the given children of the octants set in `occupancy`, in octant order, if every
+/// child held is a leaf held in memory, so that they can be held inline. Empty octants hold `T::default()`.
+fn packed<T>(children: &[NodeSlot<T>], occupancy: u8) -> Option<[T; OCTREE_CHILDREN]>
 where
     T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
 {
-    /// Creates a new `Node<T>` with the given bounds.
-    pub(crate) fn new(bounds: Bounds) -> Self {
-        Self {
-            ty: NodeType::Leaf(Default::default()),
-            bounds,
-            ..Default::default()
+    let mut values = [T::default(); OCTREE_CHILDREN];
+
+    for (octant, slot) in occupied(occupancy).zip(children.iter()) {
+        match slot {
+            NodeSlot::Empty => {}
+            NodeSlot::Loaded(child) => values[octant] = *child.leaf_data()?,
+            NodeSlot::Unloaded(_) | NodeSlot::Brick(_) => return None,
         }
     }
 
-    /// Inserts a new leaf `Node` at the given position, if possible.
-    pub(crate) fn insert(&mut self, position: Vector3<u32>, min_dimension: u32, data: T) -> Result<(), Error> {
-        if self.contains(position) {
-            if self.dimension() == min_dimension {
-                self.ty = NodeType::Leaf(data);
-            } else {
-                let ChildInfo {
-                    dimension,
-                    dimension_3d,
-                    octant,
-                } = self.child_info(position).unwrap();
+    Some(values)
+}
 
-                let bounds = self.child_bounds(dimension_3d, octant);
+/// Returns the bit mask of the octants holding a child, in octant order.
+fn occupancy_of<T>(children: &[NodeSlot<T>; OCTREE_CHILDREN]) -> u8
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    (0..OCTREE_CHILDREN)
+        .filter(|octant| !matches!(children[*octant], NodeSlot::Empty))
+        .fold(0, |occupancy, octant| occupancy | 1 << octant)
+}
 
-                let mut node = if self.children[octant as usize].as_ref().is_some() {
-                    self.children[octant as usize].take().unwrap()
-                } else {
-                    Node::<T>::new(bounds)
-                };
+/// Returns the octants set in `occupancy`, in octant order.
+pub(crate) fn occupied(occupancy: u8) -> impl Iterator<Item = usize> {
+    (0..OCTREE_CHILDREN).filter(move |octant| occupancy & 1 << octant != 0)
+}
 
-                if self.is_leaf() && dimension == min_dimension {
-                    for i in 0..OCTREE_CHILDREN {
-                        if i != octant as usize {
-                            let new_octant = Octant::try_from(i).unwrap();
-                            let bounds = self.child_bounds(dimension_3d, new_octant);
+/// Returns the index of the child in `octant` among the children of the octants set in `occupancy`.
+pub(crate) fn rank(occupancy: u8, octant: usize) -> usize {
+    (u32::from(occupancy) & ((1 << octant) - 1)).count_ones() as usize
+}
 
-                            let mut new_node = Node::<T>::new(bounds);
-                            new_node.ty = NodeType::Leaf(*self.leaf_data().unwrap());
+/// Returns the number of slots of the array holding the given number of children.
+///
+/// Arrays grow by doubling, so that a `Node` filling up octant by octant is not moved into a new array for each
+/// child.
+fn capacity(children: usize) -> usize {
+    children.next_power_of_two()
+}
 
-                            self.children[new_octant as usize] = Box::new(Some(new_node));
-                        }
-                    }
-                }
+/// The children of the octants of a `Node` holding one, in octant order, allocated together, followed by empty
+/// slots filling the rest of the array, which has at least the [`capacity`] for their number.
+///
+/// Only those octants take a slot, so that a sparse tree, whose `Node`s mostly hold a single child, does not pay
+/// for eight.
+type Children<T> = Box<[NodeSlot<T>]>;
 
-                node.insert(position, min_dimension, data).unwrap();
+/// The number of sizes arrays of children come in, from a single slot up to one for every octant.
+const CAPACITIES: usize = OCTREE_CHILDREN.trailing_zeros() as usize + 1;
 
-                self.children[octant as usize] = Box::new(Some(node));
-                self.ty = NodeType::Internal;
-            }
+/// Arrays of children freed by edits, or outgrown by `Node`s gaining children, kept for later edits to reuse
+/// rather than returned to the allocator.
+///
+/// Arrays are kept apart by [`capacity`]. At most `capacity` arrays are kept in all. A default pool keeps none, so
+/// edits given one allocate and free as usual.
+#[derive(Default)]
+pub(crate) struct NodePool<T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    free: [Vec<Children<T>>; CAPACITIES],
+    capacity: usize,
+}
 
-            self.simplify();
-            Ok(())
-        } else {
-            Err(Error::InvalidPosition {
-                x: position.x,
-                y: position.y,
-                z: position.z,
-            })
+impl<T> NodePool<T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    /// Creates a new, empty `NodePool<T>` keeping at most `capacity` arrays.
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            free: Default::default(),
+            capacity,
         }
     }
 
-    /// Removes the `Node` at the given position, if possible.
-    pub(crate) fn clear(&mut self, position: Vector3<u32>, min_dimension: u32) -> Result<(), Error> {
-        if self.contains(position) {
-            let ChildInfo {
-                dimension,
-                dimension_3d,
-                octant,
-            } = self.child_info(position).unwrap();
+    /// Returns the number of arrays kept for reuse.
+    pub(crate) fn len(&self) -> usize {
+        self.free.iter().map(Vec::len).sum()
+    }
 
-            if self.is_leaf() && dimension == min_dimension {
-                for i in 0..OCTREE_CHILDREN {
-                    let (octant, data) = if i != octant as usize {
-                        (Octant::try_from(i).unwrap(), *self.leaf_data().unwrap())
-                    } else {
-                        (octant, Default::default())
-                    };
+    /// Frees all but `len` of the arrays kept for reuse, keeping the largest.
+    pub(crate) fn shrink(&mut self, mut len: usize) {
+        for free in self.free.iter_mut().rev() {
+            free.truncate(len);
+            free.shrink_to_fit();
+            len -= free.len();
+        }
+    }
 
-                    let bounds = self.child_bounds(dimension_3d, octant);
-                    let mut node = Node::<T>::new(bounds);
-                    node.ty = NodeType::Leaf(data);
+    /// Returns an array of empty slots for at least the given number of children, reusing the smallest freed one
+    /// large enough if there is any.
+    fn take(&mut self, children: usize) -> Children<T> {
+        let capacity = capacity(children);
+        self.free[capacity.trailing_zeros() as usize..]
+            .iter_mut()
+            .find_map(Vec::pop)
+            .unwrap_or_else(|| iter::repeat_with(NodeSlot::default).take(capacity).collect())
+    }
 
-                    self.children[i].deref_mut().replace(node);
-                }
-            } else if self.children[octant as usize].as_ref().is_some() {
-                let mut child = self.children[octant as usize].take().unwrap();
-                child.clear(position, min_dimension).unwrap();
+    /// Returns an array holding the child `f` returns for each octant set in `occupancy`, taken as by
+    /// [`NodePool::take`].
+    fn collect(&mut self, occupancy: u8, mut f: impl FnMut(usize) -> NodeSlot<T>) -> Children<T> {
+        let mut children = self.take(occupancy.count_ones() as usize);
+        for (slot, octant) in children.iter_mut().zip(occupied(occupancy)) {
+            *slot = f(octant);
+        }
 
-                child.ty = if self.is_leaf() || dimension == min_dimension {
-                    NodeType::Leaf(Default::default())
-                } else {
-                    NodeType::Internal
-                };
+        children
+    }
+
+    /// Empties the given children, along with every array below them, and keeps them while there is room.
+    fn recycle(&mut self, mut children: Children<T>) {
+        if self.len() >= self.capacity {
+            return;
+        }
 
-                self.children[octant as usize].deref_mut().replace(child);
+        for slot in children.iter_mut() {
+            if let NodeSlot::Loaded(Node {
+                children: Some(children),
+                ..
+            }) = mem::take(slot)
+            {
+                self.recycle(children);
             }
+        }
 
-            Ok(())
-        } else {
-            Err(Error::InvalidPosition {
-                x: position.x,
-                y: position.y,
-                z: position.z,
-            })
+        let room = self.capacity.saturating_sub(self.len());
+        if room != 0 {
+            // A list growing reserves room for every array the pool may still keep, so that lists taking turns
+            // as `Node`s fill up and empty do not each grow a little at a time.
+            let free = &mut self.free[children.len().trailing_zeros() as usize];
+            if free.len() == free.capacity() {
+                free.reserve_exact(room);
+            }
+            free.push(children);
         }
     }
+}
 
-    /// Gets data from a `Node` at the given position, if possible.
-    pub(crate) fn get(&self, position: Vector3<u32>) -> Option<&T> {
-        if self.contains(position) {
-            return match &self.ty {
-                NodeType::Leaf(data) => Some(data),
-                _ => {
-                    let ChildInfo {
-                        dimension: _,
-                        dimension_3d: _,
-                        octant,
-                    } = self.child_info(position).unwrap();
-
-                    match self.children[octant as usize].deref() {
-                        Some(child) => child.get(position),
-                        _ => None,
-                    }
-                }
-            };
+impl<T> Debug for NodePool<T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    // The arrays kept are all empty, so only their number is of interest.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NodePool")
+            .field("len", &self.len())
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+/// A node of an `Octree`, holding either the data of every voxel within it or a child in each of its octants.
+///
+/// A `Node` does not keep its bounds, which follow from the path down to it, so they are carried alongside it
+/// instead: by [`NodeRef`] when reading, and as an argument to those methods needing them when modifying.
+///
+/// An internal `Node` whose children are all leaves holds their data inline instead, as most `Node`s just above
+/// single voxels do, rather than allocating a `Node` for each. Such children are only reached through
+/// [`NodeRef`], and methods handing out children for modification hold them as `Node`s of their own first. The
+/// same goes for children held as bricks, though edits write those in place.
+#[derive(Default, Clone)]
+pub(crate) struct Node<T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    ty: NodeType<T>,
+    /// The children of the octants holding one, allocated together, or `None` if every octant is empty.
+    children: Option<Children<T>>,
+    /// The bit of each octant holding a child, whether held in memory or not, in octant order.
+    occupancy: u8,
+    /// Whether the `Node`, or one below it, may have been modified since [`Node::simplify_recursive`] last
+    /// simplified it. Leaves and `Node`s without children start out clean, and those built from given children
+    /// dirty.
+    dirty: bool,
+}
+
+impl<T> Debug for Node<T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    // A `Node` does not know its bounds, so its children are keyed by octant. The dirty flag says nothing about the
+    // contents of the `Node`, so two `Node`s holding the same tree print the same, as do children held inline or as
+    // bricks and the `Node`s they stand for.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        DebugNode {
+            node: self,
+            depth: 0,
+            budget: &DebugBudget::new(f),
         }
+        .fmt(f)
+    }
+}
+
+/// The most levels below the `Node` printed first which `Debug` prints without the alternate flag.
+const SUMMARY_DEPTH: u32 = 3;
 
-        None
+/// The most `Node`s below the `Node` printed first which `Debug` prints without the alternate flag.
+const SUMMARY_NODES: usize = 64;
+
+/// How much more of a tree `Debug` prints: all of it with the alternate flag (`{:#?}`), and otherwise no more than
+/// [`SUMMARY_DEPTH`] levels and [`SUMMARY_NODES`] `Node`s, with `..` in place of the rest.
+pub(crate) struct DebugBudget {
+    depth: u32,
+    nodes: Cell<usize>,
+}
+
+impl DebugBudget {
+    pub(crate) fn new(f: &fmt::Formatter<'_>) -> Self {
+        match f.alternate() {
+            true => Self {
+                depth: u32::MAX,
+                nodes: Cell::new(usize::MAX),
+            },
+            false => Self {
+                depth: SUMMARY_DEPTH,
+                nodes: Cell::new(SUMMARY_NODES),
+            },
+        }
     }
 
-    /// Simplifies the `Node`.
-    ///
-    /// If all children are leaf `Node`s with identical data, destroy all children,
-    /// and mark the `Node` as a leaf containing that data.
-    pub(crate) fn simplify(&mut self) -> bool {
-        let mut data = None;
+    /// Takes a `Node` from the budget, returning whether there was one left.
+    fn take(&self) -> bool {
+        let nodes = self.nodes.get();
+        self.nodes.set(nodes.saturating_sub(1));
+        nodes > 0
+    }
+}
 
-        for i in 0..OCTREE_CHILDREN {
-            if let Some(child) = self.children[i].deref() {
-                if child.is_leaf() {
-                    let leaf_data = child.leaf_data();
+/// Prints a `Node` as a leaf's data, or as a map from the octant of each child to the child.
+struct DebugNode<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    node: &'a Node<T>,
+    depth: u32,
+    budget: &'a DebugBudget,
+}
 
-                    if data.as_ref().is_none() {
-                        data = leaf_data;
-                    } else if *data.as_ref().unwrap() != leaf_data.unwrap() {
-                        return false;
-                    }
-                }
-            } else if self.ty == NodeType::Internal {
-                return false;
-            }
+impl<T> Debug for DebugNode<'_, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(data) = self.node.leaf_data() {
+            return data.fmt(f);
         }
 
-        if data.is_some() {
-            self.ty = NodeType::Leaf((*data.unwrap()).clone());
+        let mut map = f.debug_map();
+        if self.depth >= self.budget.depth {
+            return map.finish_non_exhaustive();
         }
 
-        self.children.fill(Box::new(None));
-        true
-    }
+        for octant in (0..OCTREE_CHILDREN).filter(|octant| self.node.occupancy & 1 << octant != 0) {
+            if !self.budget.take() {
+                return map.finish_non_exhaustive();
+            }
 
-    /// Returns a higher LOD of the current `Node`.
-    ///
-    /// For all children of a leaf `Node`, take the most common data of all children,
-    /// destroy all children, and mark the `Node` as a leaf containing that data.
-    pub(crate) fn lod(&mut self) {
-        let mut all_data = [Default::default(); OCTREE_CHILDREN];
-        for (i, c) in self.children.iter_mut().enumerate().map(|(i, c)| (i, c.deref_mut())) {
-            if let Some(c) = c {
-                if c.is_leaf() {
-                    let leaf_data = c.leaf_data();
-
-                    if leaf_data.is_some() {
-                        all_data[i] = *leaf_data.unwrap();
-                    }
-                } else {
-                    c.lod();
-                }
-            } else {
-                return;
+            if let NodeType::Packed(values) = &self.node.ty {
+                map.entry(&octant, &values[octant]);
+                continue;
             }
+
+            let brick;
+            let node = match self.node.slot(octant) {
+                NodeSlot::Loaded(loaded) => loaded,
+                NodeSlot::Brick(held) => {
+                    brick = held.to_node(held.all());
+                    &brick
+                }
+                NodeSlot::Unloaded(_) => {
+                    map.entry(&octant, &format_args!("Unloaded"));
+                    continue;
+                }
+                // Octants holding a child are never empty.
+                NodeSlot::Empty => continue,
+            };
+
+            map.entry(
+                &octant,
+                &DebugNode {
+                    node,
+                    depth: self.depth + 1,
+                    budget: self.budget,
+                },
+            );
+        }
+
+        map.finish()
+    }
+}
+
+/// Prints a `Node` as a leaf's data, or as a map from the bounds of each child to the child.
+pub(crate) struct DebugTree<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    node: NodeRef<'a, T>,
+    depth: u32,
+    budget: &'a DebugBudget,
+}
+
+impl<'a, T> DebugTree<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    pub(crate) fn new(node: NodeRef<'a, T>, budget: &'a DebugBudget) -> Self {
+        Self { node, depth: 0, budget }
+    }
+}
+
+impl<T> Debug for DebugTree<'_, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(data) = self.node.leaf_data() {
+            return data.fmt(f);
         }
 
-        let mut counts = HashMap::new();
-        for data in all_data.iter() {
-            counts.entry(*data).and_modify(|e| *e += 1).or_insert(1);
+        let mut map = f.debug_map();
+        if self.depth >= self.budget.depth {
+            return map.finish_non_exhaustive();
         }
 
-        if !counts.is_empty() {
-            let mut counts = counts.iter().collect::<Vec<(&T, &i32)>>();
-            counts.sort_by(|a, b| b.1.cmp(a.1));
+        let occupancy = self.node.occupancy();
+        for (octant, bounds) in octant_bounds(self.node.bounds()).iter().enumerate() {
+            if occupancy & 1 << octant == 0 {
+                continue;
+            }
+
+            if !self.budget.take() {
+                return map.finish_non_exhaustive();
+            }
 
-            self.ty = NodeType::Leaf(*counts[0].0);
+            let key = format_args!("{}..{}", bounds[0], bounds[1]);
+            match self.node.child(octant) {
+                Some(node) => map.entry(
+                    &key,
+                    &DebugTree {
+                        node,
+                        depth: self.depth + 1,
+                        budget: self.budget,
+                    },
+                ),
+                None => map.entry(&key, &format_args!("Unloaded")),
+            };
         }
 
-        self.children.fill(Box::new(None));
+        map.finish()
     }
+}
 
-    /// Returns the dimension of the `Node`.
-    pub(crate) fn dimension(&self) -> u32 {
-        (self.bounds[0].x as i32 - self.bounds[1].x as i32).abs() as u32
+impl<T> Node<T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    /// Creates a new leaf `Node<T>` holding `T::default()`.
+    pub(crate) fn new() -> Self {
+        Self {
+            ty: NodeType::Leaf(Default::default()),
+            ..Default::default()
+        }
     }
 
-    /// Returns whether the `Node` contains the given position.
-    pub(crate) fn contains(&self, position: Vector3<u32>) -> bool {
-        position.x >= self.bounds[0].x
-            && position.x < self.bounds[1].x
-            && position.y >= self.bounds[0].y
-            && position.y < self.bounds[1].y
-            && position.z >= self.bounds[0].z
-            && position.z < self.bounds[1].z
+    /// Creates a new leaf `Node<T>` with the given data.
+    pub(crate) fn leaf(data: T) -> Self {
+        Self {
+            ty: NodeType::Leaf(data),
+            ..Default::default()
+        }
     }
 
-    /// Get leaf data from this `Node`.
-    pub(crate) fn leaf_data(&self) -> Option<&T> {
-        match &self.ty {
-            NodeType::Leaf(data) => Some(&data),
-            _ => None,
+    /// Creates a new `Node<T>` from its eight octants, in octant order.
+    ///
+    /// Background leaves are left unwritten, and the `Node` becomes a leaf if every octant holds the same
+    /// data.
+    pub(crate) fn from_octants(octants: [Node<T>; OCTREE_CHILDREN], background: T) -> Self {
+        if let Some(data) = octants[0].leaf_data().copied() {
+            if octants.iter().all(|octant| octant.leaf_data() == Some(&data)) {
+                return Self::leaf(data);
+            }
         }
+
+        Self::from_slots(octants.map(|octant| match octant.leaf_data() {
+            Some(data) if *data == background => NodeSlot::Empty,
+            _ => NodeSlot::Loaded(octant),
+        }))
     }
 
-    fn child_info(&self, position: Vector3<u32>) -> Option<ChildInfo> {
-        if self.contains(position) {
-            let dimension = self.dimension() / 2;
-            let dimension_3d = Vector3::from([dimension, dimension, dimension]);
-            let midpoint = self.min_position() + dimension_3d;
-            let octant = Octant::vector_diff(midpoint, position);
+    /// Creates a new `Node<T>` with the given bounds holding the given voxels, which must lie within them.
+    ///
+    /// The `Node` is built bottom up with [`Node::from_octants`] after sorting the voxels in place, rather than
+    /// by inserting them one at a time. Where a position is given more than once, the last data given wins.
+    pub(crate) fn from_voxels(bounds: Bounds, voxels: &mut [([u32; 3], T)], background: T) -> Self {
+        voxels.sort_by_key(|(position, _)| octant_order(*position));
+        Self::from_sorted_voxels(bounds, voxels, background)
+    }
 
-            Some(ChildInfo {
-                dimension,
-                dimension_3d,
-                octant,
-            })
-        } else {
-            None
+    /// Creates a new `Node<T>` with the given bounds holding the data `f` returns for each voxel within them.
+    ///
+    /// As with [`Node::from_voxels`], the `Node` is built bottom up with [`Node::from_octants`], so a region
+    /// holding a single value becomes a leaf as soon as its octants are built, and nothing is allocated below
+    /// it. `f` is called once for each voxel, in octant order.
+    pub(crate) fn from_fn(bounds: Bounds, f: &mut impl FnMut([u32; 3]) -> T, background: T) -> Self {
+        if bounds[1].x - bounds[0].x == 1 {
+            return Self::leaf(f(bounds[0].into()));
         }
+
+        let octants = octant_bounds(bounds).map(|bounds| Self::from_fn(bounds, f, background));
+        Self::from_octants(octants, background)
     }
 
-    fn child_bounds(&self, dimension_3d: Vector3<u32>, octant: Octant) -> Bounds {
-        let lower = self.min_position() + dimension_3d.component_mul(&octant.offset());
-        let upper = lower + dimension_3d;
+    fn from_sorted_voxels(bounds: Bounds, voxels: &[([u32; 3], T)], background: T) -> Self {
+        match voxels.last() {
+            None => return Self::leaf(background),
+            Some((_, data)) if bounds[1].x - bounds[0].x == 1 => return Self::leaf(*data),
+            _ => {}
+        }
+
+        // The voxels of each octant follow those of the octants before it.
+        let mut rest = voxels;
+        let octants = octant_bounds(bounds).map(|bounds| {
+            let min: [u32; 3] = bounds[0].into();
+            let max: [u32; 3] = bounds[1].into();
+            let len =
+                rest.partition_point(|(position, _)| (0..3).all(|i| position[i] >= min[i] && position[i] < max[i]));
+
+            let (voxels, others) = rest.split_at(len);
+            rest = others;
+            Self::from_sorted_voxels(bounds, voxels, background)
+        });
 
-        [lower, upper]
+        Self::from_octants(octants, background)
     }
 
-    fn child_count(&self) -> usize {
-        self.children
-            .iter()
-            .fold(0, |acc, child| if child.deref().is_some() { acc + 1 } else { acc })
+    /// Creates a new internal `Node<T>` with the given children, in octant order.
+    ///
+    /// Unlike [`Node::from_octants`], the children are kept exactly as given.
+    pub(crate) fn branch(octants: [Option<Node<T>>; OCTREE_CHILDREN]) -> Self {
+        Self::from_slots(octants.map(NodeSlot::from))
     }
 
-    fn min_position(&self) -> Vector3<u32> {
-        self.bounds[0]
+    /// Creates a new internal `Node<T>` with the given children, in octant order, which may be held in storage.
+    ///
+    /// Children which are all leaves are held inline.
+    pub(crate) fn from_slots(children: [NodeSlot<T>; OCTREE_CHILDREN]) -> Self {
+        let occupancy = occupancy_of(&children);
+
+        if let Some(values) = packed(&children, u8::MAX).filter(|_| occupancy != 0) {
+            return Self {
+                ty: NodeType::Packed(values),
+                children: None,
+                occupancy,
+                dirty: true,
+            };
+        }
+
+        let mut slots = IntoIterator::into_iter(children).filter(|slot| !matches!(slot, NodeSlot::Empty));
+        Self {
+            ty: NodeType::Internal,
+            children: match occupancy {
+                0 => None,
+                _ => Some(NodePool::default().collect(occupancy, |_| slots.next().unwrap_or_default())),
+            },
+            occupancy,
+            dirty: true,
+        }
     }
 
-    fn is_leaf(&self) -> bool {
-        matches!(self.ty, NodeType::Leaf(_))
+    /// Inserts a new leaf `Node` at the given position within the given bounds of this `Node`, if possible.
+    ///
+    /// Regions which have never been written hold `background`. The `Node`s are walked down in a loop rather
+    /// than by recursion, noting which of those passed through have a child in every octant, and only those are
+    /// simplified afterwards, as by [`Node::simplify_path`]. As in [`NodeRef::get`], the octant at each level is
+    /// selected by one bit of the offset of the position. Arrays of children are taken from and freed into `pool`,
+    /// and children no larger than `min_dimension`, which are always leaves, are held inline where possible. A brick
+    /// reached on the way is written in place, unless `min_dimension` leaves it holding fewer leaves than voxels.
+    pub(crate) fn insert(
+        &mut self,
+        bounds: Bounds,
+        position: Vector3<u32>,
+        min_dimension: u32,
+        data: T,
+        background: T,
+        pool: &mut NodePool<T>,
+    ) -> Result<(), Error> {
+        if !contains(bounds, position) {
+            return Err(Error::InvalidPosition(position));
+        }
+
+        let min = bounds[0];
+        let offset = [position.x - min.x, position.y - min.y, position.z - min.z];
+        let mut dimension = bounds[1].x - min.x;
+        let mut path = [0; MAX_DEPTH];
+        let mut full = [false; MAX_DEPTH];
+        let mut depth = 0;
+        let mut visited = Counter::default();
+        let mut node = &mut *self;
+
+        loop {
+            node.dirty = true;
+            visited.increment();
+
+            if dimension <= min_dimension {
+                node.ty = NodeType::Leaf(data);
+                node.clear_children(pool);
+                break;
+            } else if node.leaf_data() == Some(&data) {
+                break;
+            }
+
+            dimension /= 2;
+            let octant = Octant::from_offset(offset, dimension) as usize;
+            let packed = dimension <= min_dimension && node.pack_for(octant, background, pool);
+            if !packed {
+                node.split_with(background, pool);
+            }
+
+            path[depth] = octant as u8;
+            full[depth] = node.occupancy | 1 << octant == u8::MAX;
+            depth += 1;
+
+            if packed {
+                node.set_packed(octant, data);
+                break;
+            }
+
+            if let Some(brick) = node.brick_mut(octant, min_dimension) {
+                brick.insert(position, data, background);
+                node.collapse_brick(octant);
+                break;
+            }
+
+            node = node.child_or_insert_with(octant, || Node::leaf(background), pool)?;
+        }
+
+        let merged = self.simplify_path(&path[..depth], &full[..depth], pool);
+        record!(visited = visited.get(), merged = merged.get());
+        Ok(())
+    }
+
+    /// Removes the `Node` at the given position within the given bounds of this `Node`, if possible, leaving a
+    /// leaf holding `background`.
+    ///
+    /// Regions which have never been written are left untouched. As with [`Node::insert`], the `Node`s are
+    /// walked down in a loop rather than by recursion, arrays of children are taken from and freed into `pool`,
+    /// children no larger than `min_dimension` are held inline where possible, and bricks are written in place.
+    pub(crate) fn clear(
+        &mut self,
+        bounds: Bounds,
+        position: Vector3<u32>,
+        min_dimension: u32,
+        background: T,
+        pool: &mut NodePool<T>,
+    ) -> Result<(), Error> {
+        if !contains(bounds, position) {
+            return Err(Error::InvalidPosition(position));
+        }
+
+        let min = bounds[0];
+        let offset = [position.x - min.x, position.y - min.y, position.z - min.z];
+        let mut dimension = bounds[1].x - min.x;
+        let mut path = [0; MAX_DEPTH];
+        let mut full = [false; MAX_DEPTH];
+        let mut depth = 0;
+        let mut visited = Counter::default();
+        let mut node = &mut *self;
+
+        loop {
+            node.dirty = true;
+            visited.increment();
+
+            if dimension <= min_dimension {
+                node.ty = NodeType::Leaf(background);
+                node.clear_children(pool);
+                break;
+            } else if node.leaf_data() == Some(&background) {
+                break;
+            }
+
+            dimension /= 2;
+            let octant = Octant::from_offset(offset, dimension) as usize;
+            let packed = dimension <= min_dimension && node.pack_for(octant, background, pool);
+            if !packed {
+                node.split_with(background, pool);
+                node.unpack(pool);
+            }
+
+            path[depth] = octant as u8;
+            full[depth] = node.occupancy == u8::MAX;
+
+            if packed {
+                if node.occupancy & 1 << octant == 0 {
+                    record!(visited = visited.get());
+                    return Ok(());
+                }
+
+                depth += 1;
+                node.set_packed(octant, background);
+                break;
+            }
+
+            if let Some(brick) = node.brick_mut(octant, min_dimension) {
+                depth += 1;
+                brick.clear(position, background);
+                node.collapse_brick(octant);
+                break;
+            }
+
+            // Nothing above an internal `Node` with no child here can simplify.
+            match node.slot_mut(octant) {
+                None | Some(NodeSlot::Empty) => {
+                    record!(visited = visited.get());
+                    return Ok(());
+                }
+                Some(NodeSlot::Loaded(child)) => {
+                    depth += 1;
+                    node = child;
+                }
+                Some(NodeSlot::Unloaded(_)) => return Err(Error::SubtreeNotLoaded),
+                Some(NodeSlot::Brick(_)) => unreachable!("bricks are held as `Node`s by `Node::brick_mut`"),
+            }
+        }
+
+        let merged = self.simplify_path(&path[..depth], &full[..depth], pool);
+        record!(visited = visited.get(), merged = merged.get());
+        Ok(())
+    }
+
+    /// Simplifies each `Node` on the way from this one down through the given octants, deepest first, excluding
+    /// the leaf the octants lead to.
+    ///
+    /// `full` tells, for each of those `Node`s, whether it has a child in every octant. Only such a `Node` can
+    /// simplify, and no `Node` above the first which stays internal can, so the path is only walked again for
+    /// the `Node`s which are full, and not at all if the deepest one is not. Counts the `Node`s merged into
+    /// leaves.
+    fn simplify_path(&mut self, octants: &[u8], full: &[bool], pool: &mut NodePool<T>) -> Counter {
+        let mut merged = Counter::default();
+
+        for depth in (0..octants.len()).rev() {
+            if !full[depth] {
+                break;
+            }
+
+            let mut node = &mut *self;
+            for octant in &octants[..depth] {
+                node = match node.child_mut(*octant as usize) {
+                    Some(child) => child,
+                    None => return merged,
+                };
+            }
+
+            if !node.simplify_with(pool) {
+                break;
+            }
+            merged.increment();
+        }
+
+        merged
+    }
+
+    /// Simplifies the `Node`.
+    ///
+    /// If all children are leaf `Node`s with identical data, destroy all children,
+    /// and mark the `Node` as a leaf containing that data. Otherwise, children which are all leaves are held inline.
+    pub(crate) fn simplify(&mut self) -> bool {
+        self.simplify_with(&mut NodePool::default())
+    }
+
+    /// Simplifies the `Node` as [`Node::simplify`] does, freeing its children into `pool`.
+    fn simplify_with(&mut self, pool: &mut NodePool<T>) -> bool {
+        if self.is_leaf() {
+            return true;
+        }
+
+        self.debug_assert_occupancy();
+        let values = match (&self.ty, self.children.as_deref()) {
+            (NodeType::Packed(values), _) => *values,
+            (_, Some(children)) => match packed(children, self.occupancy) {
+                Some(values) => values,
+                None => return false,
+            },
+            (_, None) => return false,
+        };
+
+        if self.occupancy == u8::MAX && values.iter().all(|data| *data == values[0]) {
+            self.ty = NodeType::Leaf(values[0]);
+            self.clear_children(pool);
+            return true;
+        }
+
+        self.ty = NodeType::Packed(values);
+        if let Some(children) = self.children.take() {
+            pool.recycle(children);
+        }
+
+        false
+    }
+
+    /// Simplifies every `Node` below and including this one, deepest first, skipping those left clean since they
+    /// were last simplified this way, and returns the number of internal `Node`s visited. Each of them merged into
+    /// a leaf is counted in `merged`.
+    ///
+    /// Any `Node` which may have been modified, along with every `Node` above it, is dirty, so only the modified
+    /// regions and their ancestors are walked.
+    pub(crate) fn simplify_recursive(&mut self, merged: &mut Counter) -> usize {
+        if !self.dirty {
+            return 0;
+        }
+
+        let mut visited = 0;
+        if !self.is_leaf() {
+            visited = 1 + self
+                .children
+                .iter_mut()
+                .flat_map(|children| children.iter_mut())
+                .filter_map(NodeSlot::get_mut)
+                .map(|child| child.simplify_recursive(merged))
+                .sum::<usize>();
+
+            if self.simplify() {
+                merged.increment();
+            }
+        }
+
+        self.dirty = false;
+        visited
+    }
+
+    /// Removes every `Node` below this one holding nothing but `background`, leaving its octant empty as though
+    /// it had never been written, and returns the number of `Node`s removed.
+    ///
+    /// An internal `Node` left without children becomes a leaf holding `background`, and so is removed by its
+    /// parent in turn. The arrays of children freed are kept in `pool`, and children held in storage are left
+    /// untouched.
+    pub(crate) fn prune(&mut self, background: T, pool: &mut NodePool<T>) -> usize {
+        if self.is_leaf() {
+            return 0;
+        }
+
+        let mut pruned = 0;
+
+        if let NodeType::Packed(values) = &self.ty {
+            for (octant, data) in values.iter().enumerate() {
+                if self.occupancy & 1 << octant != 0 && *data == background {
+                    self.occupancy &= !(1 << octant);
+                    pruned += 1;
+                }
+            }
+        } else if let Some(children) = self.children.as_deref_mut() {
+            for (octant, slot) in occupied(self.occupancy).zip(children.iter_mut()) {
+                slot.unbrick();
+                if let NodeSlot::Loaded(child) = slot {
+                    pruned += child.prune(background, pool);
+
+                    if child.leaf_data() == Some(&background) {
+                        *slot = NodeSlot::Empty;
+                        self.occupancy &= !(1 << octant);
+                        pruned += 1;
+                    }
+                }
+            }
+        }
+
+        if self.occupancy == 0 {
+            self.ty = NodeType::Leaf(background);
+            self.clear_children(pool);
+        } else if pruned != 0 {
+            self.retain_occupied();
+        }
+
+        pruned
+    }
+
+    /// Returns the number of `Node`s held in memory below and including this one, counting those a brick stands
+    /// for.
+    pub(crate) fn node_count(&self) -> usize {
+        match self.ty {
+            NodeType::Packed(_) => 1 + self.occupancy.count_ones() as usize,
+            _ => {
+                1 + self
+                    .children
+                    .iter()
+                    .flat_map(|children| children.iter())
+                    .map(|child| match child {
+                        NodeSlot::Loaded(child) => child.node_count(),
+                        NodeSlot::Brick(brick) => brick.node_count(brick.all()),
+                        _ => 0,
+                    })
+                    .sum::<usize>()
+            }
+        }
+    }
+
+    /// Returns a higher LOD of the current `Node`, which has the given bounds.
+    ///
+    /// Every `Node` no larger than `dimension` is collapsed into a leaf holding the data `reduce` returns
+    /// for the leaves below it, as by [`NodeRef::reduce`]. If `collapsed` is given, the `Node`s replaced by those
+    /// leaves are moved into it along with their bounds. Otherwise, the arrays of their children are freed into
+    /// `pool`. Counts the internal `Node`s made leaves, whether collapsed or simplified.
+    pub(crate) fn lod<F>(
+        &mut self,
+        bounds: Bounds,
+        dimension: u32,
+        background: T,
+        reduce: &F,
+        mut collapsed: Option<&mut Vec<(Bounds, Self)>>,
+        pool: &mut NodePool<T>,
+    ) -> Counter
+    where
+        F: Fn(&[(T, u64)]) -> T,
+    {
+        let mut merged = Counter::default();
+        if self.is_leaf() {
+            return merged;
+        }
+
+        if bounds[1].x - bounds[0].x <= dimension {
+            // Subtrees held in storage are unknown here, so nothing holding one is collapsed.
+            if !self.is_loaded() {
+                return merged;
+            }
+
+            let leaf = Node::leaf(NodeRef::new(self, bounds).reduce(background, reduce));
+            let node = mem::replace(self, leaf);
+
+            match collapsed {
+                Some(collapsed) => collapsed.push((bounds, node)),
+                None => {
+                    if let Some(children) = node.children {
+                        pool.recycle(children);
+                    }
+                }
+            }
+
+            merged.increment();
+        } else {
+            // The children of a packed `Node` are leaves, which are left as they are.
+            if !self.is_packed() {
+                for (bounds, child) in self.octants_mut(bounds) {
+                    merged.add(child.lod(bounds, dimension, background, reduce, collapsed.as_deref_mut(), pool));
+                }
+            }
+
+            if self.simplify_with(pool) {
+                merged.increment();
+            }
+        }
+
+        merged
+    }
+
+    /// Replaces the `Node` below this one, which has the given bounds, with `node`, which has `node_bounds`,
+    /// splitting leaves above it as needed.
+    ///
+    /// An unloaded subtree with the same bounds as `node` is replaced, but one above it fails with
+    /// [`Error::SubtreeNotLoaded`], leaving the `Node` unchanged.
+    pub(crate) fn graft(
+        &mut self,
+        bounds: Bounds,
+        node: Self,
+        node_bounds: Bounds,
+        background: T,
+    ) -> Result<(), Error> {
+        if bounds[1].x - bounds[0].x == node_bounds[1].x - node_bounds[0].x {
+            *self = node;
+            return Ok(());
+        }
+
+        self.split(background);
+        self.unpack(&mut NodePool::default());
+
+        let octant = octant_of(bounds, node_bounds[0]).unwrap();
+        let bounds = child_bounds(bounds, octant);
+        let octant = octant as usize;
+
+        if matches!(self.slot(octant), NodeSlot::Unloaded(_))
+            && bounds[1].x - bounds[0].x == node_bounds[1].x - node_bounds[0].x
+        {
+            self.remove_child(octant);
+        }
+
+        self.child_or_insert_with(octant, || Node::leaf(background), &mut NodePool::default())?
+            .graft(bounds, node, node_bounds, background)?;
+
+        self.simplify();
+        Ok(())
+    }
+
+    /// Returns whether every subtree below the `Node` is held in memory.
+    pub(crate) fn is_loaded(&self) -> bool {
+        self.children
+            .iter()
+            .flat_map(|children| children.iter())
+            .all(|child| match child {
+                NodeSlot::Empty | NodeSlot::Brick(_) => true,
+                NodeSlot::Loaded(child) => child.is_loaded(),
+                NodeSlot::Unloaded(_) => false,
+            })
+    }
+
+    /// Returns the number of bytes allocated on the heap for the `Node` and every `Node` below it.
+    pub(crate) fn heap_bytes(&self) -> usize {
+        match &self.children {
+            Some(children) => {
+                mem::size_of_val::<[NodeSlot<T>]>(children)
+                    + children
+                        .iter()
+                        .map(|child| match child {
+                            NodeSlot::Loaded(child) => child.heap_bytes(),
+                            NodeSlot::Brick(brick) => brick.heap_bytes(),
+                            _ => 0,
+                        })
+                        .sum::<usize>()
+            }
+            None => 0,
+        }
+    }
+
+    /// Collapses every `Node` below this one, which has the given bounds, no larger than `dimension` and lying
+    /// entirely outside `focus` into a leaf holding its data as by [`NodeRef::coarse_data`].
+    ///
+    /// `Node`s intersecting `focus` keep their detail. The arrays of children discarded are freed into `pool`.
+    pub(crate) fn lod_outside<F>(
+        &mut self,
+        bounds: Bounds,
+        focus: Bounds,
+        dimension: u32,
+        background: T,
+        reduce: &F,
+        pool: &mut NodePool<T>,
+    ) where
+        F: Fn(&[(T, u64)]) -> T,
+    {
+        if self.is_leaf() {
+            return;
+        }
+
+        let [lower, min, max]: [[u32; 3]; 3] = [bounds[0].into(), focus[0].into(), focus[1].into()];
+        let size = bounds[1].x - bounds[0].x;
+        let outside = (0..3).any(|i| lower[i] + size <= min[i] || lower[i] >= max[i]);
+
+        if size > dimension {
+            if !self.is_packed() {
+                for (bounds, child) in self.octants_mut(bounds) {
+                    child.lod_outside(bounds, focus, dimension, background, reduce, pool);
+                }
+            }
+
+            self.simplify_with(pool);
+        } else if outside {
+            self.ty = NodeType::Leaf(NodeRef::new(self, bounds).coarse_data(background, reduce));
+            self.clear_children(pool);
+        }
+    }
+
+    /// Get leaf data from this `Node`.
+    pub(crate) fn leaf_data(&self) -> Option<&T> {
+        match &self.ty {
+            NodeType::Leaf(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Turns a leaf `Node` into an internal `Node` with identical contents.
+    ///
+    /// Every child of a leaf becomes a leaf holding the same data, except for the children of a leaf
+    /// holding `background`, which are left unwritten.
+    pub(crate) fn split(&mut self, background: T) {
+        self.split_with(background, &mut NodePool::default());
+    }
+
+    /// Splits the `Node` as [`Node::split`] does, taking the array of its children from `pool`.
+    fn split_with(&mut self, background: T, pool: &mut NodePool<T>) {
+        if let Some(data) = self.leaf_data().copied() {
+            if data != background {
+                self.children = Some(pool.collect(u8::MAX, |_| NodeSlot::Loaded(Node::leaf(data))));
+                self.occupancy = u8::MAX;
+                self.dirty = true;
+            }
+
+            self.ty = NodeType::Internal;
+        }
+    }
+
+    /// Holds the children of this `Node` inline, first splitting it as [`Node::split_with`] does if it is a leaf, so
+    /// that the child in `octant` can be replaced by a leaf with [`Node::set_packed`]. The arrays of children freed
+    /// are kept in `pool`.
+    ///
+    /// Returns whether the children are held inline, which they cannot be if the child in `octant` is held in
+    /// storage, or any other child is anything but a leaf held in memory.
+    fn pack_for(&mut self, octant: usize, background: T, pool: &mut NodePool<T>) -> bool {
+        let mut values = [T::default(); OCTREE_CHILDREN];
+
+        match (&self.ty, self.children.as_deref_mut()) {
+            (NodeType::Packed(_), _) => return true,
+            (NodeType::Leaf(data), _) => {
+                // As when splitting, the children of a leaf holding `background` are left unwritten.
+                values = [*data; OCTREE_CHILDREN];
+                self.occupancy = if *data == background { 0 } else { u8::MAX };
+            }
+            (_, None) => {}
+            (_, Some(children)) => {
+                let occupancy = self.occupancy;
+                let index = Some(rank(occupancy, octant)).filter(|_| occupancy & 1 << octant != 0);
+                if let Some(NodeSlot::Unloaded(_)) = index.map(|index| &children[index]) {
+                    return false;
+                }
+
+                // The child in `octant` is about to be replaced, whatever it holds.
+                let child = index.map(|index| mem::take(&mut children[index]));
+                match packed(children, occupancy) {
+                    Some(packed) => values = packed,
+                    None => {
+                        if let (Some(index), Some(child)) = (index, child) {
+                            children[index] = child;
+                        }
+                        return false;
+                    }
+                }
+
+                if let Some(NodeSlot::Loaded(Node {
+                    children: Some(children),
+                    ..
+                })) = child
+                {
+                    pool.recycle(children);
+                }
+            }
+        }
+
+        if let Some(children) = self.children.take() {
+            pool.recycle(children);
+        }
+
+        self.ty = NodeType::Packed(values);
+        self.dirty = true;
+        true
+    }
+
+    /// Replaces the child in `octant` of this `Node`, which must be held inline, with a leaf holding `data`.
+    fn set_packed(&mut self, octant: usize, data: T) {
+        if let NodeType::Packed(values) = &mut self.ty {
+            values[octant] = data;
+            self.occupancy |= 1 << octant;
+            self.dirty = true;
+        }
+    }
+
+    /// Returns the child of this `Node` in the given octant, if it is held as a brick, to be written in place.
+    ///
+    /// A brick holds every voxel, so when `min_dimension` calls for larger leaves, it is held as the `Node`s it
+    /// stands for instead, and `None` is returned.
+    fn brick_mut(&mut self, octant: usize, min_dimension: u32) -> Option<&mut Brick<T>> {
+        let slot = self.slot_mut(octant)?;
+        if min_dimension > 1 {
+            slot.unbrick();
+        }
+
+        match slot {
+            NodeSlot::Brick(brick) => Some(brick),
+            _ => None,
+        }
+    }
+
+    /// Replaces the child of this `Node` in the given octant with a leaf, if it is held as a brick whose voxels
+    /// have all been written with the same data.
+    fn collapse_brick(&mut self, octant: usize) {
+        if let Some(slot) = self.slot_mut(octant) {
+            if let NodeSlot::Brick(brick) = slot {
+                if let Some(data) = brick.uniform(brick.all()).copied() {
+                    *slot = NodeSlot::Loaded(Node::leaf(data));
+                }
+            }
+        }
+    }
+
+    /// Holds the `Node` of the given dimension below this one, which has the given bounds, containing the given
+    /// position as a brick, if it is an internal `Node` with at least `threshold` voxels written below it. The
+    /// arrays of children freed are kept in `pool`.
+    pub(crate) fn make_brick(
+        &mut self,
+        bounds: Bounds,
+        position: Vector3<u32>,
+        dimension: u32,
+        threshold: u32,
+        pool: &mut NodePool<T>,
+    ) {
+        // The way down is read first, so that nothing is marked dirty unless a brick is made.
+        let mut node = NodeRef::new(self, bounds);
+        let mut path = [0; MAX_DEPTH];
+        let mut depth = 0;
+
+        while node.dimension() > dimension {
+            let (octant, child) = match octant_of(node.bounds(), position) {
+                Some(octant) => (octant, node.child(octant as usize)),
+                None => return,
+            };
+
+            node = match child {
+                Some(child) => child,
+                None => return,
+            };
+            path[depth] = octant as u8;
+            depth += 1;
+        }
+
+        if depth == 0
+            || !matches!(node.referent, Referent::Node(node) if !node.is_leaf())
+            || brick::written_voxels(node) < threshold
+        {
+            return;
+        }
+
+        let brick = Brick::from_node(node);
+        let mut parent = &mut *self;
+        for octant in &path[..depth - 1] {
+            parent = match parent.child_mut(*octant as usize) {
+                Some(child) => child,
+                None => return,
+            };
+        }
+
+        if let Some(slot) = parent.slot_mut(path[depth - 1] as usize) {
+            let slot = mem::replace(slot, NodeSlot::Brick(Box::new(brick)));
+            if let NodeSlot::Loaded(Node {
+                children: Some(children),
+                ..
+            }) = slot
+            {
+                pool.recycle(children);
+            }
+        }
+    }
+
+    /// Holds the children of this `Node` as `Node`s of their own, if they are held inline, taking the array for
+    /// them from `pool`.
+    fn unpack(&mut self, pool: &mut NodePool<T>) {
+        if let NodeType::Packed(values) = self.ty {
+            self.ty = NodeType::Internal;
+
+            if self.occupancy != 0 {
+                let children = pool.collect(self.occupancy, |octant| NodeSlot::Loaded(Node::leaf(values[octant])));
+                self.children = Some(children);
+            }
+        }
+    }
+
+    /// Holds the children of this `Node` held as bricks as the `Node`s they stand for.
+    fn unbrick(&mut self) {
+        for slot in self.children.iter_mut().flat_map(|children| children.iter_mut()) {
+            slot.unbrick();
+        }
+    }
+
+    fn clear_children(&mut self, pool: &mut NodePool<T>) {
+        if let Some(children) = self.children.take() {
+            pool.recycle(children);
+        }
+
+        self.occupancy = 0;
+    }
+
+    /// Returns an iterator over the existing children of this `Node`.
+    ///
+    /// Only the octants set in the occupancy of the `Node` are visited. Children held inline or as bricks are not
+    /// `Node`s of their own, so are not yielded. [`NodeRef::children`] yields those too, along with the bounds of
+    /// each child.
+    pub(crate) fn children(&self) -> impl Iterator<Item = &Node<T>> {
+        self.debug_assert_occupancy();
+        let mut occupancy = if self.is_packed() { 0 } else { self.occupancy };
+
+        iter::from_fn(move || {
+            while occupancy != 0 {
+                let octant = occupancy.trailing_zeros() as usize;
+                occupancy &= occupancy - 1;
+
+                if let Some(child) = self.slot(octant).get() {
+                    return Some(child);
+                }
+            }
+
+            None
+        })
+    }
+
+    /// Consumes this `Node`, which has the given bounds, returning an iterator over its children held in memory
+    /// along with the bounds of each.
+    pub(crate) fn into_octants(mut self, bounds: Bounds) -> impl Iterator<Item = (Bounds, Node<T>)> {
+        self.unpack(&mut NodePool::default());
+        self.unbrick();
+        let (occupancy, bounds) = (self.occupancy, octant_bounds(bounds));
+        self.children
+            .into_iter()
+            .flat_map(move |children| children.into_vec().into_iter().zip(occupied(occupancy)))
+            .filter_map(move |(slot, octant)| match slot {
+                NodeSlot::Loaded(node) => Some((bounds[octant], node)),
+                _ => None,
+            })
+    }
+
+    /// Returns the bounds of the octant of this `Node`, which has the given bounds, containing the given
+    /// position, along with its index and child for modification, or `None` if the position lies outside the
+    /// `Node` or its octant is empty.
+    ///
+    /// The child may be loaded through it, but not removed, as that would leave the occupancy of the `Node` stale.
+    pub(crate) fn slot_at_mut(
+        &mut self,
+        bounds: Bounds,
+        position: Vector3<u32>,
+    ) -> Option<(Bounds, usize, &mut NodeSlot<T>)> {
+        let octant = octant_of(bounds, position)?;
+        let bounds = child_bounds(bounds, octant);
+        self.unpack(&mut NodePool::default());
+        self.dirty = true;
+        let slot = self.slot_mut(octant as usize)?;
+
+        Some((bounds, octant as usize, slot))
+    }
+
+    /// Returns the child of this `Node` in the given octant, which must not hold its children inline.
+    fn slot(&self, octant: usize) -> &NodeSlot<T> {
+        debug_assert!(!self.is_packed());
+        match &self.children {
+            Some(children) if self.occupancy & 1 << octant != 0 => &children[rank(self.occupancy, octant)],
+            _ => &NodeSlot::Empty,
+        }
+    }
+
+    /// Returns whether the given octant holds a subtree yet to be loaded.
+    fn is_unloaded(&self, octant: usize) -> bool {
+        !self.is_packed() && matches!(self.slot(octant), NodeSlot::Unloaded(_))
+    }
+
+    /// Returns the child of this `Node` in the given octant for modification, or `None` if the octant is empty or
+    /// the children are held inline.
+    fn slot_mut(&mut self, octant: usize) -> Option<&mut NodeSlot<T>> {
+        match self.children.as_deref_mut() {
+            Some(children) if self.occupancy & 1 << octant != 0 => Some(&mut children[rank(self.occupancy, octant)]),
+            _ => None,
+        }
+    }
+
+    /// Holds an empty slot for the child in the given octant, which must be empty, moving the children after it
+    /// along, into an array twice as large if the one holding them is full. Arrays are taken from and freed into
+    /// `pool`.
+    fn insert_slot(&mut self, octant: usize, pool: &mut NodePool<T>) {
+        let (index, len) = (rank(self.occupancy, octant), self.child_count());
+        let children = match self.children.take() {
+            Some(children) if children.len() > len => children,
+            mut old => {
+                let mut children = pool.take(len + 1);
+                for (slot, old) in children.iter_mut().zip(old.iter_mut().flat_map(|old| old.iter_mut())) {
+                    *slot = mem::take(old);
+                }
+
+                if let Some(old) = old {
+                    pool.recycle(old);
+                }
+                children
+            }
+        };
+
+        // The slot after the last child is empty, and is rotated into place.
+        let children = self.children.insert(children);
+        children[index..=len].rotate_right(1);
+        self.occupancy |= 1 << octant;
+    }
+
+    /// Moves the children of the octants still set in the occupancy of this `Node` ahead of the slots emptied, in
+    /// octant order.
+    fn retain_occupied(&mut self) {
+        let children = self.children.as_deref_mut().unwrap_or_default();
+        let mut len = 0;
+
+        for index in 0..children.len() {
+            if !matches!(children[index], NodeSlot::Empty) {
+                children.swap(len, index);
+                len += 1;
+            }
+        }
+    }
+
+    /// Returns the child of this `Node` in the given octant for modification, first filling an empty octant with
+    /// the `Node` returned by `f`.
+    fn child_or_insert_with(
+        &mut self,
+        octant: usize,
+        f: impl FnOnce() -> Node<T>,
+        pool: &mut NodePool<T>,
+    ) -> Result<&mut Node<T>, Error> {
+        self.unpack(pool);
+        self.dirty = true;
+        if self.occupancy & 1 << octant == 0 {
+            self.insert_slot(octant, pool);
+        }
+
+        let slot = self.slot_mut(octant).expect("occupied octant without a slot");
+        slot.unbrick();
+        slot.get_or_insert_with(f)
+    }
+
+    /// Returns the child of this `Node` in the given octant for modification, if it is held in memory.
+    pub(crate) fn child_mut(&mut self, octant: usize) -> Option<&mut Node<T>> {
+        self.unpack(&mut NodePool::default());
+        self.dirty = true;
+        let slot = self.slot_mut(octant)?;
+        slot.unbrick();
+        slot.get_mut()
+    }
+
+    /// Empties the given octant of this `Node`, dropping its child.
+    pub(crate) fn remove_child(&mut self, octant: usize) {
+        self.unpack(&mut NodePool::default());
+        self.dirty = true;
+
+        if let Some(slot) = self.slot_mut(octant) {
+            *slot = NodeSlot::Empty;
+            self.occupancy &= !(1 << octant);
+        }
+
+        if self.occupancy == 0 {
+            self.children = None;
+        } else {
+            self.retain_occupied();
+        }
+    }
+
+    /// Returns an iterator over the children of this `Node` held in memory for modification, in octant order.
+    pub(crate) fn children_mut(&mut self) -> impl Iterator<Item = &mut Node<T>> {
+        self.unpack(&mut NodePool::default());
+        self.unbrick();
+        self.dirty = true;
+        self.children
+            .iter_mut()
+            .flat_map(|children| children.iter_mut())
+            .filter_map(NodeSlot::get_mut)
+    }
+
+    /// Returns an iterator over the children of this `Node`, which has the given bounds, held in memory for
+    /// modification, in octant order, along with the bounds of each.
+    pub(crate) fn octants_mut(&mut self, bounds: Bounds) -> impl Iterator<Item = (Bounds, &mut Node<T>)> {
+        self.unpack(&mut NodePool::default());
+        self.unbrick();
+        self.dirty = true;
+        let (occupancy, bounds) = (self.occupancy, octant_bounds(bounds));
+        self.children
+            .iter_mut()
+            .flat_map(move |children| children.iter_mut().zip(occupied(occupancy)))
+            .filter_map(move |(slot, octant)| Some((bounds[octant], slot.get_mut()?)))
+    }
+
+    /// Returns the bit mask of the octants of this `Node` holding a child, whether held in memory or not.
+    pub(crate) fn occupancy(&self) -> u8 {
+        self.occupancy
+    }
+
+    /// Checks, in debug builds, that the occupancy of this `Node` matches its children.
+    pub(crate) fn debug_assert_occupancy(&self) {
+        match self.ty {
+            NodeType::Packed(_) => debug_assert!(self.children.is_none()),
+            _ => {
+                let children = self.children.as_deref().unwrap_or_default();
+                let (held, empty) = children.split_at(self.child_count().min(children.len()));
+                debug_assert_eq!(held.len(), self.child_count());
+                debug_assert!(held.iter().all(|slot| !matches!(slot, NodeSlot::Empty)));
+                debug_assert!(empty.iter().all(|slot| matches!(slot, NodeSlot::Empty)));
+            }
+        }
+    }
+
+    fn child_count(&self) -> usize {
+        self.occupancy.count_ones() as usize
+    }
+
+    pub(crate) fn is_leaf(&self) -> bool {
+        matches!(self.ty, NodeType::Leaf(_))
+    }
+
+    /// Returns whether this `Node` holds its children inline.
+    pub(crate) fn is_packed(&self) -> bool {
+        matches!(self.ty, NodeType::Packed(_))
+    }
+
+    /// Returns the child of this `Node` in the given octant, if it is held in memory, whether as a `Node` of its
+    /// own, inline or as a brick.
+    fn referent(&self, octant: usize) -> Option<Referent<'_, T>> {
+        match &self.ty {
+            NodeType::Packed(values) if self.occupancy & 1 << octant != 0 => Some(Referent::Packed(&values[octant])),
+            NodeType::Packed(_) => None,
+            _ => match self.slot(octant) {
+                NodeSlot::Loaded(node) => Some(Referent::Node(node)),
+                NodeSlot::Brick(brick) => Some(Referent::Brick(brick)),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// A `Node` along with its bounds.
+///
+/// Traversals start from the root with the bounds of the `Octree`, and derive the bounds of each child from those
+/// of its parent as they go, as [`NodeRef::children`] does. A leaf held inline by its parent is not a `Node` of its
+/// own, so only its data is referred to. Only the lower corner and dimension of the bounds are kept, as traversals
+/// keep many `NodeRef`s at once.
+pub(crate) struct NodeRef<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    referent: Referent<'a, T>,
+    min: Vector3<u32>,
+    dimension: u32,
+}
+
+/// What a [`NodeRef`] refers to: either a `Node`, the data of a leaf held inline by its parent, or the brick
+/// holding the voxels of a `Node` it stands for, which are found from the bounds of the `NodeRef`.
+#[derive(Clone, Copy)]
+enum Referent<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    Node(&'a Node<T>),
+    Packed(&'a T),
+    Brick(&'a Brick<T>),
+}
+
+impl<T> Clone for NodeRef<'_, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for NodeRef<'_, T> where T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash {}
+
+impl<T> Debug for NodeRef<'_, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    // Children are keyed by their bounds, with a leaf held inline or a brick printed as the `Node` it stands for.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        DebugTree::new(*self, &DebugBudget::new(f)).fmt(f)
+    }
+}
+
+impl<'a, T> NodeRef<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    /// Creates a new `NodeRef<T>` to the given `Node`, which has the given bounds.
+    pub(crate) fn new(node: &'a Node<T>, bounds: Bounds) -> Self {
+        Self::with_referent(Referent::Node(node), bounds)
+    }
+
+    fn with_referent(referent: Referent<'a, T>, bounds: Bounds) -> Self {
+        Self {
+            referent,
+            min: bounds[0],
+            dimension: bounds[1].x - bounds[0].x,
+        }
+    }
+
+    /// Returns the `Node`, borrowed for as long as the tree holding it, or `None` for a leaf held inline by its
+    /// parent or a `Node` held as a brick.
+    pub(crate) fn node(self) -> Option<&'a Node<T>> {
+        match self.referent {
+            Referent::Node(node) => Some(node),
+            _ => None,
+        }
+    }
+
+    /// Returns a copy of the `Node`, or of the `Node` it stands for if it is held inline or as a brick.
+    pub(crate) fn to_node(self) -> Node<T> {
+        match self.referent {
+            Referent::Node(node) => node.clone(),
+            Referent::Packed(data) => Node::leaf(*data),
+            Referent::Brick(brick) => brick.to_node(self.brick_range(brick)),
+        }
+    }
+
+    /// Returns the range of the voxels of the given brick within the bounds of the `NodeRef`.
+    fn brick_range(self, brick: &Brick<T>) -> Range<usize> {
+        brick.range(self.min, self.dimension)
+    }
+
+    pub(crate) fn bounds(self) -> Bounds {
+        [self.min, self.min + Vector3::from([self.dimension; 3])]
+    }
+
+    pub(crate) fn min_position(self) -> Vector3<u32> {
+        self.min
+    }
+
+    /// Returns the dimension of the `Node`.
+    pub(crate) fn dimension(self) -> u32 {
+        self.dimension
+    }
+
+    /// Returns whether the `Node` contains the given position.
+    pub(crate) fn contains(self, position: Vector3<u32>) -> bool {
+        contains(self.bounds(), position)
+    }
+
+    /// Get leaf data from this `Node`, borrowed for as long as the tree holding it.
+    pub(crate) fn leaf_data(self) -> Option<&'a T> {
+        match self.referent {
+            Referent::Node(node) => node.leaf_data(),
+            Referent::Packed(data) => Some(data),
+            Referent::Brick(brick) => brick.uniform(self.brick_range(brick)),
+        }
+    }
+
+    pub(crate) fn is_leaf(self) -> bool {
+        self.leaf_data().is_some()
+    }
+
+    /// Returns the bit of each octant holding a child, as [`Node::occupancy`] does.
+    pub(crate) fn occupancy(self) -> u8 {
+        match self.referent {
+            Referent::Node(node) => node.occupancy(),
+            Referent::Packed(_) => 0,
+            Referent::Brick(_) if self.is_leaf() => 0,
+            Referent::Brick(brick) => brick::octants(self.brick_range(brick))
+                .enumerate()
+                .filter(|(_, range)| !brick.is_empty(range.clone()))
+                .fold(0, |occupancy, (octant, _)| occupancy | 1 << octant),
+        }
+    }
+
+    /// Returns whether every subtree below the `Node` is held in memory, as [`Node::is_loaded`] does.
+    pub(crate) fn is_loaded(self) -> bool {
+        self.node().is_none_or(Node::is_loaded)
+    }
+
+    /// Returns the number of bytes allocated on the heap below the `Node`, as [`Node::heap_bytes`] does. Bricks are
+    /// counted by the `Node` holding them.
+    pub(crate) fn heap_bytes(self) -> usize {
+        self.node().map_or(0, Node::heap_bytes)
+    }
+
+    /// Returns the number of `Node`s held in memory below and including this one, as [`Node::node_count`] does,
+    /// counting a leaf held inline as one.
+    pub(crate) fn node_count(self) -> usize {
+        match self.referent {
+            Referent::Node(node) => node.node_count(),
+            Referent::Packed(_) => 1,
+            Referent::Brick(brick) => brick.node_count(self.brick_range(brick)),
+        }
+    }
+
+    /// Returns the number of leaves held in memory below and including this `Node`, counting leaves held inline
+    /// or within bricks.
+    pub(crate) fn leaf_count(self) -> usize {
+        let (mut stack, mut count) = (vec![self], 0);
+        while let Some(node) = stack.pop() {
+            match node.is_leaf() {
+                true => count += 1,
+                false => stack.extend(node.children()),
+            }
+        }
+
+        count
+    }
+
+    /// Returns the child of this `Node` in the given octant, if it is held in memory.
+    pub(crate) fn child(self, octant: usize) -> Option<Self> {
+        let referent = match self.referent {
+            Referent::Node(node) => node.referent(octant)?,
+            Referent::Packed(_) => return None,
+            Referent::Brick(brick) => {
+                let range = brick::octants(self.brick_range(brick)).nth(octant)?;
+                if self.is_leaf() || brick.is_empty(range) {
+                    return None;
+                }
+
+                Referent::Brick(brick)
+            }
+        };
+
+        Some(Self::with_referent(
+            referent,
+            child_bounds(self.bounds(), Octant::BY_BITS[octant]),
+        ))
+    }
+
+    /// Returns an iterator over the existing children of this `Node`, as [`Node::children`] does, including
+    /// leaves held inline.
+    pub(crate) fn children(self) -> impl Iterator<Item = Self> + 'a {
+        let mut occupancy = self.occupancy();
+
+        iter::from_fn(move || {
+            while occupancy != 0 {
+                let octant = occupancy.trailing_zeros() as usize;
+                occupancy &= occupancy - 1;
+
+                if let Some(child) = self.child(octant) {
+                    return Some(child);
+                }
+            }
+
+            None
+        })
+    }
+
+    /// Returns an iterator over all eight octants of this `Node`, yielding the minimum position of each
+    /// octant along with its child, if one exists.
+    pub(crate) fn octants(self) -> impl Iterator<Item = (Vector3<u32>, Option<Self>)> + 'a {
+        (0..OCTREE_CHILDREN).map(move |octant| {
+            (
+                child_bounds(self.bounds(), Octant::BY_BITS[octant])[0],
+                self.child(octant),
+            )
+        })
+    }
+
+    /// Returns the minimum position of the octant of this `Node` containing the given position, along with
+    /// its child, if one exists.
+    pub(crate) fn octant_at(self, position: Vector3<u32>) -> Option<(Vector3<u32>, Option<Self>)> {
+        let octant = octant_of(self.bounds(), position)?;
+        Some((child_bounds(self.bounds(), octant)[0], self.child(octant as usize)))
+    }
+
+    /// Returns whether the octant of this `Node` containing the given position holds a subtree yet to be loaded.
+    pub(crate) fn is_unloaded_at(self, position: Vector3<u32>) -> bool {
+        match (self.referent, octant_of(self.bounds(), position)) {
+            (Referent::Node(node), Some(octant)) => node.is_unloaded(octant as usize),
+            _ => false,
+        }
+    }
+
+    /// Gets data from a `Node` at the given position, if possible.
+    pub(crate) fn get(self, position: Vector3<u32>) -> Option<&'a T> {
+        self.leaf_at(position)?.leaf_data()
+    }
+
+    /// Returns the leaf holding the given position, if it is held in memory.
+    pub(crate) fn leaf_at(self, position: Vector3<u32>) -> Option<Self> {
+        self.region_at(position)?.ok()
+    }
+
+    /// Returns the leaf holding the given position if it is held in memory, or else the [`Vacancy`] holding it.
+    ///
+    /// Bounds are only checked here. Below, every `Node` is half as large as its parent, so the octant holding
+    /// the position is selected by one bit of its offset from this `Node` along each axis, and the bounds of the
+    /// leaf or octant found are those of the offsets sharing every bit above its dimension.
+    pub(crate) fn region_at(self, position: Vector3<u32>) -> Option<Result<Self, Vacancy>> {
+        if !self.contains(position) {
+            return None;
+        }
+
+        let min = self.min_position();
+        let offset = [position.x - min.x, position.y - min.y, position.z - min.z];
+        let corner = |dimension: u32| min + Vector3::from(offset.map(|c| c & !(dimension - 1)));
+        let mut referent = self.referent;
+        let mut dimension = self.dimension();
+
+        while let Referent::Node(node) = referent {
+            if node.is_leaf() {
+                break;
+            }
+
+            dimension /= 2;
+            let octant = octant_index(offset, dimension);
+            referent = match node.referent(octant) {
+                Some(referent) => referent,
+                None => return Some(Err((corner(dimension), dimension, node.is_unloaded(octant)))),
+            };
+        }
+
+        if let Referent::Brick(brick) = referent {
+            dimension = match brick.leaf_dimension(position, dimension) {
+                Some(dimension) => dimension,
+                None => return Some(Err((corner(1), 1, false))),
+            };
+        }
+
+        Some(Ok(Self {
+            referent,
+            min: corner(dimension),
+            dimension,
+        }))
+    }
+
+    /// Returns a copy of the `Node` as it would be after calling [`Node::lod`] on it, without modifying it or
+    /// copying the detail which would be discarded.
+    pub(crate) fn lod_copy<F>(self, dimension: u32, background: T, reduce: &F) -> Node<T>
+    where
+        F: Fn(&[(T, u64)]) -> T,
+    {
+        if self.is_leaf() {
+            return self.to_node();
+        }
+
+        if self.dimension() <= dimension {
+            return Node::leaf(self.reduce(background, reduce));
+        }
+
+        let mut node = Node::from_slots([0, 1, 2, 3, 4, 5, 6, 7].map(|octant| {
+            self.child(octant)
+                .map(|child| child.lod_copy(dimension, background, reduce))
+                .into()
+        }));
+
+        node.simplify();
+        node
+    }
+
+    /// Returns the data `reduce` returns for the leaves below the `Node`, as listed by [`NodeRef::leaves`],
+    /// without modifying it. A leaf `Node` returns its own data.
+    ///
+    /// A `Node` with only leaves below it, as [`Node::lod`] mostly collapses when coarsening one level at a
+    /// time, lists its eight octants in an array rather than allocating.
+    pub(crate) fn reduce<F>(self, background: T, reduce: &F) -> T
+    where
+        F: Fn(&[(T, u64)]) -> T,
+    {
+        if let Some(data) = self.leaf_data() {
+            return *data;
+        }
+
+        match self.octant_leaves(background) {
+            Some(leaves) => reduce(&leaves),
+            None => reduce(&self.leaves(background)),
+        }
+    }
+
+    /// Returns the leaves below the `Node` as [`NodeRef::leaves`] does, if every child held is a leaf, so that
+    /// there is exactly one for each octant.
+    fn octant_leaves(self, background: T) -> Option<[(T, u64); OCTREE_CHILDREN]> {
+        let mut leaves = [(background, volume(self.dimension() / 2)); OCTREE_CHILDREN];
+
+        for (octant, leaf) in leaves.iter_mut().enumerate() {
+            if let Some(child) = self.child(octant) {
+                leaf.0 = *child.leaf_data()?;
+            }
+        }
+
+        Some(leaves)
+    }
+
+    /// Returns the data of each leaf below the `Node` paired with the number of voxels it covers, in octant
+    /// order, with unwritten space as leaves holding `background`.
+    pub(crate) fn leaves(self, background: T) -> Vec<(T, u64)> {
+        let mut leaves = Vec::new();
+        let mut stack = Vec::new();
+        stack.push((Some(self), self.dimension()));
+
+        while let Some((node, dimension)) = stack.pop() {
+            match node {
+                Some(node) => match node.leaf_data() {
+                    Some(data) => leaves.push((*data, volume(dimension))),
+                    // Push in reverse, so that leaves are listed in octant order.
+                    None => stack.extend(
+                        (0..OCTREE_CHILDREN)
+                            .rev()
+                            .map(|octant| (node.child(octant), dimension / 2)),
+                    ),
+                },
+                None => leaves.push((background, volume(dimension))),
+            }
+        }
+
+        leaves
+    }
+
+    /// Returns the data the `Node` would hold after being coarsened one level at a time by [`Node::lod`]
+    /// with `reduce`, until it is a single leaf, without modifying it.
+    pub(crate) fn coarse_data<F>(self, background: T, reduce: &F) -> T
+    where
+        F: Fn(&[(T, u64)]) -> T,
+    {
+        if let Some(data) = self.leaf_data() {
+            return *data;
+        }
+
+        let volume = volume(self.dimension() / 2);
+        let mut votes = [(background, volume); OCTREE_CHILDREN];
+        for (vote, (_, child)) in votes.iter_mut().zip(self.octants()) {
+            if let Some(child) = child {
+                vote.0 = child.coarse_data(background, reduce);
+            }
+        }
+
+        reduce(&votes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        child_bounds, octant_bounds, octant_of, volume, Bounds, Node, NodePool, NodeRef, NodeSlot, NodeType, Octant,
+        Referent, OCTREE_CHILDREN,
+    };
+    use crate::{test_utils::XorShift, trace::Counter, Octree, Vector3};
+
+    use alloc::{vec, vec::Vec};
+    use core::{convert::TryFrom, mem, num::NonZeroU32};
+    use std::time::Instant;
+
+    /// The recursive lookup `NodeRef::get` replaced, which finds the octant of each `Node` from its midpoint.
+    fn get_recursive(node: NodeRef<'_, u8>, position: Vector3<u32>) -> Option<&u8> {
+        if !node.contains(position) {
+            return None;
+        }
+
+        match node.leaf_data() {
+            Some(data) => Some(data),
+            None => {
+                let octant = octant_of(node.bounds(), position).unwrap();
+                get_recursive(node.child(octant as usize)?, position)
+            }
+        }
+    }
+
+    #[test]
+    fn get_matches_recursive_lookup() {
+        let mut rng = XorShift::new(0x6e7a);
+
+        for (dimension, inserts) in [(1, 1), (2, 4), (16, 300), (32, 3000)] {
+            let octree = rng.octree(dimension, inserts, 3);
+
+            // Coarser copies hold large simplified leaves.
+            for level in 0..=octree.max_lod_level().min(2) {
+                let octree = octree.at_lod(level);
+                let root = octree.root();
+
+                for x in 0..=dimension {
+                    for y in 0..=dimension {
+                        for z in 0..=dimension {
+                            let position = Vector3::from([x, y, z]);
+                            assert_eq!(root.get(position), get_recursive(root, position), "at {:?}", [x, y, z]);
+                        }
+                    }
+                }
+
+                // `Node`s below the root are looked up from their own corner.
+                for child in root.children() {
+                    let min = child.min_position();
+                    for i in 0..child.dimension().pow(3) {
+                        let dimension = child.dimension();
+                        let position = Vector3::from([
+                            min.x + i % dimension,
+                            min.y + i / dimension % dimension,
+                            min.z + i / dimension / dimension,
+                        ]);
+                        assert_eq!(child.get(position), get_recursive(child, position));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the bounds and data of every leaf below `node`, which has the given bounds, by splitting the bounds
+    /// of each `Node` with [`octant_bounds`], as `Node`s were given their bounds when they still kept them.
+    fn leaf_spans(node: &Node<u8>, bounds: Bounds, spans: &mut Vec<([[u32; 3]; 2], u8)>) {
+        match &node.ty {
+            NodeType::Leaf(data) => spans.push((bounds.map(Into::into), *data)),
+            NodeType::Packed(values) => {
+                for (octant, bounds) in octant_bounds(bounds).iter().enumerate() {
+                    if node.occupancy & 1 << octant != 0 {
+                        spans.push((bounds.map(Into::into), values[octant]));
+                    }
+                }
+            }
+            _ => {
+                for (octant, bounds) in octant_bounds(bounds).iter().enumerate() {
+                    if let Some(child) = node.slot(octant).get() {
+                        leaf_spans(child, *bounds, spans);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn derived_bounds_match_leaf_spans() {
+        let mut rng = XorShift::new(0x6e80);
+
+        for (dimension, inserts) in [(1, 1), (4, 20), (16, 300), (32, 2000)] {
+            let octree = rng.octree(dimension, inserts, 3);
+
+            // Coarser copies hold large simplified leaves.
+            for level in 0..=octree.max_lod_level().min(2) {
+                let octree = octree.at_lod(level);
+                let root = octree.root();
+
+                let mut expected = Vec::new();
+                leaf_spans(root.node().unwrap(), root.bounds(), &mut expected);
+
+                // Leaves reached through `NodeRef`s, whose bounds are derived on the way down.
+                let mut spans = Vec::new();
+                let mut stack = vec![root];
+                while let Some(node) = stack.pop() {
+                    match node.leaf_data() {
+                        Some(data) => spans.push((node.bounds().map(Into::into), *data)),
+                        None => stack.extend(node.children()),
+                    }
+                }
+
+                spans.sort_unstable();
+                expected.sort_unstable();
+                assert_eq!(spans, expected);
+
+                // Every voxel reads the data of the span holding it, which is the span of the leaf found there.
+                for i in 0..dimension.pow(3) {
+                    let position = [i % dimension, i / dimension % dimension, i / dimension / dimension];
+                    let span = expected
+                        .iter()
+                        .find(|([min, max], _)| (0..3).all(|axis| (min[axis]..max[axis]).contains(&position[axis])));
+                    let leaf = root.leaf_at(Vector3::from(position));
+
+                    assert_eq!(root.get(Vector3::from(position)), span.map(|(_, data)| data));
+                    assert_eq!(octree.get(position), Some(span.map_or(&0, |(_, data)| data)));
+                    assert_eq!(
+                        leaf.map(|leaf| (leaf.bounds().map(Into::into), *leaf.leaf_data().unwrap())),
+                        span.copied()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn nodes_do_not_keep_their_bounds() {
+        // Besides the pointer to its children and their number, a `Node` only keeps its data, or that of its
+        // children held inline, and its occupancy, where its bounds took another three words on 64 bit targets.
+        assert!(mem::size_of::<Node<u8>>() <= 2 * mem::size_of::<usize>() + 16);
+        // A `NodeRef` keeps the lower corner and dimension of the bounds instead.
+        assert_eq!(
+            mem::size_of::<NodeRef<'_, u8>>(),
+            mem::size_of::<Referent<'_, u8>>() + 16
+        );
+    }
+
+    /// The recursive insertion `Node::insert` replaced, which simplifies every `Node` it passes through.
+    fn insert_recursive(node: &mut Node<u8>, bounds: Bounds, position: Vector3<u32>, min_dimension: u32, data: u8) {
+        if bounds[1].x - bounds[0].x <= min_dimension {
+            node.ty = NodeType::Leaf(data);
+            node.clear_children(&mut NodePool::default());
+        } else if node.leaf_data() != Some(&data) {
+            node.split(0);
+
+            let octant = octant_of(bounds, position).unwrap();
+            let child = node
+                .child_or_insert_with(octant as usize, || Node::leaf(0), &mut NodePool::default())
+                .unwrap();
+            insert_recursive(child, child_bounds(bounds, octant), position, min_dimension, data);
+            node.simplify();
+        }
+    }
+
+    /// The recursive removal `Node::clear` replaced, which simplifies every `Node` it passes through.
+    fn clear_recursive(node: &mut Node<u8>, bounds: Bounds, position: Vector3<u32>, min_dimension: u32) {
+        if bounds[1].x - bounds[0].x <= min_dimension {
+            node.ty = NodeType::Leaf(0);
+            node.clear_children(&mut NodePool::default());
+        } else if node.leaf_data() != Some(&0) {
+            node.split(0);
+
+            let octant = octant_of(bounds, position).unwrap();
+            if let Some(child) = node.child_mut(octant as usize) {
+                clear_recursive(child, child_bounds(bounds, octant), position, min_dimension);
+                node.simplify();
+            }
+        }
+    }
+
+    #[test]
+    fn simplifies_as_recursive_insert_and_clear() {
+        let mut rng = XorShift::new(0x6e7d);
+
+        for (dimension, min_dimension) in [(1, 1), (2, 1), (4, 1), (8, 1), (8, 2), (16, 4)] {
+            let bounds = Octree::<u8>::new(NonZeroU32::new(dimension).unwrap())
+                .unwrap()
+                .root()
+                .bounds();
+            let mut node = Node::leaf(0);
+            let mut expected = Node::leaf(0);
+            let mut pool = NodePool::new(64);
+
+            // Aligned blocks of one of two values are written voxel by voxel, so that `Node`s fill up and
+            // simplify several levels at once.
+            for _ in 0..(dimension.pow(3) / 2).clamp(4, 400) {
+                let size = 1 << rng.below(dimension.trailing_zeros() + 1);
+                let corner = rng.position(dimension / size).map(|c| c * size);
+                let data = rng.below(3) as u8;
+
+                for i in 0..size.pow(3) {
+                    let offset = [i % size, i / size % size, i / size / size];
+                    let position = Vector3::from([0, 1, 2].map(|axis| corner[axis] + offset[axis]));
+
+                    if data == 0 {
+                        node.clear(bounds, position, min_dimension, 0, &mut pool).unwrap();
+                        clear_recursive(&mut expected, bounds, position, min_dimension);
+                    } else {
+                        node.insert(bounds, position, min_dimension, data, 0, &mut pool)
+                            .unwrap();
+                        insert_recursive(&mut expected, bounds, position, min_dimension, data);
+                    }
+                }
+
+                assert_eq!(format!("{:#?}", node), format!("{:#?}", expected), "at {:?}", corner);
+            }
+        }
+    }
+
+    /// Returns the number of internal `Node`s below and including `node`.
+    fn internal_nodes(node: &Node<u8>) -> usize {
+        match node.is_leaf() {
+            true => 0,
+            false => 1 + node.children().map(internal_nodes).sum::<usize>(),
+        }
+    }
+
+    /// Returns the number of internal `Node`s on the way from `node` down to the leaf holding `position`.
+    fn internal_nodes_above(node: NodeRef<'_, u8>, position: Vector3<u32>) -> usize {
+        let mut node = node;
+        let mut count = 0;
+
+        while !node.is_leaf() {
+            count += 1;
+            match node.octant_at(position).unwrap().1 {
+                Some(child) => node = child,
+                None => break,
+            }
+        }
+
+        count
+    }
+
+    #[test]
+    fn simplify_visits_only_modified_ancestors() {
+        let mut rng = XorShift::new(0x6e7e);
+        let mut octree = rng.octree(64, 3000, 3);
+
+        let internal = internal_nodes(octree.root().node().unwrap());
+        assert!(octree.root_mut().simplify_recursive(&mut Counter::default()) <= internal);
+        assert_eq!(octree.root_mut().simplify_recursive(&mut Counter::default()), 0);
+
+        for _ in 0..20 {
+            let position = rng.position(64);
+            if rng.below(2) == 0 {
+                let data = octree.get(position).map_or(1, |data| data % 3 + 1);
+                octree.insert(position, data).unwrap();
+            } else {
+                octree.clear_at(position).unwrap();
+            }
+
+            let visited = octree.root_mut().simplify_recursive(&mut Counter::default());
+            assert_eq!(visited, internal_nodes_above(octree.root(), Vector3::from(position)));
+            assert!(visited <= 7);
+            assert_eq!(octree.root_mut().simplify_recursive(&mut Counter::default()), 0);
+        }
+    }
+
+    #[test]
+    fn decoded_trees_are_simplified_in_full() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(4).unwrap()).unwrap();
+
+        // Encodings keep `Node`s whose children could be simplified, as they were built.
+        let leaves = || [0; OCTREE_CHILDREN].map(|_| Some(Node::leaf(2)));
+        *octree.root_mut() = Node::branch([0; OCTREE_CHILDREN].map(|_| Some(Node::branch(leaves()))));
+
+        let mut decoded = Octree::<u8>::from_bytes(&octree.to_bytes()).unwrap();
+        assert_eq!(internal_nodes(decoded.root().node().unwrap()), 9);
+        assert_eq!(decoded.root_mut().simplify_recursive(&mut Counter::default()), 9);
+        assert!(decoded.root().is_leaf());
+        assert_eq!(decoded.get([3, 1, 2]), Some(&2));
+    }
+
+    /// The listing of leaves `Node::reduce` always reduced before, recursing into every child.
+    fn leaves_recursive(node: NodeRef<'_, u8>, background: u8, leaves: &mut Vec<(u8, u64)>) {
+        match node.leaf_data() {
+            Some(data) => leaves.push((*data, volume(node.dimension()))),
+            None => {
+                for (_, child) in node.octants() {
+                    match child {
+                        Some(child) => leaves_recursive(child, background, leaves),
+                        None => leaves.push((background, volume(node.dimension() / 2))),
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reduce_matches_reducing_every_leaf() {
+        let mut rng = XorShift::new(0x7ed0);
+
+        // Majority votes, along with a reduction telling apart the order and volume of every leaf.
+        let majority = |leaves: &[(u8, u64)]| super::majority(leaves);
+        let fold = |leaves: &[(u8, u64)]| {
+            leaves.iter().fold(0_u8, |hash, (data, volume)| {
+                hash.wrapping_mul(31).wrapping_add(*data ^ *volume as u8)
+            })
+        };
+
+        for _ in 0..10 {
+            let octree = rng.octree(16, 300, 3);
+            let mut stack = vec![octree.root()];
+
+            while let Some(node) = stack.pop() {
+                let mut leaves = Vec::new();
+                leaves_recursive(node, 7, &mut leaves);
+
+                assert_eq!(node.leaves(7), leaves);
+                if !node.is_leaf() {
+                    assert_eq!(node.reduce(7, &majority), majority(&leaves));
+                    assert_eq!(node.reduce(7, &fold), fold(&leaves));
+                }
+
+                stack.extend(node.children());
+            }
+        }
+    }
+
+    /// Asserts that the occupancy of every `Node` below and including `node` matches its children.
+    fn assert_occupancy(node: NodeRef<'_, u8>) {
+        let mut stack = vec![node];
+
+        while let Some(node) = stack.pop() {
+            let occupancy = (0..OCTREE_CHILDREN)
+                .filter(|octant| node.child(*octant).is_some())
+                .fold(0, |occupancy, octant| occupancy | 1 << octant);
+
+            assert_eq!(node.occupancy(), occupancy, "at {:?}", node.bounds());
+            assert_eq!(node.children().count(), occupancy.count_ones() as usize);
+            if let Some(node) = node.node() {
+                node.debug_assert_occupancy();
+            }
+            if node.is_leaf() {
+                assert_eq!(occupancy, 0);
+            }
+
+            stack.extend(node.children());
+        }
+    }
+
+    #[test]
+    fn occupancy_matches_children() {
+        let mut rng = XorShift::new(0x6e7c);
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+
+        for _ in 0..400 {
+            let position = rng.position(16);
+            let data = 1 + rng.below(3) as u8;
+
+            match rng.below(10) {
+                0 | 1 => octree.insert(position, data).unwrap(),
+                2 => octree.clear_at(position).unwrap(),
+                3 => {
+                    let center = position.map(|c| c as f32);
+                    octree.insert_sphere(center, rng.f32(0.0, 5.0), data, true).unwrap();
+                }
+                4 => octree.clear_sphere(position.map(|c| c as f32), rng.f32(0.0, 5.0)),
+                5 => octree.clear_region(position, position.map(|c| c + rng.below(8))),
+                6 => octree.subtract_assign(&rng.octree(16, 40, 3)).unwrap(),
+                7 => {
+                    octree.set_lod_level(1 + rng.below(3)).unwrap();
+                    assert_occupancy(octree.root());
+                    octree.set_lod_level(1).unwrap();
+                }
+                8 => {
+                    let offset = rng.position(13);
+                    octree.merge_encoded(&rng.octree(4, 20, 3).to_bytes(), offset).unwrap();
+                }
+                _ => octree = Octree::from_bytes(&octree.to_bytes()).unwrap(),
+            }
+
+            assert_occupancy(octree.root());
+        }
+    }
+
+    /// Returns a copy of `node` holding every child as a `Node` of its own, as every `Node` did before children
+    /// were held inline.
+    fn unpacked(node: &Node<u8>) -> Node<u8> {
+        let mut copy = node.clone();
+        copy.unpack(&mut NodePool::default());
+
+        if let Some(children) = copy.children.as_deref_mut() {
+            for slot in children.iter_mut() {
+                if let NodeSlot::Loaded(child) = slot {
+                    *child = unpacked(child);
+                }
+            }
+        }
+
+        copy
+    }
+
+    /// Returns the number of `Node`s below and including `node` holding their children inline.
+    fn packed_nodes(node: &Node<u8>) -> usize {
+        node.is_packed() as usize + node.children().map(packed_nodes).sum::<usize>()
+    }
+
+    #[test]
+    fn packed_nodes_read_as_unpacked() {
+        let mut rng = XorShift::new(0x9ac4);
+
+        for _ in 0..10 {
+            let mut octree = rng.octree(16, 400, 3);
+            let mut copy = Octree::from_bytes(&octree.to_bytes()).unwrap();
+            *copy.root_mut() = unpacked(octree.root().node().unwrap());
+
+            assert!(packed_nodes(octree.root().node().unwrap()) > 0);
+            assert_eq!(packed_nodes(copy.root().node().unwrap()), 0);
+
+            let same = |a: &Octree<u8>, b: &Octree<u8>| {
+                let spans = |octree: &Octree<u8>| {
+                    octree
+                        .query_region([0; 3], [16; 3])
+                        .map(|(min, dimensions, data)| (min, dimensions, data.copied()))
+                        .collect::<Vec<_>>()
+                };
+
+                assert_eq!(format!("{:#?}", a), format!("{:#?}", b));
+                assert_eq!(a.to_bytes(), b.to_bytes());
+                assert_eq!(a.node_count(), b.node_count());
+                assert_eq!(spans(a), spans(b));
+                for i in 0..16 * 16 * 16 {
+                    let position = [i % 16, i / 16 % 16, i / 256];
+                    assert_eq!(a.get(position), b.get(position));
+                }
+            };
+            same(&octree, &copy);
+
+            // Edits reaching packed `Node`s unpack them, or write to them in place.
+            for _ in 0..200 {
+                let position = rng.position(16);
+                match rng.below(3) {
+                    0 => {
+                        octree.clear_at(position).unwrap();
+                        copy.clear_at(position).unwrap();
+                    }
+                    _ => {
+                        let data = 1 + rng.below(3) as u8;
+                        octree.insert(position, data).unwrap();
+                        copy.insert(position, data).unwrap();
+                    }
+                }
+            }
+            same(&octree, &copy);
+
+            octree.prune();
+            copy.prune();
+            same(&octree, &copy);
+
+            octree.lod_down();
+            copy.lod_down();
+            same(&octree, &copy);
+        }
+    }
+
+    #[test]
+    fn inserts_hold_leaves_inline() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(4).unwrap()).unwrap();
+        octree.insert([1, 2, 3], 5).unwrap();
+        octree.insert([0, 2, 3], 6).unwrap();
+
+        // The `Node` two voxels across holds both voxels inline, so only it and the root are `Node`s of their own,
+        // although both voxels are still counted.
+        let root = octree.root();
+        let parent = root.octant_at(Vector3::from([1, 2, 3])).unwrap().1.unwrap();
+        assert!(parent.node().unwrap().is_packed());
+        assert_eq!(parent.node().unwrap().heap_bytes(), 0);
+        assert_eq!(parent.occupancy(), 0b0000_1100);
+        assert_eq!(parent.child(3).unwrap().leaf_data(), Some(&5));
+        assert!(parent.child(3).unwrap().node().is_none());
+        assert_eq!(octree.node_count(), 4);
+    }
+
+    #[test]
+    fn packing_shrinks_dense_trees() {
+        let mut rng = XorShift::new(0x9ac5);
+        let octree = Octree::from_fn(NonZeroU32::new(64).unwrap(), |_| 1 + rng.below(8) as u8).unwrap();
+        let copy = unpacked(octree.root().node().unwrap());
+
+        // Every voxel of a dense tree is a leaf of its own, which took a `Node` in an array of eight below the
+        // `Node` two voxels across, so those arrays were the bulk of the tree.
+        let packed = octree.root().heap_bytes() + mem::size_of::<Node<u8>>();
+        let unpacked = copy.heap_bytes() + mem::size_of::<Node<u8>>();
+        assert!(
+            unpacked > 5 * packed,
+            "{} bytes unpacked, {} bytes packed",
+            unpacked,
+            packed
+        );
+    }
+
+    #[test]
+    fn octants_from_offsets_match_comparisons() {
+        // Every position within every `Node` of a 16*16*16 tree, down to those two voxels across.
+        for dimension in [16_u32, 8, 4, 2] {
+            let half = dimension / 2;
+            let blocks = 16 / dimension;
+
+            for i in 0..blocks.pow(3) {
+                let min = Vector3::from([i % blocks, i / blocks % blocks, i / blocks / blocks].map(|c| c * dimension));
+                let bounds = [min, min + Vector3::from([dimension; 3])];
+
+                for j in 0..dimension.pow(3) {
+                    let offset = [j % dimension, j / dimension % dimension, j / dimension / dimension];
+                    let position = min + Vector3::from(offset);
+                    let expected = Octant::vector_diff(min + Vector3::from([half; 3]), position);
+
+                    assert_eq!(
+                        Octant::from_offset(offset, half),
+                        expected,
+                        "{:?} in {:?}",
+                        position,
+                        min
+                    );
+                    assert_eq!(octant_of(bounds, position).unwrap(), expected);
+                    assert_eq!(Octant::try_from(expected as usize).unwrap(), expected);
+                    assert_eq!(<[u32; 3]>::from(expected.offset()), offset.map(|c| (c >= half) as u32));
+                }
+            }
+        }
+    }
+
+    /// Times selecting the octant at every level down to every voxel of a 128*128*128 tree with both
+    /// selections.
+    ///
+    /// Timings are only meaningful in release builds, so this is ignored by default; run it with
+    /// `cargo test --release -- --ignored octant_selection`.
+    #[test]
+    #[ignore]
+    fn octant_selection_is_faster_from_offsets() {
+        type Select = dyn Fn(Vector3<u32>, u32, Vector3<u32>) -> Octant;
+
+        let time = |select: &Select| {
+            let start = Instant::now();
+            let mut sum = 0_u64;
+            for _ in 0..4 {
+                for i in 0..128_u32.pow(3) {
+                    let position = Vector3::from([i % 128, i / 128 % 128, i / 128 / 128]);
+                    let mut min = Vector3::from([0, 0, 0]);
+                    let mut half = 64;
+
+                    while half > 0 {
+                        let octant = select(min, half, position);
+                        sum += octant as u64;
+                        min = min + octant.offset().component_mul(&Vector3::from([half; 3]));
+                        half /= 2;
+                    }
+                }
+            }
+
+            (start.elapsed(), sum)
+        };
+
+        let (compared, compared_sum) =
+            time(&|min, half, position| Octant::vector_diff(min + Vector3::from([half; 3]), position));
+        let (bits, bits_sum) = time(&|min: Vector3<u32>, half, position: Vector3<u32>| {
+            let offset = [position.x - min.x, position.y - min.y, position.z - min.z];
+            Octant::from_offset(offset, half)
+        });
+
+        assert_eq!(compared_sum, bits_sum);
+        assert!(
+            bits < compared,
+            "selecting octants down to every voxel 4 times: {:?} by comparison, {:?} from offsets",
+            compared,
+            bits
+        );
+    }
+
+    /// Times looking up every voxel of a dense tree with both lookups.
+    ///
+    /// Timings are only meaningful in release builds, so this is ignored by default; run it with
+    /// `cargo test --release -- --ignored get_is_faster`.
+    #[test]
+    #[ignore]
+    fn get_is_faster_than_recursive_lookup() {
+        let octree = XorShift::new(0x6e7b).octree(64, 200_000, 8);
+        let root = octree.root();
+
+        let time = |get: &dyn Fn(Vector3<u32>) -> Option<u8>| {
+            let start = Instant::now();
+            let mut sum = 0_u64;
+            for _ in 0..16 {
+                for x in 0..64 {
+                    for y in 0..64 {
+                        for z in 0..64 {
+                            sum += get(Vector3::from([x, y, z])).unwrap_or(0) as u64;
+                        }
+                    }
+                }
+            }
+
+            (start.elapsed(), sum)
+        };
+
+        let (recursive, recursive_sum) = time(&|position| get_recursive(root, position).copied());
+        let (iterative, iterative_sum) = time(&|position| root.get(position).copied());
+
+        assert_eq!(recursive_sum, iterative_sum);
+        assert!(
+            iterative < recursive,
+            "looking up every voxel 16 times: {:?} recursively, {:?} iteratively",
+            recursive,
+            iterative
+        );
+    }
+
+    /// Times filling a dense tree in blocks of one value with both insertions.
+    ///
+    /// Timings are only meaningful in release builds, so this is ignored by default; run it with
+    /// `cargo test --release -- --ignored insert_is_faster`.
+    #[test]
+    #[ignore]
+    fn insert_is_faster_than_recursive_insertion() {
+        type Insert = dyn Fn(&mut Node<u8>, Vector3<u32>, u8);
+
+        let bounds = Octree::<u8>::new(NonZeroU32::new(64).unwrap()).unwrap().root().bounds();
+
+        let time = |insert: &Insert| {
+            let start = Instant::now();
+            let mut node = Node::leaf(0);
+            for _ in 0..16 {
+                for x in 0..64 {
+                    for y in 0..64 {
+                        for z in 0..64 {
+                            insert(
+                                &mut node,
+                                Vector3::from([x, y, z]),
+                                (1 + (x / 4 + y / 4 + z / 4) % 3) as u8,
+                            );
+                        }
+                    }
+                }
+            }
+
+            (start.elapsed(), format!("{:#?}", node))
+        };
+
+        let (recursive, recursive_node) =
+            time(&move |node, position, data| insert_recursive(node, bounds, position, 1, data));
+        let (iterative, iterative_node) = time(&move |node, position, data| {
+            node.insert(bounds, position, 1, data, 0, &mut NodePool::default())
+                .unwrap()
+        });
+
+        assert_eq!(recursive_node, iterative_node);
+        assert!(
+            iterative < recursive,
+            "filling 64^3 voxels 16 times: {:?} recursively, {:?} iteratively",
+            recursive,
+            iterative
+        );
     }
 }
@@ -0,0 +1,282 @@
+use crate::{fill::fill, node::octant_order, query::Containment, Error, Octree};
+
+use alloc::vec::Vec;
+use core::{fmt::Debug, hash::Hash};
+
+/// Appends `value` to `bytes` as an unsigned LEB128 varint.
+fn write_varint(bytes: &mut Vec<u8>, mut value: u128) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+
+    bytes.push(value as u8);
+}
+
+/// Reads an unsigned LEB128 varint from the start of `bytes`, advancing past it.
+fn read_varint(bytes: &mut &[u8]) -> Result<u128, Error> {
+    let mut value = 0_u128;
+
+    for shift in (0..128).step_by(7) {
+        let (byte, rest) = bytes.split_first().ok_or(Error::InvalidEncoding)?;
+        *bytes = rest;
+
+        let bits = (*byte & 0x7f) as u128;
+        if bits << shift >> shift != bits {
+            return Err(Error::InvalidEncoding);
+        }
+
+        value |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+
+    Err(Error::InvalidEncoding)
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Encodes which voxels of the `Octree` are solid as runs of empty and solid voxels, without their data.
+    ///
+    /// Voxels are visited along the Z-order curve which visits the octants of every `Node` in octant order,
+    /// so that each leaf, however large, falls within a single run. The bytes hold the dimension of the
+    /// `Octree` followed by the lengths of the runs, alternating between empty and solid runs and starting
+    /// with an empty one, of length zero if the first voxel is solid. Every number is an unsigned LEB128
+    /// varint. Unwritten space is solid if `solid` holds for the background.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(4).unwrap()).unwrap();
+    /// octree.insert([1, 0, 0], 3).unwrap();
+    ///
+    /// // Four voxels across, followed by one empty voxel, one solid voxel and 62 empty voxels.
+    /// assert_eq!(octree.encode_occupancy_rle(|data| *data != 0), vec![4, 1, 1, 62]);
+    /// ```
+    pub fn encode_occupancy_rle(&self, solid: impl Fn(&T) -> bool) -> Vec<u8> {
+        let background = solid(&self.background());
+        let mut runs = Vec::new();
+        let mut run = (false, 0_u128);
+
+        let mut stack = Vec::new();
+        stack.push((Some(self.root()), self.dimension()));
+
+        while let Some((node, dimension)) = stack.pop() {
+            let state = match node {
+                Some(node) => match node.leaf_data() {
+                    Some(data) => solid(data),
+                    None => {
+                        // Push in reverse, so that octants are visited in order.
+                        let octants = node
+                            .octants()
+                            .map(|(_, child)| (child, dimension / 2))
+                            .collect::<Vec<_>>();
+                        stack.extend(octants.into_iter().rev());
+                        continue;
+                    }
+                },
+                None => background,
+            };
+
+            let len = (dimension as u128).pow(3);
+            if state == run.0 {
+                run.1 += len;
+            } else {
+                runs.push(run.1);
+                run = (state, len);
+            }
+        }
+        runs.push(run.1);
+
+        let mut bytes = Vec::new();
+        write_varint(&mut bytes, self.dimension() as u128);
+        for run in runs {
+            write_varint(&mut bytes, run);
+        }
+
+        bytes
+    }
+
+    /// Writes `value` to every voxel marked solid by an encoding of [`Octree::encode_occupancy_rle`].
+    ///
+    /// Voxels marked empty are left as they are, so applying an encoding to an empty `Octree` of the same
+    /// dimension reconstructs the solid voxels. Each run is written as the largest cubes which fit it. Returns
+    /// [`Error::DimensionMismatch`] if the encoding is of another dimension, and [`Error::InvalidEncoding`]
+    /// if it is truncated or its runs do not cover the `Octree` exactly, leaving the `Octree` unchanged.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+    /// octree.insert([1, 2, 3], 3).unwrap();
+    /// octree.insert([9, 9, 9], 4).unwrap();
+    /// let bytes = octree.encode_occupancy_rle(|data| *data == 4);
+    ///
+    /// let mut copy = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+    /// copy.apply_occupancy_rle(&bytes, 1).unwrap();
+    /// assert_eq!(copy.get([1, 2, 3]), Some(&0));
+    /// assert_eq!(copy.get([9, 9, 9]), Some(&1));
+    /// ```
+    pub fn apply_occupancy_rle(&mut self, mut bytes: &[u8], value: T) -> Result<(), Error> {
+        let dimension = read_varint(&mut bytes)?;
+        if dimension != self.dimension() as u128 {
+            return Err(Error::DimensionMismatch {
+                expected: self.dimension(),
+                found: dimension.min(u32::MAX as u128) as u32,
+            });
+        }
+
+        // Read every run before writing any, so that a corrupt encoding leaves the `Octree` unchanged.
+        let total = dimension.pow(3);
+        let mut solid = Vec::new();
+        let mut start = 0_u128;
+        let mut state = false;
+
+        while !bytes.is_empty() {
+            let end = start
+                .checked_add(read_varint(&mut bytes)?)
+                .filter(|end| *end <= total)
+                .ok_or(Error::InvalidEncoding)?;
+
+            if state && end > start {
+                solid.push((start, end));
+            }
+
+            start = end;
+            state = !state;
+        }
+
+        if start != total {
+            return Err(Error::InvalidEncoding);
+        }
+
+        for (start, end) in solid {
+            fill(self, Some(value), |min, dimension| {
+                let first = octant_order(min);
+                let last = first + (dimension as u128).pow(3);
+
+                if first >= start && last <= end {
+                    Containment::Inside
+                } else if last <= start || first >= end {
+                    Containment::Outside
+                } else {
+                    Containment::Straddling
+                }
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_varint, write_varint};
+    use crate::{test_utils::XorShift, Error, Octree};
+
+    use alloc::vec::Vec;
+    use core::num::NonZeroU32;
+
+    /// Returns the dimension and runs of an encoding.
+    fn runs(mut bytes: &[u8]) -> (u128, Vec<u128>) {
+        let dimension = read_varint(&mut bytes).unwrap();
+        let mut runs = Vec::new();
+        while !bytes.is_empty() {
+            runs.push(read_varint(&mut bytes).unwrap());
+        }
+
+        (dimension, runs)
+    }
+
+    #[test]
+    fn varints_round_trip() {
+        for value in [0, 1, 127, 128, 300, u32::MAX as u128, 1 << 93, u128::MAX] {
+            let mut bytes = Vec::new();
+            write_varint(&mut bytes, value);
+
+            let mut slice = &bytes[..];
+            assert_eq!(read_varint(&mut slice), Ok(value));
+            assert!(slice.is_empty());
+        }
+
+        // Varints must end, and fit in 128 bits.
+        assert_eq!(read_varint(&mut &[0x80, 0x80][..]), Err(Error::InvalidEncoding));
+        let mut overlong = [0xff; 19].to_vec();
+        overlong.push(0x7f);
+        assert_eq!(read_varint(&mut &overlong[..]), Err(Error::InvalidEncoding));
+    }
+
+    #[test]
+    fn uniform_leaves_are_single_runs() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+        for x in 16..32 {
+            for y in 0..16 {
+                for z in 0..16 {
+                    octree.insert([x, y, z], 5).unwrap();
+                }
+            }
+        }
+
+        let bytes = octree.encode_occupancy_rle(|data| *data != 0);
+        assert_eq!(runs(&bytes), (32, vec![4096, 4096, 32 * 32 * 32 - 8192]));
+
+        // Leaves holding different solid data merge into the same run.
+        octree.insert([16, 0, 0], 6).unwrap();
+        assert_eq!(runs(&octree.encode_occupancy_rle(|data| *data != 0)).1.len(), 3);
+
+        let full = Octree::<u8>::new_with_background(NonZeroU32::new(16).unwrap(), 1).unwrap();
+        assert_eq!(runs(&full.encode_occupancy_rle(|data| *data != 0)), (16, vec![0, 4096]));
+    }
+
+    #[test]
+    fn occupancy_round_trips() {
+        let mut rng = XorShift::new(0x0cc1);
+
+        for _ in 0..10 {
+            let octree = rng.octree(16, 800, 4);
+            let solid = |data: &u8| *data % 2 == 1;
+            let bytes = octree.encode_occupancy_rle(solid);
+
+            let mut copy = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+            copy.apply_occupancy_rle(&bytes, 9).unwrap();
+
+            for position in (0..4096).map(|i| [i % 16, i / 16 % 16, i / 256]) {
+                let expected = octree.get(position).is_some_and(solid);
+                assert_eq!(copy.get(position) == Some(&9), expected);
+                assert_eq!(copy.root().get(position.into()).is_some(), expected);
+            }
+
+            assert_eq!(copy.encode_occupancy_rle(|data| *data == 9), bytes);
+        }
+    }
+
+    #[test]
+    fn invalid_encodings_are_rejected() {
+        let mut rng = XorShift::new(0x0cc2);
+        let octree = rng.octree(8, 100, 4);
+        let bytes = octree.encode_occupancy_rle(|data| *data > 2);
+
+        let mut other = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        assert_eq!(
+            other.apply_occupancy_rle(&bytes, 1),
+            Err(Error::DimensionMismatch { expected: 16, found: 8 })
+        );
+
+        let mut copy = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        for len in 0..bytes.len() {
+            assert_eq!(copy.apply_occupancy_rle(&bytes[..len], 1), Err(Error::InvalidEncoding));
+        }
+
+        let mut long = bytes.clone();
+        long.push(1);
+        assert_eq!(copy.apply_occupancy_rle(&long, 1), Err(Error::InvalidEncoding));
+        assert!(copy.equivalent(&Octree::new(NonZeroU32::new(8).unwrap()).unwrap()));
+    }
+}
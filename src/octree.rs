@@ -1,12 +1,24 @@
-use crate::{Error, Node, Vector3};
+use crate::{
+    brick::BRICK_DIMENSIONS,
+    cache::CachedRoot,
+    node::{contains, majority, majority_ignoring, Bounds, DebugBudget, DebugTree, NodePool},
+    trace::Counter,
+    Error, LodPolicy, Node, NodeRef, Vector3,
+};
 
-#[cfg(feature = "no-std")]
-use micromath::F32Ext;
+use alloc::vec::Vec;
+use core::{
+    fmt::{self, Debug},
+    hash::Hash,
+    mem,
+    num::NonZeroU32,
+};
 
-use alloc::boxed::Box;
-use core::{f32, fmt::Debug, hash::Hash, num::NonZeroU32};
-
-#[derive(Debug)]
+#[cfg_attr(
+    feature = "bevy_reflect",
+    derive(bevy_reflect::Reflect),
+    reflect(opaque, Clone, Debug)
+)]
 pub struct Octree<T>
 where
     T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
@@ -15,9 +27,75 @@ where
     curr_lod_level: u32,
     max_lod_level: u32,
     min_dimension: u32,
-    root: Box<Node<T>>,
+    background: T,
+    root: CachedRoot<T>,
+    lod_journal: Option<LodJournal<T>>,
+    /// The dimension of the bricks made by edits, and the number of voxels written below a `Node` making it one.
+    bricks: Option<(u32, u32)>,
+    pool: NodePool<T>,
+}
+
+/// The `Node`s collapsed by each step down in LOD, with their bounds, along with the level each step left.
+type LodJournal<T> = Vec<(u32, Vec<(Bounds, Node<T>)>)>;
+
+/// The most arrays of children an `Octree` keeps for reuse after edits free them.
+const POOL_CAPACITY: usize = 1024;
+
+impl<T> Debug for Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    // The arrays kept for reuse say nothing about the contents of the `Octree`, so two `Octree`s holding the
+    // same tree print the same. Without the alternate flag, the tree is cut short and the journal only counted.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (alternate, budget) = (f.alternate(), DebugBudget::new(f));
+        let mut debug = f.debug_struct("Octree");
+
+        debug
+            .field("dimension", &self.dimension)
+            .field("curr_lod_level", &self.curr_lod_level)
+            .field("max_lod_level", &self.max_lod_level)
+            .field("min_dimension", &self.min_dimension)
+            .field("background", &self.background)
+            .field("node_count", &self.node_count())
+            .field("leaf_count", &self.root().leaf_count())
+            .field("bricks", &self.bricks);
+
+        match alternate {
+            true => debug.field("lod_journal", &self.lod_journal),
+            false => debug.field("lod_journal", &self.lod_journal.as_ref().map(Vec::len)),
+        };
+
+        debug.field("root", &DebugTree::new(self.root(), &budget)).finish()
+    }
+}
+
+impl<T> Clone for Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    // As with printing, the arrays kept for reuse are left behind, and the leaf last read is found again.
+    fn clone(&self) -> Self {
+        let mut octree = self.with_root((*self.root).clone());
+        octree.lod_journal = self.lod_journal.clone();
+        octree
+    }
 }
 
+// `Octree`s, and the `Node`s within them, can be sent and shared between threads whenever their data can, as
+// the parallel iterators rely on.
+const _: () = {
+    fn assert_send_sync<S: Send + Sync>() {}
+
+    fn assert_octree<T>()
+    where
+        T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + Send + Sync,
+    {
+        assert_send_sync::<Octree<T>>();
+        assert_send_sync::<Node<T>>();
+    }
+};
+
 impl<T> Octree<T>
 where
     T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
@@ -40,19 +118,39 @@ where
     /// assert!(matches!(octree, Err(Error::InvalidDimension(15))));
     /// ```
     pub fn new(dimension: NonZeroU32) -> Result<Self, Error> {
-        // Check that `dimension` is a power of 2.
-        let max_depth = (dimension.get() as f32).log(2.0);
+        Self::new_with_background(dimension, T::default())
+    }
 
-        if max_depth.fract() == 0.0 {
+    /// Creates a new `Octree<T>` of given dimension, where unwritten and cleared space holds `background`
+    /// rather than `T::default()`.
+    ///
+    /// The background takes the place of `T::default()` throughout: cleared voxels hold it, it is never
+    /// stored below a leaf holding it, and every query treats it as empty.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new_with_background(NonZeroU32::new(32).unwrap(), 7).unwrap();
+    /// assert!(matches!(octree.get([0, 0, 0]), Some(7)));
+    ///
+    /// octree.insert([0, 0, 0], 0).unwrap();
+    /// octree.clear_at([0, 0, 0]).unwrap();
+    /// assert!(matches!(octree.get([0, 0, 0]), Some(7)));
+    /// ```
+    pub fn new_with_background(dimension: NonZeroU32, background: T) -> Result<Self, Error> {
+        if dimension.get().is_power_of_two() {
             Ok(Self {
                 dimension,
                 curr_lod_level: 1,
-                max_lod_level: max_depth.round() as u32,
+                max_lod_level: dimension.get().trailing_zeros(),
                 min_dimension: 1,
-                root: Box::new(Node::<T>::new([
-                    Vector3::from([0, 0, 0]),
-                    Vector3::from([dimension.get(), dimension.get(), dimension.get()]),
-                ])),
+                background,
+                root: CachedRoot::new(Node::leaf(background)),
+                lod_journal: None,
+                bricks: None,
+                pool: NodePool::new(POOL_CAPACITY),
             })
         } else {
             Err(Error::InvalidDimension(dimension.into()))
@@ -62,6 +160,10 @@ where
     /// Inserts data of type `T` into the given position in the `Octree`.
     /// Returns an error if the position does not exist within the confines of the `Octree`.
     ///
+    /// Positions are given as anything converting into a [`Vector3`], as every method taking one accepts, such as
+    /// arrays. With the `mint` feature, that includes the points and vectors of `mint`, through which most math
+    /// libraries convert their own, and with the `nalgebra` feature, those of `nalgebra`.
+    ///
     /// # Example
     /// ```
     /// # use svo_rs::{Error, Octree};
@@ -71,13 +173,43 @@ where
     /// let res = octree.insert([9, 8, 31], 1);
     ///
     /// assert!(res.is_ok());
+    ///
+    /// # #[cfg(feature = "mint")]
+    /// # {
+    /// octree.insert(mint::Point3 { x: 1, y: 2, z: 3 }, 2).unwrap();
+    /// assert!(matches!(octree.get([1, 2, 3]), Some(2)));
+    /// assert!(matches!(octree.get(mint::Vector3::from([9, 8, 31])), Some(1)));
+    /// # }
     /// ```
-    pub fn insert(&mut self, position: [u32; 3], data: T) -> Result<(), Error> {
-        self.root.insert(position.into(), self.min_dimension, data)
+    pub fn insert(&mut self, position: impl Into<Vector3<u32>>, data: T) -> Result<(), Error> {
+        let position = <[u32; 3]>::from(position.into());
+        enter_span!(TRACE, "insert", [visited, merged], ?position);
+        self.invalidate_lod_journal(position);
+        let (bounds, min_dimension) = (self.bounds(), self.min_dimension);
+        let root = self.root.get_mut();
+        root.insert(
+            bounds,
+            position.into(),
+            min_dimension,
+            data,
+            self.background,
+            &mut self.pool,
+        )?;
+
+        if let Some((dimension, threshold)) = self.bricks.filter(|_| min_dimension == 1) {
+            root.make_brick(bounds, position.into(), dimension, threshold, &mut self.pool);
+        }
+
+        Ok(())
     }
 
     /// Retrieves data of type `T` from the given position in the `Octree`.
-    /// Since the `Octree` is sparse, returns `None` if the position does not currently store any data.
+    /// Positions never written, or cleared, read as the background. Returns `None` for positions outside the
+    /// `Octree`, and for those within subtrees held in storage until they are loaded; read them through
+    /// [`Octree::with_source`].
+    ///
+    /// The leaf found is remembered until the `Octree` is next modified, so that reads landing in the same leaf,
+    /// as sweeps along an axis mostly do, are answered without walking down from the root.
     ///
     /// # Example
     /// ```
@@ -88,10 +220,11 @@ where
     /// octree.insert([9, 8, 31], 1).unwrap();
     ///
     /// assert!(matches!(octree.get([9, 8, 31]), Some(1)));
-    /// assert!(octree.get([20, 1, 12]).is_none());
+    /// assert!(matches!(octree.get([20, 1, 12]), Some(0)));
+    /// assert!(octree.get([32, 1, 12]).is_none());
     /// ```
-    pub fn get(&self, position: [u32; 3]) -> Option<&T> {
-        self.root.get(position.into())
+    pub fn get(&self, position: impl Into<Vector3<u32>>) -> Option<&T> {
+        self.root.get(self.bounds(), position.into(), &self.background)
     }
 
     /// Removes the `Node` at the given position in the `Octree`, if it exists.
@@ -118,11 +251,22 @@ where
     /// assert!(matches!(octree.get([31, 31, 31]), Some(1)));
     /// assert!(matches!(octree.get([0, 0, 0]), Some(1)));
     /// ```
-    pub fn clear_at(&mut self, position: [u32; 3]) -> Result<(), Error> {
-        self.root.clear(position.into(), self.min_dimension)
+    pub fn clear_at(&mut self, position: impl Into<Vector3<u32>>) -> Result<(), Error> {
+        let position = <[u32; 3]>::from(position.into());
+        enter_span!(TRACE, "clear_at", [visited, merged], ?position);
+        self.invalidate_lod_journal(position);
+        let bounds = self.bounds();
+        self.root.get_mut().clear(
+            bounds,
+            position.into(),
+            self.min_dimension,
+            self.background,
+            &mut self.pool,
+        )
     }
 
-    /// Removes all `Node`s from the `Octree`.
+    /// Removes all `Node`s from the `Octree`, freeing the memory kept for reuse by later edits, as
+    /// [`Octree::shrink_pool`] does.
     ///
     /// # Example
     /// ```
@@ -140,16 +284,122 @@ where
     /// assert!(matches!(octree.get([0, 0, 1]), Some(0)));
     /// ```
     pub fn clear(&mut self) {
-        self.root = Box::new(Node::<T>::new([
-            Vector3::from([0, 0, 0]),
-            Vector3::from([self.dimension.get(), self.dimension.get(), self.dimension.get()]),
-        ]));
+        self.clear_lod_journal();
+        self.pool.shrink(0);
+        *self.root.get_mut() = Node::leaf(self.background);
+    }
+
+    /// Simplifies every `Node` of the `Octree` whose children are leaves all holding the same data into a leaf
+    /// holding that data, without changing any voxel.
+    ///
+    /// Edits already simplify the `Node`s they pass through, but decoded trees are kept as they were encoded.
+    /// Only the `Node`s modified since the last call, and those above them, are visited again, so calling this
+    /// periodically costs as much as the regions edited in between. Decoded trees are visited in full once.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([0, 0, 0], 1).unwrap();
+    /// octree.simplify();
+    ///
+    /// assert!(matches!(octree.get([0, 0, 0]), Some(1)));
+    /// assert!(matches!(octree.get([0, 0, 1]), Some(0)));
+    /// ```
+    pub fn simplify(&mut self) {
+        enter_span!(DEBUG, "simplify", [visited, merged]);
+        let mut merged = Counter::default();
+        let visited = self.root.get_mut().simplify_recursive(&mut merged);
+        record!(visited = visited, merged = merged.get());
+    }
+
+    /// Removes every subtree holding nothing but the background, and returns the number of `Node`s removed.
+    ///
+    /// Clearing voxels leaves leaves holding the background behind, which [`Octree::simplify`] merges but never
+    /// removes. Pruning leaves their octants empty, as those never written are, so they take no memory and are
+    /// not visited by traversals. Voxels read the same before and after, as [`Octree::get`] reads empty octants as
+    /// the background. Subtrees not loaded from storage are left untouched.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([0, 0, 0], 1).unwrap();
+    /// octree.insert([31, 31, 31], 1).unwrap();
+    /// octree.clear_at([0, 0, 0]).unwrap();
+    /// assert_eq!(octree.node_count(), 11);
+    ///
+    /// assert_eq!(octree.prune(), 5);
+    /// assert_eq!(octree.node_count(), 6);
+    /// assert!(matches!(octree.get([0, 0, 0]), Some(0)));
+    /// assert!(matches!(octree.get([31, 31, 31]), Some(1)));
+    /// ```
+    pub fn prune(&mut self) -> usize {
+        self.root.get_mut().prune(self.background, &mut self.pool)
+    }
+
+    /// Returns the number of `Node`s held in memory, including the root.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// assert_eq!(octree.node_count(), 1);
+    ///
+    /// octree.insert([0, 0, 0], 1).unwrap();
+    /// assert_eq!(octree.node_count(), 6);
+    /// ```
+    pub fn node_count(&self) -> usize {
+        self.root.node_count()
+    }
+
+    /// Returns the number of arrays of children the `Octree` keeps for reuse.
+    ///
+    /// Edits which simplify or clear `Node`s keep the arrays their children were held in, as do `Node`s gaining
+    /// children and moving into larger arrays, up to 1024 of them, and later edits take them back rather than
+    /// allocating, so repeatedly filling and clearing a region allocates little once it has been done once.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// for i in 0..63 {
+    ///     octree.insert([i & 3, i >> 2 & 3, i >> 4], 1).unwrap();
+    /// }
+    /// let kept = octree.pool_len();
+    ///
+    /// // Filling the 4*4*4 block simplifies it into a single leaf, keeping the array of its children. The voxels
+    /// // of each 2*2*2 block are held inline, without an array.
+    /// octree.insert([3, 3, 3], 1).unwrap();
+    /// assert_eq!(octree.pool_len(), kept + 1);
+    ///
+    /// octree.shrink_pool(0);
+    /// assert_eq!(octree.pool_len(), 0);
+    /// ```
+    pub fn pool_len(&self) -> usize {
+        self.pool.len()
+    }
+
+    /// Frees all but `len` of the arrays of children the `Octree` keeps for reuse, as counted by
+    /// [`Octree::pool_len`].
+    pub fn shrink_pool(&mut self, len: usize) {
+        self.pool.shrink(len);
     }
 
     /// Effectively increases the leaf dimension of the `Octree` and simplifies where possible.
     ///
     /// Moves the leaf dimension up a level, and all leaves are formed by the most common data of their
-    /// original children.
+    /// original children, weighted by the volume each covers. Unwritten children count as the background,
+    /// and ties go to the data of the child coming first in octant order. `Node`s holding a subtree held in
+    /// storage are left as they are, as its contents are unknown until loaded.
     ///
     /// # Example
     /// ```
@@ -170,23 +420,182 @@ where
     /// assert!(matches!(octree.get([0, 1, 0]), Some(2)));
     /// ```
     pub fn lod_down(&mut self) {
-        let level = if self.curr_lod_level + 1 >= self.max_lod_level {
-            self.max_lod_level
-        } else {
-            self.curr_lod_level + 1
-        };
+        self.lod_down_with(majority);
+    }
 
-        let min_dimension = 2_u32.pow(level - 1);
+    /// Effectively increases the leaf dimension of the `Octree`, forming each new leaf with `reduce`.
+    ///
+    /// Behaves as [`Octree::lod_down`], but each `Node` collapsed into a leaf holds the data `reduce`
+    /// returns for the leaves below it. `reduce` is given the data of each of those leaves paired with the
+    /// number of voxels it covers, in octant order, with unwritten space as leaves holding the background. Leaves
+    /// more than 2^21 voxels across cover more voxels than a `u64` counts, so their volume saturates.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([0, 0, 0], 0b01).unwrap();
+    /// octree.insert([1, 1, 1], 0b10).unwrap();
+    ///
+    /// octree.lod_down_with(|leaves| leaves.iter().fold(0, |flags, (data, _)| flags | data));
+    /// assert!(matches!(octree.get([0, 1, 0]), Some(0b11)));
+    /// ```
+    pub fn lod_down_with(&mut self, reduce: impl Fn(&[(T, u64)]) -> T) {
+        // An `Octree` one voxel across has no coarser level.
+        if self.max_lod_level == 0 {
+            return;
+        }
+
+        let (level, min_dimension) = self.next_lod_level();
+        enter_span!(DEBUG, "lod_down", [merged], from = self.curr_lod_level, to = level);
+        let mut collapsed = self.lod_journal.as_ref().map(|_| Vec::new());
+        let bounds = self.bounds();
+
+        let merged = self.root.get_mut().lod(
+            bounds,
+            min_dimension,
+            self.background,
+            &reduce,
+            collapsed.as_mut(),
+            &mut self.pool,
+        );
+        record!(merged = merged.get());
+
+        if let (Some(journal), Some(collapsed)) = (&mut self.lod_journal, collapsed) {
+            match journal.last_mut() {
+                _ if level != self.curr_lod_level => journal.push((self.curr_lod_level, collapsed)),
+                Some((_, nodes)) => nodes.extend(collapsed),
+                None => {}
+            }
+        }
 
-        self.root.lod();
         self.curr_lod_level = level;
         self.min_dimension = min_dimension;
     }
 
+    /// Coarsens the `Octree` away from a focus box, keeping full detail within it.
+    ///
+    /// Every block of `2^level` voxels lying entirely outside the box from `focus_min` (inclusive) to
+    /// `focus_max` (exclusive) becomes a single leaf holding the data [`Octree::get_at_lod`] returned for
+    /// it, while blocks intersecting the box are left untouched. Levels beyond the coarsest one
+    /// [`Octree::lod_down`] reaches are treated as the coarsest. Calling this again with the same box changes
+    /// nothing, and calling it with a moved box coarsens the space the box left, but cannot restore detail
+    /// already discarded. The LOD level of the `Octree` is unchanged, so detail may still be inserted
+    /// anywhere.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([0, 0, 0], 1).unwrap();
+    /// octree.insert([0, 0, 1], 1).unwrap();
+    /// octree.insert([0, 1, 0], 1).unwrap();
+    /// octree.insert([0, 1, 1], 1).unwrap();
+    /// octree.insert([25, 25, 25], 2).unwrap();
+    ///
+    /// octree.lod_outside([16, 16, 16], [32, 32, 32], 1);
+    /// assert!(matches!(octree.get([1, 1, 1]), Some(1)));
+    /// assert!(matches!(octree.get([25, 25, 25]), Some(2)));
+    /// ```
+    pub fn lod_outside(&mut self, focus_min: impl Into<Vector3<u32>>, focus_max: impl Into<Vector3<u32>>, level: u32) {
+        let focus_min = <[u32; 3]>::from(focus_min.into());
+        let focus_max = <[u32; 3]>::from(focus_max.into());
+        let dimension = 2_u32.pow(level.min(self.max_lod_level.saturating_sub(1)));
+        let (bounds, background) = (self.bounds(), self.background);
+        let (root, pool) = self.root_and_pool_mut();
+        root.lod_outside(
+            bounds,
+            [focus_min.into(), focus_max.into()],
+            dimension,
+            background,
+            &majority,
+            pool,
+        );
+    }
+
+    /// Effectively increases the leaf dimension of the `Octree`, forming each new leaf by the given policy.
+    ///
+    /// Behaves as [`Octree::lod_down`] for [`LodPolicy::Majority`]. For [`LodPolicy::IgnoreBackground`],
+    /// unwritten space and leaves holding the background are left out of the vote, so a block holding any
+    /// other data coarsens to it.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, LodPolicy, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([0, 0, 0], 1).unwrap();
+    ///
+    /// octree.lod_down_with_policy(LodPolicy::IgnoreBackground);
+    /// assert!(matches!(octree.get([1, 1, 1]), Some(1)));
+    /// ```
+    pub fn lod_down_with_policy(&mut self, policy: LodPolicy) {
+        let background = self.background;
+
+        match policy {
+            LodPolicy::Majority => self.lod_down(),
+            LodPolicy::IgnoreBackground => self.lod_down_with(|values| majority_ignoring(values, background)),
+        }
+    }
+
+    /// Returns a copy of the `Octree` as it would be after calling [`Octree::lod_down`] `level` times,
+    /// without modifying the `Octree`.
+    ///
+    /// `level` counts the levels to coarsen by from [`Octree::lod_level`], not the level to reach, so a `level`
+    /// at or below the current LOD level still coarsens the copy further, and a `level` of 0 copies the
+    /// `Octree` unchanged. The copy keeps the dimension of the `Octree`, and its LOD level is raised to the
+    /// current one plus `level`, up to [`Octree::max_lod_level`]. Every leaf of the copy is then at least
+    /// `2^(n - 1)` voxels across, where `n` is its LOD level, as given by [`Octree::min_dimension`]. An
+    /// `Octree` one voxel across has no coarser level, and is copied unchanged. [`Octree::lod_up`] allows
+    /// inserting detail into the copy again. Detail discarded by the first level is never copied.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([0, 0, 0], 2).unwrap();
+    /// octree.insert([0, 0, 1], 2).unwrap();
+    /// octree.insert([0, 1, 0], 1).unwrap();
+    /// octree.insert([0, 1, 1], 2).unwrap();
+    /// octree.insert([1, 0, 0], 1).unwrap();
+    /// octree.insert([1, 0, 1], 2).unwrap();
+    /// octree.insert([1, 1, 0], 2).unwrap();
+    /// octree.insert([1, 1, 1], 1).unwrap();
+    ///
+    /// let coarse = octree.at_lod(1);
+    /// assert!(matches!(coarse.get([0, 1, 0]), Some(2)));
+    /// assert!(matches!(octree.get([0, 1, 0]), Some(1)));
+    /// ```
+    pub fn at_lod(&self, level: u32) -> Octree<T> {
+        if level == 0 || self.max_lod_level == 0 {
+            return self.with_root(self.root.clone());
+        }
+
+        let (curr_lod_level, min_dimension) = self.next_lod_level();
+        let mut octree = self.with_root(self.root().lod_copy(min_dimension, self.background, &majority));
+        octree.curr_lod_level = curr_lod_level;
+        octree.min_dimension = min_dimension;
+
+        // Further levels only collapse the copy, and change nothing past the coarsest level.
+        for _ in 1..level.min(self.max_lod_level) {
+            octree.lod_down();
+        }
+
+        octree
+    }
+
     /// Effectively decreases the leaf dimension of the `Octree`.
     ///
     /// Note that the structure of the `Octree` does not change, as it cannot "remember" old, higher LOD
-    /// levels. Rather, this method allows the insertion of new leaf nodes at a higher detail level.
+    /// levels, unless they were recorded with [`Octree::enable_lod_journal`]. Rather, this method allows the
+    /// insertion of new leaf nodes at a higher detail level.
     ///
     /// # Example
     /// ```
@@ -212,7 +621,7 @@ where
     /// assert!(matches!(octree.get([0, 0, 1]), Some(2)));
     /// ```
     pub fn lod_up(&mut self) {
-        let level = if self.curr_lod_level - 1 <= 0 {
+        let level = if self.curr_lod_level <= 1 {
             1
         } else {
             self.curr_lod_level - 1
@@ -220,13 +629,308 @@ where
 
         let min_dimension = 2_u32.pow(level - 1);
 
+        let bounds = self.bounds();
+
+        if let Some(journal) = &mut self.lod_journal {
+            if matches!(journal.last(), Some((from, _)) if *from == level) {
+                // Journaled detail is only recorded from loaded subtrees, so it is never grafted below unloaded
+                // ones.
+                for (node_bounds, node) in journal.pop().unwrap().1 {
+                    let _ = self.root.get_mut().graft(bounds, node, node_bounds, self.background);
+                }
+            }
+        }
+
         self.curr_lod_level = level;
         self.min_dimension = min_dimension;
     }
 
+    /// Starts recording the detail discarded by [`Octree::lod_down`], so that [`Octree::lod_up`] restores it.
+    ///
+    /// Each call to [`Octree::lod_down`] keeps the subtrees it collapses, and the matching call to
+    /// [`Octree::lod_up`] puts them back, voxel for voxel. Detail under voxels later written by
+    /// [`Octree::insert`] or [`Octree::clear_at`] is discarded rather than restored over them, and any other
+    /// modification of the `Octree` discards all of it. The memory held is reported by
+    /// [`Octree::lod_journal_bytes`], and may be released with [`Octree::clear_lod_journal`].
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.enable_lod_journal();
+    /// octree.insert([0, 0, 0], 1).unwrap();
+    ///
+    /// octree.lod_down();
+    /// assert!(!matches!(octree.get([0, 0, 0]), Some(1)));
+    /// assert!(octree.lod_journal_bytes() > 0);
+    ///
+    /// octree.lod_up();
+    /// assert!(matches!(octree.get([0, 0, 0]), Some(1)));
+    /// assert_eq!(octree.lod_journal_bytes(), 0);
+    /// ```
+    pub fn enable_lod_journal(&mut self) {
+        if self.lod_journal.is_none() {
+            self.lod_journal = Some(Vec::new());
+        }
+    }
+
+    /// Discards all detail recorded since [`Octree::enable_lod_journal`] was called, while continuing to
+    /// record further calls to [`Octree::lod_down`].
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.enable_lod_journal();
+    /// octree.insert([0, 0, 0], 1).unwrap();
+    ///
+    /// octree.lod_down();
+    /// octree.clear_lod_journal();
+    /// assert_eq!(octree.lod_journal_bytes(), 0);
+    ///
+    /// octree.lod_up();
+    /// assert!(!matches!(octree.get([0, 0, 0]), Some(1)));
+    /// ```
+    pub fn clear_lod_journal(&mut self) {
+        if let Some(journal) = &mut self.lod_journal {
+            journal.clear();
+        }
+    }
+
+    /// Returns the number of bytes held by the detail recorded since [`Octree::enable_lod_journal`] was
+    /// called.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// assert_eq!(octree.lod_journal_bytes(), 0);
+    /// ```
+    pub fn lod_journal_bytes(&self) -> usize {
+        self.lod_journal
+            .iter()
+            .flatten()
+            .flat_map(|(_, nodes)| nodes.iter())
+            .map(|(_, node)| mem::size_of::<(Bounds, Node<T>)>() + node.heap_bytes())
+            .sum()
+    }
+
+    /// Holds dense regions of the given dimension as bricks, listing the data of every voxel rather than a `Node`
+    /// for each leaf, once at least `threshold` of their voxels have been written.
+    ///
+    /// Regions are checked as [`Octree::insert`] writes to them, and a brick whose voxels all come to hold the
+    /// same data becomes a leaf again. Bricks read, encode and compare exactly as the `Node`s they stand for, and
+    /// other modifications of the `Octree` hold them as those `Node`s again. Bricks are only made at the full
+    /// level of detail, and may be 4 or 8 voxels across, smaller than the `Octree`.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.enable_bricks(4, 32).unwrap();
+    ///
+    /// for x in 0..4 {
+    ///     for y in 0..4 {
+    ///         for z in 0..4 {
+    ///             octree.insert([x, y, z], (x + y + z) as u8 % 3).unwrap();
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// assert!(matches!(octree.get([1, 2, 3]), Some(0)));
+    /// assert!(matches!(octree.enable_bricks(16, 32), Err(Error::InvalidDimension(16))));
+    /// ```
+    pub fn enable_bricks(&mut self, dimension: u32, threshold: u32) -> Result<(), Error> {
+        if !BRICK_DIMENSIONS.contains(&dimension) || dimension >= self.dimension.get() {
+            return Err(Error::InvalidDimension(dimension));
+        }
+
+        self.bricks = Some((dimension, threshold));
+        Ok(())
+    }
+
+    /// Moves the `Octree` directly to the given LOD level.
+    ///
+    /// Moving to a coarser level coarsens the `Octree` as calling [`Octree::lod_down`] once per level would,
+    /// and moving to a finer level behaves as calling [`Octree::lod_up`] once per level. Returns an error if
+    /// `level` is zero or beyond [`Octree::max_lod_level`].
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.set_lod_level(3).unwrap();
+    ///
+    /// assert_eq!(octree.lod_level(), 3);
+    /// assert_eq!(octree.min_dimension(), 4);
+    /// assert_eq!(octree.set_lod_level(6), Err(Error::InvalidLodLevel(6)));
+    /// ```
+    pub fn set_lod_level(&mut self, level: u32) -> Result<(), Error> {
+        if level == 0 || level > self.max_lod_level.max(1) {
+            return Err(Error::InvalidLodLevel(level));
+        }
+
+        while self.curr_lod_level < level {
+            self.lod_down();
+        }
+
+        while self.curr_lod_level > level {
+            self.lod_up();
+        }
+
+        Ok(())
+    }
+
+    /// Moves the `Octree` back to LOD level 1, allowing single voxels to be inserted again.
+    ///
+    /// As with [`Octree::lod_up`], detail discarded by coarsening is only restored if it was recorded with
+    /// [`Octree::enable_lod_journal`].
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.lod_down();
+    /// octree.lod_down();
+    ///
+    /// octree.reset_lod();
+    /// assert_eq!(octree.lod_level(), 1);
+    /// assert_eq!(octree.min_dimension(), 1);
+    /// ```
+    pub fn reset_lod(&mut self) {
+        while self.curr_lod_level > 1 {
+            self.lod_up();
+        }
+    }
+
+    /// Returns the current LOD level of the `Octree`, starting from 1 at full detail.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// assert_eq!(octree.lod_level(), 1);
+    ///
+    /// octree.lod_down();
+    /// assert_eq!(octree.lod_level(), 2);
+    /// ```
+    pub fn lod_level(&self) -> u32 {
+        self.curr_lod_level
+    }
+
+    /// Returns the coarsest LOD level [`Octree::lod_down`] reaches.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// assert_eq!(octree.max_lod_level(), 5);
+    /// ```
+    pub fn max_lod_level(&self) -> u32 {
+        self.max_lod_level
+    }
+
+    /// Returns the dimension of the smallest leaves inserted at the current LOD level.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.lod_down();
+    /// octree.lod_down();
+    ///
+    /// assert_eq!(octree.min_dimension(), 4);
+    /// ```
+    pub fn min_dimension(&self) -> u32 {
+        self.min_dimension
+    }
+
+    /// Returns the data at the given position as it would be after calling [`Octree::lod_down`] `level`
+    /// times, without modifying the `Octree`.
+    ///
+    /// Level 0 is the data returned by [`Octree::get`], and each further level takes the most common data
+    /// of blocks twice as large, by the same rule as [`Octree::lod_down`]. Leaves at least as large as the
+    /// block are returned directly, so only blocks holding detail are aggregated. Levels beyond the coarsest
+    /// one [`Octree::lod_down`] reaches are treated as the coarsest. Space never written reads as the background.
+    /// Returns `None` if the position is outside the `Octree`, or within a subtree yet to be loaded.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([0, 0, 0], 2).unwrap();
+    /// octree.insert([0, 0, 1], 2).unwrap();
+    /// octree.insert([0, 1, 0], 1).unwrap();
+    /// octree.insert([0, 1, 1], 2).unwrap();
+    /// octree.insert([1, 0, 0], 1).unwrap();
+    /// octree.insert([1, 0, 1], 2).unwrap();
+    /// octree.insert([1, 1, 0], 2).unwrap();
+    /// octree.insert([1, 1, 1], 1).unwrap();
+    ///
+    /// assert_eq!(octree.get_at_lod([0, 1, 0], 0), Some(1));
+    /// assert_eq!(octree.get_at_lod([0, 1, 0], 1), Some(2));
+    /// assert!(matches!(octree.get([0, 1, 0]), Some(1)));
+    /// ```
+    pub fn get_at_lod(&self, position: impl Into<Vector3<u32>>, level: u32) -> Option<T> {
+        let position = position.into();
+        let mut node = self.root();
+        if !node.contains(position) {
+            return None;
+        }
+
+        let dimension = 2_u32.pow(level.min(self.max_lod_level.saturating_sub(1)));
+
+        while !node.is_leaf() && node.dimension() > dimension {
+            node = match node.octant_at(position)?.1 {
+                Some(child) => child,
+                None if node.is_unloaded_at(position) => return None,
+                None => return Some(self.background),
+            };
+        }
+
+        Some(node.coarse_data(self.background, &majority))
+    }
+
+    /// Returns the value held by unwritten and cleared space in the `Octree`.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// assert_eq!(octree.background(), 0);
+    ///
+    /// let octree = Octree::<u8>::new_with_background(NonZeroU32::new(32).unwrap(), 7).unwrap();
+    /// assert_eq!(octree.background(), 7);
+    /// ```
+    pub fn background(&self) -> T {
+        self.background
+    }
+
     /// Returns the dimension of the root node.
     pub fn dimension(&self) -> u32 {
-        self.root.dimension()
+        self.dimension.get()
     }
 
     /// Returns whether the given position exists within the confines of the `Octree`.
@@ -241,7 +945,91 @@ where
     /// assert!(octree.contains([16, 29, 7]));
     /// assert!(!octree.contains([16, 29, 33]));
     /// ```
-    pub fn contains(&self, position: [u32; 3]) -> bool {
-        self.root.contains(position.into())
+    pub fn contains(&self, position: impl Into<Vector3<u32>>) -> bool {
+        self.root().contains(position.into())
+    }
+
+    /// Returns the bounds of the `Octree`, from which those of every `Node` follow.
+    pub(crate) fn bounds(&self) -> Bounds {
+        [Vector3::from([0; 3]), Vector3::from([self.dimension.get(); 3])]
+    }
+
+    pub(crate) fn root(&self) -> NodeRef<'_, T> {
+        NodeRef::new(&self.root, self.bounds())
+    }
+
+    /// Returns the root `Node` for loading subtrees held in storage into it, which leaves the contents of the
+    /// `Octree` unchanged, and so keeps any journaled detail.
+    pub(crate) fn root_to_load(&mut self) -> &mut Node<T> {
+        self.root.get_mut()
+    }
+
+    /// Returns the root `Node` for modification, discarding any journaled detail, as the modification may
+    /// overlap it.
+    pub(crate) fn root_mut(&mut self) -> &mut Node<T> {
+        self.clear_lod_journal();
+        self.root.get_mut()
+    }
+
+    /// Returns the root `Node` for modification as [`Octree::root_mut`] does, along with the pool its edits take
+    /// arrays of children from and free them into.
+    pub(crate) fn root_and_pool_mut(&mut self) -> (&mut Node<T>, &mut NodePool<T>) {
+        self.clear_lod_journal();
+        (self.root.get_mut(), &mut self.pool)
+    }
+
+    /// Discards journaled detail overlapping the leaf written at `position` at the current LOD level, so that
+    /// it is never restored over new edits.
+    fn invalidate_lod_journal(&mut self, position: [u32; 3]) {
+        let min_dimension = self.min_dimension;
+        let block = position.map(|c| c / min_dimension);
+
+        for (_, nodes) in self.lod_journal.iter_mut().flatten() {
+            nodes.retain(|(bounds, _)| {
+                let min: [u32; 3] = bounds[0].into();
+                !contains(*bounds, position.into()) && (0..3).any(|i| min[i] / min_dimension != block[i])
+            });
+        }
+    }
+
+    /// Creates a new `Octree<T>` of given dimension at the given LOD level from its root.
+    pub(crate) fn from_root(dimension: u32, root: Node<T>, background: T, lod_level: u32) -> Result<Self, Error> {
+        let mut octree = Self::new_with_background(NonZeroU32::new(dimension).unwrap(), background)?;
+
+        if lod_level == 0 || lod_level > octree.max_lod_level.max(1) {
+            return Err(Error::InvalidLodLevel(lod_level));
+        }
+
+        *octree.root.get_mut() = root;
+        octree.curr_lod_level = lod_level;
+        octree.min_dimension = 2_u32.pow(lod_level - 1);
+        Ok(octree)
+    }
+
+    /// Returns the LOD level [`Octree::lod_down`] moves to, and the leaf dimension at that level.
+    fn next_lod_level(&self) -> (u32, u32) {
+        let level = if self.curr_lod_level + 1 >= self.max_lod_level {
+            self.max_lod_level
+        } else {
+            self.curr_lod_level + 1
+        };
+
+        (level, 2_u32.pow(level - 1))
+    }
+
+    /// Creates a new `Octree<T>` with the same dimension, LOD level and background as this one, with the
+    /// given root.
+    pub(crate) fn with_root(&self, root: Node<T>) -> Self {
+        Self {
+            dimension: self.dimension,
+            curr_lod_level: self.curr_lod_level,
+            max_lod_level: self.max_lod_level,
+            min_dimension: self.min_dimension,
+            background: self.background,
+            root: CachedRoot::new(root),
+            lod_journal: None,
+            bricks: self.bricks,
+            pool: NodePool::new(POOL_CAPACITY),
+        }
     }
 }
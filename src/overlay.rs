@@ -0,0 +1,193 @@
+use crate::{fill::fill, query::Containment, Error, Octree};
+
+use alloc::vec::Vec;
+use core::{fmt::Debug, hash::Hash};
+
+/// Classifies the given cube against the box from `lower` (inclusive) to `upper` (exclusive).
+fn classify_box(lower: [i64; 3], upper: [i64; 3], min: [u32; 3], dimension: u32) -> Containment {
+    let min = min.map(|c| c as i64);
+    let max = min.map(|c| c + dimension as i64);
+
+    if (0..3).any(|i| max[i] <= lower[i] || min[i] >= upper[i]) {
+        Containment::Outside
+    } else if (0..3).all(|i| min[i] >= lower[i] && max[i] <= upper[i]) {
+        Containment::Inside
+    } else {
+        Containment::Straddling
+    }
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Writes every voxel of `src` for which `transparent` returns `false` into this `Octree`, moved by
+    /// `offset`.
+    ///
+    /// Voxels of `src` which are transparent leave this `Octree` untouched, and unwritten space in `src` is
+    /// tested as its background. Each leaf of `src` is written as a whole, so where `offset` keeps a leaf
+    /// aligned to the cubes of this `Octree` it is written as a single leaf, and only split where it is not.
+    /// If `clip` is `false`, returns an error without modifying this `Octree` when any opaque voxel would
+    /// land outside it. Otherwise, those voxels are ignored.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut prefab = Octree::<u8>::new(NonZeroU32::new(4).unwrap()).unwrap();
+    /// prefab.insert([0, 0, 0], 1).unwrap();
+    /// prefab.insert([1, 0, 0], 2).unwrap();
+    ///
+    /// let mut world = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// world.insert([11, 5, 5], 3).unwrap();
+    /// world.insert([12, 5, 5], 3).unwrap();
+    /// world.overlay(&prefab, [10, 5, 5], |data| *data == 0, false).unwrap();
+    ///
+    /// assert!(matches!(world.get([10, 5, 5]), Some(1)));
+    /// assert!(matches!(world.get([11, 5, 5]), Some(2)));
+    /// assert!(matches!(world.get([12, 5, 5]), Some(3)));
+    ///
+    /// assert_eq!(world.overlay(&prefab, [31, 0, 0], |data| *data == 0, false), Err(Error::OutOfBounds));
+    /// assert!(world.overlay(&prefab, [31, 0, 0], |data| *data == 0, true).is_ok());
+    /// ```
+    pub fn overlay(
+        &mut self,
+        src: &Octree<T>,
+        offset: [i32; 3],
+        transparent: impl Fn(&T) -> bool,
+        clip: bool,
+    ) -> Result<(), Error> {
+        let dimension = src.dimension();
+        let mut leaves = src.query_region([0; 3], [dimension; 3]);
+        let mut writes = Vec::new();
+
+        while let Some((min, dimension, data)) = leaves.next_cube() {
+            let data = data.copied().unwrap_or_else(|| src.background());
+            if transparent(&data) {
+                continue;
+            }
+
+            let min: [u32; 3] = min.into();
+            let lower = [0, 1, 2].map(|i| min[i] as i64 + offset[i] as i64);
+            writes.push((lower, lower.map(|c| c + dimension as i64), data));
+        }
+
+        let bound = self.dimension() as i64;
+        if !clip
+            && writes
+                .iter()
+                .any(|(lower, upper, _)| (0..3).any(|i| lower[i] < 0 || upper[i] > bound))
+        {
+            return Err(Error::OutOfBounds);
+        }
+
+        for (lower, upper, data) in writes {
+            fill(self, Some(data), |min, dimension| {
+                classify_box(lower, upper, min, dimension)
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_utils::XorShift, Error, Octree};
+
+    use alloc::vec::Vec;
+    use core::num::NonZeroU32;
+
+    /// Overlays `src` onto `dst` one voxel at a time.
+    fn reference(dst: &mut Octree<u8>, src: &Octree<u8>, offset: [i32; 3], transparent: impl Fn(&u8) -> bool) {
+        let dimension = src.dimension();
+
+        for x in 0..dimension {
+            for y in 0..dimension {
+                for z in 0..dimension {
+                    let data = *src.get([x, y, z]).unwrap_or(&0);
+                    let target = [0, 1, 2].map(|i| [x, y, z][i] as i64 + offset[i] as i64);
+
+                    if !transparent(&data) && target.iter().all(|c| (0..dst.dimension() as i64).contains(c)) {
+                        dst.insert(target.map(|c| c as u32), data).unwrap();
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn overlay_matches_reference() {
+        let mut rng = XorShift::new(0x0e1a);
+
+        for _ in 0..20 {
+            let seed = rng.next_u32() as u64;
+            let mut world = XorShift::new(seed).octree(32, 300, 3);
+            let mut expected = XorShift::new(seed).octree(32, 300, 3);
+
+            let prefab = rng.octree(8, 100, 4);
+            let offset = [0, 1, 2].map(|_| rng.below(40) as i32 - 4);
+            let transparent = |data: &u8| *data == 0 || *data == 4;
+
+            world.overlay(&prefab, offset, transparent, true).unwrap();
+            reference(&mut expected, &prefab, offset, transparent);
+
+            assert!(world.equivalent(&expected));
+        }
+    }
+
+    #[test]
+    fn stamp_near_edge() {
+        let mut prefab = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        for x in 0..8 {
+            prefab.insert([x, 0, 0], 1).unwrap();
+        }
+
+        let mut world = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        world.insert([15, 1, 1], 2).unwrap();
+
+        let before = world.content_hash();
+        assert_eq!(
+            world.overlay(&prefab, [12, 1, 1], |data| *data == 0, false),
+            Err(Error::OutOfBounds)
+        );
+        assert_eq!(world.content_hash(), before);
+
+        world.overlay(&prefab, [12, 1, 1], |data| *data == 0, true).unwrap();
+        for x in 12..16 {
+            assert!(matches!(world.get([x, 1, 1]), Some(1)));
+        }
+        assert!(!matches!(world.get([11, 1, 1]), Some(1)));
+
+        // Transparent space may lie outside the `Octree` without clipping.
+        assert!(world.overlay(&prefab, [8, 10, 10], |data| *data == 0, false).is_ok());
+    }
+
+    #[test]
+    fn aligned_leaves_stay_whole() {
+        let mut prefab = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        for x in 0..4 {
+            for y in 0..4 {
+                for z in 0..4 {
+                    prefab.insert([x, y, z], 1).unwrap();
+                }
+            }
+        }
+
+        let mut world = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+        world.overlay(&prefab, [8, 12, 4], |data| *data == 0, false).unwrap();
+
+        let spans = world.query_region_values([0; 3], [32; 3]).collect::<Vec<_>>();
+        assert_eq!(spans, vec![([8, 12, 4], [4, 4, 4], &1)]);
+
+        // A misaligning offset splits the block, but writes the same voxels.
+        let mut shifted = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+        shifted.overlay(&prefab, [9, 13, 5], |data| *data == 0, false).unwrap();
+
+        let mut expected = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+        reference(&mut expected, &prefab, [9, 13, 5], |data| *data == 0);
+        assert!(shifted.equivalent(&expected));
+        assert!(shifted.query_region_values([0; 3], [32; 3]).count() > 1);
+    }
+}
@@ -0,0 +1,966 @@
+use crate::{
+    codec::{encoded_len, read_value},
+    hash::Fnv1a,
+    node::{octant_bounds, OCTREE_CHILDREN},
+    Error, Node, NodeRef, Octree, ValueCodec, Vector3,
+};
+
+use alloc::{
+    collections::{btree_map::Entry, BTreeMap, BTreeSet},
+    vec::Vec,
+};
+use core::{cell::RefCell, convert::TryInto, fmt::Debug, hash::Hash, hash::Hasher};
+use hashbrown::HashMap;
+
+/// Starts encodings, followed by the version.
+const MAGIC: &[u8; 4] = b"svoP";
+/// The version of the encoding, bumped whenever its layout changes.
+const VERSION: u16 = 1;
+
+/// The size of the count of records at the start of a page.
+const PAGE_HEADER: usize = 2;
+/// The size of the offset of each record in a page.
+const SLOT: usize = 4;
+/// The size of a reference to a record: its page and its slot in that page.
+const REFERENCE: usize = 4 + 2;
+
+const LEAF: u8 = 0;
+const BRANCH: u8 = 1;
+
+/// Reads little-endian values from a byte slice, failing on truncation.
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let end = self.position.checked_add(len).ok_or(Error::InvalidEncoding)?;
+        let bytes = self.bytes.get(self.position..end).ok_or(Error::InvalidEncoding)?;
+        self.position = end;
+        Ok(bytes)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, Error> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    /// Returns the bytes which have not been read yet.
+    pub(crate) fn remaining(&self) -> &'a [u8] {
+        &self.bytes[self.position..]
+    }
+
+    /// Returns whether every byte has been read.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.position == self.bytes.len()
+    }
+}
+
+/// Returns the checksum of the given bytes.
+fn checksum(bytes: &[u8]) -> u64 {
+    let mut hasher = Fnv1a::new();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+/// Returns the number of `Node`s of an `Octree` of the given dimension with every octant written down to
+/// single voxels, the most any encoding of that dimension can expand to, saturating at `u64::MAX`.
+fn max_node_count(dimension: u32) -> u64 {
+    let mut count: u64 = 0;
+    let mut level: u64 = 1;
+    for _ in 0..=dimension.trailing_zeros() {
+        count = count.saturating_add(level);
+        level = level.saturating_mul(8);
+    }
+
+    count
+}
+
+/// The location of a record: the page holding it, and its slot within that page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Reference {
+    page: u32,
+    slot: u16,
+}
+
+/// A decoded `Node`.
+#[derive(Debug, Clone)]
+enum Record<T> {
+    Leaf(T),
+    /// An internal `Node`, with the references of its children present in the bit mask, in octant order.
+    Branch(u8, Vec<Reference>),
+}
+
+/// Decodes every record of a page.
+fn decode_page<T: ValueCodec>(bytes: &[u8]) -> Result<Vec<Record<T>>, Error> {
+    let mut reader = Reader::new(bytes);
+    let count = reader.u16()?;
+
+    let mut offsets = Vec::new();
+    for _ in 0..count {
+        offsets.push(reader.u32()? as usize);
+    }
+
+    let mut records = Vec::new();
+    for offset in offsets {
+        let mut reader = Reader::new(bytes.get(offset..).ok_or(Error::InvalidEncoding)?);
+
+        records.push(match reader.u8()? {
+            LEAF => Record::Leaf(read_value(&mut reader)?),
+            BRANCH => {
+                let mask = reader.u8()?;
+                let mut children = Vec::new();
+
+                for _ in 0..mask.count_ones() {
+                    children.push(Reference {
+                        page: reader.u32()?,
+                        slot: reader.u16()?,
+                    });
+                }
+
+                Record::Branch(mask, children)
+            }
+            _ => return Err(Error::InvalidEncoding),
+        });
+    }
+
+    Ok(records)
+}
+
+/// An `Octree` encoded as independently decodable pages, created by [`Octree::encode_paged`] or
+/// [`Octree::encode_paged_dag`].
+///
+/// The bytes start with a directory holding the format version, the dimension, background and LOD level of the
+/// `Octree`, whether `Node`s may be shared, the location of its root `Node`, the number of `Node`s it expands to,
+/// and the offset, length and checksum of each page, followed by a checksum of the directory itself. Each page
+/// holds a number of `Node`s, each of which refers to its children by page and slot, so that references stay
+/// valid however the pages are loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PagedBytes {
+    bytes: Vec<u8>,
+    page_count: usize,
+}
+
+impl PagedBytes {
+    /// Returns the encoded bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the encoded bytes, consuming the `PagedBytes`.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Returns the number of pages the `Octree` was split into.
+    pub fn page_count(&self) -> usize {
+        self.page_count
+    }
+}
+
+/// A lazily decoded view of an `Octree` encoded by [`Octree::encode_paged`] or [`Octree::encode_paged_dag`],
+/// created by [`Octree::open_paged`].
+///
+/// Only the directory is read when the view is opened. Pages are decoded, checked against the checksums in
+/// the directory, and cached as they are first needed.
+pub struct PagedOctree<'a, T> {
+    bytes: &'a [u8],
+    dimension: u32,
+    background: T,
+    lod_level: u32,
+    shared: bool,
+    root: Reference,
+    node_count: u64,
+    pages: Vec<(usize, usize, u64)>,
+    decoded: RefCell<BTreeMap<u32, Vec<Record<T>>>>,
+}
+
+impl<'a, T> PagedOctree<'a, T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + ValueCodec,
+{
+    /// Returns the record at the given location, decoding its page if it has not been decoded yet.
+    fn record(&self, reference: Reference) -> Result<Record<T>, Error> {
+        let mut decoded = self.decoded.borrow_mut();
+
+        let records = match decoded.entry(reference.page) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let (offset, len, sum) = *self.pages.get(reference.page as usize).ok_or(Error::InvalidEncoding)?;
+                let end = offset.checked_add(len).ok_or(Error::InvalidEncoding)?;
+                let bytes = self.bytes.get(offset..end).ok_or(Error::InvalidEncoding)?;
+
+                if checksum(bytes) != sum {
+                    return Err(Error::InvalidEncoding);
+                }
+
+                entry.insert(decode_page(bytes)?)
+            }
+        };
+
+        records
+            .get(reference.slot as usize)
+            .cloned()
+            .ok_or(Error::InvalidEncoding)
+    }
+
+    /// Returns the data at the given position, decoding only the pages along the path to it.
+    ///
+    /// Positions never written read as the background and those outside the `Octree` as `None`, as for
+    /// [`Octree::get`]. Returns an error if a page on the path is truncated or corrupt.
+    pub fn get(&self, position: impl Into<Vector3<u32>>) -> Result<Option<T>, Error> {
+        let position = <[u32; 3]>::from(position.into());
+        if position.iter().any(|c| *c >= self.dimension) {
+            return Ok(None);
+        }
+
+        let mut bounds = [Vector3::from([0, 0, 0]), Vector3::from([self.dimension; 3])];
+        let mut reference = self.root;
+
+        loop {
+            match self.record(reference)? {
+                Record::Leaf(data) => return Ok(Some(data)),
+                Record::Branch(mask, children) => {
+                    let (octant, child_bounds) = octant_bounds(bounds)
+                        .iter()
+                        .enumerate()
+                        .find(|(_, bounds)| {
+                            let min: [u32; 3] = bounds[0].into();
+                            let max: [u32; 3] = bounds[1].into();
+                            (0..3).all(|i| position[i] >= min[i] && position[i] < max[i])
+                        })
+                        .map(|(octant, bounds)| (octant, *bounds))
+                        .ok_or(Error::InvalidEncoding)?;
+
+                    if mask & (1 << octant) == 0 {
+                        return Ok(Some(self.background));
+                    }
+
+                    let rank = (mask & ((1 << octant) - 1)).count_ones() as usize;
+                    reference = *children.get(rank).ok_or(Error::InvalidEncoding)?;
+                    bounds = child_bounds;
+                }
+            }
+        }
+    }
+
+    /// Decodes every page, returning the whole `Octree`.
+    ///
+    /// `Node`s shared by an encoding of [`Octree::encode_paged_dag`] are copied wherever they are referred to,
+    /// building [`PagedOctree::node_count`] `Node`s in all. Returns an error if any page is truncated or corrupt,
+    /// if the `Node`s do not expand to exactly that many, or if a `Node` is referred to more than once in an
+    /// encoding which does not share them.
+    pub fn to_octree(&self) -> Result<Octree<T>, Error> {
+        let mut budget = self.node_count;
+        let root = self.node(self.root, self.dimension, &mut budget, &mut BTreeSet::new())?;
+
+        if budget != 0 {
+            return Err(Error::InvalidEncoding);
+        }
+
+        Octree::from_root(self.dimension, root, self.background, self.lod_level)
+    }
+
+    /// Rebuilds the `Node` of the given dimension stored at the given location, spending one of `budget` on
+    /// each `Node` built.
+    ///
+    /// Running out of budget is an error whether or not the encoding shares `Node`s, so that a corrupt
+    /// encoding cannot expand to more `Node`s than its directory records. Unless the encoding shares `Node`s,
+    /// locations already rebuilt are rejected as well.
+    fn node(
+        &self,
+        reference: Reference,
+        dimension: u32,
+        budget: &mut u64,
+        visited: &mut BTreeSet<Reference>,
+    ) -> Result<Node<T>, Error> {
+        *budget = budget.checked_sub(1).ok_or(Error::InvalidEncoding)?;
+        if !self.shared && !visited.insert(reference) {
+            return Err(Error::InvalidEncoding);
+        }
+
+        match self.record(reference)? {
+            Record::Leaf(data) => Ok(Node::leaf(data)),
+            Record::Branch(_, _) if dimension < 2 => Err(Error::InvalidEncoding),
+            Record::Branch(mask, children) => {
+                let mut octants: [Option<Node<T>>; OCTREE_CHILDREN] = Default::default();
+                let mut children = children.into_iter();
+
+                for (i, octant) in octants.iter_mut().enumerate() {
+                    if mask & (1 << i) != 0 {
+                        let child = children.next().ok_or(Error::InvalidEncoding)?;
+                        *octant = Some(self.node(child, dimension / 2, budget, visited)?);
+                    }
+                }
+
+                Ok(Node::branch(octants))
+            }
+        }
+    }
+
+    /// Returns the dimension of the encoded `Octree`.
+    pub fn dimension(&self) -> u32 {
+        self.dimension
+    }
+
+    /// Returns the number of `Node`s [`PagedOctree::to_octree`] builds, as recorded in the directory.
+    ///
+    /// Shared `Node`s are counted wherever they are referred to, so this may far exceed the number of `Node`s
+    /// held by the pages. Decoders of untrusted encodings can check it before expanding them.
+    pub fn node_count(&self) -> u64 {
+        self.node_count
+    }
+
+    /// Returns the number of pages of the encoded `Octree`.
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Returns the number of pages decoded so far.
+    pub fn decoded_pages(&self) -> usize {
+        self.decoded.borrow().len()
+    }
+}
+
+/// A `Node` to be encoded, referring to its children by their indices in the list of `Node`s to encode.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Shape<T> {
+    Leaf(T),
+    /// An internal `Node`, with the bit mask of its children and their indices, in octant order.
+    Branch(u8, Vec<u32>),
+}
+
+impl<T: ValueCodec> Shape<T> {
+    /// Returns the number of bytes the record of the `Node` takes.
+    fn record_len(&self) -> usize {
+        match self {
+            Self::Leaf(data) => 1 + encoded_len(data),
+            Self::Branch(_, children) => 2 + children.len() * REFERENCE,
+        }
+    }
+}
+
+/// Appends the shapes of `node` and its descendants to `shapes` bottom up, returning the index of the shape
+/// of `node`.
+///
+/// If `shared` is given, each distinct subtree is only appended once: since the children of a `Node` have
+/// been appended before it, equal subtrees have equal shapes, and `shared` maps each shape to its index.
+fn flatten<T>(node: NodeRef<'_, T>, shapes: &mut Vec<Shape<T>>, shared: &mut Option<HashMap<Shape<T>, u32>>) -> u32
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash + ValueCodec,
+{
+    let shape = match node.leaf_data() {
+        Some(data) => Shape::Leaf(*data),
+        None => {
+            let mut mask = 0;
+            let mut children = Vec::new();
+
+            for (i, (_, child)) in node.octants().enumerate() {
+                if let Some(child) = child {
+                    mask |= 1 << i;
+                    children.push(flatten(child, shapes, shared));
+                }
+            }
+
+            Shape::Branch(mask, children)
+        }
+    };
+
+    match shared {
+        Some(shared) => *shared.entry(shape.clone()).or_insert_with(|| {
+            shapes.push(shape);
+            shapes.len() as u32 - 1
+        }),
+        None => {
+            shapes.push(shape);
+            shapes.len() as u32 - 1
+        }
+    }
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + ValueCodec,
+{
+    /// Encodes the `Octree` as pages of about `page_size` bytes, which can be decoded independently by
+    /// [`Octree::open_paged`].
+    ///
+    /// `Node`s are laid out depth first, so that the path to any voxel crosses few pages. A page only
+    /// exceeds `page_size` if a single `Node` does not fit in it. Journaled LOD detail is not encoded, and
+    /// subtrees held in storage are encoded as if never written, unless loaded through [`Octree::with_source`]
+    /// first.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u16>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([1, 2, 3], 4).unwrap();
+    /// octree.insert([30, 20, 10], 5).unwrap();
+    ///
+    /// let paged = octree.encode_paged(64);
+    /// assert!(paged.page_count() > 1);
+    ///
+    /// let view = Octree::<u16>::open_paged(paged.as_bytes()).unwrap();
+    /// assert_eq!(view.get([1, 2, 3]), Ok(Some(4)));
+    /// assert!(view.decoded_pages() < paged.page_count());
+    /// ```
+    pub fn encode_paged(&self, page_size: usize) -> PagedBytes {
+        self.encode_pages(page_size, false)
+    }
+
+    /// Encodes the `Octree` as pages of about `page_size` bytes like [`Octree::encode_paged`], writing each
+    /// distinct subtree only once.
+    ///
+    /// Subtrees holding the same data in the same layout are stored as a single `Node` which each of their
+    /// parents refers to, turning the `Octree` into a directed acyclic graph. `Octree`s built from repeated
+    /// chunks encode to a fraction of the size of [`Octree::encode_paged`]. The encoding is decoded by
+    /// [`Octree::open_paged`] as usual, and [`PagedOctree::to_octree`] expands shared `Node`s into copies.
+    /// Subtrees held in storage are left out, as by `Octree::encode_paged`.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u16>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// for x in (0..32).step_by(2) {
+    ///     octree.insert([x, 0, 0], 4).unwrap();
+    /// }
+    ///
+    /// let shared = octree.encode_paged_dag(4096);
+    /// assert!(shared.as_bytes().len() < octree.encode_paged(4096).as_bytes().len());
+    ///
+    /// let view = Octree::<u16>::open_paged(shared.as_bytes()).unwrap();
+    /// assert_eq!(view.get([30, 0, 0]), Ok(Some(4)));
+    /// assert!(view.to_octree().unwrap().equivalent(&octree));
+    /// ```
+    pub fn encode_paged_dag(&self, page_size: usize) -> PagedBytes {
+        self.encode_pages(page_size, true)
+    }
+
+    /// Encodes the `Octree` as pages of about `page_size` bytes, sharing equal subtrees if `shared` is set.
+    fn encode_pages(&self, page_size: usize, shared: bool) -> PagedBytes {
+        let mut shapes = Vec::new();
+        let root = flatten(self.root(), &mut shapes, &mut shared.then(HashMap::new)) as usize;
+
+        // The number of `Node`s each shape expands to, counting shared shapes wherever they are referred to.
+        let mut node_counts: Vec<u64> = Vec::with_capacity(shapes.len());
+        for shape in shapes.iter() {
+            let count = match shape {
+                Shape::Leaf(_) => 1,
+                Shape::Branch(_, children) => children
+                    .iter()
+                    .fold(1, |sum: u64, child| sum.saturating_add(node_counts[*child as usize])),
+            };
+            node_counts.push(count);
+        }
+
+        // Assign each shape a page and slot in pre-order from the root, skipping shapes already assigned.
+        let mut references: Vec<Option<Reference>> = shapes.iter().map(|_| None).collect();
+        let mut order = Vec::new();
+        let mut page_lens: Vec<(usize, u16)> = Vec::new();
+
+        let mut stack = Vec::new();
+        stack.push(root);
+
+        while let Some(index) = stack.pop() {
+            if references[index].is_some() {
+                continue;
+            }
+
+            let shape = &shapes[index];
+            let len = SLOT + shape.record_len();
+
+            match page_lens.last_mut() {
+                Some((page_len, count)) if *page_len + len <= page_size && *count < u16::MAX => {
+                    *page_len += len;
+                    *count += 1;
+                }
+                _ => page_lens.push((PAGE_HEADER + len, 1)),
+            }
+
+            references[index] = Some(Reference {
+                page: page_lens.len() as u32 - 1,
+                slot: page_lens.last().unwrap().1 - 1,
+            });
+            order.push(index);
+
+            // Push in reverse, so that children are laid out in octant order.
+            if let Shape::Branch(_, children) = shape {
+                stack.extend(children.iter().rev().map(|child| *child as usize));
+            }
+        }
+
+        let references = references.into_iter().map(Option::unwrap).collect::<Vec<_>>();
+
+        // Write the records of each page, with the offset of each record from the start of the records.
+        let mut pages: Vec<(Vec<u8>, Vec<u8>)> = page_lens.iter().map(|_| (Vec::new(), Vec::new())).collect();
+
+        for index in order {
+            let (offsets, records) = &mut pages[references[index].page as usize];
+            offsets.extend_from_slice(&(records.len() as u32).to_le_bytes());
+
+            match &shapes[index] {
+                Shape::Leaf(data) => {
+                    records.push(LEAF);
+                    data.write(records);
+                }
+                Shape::Branch(mask, children) => {
+                    records.push(BRANCH);
+                    records.push(*mask);
+
+                    for child in children {
+                        let child = references[*child as usize];
+                        records.extend_from_slice(&child.page.to_le_bytes());
+                        records.extend_from_slice(&child.slot.to_le_bytes());
+                    }
+                }
+            }
+        }
+
+        let pages = pages
+            .into_iter()
+            .map(|(offsets, records)| {
+                let count = (offsets.len() / SLOT) as u16;
+                let header = PAGE_HEADER + offsets.len();
+
+                let mut page = Vec::new();
+                page.extend_from_slice(&count.to_le_bytes());
+                for offset in offsets.chunks(SLOT) {
+                    let offset = u32::from_le_bytes(offset.try_into().unwrap()) + header as u32;
+                    page.extend_from_slice(&offset.to_le_bytes());
+                }
+                page.extend_from_slice(&records);
+                page
+            })
+            .collect::<Vec<_>>();
+
+        // Write the directory, followed by the pages.
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.dimension().to_le_bytes());
+        bytes.extend_from_slice(&self.lod_level().to_le_bytes());
+        self.background().write(&mut bytes);
+        bytes.push(shared as u8);
+        bytes.extend_from_slice(&references[root].page.to_le_bytes());
+        bytes.extend_from_slice(&references[root].slot.to_le_bytes());
+        bytes.extend_from_slice(&node_counts[root].to_le_bytes());
+        bytes.extend_from_slice(&(pages.len() as u32).to_le_bytes());
+
+        let mut offset = bytes.len() + pages.len() * (8 + 4 + 8) + 8;
+        for page in pages.iter() {
+            bytes.extend_from_slice(&(offset as u64).to_le_bytes());
+            bytes.extend_from_slice(&(page.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&checksum(page).to_le_bytes());
+            offset += page.len();
+        }
+
+        let sum = checksum(&bytes);
+        bytes.extend_from_slice(&sum.to_le_bytes());
+
+        for page in pages.iter() {
+            bytes.extend_from_slice(page);
+        }
+
+        PagedBytes {
+            bytes,
+            page_count: pages.len(),
+        }
+    }
+
+    /// Opens an `Octree` encoded by [`Octree::encode_paged`] or [`Octree::encode_paged_dag`] without decoding
+    /// any of its pages.
+    ///
+    /// Returns an error if the directory is truncated or does not match its checksum, or if it was written
+    /// by another version of the format. Pages are only checked as they are decoded.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([1, 2, 3], 4).unwrap();
+    ///
+    /// let mut bytes = octree.encode_paged(4096).into_bytes();
+    /// let view = Octree::<u8>::open_paged(&bytes).unwrap();
+    /// assert_eq!(view.decoded_pages(), 0);
+    /// assert!(view.to_octree().unwrap().equivalent(&octree));
+    ///
+    /// bytes[8] ^= 1;
+    /// assert!(matches!(Octree::<u8>::open_paged(&bytes), Err(Error::InvalidEncoding)));
+    /// ```
+    pub fn open_paged(bytes: &[u8]) -> Result<PagedOctree<'_, T>, Error> {
+        let mut reader = Reader::new(bytes);
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(Error::InvalidEncoding);
+        }
+
+        let version = reader.u16()?;
+        if version != VERSION {
+            return Err(Error::UnsupportedVersion(version as u32));
+        }
+
+        let dimension = reader.u32()?;
+        let lod_level = reader.u32()?;
+        let background = read_value(&mut reader)?;
+
+        let shared = match reader.u8()? {
+            0 => false,
+            1 => true,
+            _ => return Err(Error::InvalidEncoding),
+        };
+
+        let root = Reference {
+            page: reader.u32()?,
+            slot: reader.u16()?,
+        };
+        let node_count = reader.u64()?;
+
+        let mut pages = Vec::new();
+        for _ in 0..reader.u32()? {
+            pages.push((reader.u64()? as usize, reader.u32()? as usize, reader.u64()?));
+        }
+
+        let len = reader.position;
+        if reader.u64()? != checksum(&bytes[..len])
+            || !dimension.is_power_of_two()
+            || node_count == 0
+            || node_count > max_node_count(dimension)
+        {
+            return Err(Error::InvalidEncoding);
+        }
+
+        Ok(PagedOctree {
+            bytes,
+            dimension,
+            background,
+            lod_level,
+            shared,
+            root,
+            node_count,
+            pages,
+            decoded: RefCell::new(BTreeMap::new()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{checksum, VERSION};
+    use crate::{
+        test_utils::{voxels, Voxel, XorShift},
+        Error, Octree,
+    };
+
+    use core::{convert::TryInto, num::NonZeroU32};
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    /// The length of the directory header of an `Octree<u8>`, up to the page entries.
+    const HEADER: usize = 34;
+
+    /// Recomputes the checksums of the pages and directory of an `Octree<u8>`, wherever they can be found,
+    /// so that corruption reaches the decoder instead of failing a checksum.
+    fn reseal(bytes: &mut [u8]) {
+        let read = |bytes: &[u8], at: usize, len: usize| {
+            bytes.get(at..at + len).map(|b| {
+                let mut value = [0; 8];
+                value[..len].copy_from_slice(b);
+                u64::from_le_bytes(value) as usize
+            })
+        };
+
+        let count = match read(bytes, HEADER - 4, 4) {
+            Some(count) if HEADER + count * 20 + 8 <= bytes.len() => count,
+            _ => return,
+        };
+
+        for page in 0..count {
+            let entry = HEADER + page * 20;
+            let (offset, len) = (read(bytes, entry, 8).unwrap(), read(bytes, entry + 8, 4).unwrap());
+
+            if let Some(sum) = offset
+                .checked_add(len)
+                .and_then(|end| bytes.get(offset..end))
+                .map(checksum)
+            {
+                bytes[entry + 12..entry + 20].copy_from_slice(&sum.to_le_bytes());
+            }
+        }
+
+        let end = HEADER + count * 20;
+        let sum = checksum(&bytes[..end]);
+        bytes[end..end + 8].copy_from_slice(&sum.to_le_bytes());
+    }
+
+    /// Decodes every part of a possibly corrupt `Octree<u8>`, returning whether it decoded without error.
+    fn decode(bytes: &[u8]) -> bool {
+        match Octree::<u8>::open_paged(bytes) {
+            Ok(view) => {
+                let gets = (0..view.dimension().min(4)).all(|c| view.get([c, 0, c]).is_ok());
+                view.to_octree().is_ok() && gets
+            }
+            Err(_) => false,
+        }
+    }
+
+    #[test]
+    fn reconstruction_matches_original() {
+        let mut rng = XorShift::new(0x9a6e);
+
+        for page_size in [1, 64, 512, 1 << 16] {
+            let mut octree = rng.octree(32, 2000, 5);
+            octree.lod_down();
+
+            let paged = octree.encode_paged(page_size);
+            let view = Octree::<u8>::open_paged(paged.as_bytes()).unwrap();
+            let copy = view.to_octree().unwrap();
+
+            assert_eq!(alloc::format!("{:#?}", copy), alloc::format!("{:#?}", octree));
+            assert_eq!(view.decoded_pages(), paged.page_count());
+
+            for _ in 0..200 {
+                let position = rng.position(32);
+                assert_eq!(view.get(position), Ok(octree.get(position).copied()));
+            }
+        }
+    }
+
+    #[test]
+    fn get_decodes_pages_on_its_path() {
+        let mut rng = XorShift::new(0x9a6f);
+        let octree = rng.octree(64, 5000, 5);
+        let paged = octree.encode_paged(256);
+
+        for _ in 0..20 {
+            let view = Octree::<u8>::open_paged(paged.as_bytes()).unwrap();
+            let position = rng.position(64);
+
+            assert_eq!(view.get(position), Ok(octree.get(position).copied()));
+
+            // The path holds at most one `Node` per level, from the root down to single voxels.
+            let decoded = view.decoded_pages();
+            assert!((1..=7).contains(&decoded));
+            assert!(decoded * 10 < paged.page_count());
+
+            view.get(position).unwrap();
+            assert_eq!(view.decoded_pages(), decoded);
+        }
+    }
+
+    #[test]
+    fn background_and_lod_level_survive() {
+        let mut octree = Octree::<u32>::new_with_background(NonZeroU32::new(16).unwrap(), 9).unwrap();
+        octree.insert([1, 2, 3], 400_000).unwrap();
+        octree.set_lod_level(2).unwrap();
+
+        let paged = octree.encode_paged(128);
+        let copy = Octree::<u32>::open_paged(paged.as_bytes())
+            .unwrap()
+            .to_octree()
+            .unwrap();
+
+        assert_eq!(copy.background(), 9);
+        assert_eq!(copy.lod_level(), 2);
+        assert!(copy.equivalent(&octree));
+    }
+
+    #[test]
+    fn values_of_varying_length_survive() {
+        let octree = voxels(&XorShift::new(0x9a71).octree(16, 500, 5));
+        let paged = octree.encode_paged(128);
+        let view = Octree::<Voxel>::open_paged(paged.as_bytes()).unwrap();
+
+        assert_eq!(view.get([3, 4, 5]).unwrap(), octree.get([3, 4, 5]).copied());
+        assert!(view.to_octree().unwrap().equivalent(&octree));
+    }
+
+    #[test]
+    fn corruption_is_detected() {
+        let mut rng = XorShift::new(0x9a70);
+        let octree = rng.octree(16, 500, 5);
+        let bytes = octree.encode_paged(128).into_bytes();
+
+        assert!(matches!(
+            Octree::<u8>::open_paged(&bytes[..10]),
+            Err(Error::InvalidEncoding)
+        ));
+        assert!(matches!(Octree::<u16>::open_paged(&bytes), Err(Error::InvalidEncoding)));
+
+        // Corrupting the last page is only noticed once it is decoded.
+        let mut corrupt = bytes.clone();
+        let last = corrupt.len() - 1;
+        corrupt[last] ^= 0xff;
+
+        let view = Octree::<u8>::open_paged(&corrupt).unwrap();
+        assert_eq!(view.to_octree().err(), Some(Error::InvalidEncoding));
+
+        let truncated = Octree::<u8>::open_paged(&bytes[..bytes.len() - 1]).unwrap();
+        assert_eq!(truncated.to_octree().err(), Some(Error::InvalidEncoding));
+    }
+
+    #[test]
+    fn versions_are_checked() {
+        let mut rng = XorShift::new(0x9a71);
+        let octree = rng.octree(16, 500, 5);
+        let mut bytes = octree.encode_paged(128).into_bytes();
+
+        assert_eq!(&bytes[4..6], &VERSION.to_le_bytes());
+
+        for version in [0, VERSION + 1, 0xffff] {
+            bytes[4..6].copy_from_slice(&version.to_le_bytes());
+            assert!(matches!(
+                Octree::<u8>::open_paged(&bytes),
+                Err(Error::UnsupportedVersion(v)) if v == version as u32
+            ));
+        }
+    }
+
+    #[test]
+    fn corrupt_pages_never_panic() {
+        let mut rng = XorShift::new(0x9a72);
+        let octree = rng.octree(8, 40, 5);
+
+        for bytes in [
+            octree.encode_paged(48).into_bytes(),
+            octree.encode_paged_dag(48).into_bytes(),
+        ] {
+            for len in 0..bytes.len() {
+                let mut truncated = bytes[..len].to_vec();
+                reseal(&mut truncated);
+                assert!(catch_unwind(|| decode(&truncated)).is_ok());
+            }
+
+            for i in 0..bytes.len() {
+                for flip in [0x01, 0x80, 0xff] {
+                    let mut corrupt = bytes.clone();
+                    corrupt[i] ^= flip;
+                    reseal(&mut corrupt);
+                    assert!(catch_unwind(AssertUnwindSafe(|| decode(&corrupt))).is_ok());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn shared_nodes_are_rejected() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(2).unwrap()).unwrap();
+        octree.insert([0, 0, 0], 1).unwrap();
+        octree.insert([1, 0, 0], 2).unwrap();
+
+        let mut bytes = octree.encode_paged(4096).into_bytes();
+        assert!(decode(&bytes));
+
+        // Point both children of the root at the first one: the page follows the directory and its checksum,
+        // then holds a count, three offsets, and the tag and mask of the root before its references.
+        let root = HEADER + 20 + 8 + 2 + 3 * 4 + 2;
+        let first: [u8; 6] = bytes[root..root + 6].try_into().unwrap();
+        bytes[root + 6..root + 12].copy_from_slice(&first);
+        reseal(&mut bytes);
+
+        let view = Octree::<u8>::open_paged(&bytes).unwrap();
+        assert_eq!(view.get([1, 0, 0]), Ok(Some(1)));
+        assert_eq!(view.to_octree().err(), Some(Error::InvalidEncoding));
+    }
+
+    #[test]
+    fn expansion_is_bounded_by_the_directory() {
+        // A checkerboard, whose every octant of a given size is the same as every other.
+        let octree = Octree::from_fn(NonZeroU32::new(16).unwrap(), |[x, y, z]| (x + y + z) as u8 % 2).unwrap();
+        let mut bytes = octree.encode_paged_dag(4096).into_bytes();
+
+        let view = Octree::<u8>::open_paged(&bytes).unwrap();
+        let count = view.node_count();
+        assert_eq!(
+            count,
+            Octree::<u8>::open_paged(octree.encode_paged(4096).as_bytes())
+                .unwrap()
+                .node_count()
+        );
+        assert!(view.to_octree().unwrap().equivalent(&octree));
+
+        // The count follows the shared flag and the root, and the shared flag does not lift it.
+        let at = HEADER - 4 - 8;
+        for wrong in [count - 1, count + 1] {
+            bytes[at..at + 8].copy_from_slice(&wrong.to_le_bytes());
+            reseal(&mut bytes);
+
+            let view = Octree::<u8>::open_paged(&bytes).unwrap();
+            assert_eq!(view.get([1, 0, 0]), Ok(Some(1)));
+            assert_eq!(view.to_octree().err(), Some(Error::InvalidEncoding));
+        }
+
+        // No `Octree` 16 voxels across has more `Node`s than one with every voxel written.
+        for wrong in [0, 1 + 8 + 64 + 512 + 4096 + 1, u64::MAX] {
+            bytes[at..at + 8].copy_from_slice(&wrong.to_le_bytes());
+            reseal(&mut bytes);
+            assert_eq!(Octree::<u8>::open_paged(&bytes).err(), Some(Error::InvalidEncoding));
+        }
+    }
+
+    #[test]
+    fn repeated_chunks_are_shared() {
+        let mut rng = XorShift::new(0x9a74);
+        let chunk = rng.octree(8, 200, 5);
+
+        // Place copies of the chunk in a checkerboard, leaving the other chunks unwritten.
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(64).unwrap()).unwrap();
+        for (cx, cy, cz) in (0..8).flat_map(|x| (0..8).flat_map(move |y| (0..8).map(move |z| (x, y, z)))) {
+            if (cx + cy + cz) % 2 == 1 {
+                continue;
+            }
+
+            for (x, y, z) in (0..8).flat_map(|x| (0..8).flat_map(move |y| (0..8).map(move |z| (x, y, z)))) {
+                if let Some(data) = chunk.get([x, y, z]) {
+                    octree.insert([cx * 8 + x, cy * 8 + y, cz * 8 + z], *data).unwrap();
+                }
+            }
+        }
+
+        let naive = octree.encode_paged(4096);
+        let shared = octree.encode_paged_dag(4096);
+        assert!(shared.as_bytes().len() * 20 < naive.as_bytes().len());
+
+        let view = Octree::<u8>::open_paged(shared.as_bytes()).unwrap();
+        let copy = view.to_octree().unwrap();
+        assert_eq!(alloc::format!("{:#?}", copy), alloc::format!("{:#?}", octree));
+
+        for _ in 0..200 {
+            let position = rng.position(64);
+            assert_eq!(view.get(position), Ok(octree.get(position).copied()));
+        }
+
+        // Random trees share little, but still round-trip.
+        for page_size in [1, 256] {
+            let octree = rng.octree(32, 2000, 3);
+            let shared = octree.encode_paged_dag(page_size);
+            let copy = Octree::<u8>::open_paged(shared.as_bytes())
+                .unwrap()
+                .to_octree()
+                .unwrap();
+
+            assert_eq!(alloc::format!("{:#?}", copy), alloc::format!("{:#?}", octree));
+            assert!(shared.as_bytes().len() <= octree.encode_paged(page_size).as_bytes().len());
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{LeafInfo, Octree};
+
+use alloc::vec::Vec;
+use core::{fmt::Debug, hash::Hash};
+use parry3d::{
+    math::{Isometry, Vector},
+    shape::{Compound, SharedShape},
+};
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Builds a compound collider for `parry3d` from the leaves holding data for which `solid` returns `true`,
+    /// with each voxel `voxel_size` across and the origin at the corner of voxel `[0, 0, 0]`.
+    ///
+    /// Each leaf becomes a single cuboid however many voxels it covers, and runs of leaves of the same
+    /// dimension lying next to each other along the x axis are joined into one cuboid. Unwritten space and
+    /// leaves holding the background are never solid. Returns `None` if no leaf is solid, as a compound shape
+    /// cannot be empty.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+    /// octree.insert([0, 0, 0], 1).unwrap();
+    /// octree.insert([1, 0, 0], 1).unwrap();
+    /// octree.insert([5, 5, 5], 2).unwrap();
+    ///
+    /// let compound = octree.to_parry_compound(0.5, |data| *data == 1).unwrap();
+    /// assert_eq!(compound.shapes().len(), 1);
+    /// assert_eq!(compound.local_aabb().maxs.x, 1.0);
+    /// ```
+    pub fn to_parry_compound(&self, voxel_size: f32, solid: impl Fn(&T) -> bool) -> Option<Compound> {
+        let mut leaves = self
+            .iter_leaves()
+            .filter(|leaf| solid(&leaf.data))
+            .collect::<Vec<LeafInfo<T>>>();
+        leaves.sort_unstable_by_key(|leaf| (leaf.dimension, leaf.min[2], leaf.min[1], leaf.min[0]));
+
+        // Each run is the first leaf of the run along with the number of voxels it spans along the x axis.
+        let mut runs: Vec<(LeafInfo<T>, u32)> = Vec::new();
+        for leaf in leaves {
+            match runs.last_mut() {
+                Some((first, length))
+                    if first.dimension == leaf.dimension
+                        && first.min[1..] == leaf.min[1..]
+                        && first.min[0] + *length == leaf.min[0] =>
+                {
+                    *length += leaf.dimension;
+                }
+                _ => runs.push((leaf, leaf.dimension)),
+            }
+        }
+
+        if runs.is_empty() {
+            return None;
+        }
+
+        let shapes = runs
+            .into_iter()
+            .map(|(first, length)| {
+                let extents = Vector::new(length as f32, first.dimension as f32, first.dimension as f32) / 2.0;
+                let min = Vector::new(first.min[0] as f32, first.min[1] as f32, first.min[2] as f32);
+                let [half, center] = [extents, min + extents].map(|v| v * voxel_size);
+
+                (
+                    Isometry::translation(center.x, center.y, center.z),
+                    SharedShape::cuboid(half.x, half.y, half.z),
+                )
+            })
+            .collect();
+
+        Some(Compound::new(shapes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_utils::XorShift, Octree};
+
+    use core::num::NonZeroU32;
+    use parry3d::{math::Point, query::PointQuery};
+
+    #[test]
+    fn compound_bounds_match_the_leaves() {
+        let mut rng = XorShift::new(0x9a27);
+
+        for _ in 0..10 {
+            let octree = rng.octree(16, 300, 3);
+            let solid = |data: &u8| *data != 2;
+            let voxel_size = 0.25;
+
+            let (mut min, mut max) = ([u32::MAX; 3], [0; 3]);
+            let mut solid_voxel = None;
+            for leaf in octree.iter_leaves().filter(|leaf| solid(&leaf.data)) {
+                for i in 0..3 {
+                    min[i] = min[i].min(leaf.min[i]);
+                    max[i] = max[i].max(leaf.min[i] + leaf.dimension);
+                }
+                solid_voxel = Some(leaf.min);
+            }
+
+            let compound = match octree.to_parry_compound(voxel_size, solid) {
+                Some(compound) => compound,
+                None => {
+                    assert_eq!(solid_voxel, None);
+                    continue;
+                }
+            };
+
+            let aabb = compound.local_aabb();
+            for i in 0..3 {
+                assert!((aabb.mins[i] - min[i] as f32 * voxel_size).abs() < 1e-4);
+                assert!((aabb.maxs[i] - max[i] as f32 * voxel_size).abs() < 1e-4);
+            }
+
+            let [x, y, z] = solid_voxel.unwrap().map(|c| (c as f32 + 0.5) * voxel_size);
+            assert!(compound.project_local_point(&Point::new(x, y, z), true).is_inside);
+            assert!(compound.shapes().len() <= octree.iter_leaves().count());
+        }
+    }
+
+    #[test]
+    fn large_leaves_are_single_cuboids() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+        for x in 0..16 {
+            for y in 0..16 {
+                for z in 0..16 {
+                    octree.insert([x, y, z], 1).unwrap();
+                }
+            }
+        }
+        octree.insert([20, 20, 20], 3).unwrap();
+
+        let compound = octree.to_parry_compound(1.0, |data| *data == 1).unwrap();
+        assert_eq!(compound.shapes().len(), 1);
+        assert_eq!(compound.local_aabb().maxs, Point::new(16.0, 16.0, 16.0));
+
+        assert!(compound.contains_local_point(&Point::new(8.0, 8.0, 8.0)));
+        assert!(!compound.contains_local_point(&Point::new(20.5, 20.5, 20.5)));
+        assert!(octree.to_parry_compound(1.0, |data| *data == 4).is_none());
+    }
+}
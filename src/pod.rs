@@ -0,0 +1,280 @@
+use crate::{
+    flat::{unflatten, Token},
+    node::OCTREE_CHILDREN,
+    Error, Octree,
+};
+
+use alloc::{vec, vec::Vec};
+use bytemuck::{Pod, Zeroable};
+use core::{convert::TryFrom, fmt::Debug, hash::Hash, num::NonZeroU32};
+
+/// One `Node` of an `Octree` flattened by [`Octree::flatten`], laid out to be cast to bytes with `bytemuck` and
+/// uploaded into a GPU storage buffer as it is.
+///
+/// `Node`s are listed breadth-first, with the root first, and refer to their children by their absolute index in
+/// the list. The children of an internal `Node` happen to be contiguous, but traversal should not rely on it.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Pod, Zeroable)]
+pub struct FlatNode {
+    /// The position of the corner of the `Node` closest to the origin.
+    pub min: [u32; 3],
+    /// The number of voxels the `Node` spans along each axis.
+    pub dimension: u32,
+    /// The index of the child in each octant, or [`FlatNode::NONE`] for octants never written. Always
+    /// [`FlatNode::NONE`] for leaves.
+    pub child_indices: [u32; 8],
+    /// The index of the data of a leaf in the values returned alongside the `Node`s, or [`FlatNode::NONE`] for
+    /// internal `Node`s.
+    pub value_index: u32,
+    /// A set of bits describing the `Node`, of which only [`FlatNode::LEAF`] is used.
+    pub flags: u32,
+}
+
+impl FlatNode {
+    /// The index held in place of a missing child or value.
+    pub const NONE: u32 = u32::MAX;
+
+    /// The bit of [`FlatNode::flags`] set for leaves.
+    pub const LEAF: u32 = 1;
+
+    /// Returns whether the `Node` is a leaf.
+    pub fn is_leaf(&self) -> bool {
+        self.flags & Self::LEAF != 0
+    }
+
+    /// Returns the index of the child in the given octant, if it has ever been written.
+    pub fn child(&self, octant: usize) -> Option<u32> {
+        Some(self.child_indices[octant]).filter(|index| *index != Self::NONE)
+    }
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Flattens the `Octree` into a list of [`FlatNode`]s, breadth-first with the root at index 0, and the data
+    /// of its leaves, in the order the leaves are listed.
+    ///
+    /// When `T` is `Pod`, the values can be cast to bytes with `bytemuck` and uploaded alongside the `Node`s.
+    /// Otherwise, they can be kept on the CPU, or converted into a `Pod` type first, as the `Node`s only refer to
+    /// them by index. Subtrees held in storage and not loaded are left out, as octants never written.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, FlatNode, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u32>::new(NonZeroU32::new(4).unwrap()).unwrap();
+    /// octree.insert([3, 0, 0], 1).unwrap();
+    ///
+    /// let (nodes, values) = octree.flatten();
+    /// assert_eq!(nodes.len(), 3);
+    /// assert_eq!(nodes[0].child(1), Some(1));
+    /// assert_eq!(nodes[2].min, [3, 0, 0]);
+    /// assert_eq!(values[nodes[2].value_index as usize], 1);
+    ///
+    /// let bytes: &[u8] = bytemuck::cast_slice(&nodes);
+    /// assert_eq!(bytes.len(), 3 * 56);
+    /// ```
+    pub fn flatten(&self) -> (Vec<FlatNode>, Vec<T>) {
+        let mut queue = vec![self.root()];
+        let (mut nodes, mut values) = (Vec::new(), Vec::new());
+
+        // `Node`s are numbered in the order they are queued, so walking the queue in order is breadth-first.
+        let mut next = 0;
+        while let Some(node) = queue.get(next).copied() {
+            let mut flat = FlatNode {
+                min: node.min_position().into(),
+                dimension: node.dimension(),
+                child_indices: [FlatNode::NONE; OCTREE_CHILDREN],
+                value_index: FlatNode::NONE,
+                flags: 0,
+            };
+
+            match node.leaf_data() {
+                Some(data) => {
+                    flat.value_index = u32::try_from(values.len()).expect("value index overflow");
+                    flat.flags = FlatNode::LEAF;
+                    values.push(*data);
+                }
+                None => {
+                    for octant in 0..OCTREE_CHILDREN {
+                        if let Some(child) = node.child(octant) {
+                            flat.child_indices[octant] = u32::try_from(queue.len()).expect("node index overflow");
+                            queue.push(child);
+                        }
+                    }
+                }
+            }
+
+            nodes.push(flat);
+            next += 1;
+        }
+
+        (nodes, values)
+    }
+
+    /// Rebuilds an `Octree` from the [`FlatNode`]s and values returned by [`Octree::flatten`], at LOD level 1
+    /// and with the default background.
+    ///
+    /// Only the `Node`s reachable from the root are read. Returns [`Error::InvalidEncoding`] if any index is out
+    /// of range or any child does not have half the dimension of its parent, and [`Error::InvalidDimension`] if
+    /// the root does not have a valid dimension.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([9, 8, 31], 1).unwrap();
+    ///
+    /// let (nodes, values) = octree.flatten();
+    /// let copy = Octree::from_flat(&nodes, &values)?;
+    /// assert_eq!(copy.get([9, 8, 31]), Some(&1));
+    /// assert_eq!(copy.to_bytes(), octree.to_bytes());
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn from_flat(nodes: &[FlatNode], values: &[T]) -> Result<Self, Error> {
+        let root = nodes.first().ok_or(Error::InvalidEncoding)?;
+        let octree = Self::new(NonZeroU32::new(root.dimension).ok_or(Error::InvalidDimension(0))?)?;
+
+        // Walked depth-first, pushing children in reverse, so that tokens come in the pre-order expected.
+        let mut stack = vec![(0, root.dimension)];
+        let tokens = core::iter::from_fn(|| {
+            let (index, dimension) = stack.pop()?;
+            let node = match nodes.get(index as usize) {
+                Some(node) if node.dimension == dimension => node,
+                _ => return Some(Err(Error::InvalidEncoding)),
+            };
+
+            if node.is_leaf() {
+                let value = values.get(node.value_index as usize).ok_or(Error::InvalidEncoding);
+                return Some(value.map(|value| Token::Leaf(*value)));
+            }
+
+            let mut mask = 0;
+            for octant in (0..OCTREE_CHILDREN).rev() {
+                if let Some(child) = node.child(octant) {
+                    mask |= 1 << octant;
+                    stack.push((child, dimension / 2));
+                }
+            }
+
+            Some(Ok(Token::Branch(mask)))
+        });
+
+        let root = unflatten(root.dimension, tokens, |_| Error::InvalidEncoding)?;
+        Ok(octree.with_root(root))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FlatNode;
+    use crate::{test_utils::XorShift, Error, Octree};
+
+    use alloc::vec;
+    use core::num::NonZeroU32;
+
+    /// Retrieves the data at the given position by walking `nodes` down from the root, as a shader would.
+    fn flat_get<'a, T>(nodes: &[FlatNode], values: &'a [T], position: [u32; 3]) -> Option<&'a T> {
+        let mut node = nodes[0];
+        if position.iter().any(|c| *c >= node.dimension) {
+            return None;
+        }
+
+        while !node.is_leaf() {
+            let half = node.dimension / 2;
+            let [x, y, z] = position.map(|c| (c & half != 0) as usize);
+            node = nodes[node.child(y << 2 | z << 1 | x)? as usize];
+        }
+
+        Some(&values[node.value_index as usize])
+    }
+
+    #[test]
+    fn flat_nodes_read_as_the_octree() {
+        let mut rng = XorShift::new(0xf1a8);
+
+        for dimension in [1, 2, 8, 16] {
+            for _ in 0..5 {
+                let inserts = rng.below(dimension * dimension * 4);
+                let mut octree = rng.octree(dimension, inserts, 4);
+                for _ in 0..rng.below(dimension * 4) {
+                    octree.clear_at(rng.position(dimension)).unwrap();
+                }
+
+                let (nodes, values) = octree.flatten();
+                for x in 0..dimension {
+                    for y in 0..dimension {
+                        for z in 0..dimension {
+                            let position = [x, y, z];
+                            assert_eq!(
+                                flat_get(&nodes, &values, position),
+                                octree.root().get(position.into()),
+                                "at {:?}",
+                                position
+                            );
+                        }
+                    }
+                }
+
+                // Each child lies in its octant of its parent, after the parent.
+                for (index, node) in nodes.iter().enumerate() {
+                    for (octant, child_index) in (0..8).filter_map(|octant| Some((octant, node.child(octant)?))) {
+                        let child = &nodes[child_index as usize];
+                        let offset =
+                            [octant & 1, (octant >> 2) & 1, (octant >> 1) & 1].map(|c| c as u32 * child.dimension);
+                        assert!(child_index as usize > index);
+                        assert_eq!(child.min, [0, 1, 2].map(|a| node.min[a] + offset[a]));
+                    }
+                }
+
+                let copy = Octree::from_flat(&nodes, &values).unwrap();
+                assert_eq!(copy.to_bytes(), octree.to_bytes());
+            }
+        }
+    }
+
+    #[test]
+    fn malformed_flat_nodes_are_rejected() {
+        let octree = XorShift::new(0xf1a9).octree(8, 40, 3);
+        let (nodes, values) = octree.flatten();
+
+        assert!(matches!(
+            Octree::<u8>::from_flat(&[], &values),
+            Err(Error::InvalidEncoding)
+        ));
+        assert!(matches!(
+            Octree::from_flat(&nodes, &values[1..]),
+            Err(Error::InvalidEncoding)
+        ));
+
+        let mut cycle = nodes.clone();
+        let octant = (0..8).find(|octant| cycle[0].child(*octant).is_some()).unwrap();
+        cycle[0].child_indices[octant] = 0;
+        assert!(matches!(
+            Octree::from_flat(&cycle, &values),
+            Err(Error::InvalidEncoding)
+        ));
+
+        let odd = vec![FlatNode {
+            dimension: 3,
+            flags: FlatNode::LEAF,
+            ..FlatNode::default()
+        }];
+        assert!(matches!(
+            Octree::from_flat(&odd, &[1_u8]),
+            Err(Error::InvalidDimension(3))
+        ));
+        assert_eq!(
+            Octree::<u8>::new(NonZeroU32::new(4).unwrap())
+                .unwrap()
+                .flatten()
+                .0
+                .len(),
+            1
+        );
+    }
+}
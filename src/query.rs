@@ -0,0 +1,905 @@
+use crate::{fill::fill, Error, LeafInfo, NodeRef, Octree, Vector3};
+
+use alloc::vec::Vec;
+use core::{fmt::Debug, hash::Hash};
+
+enum Pending<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    Node(NodeRef<'a, T>),
+    Gap(Vector3<u32>, u32),
+}
+
+/// An iterator over the spans of an `Octree` intersecting an axis-aligned box, clipped to that box.
+///
+/// Yields `(min, dimensions, data)`, where `data` is `None` for space that has never been written.
+/// Created by [`Octree::query_region`].
+pub struct RegionIter<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    min: [u32; 3],
+    max: [u32; 3],
+    stack: Vec<Pending<'a, T>>,
+}
+
+impl<'a, T> RegionIter<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    pub(crate) fn new(root: NodeRef<'a, T>, min: [u32; 3], max: [u32; 3]) -> Self {
+        let mut iter = Self {
+            min,
+            max,
+            stack: Vec::new(),
+        };
+
+        if iter.clip(root.min_position(), root.dimension()).is_some() {
+            iter.stack.push(Pending::Node(root));
+        }
+
+        iter
+    }
+
+    /// Clips the given cube to the query box, returning the minimum position and dimensions of the result.
+    fn clip(&self, min: Vector3<u32>, dimension: u32) -> Option<([u32; 3], [u32; 3])> {
+        let min: [u32; 3] = min.into();
+
+        let lower = [0, 1, 2].map(|i| min[i].max(self.min[i]));
+        let upper = [0, 1, 2].map(|i| (min[i] + dimension).min(self.max[i]));
+
+        if (0..3).all(|i| lower[i] < upper[i]) {
+            Some((lower, [0, 1, 2].map(|i| upper[i] - lower[i])))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the next leaf or unwritten cube intersecting the query box, without clipping it.
+    pub(crate) fn next_cube(&mut self) -> Option<(Vector3<u32>, u32, Option<&'a T>)> {
+        while let Some(pending) = self.stack.pop() {
+            let node = match pending {
+                Pending::Node(node) => node,
+                Pending::Gap(min, dimension) => return Some((min, dimension, None)),
+            };
+
+            if let Some(data) = node.leaf_data() {
+                return Some((node.min_position(), node.dimension(), Some(data)));
+            }
+
+            let dimension = node.dimension() / 2;
+
+            let mut octants = [None; 8];
+            let mut count = 0;
+
+            for (min, child) in node.octants() {
+                if self.clip(min, dimension).is_some() {
+                    octants[count] = Some((min, child));
+                    count += 1;
+                }
+            }
+
+            // Push in reverse, so that octants are yielded in order.
+            for (min, child) in octants[..count].iter().rev().flatten() {
+                self.stack.push(match child {
+                    Some(child) => Pending::Node(*child),
+                    None => Pending::Gap(*min, dimension),
+                });
+            }
+        }
+
+        None
+    }
+}
+
+impl<'a, T> Iterator for RegionIter<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    type Item = ([u32; 3], [u32; 3], Option<&'a T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_cube().map(|(min, dimension, data)| {
+            let (min, dimensions) = self.clip(min, dimension).unwrap();
+            (min, dimensions, data)
+        })
+    }
+}
+
+/// How the voxel centers of a cube relate to a shape.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Containment {
+    Outside,
+    Inside,
+    Straddling,
+}
+
+/// Classifies the voxel centers of the given cube against the sphere with the given center and radius.
+///
+/// A voxel center is inside the sphere when its distance from `center` is at most `radius`.
+pub(crate) fn classify_sphere(center: [f32; 3], radius: f32, min: Vector3<u32>, dimension: u32) -> Containment {
+    let min: [u32; 3] = min.into();
+
+    let mut near = 0.0;
+    let mut far = 0.0;
+
+    for (min, center) in min.iter().zip(center.iter()) {
+        let lower = *min as f32 + 0.5;
+        let upper = lower + (dimension - 1) as f32;
+
+        let near_axis = if *center < lower {
+            lower - center
+        } else if *center > upper {
+            center - upper
+        } else {
+            0.0
+        };
+        let far_axis = (center - lower).max(upper - center);
+
+        near += near_axis * near_axis;
+        far += far_axis * far_axis;
+    }
+
+    if near > radius * radius {
+        Containment::Outside
+    } else if far <= radius * radius {
+        Containment::Inside
+    } else {
+        Containment::Straddling
+    }
+}
+
+/// Classifies the given cube against the box from `box_min` (inclusive) to `box_max` (exclusive).
+pub(crate) fn classify_box(box_min: [u32; 3], box_max: [u32; 3], min: [u32; 3], dimension: u32) -> Containment {
+    let max = min.map(|c| c + dimension);
+
+    if (0..3).any(|axis| max[axis] <= box_min[axis] || min[axis] >= box_max[axis]) {
+        Containment::Outside
+    } else if (0..3).all(|axis| min[axis] >= box_min[axis] && max[axis] <= box_max[axis]) {
+        Containment::Inside
+    } else {
+        Containment::Straddling
+    }
+}
+
+/// Counts the voxels of the given cube whose centers lie within the sphere.
+fn count_in_sphere(center: [f32; 3], radius: f32, min: Vector3<u32>, dimension: u32) -> u64 {
+    match classify_sphere(center, radius, min, dimension) {
+        Containment::Outside => 0,
+        Containment::Inside => (dimension as u64).pow(3),
+        Containment::Straddling => {
+            let half = dimension / 2;
+
+            (0..8)
+                .map(|i| {
+                    let offset = Vector3::from([i & 1, (i >> 1) & 1, (i >> 2) & 1]);
+                    let min = min + offset.component_mul(&Vector3::from([half, half, half]));
+                    count_in_sphere(center, radius, min, half)
+                })
+                .sum()
+        }
+    }
+}
+
+enum SpherePending<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    Node(NodeRef<'a, T>),
+    Uniform(Vector3<u32>, u32, T),
+}
+
+/// An iterator over the non-empty voxels of an `Octree` whose centers lie within a sphere.
+///
+/// Yields cubes of voxels as `LeafInfo<T>`s: whole leaves where they lie entirely inside the sphere, and
+/// smaller cubes split from leaves straddling its surface. Created by [`Octree::query_sphere`].
+pub struct SphereIter<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    center: [f32; 3],
+    radius: f32,
+    background: T,
+    stack: Vec<SpherePending<'a, T>>,
+}
+
+impl<'a, T> SphereIter<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    pub(crate) fn new(root: NodeRef<'a, T>, center: [f32; 3], radius: f32, background: T) -> Self {
+        let mut iter = Self {
+            center,
+            radius,
+            background,
+            stack: Vec::new(),
+        };
+
+        if iter.classify(root.min_position(), root.dimension()) != Containment::Outside {
+            iter.stack.push(SpherePending::Node(root));
+        }
+
+        iter
+    }
+
+    fn classify(&self, min: Vector3<u32>, dimension: u32) -> Containment {
+        classify_sphere(self.center, self.radius, min, dimension)
+    }
+}
+
+impl<'a, T> Iterator for SphereIter<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    type Item = LeafInfo<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(pending) = self.stack.pop() {
+            let (min, dimension, data) = match pending {
+                SpherePending::Uniform(min, dimension, data) => (min, dimension, data),
+                SpherePending::Node(node) => match node.leaf_data() {
+                    Some(data) => (node.min_position(), node.dimension(), *data),
+                    None => {
+                        for child in node.children() {
+                            if self.classify(child.min_position(), child.dimension()) != Containment::Outside {
+                                self.stack.push(SpherePending::Node(child));
+                            }
+                        }
+
+                        continue;
+                    }
+                },
+            };
+
+            if data == self.background {
+                continue;
+            }
+
+            match self.classify(min, dimension) {
+                Containment::Outside => {}
+                Containment::Inside => {
+                    return Some(LeafInfo {
+                        min: min.into(),
+                        dimension,
+                        data,
+                    })
+                }
+                Containment::Straddling => {
+                    // Only the part of the leaf on the surface of the sphere needs splitting further.
+                    let half = dimension / 2;
+
+                    for i in 0..8 {
+                        let offset = Vector3::from([i & 1, (i >> 1) & 1, (i >> 2) & 1]);
+                        let min = min + offset.component_mul(&Vector3::from([half, half, half]));
+                        self.stack.push(SpherePending::Uniform(min, half, data));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Returns an iterator over the spans of the `Octree` intersecting the box from `min` (inclusive) to `max`
+    /// (exclusive).
+    ///
+    /// Each item is `(min, dimensions, data)`, describing a leaf clipped to the box, or `data` of `None` for a
+    /// region which has never been written. Together, the spans cover the part of the box inside the
+    /// `Octree` exactly once. Subtrees outside the box are never visited, and a large leaf is yielded as a
+    /// single clipped span.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// let spans = octree.query_region([4, 4, 4], [8, 40, 6]).collect::<Vec<_>>();
+    ///
+    /// assert_eq!(spans, vec![([4, 4, 4], [4, 28, 2], Some(&0))]);
+    /// ```
+    pub fn query_region(&self, min: impl Into<Vector3<u32>>, max: impl Into<Vector3<u32>>) -> RegionIter<'_, T> {
+        RegionIter::new(self.root(), <[u32; 3]>::from(min.into()), <[u32; 3]>::from(max.into()))
+    }
+
+    /// Returns an iterator over the non-empty spans of the `Octree` intersecting the box from `min`
+    /// (inclusive) to `max` (exclusive).
+    ///
+    /// Behaves like [`Octree::query_region`], but skips unwritten space and leaves holding the
+    /// background.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([5, 5, 5], 1).unwrap();
+    /// octree.insert([20, 20, 20], 2).unwrap();
+    ///
+    /// let spans = octree.query_region_values([0, 0, 0], [16, 16, 16]).collect::<Vec<_>>();
+    /// assert_eq!(spans, vec![([5, 5, 5], [1, 1, 1], &1)]);
+    /// ```
+    pub fn query_region_values(
+        &self,
+        min: impl Into<Vector3<u32>>,
+        max: impl Into<Vector3<u32>>,
+    ) -> impl Iterator<Item = ([u32; 3], [u32; 3], &T)> + '_ {
+        let background = self.background();
+        self.query_region(min, max)
+            .filter_map(move |(min, dimensions, data)| match data {
+                Some(data) if *data != background => Some((min, dimensions, data)),
+                _ => None,
+            })
+    }
+
+    /// Returns an iterator over the non-empty voxels whose centers lie within `radius` of `center`.
+    ///
+    /// Voxels are yielded in cubes: leaves lying entirely inside the sphere are yielded whole, and only
+    /// leaves straddling its surface are split further. Subtrees outside the sphere are never visited.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([4, 4, 4], 1).unwrap();
+    /// octree.insert([6, 4, 4], 2).unwrap();
+    ///
+    /// let voxels = octree.query_sphere([4.5, 4.5, 4.5], 1.5).collect::<Vec<_>>();
+    /// assert_eq!(voxels.len(), 1);
+    /// assert_eq!(voxels[0].min, [4, 4, 4]);
+    /// ```
+    pub fn query_sphere(&self, center: impl Into<Vector3<f32>>, radius: f32) -> SphereIter<'_, T> {
+        SphereIter::new(self.root(), <[f32; 3]>::from(center.into()), radius, self.background())
+    }
+
+    /// Returns the fraction of voxels whose centers lie within `radius` of `center` which are solid.
+    ///
+    /// Only voxels inside the `Octree` are counted, so a sphere extending outside it is clipped. Voxels are
+    /// counted in cubes, just as [`Octree::query_sphere`] yields them, so only the nodes on the surface of
+    /// the sphere are split into single voxels. Unwritten space is solid if the background is. Returns zero
+    /// if no voxel center lies within the sphere.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([4, 4, 4], 1).unwrap();
+    /// octree.insert([5, 4, 4], 1).unwrap();
+    ///
+    /// // The sphere holds the centers of [4, 4, 4] and its six neighbours.
+    /// let density = octree.density_in_sphere([4.5, 4.5, 4.5], 1.0, |data| *data != 0);
+    /// assert_eq!(density, 2.0 / 7.0);
+    /// ```
+    pub fn density_in_sphere(&self, center: impl Into<Vector3<f32>>, radius: f32, solid: impl Fn(&T) -> bool) -> f32 {
+        let center = <[f32; 3]>::from(center.into());
+        let total = count_in_sphere(center, radius, self.root().min_position(), self.dimension());
+        if total == 0 {
+            return 0.0;
+        }
+
+        let mut solid_count = 0;
+        let mut written_count = 0;
+
+        for cube in self.query_sphere(center, radius) {
+            let volume = (cube.dimension as u64).pow(3);
+            written_count += volume;
+
+            if solid(&cube.data) {
+                solid_count += volume;
+            }
+        }
+
+        // The sphere yields only non-empty voxels, so the rest hold the background.
+        if solid(&self.background()) {
+            solid_count += total - written_count;
+        }
+
+        (solid_count as f64 / total as f64) as f32
+    }
+
+    /// Clears every voxel whose center lies within `radius` of `center`.
+    ///
+    /// Uses the same traversal as [`Octree::query_sphere`], so cubes lying entirely inside the sphere are
+    /// cleared as a whole.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([4, 4, 4], 1).unwrap();
+    /// octree.insert([6, 4, 4], 2).unwrap();
+    ///
+    /// octree.clear_sphere([4.5, 4.5, 4.5], 1.5);
+    /// assert!(matches!(octree.get([4, 4, 4]), Some(0)));
+    /// assert!(matches!(octree.get([6, 4, 4]), Some(2)));
+    /// ```
+    pub fn clear_sphere(&mut self, center: impl Into<Vector3<f32>>, radius: f32) {
+        let center = <[f32; 3]>::from(center.into());
+        enter_span!(DEBUG, "clear_sphere", [written], ?center, radius);
+        let cubes = self.query_sphere(center, radius).collect::<Vec<_>>();
+        record!(written = cubes.len());
+
+        let (bounds, background) = (self.bounds(), self.background());
+        for cube in cubes {
+            let dimension = cube.dimension.max(self.min_dimension());
+            let (root, pool) = self.root_and_pool_mut();
+            root.clear(bounds, cube.min.into(), dimension, background, pool)
+                .unwrap();
+        }
+    }
+
+    /// Clears every voxel in the box from `min` (inclusive) to `max` (exclusive), leaving the background.
+    ///
+    /// Cubes lying entirely inside the box are cleared as single leaves, and the parts of the box outside the
+    /// `Octree` are ignored.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([4, 4, 4], 1).unwrap();
+    /// octree.insert([8, 4, 4], 2).unwrap();
+    ///
+    /// octree.clear_region([0, 0, 0], [8, 40, 8]);
+    /// assert!(matches!(octree.get([4, 4, 4]), Some(0)));
+    /// assert!(matches!(octree.get([8, 4, 4]), Some(2)));
+    /// ```
+    pub fn clear_region(&mut self, min: impl Into<Vector3<u32>>, max: impl Into<Vector3<u32>>) {
+        let min = <[u32; 3]>::from(min.into());
+        let max = <[u32; 3]>::from(max.into());
+        enter_span!(DEBUG, "clear_region", [cubes, written], ?min, ?max);
+        fill(self, None, |cube, dimension| classify_box(min, max, cube, dimension));
+    }
+
+    /// Writes `data` to every voxel whose center lies within `radius` of `center`.
+    ///
+    /// Cubes lying entirely inside the sphere are written as single leaves, so only voxels near its surface
+    /// are tested individually. If `clip` is `false`, returns an error without modifying the `Octree` when
+    /// the sphere extends outside it. Otherwise, the parts of the sphere outside the `Octree` are ignored.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert_sphere([8.0, 8.0, 8.0], 4.0, 1, false).unwrap();
+    ///
+    /// assert!(matches!(octree.get([8, 8, 8]), Some(1)));
+    /// assert!(!matches!(octree.get([8, 8, 12]), Some(1)));
+    ///
+    /// assert_eq!(octree.insert_sphere([0.0, 8.0, 8.0], 4.0, 1, false), Err(Error::OutOfBounds));
+    /// assert!(octree.insert_sphere([0.0, 8.0, 8.0], 4.0, 1, true).is_ok());
+    /// ```
+    pub fn insert_sphere(
+        &mut self,
+        center: impl Into<Vector3<f32>>,
+        radius: f32,
+        data: T,
+        clip: bool,
+    ) -> Result<(), Error> {
+        let center = <[f32; 3]>::from(center.into());
+        enter_span!(DEBUG, "insert_sphere", [cubes, written], ?center, radius);
+        if !clip && !self.contains_sphere(center, radius) {
+            return Err(Error::OutOfBounds);
+        }
+
+        fill(self, Some(data), |min, dimension| {
+            classify_sphere(center, radius, min.into(), dimension)
+        });
+
+        Ok(())
+    }
+
+    /// Writes `data` to every voxel whose center lies within `outer_radius` of `center`, but not within
+    /// `inner_radius` of it.
+    ///
+    /// Behaves as [`Octree::insert_sphere`] with the outer radius, leaving the voxels inside the inner sphere
+    /// untouched.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert_shell([8.0, 8.0, 8.0], 2.0, 4.0, 1, false).unwrap();
+    ///
+    /// assert!(!matches!(octree.get([8, 8, 8]), Some(1)));
+    /// assert!(matches!(octree.get([8, 8, 10]), Some(1)));
+    /// assert!(!matches!(octree.get([8, 8, 12]), Some(1)));
+    /// ```
+    pub fn insert_shell(
+        &mut self,
+        center: impl Into<Vector3<f32>>,
+        inner_radius: f32,
+        outer_radius: f32,
+        data: T,
+        clip: bool,
+    ) -> Result<(), Error> {
+        let center = <[f32; 3]>::from(center.into());
+        enter_span!(
+            DEBUG,
+            "insert_shell",
+            [cubes, written],
+            ?center,
+            inner_radius,
+            outer_radius
+        );
+        if !clip && !self.contains_sphere(center, outer_radius) {
+            return Err(Error::OutOfBounds);
+        }
+
+        fill(self, Some(data), |min, dimension| {
+            match (
+                classify_sphere(center, inner_radius, min.into(), dimension),
+                classify_sphere(center, outer_radius, min.into(), dimension),
+            ) {
+                (Containment::Inside, _) | (_, Containment::Outside) => Containment::Outside,
+                (Containment::Outside, Containment::Inside) => Containment::Inside,
+                _ => Containment::Straddling,
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Returns whether the sphere with the given center and radius lies entirely inside the `Octree`.
+    fn contains_sphere(&self, center: [f32; 3], radius: f32) -> bool {
+        let dimension = self.dimension() as f32;
+        center.iter().all(|c| *c - radius >= 0.0 && *c + radius <= dimension)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_utils::XorShift, Error, Octree};
+
+    use alloc::vec::Vec;
+    use core::num::NonZeroU32;
+
+    #[test]
+    fn large_leaf_yields_single_clipped_span() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        for i in 0..8 {
+            octree
+                .insert([8 + (i & 1), 8 + ((i >> 1) & 1), 8 + ((i >> 2) & 1)], 3)
+                .unwrap();
+        }
+
+        let spans = octree.query_region_values([9, 0, 0], [16, 16, 9]).collect::<Vec<_>>();
+        assert_eq!(spans, vec![([9, 8, 8], [1, 2, 1], &3)]);
+    }
+
+    #[test]
+    fn box_outside_octree() {
+        let octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+
+        assert!(octree.query_region([16, 0, 0], [20, 4, 4]).next().is_none());
+        assert!(octree.query_region([4, 4, 4], [4, 8, 8]).next().is_none());
+    }
+
+    fn sphere_brute_force(octree: &Octree<u8>, center: [f32; 3], radius: f32) -> Vec<[u32; 3]> {
+        let mut voxels = Vec::new();
+        let dimension = octree.dimension();
+
+        for x in 0..dimension {
+            for y in 0..dimension {
+                for z in 0..dimension {
+                    let d = [x, y, z].map(|c| c as f32 + 0.5);
+                    let d = [0, 1, 2].map(|i| d[i] - center[i]);
+
+                    if d[0] * d[0] + d[1] * d[1] + d[2] * d[2] <= radius * radius
+                        && matches!(octree.get([x, y, z]), Some(data) if *data != 0)
+                    {
+                        voxels.push([x, y, z]);
+                    }
+                }
+            }
+        }
+
+        voxels
+    }
+
+    fn sphere_voxels(octree: &Octree<u8>, center: [f32; 3], radius: f32) -> Vec<[u32; 3]> {
+        let mut voxels = Vec::new();
+
+        for cube in octree.query_sphere(center, radius) {
+            assert_eq!(octree.get(cube.min), Some(&cube.data));
+
+            for x in 0..cube.dimension {
+                for y in 0..cube.dimension {
+                    for z in 0..cube.dimension {
+                        voxels.push([cube.min[0] + x, cube.min[1] + y, cube.min[2] + z]);
+                    }
+                }
+            }
+        }
+
+        voxels.sort_unstable();
+        voxels
+    }
+
+    #[test]
+    fn sphere_smaller_than_voxel() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        octree.insert([3, 3, 3], 1).unwrap();
+
+        assert_eq!(sphere_voxels(&octree, [3.5, 3.5, 3.5], 0.0), vec![[3, 3, 3]]);
+        assert_eq!(sphere_voxels(&octree, [3.2, 3.5, 3.6], 0.4), vec![[3, 3, 3]]);
+        assert!(sphere_voxels(&octree, [3.1, 3.1, 3.1], 0.4).is_empty());
+    }
+
+    #[test]
+    fn sphere_yields_whole_leaves_inside() {
+        let octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        assert!(octree.query_sphere([8.0, 8.0, 8.0], 100.0).next().is_none());
+
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        for i in 0..8 {
+            octree.insert([i & 1, (i >> 1) & 1, (i >> 2) & 1], 5).unwrap();
+        }
+
+        let cubes = octree.query_sphere([1.0, 1.0, 1.0], 1.0).collect::<Vec<_>>();
+        assert_eq!(cubes.len(), 1);
+        assert_eq!((cubes[0].min, cubes[0].dimension), ([0, 0, 0], 2));
+    }
+
+    #[test]
+    fn query_sphere_matches_brute_force() {
+        let mut rng = XorShift::new(0x5a11);
+
+        for _ in 0..10 {
+            let octree = rng.octree(16, 300, 3);
+
+            for _ in 0..20 {
+                let center = [rng.f32(-2.0, 18.0), rng.f32(-2.0, 18.0), rng.f32(-2.0, 18.0)];
+                let radius = rng.f32(0.0, 8.0);
+
+                assert_eq!(
+                    sphere_voxels(&octree, center, radius),
+                    sphere_brute_force(&octree, center, radius)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn clear_sphere_matches_brute_force() {
+        let mut rng = XorShift::new(0xc1ea);
+
+        for _ in 0..10 {
+            let mut octree = rng.octree(16, 300, 3);
+            let center = [rng.f32(0.0, 16.0), rng.f32(0.0, 16.0), rng.f32(0.0, 16.0)];
+            let radius = rng.f32(0.0, 8.0);
+
+            let mut expected = Vec::new();
+            for x in 0..16 {
+                for y in 0..16 {
+                    for z in 0..16 {
+                        expected.push(octree.get([x, y, z]).copied());
+                    }
+                }
+            }
+
+            for voxel in sphere_brute_force(&octree, center, radius) {
+                let i = (voxel[0] * 256 + voxel[1] * 16 + voxel[2]) as usize;
+                expected[i] = Some(0);
+            }
+
+            octree.clear_sphere(center, radius);
+
+            let mut i = 0;
+            for x in 0..16 {
+                for y in 0..16 {
+                    for z in 0..16 {
+                        assert_eq!(octree.get([x, y, z]).copied(), expected[i]);
+                        i += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn query_region_matches_dense_reference() {
+        let mut rng = XorShift::new(0xb0c5);
+
+        for _ in 0..20 {
+            let octree = rng.octree(16, 200, 3);
+
+            for _ in 0..20 {
+                let a = [rng.below(20), rng.below(20), rng.below(20)];
+                let b = [rng.below(20), rng.below(20), rng.below(20)];
+                let min = [0, 1, 2].map(|i| a[i].min(b[i]));
+                let max = [0, 1, 2].map(|i| a[i].max(b[i]));
+
+                let mut covered = [[[0; 16]; 16]; 16];
+                for (min, dimensions, data) in octree.query_region(min, max) {
+                    for x in min[0]..min[0] + dimensions[0] {
+                        for y in min[1]..min[1] + dimensions[1] {
+                            for z in min[2]..min[2] + dimensions[2] {
+                                assert_eq!(octree.root().get([x, y, z].into()), data);
+                                covered[x as usize][y as usize][z as usize] += 1;
+                            }
+                        }
+                    }
+                }
+
+                let mut volume = 0;
+                let mut occupied = 0;
+                for x in 0..16 {
+                    for y in 0..16 {
+                        for z in 0..16 {
+                            let inside = (0..3).all(|i| [x, y, z][i] >= min[i] && [x, y, z][i] < max[i]);
+                            assert_eq!(covered[x as usize][y as usize][z as usize], inside as u32);
+
+                            if inside {
+                                volume += 1;
+                                occupied += matches!(octree.get([x, y, z]), Some(data) if *data != 0) as u32;
+                            }
+                        }
+                    }
+                }
+
+                let spans = octree.query_region(min, max).collect::<Vec<_>>();
+                let total = spans.iter().map(|(_, d, _)| d[0] * d[1] * d[2]).sum::<u32>();
+                assert_eq!(total, volume);
+
+                let values = octree.query_region_values(min, max).collect::<Vec<_>>();
+                let total = values.iter().map(|(_, d, _)| d[0] * d[1] * d[2]).sum::<u32>();
+                assert_eq!(total, occupied);
+            }
+        }
+    }
+
+    /// Returns every voxel of the `Octree` holding `1`.
+    fn written(octree: &Octree<u8>) -> Vec<[u32; 3]> {
+        let dimension = octree.dimension();
+        let mut voxels = Vec::new();
+
+        for x in 0..dimension {
+            for y in 0..dimension {
+                for z in 0..dimension {
+                    if octree.get([x, y, z]) == Some(&1) {
+                        voxels.push([x, y, z]);
+                    }
+                }
+            }
+        }
+
+        voxels
+    }
+
+    fn distance_squared(voxel: [u32; 3], center: [f32; 3]) -> f32 {
+        (0..3).map(|i| voxel[i] as f32 + 0.5 - center[i]).map(|d| d * d).sum()
+    }
+
+    #[test]
+    fn insert_sphere_matches_brute_force() {
+        let mut rng = XorShift::new(0x1b5);
+
+        for _ in 0..20 {
+            let center = [0, 1, 2].map(|_| rng.f32(-2.0, 18.0));
+            let (inner, outer) = (rng.f32(0.0, 4.0), rng.f32(0.0, 8.0));
+
+            let mut sphere = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+            sphere.insert_sphere(center, outer, 1, true).unwrap();
+
+            let mut shell = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+            shell.insert_shell(center, inner, outer, 1, true).unwrap();
+
+            let mut expected_sphere = Vec::new();
+            let mut expected_shell = Vec::new();
+            for x in 0..16 {
+                for y in 0..16 {
+                    for z in 0..16 {
+                        let d = distance_squared([x, y, z], center);
+                        if d <= outer * outer {
+                            expected_sphere.push([x, y, z]);
+
+                            if d > inner * inner {
+                                expected_shell.push([x, y, z]);
+                            }
+                        }
+                    }
+                }
+            }
+
+            assert_eq!(written(&sphere), expected_sphere);
+            assert_eq!(written(&shell), expected_shell);
+        }
+    }
+
+    #[test]
+    fn sphere_covering_octree_is_single_leaf() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        octree.insert_sphere([8.0, 8.0, 8.0], 14.0, 1, true).unwrap();
+
+        assert_eq!(
+            octree.query_region([0; 3], [16; 3]).collect::<Vec<_>>(),
+            vec![([0; 3], [16; 3], Some(&1))]
+        );
+    }
+
+    #[test]
+    fn insert_sphere_out_of_bounds() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+
+        assert_eq!(
+            octree.insert_sphere([14.0, 8.0, 8.0], 4.0, 1, false),
+            Err(Error::OutOfBounds)
+        );
+        assert_eq!(
+            octree.insert_shell([8.0, 8.0, 8.0], 2.0, 9.0, 1, false),
+            Err(Error::OutOfBounds)
+        );
+        assert!(written(&octree).is_empty());
+
+        octree.insert_sphere([14.0, 8.0, 8.0], 4.0, 1, true).unwrap();
+        assert!(matches!(octree.get([15, 8, 8]), Some(1)));
+    }
+
+    #[test]
+    fn density_matches_brute_force() {
+        let mut rng = XorShift::new(0xde5);
+
+        for _ in 0..10 {
+            let octree = rng.octree(16, 400, 3);
+
+            for _ in 0..20 {
+                let center = [0, 1, 2].map(|_| rng.f32(-2.0, 18.0));
+                let radius = rng.f32(0.0, 10.0);
+
+                let mut total = 0;
+                let mut solid = 0;
+                for x in 0..16 {
+                    for y in 0..16 {
+                        for z in 0..16 {
+                            if distance_squared([x, y, z], center) <= radius * radius {
+                                total += 1;
+                                if matches!(octree.get([x, y, z]), Some(1) | Some(2)) {
+                                    solid += 1;
+                                }
+                            }
+                        }
+                    }
+                }
+
+                let expected = if total == 0 { 0.0 } else { solid as f32 / total as f32 };
+                let density = octree.density_in_sphere(center, radius, |data| *data == 1 || *data == 2);
+                assert!((density - expected).abs() < 1e-6);
+
+                // Counting unwritten space as solid gives the complement of the remaining values.
+                let inverse = octree.density_in_sphere(center, radius, |data| *data != 1 && *data != 2);
+                assert!(total == 0 || (density + inverse - 1.0).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn density_trivial_cases() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        assert_eq!(octree.density_in_sphere([8.0, 8.0, 8.0], 5.0, |data| *data != 0), 0.0);
+
+        octree.insert_sphere([8.0, 8.0, 8.0], 100.0, 1, true).unwrap();
+        assert_eq!(octree.density_in_sphere([8.0, 8.0, 8.0], 5.0, |data| *data != 0), 1.0);
+        assert_eq!(octree.density_in_sphere([0.0, 0.0, 0.0], 30.0, |data| *data != 0), 1.0);
+        assert_eq!(octree.density_in_sphere([-9.0, 0.0, 0.0], 5.0, |data| *data != 0), 0.0);
+    }
+}
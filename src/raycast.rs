@@ -0,0 +1,319 @@
+use crate::{LeafInfo, NodeRef, Octree, Vector3};
+
+use alloc::vec::Vec;
+use core::{cmp::Ordering, fmt::Debug, hash::Hash};
+
+/// A lazy, front-to-back iterator over the non-empty leaves intersected by a ray.
+///
+/// Yields `(t_enter, t_exit, leaf)`, where `t_enter` and `t_exit` are the ray parameters at which the ray
+/// enters and exits the leaf. Created by [`Octree::raycast_iter`].
+pub struct RaycastIter<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    origin: [f32; 3],
+    direction: [f32; 3],
+    background: T,
+    stack: Vec<(NodeRef<'a, T>, f32, f32)>,
+}
+
+impl<'a, T> RaycastIter<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    pub(crate) fn new(root: NodeRef<'a, T>, origin: [f32; 3], direction: [f32; 3], background: T) -> Self {
+        let mut iter = Self {
+            origin,
+            direction,
+            background,
+            stack: Vec::new(),
+        };
+
+        let valid = origin.iter().chain(direction.iter()).all(|c| c.is_finite()) && direction.iter().any(|c| *c != 0.0);
+
+        if valid {
+            if let Some((t_enter, t_exit)) = iter.intersect(root.min_position(), root.dimension()) {
+                iter.stack.push((root, t_enter, t_exit));
+            }
+        }
+
+        iter
+    }
+
+    /// Returns the ray parameters at which the ray enters and exits the given cube, if they intersect.
+    ///
+    /// Only the part of the ray with `t >= 0` is considered, and intersections of zero length (grazing an
+    /// edge or corner) are ignored. Along axes the ray is parallel to, the cube is treated as half-open.
+    fn intersect(&self, min: Vector3<u32>, dimension: u32) -> Option<(f32, f32)> {
+        let min: [u32; 3] = min.into();
+
+        let mut t_enter = 0.0_f32;
+        let mut t_exit = f32::INFINITY;
+
+        for ((min, origin), direction) in min.iter().zip(self.origin.iter()).zip(self.direction.iter()) {
+            let lower = *min as f32;
+            let upper = lower + dimension as f32;
+            let (origin, direction) = (*origin, *direction);
+
+            if direction == 0.0 {
+                if origin < lower || origin >= upper {
+                    return None;
+                }
+            } else {
+                let t0 = (lower - origin) / direction;
+                let t1 = (upper - origin) / direction;
+
+                t_enter = t_enter.max(t0.min(t1));
+                t_exit = t_exit.min(t0.max(t1));
+            }
+        }
+
+        if t_enter < t_exit {
+            Some((t_enter, t_exit))
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, T> Iterator for RaycastIter<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    type Item = (f32, f32, LeafInfo<T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((node, t_enter, t_exit)) = self.stack.pop() {
+            if let Some(leaf) = LeafInfo::from_node(node) {
+                if leaf.data != self.background {
+                    return Some((t_enter, t_exit, leaf));
+                }
+
+                continue;
+            }
+
+            let mut hits = [None; 8];
+            let mut count = 0;
+
+            for child in node.children() {
+                if let Some((t_enter, t_exit)) = self.intersect(child.min_position(), child.dimension()) {
+                    hits[count] = Some((child, t_enter, t_exit));
+                    count += 1;
+                }
+            }
+
+            // Children never overlap, so ordering them by entry point gives front-to-back order.
+            // Push the furthest first, so that the nearest is popped next.
+            let hits = &mut hits[..count];
+            hits.sort_unstable_by(|a, b| match (a, b) {
+                (Some(a), Some(b)) => b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal),
+                _ => Ordering::Equal,
+            });
+
+            self.stack.extend(hits.iter().flatten());
+        }
+
+        None
+    }
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Casts a ray through the `Octree`, returning the first non-empty leaf it hits.
+    ///
+    /// Returns the ray parameter at which the leaf is entered, along with the leaf itself. This is always
+    /// the first item yielded by [`Octree::raycast_iter`].
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([10, 0, 0], 1).unwrap();
+    ///
+    /// let (t, leaf) = octree.raycast([0.5, 0.5, 0.5], [1.0, 0.0, 0.0]).unwrap();
+    /// assert_eq!(t, 9.5);
+    /// assert_eq!(leaf.min, [10, 0, 0]);
+    /// assert_eq!(leaf.data, 1);
+    /// ```
+    pub fn raycast(
+        &self,
+        origin: impl Into<Vector3<f32>>,
+        direction: impl Into<Vector3<f32>>,
+    ) -> Option<(f32, LeafInfo<T>)> {
+        self.raycast_iter(origin, direction)
+            .next()
+            .map(|(t_enter, _, leaf)| (t_enter, leaf))
+    }
+
+    /// Returns a lazy iterator over every non-empty leaf the ray passes through, in front-to-back order.
+    ///
+    /// Each item is `(t_enter, t_exit, leaf)`, where the ray is inside the leaf for parameters between
+    /// `t_enter` and `t_exit`. Parameters are in units of `direction`, which does not need to be
+    /// normalized, and only the part of the ray with `t >= 0` is considered. A ray with a zero or
+    /// non-finite direction yields nothing.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([4, 0, 0], 1).unwrap();
+    /// octree.insert([8, 0, 0], 2).unwrap();
+    ///
+    /// let hits = octree
+    ///     .raycast_iter([0.5, 0.5, 0.5], [1.0, 0.0, 0.0])
+    ///     .map(|(t_enter, t_exit, leaf)| (t_enter, t_exit, leaf.data))
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(hits, vec![(3.5, 4.5, 1), (7.5, 8.5, 2)]);
+    /// ```
+    pub fn raycast_iter(
+        &self,
+        origin: impl Into<Vector3<f32>>,
+        direction: impl Into<Vector3<f32>>,
+    ) -> RaycastIter<'_, T> {
+        RaycastIter::new(
+            self.root(),
+            <[f32; 3]>::from(origin.into()),
+            <[f32; 3]>::from(direction.into()),
+            self.background(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_utils::XorShift, Octree};
+
+    use alloc::vec::Vec;
+    use core::num::NonZeroU32;
+
+    #[test]
+    fn ray_missing_octree() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        octree.insert([0, 0, 0], 1).unwrap();
+
+        assert!(octree.raycast_iter([-1.0, 0.5, 0.5], [-1.0, 0.0, 0.0]).next().is_none());
+        assert!(octree.raycast_iter([9.0, 0.5, 0.5], [1.0, 0.0, 0.0]).next().is_none());
+        assert!(octree.raycast_iter([0.5, 0.5, 0.5], [0.0, 0.0, 0.0]).next().is_none());
+    }
+
+    #[test]
+    fn ray_parallel_to_face() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        octree.insert([3, 2, 5], 1).unwrap();
+        octree.insert([3, 1, 5], 2).unwrap();
+
+        // Travelling along the boundary between y = 1 and y = 2 belongs to the upper voxel.
+        let hits = octree
+            .raycast_iter([-2.0, 2.0, 5.5], [1.0, 0.0, 0.0])
+            .map(|(_, _, leaf)| leaf.data)
+            .collect::<Vec<_>>();
+
+        assert_eq!(hits, vec![1]);
+
+        // Travelling along the outer face of the octree misses it entirely.
+        assert!(octree.raycast_iter([0.5, 8.0, 0.5], [0.0, 0.0, 1.0]).next().is_none());
+    }
+
+    #[test]
+    fn ray_from_inside() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        octree.insert([2, 2, 2], 1).unwrap();
+        octree.insert([5, 2, 2], 2).unwrap();
+
+        let hits = octree
+            .raycast_iter([2.5, 2.5, 2.5], [1.0, 0.0, 0.0])
+            .map(|(t_enter, t_exit, leaf)| (t_enter, t_exit, leaf.data))
+            .collect::<Vec<_>>();
+
+        assert_eq!(hits, vec![(0.0, 0.5, 1), (2.5, 3.5, 2)]);
+    }
+
+    /// Checks the leaves yielded against the voxels found by stepping densely along the ray: every non-empty
+    /// voxel stepped through must lie in a leaf yielded, in order, and every leaf yielded must either hold
+    /// voxels stepped through or be crossed too briefly for a step to land in it. The first leaf yielded, which
+    /// [`Octree::raycast`] returns, is therefore the first holding a voxel stepped through, unless the steps
+    /// missed it.
+    #[test]
+    fn raycast_iter_matches_dense_sampling() {
+        const STEP: f32 = 0.02;
+        const STEPS: u32 = 2000;
+        const EPSILON: f32 = 1e-3;
+
+        let mut rng = XorShift::new(0x5eed);
+
+        for _ in 0..20 {
+            let octree = rng.octree(16, 200, 3);
+
+            for _ in 0..20 {
+                let origin = [rng.f32(-4.0, 20.0), rng.f32(-4.0, 20.0), rng.f32(-4.0, 20.0)];
+                let direction = [rng.f32(-1.0, 1.0), rng.f32(-1.0, 1.0), rng.f32(-1.0, 1.0)];
+                let hits = octree.raycast_iter(origin, direction).collect::<Vec<_>>();
+
+                for pair in hits.windows(2) {
+                    assert!(pair[0].1 <= pair[1].0 + 1e-4);
+                }
+
+                for (t_enter, t_exit, leaf) in hits.iter() {
+                    let t = (t_enter + t_exit) / 2.0;
+                    let position = [0, 1, 2].map(|i| (origin[i] + direction[i] * t).floor() as u32);
+
+                    assert_eq!(octree.get(position), Some(&leaf.data));
+                    assert_ne!(leaf.data, 0);
+                }
+
+                // The leaves yielded before the one holding each voxel stepped through were skipped by the steps.
+                let mut matched = None;
+                for step in 0..STEPS {
+                    let t = step as f32 * STEP;
+                    let point = [0, 1, 2].map(|i| origin[i] + direction[i] * t);
+
+                    if point.iter().any(|c| *c < 0.0 || *c >= 16.0) {
+                        continue;
+                    }
+
+                    let position = point.map(|c| c as u32);
+                    if matches!(octree.get(position), Some(data) if *data == 0) {
+                        continue;
+                    }
+
+                    let index = (matched.unwrap_or(0)..hits.len())
+                        .find(|&index| {
+                            let (t_enter, t_exit, leaf) = &hits[index];
+                            *t_enter - EPSILON <= t && t <= *t_exit + EPSILON && leaf.contains(position)
+                        })
+                        .unwrap_or_else(|| panic!("{:?} at t = {} not yielded in order", position, t));
+
+                    for (t_enter, t_exit, _) in &hits[matched.map_or(0, |i| i + 1).min(index)..index] {
+                        assert!(
+                            t_exit - t_enter <= STEP + EPSILON,
+                            "leaf from t = {} missed by the steps",
+                            t_enter
+                        );
+                    }
+                    matched = Some(index);
+                }
+
+                // So were those yielded after the last voxel stepped through, within the distance stepped.
+                let stepped = (STEPS - 1) as f32 * STEP;
+                for (t_enter, t_exit, _) in hits
+                    .iter()
+                    .skip(matched.map_or(0, |i| i + 1))
+                    .filter(|(_, t_exit, _)| *t_exit < stepped)
+                {
+                    assert!(
+                        t_exit - t_enter <= STEP + EPSILON,
+                        "leaf from t = {} missed by the steps",
+                        t_enter
+                    );
+                }
+            }
+        }
+    }
+}
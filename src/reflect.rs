@@ -0,0 +1,156 @@
+use crate::{LeafInfo, Octree};
+
+use alloc::{vec, vec::Vec};
+use bevy_reflect::Reflect;
+// The derive of `Reflect` collects with `FromIterator`, which is not in the prelude before edition 2021.
+use core::{fmt::Debug, hash::Hash, iter::FromIterator};
+
+/// What editor tooling shows of an `Octree`, inspectable through reflection, as returned by
+/// [`Octree::reflect_summary`].
+///
+/// The `Octree` itself reflects as an opaque value, which reflection clones and compares as a whole, as the
+/// `Node`s within it are not meant to be edited one by one.
+#[derive(Debug, Clone, PartialEq, Reflect)]
+#[reflect(Clone, PartialEq)]
+pub struct OctreeSummary<T>
+where
+    T: Reflect + Clone + PartialEq,
+{
+    /// The dimension of the root node.
+    pub dimension: u32,
+    /// The current LOD level, as by [`Octree::lod_level`].
+    pub lod_level: u32,
+    /// The coarsest LOD level, as by [`Octree::max_lod_level`].
+    pub max_lod_level: u32,
+    /// The data held by voxels never written.
+    pub background: T,
+    /// The number of `Node`s held in memory, as by [`Octree::node_count`].
+    pub node_count: usize,
+    /// The number of leaves among them.
+    pub leaf_count: usize,
+    /// Every leaf holding data other than the background, in octant order, if they were asked for and there were
+    /// few enough of them.
+    pub leaves: Option<Vec<LeafInfo<T>>>,
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + Reflect,
+{
+    /// Returns a summary of the `Octree` for editor tooling to inspect through reflection, listing its leaves
+    /// holding data other than the background if there are no more than `max_leaves` of them.
+    ///
+    /// Listing leaves walks the whole tree, so `max_leaves` should be left at 0 for all but small trees.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, LeafInfo, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(4).unwrap()).unwrap();
+    /// octree.insert([3, 0, 0], 1).unwrap();
+    ///
+    /// let summary = octree.reflect_summary(0);
+    /// assert_eq!((summary.dimension, summary.node_count, summary.leaf_count), (4, 3, 1));
+    /// assert_eq!(summary.leaves, None);
+    ///
+    /// let leaf = LeafInfo { min: [3, 0, 0], dimension: 1, data: 1 };
+    /// assert_eq!(octree.reflect_summary(8).leaves, Some(vec![leaf]));
+    /// ```
+    pub fn reflect_summary(&self, max_leaves: usize) -> OctreeSummary<T> {
+        let mut leaf_count = 0;
+        let mut leaves = Some(Vec::new()).filter(|_| max_leaves > 0);
+        let mut stack = vec![self.root()];
+
+        while let Some(node) = stack.pop() {
+            match node.leaf_data() {
+                Some(data) => {
+                    leaf_count += 1;
+                    if *data == self.background() {
+                        continue;
+                    }
+
+                    if let Some(list) = &mut leaves {
+                        list.push(LeafInfo {
+                            min: node.min_position().into(),
+                            dimension: node.dimension(),
+                            data: *data,
+                        });
+                    }
+                    leaves = leaves.filter(|list| list.len() <= max_leaves);
+                }
+                // Push in reverse, so that leaves are listed in octant order.
+                None => stack.extend(node.children().collect::<Vec<_>>().into_iter().rev()),
+            }
+        }
+
+        OctreeSummary {
+            dimension: self.dimension(),
+            lod_level: self.lod_level(),
+            max_lod_level: self.max_lod_level(),
+            background: self.background(),
+            node_count: self.node_count(),
+            leaf_count,
+            leaves,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OctreeSummary;
+    use crate::{test_utils::XorShift, LeafInfo, Octree};
+
+    use alloc::vec::Vec;
+    use bevy_reflect::{FromReflect, PartialReflect, Reflect, ReflectRef, TypeRegistry};
+
+    #[test]
+    fn octrees_round_trip_through_reflection() {
+        let mut registry = TypeRegistry::default();
+        registry.register::<Octree<u8>>();
+        registry.register::<OctreeSummary<u8>>();
+        assert!(registry.get(core::any::TypeId::of::<Octree<u8>>()).is_some());
+
+        let octree = XorShift::new(0x4ef1).octree(16, 200, 3);
+        let reflected: &dyn Reflect = &octree;
+        assert!(matches!(reflected.reflect_ref(), ReflectRef::Opaque(_)));
+
+        let cloned = reflected.reflect_clone().unwrap().take::<Octree<u8>>().unwrap();
+        assert_eq!(cloned.to_bytes(), octree.to_bytes());
+
+        let from_reflect = Octree::<u8>::from_reflect(reflected.as_partial_reflect()).unwrap();
+        assert_eq!(from_reflect.to_bytes(), octree.to_bytes());
+        assert_eq!(from_reflect.get([1, 2, 3]), octree.get([1, 2, 3]));
+    }
+
+    #[test]
+    fn summaries_expose_fields() {
+        let octree = XorShift::new(0x4ef2).octree(8, 20, 3);
+        let summary = octree.reflect_summary(usize::MAX);
+
+        let fields = match summary.reflect_ref() {
+            ReflectRef::Struct(fields) => fields,
+            _ => panic!("summaries reflect as structs"),
+        };
+        assert_eq!(
+            fields.field("dimension").and_then(|f| f.try_downcast_ref::<u32>()),
+            Some(&8)
+        );
+        assert_eq!(
+            fields.field("node_count").and_then(|f| f.try_downcast_ref::<usize>()),
+            Some(&octree.node_count())
+        );
+
+        let leaves = summary.leaves.clone().unwrap();
+        let expected = octree
+            .iter_leaves()
+            .filter(|leaf| leaf.data != octree.background())
+            .collect::<Vec<LeafInfo<u8>>>();
+        assert_eq!(leaves, expected);
+        assert_eq!(octree.reflect_summary(leaves.len() - 1).leaves, None);
+
+        let cloned = summary.reflect_clone().unwrap().take::<OctreeSummary<u8>>().unwrap();
+        assert_eq!(cloned, summary);
+        assert!(summary.reflect_partial_eq(cloned.as_partial_reflect()).unwrap());
+    }
+}
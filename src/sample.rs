@@ -0,0 +1,220 @@
+use crate::{float, NodeRef, Octree, Vector3};
+
+use core::{fmt::Debug, hash::Hash};
+
+/// How samples falling outside an `Octree` are treated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Boundary {
+    /// Samples outside the `Octree` take the value of the nearest voxel inside it.
+    Clamp,
+    /// Samples outside the `Octree` are zero.
+    Zero,
+}
+
+/// Descends from `node` to the leaf covering `position`, returning its data, or `None` for unwritten space.
+fn data_at<T>(node: NodeRef<'_, T>, position: Vector3<u32>) -> Option<&T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash,
+{
+    let mut node = node;
+
+    loop {
+        if let Some(data) = node.leaf_data() {
+            return Some(data);
+        }
+
+        node = node.octant_at(position)?.1?;
+    }
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Samples the `Octree` at an arbitrary point, interpolating trilinearly between voxel centers.
+    ///
+    /// Each voxel is converted through `to_f` and placed at its center, so sampling exactly at the center
+    /// of a voxel returns its own value. Unwritten space is converted as the background, and `boundary`
+    /// decides the value of samples outside the `Octree`. The eight voxels surrounding the point share a
+    /// single descent down to the smallest node containing all of them.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Boundary, Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u16>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([4, 4, 4], 100).unwrap();
+    ///
+    /// let to_f = |data: &u16| *data as f32;
+    /// assert_eq!(octree.sample_trilinear([4.5, 4.5, 4.5], Boundary::Zero, to_f), 100.0);
+    /// assert_eq!(octree.sample_trilinear([5.0, 4.5, 4.5], Boundary::Zero, to_f), 50.0);
+    /// assert_eq!(octree.sample_trilinear([5.0, 5.0, 5.0], Boundary::Zero, to_f), 12.5);
+    /// ```
+    pub fn sample_trilinear(
+        &self,
+        point: impl Into<Vector3<f32>>,
+        boundary: Boundary,
+        to_f: impl Fn(&T) -> f32,
+    ) -> f32 {
+        let point = <[f32; 3]>::from(point.into());
+        let last = (self.dimension() - 1) as i64;
+
+        let shifted = point.map(|c| c - 0.5);
+        let base = shifted.map(|c| float::floor(c) as i64);
+        let fraction = [0, 1, 2].map(|i| shifted[i] - base[i] as f32);
+
+        // Find the smallest node containing every sample inside the `Octree`.
+        let lower = base.map(|c| c.max(0).min(last) as u32);
+        let upper = base.map(|c| (c + 1).max(0).min(last) as u32);
+
+        let mut node = self.root();
+        while let (Some((_, Some(a))), Some((_, Some(b)))) =
+            (node.octant_at(lower.into()), node.octant_at(upper.into()))
+        {
+            // Both are children of `node`, so they are the same child if they start at the same corner.
+            if <[u32; 3]>::from(a.min_position()) != <[u32; 3]>::from(b.min_position()) {
+                break;
+            }
+
+            node = a;
+        }
+
+        let mut value = 0.0;
+
+        for i in 0..8 {
+            let offset = [i & 1, (i >> 1) & 1, (i >> 2) & 1];
+            let position = [0, 1, 2].map(|axis| base[axis] + offset[axis] as i64);
+
+            let weight = (0..3)
+                .map(|axis| {
+                    if offset[axis] == 1 {
+                        fraction[axis]
+                    } else {
+                        1.0 - fraction[axis]
+                    }
+                })
+                .product::<f32>();
+
+            if weight == 0.0 {
+                continue;
+            }
+
+            let inside = position.iter().all(|c| (0..=last).contains(c));
+            if !inside && boundary == Boundary::Zero {
+                continue;
+            }
+
+            let position = position.map(|c| c.max(0).min(last) as u32);
+            let sample = match data_at(node, position.into()) {
+                Some(data) => to_f(data),
+                None => to_f(&self.background()),
+            };
+
+            value += weight * sample;
+        }
+
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Boundary;
+    use crate::{test_utils::XorShift, Octree};
+
+    use core::num::NonZeroU32;
+
+    /// Samples the `Octree` with a separate `get` for each of the eight surrounding voxels.
+    fn reference(octree: &Octree<u8>, point: [f32; 3], boundary: Boundary) -> f32 {
+        let last = (octree.dimension() - 1) as i64;
+        let mut value = 0.0;
+
+        for i in 0..8 {
+            let offset = [i & 1, (i >> 1) & 1, (i >> 2) & 1];
+            let mut weight = 1.0;
+            let mut position = [0; 3];
+
+            for axis in [0, 1, 2].iter() {
+                let shifted = point[*axis] - 0.5;
+                let base = shifted.floor();
+                let fraction = shifted - base;
+
+                weight *= if offset[*axis] == 1 { fraction } else { 1.0 - fraction };
+                position[*axis] = base as i64 + offset[*axis] as i64;
+            }
+
+            let sample = if position.iter().all(|c| (0..=last).contains(c)) {
+                *octree.get(position.map(|c| c as u32)).unwrap_or(&0) as f32
+            } else if boundary == Boundary::Clamp {
+                *octree.get(position.map(|c| c.max(0).min(last) as u32)).unwrap_or(&0) as f32
+            } else {
+                0.0
+            };
+
+            value += weight * sample;
+        }
+
+        value
+    }
+
+    #[test]
+    fn boundary_modes() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(4).unwrap()).unwrap();
+        octree.insert([0, 0, 0], 8).unwrap();
+
+        let to_f = |data: &u8| *data as f32;
+        assert_eq!(octree.sample_trilinear([0.0, 0.5, 0.5], Boundary::Clamp, to_f), 8.0);
+        assert_eq!(octree.sample_trilinear([0.0, 0.5, 0.5], Boundary::Zero, to_f), 4.0);
+        assert_eq!(octree.sample_trilinear([-3.0, -3.0, -3.0], Boundary::Clamp, to_f), 8.0);
+        assert_eq!(octree.sample_trilinear([-3.0, -3.0, -3.0], Boundary::Zero, to_f), 0.0);
+
+        // Unwritten space is converted like any other default value.
+        assert_eq!(
+            octree.sample_trilinear([1.5, 0.5, 0.5], Boundary::Zero, |data| *data as f32 + 1.0),
+            1.0
+        );
+    }
+
+    #[test]
+    fn sampling_matches_reference() {
+        let mut rng = XorShift::new(0x7151);
+
+        for _ in 0..20 {
+            let octree = rng.octree(16, 300, 4);
+
+            for _ in 0..200 {
+                let point = [0, 1, 2].map(|_| rng.f32(-2.0, 18.0));
+
+                for boundary in [Boundary::Clamp, Boundary::Zero].iter() {
+                    let expected = reference(&octree, point, *boundary);
+                    let value = octree.sample_trilinear(point, *boundary, |data| *data as f32);
+                    assert!((value - expected).abs() < 1e-4);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn continuous_across_leaf_boundaries() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        for x in 0..8 {
+            for y in 0..8 {
+                for z in 0..8 {
+                    octree.insert([x, y, z], 40).unwrap();
+                }
+            }
+        }
+        octree.insert([8, 3, 3], 10).unwrap();
+        octree.insert([9, 3, 3], 30).unwrap();
+
+        let to_f = |data: &u8| *data as f32;
+        let mut previous = octree.sample_trilinear([6.0, 3.3, 3.6], Boundary::Clamp, to_f);
+
+        for step in 1..=4000 {
+            let value = octree.sample_trilinear([6.0 + step as f32 * 0.001, 3.3, 3.6], Boundary::Clamp, to_f);
+            assert!((value - previous).abs() < 0.1);
+            previous = value;
+        }
+    }
+}
@@ -0,0 +1,486 @@
+use crate::{
+    flat::{unflatten, Flatten, Token},
+    ArenaOctree, Error, Node, NodeRef, Octree,
+};
+
+use alloc::{format, vec::Vec};
+use core::{fmt, fmt::Debug, hash::Hash, iter, marker::PhantomData};
+use serde::{
+    de::{DeserializeOwned, DeserializeSeed, Error as _, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    ser::{Error as _, SerializeSeq},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+/// The serialized form of an `Octree`, which stays flat however deep the `Octree` is, with its nodes
+/// serialized by `N`.
+#[derive(Serialize)]
+struct Flat<T, N> {
+    version: u32,
+    dimension: u32,
+    background: T,
+    lod_level: u32,
+    nodes: N,
+}
+
+/// The version of the serialized form, bumped whenever its layout changes.
+const VERSION: u32 = 1;
+
+/// The names of the fields of [`Flat`], in the order they are serialized.
+const FIELDS: &[&str] = &["version", "dimension", "background", "lod_level", "nodes"];
+
+/// The tokens of a `Node` and every `Node` below it, serialized as a sequence as they are flattened, without
+/// collecting them first.
+struct Nodes<'a, T>(NodeRef<'a, T>)
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash;
+
+impl<'a, T> Serialize for Nodes<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash + Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !self.0.is_loaded() {
+            return Err(S::Error::custom(Error::SubtreeNotLoaded));
+        }
+
+        // Count the tokens first, since some formats write the length of a sequence before it.
+        let mut seq = serializer.serialize_seq(Some(Flatten::new(self.0).count()))?;
+        for token in Flatten::new(self.0) {
+            seq.serialize_element(&token)?;
+        }
+
+        seq.end()
+    }
+}
+
+/// The tokens of every `Node` of an `ArenaOctree`, serialized as a sequence as they are listed.
+struct ArenaNodes<'a, T>(&'a ArenaOctree<T>)
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash;
+
+impl<'a, T> Serialize for ArenaNodes<'a, T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash + Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.tokens())
+    }
+}
+
+/// Serializes the `Octree` as its format version, dimension, background and LOD level, followed by its nodes
+/// in pre-order.
+///
+/// Nodes are written as a flat list rather than nested, so that deep `Octree`s neither overflow the stack
+/// nor exceed the nesting limits of formats. Journaled LOD detail is not serialized, and an `Octree` with subtrees
+/// held in storage fails to serialize with [`Error::SubtreeNotLoaded`] until they are loaded.
+impl<T> Serialize for Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Flat {
+            version: VERSION,
+            dimension: self.dimension(),
+            background: self.background(),
+            lod_level: self.lod_level(),
+            nodes: Nodes(self.root()),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Serializes the `ArenaOctree` as the `Octree` holding the same `Node`s is serialized, at LOD level 1.
+impl<T> Serialize for ArenaOctree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Flat {
+            version: VERSION,
+            dimension: self.dimension(),
+            background: self.background(),
+            lod_level: 1,
+            nodes: ArenaNodes(self),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Checks a format version read from a serialized `Octree`.
+fn check_version<E: serde::de::Error>(version: u32) -> Result<(), E> {
+    if version != VERSION {
+        return Err(E::custom(Error::UnsupportedVersion(version)));
+    }
+
+    Ok(())
+}
+
+/// Checks the dimension of a serialized `Octree`, returning it if valid.
+fn check_dimension<E: serde::de::Error>(dimension: u32) -> Result<u32, E> {
+    if !dimension.is_power_of_two() {
+        return Err(E::custom(format!("invalid dimension: {}", dimension)));
+    }
+
+    Ok(dimension)
+}
+
+/// Rebuilds the root `Node` of the given dimension from tokens, as they are deserialized from a sequence.
+///
+/// Each `Node` is attached to its parent as soon as it is read, so that decoding holds no more than the
+/// branches from the root to the current `Node` besides the `Octree` itself.
+struct NodesSeed<T> {
+    dimension: u32,
+    data: PhantomData<T>,
+}
+
+impl<'de, T> DeserializeSeed<'de> for NodesSeed<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + DeserializeOwned,
+{
+    type Value = Node<T>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Node<T>, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, T> Visitor<'de> for NodesSeed<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + DeserializeOwned,
+{
+    type Value = Node<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a sequence of nodes")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Node<T>, A::Error> {
+        let tokens = iter::from_fn(|| seq.next_element::<Token<T>>().transpose());
+        let root = unflatten(self.dimension, tokens, A::Error::custom)?;
+
+        if seq.next_element::<IgnoredAny>()?.is_some() {
+            return Err(A::Error::custom("trailing nodes"));
+        }
+
+        Ok(root)
+    }
+}
+
+/// The fields of a serialized `Octree`, when it is deserialized from a map.
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "snake_case")]
+enum Field {
+    Version,
+    Dimension,
+    Background,
+    LodLevel,
+    Nodes,
+    #[serde(other)]
+    Other,
+}
+
+/// Deserializes an `Octree` from its fields.
+struct OctreeVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for OctreeVisitor<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + DeserializeOwned,
+{
+    type Value = Octree<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("struct Octree")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Octree<T>, A::Error> {
+        let version = seq.next_element()?.ok_or_else(|| A::Error::invalid_length(0, &self))?;
+        check_version(version)?;
+
+        let dimension = seq.next_element()?.ok_or_else(|| A::Error::invalid_length(1, &self))?;
+        let background = seq.next_element()?.ok_or_else(|| A::Error::invalid_length(2, &self))?;
+        let lod_level = seq.next_element()?.ok_or_else(|| A::Error::invalid_length(3, &self))?;
+
+        let seed = NodesSeed {
+            dimension: check_dimension(dimension)?,
+            data: PhantomData,
+        };
+        let root = seq
+            .next_element_seed(seed)?
+            .ok_or_else(|| A::Error::invalid_length(4, &self))?;
+
+        Octree::from_root(dimension, root, background, lod_level).map_err(A::Error::custom)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Octree<T>, A::Error> {
+        let mut version = None;
+        let mut dimension = None;
+        let mut background = None;
+        let mut lod_level = None;
+        let mut root = None;
+        // Nodes read before the dimension, which can only be rebuilt once it is known.
+        let mut tokens: Option<Vec<Token<T>>> = None;
+
+        while let Some(field) = map.next_key()? {
+            match field {
+                Field::Version if version.is_some() => return Err(A::Error::duplicate_field("version")),
+                Field::Version => {
+                    let value = map.next_value()?;
+                    check_version(value)?;
+                    version = Some(value);
+                }
+                Field::Dimension if dimension.is_some() => return Err(A::Error::duplicate_field("dimension")),
+                Field::Dimension => dimension = Some(map.next_value()?),
+                Field::Background if background.is_some() => return Err(A::Error::duplicate_field("background")),
+                Field::Background => background = Some(map.next_value()?),
+                Field::LodLevel if lod_level.is_some() => return Err(A::Error::duplicate_field("lod_level")),
+                Field::LodLevel => lod_level = Some(map.next_value()?),
+                Field::Nodes if root.is_some() || tokens.is_some() => {
+                    return Err(A::Error::duplicate_field("nodes"));
+                }
+                Field::Nodes => match dimension {
+                    Some(dimension) => {
+                        root = Some(map.next_value_seed(NodesSeed {
+                            dimension: check_dimension(dimension)?,
+                            data: PhantomData,
+                        })?);
+                    }
+                    None => tokens = Some(map.next_value()?),
+                },
+                Field::Other => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        version.ok_or_else(|| A::Error::missing_field("version"))?;
+        let dimension = dimension.ok_or_else(|| A::Error::missing_field("dimension"))?;
+        let background = background.ok_or_else(|| A::Error::missing_field("background"))?;
+        let lod_level = lod_level.ok_or_else(|| A::Error::missing_field("lod_level"))?;
+
+        let root = match (root, tokens) {
+            (Some(root), _) => root,
+            (None, Some(tokens)) => {
+                let mut tokens = tokens.into_iter();
+                let root = unflatten(check_dimension(dimension)?, tokens.by_ref().map(Ok), A::Error::custom)?;
+
+                if tokens.next().is_some() {
+                    return Err(A::Error::custom("trailing nodes"));
+                }
+
+                root
+            }
+            (None, None) => return Err(A::Error::missing_field("nodes")),
+        };
+
+        Octree::from_root(dimension, root, background, lod_level).map_err(A::Error::custom)
+    }
+}
+
+/// Deserializes an `Octree` serialized by the same version of the format.
+///
+/// Each node is attached to its parent as soon as it is read, rather than after reading them all, unless a
+/// map lists the nodes ahead of the dimension.
+impl<'de, T> Deserialize<'de> for Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + DeserializeOwned,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct("Flat", FIELDS, OctreeVisitor(PhantomData))
+    }
+}
+
+/// Deserializes an `ArenaOctree` serialized by the same version of the format, from either backend, failing if
+/// it was serialized above LOD level 1.
+impl<'de, T> Deserialize<'de> for ArenaOctree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + DeserializeOwned,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ArenaOctree::from_level_one(&Octree::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VERSION;
+    use crate::{flat::Flatten, test_utils::XorShift, ArenaOctree, Octree};
+
+    use alloc::vec::Vec;
+    use core::num::NonZeroU32;
+    use std::panic::catch_unwind;
+
+    #[test]
+    fn json_round_trip() {
+        let mut rng = XorShift::new(0x5e7d);
+
+        for _ in 0..10 {
+            let mut octree = rng.octree(16, 500, 4);
+            octree.lod_down();
+
+            let json = serde_json::to_string(&octree).unwrap();
+            let copy: Octree<u8> = serde_json::from_str(&json).unwrap();
+
+            assert_eq!(alloc::format!("{:#?}", copy), alloc::format!("{:#?}", octree));
+            assert_eq!((copy.lod_level(), copy.min_dimension()), (2, 2));
+        }
+    }
+
+    #[test]
+    fn msgpack_round_trip() {
+        let mut rng = XorShift::new(0x5e7e);
+
+        for _ in 0..10 {
+            let octree = rng.octree(32, 2000, 4);
+
+            let bytes = rmp_serde::to_vec(&octree).unwrap();
+            let copy: Octree<u8> = rmp_serde::from_slice(&bytes).unwrap();
+
+            assert_eq!(alloc::format!("{:#?}", copy), alloc::format!("{:#?}", octree));
+        }
+    }
+
+    #[test]
+    fn output_matches_fixtures() {
+        let mut octree = Octree::<u16>::new_with_background(NonZeroU32::new(4).unwrap(), 7).unwrap();
+        octree.insert([1, 0, 0], 300).unwrap();
+        octree.insert([2, 3, 1], 5).unwrap();
+
+        let version = VERSION;
+        let json = alloc::format!(
+            r#"{{"version":{},"dimension":4,"background":7,"lod_level":1,"nodes":[{{"Branch":33}},{{"Branch":2}},{{"Leaf":300}},{{"Branch":64}},{{"Leaf":5}}]}}"#,
+            version
+        );
+        assert_eq!(serde_json::to_string(&octree).unwrap(), json);
+
+        // The second byte holds the version.
+        let mut msgpack = vec![
+            149, 0, 4, 7, 1, 149, 129, 166, 66, 114, 97, 110, 99, 104, 33, 129, 166, 66, 114, 97, 110, 99, 104, 2, 129,
+            164, 76, 101, 97, 102, 205, 1, 44, 129, 166, 66, 114, 97, 110, 99, 104, 64, 129, 164, 76, 101, 97, 102, 5,
+        ];
+        msgpack[1] = version as u8;
+        assert_eq!(rmp_serde::to_vec(&octree).unwrap(), msgpack);
+    }
+
+    #[test]
+    fn arenas_serialize_as_octrees() {
+        let octree = XorShift::new(0x5e81).octree(16, 300, 4);
+        let arena = ArenaOctree::from(&octree);
+
+        let json = serde_json::to_string(&arena).unwrap();
+        assert_eq!(json, serde_json::to_string(&octree).unwrap());
+        let msgpack = rmp_serde::to_vec(&arena).unwrap();
+        assert_eq!(msgpack, rmp_serde::to_vec(&octree).unwrap());
+
+        let copy: ArenaOctree<u8> = serde_json::from_str(&json).unwrap();
+        assert_eq!(copy.to_bytes(), arena.to_bytes());
+        let copy: ArenaOctree<u8> = rmp_serde::from_slice(&msgpack).unwrap();
+        assert_eq!(copy.to_bytes(), arena.to_bytes());
+
+        let coarse = serde_json::to_string(&octree.at_lod(1)).unwrap();
+        let error = serde_json::from_str::<ArenaOctree<u8>>(&coarse).unwrap_err();
+        assert!(alloc::format!("{}", error).contains("Invalid LOD level"), "{}", error);
+    }
+
+    #[test]
+    fn background_and_deep_trees_survive() {
+        let dimension = 1 << 20;
+        let mut octree = Octree::<u16>::new_with_background(NonZeroU32::new(dimension).unwrap(), 9).unwrap();
+        octree.insert([1, 2, 3], 4).unwrap();
+        octree.insert([dimension - 1; 3], 5).unwrap();
+
+        let copy: Octree<u16> = rmp_serde::from_slice(&rmp_serde::to_vec(&octree).unwrap()).unwrap();
+        assert_eq!(copy.background(), 9);
+        assert!(copy.equivalent(&octree));
+    }
+
+    #[test]
+    fn sparse_large_trees_serialize_their_nodes_only() {
+        let dimension = 4096;
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(dimension).unwrap()).unwrap();
+        let mut rng = XorShift::new(0x5e80);
+        for _ in 0..100 {
+            octree.insert(rng.position(dimension), 1 + rng.below(4) as u8).unwrap();
+        }
+
+        // Nothing is sized from the dimension, so only the nodes of the tree are listed, and each voxel adds at
+        // most one node on each of the 12 levels below the root.
+        let value = serde_json::to_value(&octree).unwrap();
+        let nodes = value["nodes"].as_array().unwrap();
+        assert_eq!(nodes.len(), Flatten::new(octree.root()).count());
+        assert!(nodes.len() <= 1 + 100 * 12);
+
+        let copy: Octree<u8> = serde_json::from_value(value).unwrap();
+        assert!(copy.equivalent(&octree));
+    }
+
+    #[test]
+    fn malformed_input_is_rejected() {
+        let cases = [
+            r#"{"version":1,"dimension":12,"background":0,"lod_level":1,"nodes":[{"Leaf":0}]}"#,
+            r#"{"version":1,"dimension":2,"background":0,"lod_level":1,"nodes":[{"Branch":1}]}"#,
+            r#"{"version":1,"dimension":2,"background":0,"lod_level":1,"nodes":[{"Leaf":0},{"Leaf":1}]}"#,
+            r#"{"version":1,"dimension":1,"background":0,"lod_level":1,"nodes":[{"Branch":1},{"Leaf":1}]}"#,
+            r#"{"version":1,"dimension":4,"background":0,"lod_level":3,"nodes":[{"Leaf":0}]}"#,
+            r#"{"version":1,"dimension":2,"background":0,"lod_level":1,"nodes":[{"Leaf":0}],"dimension":2}"#,
+            r#"{"version":1,"background":0,"lod_level":1,"nodes":[{"Leaf":0}]}"#,
+            r#"{"dimension":2,"background":0,"lod_level":1,"nodes":[{"Leaf":0}]}"#,
+        ];
+
+        for case in cases.iter() {
+            assert!(serde_json::from_str::<Octree<u8>>(case).is_err());
+        }
+
+        for version in [0, VERSION + 1] {
+            let other = alloc::format!(
+                r#"{{"version":{},"dimension":2,"background":0,"lod_level":1,"nodes":[{{"Leaf":0}}]}}"#,
+                version
+            );
+            let error = serde_json::from_str::<Octree<u8>>(&other).unwrap_err();
+            assert!(alloc::format!("{}", error).contains(&alloc::format!("Unsupported format version: {}", version)));
+        }
+
+        let valid = concat!(
+            r#"{"version":1,"dimension":2,"background":0,"lod_level":1,"#,
+            r#""nodes":[{"Branch":3},{"Leaf":1},{"Leaf":2}]}"#
+        );
+        let octree = serde_json::from_str::<Octree<u8>>(valid).unwrap();
+        assert!(matches!(octree.get([1, 0, 0]), Some(2)));
+        assert_eq!(octree.query_region_values([0; 3], [2; 3]).collect::<Vec<_>>().len(), 2);
+    }
+
+    #[test]
+    fn fields_are_read_in_any_order() {
+        let mut rng = XorShift::new(0x5e80);
+        let octree = rng.octree(8, 100, 4);
+
+        let json: serde_json::Value = serde_json::to_value(&octree).unwrap();
+        let reordered = alloc::format!(
+            r#"{{"nodes":{},"extra":[1,2],"lod_level":1,"background":0,"dimension":8,"version":1}}"#,
+            json["nodes"]
+        );
+        let copy = serde_json::from_str::<Octree<u8>>(&reordered).unwrap();
+        assert!(copy.equivalent(&octree));
+
+        let trailing = reordered.replacen("]", r#",{"Leaf":1}]"#, 1);
+        assert!(serde_json::from_str::<Octree<u8>>(&trailing).is_err());
+    }
+
+    #[test]
+    fn corrupt_msgpack_never_panics() {
+        let mut rng = XorShift::new(0x5e7f);
+        let bytes = rmp_serde::to_vec(&rng.octree(8, 40, 5)).unwrap();
+
+        for len in 0..bytes.len() {
+            let result = catch_unwind(|| rmp_serde::from_slice::<Octree<u8>>(&bytes[..len]).is_err());
+            assert!(matches!(result, Ok(true)));
+        }
+
+        for i in 0..bytes.len() {
+            for flip in [0x01, 0x80, 0xff] {
+                let mut corrupt = bytes.clone();
+                corrupt[i] ^= flip;
+                assert!(catch_unwind(|| rmp_serde::from_slice::<Octree<u8>>(&corrupt).is_ok()).is_ok());
+            }
+        }
+    }
+}
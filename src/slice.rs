@@ -0,0 +1,358 @@
+use crate::{Axis, Error, Octree};
+
+use alloc::{format, string::String, vec};
+use core::{
+    fmt::{Debug, Write},
+    hash::Hash,
+};
+#[cfg(feature = "image")]
+use image::{ImageResult, Rgba, RgbaImage};
+#[cfg(feature = "image")]
+use std::path::Path;
+
+/// Returns the axes across and down a slice along `axis`: the two other axes, in order.
+fn slice_axes(axis: Axis) -> (usize, usize) {
+    match axis {
+        Axis::X => (1, 2),
+        Axis::Y => (0, 2),
+        Axis::Z => (0, 1),
+    }
+}
+
+/// Returns the name of the axis with the given index.
+fn axis_name(axis: usize) -> char {
+    ['x', 'y', 'z'][axis]
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Renders the cross-section of the `Octree` one voxel thick at `index` along `axis` as a grid of
+    /// characters, one per voxel given by `glyph`, which is given `None` for space never written.
+    ///
+    /// The grid is laid out as by [`Octree::slice_to_image`], inside a border, under a line naming the slice and
+    /// its axes and with each row labelled by its coordinate. Only every `stride`th voxel along each axis is
+    /// shown, starting from 0, so that slices of large `Octree`s fit in a terminal; a stride of 0 is taken as 1.
+    /// Each leaf is filled as one rectangle, with `glyph` called once for it.
+    ///
+    /// Returns [`Error::OutOfBounds`] if `index` is outside the `Octree`.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Axis, Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(4).unwrap()).unwrap();
+    /// octree.insert([3, 1, 2], 1).unwrap();
+    ///
+    /// let glyph = |data: Option<&u8>| match data {
+    ///     Some(1) => '#',
+    ///     Some(_) => '.',
+    ///     None => ' ',
+    /// };
+    /// let slice = octree.format_slice(Axis::Z, 2, 1, glyph)?;
+    /// assert_eq!(slice, "\
+    /// z = 2, x across, y down
+    ///   +----+
+    /// 0 |    |
+    /// 1 |   #|
+    /// 2 |    |
+    /// 3 |    |
+    ///   +----+
+    /// ");
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn format_slice(
+        &self,
+        axis: Axis,
+        index: u32,
+        stride: u32,
+        glyph: impl Fn(Option<&T>) -> char,
+    ) -> Result<String, Error> {
+        let dimension = self.dimension();
+        if index >= dimension {
+            return Err(Error::OutOfBounds);
+        }
+
+        let stride = stride.max(1);
+        let cells = dimension.div_ceil(stride) as usize;
+        let (across, down) = slice_axes(axis);
+        let axis = axis.index();
+
+        let (mut min, mut max) = ([0; 3], [dimension; 3]);
+        min[axis] = index;
+        max[axis] = index + 1;
+
+        // The cells covering the voxels from `min` (inclusive) to `max` (exclusive) along an axis.
+        let covered = |min: u32, max: u32| min.div_ceil(stride) as usize..max.div_ceil(stride) as usize;
+
+        let mut grid = vec![' '; cells * cells];
+        for (span_min, span_dimensions, data) in self.query_region(min, max) {
+            let character = glyph(data);
+            let columns = covered(span_min[across], span_min[across] + span_dimensions[across]);
+
+            for row in covered(span_min[down], span_min[down] + span_dimensions[down]) {
+                for cell in &mut grid[row * cells..(row + 1) * cells][columns.clone()] {
+                    *cell = character;
+                }
+            }
+        }
+
+        let label = format!("{}", (cells as u32 - 1) * stride).len();
+        let border = format!("{:width$} +{:-<cells$}+", "", "", width = label, cells = cells);
+
+        let mut text = String::new();
+        write!(
+            text,
+            "{} = {}, {} across, {} down",
+            axis_name(axis),
+            index,
+            axis_name(across),
+            axis_name(down)
+        )
+        .unwrap();
+        if stride > 1 {
+            write!(text, ", every {} voxels", stride).unwrap();
+        }
+
+        writeln!(text).unwrap();
+        writeln!(text, "{}", border).unwrap();
+        for (row, cells) in grid.chunks(cells).enumerate() {
+            write!(text, "{:>width$} |", row as u32 * stride, width = label).unwrap();
+            text.extend(cells);
+            writeln!(text, "|").unwrap();
+        }
+        writeln!(text, "{}", border).unwrap();
+
+        Ok(text)
+    }
+
+    /// Renders the cross-section of the `Octree` one voxel thick at `index` along `axis` as an image as wide and
+    /// high as the `Octree`, coloring each voxel by `color`, which is given `None` for space never written.
+    ///
+    /// The columns and rows of the image follow the two other axes in order, so that a slice along [`Axis::Z`]
+    /// has `x` across and `y` down. Each leaf is filled as one rectangle, with `color` called once for it.
+    ///
+    /// Returns [`Error::OutOfBounds`] if `index` is outside the `Octree`.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Axis, Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(4).unwrap()).unwrap();
+    /// octree.insert([3, 1, 2], 1).unwrap();
+    ///
+    /// let color = |data: Option<&u8>| match data {
+    ///     Some(1) => [255, 0, 0, 255],
+    ///     _ => [0, 0, 0, 0],
+    /// };
+    /// let image = octree.slice_to_image(Axis::Z, 2, color)?;
+    /// assert_eq!(image.get_pixel(3, 1).0, [255, 0, 0, 255]);
+    /// assert_eq!(image.get_pixel(1, 3).0, [0, 0, 0, 0]);
+    /// # Ok::<(), Error>(())
+    /// ```
+    #[cfg(feature = "image")]
+    pub fn slice_to_image(
+        &self,
+        axis: Axis,
+        index: u32,
+        color: impl Fn(Option<&T>) -> [u8; 4],
+    ) -> Result<RgbaImage, Error> {
+        let dimension = self.dimension();
+        if index >= dimension {
+            return Err(Error::OutOfBounds);
+        }
+
+        let (across, down) = slice_axes(axis);
+        let axis = axis.index();
+
+        let (mut min, mut max) = ([0; 3], [dimension; 3]);
+        min[axis] = index;
+        max[axis] = index + 1;
+
+        let mut image = RgbaImage::new(dimension, dimension);
+        for (span_min, span_dimensions, data) in self.query_region(min, max) {
+            let pixel = Rgba(color(data));
+
+            for y in span_min[down]..span_min[down] + span_dimensions[down] {
+                for x in span_min[across]..span_min[across] + span_dimensions[across] {
+                    image.put_pixel(x, y, pixel);
+                }
+            }
+        }
+
+        Ok(image)
+    }
+
+    /// Renders every cross-section of the `Octree` along `axis` as by [`Octree::slice_to_image`], writing each
+    /// to `dir` as a PNG named by its index, padded with zeros so that the files sort in order.
+    ///
+    /// The directory must already exist. Stops at the first image which cannot be written.
+    #[cfg(feature = "image")]
+    pub fn export_slices(
+        &self,
+        dir: impl AsRef<Path>,
+        axis: Axis,
+        color: impl Fn(Option<&T>) -> [u8; 4],
+    ) -> ImageResult<()> {
+        let dimension = self.dimension();
+        let width = format!("{}", dimension - 1).len();
+
+        for index in 0..dimension {
+            let image = self.slice_to_image(axis, index, &color).unwrap();
+            image.save(dir.as_ref().join(format!("slice_{:0width$}.png", index, width = width)))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Axis, Error, Octree};
+
+    #[cfg(feature = "image")]
+    use alloc::vec::Vec;
+    use core::num::NonZeroU32;
+
+    #[cfg(feature = "image")]
+    fn color(data: Option<&u8>) -> [u8; 4] {
+        match data {
+            Some(data) => [*data, 0, 0, 255],
+            None => [0, 0, 255, 0],
+        }
+    }
+
+    fn glyph(data: Option<&u8>) -> char {
+        match data {
+            Some(0) => '.',
+            Some(data) => (b'0' + data) as char,
+            None => ' ',
+        }
+    }
+
+    #[test]
+    fn formatted_slices_match_the_fixture() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        for x in 0..4 {
+            for y in 0..4 {
+                octree.insert([x, y, 1], 1).unwrap();
+            }
+        }
+        octree.insert([6, 1, 1], 2).unwrap();
+        octree.insert([7, 7, 1], 3).unwrap();
+        octree.clear_at([2, 2, 1]).unwrap();
+
+        let expected = "\
+z = 1, x across, y down
+  +--------+
+0 |1111    |
+1 |1111  2 |
+2 |11.1    |
+3 |1111    |
+4 |        |
+5 |        |
+6 |        |
+7 |       3|
+  +--------+
+";
+        assert_eq!(octree.format_slice(Axis::Z, 1, 1, glyph).unwrap(), expected);
+
+        let expected = "\
+x = 6, y across, z down
+  +--------+
+0 |        |
+1 | 2      |
+2 |        |
+3 |        |
+4 |        |
+5 |        |
+6 |        |
+7 |        |
+  +--------+
+";
+        assert_eq!(octree.format_slice(Axis::X, 6, 1, glyph).unwrap(), expected);
+    }
+
+    #[test]
+    fn formatted_slices_are_downsampled() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+        octree.insert([0, 0, 0], 1).unwrap();
+        octree.insert([8, 24, 0], 2).unwrap();
+        octree.insert([9, 8, 0], 3).unwrap();
+
+        let expected = "\
+z = 0, x across, y down, every 8 voxels
+   +----+
+ 0 |1   |
+ 8 |    |
+16 |    |
+24 | 2  |
+   +----+
+";
+        assert_eq!(octree.format_slice(Axis::Z, 0, 8, glyph).unwrap(), expected);
+        assert_eq!(
+            octree.format_slice(Axis::Z, 0, 0, glyph),
+            octree.format_slice(Axis::Z, 0, 1, glyph)
+        );
+        assert_eq!(octree.format_slice(Axis::Y, 32, 1, glyph), Err(Error::OutOfBounds));
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn slices_match_the_voxels() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        for x in 0..8 {
+            for y in 0..8 {
+                for z in 0..8 {
+                    octree.insert([x, y, z], 1).unwrap();
+                }
+            }
+        }
+        octree.insert([12, 3, 5], 2).unwrap();
+        octree.insert([0, 15, 5], 3).unwrap();
+
+        let image = octree.slice_to_image(Axis::Z, 5, color).unwrap();
+        assert_eq!(image.dimensions(), (16, 16));
+        assert_eq!(image.get_pixel(0, 0).0, [1, 0, 0, 255]);
+        assert_eq!(image.get_pixel(7, 7).0, [1, 0, 0, 255]);
+        assert_eq!(image.get_pixel(12, 3).0, [2, 0, 0, 255]);
+        assert_eq!(image.get_pixel(0, 15).0, [3, 0, 0, 255]);
+        assert_eq!(image.get_pixel(8, 8).0, [0, 0, 255, 0]);
+
+        // Along x, columns follow y and rows follow z.
+        let image = octree.slice_to_image(Axis::X, 12, color).unwrap();
+        assert_eq!(image.get_pixel(3, 5).0, [2, 0, 0, 255]);
+        assert_eq!(image.get_pixel(5, 3).0, [0, 0, 255, 0]);
+
+        // Along y, columns follow x and rows follow z.
+        let image = octree.slice_to_image(Axis::Y, 15, color).unwrap();
+        assert_eq!(image.get_pixel(0, 5).0, [3, 0, 0, 255]);
+
+        for axis in Axis::ALL.iter().copied() {
+            let image = octree.slice_to_image(axis, 2, color).unwrap();
+            for (x, y, pixel) in image.enumerate_pixels() {
+                let mut position = [2; 3];
+                let others = Axis::ALL
+                    .iter()
+                    .map(|a| a.index())
+                    .filter(|a| *a != axis.index())
+                    .collect::<Vec<_>>();
+                position[others[0]] = x;
+                position[others[1]] = y;
+                assert_eq!(pixel.0, color(octree.root().get(position.into())), "at {:?}", position);
+            }
+        }
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn out_of_range_slices_are_rejected() {
+        let octree = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+
+        assert!(octree.slice_to_image(Axis::Y, 7, color).is_ok());
+        assert_eq!(octree.slice_to_image(Axis::Y, 8, color), Err(Error::OutOfBounds));
+    }
+}
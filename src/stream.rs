@@ -0,0 +1,1450 @@
+use crate::{
+    codec::encoded_len,
+    fill::fill,
+    flat::{unflatten, Flatten, Token},
+    hash::Crc32,
+    query::classify_box,
+    Error, Node, NodeRef, Octree, ValueCodec, Vector3,
+};
+
+use alloc::vec::Vec;
+use core::{
+    convert::TryFrom,
+    fmt::{self, Debug},
+    hash::Hash,
+};
+use hashbrown::HashMap;
+use std::{
+    error,
+    io::{self, Read, Write},
+};
+
+/// Starts encodings of whole trees, followed by the version.
+const MAGIC: &[u8; 4] = b"svoS";
+/// The version of the encoding of whole trees, bumped whenever its layout changes.
+const VERSION: u16 = 1;
+/// Starts encodings of regions, followed by the version.
+const REGION_MAGIC: &[u8; 4] = b"svoR";
+/// The version of the encoding of regions, bumped whenever its layout changes.
+const REGION_VERSION: u16 = 1;
+
+/// The number of bytes buffered before being handed to the writer.
+const CHUNK: usize = 4096;
+/// The number of node bytes compressed together.
+#[cfg(feature = "compression")]
+const LZ4_BLOCK: usize = 1 << 16;
+/// The most bytes a value is read from, so that a corrupt value cannot go on claiming memory until the reader
+/// ends.
+const MAX_VALUE: usize = 1024;
+
+const LEAF: u8 = 0;
+const BRANCH: u8 = 1;
+
+/// An error returned by [`Octree::encode_to`] and [`Octree::encode_region`].
+#[derive(Debug)]
+pub enum EncodeError {
+    /// The writer failed.
+    Io(io::Error),
+    /// The part of the `Octree` asked for cannot be encoded.
+    Octree(Error),
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "Failed to write octree: {}", error),
+            Self::Octree(error) => write!(f, "Cannot encode octree: {}", error),
+        }
+    }
+}
+
+impl error::Error for EncodeError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Octree(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for EncodeError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// An error returned by [`Octree::decode_from`] and [`Octree::decode_region_into`].
+#[derive(Debug)]
+pub enum DecodeError {
+    /// The reader failed, or ended before the whole `Octree` was read.
+    Io(io::Error),
+    /// The stream does not hold a valid `Octree`.
+    Malformed(&'static str),
+    /// The stream describes an `Octree` which cannot be built.
+    Octree(Error),
+    /// The nodes are compressed in a mode which is unknown, or needs the `compression` feature.
+    UnsupportedCompression(u8),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "Failed to read octree: {}", error),
+            Self::Malformed(reason) => write!(f, "Malformed octree: {}", reason),
+            Self::Octree(error) => write!(f, "Invalid octree: {}", error),
+            Self::UnsupportedCompression(flag) => write!(f, "Unsupported compression mode: {}", flag),
+        }
+    }
+}
+
+impl error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for DecodeError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// How [`Octree::encode_to_with`] stores the nodes of an `Octree`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum CompressionMode {
+    /// Nodes are stored as they are.
+    #[default]
+    None,
+    /// Nodes are compressed with LZ4, a block at a time.
+    #[cfg(feature = "compression")]
+    Lz4,
+}
+
+impl CompressionMode {
+    /// Returns the flag marking the mode in the header.
+    fn flag(self) -> u8 {
+        match self {
+            Self::None => 0,
+            #[cfg(feature = "compression")]
+            Self::Lz4 => 1,
+        }
+    }
+}
+
+/// Hands a chunk of encoded nodes to the writer.
+type Flush<'a> = &'a mut dyn FnMut(&[u8]) -> io::Result<()>;
+
+/// Reads exactly `N` bytes.
+fn read_array<const N: usize>(r: &mut impl Read) -> Result<[u8; N], DecodeError> {
+    let mut bytes = [0; N];
+    r.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Reads one encoded value, a byte at a time until the bytes read hold exactly one value.
+fn read_data<T: ValueCodec>(r: &mut impl Read) -> Result<T, DecodeError> {
+    let mut bytes = Vec::new();
+
+    loop {
+        if let Some((value, len)) = T::read(&bytes) {
+            return match len == bytes.len() {
+                true => Ok(value),
+                false => Err(DecodeError::Malformed("invalid value")),
+            };
+        }
+
+        if bytes.len() == MAX_VALUE {
+            return Err(DecodeError::Malformed("invalid value"));
+        }
+        bytes.push(read_array::<1>(r)?[0]);
+    }
+}
+
+/// Returns the number of bytes needed to index a palette of `count` values.
+fn index_width(count: usize) -> u8 {
+    let max = count.saturating_sub(1) as u64;
+    (1..4).find(|width| max < 1 << (8 * width)).unwrap_or(4)
+}
+
+/// How the values of the leaves of some nodes are written, chosen by [`Layout::new`].
+struct Layout<T> {
+    /// The distinct values of the leaves, if they are written as a palette.
+    palette: Vec<T>,
+    /// The index of each value in the palette.
+    indices: HashMap<T, u32>,
+    /// The number of bytes each leaf indexes the palette with, or 0 if leaves hold their values.
+    width: u8,
+    /// The number of bytes of the palette and the nodes.
+    len: u64,
+}
+
+impl<T> Layout<T>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash + ValueCodec,
+{
+    /// Writes the values of `tokens` as a palette, where that makes their encoding smaller.
+    fn new(tokens: impl Iterator<Item = Token<T>>) -> Self {
+        let (mut branches, mut leaves, mut inline) = (0_u64, 0_u64, 0_u64);
+        let (mut palette, mut indices, mut lens) = (Vec::new(), HashMap::new(), Vec::new());
+
+        for token in tokens {
+            match token {
+                Token::Leaf(data) => {
+                    leaves += 1;
+                    let index = *indices.entry(data).or_insert_with(|| {
+                        palette.push(data);
+                        lens.push(encoded_len(&data) as u64);
+                        palette.len() as u32 - 1
+                    });
+                    inline += lens[index as usize];
+                }
+                Token::Branch(_) => branches += 1,
+            }
+        }
+
+        let width = index_width(palette.len());
+        let indexed = lens.iter().sum::<u64>() + leaves * width as u64;
+
+        // Leaves hold their values where a palette would not be smaller.
+        let (width, len) = match u32::try_from(palette.len()) {
+            Ok(_) if indexed < inline => (width, indexed),
+            _ => {
+                palette.clear();
+                indices.clear();
+                (0, inline)
+            }
+        };
+
+        Self {
+            palette,
+            indices,
+            width,
+            len: 2 * branches + leaves + len,
+        }
+    }
+
+    /// Appends the header fields describing the palette: the width of its indices and its length.
+    fn write_header(&self, bytes: &mut Vec<u8>) {
+        bytes.push(self.width);
+        bytes.extend_from_slice(&(self.palette.len() as u32).to_le_bytes());
+    }
+
+    /// Appends the encoding of one node.
+    fn write_token(&self, bytes: &mut Vec<u8>, token: Token<T>) {
+        match token {
+            Token::Leaf(data) if self.width > 0 => {
+                bytes.push(LEAF);
+                bytes.extend_from_slice(&self.indices[&data].to_le_bytes()[..self.width as usize]);
+            }
+            Token::Leaf(data) => {
+                bytes.push(LEAF);
+                data.write(bytes);
+            }
+            Token::Branch(mask) => {
+                bytes.push(BRANCH);
+                bytes.push(mask);
+            }
+        }
+    }
+}
+
+/// How the values of leaves are stored.
+enum Values<T> {
+    /// Each leaf holds its value.
+    Inline,
+    /// Each leaf holds the index of its value in a palette, in the given number of bytes.
+    Palette(Vec<T>, u8),
+}
+
+impl<T: ValueCodec + Copy> Values<T> {
+    /// Reads the palette of `count` values preceding the nodes, if their values are indexed in `width` bytes.
+    fn read(r: &mut impl Read, width: u8, count: u32) -> Result<Self, DecodeError> {
+        if width == 0 {
+            return Ok(Self::Inline);
+        }
+
+        // Grown as values are read, so that a corrupt count claims no more memory than the nodes hold.
+        let mut values = Vec::new();
+        for _ in 0..count {
+            values.push(read_data(r)?);
+        }
+
+        Ok(Self::Palette(values, width))
+    }
+
+    /// Reads the value of one leaf.
+    fn read_value(&self, r: &mut impl Read) -> Result<T, DecodeError> {
+        match self {
+            Self::Inline => read_data(r),
+            Self::Palette(values, width) => {
+                let mut index = [0; 4];
+                r.read_exact(&mut index[..*width as usize])?;
+
+                let index = u32::from_le_bytes(index) as usize;
+                values
+                    .get(index)
+                    .copied()
+                    .ok_or(DecodeError::Malformed("palette index out of range"))
+            }
+        }
+    }
+}
+
+/// Reads the nodes of an `Octree` of the given dimension, in pre-order.
+fn read_root<T>(r: &mut impl Read, dimension: u32, values: &Values<T>) -> Result<Node<T>, DecodeError>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash + ValueCodec,
+{
+    let tokens = core::iter::from_fn(|| {
+        Some(match read_array::<1>(r) {
+            Ok([LEAF]) => values.read_value(r).map(Token::Leaf),
+            Ok([BRANCH]) => read_array::<1>(r).map(|[mask]| Token::Branch(mask)),
+            Ok(_) => Err(DecodeError::Malformed("unknown node")),
+            Err(error) => Err(error),
+        })
+    });
+
+    unflatten(dimension, tokens, DecodeError::Malformed)
+}
+
+/// Checks that `dimension` is valid for an `Octree`.
+fn check_dimension(dimension: u32) -> Result<(), DecodeError> {
+    if dimension.is_power_of_two() {
+        Ok(())
+    } else {
+        Err(DecodeError::Octree(Error::InvalidDimension(dimension)))
+    }
+}
+
+/// Returns whether the box from `min` (inclusive) to `max` (exclusive) is non-empty and lies within an `Octree`
+/// of the given dimension.
+fn is_region(dimension: u32, min: [u32; 3], max: [u32; 3]) -> bool {
+    (0..3).all(|axis| min[axis] < max[axis] && max[axis] <= dimension)
+}
+
+/// Checks that the checksum read from `r` matches `actual`.
+fn check_crc(r: &mut impl Read, actual: u32) -> Result<(), DecodeError> {
+    let expected = u32::from_le_bytes(read_array(r)?);
+
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(DecodeError::Octree(Error::ChecksumMismatch { expected, actual }))
+    }
+}
+
+/// A reader computing the checksum of everything read through it.
+struct CrcReader<'a, R> {
+    inner: &'a mut R,
+    crc: Crc32,
+}
+
+impl<'a, R: Read> Read for CrcReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.crc.update(&buf[..len]);
+        Ok(len)
+    }
+}
+
+/// Writes `bytes` to `w` as a block of LZ4, preceded by its uncompressed and compressed lengths.
+#[cfg(feature = "compression")]
+fn write_lz4_block(w: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    let compressed = lz4_flex::compress(bytes);
+
+    w.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    w.write_all(&(compressed.len() as u32).to_le_bytes())?;
+    w.write_all(&compressed)
+}
+
+/// A reader decompressing the blocks written by [`write_lz4_block`], up to a number of uncompressed bytes.
+#[cfg(feature = "compression")]
+struct Lz4Reader<'a, R> {
+    inner: &'a mut R,
+    block: Vec<u8>,
+    position: usize,
+    remaining: u64,
+}
+
+#[cfg(feature = "compression")]
+impl<'a, R: Read> Lz4Reader<'a, R> {
+    /// Reads and decompresses the next block, which must not exceed the bytes remaining.
+    fn next_block(&mut self) -> io::Result<()> {
+        let (mut len, mut compressed) = ([0; 4], [0; 4]);
+        self.inner.read_exact(&mut len)?;
+        self.inner.read_exact(&mut compressed)?;
+
+        let len = u32::from_le_bytes(len) as usize;
+        let compressed = u32::from_le_bytes(compressed) as usize;
+
+        // Bounds the memory a corrupt or hostile block can claim, whatever it declares.
+        if len == 0
+            || len > LZ4_BLOCK + CHUNK
+            || len as u64 > self.remaining
+            || compressed > lz4_flex::block::get_maximum_output_size(len)
+        {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid compressed block"));
+        }
+
+        let mut bytes = vec![0; compressed];
+        self.inner.read_exact(&mut bytes)?;
+
+        self.block.resize(len, 0);
+        match lz4_flex::decompress_into(&bytes, &mut self.block) {
+            Ok(decompressed) if decompressed == len => {}
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "corrupt compressed block")),
+        }
+
+        self.position = 0;
+        self.remaining -= len as u64;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "compression")]
+impl<'a, R: Read> Read for Lz4Reader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.block.len() {
+            if self.remaining == 0 {
+                return Ok(0);
+            }
+
+            self.next_block()?;
+        }
+
+        let len = buf.len().min(self.block.len() - self.position);
+        buf[..len].copy_from_slice(&self.block[self.position..self.position + len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+
+/// Reads `len` bytes of nodes of an `Octree` of the given dimension, preceded by their palette if their values
+/// are indexed in `width` bytes, returning them with the checksum of the bytes.
+///
+/// All `len` bytes are read before any error in the nodes themselves is returned, so that the checksum can be
+/// compared first.
+fn read_checked<T>(
+    r: &mut impl Read,
+    dimension: u32,
+    len: u64,
+    (width, count): (u8, u32),
+) -> Result<(Result<Node<T>, DecodeError>, u32), DecodeError>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash + ValueCodec,
+{
+    let mut nodes = CrcReader {
+        inner: r,
+        crc: Crc32::new(),
+    }
+    .take(len);
+    let root = Values::read(&mut nodes, width, count).and_then(|values| read_root(&mut nodes, dimension, &values));
+    let trailing = io::copy(&mut nodes, &mut io::sink())?;
+
+    if nodes.limit() > 0 {
+        return Err(DecodeError::Io(io::ErrorKind::UnexpectedEof.into()));
+    }
+
+    let root = match root {
+        Err(DecodeError::Io(error)) if error.kind() == io::ErrorKind::UnexpectedEof => {
+            Err(DecodeError::Malformed("missing nodes"))
+        }
+        Ok(_) if trailing > 0 => Err(DecodeError::Malformed("trailing nodes")),
+        root => root,
+    };
+
+    Ok((root, nodes.into_inner().crc.finish()))
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + ValueCodec,
+{
+    /// Writes the `Octree` to `w` as a header holding its format version, dimension, LOD level and
+    /// background, followed by its nodes in pre-order.
+    ///
+    /// The header and the nodes are each followed by their CRC-32 checksum. Nodes are encoded as the `Octree`
+    /// is walked, and handed to the writer a few kilobytes at a time, so the whole encoding is never held in
+    /// memory. Journaled LOD detail is not encoded. Fails with [`Error::SubtreeNotLoaded`] if any subtree is held
+    /// in storage, as its contents are unknown until it is loaded through [`Octree::with_source`].
+    ///
+    /// Where it makes the encoding smaller, the distinct values of the leaves are written once, as a palette
+    /// ahead of the nodes, and each leaf holds the index of its value in the palette instead of the value
+    /// itself. Finding them takes a first walk of the `Octree`, and memory for each distinct value.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// # use std::io::Cursor;
+    /// #
+    /// let mut octree = Octree::<u16>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([1, 2, 3], 4).unwrap();
+    ///
+    /// let mut bytes = Vec::new();
+    /// octree.encode_to(&mut bytes).unwrap();
+    ///
+    /// let copy = Octree::<u16>::decode_from(&mut Cursor::new(bytes)).unwrap();
+    /// assert!(copy.equivalent(&octree));
+    /// ```
+    pub fn encode_to(&self, w: &mut impl Write) -> Result<(), EncodeError> {
+        self.encode_to_with(w, CompressionMode::None)
+    }
+
+    /// Writes the `Octree` to `w` as [`Octree::encode_to`] does, storing its nodes as given by `compression`.
+    ///
+    /// The checksum of the nodes is computed before compression, and the header records the mode, so that
+    /// [`Octree::decode_from`] reads either.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{CompressionMode, Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u16>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([1, 2, 3], 4).unwrap();
+    ///
+    /// let mut bytes = Vec::new();
+    /// octree.encode_to_with(&mut bytes, CompressionMode::None).unwrap();
+    ///
+    /// let copy = Octree::<u16>::decode_from(&mut bytes.as_slice()).unwrap();
+    /// assert!(copy.equivalent(&octree));
+    /// ```
+    pub fn encode_to_with(&self, w: &mut impl Write, compression: CompressionMode) -> Result<(), EncodeError> {
+        enter_span!(DEBUG, "encode_to", [len], ?compression);
+        if !self.root().is_loaded() {
+            return Err(EncodeError::Octree(Error::SubtreeNotLoaded));
+        }
+
+        let layout = Layout::new(Flatten::new(self.root()));
+        record!(len = layout.len);
+
+        let mut chunk = Vec::with_capacity(CHUNK);
+        chunk.extend_from_slice(MAGIC);
+        chunk.extend_from_slice(&VERSION.to_le_bytes());
+
+        let start = chunk.len();
+        chunk.extend_from_slice(&self.dimension().to_le_bytes());
+        chunk.extend_from_slice(&self.lod_level().to_le_bytes());
+        self.background().write(&mut chunk);
+        chunk.extend_from_slice(&layout.len.to_le_bytes());
+        chunk.push(compression.flag());
+        layout.write_header(&mut chunk);
+
+        let mut crc = Crc32::new();
+        crc.update(&chunk[start..]);
+        chunk.extend_from_slice(&crc.finish().to_le_bytes());
+
+        let (flush, size): (Flush<'_>, usize) = match compression {
+            CompressionMode::None => (&mut |bytes| w.write_all(bytes), CHUNK),
+            #[cfg(feature = "compression")]
+            CompressionMode::Lz4 => {
+                w.write_all(&chunk)?;
+                chunk.clear();
+                (&mut |bytes| write_lz4_block(w, bytes), LZ4_BLOCK)
+            }
+        };
+
+        let mut crc = Crc32::new();
+        for data in layout.palette.iter() {
+            let start = chunk.len();
+            data.write(&mut chunk);
+            crc.update(&chunk[start..]);
+
+            if chunk.len() >= size {
+                flush(&chunk)?;
+                chunk.clear();
+            }
+        }
+
+        for token in Flatten::new(self.root()) {
+            let start = chunk.len();
+            layout.write_token(&mut chunk, token);
+            crc.update(&chunk[start..]);
+
+            if chunk.len() >= size {
+                flush(&chunk)?;
+                chunk.clear();
+            }
+        }
+
+        if !chunk.is_empty() {
+            flush(&chunk)?;
+        }
+
+        w.write_all(&crc.finish().to_le_bytes())?;
+        Ok(())
+    }
+
+    /// Reads an `Octree` written by [`Octree::encode_to`] from `r`.
+    ///
+    /// Nodes are decoded as they are read, and reading stops at the end of the `Octree`, leaving anything
+    /// after it in the reader. Values are read a byte at a time until their [`ValueCodec`] reads a whole value,
+    /// so unbuffered readers should be wrapped in a [`std::io::BufReader`], and values encoded in more than
+    /// 1024 bytes are rejected as malformed.
+    ///
+    /// Any corruption after the format version is reported as [`Error::ChecksumMismatch`], whether or not the
+    /// corrupt bytes still parse. Encodings of any other version are rejected with [`Error::UnsupportedVersion`].
+    /// Compressed nodes which fail to decompress are reported as [`DecodeError::Io`] with
+    /// [`io::ErrorKind::InvalidData`], and never expand beyond the length of the nodes declared in the header.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{DecodeError, Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// # use std::io::Cursor;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([1, 2, 3], 4).unwrap();
+    ///
+    /// let mut bytes = Vec::new();
+    /// octree.encode_to(&mut bytes).unwrap();
+    ///
+    /// let last = bytes.len() - 5;
+    /// bytes[last] ^= 1;
+    /// let result = Octree::<u8>::decode_from(&mut Cursor::new(&bytes));
+    /// assert!(matches!(result, Err(DecodeError::Octree(Error::ChecksumMismatch { .. }))));
+    ///
+    /// bytes.pop();
+    /// let result = Octree::<u8>::decode_from(&mut Cursor::new(&bytes));
+    /// assert!(matches!(result, Err(DecodeError::Io(_))));
+    /// ```
+    pub fn decode_from(r: &mut impl Read) -> Result<Self, DecodeError> {
+        enter_span!(DEBUG, "decode_from", [version, len]);
+        if &read_array::<4>(r)? != MAGIC {
+            return Err(DecodeError::Malformed("missing header"));
+        }
+
+        let version = u16::from_le_bytes(read_array(r)?);
+        record!(version = version);
+        if version != VERSION {
+            return Err(DecodeError::Octree(Error::UnsupportedVersion(version as u32)));
+        }
+
+        let mut header = CrcReader {
+            inner: &mut *r,
+            crc: Crc32::new(),
+        };
+        let dimension = u32::from_le_bytes(read_array(&mut header)?);
+        let lod_level = u32::from_le_bytes(read_array(&mut header)?);
+        let background = read_data::<T>(&mut header)?;
+        let len = u64::from_le_bytes(read_array(&mut header)?);
+        record!(len = len);
+        let compression = read_array::<1>(&mut header)?[0];
+        let palette = (
+            read_array::<1>(&mut header)?[0],
+            u32::from_le_bytes(read_array(&mut header)?),
+        );
+
+        let crc = header.crc.finish();
+        check_crc(r, crc)?;
+        check_dimension(dimension)?;
+
+        if palette.0 > 4 || (palette.0 == 0) != (palette.1 == 0) {
+            return Err(DecodeError::Malformed("invalid palette"));
+        }
+
+        let (root, crc) = match compression {
+            0 => read_checked(r, dimension, len, palette)?,
+            #[cfg(feature = "compression")]
+            1 => {
+                let mut lz4 = Lz4Reader {
+                    inner: &mut *r,
+                    block: Vec::new(),
+                    position: 0,
+                    remaining: len,
+                };
+                read_checked(&mut lz4, dimension, len, palette)?
+            }
+            flag => return Err(DecodeError::UnsupportedCompression(flag)),
+        };
+
+        check_crc(r, crc)?;
+        let root = root?;
+
+        Octree::from_root(dimension, root, background, lod_level).map_err(DecodeError::Octree)
+    }
+
+    /// Encodes the voxels of the `Octree` in the box from `min` (inclusive) to `max` (exclusive), so that
+    /// [`Octree::decode_region_into`] can write them into another `Octree` of the same dimension.
+    ///
+    /// The encoding holds a header with the format version, the dimension and the box, followed by the
+    /// `Node`s intersecting the box in pre-order, laid out as [`Octree::encode_to`] lays out every `Node`.
+    /// Leaves extending outside the box are encoded whole, and clipped to it when decoded. The header and the
+    /// `Node`s are each followed by their CRC-32 checksum. The LOD level and background are not encoded.
+    ///
+    /// Returns [`Error::OutOfBounds`] if the box is empty or extends outside the `Octree`, and
+    /// [`Error::SubtreeNotLoaded`] if any of the `Octree` is held in storage.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u16>::new(NonZeroU32::new(512).unwrap()).unwrap();
+    /// octree.insert([1, 2, 3], 4).unwrap();
+    /// octree.insert([300, 2, 3], 5).unwrap();
+    ///
+    /// let bytes = octree.encode_region([0, 0, 0], [8, 8, 8]).unwrap();
+    /// assert!(bytes.len() < 100);
+    ///
+    /// let mut copy = Octree::<u16>::new(NonZeroU32::new(512).unwrap()).unwrap();
+    /// copy.decode_region_into(&bytes).unwrap();
+    /// assert!(matches!(copy.get([1, 2, 3]), Some(4)));
+    /// assert_eq!(copy.get([300, 2, 3]), Some(&0));
+    /// ```
+    pub fn encode_region(
+        &self,
+        min: impl Into<Vector3<u32>>,
+        max: impl Into<Vector3<u32>>,
+    ) -> Result<Vec<u8>, EncodeError> {
+        let min = <[u32; 3]>::from(min.into());
+        let max = <[u32; 3]>::from(max.into());
+        if !is_region(self.dimension(), min, max) {
+            return Err(EncodeError::Octree(Error::OutOfBounds));
+        }
+        if !self.root().is_loaded() {
+            return Err(EncodeError::Octree(Error::SubtreeNotLoaded));
+        }
+
+        let layout = Layout::new(Flatten::within(self.root(), min, max));
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(REGION_MAGIC);
+        bytes.extend_from_slice(&REGION_VERSION.to_le_bytes());
+
+        let start = bytes.len();
+        bytes.extend_from_slice(&self.dimension().to_le_bytes());
+        for c in min.iter().chain(max.iter()) {
+            bytes.extend_from_slice(&c.to_le_bytes());
+        }
+        bytes.extend_from_slice(&layout.len.to_le_bytes());
+        layout.write_header(&mut bytes);
+
+        let mut crc = Crc32::new();
+        crc.update(&bytes[start..]);
+        bytes.extend_from_slice(&crc.finish().to_le_bytes());
+
+        let start = bytes.len();
+        for data in layout.palette.iter() {
+            data.write(&mut bytes);
+        }
+        for token in Flatten::within(self.root(), min, max) {
+            layout.write_token(&mut bytes, token);
+        }
+
+        let mut crc = Crc32::new();
+        crc.update(&bytes[start..]);
+        bytes.extend_from_slice(&crc.finish().to_le_bytes());
+
+        Ok(bytes)
+    }
+
+    /// Replaces the voxels of the `Octree` in a box with those encoded by [`Octree::encode_region`].
+    ///
+    /// The box is cleared, then each encoded leaf is written to the part of it inside the box, so that space
+    /// which was never written when encoded holds the background. The whole encoding is checked before the
+    /// `Octree` is changed. Returns [`Error::DimensionMismatch`] if it was encoded from an `Octree` of another
+    /// dimension, and reports corruption as [`decode_from`](Octree::decode_from) does.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{DecodeError, Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([1, 2, 3], 4).unwrap();
+    /// let bytes = octree.encode_region([0, 0, 0], [4, 4, 4]).unwrap();
+    ///
+    /// octree.insert([1, 2, 3], 5).unwrap();
+    /// octree.decode_region_into(&bytes).unwrap();
+    /// assert!(matches!(octree.get([1, 2, 3]), Some(4)));
+    ///
+    /// let mut other = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+    /// let result = other.decode_region_into(&bytes);
+    /// assert!(matches!(result, Err(DecodeError::Octree(Error::DimensionMismatch { .. }))));
+    /// ```
+    pub fn decode_region_into(&mut self, mut bytes: &[u8]) -> Result<(), DecodeError> {
+        let r = &mut bytes;
+
+        if &read_array::<4>(r)? != REGION_MAGIC {
+            return Err(DecodeError::Malformed("missing header"));
+        }
+
+        let version = u16::from_le_bytes(read_array(r)?);
+        if version != REGION_VERSION {
+            return Err(DecodeError::Octree(Error::UnsupportedVersion(version as u32)));
+        }
+
+        let mut header = CrcReader {
+            inner: &mut *r,
+            crc: Crc32::new(),
+        };
+        let dimension = u32::from_le_bytes(read_array(&mut header)?);
+        let mut region = [0; 6];
+        for c in region.iter_mut() {
+            *c = u32::from_le_bytes(read_array(&mut header)?);
+        }
+        let len = u64::from_le_bytes(read_array(&mut header)?);
+        let palette = (
+            read_array::<1>(&mut header)?[0],
+            u32::from_le_bytes(read_array(&mut header)?),
+        );
+
+        let crc = header.crc.finish();
+        check_crc(r, crc)?;
+
+        if dimension != self.dimension() {
+            return Err(DecodeError::Octree(Error::DimensionMismatch {
+                expected: self.dimension(),
+                found: dimension,
+            }));
+        }
+
+        let min = [region[0], region[1], region[2]];
+        let max = [region[3], region[4], region[5]];
+        if !is_region(dimension, min, max) {
+            return Err(DecodeError::Malformed("invalid region"));
+        }
+
+        if palette.0 > 4 || (palette.0 == 0) != (palette.1 == 0) {
+            return Err(DecodeError::Malformed("invalid palette"));
+        }
+
+        let (root, crc) = read_checked(r, dimension, len, palette)?;
+        check_crc(r, crc)?;
+        let root = root?;
+
+        if !r.is_empty() {
+            return Err(DecodeError::Malformed("trailing bytes"));
+        }
+
+        self.clear_region(min, max);
+
+        let bounds = [Vector3::from([0, 0, 0]), Vector3::from([dimension; 3])];
+        let mut stack = vec![NodeRef::new(&root, bounds)];
+        while let Some(node) = stack.pop() {
+            let data = match node.leaf_data() {
+                Some(data) => *data,
+                None => {
+                    stack.extend(node.children());
+                    continue;
+                }
+            };
+
+            let [leaf_min, leaf_max] = node.bounds();
+            let (leaf_min, leaf_max) = (<[u32; 3]>::from(leaf_min), <[u32; 3]>::from(leaf_max));
+            let clip_min = [0, 1, 2].map(|axis| leaf_min[axis].max(min[axis]));
+            let clip_max = [0, 1, 2].map(|axis| leaf_max[axis].min(max[axis]));
+
+            if is_region(dimension, clip_min, clip_max) {
+                fill(self, Some(data), |cube, dimension| {
+                    classify_box(clip_min, clip_max, cube, dimension)
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{REGION_VERSION, VERSION};
+    use crate::{
+        hash::Crc32,
+        test_utils::{voxels, Voxel, XorShift},
+        CompressionMode, DecodeError, EncodeError, Error, Octree,
+    };
+
+    use alloc::{collections::BTreeSet, vec::Vec};
+    use core::num::NonZeroU32;
+    use std::{
+        io::{self, Cursor, Read, Write},
+        panic::catch_unwind,
+    };
+
+    /// A writer accepting at most a few bytes per call.
+    struct Trickle {
+        bytes: Vec<u8>,
+        calls: usize,
+    }
+
+    impl Write for Trickle {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.calls += 1;
+            let len = buf.len().min(3);
+            self.bytes.extend_from_slice(&buf[..len]);
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// A writer failing after a number of bytes.
+    struct Failing(usize);
+
+    impl Write for Failing {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.0 < buf.len() {
+                return Err(io::Error::other("disk full"));
+            }
+
+            self.0 -= buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn cursor_round_trip() {
+        let mut rng = XorShift::new(0x57e4);
+
+        for _ in 0..10 {
+            let mut octree = rng.octree(32, 2000, 5);
+            octree.lod_down();
+
+            let mut cursor = Cursor::new(Vec::new());
+            octree.encode_to(&mut cursor).unwrap();
+
+            // Anything after the `Octree` is left in the reader.
+            cursor.write_all(b"tail").unwrap();
+            cursor.set_position(0);
+
+            let copy = Octree::<u8>::decode_from(&mut cursor).unwrap();
+            assert_eq!(alloc::format!("{:#?}", copy), alloc::format!("{:#?}", octree));
+
+            let mut tail = Vec::new();
+            cursor.read_to_end(&mut tail).unwrap();
+            assert_eq!(tail, b"tail");
+        }
+    }
+
+    #[test]
+    fn values_of_varying_length_round_trip() {
+        // Few distinct values are written as a palette, and many each in their leaves.
+        let few = voxels(&XorShift::new(0x57ed).octree(16, 300, 3));
+        let many = Octree::from_fn(NonZeroU32::new(8).unwrap(), |[x, y, z]| match (x + y + z) % 3 {
+            0 => Voxel::Air,
+            _ => Voxel::Block((x | y << 3 | z << 6) as u16),
+        })
+        .unwrap();
+
+        for octree in [few, many] {
+            let mut bytes = Vec::new();
+            octree.encode_to(&mut bytes).unwrap();
+
+            let copy = Octree::<Voxel>::decode_from(&mut bytes.as_slice()).unwrap();
+            assert_eq!(alloc::format!("{:#?}", copy), alloc::format!("{:#?}", octree));
+        }
+    }
+
+    #[test]
+    fn nodes_are_encoded_compactly() {
+        let mut rng = XorShift::new(0x57e7);
+        let octree = rng.octree(64, 3000, 5);
+
+        let mut bytes = Vec::new();
+        octree.encode_to(&mut bytes).unwrap();
+
+        // A tag byte and an octant mask per branch, and a tag byte and the data per leaf, with no child
+        // indices, between the header and the checksum of the nodes.
+        let (mut branches, mut leaves) = (0, 0);
+        let mut stack = vec![octree.root()];
+        while let Some(node) = stack.pop() {
+            match node.leaf_data() {
+                Some(_) => leaves += 1,
+                None => branches += 1,
+            }
+            stack.extend(node.children());
+        }
+
+        assert_eq!(bytes.len(), 33 + 2 * branches + 2 * leaves + 4);
+    }
+
+    #[test]
+    fn partial_writes_round_trip() {
+        let mut rng = XorShift::new(0x57e5);
+        let octree = rng.octree(64, 5000, 5);
+
+        let mut expected = Vec::new();
+        octree.encode_to(&mut expected).unwrap();
+        assert!(expected.len() > 4096);
+
+        let mut trickle = Trickle {
+            bytes: Vec::new(),
+            calls: 0,
+        };
+        octree.encode_to(&mut trickle).unwrap();
+
+        assert_eq!(trickle.bytes, expected);
+        assert!(trickle.calls > expected.len() / 3);
+
+        let copy = Octree::<u8>::decode_from(&mut trickle.bytes.as_slice()).unwrap();
+        assert!(copy.equivalent(&octree));
+    }
+
+    #[test]
+    fn io_errors_propagate() {
+        let mut rng = XorShift::new(0x57e6);
+        let octree = rng.octree(64, 5000, 5);
+
+        assert!(matches!(octree.encode_to(&mut Failing(5000)), Err(EncodeError::Io(_))));
+
+        let mut bytes = Vec::new();
+        octree.encode_to(&mut bytes).unwrap();
+
+        for len in [0, 3, 12, bytes.len() / 2, bytes.len() - 1] {
+            let result = Octree::<u8>::decode_from(&mut &bytes[..len]);
+            assert!(matches!(result, Err(DecodeError::Io(error)) if error.kind() == io::ErrorKind::UnexpectedEof));
+        }
+    }
+
+    /// The header fields after the length of the nodes, for uncompressed nodes holding
+    /// their values.
+    const INLINE: &[u8] = &[0, 0, 0, 0, 0, 0];
+
+    /// Encodes a stream of the given version for an `Octree<u8>` from its parts, with valid checksums, ending
+    /// its header with `fields`.
+    fn seal(version: u16, fields: &[u8], dimension: u32, nodes: &[u8]) -> Vec<u8> {
+        let mut header = Vec::new();
+        header.extend_from_slice(&dimension.to_le_bytes());
+        header.extend_from_slice(&1_u32.to_le_bytes());
+        header.push(0);
+        header.extend_from_slice(&(nodes.len() as u64).to_le_bytes());
+        header.extend_from_slice(fields);
+
+        let crc = |bytes: &[u8]| {
+            let mut crc = Crc32::new();
+            crc.update(bytes);
+            crc.finish().to_le_bytes()
+        };
+
+        [
+            b"svoS".as_ref(),
+            &version.to_le_bytes(),
+            &header,
+            &crc(&header),
+            nodes,
+            &crc(nodes),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn malformed_streams_are_rejected() {
+        let mut octree = Octree::<u8>::new_with_background(NonZeroU32::new(2).unwrap(), 7).unwrap();
+        octree.insert([1, 0, 0], 3).unwrap();
+
+        let mut bytes = Vec::new();
+        octree.encode_to(&mut bytes).unwrap();
+
+        let copy = Octree::<u8>::decode_from(&mut bytes.as_slice()).unwrap();
+        assert_eq!(copy.background(), 7);
+        assert!(copy.equivalent(&octree));
+
+        let decode = |bytes: Vec<u8>| Octree::<u8>::decode_from(&mut bytes.as_slice());
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        assert!(matches!(decode(bad_magic), Err(DecodeError::Malformed(_))));
+
+        let valid = [1, 0b10, 0, 3];
+        assert!(decode(seal(VERSION, INLINE, 2, &valid)).is_ok());
+        assert!(matches!(
+            decode(seal(VERSION, INLINE, 3, &valid)),
+            Err(DecodeError::Octree(Error::InvalidDimension(3)))
+        ));
+
+        let cases: [(&[u8], &str); 4] = [
+            (&[1, 0b10, 1, 1, 0, 1], "branch of a single voxel"),
+            (&[9], "unknown node"),
+            (&[1, 0b11, 0, 1], "missing nodes"),
+            (&[0, 1, 0, 1], "trailing nodes"),
+        ];
+
+        for (nodes, reason) in cases.iter() {
+            assert!(matches!(decode(seal(VERSION, INLINE, 2, nodes)), Err(DecodeError::Malformed(r)) if r == *reason));
+        }
+    }
+
+    #[test]
+    fn versions_are_checked() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(2).unwrap()).unwrap();
+        octree.insert([1, 0, 0], 3).unwrap();
+
+        let mut bytes = Vec::new();
+        octree.encode_to(&mut bytes).unwrap();
+        assert_eq!(&bytes[4..6], &VERSION.to_le_bytes());
+
+        for version in [0, VERSION + 1, 0xffff] {
+            bytes[4..6].copy_from_slice(&version.to_le_bytes());
+            assert!(matches!(
+                Octree::<u8>::decode_from(&mut bytes.as_slice()),
+                Err(DecodeError::Octree(Error::UnsupportedVersion(v))) if v == version as u32
+            ));
+        }
+    }
+
+    #[test]
+    fn corruption_is_reported_as_checksum_mismatch() {
+        let mut rng = XorShift::new(0x57e9);
+        let octree = rng.octree(16, 300, 5);
+
+        let mut bytes = Vec::new();
+        octree.encode_to(&mut bytes).unwrap();
+
+        // Everything after the magic and the version is covered by a checksum.
+        for i in 6..bytes.len() {
+            for flip in [0x01, 0x80, 0xff] {
+                let mut corrupt = bytes.clone();
+                corrupt[i] ^= flip;
+
+                let result = Octree::<u8>::decode_from(&mut corrupt.as_slice());
+                assert!(matches!(
+                    result,
+                    Err(DecodeError::Octree(Error::ChecksumMismatch { .. }))
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn corrupt_streams_never_panic() {
+        let octree = XorShift::new(0x57e8).octree(8, 40, 5);
+        assert_corruption_never_panics(&octree, CompressionMode::None);
+    }
+
+    /// Decodes every truncation of `octree` encoded with `mode`, and every copy with one byte flipped, checking
+    /// that none panics, that every truncation fails, and that any flipped copy decoding holds the same nodes.
+    fn assert_corruption_never_panics(octree: &Octree<u8>, mode: CompressionMode) {
+        let mut bytes = Vec::new();
+        octree.encode_to_with(&mut bytes, mode).unwrap();
+
+        for len in 0..bytes.len() {
+            let result = catch_unwind(|| Octree::<u8>::decode_from(&mut &bytes[..len]).is_err());
+            assert!(matches!(result, Ok(true)));
+        }
+
+        for i in 0..bytes.len() {
+            for flip in [0x01, 0x80, 0xff] {
+                let mut corrupt = bytes.clone();
+                corrupt[i] ^= flip;
+                let result = catch_unwind(|| Octree::<u8>::decode_from(&mut corrupt.as_slice()));
+
+                // A corrupt match may still copy the same bytes from elsewhere, leaving the nodes intact.
+                if let Ok(copy) = result.unwrap() {
+                    assert_eq!(copy.content_hash(), octree.content_hash());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_compression_is_rejected() {
+        let nodes = [1, 0b10, 0, 3];
+        assert!(Octree::<u8>::decode_from(&mut seal(VERSION, INLINE, 2, &nodes).as_slice()).is_ok());
+
+        let flag = if cfg!(feature = "compression") { 2 } else { 1 };
+        assert!(matches!(
+            Octree::<u8>::decode_from(&mut seal(VERSION, &[flag, 0, 0, 0, 0, 0], 2, &nodes).as_slice()),
+            Err(DecodeError::UnsupportedCompression(f)) if f == flag
+        ));
+    }
+
+    #[test]
+    fn leaf_values_are_indexed_in_a_palette() {
+        let mut rng = XorShift::new(0x57ed);
+
+        // The number of distinct values, the number of voxels written, and the bytes per palette index, if
+        // leaves are indexed.
+        let cases = [
+            (1, 2000, Some(1)),
+            (3, 2000, Some(1)),
+            (3000, 30000, Some(2)),
+            (3000, 3000, None),
+        ];
+
+        for &(distinct, inserts, width) in cases.iter() {
+            let mut octree = Octree::<u32>::new(NonZeroU32::new(64).unwrap()).unwrap();
+            for i in 0..inserts {
+                octree.insert(rng.position(64), 0xdead_0000 + i % distinct).unwrap();
+            }
+
+            let (mut branches, mut leaves, mut values) = (0, 0, BTreeSet::new());
+            let mut stack = vec![octree.root()];
+            while let Some(node) = stack.pop() {
+                match node.leaf_data() {
+                    Some(data) => {
+                        leaves += 1;
+                        values.insert(*data);
+                    }
+                    None => branches += 1,
+                }
+                stack.extend(node.children());
+            }
+
+            let mut bytes = Vec::new();
+            octree.encode_to(&mut bytes).unwrap();
+
+            let copy = Octree::<u32>::decode_from(&mut bytes.as_slice()).unwrap();
+            assert_eq!(alloc::format!("{:#?}", copy), alloc::format!("{:#?}", octree));
+
+            // The header holds the width of the indices and the size of the palette, which precedes the nodes.
+            let inline = 36 + 2 * branches + 5 * leaves + 4;
+            match width {
+                Some(width) => {
+                    assert_eq!(bytes[27], width as u8);
+                    assert_eq!(
+                        bytes.len(),
+                        36 + 4 * values.len() + 2 * branches + (1 + width) * leaves + 4
+                    );
+                    assert!(bytes.len() < inline);
+                }
+                None => {
+                    assert_eq!(bytes[27], 0);
+                    assert_eq!(bytes.len(), inline);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn palettes_are_checked() {
+        let decode =
+            |fields: &[u8], nodes: &[u8]| Octree::<u8>::decode_from(&mut seal(VERSION, fields, 2, nodes).as_slice());
+
+        // A palette of one value, indexed in one byte.
+        let fields = [0, 1, 1, 0, 0, 0];
+        let copy = decode(&fields, &[3, 1, 0b10, 0, 0]).unwrap();
+        assert_eq!(copy.get([1, 0, 0]), Some(&3));
+
+        assert!(matches!(
+            decode(&fields, &[3, 1, 0b10, 0, 1]),
+            Err(DecodeError::Malformed("palette index out of range"))
+        ));
+        assert!(matches!(
+            decode(&fields, &[3]),
+            Err(DecodeError::Malformed("missing nodes"))
+        ));
+
+        for fields in [[0, 5, 1, 0, 0, 0], [0, 1, 0, 0, 0, 0], [0, 0, 1, 0, 0, 0]].iter() {
+            assert!(matches!(
+                decode(fields, &[3, 1, 0b10, 0, 0]),
+                Err(DecodeError::Malformed("invalid palette"))
+            ));
+        }
+    }
+
+    #[test]
+    fn regions_round_trip() {
+        let mut rng = XorShift::new(0x57ea);
+
+        for _ in 0..20 {
+            let octree = rng.octree(32, 1500, 5);
+            let a = rng.position(32);
+            let b = rng.position(32);
+            let min = [0, 1, 2].map(|axis| a[axis].min(b[axis]));
+            let max = [0, 1, 2].map(|axis| a[axis].max(b[axis]) + 1);
+
+            let bytes = octree.encode_region(min, max).unwrap();
+
+            let mut copy = octree.at_lod(0);
+            copy.clear_region(min, max);
+            copy.decode_region_into(&bytes).unwrap();
+
+            let mut other = rng.octree(32, 500, 5);
+            let before = other.at_lod(0);
+            other.decode_region_into(&bytes).unwrap();
+
+            for position in (0..32 * 32 * 32).map(|i| [i % 32, i / 32 % 32, i / 1024]) {
+                assert_eq!(copy.get(position), octree.get(position));
+
+                let inside = (0..3).all(|axis| position[axis] >= min[axis] && position[axis] < max[axis]);
+                if inside {
+                    assert_eq!(other.get(position), octree.get(position));
+                } else {
+                    assert_eq!(other.get(position), before.get(position));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn regions_encode_only_their_nodes() {
+        let mut rng = XorShift::new(0x57eb);
+        let octree = rng.octree(128, 20_000, 5);
+
+        let mut whole = Vec::new();
+        octree.encode_to(&mut whole).unwrap();
+
+        let corner = octree.encode_region([0, 0, 0], [8, 8, 8]).unwrap();
+        assert!(corner.len() * 50 < whole.len());
+
+        // The whole `Octree` as a region holds the same nodes, after a header holding the box instead of the
+        // LOD level, background and compression mode.
+        let everything = octree.encode_region([0, 0, 0], [128, 128, 128]).unwrap();
+        assert_eq!(everything.len(), whole.len() + 18);
+
+        // A leaf larger than the box is encoded whole, and written only inside the box.
+        let mut filled = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+        filled.insert_sphere([16.0, 16.0, 16.0], 30.0, 3, true).unwrap();
+        let bytes = filled.encode_region([1, 2, 3], [4, 5, 6]).unwrap();
+
+        let mut empty = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+        empty.decode_region_into(&bytes).unwrap();
+        assert!(matches!(empty.get([1, 2, 3]), Some(3)));
+        assert!(matches!(empty.get([3, 4, 5]), Some(3)));
+        assert_eq!(empty.get([4, 4, 5]), Some(&0));
+        assert_eq!(empty.get([0, 2, 3]), Some(&0));
+    }
+
+    #[test]
+    fn invalid_regions_are_rejected() {
+        let mut rng = XorShift::new(0x57ec);
+        let octree = rng.octree(16, 300, 5);
+
+        for (min, max) in [([0, 0, 0], [0, 4, 4]), ([4, 4, 4], [2, 8, 8]), ([0, 0, 0], [17, 4, 4])] {
+            assert!(matches!(
+                octree.encode_region(min, max),
+                Err(EncodeError::Octree(Error::OutOfBounds))
+            ));
+        }
+
+        let bytes = octree.encode_region([2, 3, 4], [10, 11, 12]).unwrap();
+        let mut copy = octree.at_lod(0);
+        copy.insert([5, 5, 5], 9).unwrap();
+
+        let mut other = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+        assert!(matches!(
+            other.decode_region_into(&bytes),
+            Err(DecodeError::Octree(Error::DimensionMismatch {
+                expected: 32,
+                found: 16
+            }))
+        ));
+
+        // Everything after the magic and the version is covered by a checksum.
+        for i in 6..bytes.len() {
+            let mut corrupt = bytes.clone();
+            corrupt[i] ^= 0x10;
+            assert!(matches!(
+                copy.decode_region_into(&corrupt),
+                Err(DecodeError::Octree(Error::ChecksumMismatch { .. }))
+            ));
+        }
+
+        for len in 0..bytes.len() {
+            assert!(copy.decode_region_into(&bytes[..len]).is_err());
+        }
+
+        let mut long = bytes.clone();
+        long.push(0);
+        assert!(matches!(
+            copy.decode_region_into(&long),
+            Err(DecodeError::Malformed("trailing bytes"))
+        ));
+
+        let mut newer = bytes.clone();
+        newer[4..6].copy_from_slice(&(REGION_VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            copy.decode_region_into(&newer),
+            Err(DecodeError::Octree(Error::UnsupportedVersion(_)))
+        ));
+
+        // Failed decodes leave the `Octree` unchanged.
+        assert!(matches!(copy.get([5, 5, 5]), Some(9)));
+
+        let mut whole = Vec::new();
+        octree.encode_to(&mut whole).unwrap();
+        assert!(matches!(
+            copy.decode_region_into(&whole),
+            Err(DecodeError::Malformed("missing header"))
+        ));
+    }
+
+    #[cfg(feature = "compression")]
+    mod compression {
+        use super::*;
+
+        use core::convert::TryInto;
+
+        /// Returns an `Octree` of solid ground below a noisy surface, with scattered voxels above it.
+        fn terrain(rng: &mut XorShift) -> Octree<u8> {
+            let mut octree = Octree::<u8>::new(NonZeroU32::new(64).unwrap()).unwrap();
+
+            for x in 0..64 {
+                for z in 0..64 {
+                    let height = 20 + x / 8 + z / 16 + rng.below(2);
+                    for y in 0..height {
+                        octree.insert([x, y, z], if y + 3 < height { 1 } else { 2 }).unwrap();
+                    }
+                }
+            }
+
+            for _ in 0..4000 {
+                let position = rng.position(64);
+                octree.insert(position, 3).unwrap();
+            }
+
+            octree
+        }
+
+        #[test]
+        fn lz4_round_trip() {
+            let mut rng = XorShift::new(0x57ea);
+            let octree = terrain(&mut rng);
+
+            let mut plain = Vec::new();
+            octree.encode_to(&mut plain).unwrap();
+
+            let mut compressed = Cursor::new(Vec::new());
+            octree.encode_to_with(&mut compressed, CompressionMode::Lz4).unwrap();
+            compressed.write_all(b"tail").unwrap();
+            compressed.set_position(0);
+
+            let copy = Octree::<u8>::decode_from(&mut compressed).unwrap();
+            assert_eq!(alloc::format!("{:#?}", copy), alloc::format!("{:#?}", octree));
+
+            let mut tail = Vec::new();
+            compressed.read_to_end(&mut tail).unwrap();
+            assert_eq!(tail, b"tail");
+
+            let len = compressed.into_inner().len() - 4;
+            assert!(plain.len() > 64 * 1024);
+            assert!(len * 2 < plain.len(), "{} compressed to {}", plain.len(), len);
+        }
+
+        #[test]
+        fn blocks_cannot_expand_past_declared_length() {
+            let mut rng = XorShift::new(0x57eb);
+            let octree = rng.octree(16, 300, 5);
+
+            let mut bytes = Vec::new();
+            octree.encode_to_with(&mut bytes, CompressionMode::Lz4).unwrap();
+
+            // The first block follows the header, starting with its uncompressed length.
+            let declared = u32::from_le_bytes(bytes[33..37].try_into().unwrap());
+            for len in [declared + 1, u32::MAX] {
+                bytes[33..37].copy_from_slice(&len.to_le_bytes());
+                let result = Octree::<u8>::decode_from(&mut bytes.as_slice());
+                assert!(matches!(result, Err(DecodeError::Io(error)) if error.kind() == io::ErrorKind::InvalidData));
+            }
+        }
+
+        #[test]
+        fn corrupt_blocks_never_panic() {
+            let octree = XorShift::new(0x57ec).octree(8, 40, 5);
+            assert_corruption_never_panics(&octree, CompressionMode::Lz4);
+        }
+    }
+}
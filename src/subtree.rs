@@ -0,0 +1,890 @@
+use crate::{
+    codec::read_value,
+    hash::Crc32,
+    node::{octant_bounds, Bounds, NodeSlot, OCTREE_CHILDREN},
+    paged::Reader,
+    Error, Node, NodeRef, Octree, ValueCodec, Vector3,
+};
+
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::{convert::TryInto, fmt::Debug, hash::Hash, num::NonZeroU32};
+
+/// Starts every blob, followed by the version.
+const MAGIC: &[u8; 4] = b"svoT";
+/// The version of the blob encoding, bumped whenever its layout changes.
+const VERSION: u16 = 1;
+/// The size of the checksum ending every blob.
+const CRC: usize = 4;
+
+const LEAF: u8 = 0;
+const BRANCH: u8 = 1;
+const UNLOADED: u8 = 2;
+
+/// The blobs of an `Octree`, keyed by the path to the root of each.
+type Blobs = BTreeMap<Vec<u8>, Vec<u8>>;
+
+/// A subtree held in storage rather than in memory, as written by [`Octree::encode_subtrees`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SubtreeRef {
+    /// The checksum of the blob holding the subtree, which the blob fetched for it must match.
+    crc: u32,
+}
+
+/// The path from the root of an `Octree` to one of its `Node`s, as the octant taken at each level.
+///
+/// Octant `i` covers the upper half of its parent along x if bit 0 of `i` is set, along z if bit 1 is set, and
+/// along y if bit 2 is set.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodePath(Vec<u8>);
+
+impl NodePath {
+    /// Returns the path to the root of an `Octree`.
+    pub fn root() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Creates a path from the octant taken at each level, each of which must be below 8.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, NodePath};
+    /// #
+    /// assert_eq!(NodePath::new(&[3, 7]).unwrap().octants(), &[3, 7]);
+    /// assert_eq!(NodePath::new(&[3, 8]), Err(Error::InvalidOctant(8)));
+    /// ```
+    pub fn new(octants: &[u8]) -> Result<Self, Error> {
+        match octants.iter().find(|octant| **octant as usize >= OCTREE_CHILDREN) {
+            Some(octant) => Err(Error::InvalidOctant(*octant as usize)),
+            None => Ok(Self(octants.to_vec())),
+        }
+    }
+
+    /// Returns the path to the given octant of the `Node` at this path.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, NodePath};
+    /// #
+    /// let path = NodePath::root().child(3).unwrap().child(7).unwrap();
+    /// assert_eq!(path, NodePath::new(&[3, 7]).unwrap());
+    /// ```
+    pub fn child(&self, octant: u8) -> Result<Self, Error> {
+        if octant as usize >= OCTREE_CHILDREN {
+            return Err(Error::InvalidOctant(octant as usize));
+        }
+
+        let mut path = self.clone();
+        path.0.push(octant);
+        Ok(path)
+    }
+
+    /// Returns the octant taken at each level.
+    pub fn octants(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Returns the bounds of the `Node` at this path in an `Octree` of the given dimension, or
+    /// [`Error::InvalidDimension`] if the path leads below a single voxel.
+    fn bounds(&self, dimension: u32) -> Result<Bounds, Error> {
+        let mut bounds = [Vector3::from([0, 0, 0]), Vector3::from([dimension; 3])];
+
+        for octant in self.0.iter() {
+            if bounds[1].x - bounds[0].x < 2 {
+                return Err(Error::InvalidDimension(0));
+            }
+
+            bounds = octant_bounds(bounds)[*octant as usize];
+        }
+
+        Ok(bounds)
+    }
+}
+
+/// Storage holding the blobs written by [`Octree::encode_subtrees`], from which subtrees are loaded as they are
+/// touched.
+///
+/// Closures taking the path of a blob are sources.
+pub trait SubtreeSource {
+    /// Returns the blob of the subtree at `path`, the octants leading to it from the root in order, or `None` if
+    /// there is none. The blob of the root is at the empty path.
+    fn fetch(&mut self, path: &[u8]) -> Option<Vec<u8>>;
+}
+
+impl<F> SubtreeSource for F
+where
+    F: FnMut(&[u8]) -> Option<Vec<u8>>,
+{
+    fn fetch(&mut self, path: &[u8]) -> Option<Vec<u8>> {
+        self(path)
+    }
+}
+
+/// Appends the nodes of `node`, `level` levels below the root of its blob, to `bytes` in pre-order, writing
+/// every branch `depth` levels down to a blob of its own.
+fn write_nodes<T>(
+    node: NodeRef<'_, T>,
+    level: u32,
+    depth: u32,
+    path: &mut Vec<u8>,
+    bytes: &mut Vec<u8>,
+    blobs: &mut Blobs,
+) where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + ValueCodec,
+{
+    if let Some(data) = node.leaf_data() {
+        bytes.push(LEAF);
+        data.write(bytes);
+    } else if level == depth {
+        let blob = write_blob(node, depth, path, &node.dimension().to_le_bytes(), blobs);
+        bytes.push(UNLOADED);
+        bytes.extend_from_slice(&blob[blob.len() - CRC..]);
+        blobs.insert(path.clone(), blob);
+    } else {
+        let mut mask = 0;
+        for (i, (_, child)) in node.octants().enumerate() {
+            if child.is_some() {
+                mask |= 1 << i;
+            }
+        }
+
+        bytes.push(BRANCH);
+        bytes.push(mask);
+
+        for (i, (_, child)) in node.octants().enumerate() {
+            if let Some(child) = child {
+                path.push(i as u8);
+                write_nodes(child, level + 1, depth, path, bytes, blobs);
+                path.pop();
+            }
+        }
+    }
+}
+
+/// Encodes `node`, at `path`, as a blob of its own after `header`, adding the blobs below it to `blobs`.
+fn write_blob<T>(node: NodeRef<'_, T>, depth: u32, path: &mut Vec<u8>, header: &[u8], blobs: &mut Blobs) -> Vec<u8>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + ValueCodec,
+{
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(header);
+    write_nodes(node, 0, depth, path, &mut bytes, blobs);
+
+    let mut crc = Crc32::new();
+    crc.update(&bytes);
+    bytes.extend_from_slice(&crc.finish().to_le_bytes());
+    bytes
+}
+
+/// Checks the checksum and version of a blob, returning a reader over what lies between them.
+///
+/// A subtree blob must also match the checksum its parent recorded for it.
+fn open_blob(bytes: &[u8], reference: Option<SubtreeRef>) -> Result<Reader<'_>, Error> {
+    let len = bytes.len().checked_sub(CRC).ok_or(Error::InvalidEncoding)?;
+    let (body, stored) = bytes.split_at(len);
+
+    let mut crc = Crc32::new();
+    crc.update(body);
+    let actual = crc.finish();
+
+    let stored = u32::from_le_bytes(stored.try_into().unwrap());
+    for expected in core::iter::once(stored).chain(reference.map(|reference| reference.crc)) {
+        if expected != actual {
+            return Err(Error::ChecksumMismatch { expected, actual });
+        }
+    }
+
+    let mut reader = Reader::new(body);
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(Error::InvalidEncoding);
+    }
+
+    let version = reader.u16()?;
+    if version != VERSION {
+        return Err(Error::UnsupportedVersion(version as u32));
+    }
+
+    Ok(reader)
+}
+
+/// Reads the child of an octant of the given dimension.
+fn read_slot<T>(reader: &mut Reader<'_>, dimension: u32) -> Result<NodeSlot<T>, Error>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash + ValueCodec,
+{
+    let single = dimension < 2;
+
+    match reader.u8()? {
+        LEAF => Ok(NodeSlot::Loaded(Node::leaf(read_value(reader)?))),
+        BRANCH if !single => {
+            let mask = reader.u8()?;
+            let mut children: [NodeSlot<T>; OCTREE_CHILDREN] = Default::default();
+
+            for (i, child) in children.iter_mut().enumerate() {
+                if mask & (1 << i) != 0 {
+                    *child = read_slot(reader, dimension / 2)?;
+                }
+            }
+
+            Ok(NodeSlot::Loaded(Node::from_slots(children)))
+        }
+        UNLOADED if !single => Ok(NodeSlot::Unloaded(SubtreeRef { crc: reader.u32()? })),
+        _ => Err(Error::InvalidEncoding),
+    }
+}
+
+/// Reads the `Node` of the given dimension at the root of a blob, which must hold nothing after it.
+fn read_root<T>(reader: &mut Reader<'_>, dimension: u32) -> Result<Node<T>, Error>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash + ValueCodec,
+{
+    match read_slot(reader, dimension)? {
+        NodeSlot::Loaded(node) if reader.is_empty() => Ok(node),
+        _ => Err(Error::InvalidEncoding),
+    }
+}
+
+/// Reads the `Node` of the given dimension from a blob holding it alone, after its dimension.
+fn read_subtree<T>(reader: &mut Reader<'_>, expected: u32) -> Result<Node<T>, Error>
+where
+    T: Debug + Default + Eq + PartialEq + Clone + Copy + Hash + ValueCodec,
+{
+    let found = reader.u32()?;
+
+    if found != expected {
+        return Err(Error::DimensionMismatch { expected, found });
+    }
+
+    read_root(reader, expected)
+}
+
+/// Loads every subtree held in storage on the way from `root`, which has the given bounds, to `position` from
+/// `source`.
+fn load<T, S>(root: &mut Node<T>, bounds: Bounds, position: Vector3<u32>, source: &mut S) -> Result<(), Error>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + ValueCodec,
+    S: SubtreeSource + ?Sized,
+{
+    let (mut node, mut bounds) = (root, bounds);
+    let mut path = Vec::new();
+
+    while let Some((child_bounds, octant, slot)) = node.slot_at_mut(bounds, position) {
+        path.push(octant as u8);
+
+        if let NodeSlot::Unloaded(reference) = *slot {
+            let bytes = source.fetch(&path).ok_or(Error::SubtreeNotLoaded)?;
+            let mut reader = open_blob(&bytes, Some(reference))?;
+            *slot = NodeSlot::Loaded(read_subtree(&mut reader, child_bounds[1].x - child_bounds[0].x)?);
+        }
+
+        match slot {
+            NodeSlot::Loaded(child) => {
+                node = child;
+                bounds = child_bounds;
+            }
+            _ => break,
+        }
+    }
+
+    Ok(())
+}
+
+/// An `Octree` whose subtrees held in storage are loaded from a [`SubtreeSource`] as they are touched, returned
+/// by [`Octree::with_source`].
+pub struct SourcedOctree<'a, T, S>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + ValueCodec,
+    S: SubtreeSource + ?Sized,
+{
+    octree: &'a mut Octree<T>,
+    source: &'a mut S,
+}
+
+impl<'a, T, S> SourcedOctree<'a, T, S>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + ValueCodec,
+    S: SubtreeSource + ?Sized,
+{
+    /// Retrieves data from the given position, as [`Octree::get`] does, first loading the subtrees holding it.
+    ///
+    /// Returns [`Error::SubtreeNotLoaded`] if the source holds no blob for one of them.
+    pub fn get(&mut self, position: impl Into<Vector3<u32>>) -> Result<Option<&T>, Error> {
+        let position = <[u32; 3]>::from(position.into());
+        self.load(position)?;
+        Ok(self.octree.get(position))
+    }
+
+    /// Inserts data at the given position, as [`Octree::insert`] does, first loading the subtrees holding it.
+    pub fn insert(&mut self, position: impl Into<Vector3<u32>>, data: T) -> Result<(), Error> {
+        let position = <[u32; 3]>::from(position.into());
+        self.load(position)?;
+        self.octree.insert(position, data)
+    }
+
+    /// Removes the `Node` at the given position, as [`Octree::clear_at`] does, first loading the subtrees
+    /// holding it.
+    pub fn clear_at(&mut self, position: impl Into<Vector3<u32>>) -> Result<(), Error> {
+        let position = <[u32; 3]>::from(position.into());
+        self.load(position)?;
+        self.octree.clear_at(position)
+    }
+
+    fn load(&mut self, position: [u32; 3]) -> Result<(), Error> {
+        let bounds = self.octree.bounds();
+        load(self.octree.root_to_load(), bounds, position.into(), self.source)
+    }
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash + ValueCodec,
+{
+    /// Encodes the `Octree` as blobs which can be stored and loaded separately, keyed by the path to the root of
+    /// each: the octants leading to it from the root of the `Octree`, in order.
+    ///
+    /// The blob at the empty path holds the dimension, LOD level and background of the `Octree` along with its
+    /// top `depth` levels. Every branch below them is written to a blob of its own, holding its dimension and its
+    /// own top `depth` levels, and so on. Each blob ends with its CRC-32 checksum, which the blob above it also
+    /// records. Subtrees which have not been loaded, and journaled LOD detail, are not encoded.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([1, 2, 3], 4).unwrap();
+    ///
+    /// // The root blob holds the levels of dimension 32 and 16, and the blobs below it two levels each.
+    /// let blobs = octree.encode_subtrees(NonZeroU32::new(2).unwrap());
+    /// assert_eq!(blobs.len(), 3);
+    /// assert!(blobs.contains_key(&[][..]));
+    /// ```
+    pub fn encode_subtrees(&self, depth: NonZeroU32) -> BTreeMap<Vec<u8>, Vec<u8>> {
+        let mut header = Vec::new();
+        header.extend_from_slice(&self.dimension().to_le_bytes());
+        header.extend_from_slice(&self.lod_level().to_le_bytes());
+        self.background().write(&mut header);
+
+        let mut blobs = BTreeMap::new();
+        let root = write_blob(self.root(), depth.get(), &mut Vec::new(), &header, &mut blobs);
+        blobs.insert(Vec::new(), root);
+        blobs
+    }
+
+    /// Opens an `Octree` encoded by [`Octree::encode_subtrees`], fetching only the blob at the empty path from
+    /// `source`.
+    ///
+    /// Every other subtree is left in storage. Such subtrees read as `None`, and writes reaching them fail
+    /// with [`Error::SubtreeNotLoaded`], until they are loaded through [`Octree::with_source`].
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([1, 2, 3], 4).unwrap();
+    ///
+    /// let blobs = octree.encode_subtrees(NonZeroU32::new(2).unwrap());
+    /// let mut source = |path: &[u8]| blobs.get(path).cloned();
+    ///
+    /// let mut copy = Octree::<u8>::open_subtrees(&mut source).unwrap();
+    /// assert_eq!(copy.get([1, 2, 3]), None);
+    /// assert_eq!(copy.insert([1, 2, 2], 4), Err(Error::SubtreeNotLoaded));
+    ///
+    /// assert_eq!(copy.with_source(&mut source).get([1, 2, 3]), Ok(Some(&4)));
+    /// assert!(copy.equivalent(&octree));
+    /// ```
+    pub fn open_subtrees<S>(source: &mut S) -> Result<Self, Error>
+    where
+        S: SubtreeSource + ?Sized,
+    {
+        let bytes = source.fetch(&[]).ok_or(Error::SubtreeNotLoaded)?;
+        let mut reader = open_blob(&bytes, None)?;
+
+        let dimension = reader.u32()?;
+        let lod_level = reader.u32()?;
+        let background = read_value(&mut reader)?;
+
+        if !dimension.is_power_of_two() {
+            return Err(Error::InvalidDimension(dimension));
+        }
+
+        let root = read_root(&mut reader, dimension)?;
+        Octree::from_root(dimension, root, background, lod_level)
+    }
+
+    /// Encodes the `Node` at `path` alone, as a blob which [`Octree::import_subtree`] splices back in.
+    ///
+    /// A path leading into a leaf, or into space which was never written, encodes a leaf holding its data.
+    /// Returns [`Error::InvalidDimension`] if the path leads below a single voxel, and
+    /// [`Error::SubtreeNotLoaded`] if any of the `Node` is held in storage.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, NodePath, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([1, 2, 3], 4).unwrap();
+    ///
+    /// let path = NodePath::new(&[0]).unwrap();
+    /// let bytes = octree.export_subtree(&path).unwrap();
+    ///
+    /// octree.clear_at([1, 2, 3]).unwrap();
+    /// octree.import_subtree(&path, &bytes).unwrap();
+    /// assert_eq!(octree.get([1, 2, 3]), Some(&4));
+    /// ```
+    pub fn export_subtree(&self, path: &NodePath) -> Result<Vec<u8>, Error> {
+        let bounds = path.bounds(self.dimension())?;
+        let mut node = self.root();
+
+        for octant in path.octants().iter().map(|octant| *octant as usize) {
+            // An octant whose child is held, but not in memory, is held in storage.
+            node = match (node.child(octant), node.occupancy() & 1 << octant != 0) {
+                _ if node.is_leaf() => break,
+                (Some(child), _) => child,
+                (None, false) => break,
+                (None, true) => return Err(Error::SubtreeNotLoaded),
+            };
+        }
+
+        // Stopping short of the path leaves either the leaf holding it, or the branch without it.
+        let leaf;
+        if node.dimension() != bounds[1].x - bounds[0].x {
+            leaf = Node::leaf(node.leaf_data().copied().unwrap_or_else(|| self.background()));
+            node = NodeRef::new(&leaf, bounds);
+        }
+
+        if !node.is_loaded() {
+            return Err(Error::SubtreeNotLoaded);
+        }
+
+        let header = node.dimension().to_le_bytes();
+        Ok(write_blob(
+            node,
+            u32::MAX,
+            &mut path.0.clone(),
+            &header,
+            &mut BTreeMap::new(),
+        ))
+    }
+
+    /// Replaces the `Node` at `path` with the one encoded by [`Octree::export_subtree`] in `bytes`, simplifying
+    /// the `Node`s above it.
+    ///
+    /// Returns [`Error::DimensionMismatch`] if the encoded `Node` has a different dimension than the path
+    /// implies, and [`Error::SubtreeNotLoaded`] if a subtree above the path is held in storage. The contents of
+    /// the `Octree` are unchanged on error.
+    pub fn import_subtree(&mut self, path: &NodePath, bytes: &[u8]) -> Result<(), Error> {
+        let bounds = path.bounds(self.dimension())?;
+        let mut reader = open_blob(bytes, None)?;
+        let node = read_subtree(&mut reader, bounds[1].x - bounds[0].x)?;
+
+        let (octree_bounds, background) = (self.bounds(), self.background());
+        self.root_mut().graft(octree_bounds, node, bounds, background)
+    }
+
+    /// Returns a handle to the `Octree` which loads the subtrees held in storage from `source` as they are
+    /// touched.
+    ///
+    /// Only the subtrees on the way to each position read or written are fetched. Loaded subtrees stay in
+    /// memory.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    /// octree.insert([1, 2, 3], 4).unwrap();
+    ///
+    /// let blobs = octree.encode_subtrees(NonZeroU32::new(1).unwrap());
+    /// let mut fetched = 0;
+    /// let mut source = |path: &[u8]| {
+    ///     fetched += 1;
+    ///     blobs.get(path).cloned()
+    /// };
+    ///
+    /// let mut copy = Octree::<u8>::open_subtrees(&mut source).unwrap();
+    /// copy.with_source(&mut source).insert([1, 2, 2], 4).unwrap();
+    /// assert_eq!(copy.get([1, 2, 3]), Some(&4));
+    /// assert_eq!(fetched, blobs.len());
+    /// ```
+    pub fn with_source<'a, S>(&'a mut self, source: &'a mut S) -> SourcedOctree<'a, T, S>
+    where
+        S: SubtreeSource + ?Sized,
+    {
+        SourcedOctree { octree: self, source }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Blobs, VERSION};
+    use crate::{hash::Crc32, node::octant_bounds, test_utils::XorShift, Error, NodePath, Octree, Vector3};
+
+    use alloc::vec::Vec;
+    use core::num::NonZeroU32;
+    use hashbrown::HashMap;
+    use std::panic::catch_unwind;
+
+    /// An in-memory source, recording the path of every blob fetched from it.
+    struct Source {
+        blobs: HashMap<Vec<u8>, Vec<u8>>,
+        fetched: Vec<Vec<u8>>,
+    }
+
+    impl Source {
+        fn new(blobs: Blobs) -> Self {
+            Self {
+                blobs: blobs.into_iter().collect(),
+                fetched: Vec::new(),
+            }
+        }
+    }
+
+    impl super::SubtreeSource for Source {
+        fn fetch(&mut self, path: &[u8]) -> Option<Vec<u8>> {
+            self.fetched.push(path.to_vec());
+            self.blobs.get(path).cloned()
+        }
+    }
+
+    /// Returns whether the subtree at `path` in an `Octree` of the given dimension holds `position`.
+    fn holds(path: &[u8], dimension: u32, position: [u32; 3]) -> bool {
+        let mut bounds = [Vector3::from([0, 0, 0]), Vector3::from([dimension; 3])];
+        for octant in path {
+            bounds = octant_bounds(bounds)[*octant as usize];
+        }
+
+        let [min, max]: [[u32; 3]; 2] = [bounds[0].into(), bounds[1].into()];
+        (0..3).all(|i| min[i] <= position[i] && position[i] < max[i])
+    }
+
+    #[test]
+    fn subtrees_round_trip() {
+        let mut rng = XorShift::new(0x5b7e);
+
+        for depth in 1..5 {
+            let mut octree = rng.octree(32, 2000, 5);
+            octree.lod_down();
+
+            let blobs = octree.encode_subtrees(NonZeroU32::new(depth).unwrap());
+            let mut source = Source::new(blobs);
+
+            let mut copy = Octree::<u8>::open_subtrees(&mut source).unwrap();
+            assert_eq!(copy.lod_level(), octree.lod_level());
+
+            let mut handle = copy.with_source(&mut source);
+            for x in 0..32 {
+                for y in 0..32 {
+                    for z in 0..32 {
+                        assert_eq!(handle.get([x, y, z]).unwrap(), octree.get([x, y, z]));
+                    }
+                }
+            }
+
+            assert_eq!(alloc::format!("{:#?}", copy), alloc::format!("{:#?}", octree));
+            assert_eq!(source.fetched.len(), source.blobs.len());
+        }
+    }
+
+    #[test]
+    fn only_touched_paths_are_fetched() {
+        let mut rng = XorShift::new(0x5b7f);
+        let mut octree = rng.octree(32, 3000, 5);
+
+        let blobs = octree.encode_subtrees(NonZeroU32::new(1).unwrap());
+        let mut source = Source::new(blobs.clone());
+        let mut copy = Octree::<u8>::open_subtrees(&mut source).unwrap();
+        let mut expected = vec![Vec::<u8>::new()];
+        assert_eq!(source.fetched, expected);
+
+        for _ in 0..20 {
+            let position = rng.position(32);
+            let mut touched = blobs
+                .keys()
+                .filter(|path| !path.is_empty() && holds(path, 32, position))
+                .filter(|path| !expected.contains(*path))
+                .cloned()
+                .collect::<Vec<_>>();
+            touched.sort_by_key(|path| path.len());
+            expected.extend(touched);
+
+            let data = rng.below(5) as u8;
+            octree.insert(position, data).unwrap();
+            copy.with_source(&mut source).insert(position, data).unwrap();
+
+            assert_eq!(source.fetched, expected);
+            assert!(source.fetched.len() < blobs.len());
+        }
+
+        // Writes reaching subtrees which have not been loaded fail without a source.
+        let path = blobs.keys().find(|path| !expected.contains(*path)).unwrap();
+        let position = (0..).map(|_| rng.position(32)).find(|p| holds(path, 32, *p)).unwrap();
+        assert_eq!(copy.insert(position, 1), Err(Error::SubtreeNotLoaded));
+        assert_eq!(copy.clear_at(position), Err(Error::SubtreeNotLoaded));
+
+        let mut handle = copy.with_source(&mut source);
+        for x in 0..32 {
+            for y in 0..32 {
+                for z in 0..32 {
+                    assert_eq!(handle.get([x, y, z]).unwrap(), octree.get([x, y, z]));
+                }
+            }
+        }
+        assert!(copy.equivalent(&octree));
+    }
+
+    #[test]
+    fn unloaded_subtrees_are_neither_encoded_nor_coarsened() {
+        let mut rng = XorShift::new(0x5b84);
+        let octree = rng.octree(32, 2000, 5);
+        let blobs = octree.encode_subtrees(NonZeroU32::new(3).unwrap());
+        let mut source = Source::new(blobs);
+        let mut copy = Octree::<u8>::open_subtrees(&mut source).unwrap();
+
+        #[cfg(feature = "std")]
+        {
+            use crate::EncodeError;
+
+            let encoded = copy.encode_to(&mut Vec::new());
+            assert!(matches!(encoded, Err(EncodeError::Octree(Error::SubtreeNotLoaded))));
+            let encoded = copy.encode_region([0, 0, 0], [32, 32, 32]);
+            assert!(matches!(encoded, Err(EncodeError::Octree(Error::SubtreeNotLoaded))));
+        }
+        #[cfg(feature = "serde")]
+        assert!(serde_json::to_string(&copy).is_err());
+
+        // Coarsening to leaves 8 voxels across would collapse the `Node`s holding the subtrees held in storage,
+        // which are 4 voxels across, so those are left whole, to be loaded as they were written.
+        for _ in 0..3 {
+            copy.lod_down();
+        }
+        let mut handle = copy.with_source(&mut source);
+        for x in 0..32 {
+            for y in 0..32 {
+                for z in 0..32 {
+                    assert_eq!(handle.get([x, y, z]).unwrap(), octree.get([x, y, z]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn missing_and_corrupt_blobs_are_rejected() {
+        let mut rng = XorShift::new(0x5b80);
+        let octree = rng.octree(16, 500, 5);
+        let blobs = octree.encode_subtrees(NonZeroU32::new(1).unwrap());
+
+        let (path, blob) = blobs.iter().find(|(path, _)| path.len() == 1).unwrap();
+        let position = (0..).map(|_| rng.position(16)).find(|p| holds(path, 16, *p)).unwrap();
+
+        let mut missing = Source::new(blobs.clone());
+        missing.blobs.remove(path);
+        let mut copy = Octree::<u8>::open_subtrees(&mut missing).unwrap();
+        assert_eq!(
+            copy.with_source(&mut missing).get(position),
+            Err(Error::SubtreeNotLoaded)
+        );
+
+        // A blob with a valid checksum of its own must still match the checksum recorded above it.
+        let mut stale = blob.clone();
+        let len = stale.len() - 4;
+        stale[len - 1] ^= 1;
+        let mut crc = Crc32::new();
+        crc.update(&stale[..len]);
+        stale[len..].copy_from_slice(&crc.finish().to_le_bytes());
+
+        let mut corrupt = Source::new(blobs.clone());
+        corrupt.blobs.insert(path.clone(), stale);
+        let mut copy = Octree::<u8>::open_subtrees(&mut corrupt).unwrap();
+        assert!(matches!(
+            copy.with_source(&mut corrupt).get(position),
+            Err(Error::ChecksumMismatch { .. })
+        ));
+        assert!(matches!(
+            copy.with_source(&mut corrupt).insert(position, 1),
+            Err(Error::ChecksumMismatch { .. })
+        ));
+
+        let mut newer = blobs[&Vec::new()].clone();
+        newer[4..6].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        let len = newer.len() - 4;
+        let mut crc = Crc32::new();
+        crc.update(&newer[..len]);
+        newer[len..].copy_from_slice(&crc.finish().to_le_bytes());
+
+        let mut source = |_: &[u8]| Some(newer.clone());
+        assert!(matches!(
+            Octree::<u8>::open_subtrees(&mut source),
+            Err(Error::UnsupportedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn corrupt_blobs_never_panic() {
+        let mut rng = XorShift::new(0x5b81);
+        let octree = rng.octree(8, 40, 5);
+        let blobs = octree.encode_subtrees(NonZeroU32::new(1).unwrap());
+
+        // Resealed, so that corruption reaches the decoder rather than failing the checksum.
+        let decode = |root: &[u8]| {
+            let mut root = root.to_vec();
+            if root.len() >= 4 {
+                let len = root.len() - 4;
+                let mut crc = Crc32::new();
+                crc.update(&root[..len]);
+                root[len..].copy_from_slice(&crc.finish().to_le_bytes());
+            }
+
+            let mut source = |path: &[u8]| match path {
+                [] => Some(root.clone()),
+                path => blobs.get(path).cloned(),
+            };
+            if let Ok(mut copy) = Octree::<u8>::open_subtrees(&mut source) {
+                let _ = copy.with_source(&mut source).get([1, 2, 3]);
+            }
+        };
+
+        let root = &blobs[&Vec::new()];
+        for len in 0..root.len() {
+            assert!(catch_unwind(|| decode(&root[..len])).is_ok());
+        }
+
+        for i in 0..root.len() {
+            for flip in [0x01, 0x80, 0xff] {
+                let mut corrupt = root.clone();
+                corrupt[i] ^= flip;
+                assert!(catch_unwind(|| decode(&corrupt)).is_ok());
+            }
+        }
+    }
+
+    /// Returns the data of every voxel of the `Octree`.
+    fn voxels(octree: &Octree<u8>) -> Vec<u8> {
+        let dimension = octree.dimension();
+        let mut voxels = Vec::new();
+
+        for x in 0..dimension {
+            for y in 0..dimension {
+                for z in 0..dimension {
+                    voxels.push(*octree.get([x, y, z]).unwrap());
+                }
+            }
+        }
+
+        voxels
+    }
+
+    #[test]
+    fn exported_subtrees_round_trip() {
+        let mut rng = XorShift::new(0x5b82);
+
+        for _ in 0..20 {
+            let mut octree = rng.octree(16, 1500, 5);
+            let original = voxels(&octree);
+
+            let octants = [rng.below(8) as u8, rng.below(8) as u8];
+            let path = NodePath::new(&octants[..1 + rng.below(2) as usize]).unwrap();
+            let bytes = octree.export_subtree(&path).unwrap();
+
+            let [min, max]: [[u32; 3]; 2] = path.bounds(16).unwrap().map(Into::into);
+            for x in min[0]..max[0] {
+                for y in min[1]..max[1] {
+                    for z in min[2]..max[2] {
+                        octree.clear_at([x, y, z]).unwrap();
+                    }
+                }
+            }
+
+            octree.import_subtree(&path, &bytes).unwrap();
+            assert_eq!(voxels(&octree), original);
+        }
+    }
+
+    #[test]
+    fn exports_stopping_short_of_the_path_hold_leaves() {
+        let mut octree = Octree::<u8>::new_with_background(NonZeroU32::new(16).unwrap(), 9).unwrap();
+        octree.insert([0, 0, 0], 3).unwrap();
+
+        let mut copy = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        for octants in [&[7][..], &[0, 0, 0, 0], &[0, 0, 0, 1]].iter() {
+            let path = NodePath::new(octants).unwrap();
+            copy.import_subtree(&path, &octree.export_subtree(&path).unwrap())
+                .unwrap();
+        }
+
+        assert_eq!(copy.get([15, 15, 15]), Some(&9));
+        assert_eq!(copy.get([0, 0, 0]), Some(&3));
+        assert_eq!(copy.get([1, 0, 0]), Some(&9));
+        assert_eq!(copy.get([0, 1, 0]), Some(&0));
+
+        let path = NodePath::new(&[0, 0, 0, 0, 0]).unwrap();
+        assert_eq!(octree.export_subtree(&path), Err(Error::InvalidDimension(0)));
+    }
+
+    #[test]
+    fn replacing_subtrees_simplifies_parents() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        for x in 0..8 {
+            for y in 0..8 {
+                for z in 0..8 {
+                    octree.insert([x, y, z], 1).unwrap();
+                }
+            }
+        }
+
+        octree.insert([0, 0, 0], 2).unwrap();
+        assert!(!octree.root().is_leaf());
+
+        let bytes = octree.export_subtree(&NodePath::new(&[1]).unwrap()).unwrap();
+        octree.import_subtree(&NodePath::new(&[0]).unwrap(), &bytes).unwrap();
+        assert!(octree.root().is_leaf());
+        assert_eq!(octree.get([0, 0, 0]), Some(&1));
+    }
+
+    #[test]
+    fn invalid_imports_are_rejected() {
+        let mut rng = XorShift::new(0x5b83);
+        let mut octree = rng.octree(16, 1500, 5);
+        let before = alloc::format!("{:#?}", octree);
+
+        let bytes = octree.export_subtree(&NodePath::new(&[2]).unwrap()).unwrap();
+        assert_eq!(
+            octree.import_subtree(&NodePath::new(&[2, 1]).unwrap(), &bytes),
+            Err(Error::DimensionMismatch { expected: 4, found: 8 })
+        );
+
+        let mut corrupt = bytes.clone();
+        corrupt[8] ^= 1;
+        assert!(matches!(
+            octree.import_subtree(&NodePath::new(&[2]).unwrap(), &corrupt),
+            Err(Error::ChecksumMismatch { .. })
+        ));
+        assert_eq!(alloc::format!("{:#?}", octree), before);
+
+        // Subtrees held in storage can be replaced outright, but neither exported nor imported into.
+        let blobs = octree.encode_subtrees(NonZeroU32::new(1).unwrap());
+        let path = blobs.keys().find(|path| path.len() == 1).unwrap();
+        let mut copy = Octree::<u8>::open_subtrees(&mut |path: &[u8]| blobs.get(path).cloned()).unwrap();
+
+        let path = NodePath::new(path).unwrap();
+        let bytes = octree.export_subtree(&path).unwrap();
+        let child = octree.export_subtree(&path.child(0).unwrap()).unwrap();
+
+        assert_eq!(copy.export_subtree(&path), Err(Error::SubtreeNotLoaded));
+        assert_eq!(copy.export_subtree(&NodePath::root()), Err(Error::SubtreeNotLoaded));
+        assert_eq!(
+            copy.import_subtree(&path.child(0).unwrap(), &child),
+            Err(Error::SubtreeNotLoaded)
+        );
+
+        copy.import_subtree(&path, &bytes).unwrap();
+        copy.import_subtree(&path.child(0).unwrap(), &child).unwrap();
+        assert_eq!(copy.export_subtree(&path).unwrap(), bytes);
+    }
+}
@@ -0,0 +1,353 @@
+use crate::{ArenaOctree, CowOctree, Error, LinearOctree, Octree, ValueCodec, Vector3};
+
+use alloc::vec::Vec;
+use core::{convert::TryFrom, num::NonZeroU32};
+
+/// A value whose encoding varies in length: a single byte for air, and three for a block.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Voxel {
+    #[default]
+    Air,
+    Block(u16),
+}
+
+impl ValueCodec for Voxel {
+    fn write(&self, bytes: &mut Vec<u8>) {
+        match self {
+            Self::Air => bytes.push(0),
+            Self::Block(id) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&id.to_le_bytes());
+            }
+        }
+    }
+
+    fn read(bytes: &[u8]) -> Option<(Self, usize)> {
+        match bytes.first()? {
+            0 => Some((Self::Air, 1)),
+            1 => Some((Self::Block(u16::read(&bytes[1..])?.0), 3)),
+            _ => None,
+        }
+    }
+}
+
+/// Returns a copy of `octree` holding air for 0 and a block for any other value.
+pub(crate) fn voxels(octree: &Octree<u8>) -> Octree<Voxel> {
+    Octree::from_fn(NonZeroU32::new(octree.dimension()).unwrap(), |position| {
+        match *octree.get(position).unwrap() {
+            0 => Voxel::Air,
+            n => Voxel::Block(n as u16 * 300),
+        }
+    })
+    .unwrap()
+}
+
+/// A tiny deterministic PRNG, so that randomized tests are reproducible.
+pub(crate) struct XorShift(u64);
+
+impl XorShift {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    pub(crate) fn next_u32(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 32) as u32
+    }
+
+    /// Returns a value in `0..n`.
+    pub(crate) fn below(&mut self, n: u32) -> u32 {
+        self.next_u32() % n
+    }
+
+    /// Returns a value in `low..high`.
+    pub(crate) fn f32(&mut self, low: f32, high: f32) -> f32 {
+        low + (self.next_u32() as f32 / u32::MAX as f32) * (high - low)
+    }
+
+    pub(crate) fn position(&mut self, dimension: u32) -> [u32; 3] {
+        [self.below(dimension), self.below(dimension), self.below(dimension)]
+    }
+
+    /// Builds an `Octree<u8>` from `inserts` random writes of values in `1..=max_value`.
+    ///
+    /// Some writes fill a whole aligned 2*2*2 block, so that the result contains simplified leaves.
+    pub(crate) fn octree(&mut self, dimension: u32, inserts: u32, max_value: u8) -> Octree<u8> {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(dimension).unwrap()).unwrap();
+
+        for _ in 0..inserts {
+            let position = self.position(dimension);
+            let data = 1 + self.below(max_value as u32) as u8;
+
+            if dimension > 1 && self.below(4) == 0 {
+                let base = position.map(|c| c & !1);
+
+                for i in 0..8 {
+                    let offset = [i & 1, (i >> 1) & 1, (i >> 2) & 1];
+                    octree.insert([0, 1, 2].map(|a| base[a] + offset[a]), data).unwrap();
+                }
+            } else {
+                octree.insert(position, data).unwrap();
+            }
+        }
+
+        octree
+    }
+}
+
+/// A backend holding the same voxels as an `Octree<u8>`, and encoding them to the same bytes, checked against one
+/// by [`assert_matches_octree`], [`assert_converts_octree`] and [`assert_rejects_out_of_bounds`].
+pub(crate) trait Mirror: Sized {
+    /// The largest dimension the backend holds.
+    const MAX_DIMENSION: u32 = 1 << 31;
+
+    fn new_with_background(dimension: NonZeroU32, background: u8) -> Result<Self, Error>;
+    fn from_octree(octree: &Octree<u8>) -> Self;
+    fn to_octree(&self) -> Octree<u8>;
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error>;
+    fn to_bytes(&self) -> Vec<u8>;
+    fn insert(&mut self, position: [u32; 3], data: u8) -> Result<(), Error>;
+    fn clear_at(&mut self, position: [u32; 3]) -> Result<(), Error>;
+    fn clear(&mut self);
+    fn get(&self, position: [u32; 3]) -> Option<&u8>;
+
+    /// Simplifies the backend, for those which may hold `Node`s left unsimplified.
+    fn simplify(&mut self) {}
+
+    /// Returns a copy sharing the `Node`s of the backend, for those which can take one cheaply.
+    fn snapshot(&self) -> Option<Self> {
+        None
+    }
+
+    /// Checks anything else the backend should agree with `octree` on after a batch of edits.
+    fn check(&self, _octree: &Octree<u8>, _rng: &mut XorShift) {}
+}
+
+macro_rules! impl_mirror {
+    ($ty:ty, $from_octree:expr $(, $extra:item)*) => {
+        impl Mirror for $ty {
+            fn new_with_background(dimension: NonZeroU32, background: u8) -> Result<Self, Error> {
+                <$ty>::new_with_background(dimension, background)
+            }
+
+            fn from_octree(octree: &Octree<u8>) -> Self {
+                $from_octree(octree)
+            }
+
+            fn to_octree(&self) -> Octree<u8> {
+                Octree::from(self)
+            }
+
+            fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+                <$ty>::from_bytes(bytes)
+            }
+
+            fn to_bytes(&self) -> Vec<u8> {
+                <$ty>::to_bytes(self)
+            }
+
+            fn insert(&mut self, position: [u32; 3], data: u8) -> Result<(), Error> {
+                <$ty>::insert(self, position, data)
+            }
+
+            fn clear_at(&mut self, position: [u32; 3]) -> Result<(), Error> {
+                <$ty>::clear_at(self, position)
+            }
+
+            fn clear(&mut self) {
+                <$ty>::clear(self);
+            }
+
+            fn get(&self, position: [u32; 3]) -> Option<&u8> {
+                <$ty>::get(self, position)
+            }
+
+            $($extra)*
+        }
+    };
+}
+
+impl_mirror!(
+    ArenaOctree<u8>,
+    ArenaOctree::from,
+    fn simplify(&mut self) {
+        ArenaOctree::simplify(self);
+    },
+    fn check(&self, octree: &Octree<u8>, _rng: &mut XorShift) {
+        assert_eq!(
+            self.iter_leaves().collect::<Vec<_>>(),
+            octree.iter_leaves().collect::<Vec<_>>()
+        );
+    }
+);
+
+impl_mirror!(
+    CowOctree<u8>,
+    CowOctree::from,
+    fn snapshot(&self) -> Option<Self> {
+        Some(CowOctree::snapshot(self))
+    }
+);
+
+impl_mirror!(
+    LinearOctree<u8>,
+    |octree| LinearOctree::try_from(octree).unwrap(),
+    const MAX_DIMENSION: u32 = 1 << 21;,
+    fn check(&self, octree: &Octree<u8>, rng: &mut XorShift) {
+        assert_eq!(
+            self.iter_leaves().collect::<Vec<_>>(),
+            octree.iter_leaves().collect::<Vec<_>>()
+        );
+
+        let dimension = octree.dimension();
+        for _ in 0..10 {
+            let min = rng.position(dimension + 2);
+            let max = rng.position(dimension + 2);
+            assert_eq!(
+                self.query_region(min, max).collect::<Vec<_>>(),
+                octree.query_region(min, max).collect::<Vec<_>>(),
+                "from {:?} to {:?}",
+                min,
+                max
+            );
+        }
+    }
+);
+
+/// Applies the same random edits to an `Octree` and a `B`, checking after each batch that they read the same
+/// everywhere and encode to the same bytes, along with whatever [`Mirror::check`] checks.
+///
+/// Backends able to take snapshots have one taken before each batch, which is edited on the side while the
+/// backend itself is, so that their shared `Node`s are copied rather than changed.
+pub(crate) fn assert_matches_octree<B: Mirror>(seed: u64) {
+    let mut rng = XorShift::new(seed);
+
+    for dimension in [1, 2, 8, 16] {
+        let background = rng.below(3) as u8;
+        let dimension_nz = NonZeroU32::new(dimension).unwrap();
+        let mut octree = Octree::new_with_background(dimension_nz, background).unwrap();
+        let mut backend = B::new_with_background(dimension_nz, background).unwrap();
+
+        for _ in 0..20 {
+            let mut snapshot = backend.snapshot();
+
+            for _ in 0..50 {
+                let position = rng.position(dimension);
+                match rng.below(10) {
+                    0..=5 => {
+                        let data = rng.below(3) as u8;
+                        octree.insert(position, data).unwrap();
+                        backend.insert(position, data).unwrap();
+                    }
+                    6..=8 => {
+                        octree.clear_at(position).unwrap();
+                        backend.clear_at(position).unwrap();
+                    }
+                    _ if rng.below(10) == 0 => {
+                        octree.clear();
+                        backend.clear();
+                    }
+                    _ if rng.below(10) == 0 => {
+                        octree.simplify();
+                        backend.simplify();
+                    }
+                    _ => {
+                        if let Some(snapshot) = &mut snapshot {
+                            snapshot.insert(position, 7).unwrap();
+                        }
+                    }
+                }
+            }
+
+            for x in 0..dimension {
+                for y in 0..dimension {
+                    for z in 0..dimension {
+                        assert_eq!(backend.get([x, y, z]), octree.get([x, y, z]), "at {:?}", [x, y, z]);
+                    }
+                }
+            }
+            assert_eq!(backend.get([dimension, 0, 0]), None);
+            assert_eq!(backend.to_bytes(), octree.to_bytes());
+            backend.check(&octree, &mut rng);
+        }
+    }
+
+    assert_matches_octree_at_edges::<B>(&mut rng);
+}
+
+/// Makes the edits random ones rarely do, checking after each that a `B` reads the same as an `Octree` around
+/// them and encodes to the same bytes: filling a block of 4*4*4 voxels, so that it simplifies into a single leaf,
+/// splitting that leaf by writing and clearing single voxels, and clearing the whole tree. Each is made both near
+/// the origin of a small tree and at the far corner of the largest tree `B` holds.
+fn assert_matches_octree_at_edges<B: Mirror>(rng: &mut XorShift) {
+    for dimension in [32, B::MAX_DIMENSION] {
+        let dimension_nz = NonZeroU32::new(dimension).unwrap();
+        let mut octree = Octree::new_with_background(dimension_nz, 7).unwrap();
+        let mut backend = B::new_with_background(dimension_nz, 7).unwrap();
+
+        let min = if dimension == 32 { 4 } else { dimension - 4 };
+        let block = (0..64)
+            .map(|i| [min + i % 4, min + i / 4 % 4, min + i / 16])
+            .collect::<Vec<_>>();
+        let mut assert_same = |octree: &Octree<u8>, backend: &B| {
+            for position in block.iter().chain(&[[0, 0, 0], [min - 1, min, min]]) {
+                assert_eq!(backend.get(*position), octree.get(*position), "at {:?}", position);
+            }
+            assert_eq!(backend.to_bytes(), octree.to_bytes());
+            backend.check(octree, rng);
+        };
+
+        for position in &block {
+            octree.insert(*position, 1).unwrap();
+            backend.insert(*position, 1).unwrap();
+        }
+        assert_same(&octree, &backend);
+
+        octree.insert(block[21], 2).unwrap();
+        backend.insert(block[21], 2).unwrap();
+        octree.clear_at(block[42]).unwrap();
+        backend.clear_at(block[42]).unwrap();
+        octree.insert([0, 0, 0], 0).unwrap();
+        backend.insert([0, 0, 0], 0).unwrap();
+        assert_same(&octree, &backend);
+
+        octree.clear();
+        backend.clear();
+        assert_same(&octree, &backend);
+    }
+}
+
+/// Checks that a `B` converted from a random `Octree`, or decoded from its bytes, encodes to the same bytes and
+/// converts back to an equivalent `Octree`, and that the bytes of an `Octree` at a coarser LOD level are rejected.
+pub(crate) fn assert_converts_octree<B: Mirror>(seed: u64) {
+    let octree = XorShift::new(seed).octree(32, 500, 4);
+
+    let backend = B::from_octree(&octree);
+    assert_eq!(backend.to_bytes(), octree.to_bytes());
+    assert!(backend.to_octree().equivalent(&octree));
+    assert_eq!(backend.to_octree().to_bytes(), octree.to_bytes());
+
+    let decoded = B::from_bytes(&octree.to_bytes()).unwrap();
+    assert_eq!(decoded.to_bytes(), octree.to_bytes());
+
+    let bytes = octree.at_lod(1).to_bytes();
+    assert!(matches!(B::from_bytes(&bytes), Err(Error::InvalidLodLevel(2))));
+}
+
+/// Checks that a `B` rejects edits outside of it, and is left unchanged by them.
+pub(crate) fn assert_rejects_out_of_bounds<B: Mirror>() {
+    let dimension = NonZeroU32::new(4).unwrap();
+    let mut backend = B::new_with_background(dimension, 0).unwrap();
+
+    assert_eq!(
+        backend.insert([4, 0, 0], 1),
+        Err(Error::InvalidPosition(Vector3::new(4, 0, 0)))
+    );
+    assert_eq!(
+        backend.clear_at([0, 0, 9]),
+        Err(Error::InvalidPosition(Vector3::new(0, 0, 9)))
+    );
+    assert_eq!(backend.to_bytes(), Octree::<u8>::new(dimension).unwrap().to_bytes());
+}
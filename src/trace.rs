@@ -0,0 +1,231 @@
+//! Spans around the costly operations of an `Octree`, reported through `tracing` with the `tracing` feature.
+//!
+//! Without the feature, [`enter_span!`] expands to nothing, [`record!`] to a branch never taken, and every
+//! [`Counter`] is empty, so nothing is counted or recorded.
+
+/// Enters a span at the given level, which is exited at the end of the enclosing block.
+///
+/// The fields in brackets are known only once the operation is done, and are filled in by [`record!`] from
+/// within it. Those following are given as to `tracing::span!`.
+#[cfg(feature = "tracing")]
+macro_rules! enter_span {
+    ($level:ident, $name:literal, [$($later:ident),*] $(, $($fields:tt)*)?) => {
+        let _span = tracing::span!(
+            tracing::Level::$level,
+            $name,
+            $($later = tracing::field::Empty,)*
+            $($($fields)*)?
+        )
+        .entered();
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! enter_span {
+    ($level:ident, $name:literal, [$($later:ident),*] $(, $($fields:tt)*)?) => {};
+}
+
+/// Records the given fields on the span last entered, most often by the public method of the `Octree` calling
+/// into the `Node`s. Fields the span does not declare are ignored, so the same counters may be recorded from a
+/// `Node` whichever operation it is part of.
+#[cfg(feature = "tracing")]
+macro_rules! record {
+    ($($field:ident = $value:expr),+ $(,)?) => {{
+        let span = tracing::Span::current();
+        $(span.record(stringify!($field), $value);)+
+    }};
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! record {
+    ($($field:ident = $value:expr),+ $(,)?) => {
+        if false {
+            $(let _ = $value;)+
+        }
+    };
+}
+
+/// Counts the `Node`s an operation visits or merges, or the cubes it writes, to be recorded on its span.
+///
+/// Without the `tracing` feature it holds nothing, and counting does nothing.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Counter(#[cfg(feature = "tracing")] usize);
+
+impl Counter {
+    /// Counts one more.
+    #[inline]
+    pub(crate) fn increment(&mut self) {
+        #[cfg(feature = "tracing")]
+        {
+            self.0 += 1;
+        }
+    }
+
+    /// Adds the count of `other`.
+    #[inline]
+    pub(crate) fn add(&mut self, other: Self) {
+        #[cfg(feature = "tracing")]
+        {
+            self.0 += other.0;
+        }
+        #[cfg(not(feature = "tracing"))]
+        let _ = other;
+    }
+
+    /// Returns the count, as recorded. Without the `tracing` feature nothing is counted, and it is always 0.
+    pub(crate) fn get(self) -> usize {
+        #[cfg(feature = "tracing")]
+        return self.0;
+        #[cfg(not(feature = "tracing"))]
+        0
+    }
+}
+
+#[cfg(all(test, not(feature = "tracing")))]
+mod tests {
+    use super::Counter;
+
+    use core::mem;
+
+    #[test]
+    fn counters_are_empty() {
+        assert_eq!(mem::size_of::<Counter>(), 0);
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use crate::Octree;
+
+    use alloc::{
+        format,
+        string::{String, ToString},
+        sync::Arc,
+        vec::Vec,
+    };
+    use core::{fmt::Debug, num::NonZeroU32};
+    use std::sync::Mutex;
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        Subscriber,
+    };
+    use tracing_subscriber::{
+        layer::{Context, SubscriberExt},
+        registry::LookupSpan,
+        Layer, Registry,
+    };
+
+    /// The name of each span created, in order, along with the fields recorded on it.
+    type Spans = Arc<Mutex<Vec<(&'static str, Vec<(&'static str, String)>)>>>;
+
+    /// Collects every span created while it is part of the default subscriber.
+    struct Collector(Spans);
+
+    /// The index of a span in those collected, kept in its extensions.
+    struct Index(usize);
+
+    struct Fields<'a>(&'a mut Vec<(&'static str, String)>);
+
+    impl Visit for Fields<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0.retain(|(name, _)| *name != field.name());
+            self.0.push((field.name(), format!("{:?}", value)));
+        }
+    }
+
+    impl<S> Layer<S> for Collector
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+            let mut spans = self.0.lock().unwrap();
+            let mut fields = Vec::new();
+            attrs.record(&mut Fields(&mut fields));
+
+            ctx.span(id).unwrap().extensions_mut().insert(Index(spans.len()));
+            spans.push((attrs.metadata().name(), fields));
+        }
+
+        fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+            let index = ctx.span(id).unwrap().extensions().get::<Index>().unwrap().0;
+            values.record(&mut Fields(&mut self.0.lock().unwrap()[index].1));
+        }
+    }
+
+    /// Runs `f` with a [`Collector`] as the default subscriber, and returns the spans it collected, with their
+    /// fields sorted by name.
+    fn collect(f: impl FnOnce()) -> Vec<(&'static str, Vec<(&'static str, String)>)> {
+        let spans = Spans::default();
+        tracing::subscriber::with_default(Registry::default().with(Collector(spans.clone())), f);
+
+        let mut spans = spans.lock().unwrap().clone();
+        for (_, fields) in &mut spans {
+            fields.sort();
+        }
+        spans
+    }
+
+    fn fields(fields: &[(&'static str, &str)]) -> Vec<(&'static str, String)> {
+        fields.iter().map(|(name, value)| (*name, value.to_string())).collect()
+    }
+
+    #[test]
+    fn operations_record_their_spans() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+        let mut bytes = Vec::new();
+
+        let spans = collect(|| {
+            for i in 0..7 {
+                octree.insert([i & 1, i >> 1 & 1, i >> 2], 1).unwrap();
+            }
+            octree.insert([1, 1, 1], 1).unwrap();
+            octree.clear_at([7, 7, 7]).unwrap();
+            octree.clear_region([0, 0, 0], [2, 2, 1]);
+
+            bytes = octree.to_bytes();
+            let mut decoded = Octree::<u8>::from_bytes(&bytes).unwrap();
+            decoded.simplify();
+            decoded.lod_down();
+        });
+
+        let names = spans.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        assert_eq!(names[..8], ["insert"; 8]);
+        assert_eq!(
+            names[8..],
+            [
+                "clear_at",
+                "clear_region",
+                "to_bytes",
+                "from_bytes",
+                "simplify",
+                "lod_down"
+            ]
+        );
+
+        // The last voxel of the corner walks down through three `Node`s, and fills the smallest, merging it.
+        assert_eq!(
+            spans[7].1,
+            fields(&[("merged", "1"), ("position", "[1, 1, 1]"), ("visited", "3")])
+        );
+        // Nothing was ever written there, so nothing below the root is visited, and nothing merges.
+        assert_eq!(spans[8].1, fields(&[("position", "[7, 7, 7]"), ("visited", "1")]));
+        assert_eq!(
+            spans[9].1,
+            fields(&[
+                ("cubes", "25"),
+                ("max", "[2, 2, 1]"),
+                ("min", "[0, 0, 0]"),
+                ("written", "4")
+            ])
+        );
+        assert_eq!(spans[10].1, fields(&[("bytes", &bytes.len().to_string())]));
+        assert_eq!(
+            spans[11].1,
+            fields(&[("bytes", &bytes.len().to_string()), ("nodes", "11")])
+        );
+        // Decoded trees are simplified in full, and the cleared corner now holds two values.
+        assert_eq!(spans[12].1, fields(&[("merged", "0"), ("visited", "3")]));
+        assert_eq!(spans[13].1, fields(&[("from", "1"), ("merged", "1"), ("to", "2")]));
+    }
+}
@@ -1,7 +1,28 @@
-use core::ops::{Add, Mul};
+use core::{
+    fmt,
+    ops::{Add, Div, Index, IndexMut, Mul, Sub},
+};
 
-#[derive(Default, Debug, Clone, Copy)]
-pub(crate) struct Vector3<T>
+/// A position, offset or size along the three axes, as taken by every method of an `Octree` accepting a position.
+///
+/// Arithmetic is that of `T`, component by component. For integers, overflow therefore panics in debug builds and
+/// wraps in release builds, as it does for `T` itself, so subtracting a larger position from a smaller one is a
+/// bug either way rather than a way to find a distance.
+///
+/// # Example
+/// ```
+/// # use svo_rs::Vector3;
+/// #
+/// let a = Vector3::new(1_u32, 6, 3);
+/// let b = Vector3::from([4, 2, 3]);
+///
+/// assert_eq!(a + b, Vector3::new(5, 8, 6));
+/// assert_eq!(a.max(b) - a.min(b), Vector3::new(3, 4, 0));
+/// assert_eq!((a * 2)[1], 12);
+/// assert_eq!(a.to_string(), "(1, 6, 3)");
+/// ```
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Vector3<T>
 where
     T: Copy,
 {
@@ -10,8 +31,16 @@ where
     pub z: T,
 }
 
+impl<T: Copy> Vector3<T> {
+    /// Creates a new `Vector3<T>` from its components.
+    pub const fn new(x: T, y: T, z: T) -> Self {
+        Self { x, y, z }
+    }
+}
+
 impl<T: Mul<Output = T> + Copy> Vector3<T> {
-    pub(crate) fn component_mul(self, other: &Self) -> Self {
+    /// Multiplies each component by the matching component of `other`.
+    pub fn component_mul(self, other: &Self) -> Self {
         Self {
             x: self.x * other.x,
             y: self.y * other.y,
@@ -20,6 +49,22 @@ impl<T: Mul<Output = T> + Copy> Vector3<T> {
     }
 }
 
+impl<T: PartialOrd + Copy> Vector3<T> {
+    /// Returns the smaller of each pair of components, taking those of `self` where they compare equal or not at
+    /// all.
+    pub fn min(self, other: Self) -> Self {
+        let min = |a: T, b: T| if b < a { b } else { a };
+        Self::new(min(self.x, other.x), min(self.y, other.y), min(self.z, other.z))
+    }
+
+    /// Returns the larger of each pair of components, taking those of `self` where they compare equal or not at
+    /// all.
+    pub fn max(self, other: Self) -> Self {
+        let max = |a: T, b: T| if b > a { b } else { a };
+        Self::new(max(self.x, other.x), max(self.y, other.y), max(self.z, other.z))
+    }
+}
+
 impl<T: Add<Output = T> + Copy> Add for Vector3<T> {
     type Output = Self;
 
@@ -32,6 +77,77 @@ impl<T: Add<Output = T> + Copy> Add for Vector3<T> {
     }
 }
 
+impl<T: Sub<Output = T> + Copy> Sub for Vector3<T> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self {
+            x: self.x - other.x,
+            y: self.y - other.y,
+            z: self.z - other.z,
+        }
+    }
+}
+
+impl<T: Mul<Output = T> + Copy> Mul<T> for Vector3<T> {
+    type Output = Self;
+
+    fn mul(self, scalar: T) -> Self {
+        Self {
+            x: self.x * scalar,
+            y: self.y * scalar,
+            z: self.z * scalar,
+        }
+    }
+}
+
+impl<T: Div<Output = T> + Copy> Div<T> for Vector3<T> {
+    type Output = Self;
+
+    fn div(self, scalar: T) -> Self {
+        Self {
+            x: self.x / scalar,
+            y: self.y / scalar,
+            z: self.z / scalar,
+        }
+    }
+}
+
+impl<T: Copy> Index<usize> for Vector3<T> {
+    type Output = T;
+
+    /// Returns the component along the given axis, with `x`, `y` and `z` at 0, 1 and 2, as in the arrays a
+    /// `Vector3` converts to and from.
+    ///
+    /// # Panics
+    /// Panics if `axis` is greater than 2.
+    fn index(&self, axis: usize) -> &T {
+        match axis {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("axis {} is out of range for a Vector3", axis),
+        }
+    }
+}
+
+impl<T: Copy> IndexMut<usize> for Vector3<T> {
+    fn index_mut(&mut self, axis: usize) -> &mut T {
+        match axis {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            2 => &mut self.z,
+            _ => panic!("axis {} is out of range for a Vector3", axis),
+        }
+    }
+}
+
+impl<T: fmt::Display + Copy> fmt::Display for Vector3<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {}, {})", self.x, self.y, self.z)
+    }
+}
+
 impl<T: Copy> From<[T; 3]> for Vector3<T> {
     fn from(v: [T; 3]) -> Self {
         Self {
@@ -41,3 +157,323 @@ impl<T: Copy> From<[T; 3]> for Vector3<T> {
         }
     }
 }
+
+impl<T: Copy> From<Vector3<T>> for [T; 3] {
+    fn from(v: Vector3<T>) -> Self {
+        [v.x, v.y, v.z]
+    }
+}
+
+/// Conversions from the types of `nalgebra`, so that positions given as those reach the `Octree` as they are, as
+/// arrays do.
+#[cfg(feature = "nalgebra")]
+mod nalgebra_interop {
+    use super::Vector3;
+
+    use nalgebra::{Point3, Scalar};
+
+    impl<T: Scalar + Copy> From<nalgebra::Vector3<T>> for Vector3<T> {
+        fn from(v: nalgebra::Vector3<T>) -> Self {
+            Self { x: v.x, y: v.y, z: v.z }
+        }
+    }
+
+    impl<T: Scalar + Copy> From<Vector3<T>> for nalgebra::Vector3<T> {
+        fn from(v: Vector3<T>) -> Self {
+            Self::new(v.x, v.y, v.z)
+        }
+    }
+
+    impl<T: Scalar + Copy> From<Point3<T>> for Vector3<T> {
+        fn from(p: Point3<T>) -> Self {
+            Self { x: p.x, y: p.y, z: p.z }
+        }
+    }
+
+    impl<T: Scalar + Copy> From<Vector3<T>> for Point3<T> {
+        fn from(v: Vector3<T>) -> Self {
+            Self::new(v.x, v.y, v.z)
+        }
+    }
+}
+
+/// Conversions from the types of `mint`, through which most math libraries convert their own, as with those of
+/// `nalgebra`.
+#[cfg(feature = "mint")]
+mod mint_interop {
+    use super::Vector3;
+
+    impl<T: Copy> From<mint::Vector3<T>> for Vector3<T> {
+        fn from(v: mint::Vector3<T>) -> Self {
+            Self { x: v.x, y: v.y, z: v.z }
+        }
+    }
+
+    impl<T: Copy> From<Vector3<T>> for mint::Vector3<T> {
+        fn from(v: Vector3<T>) -> Self {
+            Self { x: v.x, y: v.y, z: v.z }
+        }
+    }
+
+    impl<T: Copy> From<mint::Point3<T>> for Vector3<T> {
+        fn from(p: mint::Point3<T>) -> Self {
+            Self { x: p.x, y: p.y, z: p.z }
+        }
+    }
+
+    impl<T: Copy> From<Vector3<T>> for mint::Point3<T> {
+        fn from(v: Vector3<T>) -> Self {
+            Self { x: v.x, y: v.y, z: v.z }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Vector3;
+
+    use std::{collections::HashSet, string::ToString};
+
+    #[test]
+    fn arithmetic_is_component_wise() {
+        let (a, b) = (Vector3::new(6_u32, 9, 12), Vector3::new(1, 2, 3));
+
+        assert_eq!(a + b, Vector3::new(7, 11, 15));
+        assert_eq!(a - b, Vector3::new(5, 7, 9));
+        assert_eq!(a * 2, Vector3::new(12, 18, 24));
+        assert_eq!(a / 3, Vector3::new(2, 3, 4));
+        assert_eq!(Vector3::new(7_u32, 8, 9) / 2, Vector3::new(3, 4, 4));
+        assert_eq!(a.component_mul(&b), Vector3::new(6, 18, 36));
+        assert_eq!(Vector3::new(1.5_f32, -2.0, 0.0) * 2.0, Vector3::new(3.0, -4.0, 0.0));
+    }
+
+    #[test]
+    fn min_and_max_are_component_wise() {
+        let (a, b) = (Vector3::new(1_u32, 8, 3), Vector3::new(4, 2, 3));
+        assert_eq!(a.min(b), Vector3::new(1, 2, 3));
+        assert_eq!(a.max(b), Vector3::new(4, 8, 3));
+
+        // Components which do not compare are taken from `self`.
+        let nan = Vector3::new(f32::NAN, 1.0, 1.0).min(Vector3::new(0.0, 0.0, 2.0));
+        assert!(nan.x.is_nan());
+        assert_eq!([nan.y, nan.z], [0.0, 1.0]);
+    }
+
+    #[test]
+    fn components_are_indexed_by_axis() {
+        let mut v = Vector3::new(1_u32, 2, 3);
+        assert_eq!([v[0], v[1], v[2]], [1, 2, 3]);
+
+        v[1] = 5;
+        v[2] += 1;
+        assert_eq!(v, Vector3::new(1, 5, 4));
+        assert_eq!(<[u32; 3]>::from(v), [1, 5, 4]);
+        assert_eq!(Vector3::from([1, 5, 4]), v);
+    }
+
+    #[test]
+    #[should_panic(expected = "axis 3 is out of range")]
+    fn indexing_past_z_panics() {
+        let _ = Vector3::new(1_u32, 2, 3)[3];
+    }
+
+    #[test]
+    #[should_panic(expected = "axis 3 is out of range")]
+    fn mutably_indexing_past_z_panics() {
+        Vector3::new(1_u32, 2, 3)[3] = 0;
+    }
+
+    #[test]
+    fn equal_vectors_hash_equally() {
+        let set = [
+            Vector3::new(1_u32, 2, 3),
+            Vector3::from([1, 2, 3]),
+            Vector3::new(3, 2, 1),
+        ]
+        .iter()
+        .copied()
+        .collect::<HashSet<_>>();
+
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&Vector3::new(3, 2, 1)));
+        assert_ne!(Vector3::new(1_u32, 2, 3), Vector3::new(1, 2, 4));
+    }
+
+    #[test]
+    fn displays_as_a_tuple() {
+        assert_eq!(Vector3::new(1_u32, 20, 300).to_string(), "(1, 20, 300)");
+        assert_eq!(Vector3::new(0.5_f32, -1.0, 2.25).to_string(), "(0.5, -1, 2.25)");
+    }
+
+    /// Overflow follows the arithmetic of `u32`, which panics in debug builds.
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "overflow")]
+    fn overflow_panics_in_debug_builds() {
+        let _ = Vector3::new(0_u32, 1, 2) - Vector3::new(1, 0, 0);
+    }
+
+    /// Overflow follows the arithmetic of `u32`, which wraps in release builds.
+    #[test]
+    #[cfg(not(debug_assertions))]
+    fn overflow_wraps_in_release_builds() {
+        assert_eq!(
+            Vector3::new(0_u32, 1, 2) - Vector3::new(1, 0, 0),
+            Vector3::new(u32::MAX, 1, 2)
+        );
+        assert_eq!(
+            Vector3::new(u32::MAX, 0, 0) + Vector3::new(1, 0, 0),
+            Vector3::new(0, 0, 0)
+        );
+        assert_eq!(Vector3::new(u32::MAX, 1, 1) * 2, Vector3::new(u32::MAX - 1, 2, 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "divide by zero")]
+    fn dividing_by_zero_panics() {
+        let _ = Vector3::new(1_u32, 2, 3) / 0;
+    }
+}
+
+#[cfg(all(test, feature = "nalgebra"))]
+mod nalgebra_tests {
+    use super::Vector3;
+    use crate::test_utils::XorShift;
+
+    use alloc::vec::Vec;
+    use nalgebra::Point3;
+
+    #[test]
+    fn converts_to_and_from_nalgebra() {
+        let v = Vector3::from(nalgebra::Vector3::new(1_u32, 2, 3));
+        assert_eq!([v.x, v.y, v.z], [1, 2, 3]);
+        assert_eq!(nalgebra::Vector3::from(v), nalgebra::Vector3::new(1, 2, 3));
+        assert_eq!(
+            Point3::from(Vector3::from(Point3::new(4_u32, 5, 6))),
+            Point3::new(4, 5, 6)
+        );
+    }
+
+    /// Reads the same tree through arrays and through `nalgebra` types, which must agree everywhere.
+    #[test]
+    fn positions_are_accepted_as_nalgebra_types() {
+        let mut rng = XorShift::new(0x4a1a);
+        let mut octree = rng.octree(16, 300, 3);
+
+        for x in 0..16 {
+            for y in 0..16 {
+                for z in 0..16 {
+                    let data = octree.get([x, y, z]).copied();
+                    assert_eq!(octree.get(Point3::new(x, y, z)).copied(), data);
+                    assert_eq!(octree.get(nalgebra::Vector3::new(x, y, z)).copied(), data);
+                }
+            }
+        }
+
+        octree.insert(Point3::new(3, 4, 5), 7).unwrap();
+        assert_eq!(octree.get([3, 4, 5]), Some(&7));
+        octree.clear_at(nalgebra::Vector3::new(3, 4, 5)).unwrap();
+        assert_eq!(octree.get([3, 4, 5]), Some(&0));
+        assert!(octree.contains(Point3::new(15, 15, 15)));
+        assert!(!octree.contains(Point3::new(16, 0, 0)));
+
+        assert_eq!(
+            octree
+                .query_region(Point3::new(2, 2, 2), Point3::new(9, 9, 9))
+                .collect::<Vec<_>>(),
+            octree.query_region([2, 2, 2], [9, 9, 9]).collect::<Vec<_>>()
+        );
+
+        let (origin, direction) = ([-1.0, 8.5, 8.5], [1.0, 0.0, 0.0]);
+        assert_eq!(
+            octree.raycast(Point3::from(origin), nalgebra::Vector3::from(direction)),
+            octree.raycast(origin, direction)
+        );
+        assert_eq!(
+            octree.query_sphere(Point3::new(8.0, 8.0, 8.0), 5.0).collect::<Vec<_>>(),
+            octree.query_sphere([8.0, 8.0, 8.0], 5.0).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            octree.nearest(Point3::new(0.5, 0.5, 0.5)),
+            octree.nearest([0.5, 0.5, 0.5])
+        );
+    }
+}
+
+#[cfg(all(test, feature = "mint"))]
+mod mint_tests {
+    use super::Vector3;
+    use crate::test_utils::XorShift;
+
+    use alloc::vec::Vec;
+
+    /// A position as a math library would represent it, converting to and from `mint` as most do.
+    #[derive(Clone, Copy)]
+    struct LibraryVector {
+        x: u32,
+        y: u32,
+        z: u32,
+    }
+
+    impl From<LibraryVector> for mint::Vector3<u32> {
+        fn from(v: LibraryVector) -> Self {
+            Self { x: v.x, y: v.y, z: v.z }
+        }
+    }
+
+    impl From<mint::Vector3<u32>> for LibraryVector {
+        fn from(v: mint::Vector3<u32>) -> Self {
+            Self { x: v.x, y: v.y, z: v.z }
+        }
+    }
+
+    #[test]
+    fn converts_to_and_from_mint() {
+        let v = Vector3::from(mint::Vector3::from([1_u32, 2, 3]));
+        assert_eq!([v.x, v.y, v.z], [1, 2, 3]);
+        assert_eq!(mint::Point3::from(v), mint::Point3::from([1, 2, 3]));
+
+        let library = LibraryVector::from(mint::Vector3::from(Vector3::from(mint::Point3::from([4_u32, 5, 6]))));
+        assert_eq!([library.x, library.y, library.z], [4, 5, 6]);
+    }
+
+    /// Reads the same tree through arrays, `mint` points and a library's vectors converted through `mint`, which
+    /// must agree everywhere.
+    #[test]
+    fn positions_are_accepted_as_mint_types() {
+        let mut octree = XorShift::new(0x3147).octree(16, 300, 3);
+
+        for x in 0..16 {
+            for y in 0..16 {
+                for z in 0..16 {
+                    let data = octree.get([x, y, z]).copied();
+                    assert_eq!(octree.get(mint::Point3 { x, y, z }).copied(), data);
+                    assert_eq!(
+                        octree.get(mint::Vector3::from(LibraryVector { x, y, z })).copied(),
+                        data
+                    );
+                }
+            }
+        }
+
+        let library = LibraryVector { x: 3, y: 4, z: 5 };
+        octree.insert(mint::Vector3::from(library), 7).unwrap();
+        assert_eq!(octree.get([3, 4, 5]), Some(&7));
+        octree.clear_at(mint::Point3::from([3, 4, 5])).unwrap();
+        assert_eq!(octree.get([3, 4, 5]), Some(&0));
+
+        assert_eq!(
+            octree
+                .query_region(mint::Point3::from([2, 2, 2]), mint::Point3::from([9, 9, 9]))
+                .collect::<Vec<_>>(),
+            octree.query_region([2, 2, 2], [9, 9, 9]).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            octree.raycast(
+                mint::Point3::from([-1.0, 8.5, 8.5]),
+                mint::Vector3::from([1.0, 0.0, 0.0])
+            ),
+            octree.raycast([-1.0, 8.5, 8.5], [1.0, 0.0, 0.0])
+        );
+    }
+}
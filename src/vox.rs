@@ -0,0 +1,633 @@
+use crate::{Node, Octree, Vector3};
+
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::{convert::TryInto, fmt::Debug, hash::Hash};
+use std::{
+    error, fmt,
+    io::{self, Write},
+};
+
+/// The edge length of the largest model MagicaVoxel loads.
+const MAX_MODEL: u32 = 256;
+/// The version of the `.vox` format written.
+const VERSION: i32 = 150;
+
+/// Options for [`Octree::export_vox`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct VoxConfig {
+    /// Whether an `Octree` more than 256 voxels across is split into models of 256 voxels across, placed
+    /// side by side, rather than rejected.
+    pub split_models: bool,
+    /// The colour of each palette index from 1 to 255 as red, green, blue and alpha, written as an `RGBA`
+    /// chunk. If `None`, MagicaVoxel uses its default palette.
+    pub palette: Option<[[u8; 4]; 255]>,
+}
+
+/// An error returned by [`Octree::export_vox`] and [`Octree::from_vox`].
+#[derive(Debug)]
+pub enum VoxError {
+    /// The writer failed.
+    Io(io::Error),
+    /// The `Octree` is more than 256 voxels across, and splitting it into several models was not enabled.
+    TooLarge(u32),
+    /// The bytes do not hold a valid `.vox` file.
+    Malformed(&'static str),
+    /// The file holds no model of the given index.
+    MissingModel(usize),
+}
+
+impl fmt::Display for VoxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "Failed to write .vox file: {}", error),
+            Self::TooLarge(dimension) => write!(
+                f,
+                "Octree of dimension {} exceeds the .vox model limit of {}.",
+                dimension, MAX_MODEL
+            ),
+            Self::Malformed(reason) => write!(f, "Malformed .vox file: {}", reason),
+            Self::MissingModel(index) => write!(f, "The .vox file holds no model {}.", index),
+        }
+    }
+}
+
+impl error::Error for VoxError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::TooLarge(_) | Self::Malformed(_) | Self::MissingModel(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for VoxError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Appends a chunk with the given id, content and children to `bytes`.
+fn chunk(bytes: &mut Vec<u8>, id: &[u8; 4], content: &[u8], children: &[u8]) {
+    bytes.extend_from_slice(id);
+    bytes.extend_from_slice(&(content.len() as i32).to_le_bytes());
+    bytes.extend_from_slice(&(children.len() as i32).to_le_bytes());
+    bytes.extend_from_slice(content);
+    bytes.extend_from_slice(children);
+}
+
+/// Appends a dictionary of strings to `bytes`.
+fn dict(bytes: &mut Vec<u8>, entries: &[(&str, &str)]) {
+    bytes.extend_from_slice(&(entries.len() as i32).to_le_bytes());
+
+    for (key, value) in entries {
+        for text in [key, value] {
+            bytes.extend_from_slice(&(text.len() as i32).to_le_bytes());
+            bytes.extend_from_slice(text.as_bytes());
+        }
+    }
+}
+
+/// Appends a transform node with the given id, child and translation to `bytes`.
+fn transform(bytes: &mut Vec<u8>, id: i32, child: i32, translation: Option<[i32; 3]>) {
+    let mut content = Vec::new();
+    content.extend_from_slice(&id.to_le_bytes());
+    dict(&mut content, &[]);
+    content.extend_from_slice(&child.to_le_bytes());
+    content.extend_from_slice(&(-1_i32).to_le_bytes());
+    content.extend_from_slice(&(-1_i32).to_le_bytes());
+    content.extend_from_slice(&1_i32.to_le_bytes());
+
+    match translation {
+        Some([x, y, z]) => dict(&mut content, &[("_t", &format!("{} {} {}", x, y, z))]),
+        None => dict(&mut content, &[]),
+    }
+
+    chunk(bytes, b"nTRN", &content, &[]);
+}
+
+/// The id, content and children of a chunk.
+type ChunkParts<'a> = (&'a [u8], &'a [u8], &'a [u8]);
+
+/// Splits the chunks held by `bytes` into their parts.
+fn read_chunks(mut bytes: &[u8]) -> Result<Vec<ChunkParts<'_>>, VoxError> {
+    let mut chunks = Vec::new();
+
+    while !bytes.is_empty() {
+        let header = bytes.get(..12).ok_or(VoxError::Malformed("truncated chunk"))?;
+        let content = u32::from_le_bytes(header[4..8].try_into().unwrap()) as usize;
+        let children = u32::from_le_bytes(header[8..12].try_into().unwrap()) as usize;
+
+        let end = content
+            .checked_add(children)
+            .and_then(|len| len.checked_add(12))
+            .filter(|end| *end <= bytes.len())
+            .ok_or(VoxError::Malformed("truncated chunk"))?;
+
+        chunks.push((&header[..4], &bytes[12..12 + content], &bytes[12 + content..end]));
+        bytes = &bytes[end..];
+    }
+
+    Ok(chunks)
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Writes the `Octree` to `w` as a MagicaVoxel `.vox` file.
+    ///
+    /// Each voxel not holding the background is written with the palette index `palette` returns for its
+    /// data, and voxels given index 0 are left empty. Leaves are expanded into their voxels. An `Octree` up
+    /// to 256 voxels across is written as a single model. Larger ones are rejected with
+    /// [`VoxError::TooLarge`] unless [`VoxConfig::split_models`] is set, in which case each non-empty block
+    /// of 256 voxels across is written as its own model, translated to its place by the scene graph.
+    /// Positions are written unchanged, so the `z` axis of the `Octree` points up in MagicaVoxel.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree, VoxConfig};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+    /// octree.insert([1, 2, 3], 4).unwrap();
+    ///
+    /// let mut bytes = Vec::new();
+    /// octree.export_vox(&mut bytes, |data| *data, VoxConfig::default()).unwrap();
+    /// assert_eq!(&bytes[..4], b"VOX ");
+    /// ```
+    pub fn export_vox<F>(&self, w: &mut impl Write, palette: F, config: VoxConfig) -> Result<(), VoxError>
+    where
+        F: Fn(&T) -> u8,
+    {
+        let dimension = self.dimension();
+        if dimension > MAX_MODEL && !config.split_models {
+            return Err(VoxError::TooLarge(dimension));
+        }
+
+        // Gather the voxels of each model, keyed by the position of the model.
+        let size = dimension.min(MAX_MODEL);
+        let mut models = BTreeMap::<[u32; 3], Vec<u8>>::new();
+
+        for leaf in self.iter_leaves() {
+            let index = palette(&leaf.data);
+            if index == 0 {
+                continue;
+            }
+
+            let [x, y, z] = leaf.min;
+            for (x, y, z) in (x..x + leaf.dimension).flat_map(|x| {
+                (y..y + leaf.dimension).flat_map(move |y| (z..z + leaf.dimension).map(move |z| (x, y, z)))
+            }) {
+                let voxels = models.entry([x / size, y / size, z / size]).or_default();
+                voxels.extend_from_slice(&[(x % size) as u8, (y % size) as u8, (z % size) as u8, index]);
+            }
+        }
+
+        if models.is_empty() {
+            models.insert([0; 3], Vec::new());
+        }
+
+        let mut children = Vec::new();
+        for voxels in models.values() {
+            let mut content = Vec::new();
+            for _ in 0..3 {
+                content.extend_from_slice(&(size as i32).to_le_bytes());
+            }
+            chunk(&mut children, b"SIZE", &content, &[]);
+
+            let mut content = Vec::new();
+            content.extend_from_slice(&((voxels.len() / 4) as i32).to_le_bytes());
+            content.extend_from_slice(voxels);
+            chunk(&mut children, b"XYZI", &content, &[]);
+        }
+
+        // Place the models with a transform above a group, which holds a transform above a shape for each.
+        if dimension > MAX_MODEL {
+            transform(&mut children, 0, 1, None);
+
+            let mut content = Vec::new();
+            content.extend_from_slice(&1_i32.to_le_bytes());
+            dict(&mut content, &[]);
+            content.extend_from_slice(&(models.len() as i32).to_le_bytes());
+            for model in 0..models.len() as i32 {
+                content.extend_from_slice(&(2 + 2 * model).to_le_bytes());
+            }
+            chunk(&mut children, b"nGRP", &content, &[]);
+
+            for (model, position) in models.keys().enumerate() {
+                let model = model as i32;
+                let translation = [0, 1, 2].map(|i| (position[i] * size + size / 2) as i32);
+                transform(&mut children, 2 + 2 * model, 3 + 2 * model, Some(translation));
+
+                let mut content = Vec::new();
+                content.extend_from_slice(&(3 + 2 * model).to_le_bytes());
+                dict(&mut content, &[]);
+                content.extend_from_slice(&1_i32.to_le_bytes());
+                content.extend_from_slice(&model.to_le_bytes());
+                dict(&mut content, &[]);
+                chunk(&mut children, b"nSHP", &content, &[]);
+            }
+        }
+
+        if let Some(colours) = config.palette {
+            let mut content = colours.concat();
+            content.extend_from_slice(&[0; 4]);
+            chunk(&mut children, b"RGBA", &content, &[]);
+        }
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"VOX ");
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        chunk(&mut bytes, b"MAIN", &[], &children);
+
+        w.write_all(&bytes)?;
+        Ok(())
+    }
+
+    /// Reads the first model of a MagicaVoxel `.vox` file as an `Octree`, as [`Octree::from_vox_model`] does.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, Octree, VoxConfig};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+    /// octree.insert([1, 2, 3], 4).unwrap();
+    ///
+    /// let mut bytes = Vec::new();
+    /// octree.export_vox(&mut bytes, |data| *data, VoxConfig::default()).unwrap();
+    ///
+    /// let copy = Octree::<u8>::from_vox(&bytes, |index| index).unwrap();
+    /// assert!(copy.equivalent(&octree));
+    /// ```
+    pub fn from_vox<F>(bytes: &[u8], map: F) -> Result<Self, VoxError>
+    where
+        F: Fn(u8) -> T,
+    {
+        Self::from_vox_model(bytes, 0, map)
+    }
+
+    /// Reads the model of the given index in a MagicaVoxel `.vox` file as an `Octree`, with each voxel holding
+    /// the data `map` returns for its palette index.
+    ///
+    /// The `Octree` is the smallest power of two across which holds the model, with its voxels at the positions
+    /// they have within the model, and a background of `T::default()`. Voxels of index 0 are left unwritten.
+    /// Models are numbered in the order they appear, and their placement in the scene graph is ignored.
+    /// Returns [`VoxError::Malformed`] if the file is truncated or its chunks are invalid, or a voxel lies
+    /// outside its model, and [`VoxError::MissingModel`] if there are not enough models.
+    pub fn from_vox_model<F>(bytes: &[u8], model: usize, map: F) -> Result<Self, VoxError>
+    where
+        F: Fn(u8) -> T,
+    {
+        if bytes.get(..4) != Some(b"VOX ") || bytes.len() < 8 {
+            return Err(VoxError::Malformed("missing header"));
+        }
+
+        let main = match read_chunks(&bytes[8..])?.as_slice() {
+            [(b"MAIN", _, children)] => read_chunks(children)?,
+            _ => return Err(VoxError::Malformed("missing MAIN chunk")),
+        };
+
+        let mut sizes = main.iter().filter(|(id, _, _)| id == b"SIZE");
+        let mut voxels = main.iter().filter(|(id, _, _)| id == b"XYZI");
+
+        let size = match sizes.nth(model) {
+            Some((_, content, _)) => content
+                .get(..12)
+                .ok_or(VoxError::Malformed("truncated SIZE chunk"))?
+                .chunks(4)
+                .map(|c| i32::from_le_bytes(c.try_into().unwrap()))
+                .map(|c| match c {
+                    1..=256 => Ok(c as u32),
+                    _ => Err(VoxError::Malformed("invalid model size")),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            None => return Err(VoxError::MissingModel(model)),
+        };
+
+        let content = match voxels.nth(model) {
+            Some((_, content, _)) => *content,
+            None => return Err(VoxError::Malformed("missing XYZI chunk")),
+        };
+
+        let count = content
+            .get(..4)
+            .map(|c| u32::from_le_bytes(c.try_into().unwrap()) as usize)
+            .ok_or(VoxError::Malformed("truncated XYZI chunk"))?;
+        let records = count
+            .checked_mul(4)
+            .and_then(|len| content.get(4..4 + len))
+            .ok_or(VoxError::Malformed("truncated XYZI chunk"))?;
+
+        let mut voxels = Vec::with_capacity(count);
+        for record in records.chunks(4) {
+            let position = [record[0] as u32, record[1] as u32, record[2] as u32];
+            if (0..3).any(|i| position[i] >= size[i]) {
+                return Err(VoxError::Malformed("voxel outside model"));
+            }
+
+            if record[3] != 0 {
+                voxels.push((position, map(record[3])));
+            }
+        }
+
+        let dimension = size.iter().max().unwrap().next_power_of_two();
+        let bounds = [Vector3::from([0, 0, 0]), Vector3::from([dimension; 3])];
+        let root = Node::from_voxels(bounds, &mut voxels, T::default());
+
+        Octree::from_root(dimension, root, T::default(), 1).map_err(|_| VoxError::Malformed("invalid model size"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{VoxConfig, VoxError};
+    use crate::{test_utils::XorShift, Octree};
+
+    use alloc::{
+        string::{String, ToString},
+        vec::Vec,
+    };
+    use core::{convert::TryInto, num::NonZeroU32};
+    use std::collections::BTreeSet;
+
+    /// A chunk of a `.vox` file: its id, content and children.
+    type Chunk = ([u8; 4], Vec<u8>, Vec<u8>);
+
+    /// Splits `bytes` into the chunks it holds, checking that their lengths add up.
+    fn chunks(mut bytes: &[u8]) -> Vec<Chunk> {
+        let mut chunks = Vec::new();
+
+        while !bytes.is_empty() {
+            let id = bytes[..4].try_into().unwrap();
+            let content = i32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize;
+            let children = i32::from_le_bytes(bytes[8..12].try_into().unwrap()) as usize;
+
+            let (chunk, rest) = bytes[12..].split_at(content + children);
+            chunks.push((id, chunk[..content].to_vec(), chunk[content..].to_vec()));
+            bytes = rest;
+        }
+
+        chunks
+    }
+
+    fn i32_at(bytes: &[u8], at: usize) -> i32 {
+        i32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    /// Reads the dictionary of strings at `at`, advancing past it.
+    fn dict(bytes: &[u8], at: &mut usize) -> Vec<(String, String)> {
+        let count = i32_at(bytes, *at);
+        *at += 4;
+
+        let mut string = || {
+            let len = i32_at(bytes, *at) as usize;
+            let text = std::str::from_utf8(&bytes[*at + 4..*at + 4 + len]).unwrap().to_string();
+            *at += 4 + len;
+            text
+        };
+
+        (0..count).map(|_| (string(), string())).collect()
+    }
+
+    /// Returns the chunks within the `MAIN` chunk of a `.vox` file.
+    fn parse(bytes: &[u8]) -> Vec<Chunk> {
+        assert_eq!(&bytes[..4], b"VOX ");
+        assert_eq!(i32_at(bytes, 4), 150);
+
+        let main = chunks(&bytes[8..]);
+        assert_eq!(main.len(), 1);
+        assert_eq!(&main[0].0, b"MAIN");
+        assert!(main[0].1.is_empty());
+
+        chunks(&main[0].2)
+    }
+
+    /// Returns the voxels of an `XYZI` chunk.
+    fn voxels(content: &[u8]) -> BTreeSet<[u8; 4]> {
+        let count = i32_at(content, 0) as usize;
+        assert_eq!(content.len(), 4 + count * 4);
+
+        content[4..]
+            .chunks(4)
+            .map(|voxel| voxel.try_into().unwrap())
+            .collect::<BTreeSet<_>>()
+    }
+
+    #[test]
+    fn small_trees_are_one_model() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        octree.insert([1, 2, 3], 4).unwrap();
+        octree.insert([15, 0, 9], 200).unwrap();
+        octree.insert([5, 5, 5], 9).unwrap();
+        for x in 8..10 {
+            for y in 8..10 {
+                for z in 8..10 {
+                    octree.insert([x, y, z], 7).unwrap();
+                }
+            }
+        }
+
+        let mut bytes = Vec::new();
+        octree
+            .export_vox(
+                &mut bytes,
+                |data| if *data == 9 { 0 } else { *data },
+                VoxConfig::default(),
+            )
+            .unwrap();
+
+        let chunks = parse(&bytes);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(&chunks[0].0, b"SIZE");
+        assert_eq!(
+            (0..3).map(|i| i32_at(&chunks[0].1, i * 4)).collect::<Vec<_>>(),
+            vec![16; 3]
+        );
+
+        // The simplified leaf is expanded into eight voxels, and voxels given index 0 are left out.
+        assert_eq!(&chunks[1].0, b"XYZI");
+        let mut expected = vec![[1, 2, 3, 4], [15, 0, 9, 200]];
+        for x in 8..10 {
+            for y in 8..10 {
+                for z in 8..10 {
+                    expected.push([x, y, z, 7]);
+                }
+            }
+        }
+        assert_eq!(voxels(&chunks[1].1), expected.into_iter().collect());
+    }
+
+    #[test]
+    fn palettes_are_written() {
+        let octree = Octree::<u8>::new(NonZeroU32::new(4).unwrap()).unwrap();
+
+        let mut colours = [[0; 4]; 255];
+        colours[0] = [255, 0, 0, 255];
+
+        let config = VoxConfig {
+            palette: Some(colours),
+            ..VoxConfig::default()
+        };
+
+        let mut bytes = Vec::new();
+        octree.export_vox(&mut bytes, |data| *data, config).unwrap();
+
+        let chunks = parse(&bytes);
+        assert_eq!(
+            chunks.iter().map(|chunk| chunk.0).collect::<Vec<_>>(),
+            vec![*b"SIZE", *b"XYZI", *b"RGBA"]
+        );
+        assert_eq!(voxels(&chunks[1].1).len(), 0);
+        assert_eq!(chunks[2].1.len(), 256 * 4);
+        assert_eq!(&chunks[2].1[..4], &[255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn large_trees_are_split_or_rejected() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(512).unwrap()).unwrap();
+        octree.insert([1, 2, 3], 1).unwrap();
+        octree.insert([300, 2, 500], 2).unwrap();
+
+        let mut bytes = Vec::new();
+        assert!(matches!(
+            octree.export_vox(&mut bytes, |data| *data, VoxConfig::default()),
+            Err(VoxError::TooLarge(512))
+        ));
+        assert!(bytes.is_empty());
+
+        let config = VoxConfig {
+            split_models: true,
+            ..VoxConfig::default()
+        };
+        octree.export_vox(&mut bytes, |data| *data, config).unwrap();
+
+        let chunks = parse(&bytes);
+        let ids = chunks.iter().map(|chunk| chunk.0).collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            vec![*b"SIZE", *b"XYZI", *b"SIZE", *b"XYZI", *b"nTRN", *b"nGRP", *b"nTRN", *b"nSHP", *b"nTRN", *b"nSHP"]
+        );
+
+        assert_eq!(voxels(&chunks[1].1), [[1, 2, 3, 1]].iter().copied().collect());
+        assert_eq!(voxels(&chunks[3].1), [[44, 2, 244, 2]].iter().copied().collect());
+
+        // Each model is translated to the centre of its block: the frame of each transform follows its id,
+        // attributes, child, reserved id, layer and frame count.
+        let translation = |content: &[u8]| {
+            let mut at = 4;
+            assert!(dict(content, &mut at).is_empty());
+            at += 16;
+            dict(content, &mut at)
+        };
+        assert_eq!(
+            translation(&chunks[6].1),
+            vec![("_t".to_string(), "128 128 128".to_string())]
+        );
+        assert_eq!(
+            translation(&chunks[8].1),
+            vec![("_t".to_string(), "384 128 384".to_string())]
+        );
+    }
+
+    fn export(octree: &Octree<u8>, config: VoxConfig) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        octree.export_vox(&mut bytes, |data| *data, config).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn exported_trees_round_trip() {
+        let mut rng = XorShift::new(0x7a01);
+
+        for dimension in [1, 2, 16, 64, 256] {
+            let octree = rng.octree(dimension, 3000, 5);
+            let copy = Octree::<u8>::from_vox(&export(&octree, VoxConfig::default()), |index| index).unwrap();
+
+            assert_eq!(copy.dimension(), dimension);
+            assert!(copy.equivalent(&octree));
+        }
+
+        // Values are mapped from their palette index.
+        let octree = rng.octree(8, 100, 5);
+        let copy = Octree::<u32>::from_vox(&export(&octree, VoxConfig::default()), |index| index as u32 * 10).unwrap();
+        for position in (0..512).map(|i| [i % 8, i / 8 % 8, i / 64]) {
+            assert_eq!(
+                copy.get(position).copied(),
+                octree.get(position).map(|data| *data as u32 * 10)
+            );
+        }
+    }
+
+    #[test]
+    fn models_are_read_by_index() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(512).unwrap()).unwrap();
+        octree.insert([1, 2, 3], 1).unwrap();
+        octree.insert([300, 2, 500], 2).unwrap();
+
+        let config = VoxConfig {
+            split_models: true,
+            ..VoxConfig::default()
+        };
+        let bytes = export(&octree, config);
+
+        let first = Octree::<u8>::from_vox(&bytes, |index| index).unwrap();
+        assert_eq!(first.dimension(), 256);
+        assert_eq!(first.get([1, 2, 3]), Some(&1));
+
+        let second = Octree::<u8>::from_vox_model(&bytes, 1, |index| index).unwrap();
+        assert_eq!(second.get([44, 2, 244]), Some(&2));
+        assert_eq!(second.get([1, 2, 3]), Some(&0));
+
+        assert!(matches!(
+            Octree::<u8>::from_vox_model(&bytes, 2, |index| index),
+            Err(VoxError::MissingModel(2))
+        ));
+    }
+
+    #[test]
+    fn sizes_are_rounded_up_to_powers_of_two() {
+        // A model 3 by 5 by 2 voxels across, holding its far corner.
+        let mut children = Vec::new();
+        let size = [3_i32, 5, 2].iter().flat_map(|c| c.to_le_bytes()).collect::<Vec<_>>();
+        super::chunk(&mut children, b"SIZE", &size, &[]);
+        super::chunk(&mut children, b"XYZI", &[1, 0, 0, 0, 2, 4, 1, 9], &[]);
+
+        let mut bytes = b"VOX ".to_vec();
+        bytes.extend_from_slice(&150_i32.to_le_bytes());
+        super::chunk(&mut bytes, b"MAIN", &[], &children);
+
+        let octree = Octree::<u8>::from_vox(&bytes, |index| index).unwrap();
+        assert_eq!(octree.dimension(), 8);
+        assert_eq!(octree.get([2, 4, 1]), Some(&9));
+
+        // Voxels beyond the size of their model are rejected.
+        let at = bytes.len() - 4;
+        bytes[at] = 3;
+        assert!(matches!(
+            Octree::<u8>::from_vox(&bytes, |index| index),
+            Err(VoxError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn malformed_files_are_rejected() {
+        let mut rng = XorShift::new(0x7a02);
+        let bytes = export(&rng.octree(8, 20, 5), VoxConfig::default());
+
+        for len in 0..bytes.len() {
+            assert!(matches!(
+                Octree::<u8>::from_vox(&bytes[..len], |index| index),
+                Err(VoxError::Malformed(_))
+            ));
+        }
+
+        for i in 0..bytes.len() {
+            for flip in [0x01, 0x80, 0xff] {
+                let mut corrupt = bytes.clone();
+                corrupt[i] ^= flip;
+                let _ = Octree::<u8>::from_vox(&corrupt, |index| index);
+            }
+        }
+    }
+}
@@ -0,0 +1,454 @@
+use crate::{float, Error, Octree};
+
+use alloc::vec::Vec;
+use core::{fmt::Debug, hash::Hash};
+use hashbrown::HashMap;
+
+/// Which voxels [`Octree::voxelize_mesh`] writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FillMode {
+    /// Only voxels touched by a triangle of the mesh.
+    Surface,
+    /// Voxels touched by a triangle of the mesh, and every voxel whose center lies inside it.
+    Solid,
+}
+
+type Triangle = [[f32; 3]; 3];
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn abs(value: f32) -> f32 {
+    if value < 0.0 {
+        -value
+    } else {
+        value
+    }
+}
+
+/// Returns whether the triangle touches the closed cube with the given minimum position and dimension,
+/// using the separating axis test.
+///
+/// Degenerate triangles are tested as the segment or point they collapse to.
+fn touches(triangle: &Triangle, min: [u32; 3], dimension: u32) -> bool {
+    let half = dimension as f32 / 2.0;
+    let center = min.map(|c| c as f32 + half);
+    let v = triangle.map(|vertex| sub(vertex, center));
+
+    // Separated along `axis` if the projections of the triangle and cube do not overlap.
+    let separated = |axis: [f32; 3]| {
+        let p = v.map(|vertex| dot(vertex, axis));
+        let r = half * (abs(axis[0]) + abs(axis[1]) + abs(axis[2]));
+        p[0].min(p[1]).min(p[2]) > r || p[0].max(p[1]).max(p[2]) < -r
+    };
+
+    let units = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    if units.iter().any(|unit| separated(*unit)) {
+        return false;
+    }
+
+    let edges = [sub(v[1], v[0]), sub(v[2], v[1]), sub(v[0], v[2])];
+    if separated(cross(edges[0], edges[1])) {
+        return false;
+    }
+
+    !units
+        .iter()
+        .any(|unit| edges.iter().any(|edge| separated(cross(*unit, *edge))))
+}
+
+/// Returns the edge function of `point` against the edge from `a` to `b`, projected along the z axis.
+///
+/// The endpoints are ordered canonically before evaluating, so that the triangles on either side of a
+/// shared edge evaluate it to exactly opposite values.
+fn edge_function(a: [f32; 2], b: [f32; 2], point: [f32; 2]) -> f32 {
+    let (lower, upper, sign) = if (a[0], a[1]) <= (b[0], b[1]) {
+        (a, b, 1.0)
+    } else {
+        (b, a, -1.0)
+    };
+
+    sign * ((upper[0] - lower[0]) * (point[1] - lower[1]) - (upper[1] - lower[1]) * (point[0] - lower[0]))
+}
+
+/// Returns whether a point lying exactly on the edge from `a` to `b` belongs to the triangle on its left.
+///
+/// Exactly one of the two directions along every edge owns it, so a point on an edge shared by two
+/// triangles is counted once.
+fn owns_edge(a: [f32; 2], b: [f32; 2]) -> bool {
+    b[1] > a[1] || (b[1] == a[1] && b[0] < a[0])
+}
+
+/// Adds the height at which the triangle crosses each column of voxel centers below it to `crossings`.
+///
+/// Columns lying exactly on the projected edge of the triangle are counted for only one of the triangles
+/// sharing that edge. Triangles seen edge-on do not cross any column.
+fn add_crossings(triangle: &Triangle, dimension: u32, crossings: &mut HashMap<[u32; 2], Vec<f32>>) {
+    let mut t = *triangle;
+    let flat = |vertex: [f32; 3]| [vertex[0], vertex[1]];
+
+    let area = edge_function(flat(t[0]), flat(t[1]), flat(t[2]));
+    if area == 0.0 {
+        return;
+    } else if area < 0.0 {
+        t.swap(1, 2);
+    }
+
+    let area = abs(area);
+    let last = dimension as f32 - 1.0;
+    let lower = [0, 1].map(|i| float::ceil(t[0][i].min(t[1][i]).min(t[2][i]) - 0.5).max(0.0));
+    let upper = [0, 1].map(|i| float::floor(t[0][i].max(t[1][i]).max(t[2][i]) - 0.5).min(last));
+
+    if lower[0] > upper[0] || lower[1] > upper[1] {
+        return;
+    }
+
+    for x in lower[0] as u32..=upper[0] as u32 {
+        for y in lower[1] as u32..=upper[1] as u32 {
+            let point = [x as f32 + 0.5, y as f32 + 0.5];
+
+            let mut weights = [0.0; 3];
+            let mut inside = true;
+
+            for (i, weight) in weights.iter_mut().enumerate() {
+                let (a, b) = (flat(t[(i + 1) % 3]), flat(t[(i + 2) % 3]));
+                *weight = edge_function(a, b, point);
+                inside &= *weight > 0.0 || (*weight == 0.0 && owns_edge(a, b));
+            }
+
+            if inside {
+                let z = (0..3).map(|i| weights[i] * t[i][2]).sum::<f32>() / area;
+                crossings.entry([x, y]).or_default().push(z);
+            }
+        }
+    }
+}
+
+impl<T> Octree<T>
+where
+    T: Debug + Default + Clone + Eq + PartialEq + Copy + Hash,
+{
+    /// Writes `data` to the voxels of a triangle mesh, given as vertex positions and triangle indices.
+    ///
+    /// Vertices are in voxel coordinates, so scaling the mesh to the `Octree` is left to the caller, and
+    /// parts of the mesh outside the `Octree` are ignored. Each voxel is the closed unit cube at its
+    /// position, and with [`FillMode::Surface`] every voxel touched by a triangle is written, so triangles
+    /// lying exactly on the boundary between voxels write both of them. Degenerate triangles write the
+    /// voxels touched by the segment or point they collapse to. Nodes touched by no triangle are skipped
+    /// whole.
+    ///
+    /// With [`FillMode::Solid`], voxels whose centers lie inside the mesh are written too, found by the
+    /// parity of the triangles crossed along each column of voxel centers. The mesh should be closed for
+    /// this to be meaningful, and degenerate triangles bound nothing.
+    ///
+    /// Returns an error, without modifying the `Octree`, if the number of indices is not a multiple of
+    /// three, an index is out of range or a vertex is not finite.
+    ///
+    /// # Example
+    /// ```
+    /// # use svo_rs::{Error, FillMode, Octree};
+    /// # use core::num::NonZeroU32;
+    /// #
+    /// let mut octree = Octree::<u8>::new(NonZeroU32::new(32).unwrap()).unwrap();
+    ///
+    /// let vertices = [[0.5, 0.5, 2.5], [8.5, 0.5, 2.5], [0.5, 8.5, 2.5]];
+    /// octree.voxelize_mesh(&vertices, &[0, 1, 2], 1, FillMode::Surface).unwrap();
+    ///
+    /// assert!(matches!(octree.get([4, 4, 2]), Some(1)));
+    /// assert!(!matches!(octree.get([4, 4, 3]), Some(1)));
+    ///
+    /// assert_eq!(octree.voxelize_mesh(&vertices, &[0, 1, 3], 1, FillMode::Surface), Err(Error::InvalidMesh));
+    /// ```
+    pub fn voxelize_mesh(
+        &mut self,
+        vertices: &[[f32; 3]],
+        indices: &[u32],
+        data: T,
+        fill: FillMode,
+    ) -> Result<(), Error> {
+        let valid = indices.chunks_exact(3).remainder().is_empty()
+            && indices.iter().all(|index| (*index as usize) < vertices.len())
+            && vertices.iter().flatten().all(|c| c.is_finite());
+
+        if !valid {
+            return Err(Error::InvalidMesh);
+        }
+
+        let triangles = indices
+            .chunks_exact(3)
+            .map(|triangle| [0, 1, 2].map(|i| vertices[triangle[i] as usize]))
+            .collect::<Vec<Triangle>>();
+
+        let mut voxels = Vec::new();
+        let mut stack = Vec::new();
+
+        let touching = (0..triangles.len())
+            .filter(|i| touches(&triangles[*i], [0; 3], self.dimension()))
+            .collect::<Vec<_>>();
+
+        if !touching.is_empty() {
+            stack.push(([0; 3], self.dimension(), touching));
+        }
+
+        while let Some((min, dimension, touching)) = stack.pop() {
+            if dimension == 1 {
+                voxels.push(min);
+                continue;
+            }
+
+            let half = dimension / 2;
+
+            for i in 0..8 {
+                let offset = [i & 1, (i >> 1) & 1, (i >> 2) & 1];
+                let min = [0, 1, 2].map(|axis| min[axis] + offset[axis] * half);
+
+                let touching = touching
+                    .iter()
+                    .copied()
+                    .filter(|i| touches(&triangles[*i], min, half))
+                    .collect::<Vec<_>>();
+
+                if !touching.is_empty() {
+                    stack.push((min, half, touching));
+                }
+            }
+        }
+
+        if fill == FillMode::Solid {
+            let dimension = self.dimension();
+            let mut crossings = HashMap::new();
+
+            for triangle in triangles.iter() {
+                add_crossings(triangle, dimension, &mut crossings);
+            }
+
+            for ([x, y], mut heights) in crossings {
+                heights.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+
+                // An unpaired crossing means the mesh is not closed, and is ignored.
+                for span in heights.chunks_exact(2) {
+                    let lower = float::ceil(span[0] - 0.5).max(0.0);
+                    let upper = float::floor(span[1] - 0.5).min(dimension as f32 - 1.0);
+
+                    if lower <= upper {
+                        voxels.extend((lower as u32..=upper as u32).map(|z| [x, y, z]));
+                    }
+                }
+            }
+        }
+
+        for voxel in voxels {
+            self.insert(voxel, data)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FillMode;
+    use crate::{test_utils::XorShift, Error, Octree};
+
+    use alloc::vec::Vec;
+    use core::num::NonZeroU32;
+
+    /// Returns every voxel of the `Octree` holding `1`.
+    fn written(octree: &Octree<u8>) -> Vec<[u32; 3]> {
+        let dimension = octree.dimension();
+        let mut voxels = Vec::new();
+
+        for x in 0..dimension {
+            for y in 0..dimension {
+                for z in 0..dimension {
+                    if octree.get([x, y, z]) == Some(&1) {
+                        voxels.push([x, y, z]);
+                    }
+                }
+            }
+        }
+
+        voxels
+    }
+
+    /// Returns the vertices and indices of an axis-aligned box, wound outwards.
+    fn box_mesh(min: [f32; 3], max: [f32; 3]) -> (Vec<[f32; 3]>, Vec<u32>) {
+        let vertices = (0..8)
+            .map(|i| [0, 1, 2].map(|axis| if (i >> axis) & 1 == 1 { max[axis] } else { min[axis] }))
+            .collect();
+
+        #[rustfmt::skip]
+        let indices = vec![
+            0, 2, 1, 1, 2, 3, // -z
+            4, 5, 6, 5, 7, 6, // +z
+            0, 1, 4, 1, 5, 4, // -y
+            2, 6, 3, 3, 6, 7, // +y
+            0, 4, 2, 2, 4, 6, // -x
+            1, 3, 5, 3, 7, 5, // +x
+        ];
+
+        (vertices, indices)
+    }
+
+    #[test]
+    fn box_mesh_matches_inserted_cube() {
+        let (vertices, indices) = box_mesh([2.5, 3.5, 1.5], [9.5, 7.5, 12.5]);
+
+        let mut solid = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        solid.voxelize_mesh(&vertices, &indices, 1, FillMode::Solid).unwrap();
+
+        let mut surface = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        surface
+            .voxelize_mesh(&vertices, &indices, 1, FillMode::Surface)
+            .unwrap();
+
+        let mut expected = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        let mut shell = Vec::new();
+        for x in 2..10 {
+            for y in 3..8 {
+                for z in 1..13 {
+                    expected.insert([x, y, z], 1).unwrap();
+
+                    if x == 2 || x == 9 || y == 3 || y == 7 || z == 1 || z == 12 {
+                        shell.push([x, y, z]);
+                    }
+                }
+            }
+        }
+
+        assert_eq!(written(&solid), written(&expected));
+        assert_eq!(
+            solid.query_region([0; 3], [16; 3]).count(),
+            expected.query_region([0; 3], [16; 3]).count()
+        );
+        assert_eq!(written(&surface), shell);
+    }
+
+    #[test]
+    fn grid_aligned_box_mesh_is_closed_on_both_sides() {
+        let (vertices, indices) = box_mesh([4.0, 4.0, 4.0], [8.0, 8.0, 8.0]);
+
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        octree.voxelize_mesh(&vertices, &indices, 1, FillMode::Solid).unwrap();
+
+        assert_eq!(written(&octree).len(), 6 * 6 * 6);
+        assert!(matches!(octree.get([3, 3, 3]), Some(1)));
+        assert!(matches!(octree.get([8, 8, 8]), Some(1)));
+    }
+
+    #[test]
+    fn random_boxes_fill_inside_centers() {
+        let mut rng = XorShift::new(0x40c5);
+
+        for _ in 0..20 {
+            let min = [0, 1, 2].map(|_| rng.f32(-4.0, 10.0));
+            let max = [0, 1, 2].map(|i| min[i] + rng.f32(0.0, 10.0));
+            let (vertices, indices) = box_mesh(min, max);
+
+            let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+            octree.voxelize_mesh(&vertices, &indices, 1, FillMode::Solid).unwrap();
+
+            for x in 0..16 {
+                for y in 0..16 {
+                    for z in 0..16 {
+                        let center = [x, y, z].map(|c| c as f32 + 0.5);
+                        let inside = (0..3).all(|i| min[i] < center[i] && center[i] < max[i]);
+                        let near = (0..3).all(|i| min[i] - 1.0 <= center[i] && center[i] <= max[i] + 1.0);
+
+                        let value = octree.get([x, y, z]);
+                        assert!(!inside || value == Some(&1));
+                        assert!(near || value != Some(&1));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn thin_triangle_is_watertight() {
+        let vertices = [[0.2, 1.3, 2.1], [15.7, 2.9, 9.8], [14.9, 3.4, 10.2]];
+
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(16).unwrap()).unwrap();
+        octree
+            .voxelize_mesh(&vertices, &[0, 1, 2], 1, FillMode::Surface)
+            .unwrap();
+
+        // Every point of the triangle lies in a written voxel.
+        for i in 0..=100 {
+            for j in 0..=(100 - i) {
+                let (u, v) = (i as f32 / 100.0, j as f32 / 100.0);
+                let point = [0, 1, 2].map(|a| {
+                    vertices[0][a] + u * (vertices[1][a] - vertices[0][a]) + v * (vertices[2][a] - vertices[0][a])
+                });
+
+                assert_eq!(octree.get(point.map(|c| c as u32)), Some(&1));
+            }
+        }
+
+        // Every written voxel is touched by the triangle, so the surface is no thicker than necessary.
+        let voxels = written(&octree);
+        for voxel in voxels.iter() {
+            assert!(super::touches(&vertices, *voxel, 1));
+        }
+
+        // The written voxels form a single face-connected surface.
+        let mut reached = vec![voxels[0]];
+        let mut i = 0;
+        while i < reached.len() {
+            let current = reached[i];
+            for voxel in voxels.iter() {
+                let distance = (0..3).map(|a| (voxel[a] as i64 - current[a] as i64).abs()).sum::<i64>();
+                if distance == 1 && !reached.contains(voxel) {
+                    reached.push(*voxel);
+                }
+            }
+            i += 1;
+        }
+
+        assert_eq!(reached.len(), voxels.len());
+    }
+
+    #[test]
+    fn degenerate_and_out_of_bounds_triangles() {
+        let mut octree = Octree::<u8>::new(NonZeroU32::new(8).unwrap()).unwrap();
+
+        let vertices = [
+            [2.5, 2.5, 2.5],
+            [-20.0, -20.0, 3.5],
+            [40.0, -20.0, 3.5],
+            [-20.0, 40.0, 3.5],
+        ];
+        octree.voxelize_mesh(&vertices, &[0, 0, 0], 1, FillMode::Solid).unwrap();
+        assert_eq!(written(&octree), vec![[2, 2, 2]]);
+
+        // A huge triangle is clipped to the `Octree`.
+        octree.clear();
+        octree
+            .voxelize_mesh(&vertices, &[1, 2, 3], 1, FillMode::Surface)
+            .unwrap();
+        assert_eq!(written(&octree).len(), 64);
+        assert!(written(&octree).iter().all(|voxel| voxel[2] == 3));
+
+        assert_eq!(
+            octree.voxelize_mesh(&vertices, &[0, 1], 1, FillMode::Surface),
+            Err(Error::InvalidMesh)
+        );
+        assert_eq!(
+            octree.voxelize_mesh(&[[f32::NAN; 3]], &[0, 0, 0], 1, FillMode::Surface),
+            Err(Error::InvalidMesh)
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{Error, Octree};
+
+use alloc::{string::ToString, vec::Vec};
+use core::num::NonZeroU32;
+use wasm_bindgen::prelude::*;
+
+impl From<Error> for JsValue {
+    fn from(error: Error) -> Self {
+        js_sys::Error::new(&error.to_string()).into()
+    }
+}
+
+/// An `Octree` of `u32` values for use from JavaScript through `wasm-bindgen`.
+///
+/// Errors are thrown as JavaScript `Error`s holding the message the [`Error`] displays as.
+#[wasm_bindgen]
+pub struct JsOctree {
+    octree: Octree<u32>,
+}
+
+#[wasm_bindgen]
+impl JsOctree {
+    /// Creates an empty `Octree` with the given dimension, which must be a power of 2.
+    #[wasm_bindgen(constructor)]
+    pub fn new(dimension: u32) -> Result<JsOctree, JsValue> {
+        let dimension = NonZeroU32::new(dimension).ok_or(Error::InvalidDimension(dimension))?;
+        Ok(Self {
+            octree: Octree::new(dimension)?,
+        })
+    }
+
+    /// Returns the dimension of the `Octree`.
+    #[wasm_bindgen(getter)]
+    pub fn dimension(&self) -> u32 {
+        self.octree.dimension()
+    }
+
+    /// Writes `value` to the voxel at the given position, as [`Octree::insert`] does.
+    pub fn insert(&mut self, x: u32, y: u32, z: u32, value: u32) -> Result<(), JsValue> {
+        Ok(self.octree.insert([x, y, z], value)?)
+    }
+
+    /// Returns the value of the voxel at the given position, which is the background if it has never been
+    /// written, or `undefined` if it is outside the `Octree`.
+    pub fn get(&self, x: u32, y: u32, z: u32) -> Option<u32> {
+        self.octree.get([x, y, z]).copied()
+    }
+
+    /// Clears the voxel at the given position, as [`Octree::clear_at`] does.
+    #[wasm_bindgen(js_name = clearAt)]
+    pub fn clear_at(&mut self, x: u32, y: u32, z: u32) -> Result<(), JsValue> {
+        Ok(self.octree.clear_at([x, y, z])?)
+    }
+
+    /// Merges leaves holding the same value, as [`Octree::simplify`] does.
+    pub fn simplify(&mut self) {
+        self.octree.simplify();
+    }
+
+    /// Encodes the `Octree` as by [`Octree::to_bytes`], into a `Uint8Array`.
+    pub fn serialize(&self) -> Vec<u8> {
+        self.octree.to_bytes()
+    }
+
+    /// Decodes an `Octree` encoded by [`JsOctree::serialize`], or by [`Octree::to_bytes`] on the Rust side.
+    pub fn deserialize(bytes: &[u8]) -> Result<JsOctree, JsValue> {
+        Ok(Self {
+            octree: Octree::from_bytes(bytes)?,
+        })
+    }
+
+    /// Returns the leaves of the `Octree` holding data as a flat `Uint32Array` of five values each: the position
+    /// of the corner closest to the origin, the dimension and the value, in that order.
+    ///
+    /// Meshing from the returned array takes a single call across the boundary, rather than one per leaf.
+    #[wasm_bindgen(js_name = leafSpans)]
+    pub fn leaf_spans(&self) -> Vec<u32> {
+        self.octree
+            .iter_leaves()
+            .flat_map(|leaf| {
+                let [x, y, z] = leaf.min;
+                [x, y, z, leaf.dimension, leaf.data]
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use super::JsOctree;
+
+    use alloc::{string::String, vec::Vec};
+    use wasm_bindgen::{JsCast, JsValue};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    #[wasm_bindgen_test]
+    fn serialization_round_trips() {
+        let mut octree = JsOctree::new(16).unwrap();
+        octree.insert(1, 2, 3, 7).unwrap();
+        octree.insert(15, 0, 9, 8).unwrap();
+        octree.clear_at(1, 2, 3).unwrap();
+
+        let copy = JsOctree::deserialize(&octree.serialize()).unwrap();
+        assert_eq!(copy.dimension(), 16);
+        assert_eq!(copy.get(1, 2, 3), Some(0));
+        assert_eq!(copy.get(15, 0, 9), Some(8));
+        assert_eq!(copy.serialize(), octree.serialize());
+
+        let error = JsOctree::deserialize(&[1, 2, 3]).err().unwrap();
+        assert!(error.is_instance_of::<js_sys::Error>());
+        assert!(JsOctree::new(12).is_err());
+        assert!(octree.insert(16, 0, 0, 1).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn leaf_spans_are_flat() {
+        let mut octree = JsOctree::new(4).unwrap();
+        for i in 0..8 {
+            octree.insert(i & 1, i >> 1 & 1, i >> 2, 5).unwrap();
+        }
+        octree.insert(3, 3, 3, 6).unwrap();
+
+        let spans = octree.leaf_spans();
+        let leaves = spans.chunks(5).map(<[u32]>::to_vec).collect::<Vec<_>>();
+        assert_eq!(leaves, [[0, 0, 0, 2, 5].to_vec(), [3, 3, 3, 1, 6].to_vec()]);
+
+        let error = js_sys::Error::from(JsValue::from(crate::Error::OutOfBounds));
+        assert_eq!(String::from(error.message()), "Shape extends outside octree.");
+    }
+}
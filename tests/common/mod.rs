@@ -0,0 +1,71 @@
+//! A global allocator counting allocations, shared by the test binaries measuring them.
+//!
+//! Each of those binaries installs it with `#[global_allocator] static A: common::Counting = common::Counting;`,
+//! and lives on its own, as it counts every allocation made by the binary.
+
+// Not every binary uses every helper.
+#![allow(dead_code)]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, MutexGuard, PoisonError,
+    },
+};
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// Held for the whole of each test, so that tests running at the same time do not count each other's
+/// allocations, including those made while building what is measured.
+static MEASURING: Mutex<()> = Mutex::new(());
+
+/// Counts the number of allocations made, along with the bytes allocated and the most allocated at once.
+pub struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+            let current = CURRENT.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(current, Ordering::SeqCst);
+        }
+
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+/// Locks [`MEASURING`] for the rest of a test. A test failing while holding it does not fail the others.
+pub fn exclusive() -> MutexGuard<'static, ()> {
+    MEASURING.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Returns the number of bytes currently allocated.
+pub fn allocated() -> usize {
+    CURRENT.load(Ordering::SeqCst)
+}
+
+/// Runs `f`, returning its result along with the number of allocations made while running it and the most bytes
+/// allocated at once, beyond those allocated before it.
+///
+/// The caller must hold [`MEASURING`], as returned by [`exclusive`].
+pub fn measure<R>(f: impl FnOnce() -> R) -> (R, usize, usize) {
+    let allocations = ALLOCATIONS.load(Ordering::SeqCst);
+    let before = CURRENT.load(Ordering::SeqCst);
+    PEAK.store(before, Ordering::SeqCst);
+    let result = f();
+
+    (
+        result,
+        ALLOCATIONS.load(Ordering::SeqCst) - allocations,
+        PEAK.load(Ordering::SeqCst) - before,
+    )
+}
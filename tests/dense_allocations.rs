@@ -0,0 +1,52 @@
+//! Checks that building an `Octree` from dense data allocates nothing inside uniform regions.
+//!
+//! This lives in a test binary of its own, as it counts every allocation through the global allocator in
+//! `common`.
+
+use common::{exclusive, measure};
+use std::num::NonZeroU32;
+use svo_rs::Octree;
+
+mod common;
+
+#[global_allocator]
+static A: common::Counting = common::Counting;
+
+#[test]
+fn uniform_data_allocates_a_constant_number_of_times() {
+    let _measuring = exclusive();
+    let mut counts = Vec::new();
+
+    for dimension in [4, 16, 64] {
+        let data = vec![7_u16; (dimension as usize).pow(3)];
+        let dimension = NonZeroU32::new(dimension).unwrap();
+
+        let (octree, allocations, _) = measure(|| Octree::from_dense(dimension, &data).unwrap());
+        assert!(matches!(octree.get([3, 2, 1]), Some(7)));
+        counts.push(allocations);
+
+        let (inserted, insert_allocations, _) = measure(|| {
+            let mut octree = Octree::<u16>::new(dimension).unwrap();
+            for (i, data) in data.iter().enumerate() {
+                let i = i as u32;
+                let dimension = dimension.get();
+                octree
+                    .insert(
+                        [i % dimension, i / dimension % dimension, i / dimension / dimension],
+                        *data,
+                    )
+                    .unwrap();
+            }
+
+            octree
+        });
+        assert!(inserted.equivalent(&octree));
+        assert!(insert_allocations > allocations);
+    }
+
+    assert!(
+        counts.iter().all(|count| *count == counts[0] && *count <= 2),
+        "{:?}",
+        counts
+    );
+}
@@ -0,0 +1,56 @@
+//! Checks that coarsening an `Octree` with `lod_down` does not allocate, whichever `LodPolicy` it votes by.
+//!
+//! This lives in a test binary of its own, as it counts every allocation through the global allocator in
+//! `common`.
+
+use common::{exclusive, measure};
+use std::num::NonZeroU32;
+use svo_rs::{LodPolicy, Octree};
+
+mod common;
+
+#[global_allocator]
+static A: common::Counting = common::Counting;
+
+/// Returns an `Octree` with detail to coarsen at every level, and its pool filled.
+fn terrain() -> Octree<u8> {
+    let dimension = NonZeroU32::new(64).unwrap();
+
+    // Noise over blocky terrain in one half, so that every level has detail to coarsen.
+    let mut octree = Octree::from_fn(dimension, |[x, y, z]| {
+        let hash = (x.wrapping_mul(0x9e37_79b9) ^ y.wrapping_mul(0x85eb_ca6b) ^ z).count_ones();
+        match (x < 32, y < 8 + (x / 4 + z / 4) % 16) {
+            (true, true) => 1 + (hash % 3) as u8,
+            _ => 0,
+        }
+    })
+    .unwrap();
+
+    // Filling the pool up front, by pruning scattered voxels written in the other half, leaves `lod_down` room
+    // to keep the arrays it frees without growing it.
+    let positions = (0..2048_u32).map(|i| [32 + i % 32, i / 32, (i * 37) % 64]);
+    for position in positions.clone() {
+        octree.insert(position, 9).unwrap();
+    }
+    for position in positions {
+        octree.clear_at(position).unwrap();
+    }
+    octree.prune();
+    assert_eq!(octree.pool_len(), 1024);
+
+    octree
+}
+
+#[test]
+fn lod_down_does_not_allocate() {
+    let _measuring = exclusive();
+
+    for policy in [LodPolicy::Majority, LodPolicy::IgnoreBackground] {
+        let mut octree = terrain();
+
+        for _ in 1..octree.max_lod_level() {
+            let (_, allocations, _) = measure(|| octree.lod_down_with_policy(policy));
+            assert_eq!(allocations, 0, "at LOD level {} with {:?}", octree.lod_level(), policy);
+        }
+    }
+}
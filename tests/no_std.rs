@@ -0,0 +1,59 @@
+//! Checks that the crate builds without `std`, as it must for embedded targets.
+//!
+//! The crate is linted again by Clippy, in a target directory of its own, without the default features: once
+//! with none at all, and once with `no-std` along with every optional feature not needing `std`, each along with
+//! the unit tests. When the bare-metal target below is installed the library is then checked for it. Otherwise it
+//! is checked for the host, where outside of its tests it links `std` no more than on an embedded target, so that a
+//! use of `std` fails the same way.
+
+use std::{env, path::Path, process::Command};
+
+/// The embedded target checked for when it is installed.
+const TARGET: &str = "thumbv7em-none-eabihf";
+
+/// The features checked along with `no-std`.
+const FEATURES: &str = "no-std,serde,mint,nalgebra,bytemuck,tracing";
+
+/// Returns whether the standard libraries of `target` are installed for the `rustc` building the tests.
+fn target_installed(target: &str) -> bool {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".into());
+    let output = Command::new(rustc)
+        .args(["--print", "target-libdir", "--target", target])
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => Path::new(String::from_utf8_lossy(&output.stdout).trim()).exists(),
+        _ => false,
+    }
+}
+
+/// Runs Clippy without the default features, with the given features and arguments, denying every warning.
+fn clippy_without_std(features: &[&str], args: &[&str]) {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let output = Command::new(env!("CARGO"))
+        .current_dir(manifest_dir)
+        .args(["clippy", "--no-default-features"])
+        .args(features.iter().flat_map(|features| ["--features", features]))
+        .args(args)
+        .arg("--target-dir")
+        .arg(manifest_dir.join("target").join("no-std"))
+        .args(["--", "-D", "warnings"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn builds_without_std() {
+    let target: &[&str] = if target_installed(TARGET) {
+        &["--target", TARGET]
+    } else {
+        &[]
+    };
+
+    clippy_without_std(&[], &["--lib", "--tests"]);
+    clippy_without_std(&[], &[&["--lib"], target].concat());
+    clippy_without_std(&[FEATURES], &["--lib", "--tests"]);
+    clippy_without_std(&[FEATURES], &[&["--lib"], target].concat());
+}
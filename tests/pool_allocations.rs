@@ -0,0 +1,58 @@
+//! Checks that repeatedly filling and clearing a region of an `Octree` reuses the memory its edits free.
+//!
+//! This lives in a test binary of its own, as it counts every allocation through the global allocator in
+//! `common`.
+
+use common::{exclusive, measure};
+use std::num::NonZeroU32;
+use svo_rs::Octree;
+
+mod common;
+
+#[global_allocator]
+static A: common::Counting = common::Counting;
+
+/// Writes `data` to every voxel of the box from `min` spanning `size` voxels along each axis, one at a time, or
+/// clears them if `data` is `None`.
+fn edit(octree: &mut Octree<u8>, min: [u32; 3], size: u32, data: Option<u8>) {
+    for i in 0..size.pow(3) {
+        let position = [min[0] + i % size, min[1] + i / size % size, min[2] + i / size / size];
+        match data {
+            Some(data) => octree.insert(position, data).unwrap(),
+            None => octree.clear_at(position).unwrap(),
+        }
+    }
+}
+
+#[test]
+fn filling_and_clearing_reuses_freed_nodes() {
+    let _measuring = exclusive();
+    let mut octree = Octree::<u8>::new(NonZeroU32::new(64).unwrap()).unwrap();
+
+    // Scattered voxels keep the tree around the region from collapsing whole.
+    for i in 0..64 {
+        octree.insert([i, (i * 7) % 64, (i * 13) % 64], 2).unwrap();
+    }
+
+    // Digging and filling, offset from the cubes of the tree so that each edit splits and simplifies nodes.
+    let round = |octree: &mut Octree<u8>| {
+        edit(octree, [5, 9, 3], 12, Some(1));
+        edit(octree, [5, 9, 3], 12, None);
+    };
+
+    let (_, cold, _) = measure(|| round(&mut octree));
+    let (_, warm, _) = measure(|| {
+        for _ in 0..10 {
+            round(&mut octree);
+        }
+    });
+
+    assert!(cold > 0);
+    assert_eq!(warm, 0);
+    assert!(octree.pool_len() > 0);
+
+    // Without the pool, each round allocates afresh.
+    octree.shrink_pool(0);
+    assert_eq!(octree.pool_len(), 0);
+    assert!(measure(|| round(&mut octree)).1 > 0);
+}
@@ -0,0 +1,83 @@
+//! Checks that serializing and deserializing an `Octree` stream its nodes rather than collecting them.
+//!
+//! This lives in a test binary of its own, as it counts every allocation through the global allocator in
+//! `common`.
+#![cfg(feature = "serde")]
+
+use common::{allocated, exclusive, measure};
+use std::{io, num::NonZeroU32};
+use svo_rs::Octree;
+
+mod common;
+
+#[global_allocator]
+static A: common::Counting = common::Counting;
+
+/// Builds an `Octree` with tens of thousands of `Node`s.
+fn large_octree() -> Octree<u16> {
+    let mut octree = Octree::<u16>::new(NonZeroU32::new(64).unwrap()).unwrap();
+    let mut state = 0x2545_f491_u32;
+    for _ in 0..20_000 {
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+        octree
+            .insert([next() % 64, next() % 64, next() % 64], (next() % 100) as u16)
+            .unwrap();
+    }
+
+    // Collecting the nodes would take several bytes for each of them.
+    let json = serde_json::to_string(&octree).unwrap();
+    assert!(json.matches("Leaf").count() + json.matches("Branch").count() > 10_000);
+
+    octree
+}
+
+#[test]
+fn serializing_allocates_little() {
+    let _measuring = exclusive();
+    let octree = large_octree();
+
+    let (_, _, json) = measure(|| serde_json::to_writer(io::sink(), &octree).unwrap());
+    let (_, _, msgpack) = measure(|| rmp_serde::encode::write(&mut io::sink(), &octree).unwrap());
+
+    assert!(json < 4096, "serializing to JSON allocated {} bytes at once", json);
+    assert!(
+        msgpack < 4096,
+        "serializing to MessagePack allocated {} bytes at once",
+        msgpack
+    );
+}
+
+#[test]
+fn deserializing_allocates_little_beyond_the_octree() {
+    let _measuring = exclusive();
+    let octree = large_octree();
+    let json = serde_json::to_vec(&octree).unwrap();
+    let msgpack = rmp_serde::to_vec(&octree).unwrap();
+
+    // Decoding holds the children decoded so far of each branch from the root, which take a few kilobytes.
+    let before = allocated();
+    let (copy, _, peak) = measure(|| serde_json::from_slice::<Octree<u16>>(&json).unwrap());
+    let kept = allocated() - before;
+    assert!(copy.equivalent(&octree));
+    assert!(
+        peak - kept < 16384,
+        "deserializing JSON allocated {} bytes beyond the octree",
+        peak - kept
+    );
+    drop(copy);
+
+    let before = allocated();
+    let (copy, _, peak) = measure(|| rmp_serde::from_slice::<Octree<u16>>(&msgpack).unwrap());
+    let kept = allocated() - before;
+    assert!(copy.equivalent(&octree));
+    assert!(
+        peak - kept < 16384,
+        "deserializing MessagePack allocated {} bytes beyond the octree",
+        peak - kept
+    );
+}